@@ -222,7 +222,8 @@ impl trace::VMTracer for Informant {
         });
     }
 
-    fn prepare_subtrace(&mut self, code: &[u8]) {
+    fn prepare_subtrace(&mut self, params: &::vm::ActionParams) {
+        let code = params.code.as_ref().map_or(&[][..], |code| &code[..]);
         let subdepth = self.subdepth;
         Self::with_informant_in_depth(self, subdepth, |informant: &mut Informant| {
             let mut vm = Informant::default();
@@ -64,7 +64,7 @@ impl vm::Informant for Informant {
 impl trace::VMTracer for Informant {
     type Output = ();
 
-    fn prepare_subtrace(&mut self, _code: &[u8]) {
+    fn prepare_subtrace(&mut self, _params: &::vm::ActionParams) {
         Default::default()
     }
     fn done_subtrace(&mut self) {}
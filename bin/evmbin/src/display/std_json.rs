@@ -259,7 +259,8 @@ impl<Trace: Writer, Out: Writer> trace::VMTracer for Informant<Trace, Out> {
         });
     }
 
-    fn prepare_subtrace(&mut self, code: &[u8]) {
+    fn prepare_subtrace(&mut self, params: &::vm::ActionParams) {
+        let code = params.code.as_ref().map_or(&[][..], |code| &code[..]);
         let subdepth = self.subdepth;
         Self::with_informant_in_depth(self, subdepth, |informant: &mut Informant<Trace, Out>| {
             let mut vm = Informant::new(
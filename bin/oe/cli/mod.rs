@@ -310,10 +310,18 @@ usage! {
             "--no-warp",
             "Disable syncing from the snapshot over the network.",
 
+            FLAG flag_snap_sync: (bool) = false, or |c: &Config| c.network.as_ref()?.snap_sync.clone(),
+            "--snap-sync",
+            "Download the state over the snap protocol when no snapshot is found. Requires warp sync and a chain without epoch proofs.",
+
             FLAG flag_no_discovery: (bool) = false, or |c: &Config| c.network.as_ref()?.discovery.map(|d| !d).clone(),
             "--no-discovery",
             "Disable new peer discovery.",
 
+            FLAG flag_discovery_v5: (bool) = false, or |c: &Config| c.network.as_ref()?.discovery_v5.clone(),
+            "--discovery-v5",
+            "Enable topic-less discovery v5 alongside discovery v4. Shares the discovery UDP port.",
+
             FLAG flag_reserved_only: (bool) = false, or |c: &Config| c.network.as_ref()?.reserved_only.clone(),
             "--reserved-only",
             "Connect only to reserved nodes.",
@@ -322,10 +330,26 @@ usage! {
             "--no-ancient-blocks",
             "Disable downloading old blocks after snapshot restoration or warp sync. Not recommended.",
 
+            ARG arg_ancient_blocks_from: (Option<u64>) = None, or |c: &Config| c.network.as_ref()?.ancient_blocks_from.clone(),
+            "--ancient-blocks-from=[NUM]",
+            "Old blocks are downloaded as headers first. Download bodies and receipts only for the old blocks from block NUM on instead of all of them. A NUM past the first restored block keeps headers only.",
+
             ARG arg_warp_barrier: (Option<u64>) = None, or |c: &Config| c.network.as_ref()?.warp_barrier.clone(),
             "--warp-barrier=[NUM]",
             "When warp enabled never attempt regular sync before warping to block NUM.",
 
+            ARG arg_checkpoint_sync: (Option<String>) = None, or |c: &Config| c.network.as_ref()?.checkpoint_sync.clone(),
+            "--checkpoint-sync=[NUMBER:HASH:TD]",
+            "Start the chain at the trusted block NUMBER with hash HASH and total difficulty TD, downloading its state over the snap protocol, then sync the blocks before it in the background. Falls back to regular sync if peers no longer serve the state of the block. Requires warp sync and a chain without epoch proofs.",
+
+            ARG arg_tx_propagation: (String) = "default", or |c: &Config| c.network.as_ref()?.tx_propagation.clone(),
+            "--tx-propagation=[MODE]",
+            "How transactions are propagated to peers. MODE may be one of: default - announce to peers supporting eth/65 and send in full to others; announce - only announce transaction hashes; broadcast - additionally send every transaction in full to the peers given with --tx-propagation-peers; no-local - don't propagate local transactions; private - send local transactions only to the peers given with --tx-propagation-peers.",
+
+            ARG arg_tx_propagation_peers: (Option<String>) = None, or |c: &Config| c.network.as_ref()?.tx_propagation_peers.clone(),
+            "--tx-propagation-peers=[ENODES]",
+            "Comma separated list of enodes used by the broadcast and private transaction propagation modes.",
+
             ARG arg_port: (u16) = 30303u16, or |c: &Config| c.network.as_ref()?.port.clone(),
             "--port=[PORT]",
             "Override the port on which the node should listen.",
@@ -366,6 +390,10 @@ usage! {
             "--bootnodes=[NODES]",
             "Override the bootnodes from our chain. NODES should be comma-delimited enodes.",
 
+            ARG arg_dns_discovery: (Option<String>) = None, or |c: &Config| c.network.as_ref()?.dns_discovery.as_ref().map(|vec| vec.join(",")),
+            "--dns-discovery=[URLS]",
+            "Override the DNS node lists (EIP-1459) from our chain. URLS should be comma-delimited enrtree:// URLs.",
+
             ARG arg_node_key: (Option<String>) = None, or |c: &Config| c.network.as_ref()?.node_key.clone(),
             "--node-key=[KEY]",
             "Specify node secret key, either as 64-character hex string or input to SHA3 operation.",
@@ -411,7 +439,7 @@ usage! {
 
             ARG arg_jsonrpc_apis: (String) = "web3,eth,pubsub,net,parity,parity_pubsub,traces,rpc", or |c: &Config| c.rpc.as_ref()?.apis.as_ref().map(|vec| vec.join(",")),
             "--jsonrpc-apis=[APIS]",
-            "Specify the APIs available through the HTTP JSON-RPC interface using a comma-delimited list of API names. Possible names are: all, safe, debug, web3, net, eth, pubsub, personal, signer, parity, parity_pubsub, parity_accounts, parity_set, traces, rpc, secretstore. You can also disable a specific API by putting '-' in the front, example: all,-personal. 'safe' enables the following APIs: web3, net, eth, pubsub, parity, parity_pubsub, traces, rpc",
+            "Specify the APIs available through the HTTP JSON-RPC interface using a comma-delimited list of API names. Possible names are: all, safe, debug, web3, net, eth, pubsub, personal, signer, parity, parity_pubsub, parity_accounts, parity_set, traces, rpc, secretstore, txpool. You can also disable a specific API by putting '-' in the front, example: all,-personal. 'safe' enables the following APIs: web3, net, eth, pubsub, parity, parity_pubsub, traces, rpc",
 
             ARG arg_jsonrpc_hosts: (String) = "none", or |c: &Config| c.rpc.as_ref()?.hosts.as_ref().map(|vec| vec.join(",")),
             "--jsonrpc-hosts=[HOSTS]",
@@ -437,6 +465,14 @@ usage! {
             "--poll-lifetime=[S]",
             "Set the RPC filter lifetime to S seconds. The filter has to be polled at least every S seconds , otherwise it is removed.",
 
+            ARG arg_jwt_secret: (Option<String>) = None, or |c: &Config| c.rpc.as_ref()?.jwt_secret.clone(),
+            "--jwt-secret=[PATH]",
+            "Require a JWT signed (HS256) with the hex encoded 32 byte secret stored in PATH on the HTTP and WebSockets JSON-RPC servers. A new secret is generated if the file doesn't exist. The iat claim of a token must be within 60 seconds of the current time.",
+
+            ARG arg_jwt_apis: (String) = "all", or |c: &Config| c.rpc.as_ref()?.jwt_apis.as_ref().map(|vec| vec.join(",")),
+            "--jwt-apis=[APIS]",
+            "Specify the HTTP JSON-RPC APIs requiring a JWT when --jwt-secret is set, using the same names as --jsonrpc-apis. The remaining APIs stay public. WebSockets connections always need a token.",
+
         ["API and Console Options – WebSockets"]
             FLAG flag_no_ws: (bool) = false, or |c: &Config| c.websockets.as_ref()?.disable.clone(),
             "--no-ws",
@@ -452,7 +488,7 @@ usage! {
 
             ARG arg_ws_apis: (String) = "web3,eth,pubsub,net,parity,parity_pubsub,traces,rpc", or |c: &Config| c.websockets.as_ref()?.apis.as_ref().map(|vec| vec.join(",")),
             "--ws-apis=[APIS]",
-            "Specify the JSON-RPC APIs available through the WebSockets interface using a comma-delimited list of API names. Possible names are: all, safe, web3, net, eth, pubsub, personal, signer, parity, parity_pubsub, parity_accounts, parity_set, traces, rpc, secretstore. You can also disable a specific API by putting '-' in the front, example: all,-personal. 'safe' enables the following APIs: web3, net, eth, pubsub, parity, parity_pubsub, traces, rpc",
+            "Specify the JSON-RPC APIs available through the WebSockets interface using a comma-delimited list of API names. Possible names are: all, safe, web3, net, eth, pubsub, personal, signer, parity, parity_pubsub, parity_accounts, parity_set, traces, rpc, secretstore, txpool. You can also disable a specific API by putting '-' in the front, example: all,-personal. 'safe' enables the following APIs: web3, net, eth, pubsub, parity, parity_pubsub, traces, rpc",
 
             ARG arg_ws_origins: (String) = "parity://*,chrome-extension://*,moz-extension://*", or |c: &Config| c.websockets.as_ref()?.origins.as_ref().map(|vec| vec.join(",")),
             "--ws-origins=[URL]",
@@ -498,7 +534,7 @@ usage! {
 
             ARG arg_ipc_apis: (String) = "web3,eth,pubsub,net,parity,parity_pubsub,parity_accounts,traces,rpc", or |c: &Config| c.ipc.as_ref()?.apis.as_ref().map(|vec| vec.join(",")),
             "--ipc-apis=[APIS]",
-            "Specify custom API set available via JSON-RPC over IPC using a comma-delimited list of API names. Possible names are: all, safe, web3, net, eth, pubsub, personal, signer, parity, parity_pubsub, parity_accounts, parity_set, traces, rpc, secretstore. You can also disable a specific API by putting '-' in the front, example: all,-personal. 'safe' enables the following APIs: web3, net, eth, pubsub, parity, parity_pubsub, traces, rpc",
+            "Specify custom API set available via JSON-RPC over IPC using a comma-delimited list of API names. Possible names are: all, safe, web3, net, eth, pubsub, personal, signer, parity, parity_pubsub, parity_accounts, parity_set, traces, rpc, secretstore, txpool. You can also disable a specific API by putting '-' in the front, example: all,-personal. 'safe' enables the following APIs: web3, net, eth, pubsub, parity, parity_pubsub, traces, rpc",
 
         ["Secret Store Options"]
             FLAG flag_no_secretstore: (bool) = false, or |c: &Config| c.secretstore.as_ref()?.disable.clone(),
@@ -606,6 +642,10 @@ usage! {
             "--no-persistent-txqueue",
             "Don't save pending local transactions to disk to be restored whenever the node restarts.",
 
+            FLAG flag_tx_queue_journal: (bool) = false, or |c: &Config| c.parity.as_ref()?.tx_queue_journal,
+            "--tx-queue-journal",
+            "Periodically save all transactions in the queue to disk, and restore them whenever the node restarts. Transactions which became invalid in the meantime are dropped. Has no effect with --no-persistent-txqueue.",
+
             FLAG flag_stratum: (bool) = false, or |c: &Config| Some(c.stratum.is_some()),
             "--stratum",
             "Run Stratum server for miner push notification.",
@@ -668,7 +708,31 @@ usage! {
 
             ARG arg_tx_queue_strategy: (String) = "gas_price", or |c: &Config| c.mining.as_ref()?.tx_queue_strategy.clone(),
             "--tx-queue-strategy=[S]",
-            "Prioritization strategy used to order transactions in the queue. S may be: gas_price - Prioritize txs with high gas price",
+            "Prioritization strategy used to order transactions in the queue. S may be: gas_price - Prioritize txs with high gas price; profit - Like gas_price, but build blocks from the txs earning the most per gas actually used, executing them first",
+
+            ARG arg_tx_queue_sender_rate: (Option<u32>) = None, or |c: &Config| c.mining.as_ref()?.tx_queue_sender_rate.clone(),
+            "--tx-queue-sender-rate=[TXS]",
+            "Maximum number of transactions a single sender may add to the queue per minute. Local transactions are not limited.",
+
+            ARG arg_tx_queue_contract_rate: (Option<u32>) = None, or |c: &Config| c.mining.as_ref()?.tx_queue_contract_rate.clone(),
+            "--tx-queue-contract-rate=[TXS]",
+            "Maximum number of transactions calling a single contract (or sending to a single account) that may be added to the queue per minute.",
+
+            ARG arg_tx_queue_replace_bump: (u32) = 0u32, or |c: &Config| c.mining.as_ref()?.tx_queue_replace_bump.clone(),
+            "--tx-queue-replace-bump=[PCT]",
+            "Minimal gas price increase in percent required to replace a queued transaction with the same sender and nonce. The queue always requires at least 12.5%.",
+
+            ARG arg_tx_queue_ban_count: (u16) = 0u16, or |c: &Config| c.mining.as_ref()?.tx_queue_ban_count.clone(),
+            "--tx-queue-ban-count=[C]",
+            "Number of times transactions of a sender may be pushed out of the queue or fail when building a block before the sender gets temporarily banned. 0 disables banning.",
+
+            ARG arg_tx_queue_ban_time: (u16) = 180u16, or |c: &Config| c.mining.as_ref()?.tx_queue_ban_time.clone(),
+            "--tx-queue-ban-time=[SEC]",
+            "Banning time (in seconds) for senders of offending transactions.",
+
+            ARG arg_tx_queue_events_log: (Option<String>) = None, or |c: &Config| c.mining.as_ref()?.tx_queue_events_log.clone(),
+            "--tx-queue-events-log=[FILE]",
+            "Append lifecycle events (added, replaced, dropped, culled, mined) of all transactions in the queue to FILE, one JSON object per line.",
 
             ARG arg_stratum_interface: (String) = "local", or |c: &Config| c.stratum.as_ref()?.interface.clone(),
             "--stratum-interface=[IP]",
@@ -848,6 +912,7 @@ struct Operating {
     keys_path: Option<String>,
     identity: Option<String>,
     no_persistent_txqueue: Option<bool>,
+    tx_queue_journal: Option<bool>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize)]
@@ -871,6 +936,11 @@ struct Ui {
 struct Network {
     warp: Option<bool>,
     warp_barrier: Option<u64>,
+    ancient_blocks_from: Option<u64>,
+    snap_sync: Option<bool>,
+    checkpoint_sync: Option<String>,
+    tx_propagation: Option<String>,
+    tx_propagation_peers: Option<String>,
     port: Option<u16>,
     interface: Option<String>,
     min_peers: Option<u16>,
@@ -881,7 +951,9 @@ struct Network {
     allow_ips: Option<String>,
     id: Option<u64>,
     bootnodes: Option<Vec<String>>,
+    dns_discovery: Option<Vec<String>>,
     discovery: Option<bool>,
+    discovery_v5: Option<bool>,
     node_key: Option<String>,
     reserved_peers: Option<String>,
     reserved_only: Option<bool>,
@@ -903,6 +975,8 @@ struct Rpc {
     experimental_rpcs: Option<bool>,
     poll_lifetime: Option<u32>,
     allow_missing_blocks: Option<bool>,
+    jwt_secret: Option<String>,
+    jwt_apis: Option<Vec<String>>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize)]
@@ -985,8 +1059,12 @@ struct Mining {
     tx_queue_mem_limit: Option<u32>,
     tx_queue_locals: Option<HashSet<String>>,
     tx_queue_strategy: Option<String>,
+    tx_queue_sender_rate: Option<u32>,
+    tx_queue_contract_rate: Option<u32>,
+    tx_queue_replace_bump: Option<u32>,
     tx_queue_ban_count: Option<u16>,
     tx_queue_ban_time: Option<u16>,
+    tx_queue_events_log: Option<String>,
     tx_queue_no_unfamiliar_locals: Option<bool>,
     tx_queue_no_early_reject: Option<bool>,
     remove_solved: Option<bool>,
@@ -1280,6 +1358,7 @@ mod tests {
                 arg_keys_path: "$HOME/.parity/keys".into(),
                 arg_identity: "".into(),
                 flag_no_persistent_txqueue: false,
+                flag_tx_queue_journal: false,
 
                 // -- Convenience Options
                 arg_config: "$BASE/config.toml".into(),
@@ -1297,6 +1376,7 @@ mod tests {
 
                 // -- Networking Options
                 flag_no_warp: false,
+                flag_snap_sync: false,
                 arg_port: 30303u16,
                 arg_interface: "all".into(),
                 arg_min_peers: Some(25u16),
@@ -1307,12 +1387,18 @@ mod tests {
                 arg_nat: "any".into(),
                 arg_network_id: Some(1),
                 arg_bootnodes: Some("".into()),
+                arg_dns_discovery: Some("".into()),
                 flag_no_discovery: false,
+                flag_discovery_v5: false,
                 arg_node_key: None,
                 arg_reserved_peers: Some("./path_to_file".into()),
                 flag_reserved_only: false,
                 flag_no_ancient_blocks: false,
+                arg_ancient_blocks_from: None,
                 arg_warp_barrier: None,
+                arg_checkpoint_sync: None,
+                arg_tx_propagation: "default".into(),
+                arg_tx_propagation_peers: None,
 
                 // -- API and Console Options
                 // RPC
@@ -1329,6 +1415,8 @@ mod tests {
                 arg_jsonrpc_max_payload: None,
                 arg_poll_lifetime: 60u32,
                 flag_jsonrpc_allow_missing_blocks: false,
+                arg_jwt_secret: None,
+                arg_jwt_apis: "all".into(),
 
                 // WS
                 flag_no_ws: false,
@@ -1399,6 +1487,12 @@ mod tests {
                 arg_tx_queue_mem_limit: 4u32,
                 arg_tx_queue_locals: Some("0xdeadbeefcafe0000000000000000000000000000".into()),
                 arg_tx_queue_strategy: "gas_factor".into(),
+                arg_tx_queue_sender_rate: None,
+                arg_tx_queue_contract_rate: None,
+                arg_tx_queue_replace_bump: 0u32,
+                arg_tx_queue_ban_count: 1u16,
+                arg_tx_queue_ban_time: 180u16,
+                arg_tx_queue_events_log: None,
                 flag_remove_solved: false,
                 arg_notify_work: Some("http://localhost:3001".into()),
                 flag_refuse_service_transactions: false,
@@ -1495,6 +1589,7 @@ mod tests {
                     keys_path: None,
                     identity: None,
                     no_persistent_txqueue: None,
+                    tx_queue_journal: None,
                 }),
                 account: Some(Account {
                     unlock: Some(vec!["0x1".into(), "0x2".into(), "0x3".into()]),
@@ -1507,6 +1602,11 @@ mod tests {
                 network: Some(Network {
                     warp: Some(false),
                     warp_barrier: None,
+                    ancient_blocks_from: None,
+                    snap_sync: None,
+                    checkpoint_sync: None,
+                    tx_propagation: None,
+                    tx_propagation_peers: None,
                     port: None,
                     interface: None,
                     min_peers: Some(10),
@@ -1517,7 +1617,9 @@ mod tests {
                     nat: Some("any".into()),
                     id: None,
                     bootnodes: None,
+                    dns_discovery: None,
                     discovery: Some(true),
+                    discovery_v5: None,
                     node_key: None,
                     reserved_peers: Some("./path/to/reserved_peers".into()),
                     reserved_only: Some(true),
@@ -1545,7 +1647,9 @@ mod tests {
                     keep_alive: None,
                     experimental_rpcs: None,
                     poll_lifetime: None,
-                    allow_missing_blocks: None
+                    allow_missing_blocks: None,
+                    jwt_secret: None,
+                    jwt_apis: None,
                 }),
                 ipc: Some(Ipc {
                     disable: None,
@@ -1600,8 +1704,12 @@ mod tests {
                     tx_queue_mem_limit: None,
                     tx_queue_locals: None,
                     tx_queue_strategy: None,
+                    tx_queue_sender_rate: None,
+                    tx_queue_contract_rate: None,
+                    tx_queue_replace_bump: None,
                     tx_queue_ban_count: None,
                     tx_queue_ban_time: None,
+                    tx_queue_events_log: None,
                     tx_queue_no_unfamiliar_locals: None,
                     tx_queue_no_early_reject: None,
                     tx_gas_limit: None,
@@ -54,13 +54,14 @@ use crate::{
     },
     cache::CacheConfig,
     helpers::{
-        parity_ipc_path, to_address, to_addresses, to_block_id, to_bootnodes, to_duration, to_mode,
-        to_pending_set, to_price, to_queue_penalization, to_queue_strategy, to_u256,
+        parity_ipc_path, to_address, to_addresses, to_block_id, to_bootnodes, to_checkpoint,
+        to_dns_discovery, to_duration, to_mode, to_pending_set, to_price, to_propagation_mode,
+        to_queue_penalization, to_queue_strategy, to_u256,
     },
     network::IpFilter,
     params::{AccountsConfig, GasPricerConfig, MinerExtras, ResealPolicy, SpecType},
     presale::ImportWallet,
-    rpc::{HttpConfiguration, IpcConfiguration, WsConfiguration},
+    rpc::{HttpConfiguration, IpcConfiguration, JwtConfiguration, WsConfiguration},
     run::RunCmd,
     secretstore::{
         Configuration as SecretStoreConfiguration, ContractAddress as SecretStoreContractAddress,
@@ -421,16 +422,33 @@ impl Configuration {
                 vm_type: vm_type,
                 warp_sync: warp_sync,
                 warp_barrier: self.args.arg_warp_barrier,
+                snap_sync: self.args.flag_snap_sync,
+                checkpoint_sync: match self.args.arg_checkpoint_sync {
+                    Some(ref checkpoint) => Some(to_checkpoint(checkpoint)?),
+                    None => None,
+                },
+                tx_propagation: to_propagation_mode(
+                    &self.args.arg_tx_propagation,
+                    &self.args.arg_tx_propagation_peers,
+                )?,
                 experimental_rpcs,
                 net_settings: self.network_settings()?,
                 secretstore_conf: secretstore_conf,
                 name: self.args.arg_identity,
                 custom_bootnodes: self.args.arg_bootnodes.is_some(),
+                custom_dns_discovery: self.args.arg_dns_discovery.is_some(),
                 check_seal: !self.args.flag_no_seal_check,
                 download_old_blocks: !self.args.flag_no_ancient_blocks,
+                ancient_blocks_from: self.args.arg_ancient_blocks_from,
                 new_transactions_stats_period: self.args.arg_new_transactions_stats_period,
                 verifier_settings: verifier_settings,
                 no_persistent_txqueue: self.args.flag_no_persistent_txqueue,
+                tx_queue_journal: self.args.flag_tx_queue_journal,
+                tx_queue_events_log: self
+                    .args
+                    .arg_tx_queue_events_log
+                    .as_ref()
+                    .map(|path| replace_home(&self.directories().base, path)),
                 max_round_blocks_to_import: self.args.arg_max_round_blocks_to_import,
                 metrics_conf,
             };
@@ -617,6 +635,7 @@ impl Configuration {
 
             pool_limits: self.pool_limits()?,
             pool_verification_options: self.pool_verification_options()?,
+            pool_spam_protection: self.pool_spam_protection()?,
         };
 
         Ok(options)
@@ -654,6 +673,31 @@ impl Configuration {
         })
     }
 
+    fn pool_spam_protection(&self) -> Result<pool::spam::Options, String> {
+        let rate_limit = |per_minute: Option<u32>, flag: &str| match per_minute {
+            Some(0) => Err(format!("{} must be greater than 0", flag)),
+            Some(per_minute) => Ok(Some(pool::spam::RateLimit {
+                per_minute,
+                burst: per_minute,
+            })),
+            None => Ok(None),
+        };
+
+        Ok(pool::spam::Options {
+            sender_rate_limit: rate_limit(
+                self.args.arg_tx_queue_sender_rate,
+                "--tx-queue-sender-rate",
+            )?,
+            recipient_rate_limit: rate_limit(
+                self.args.arg_tx_queue_contract_rate,
+                "--tx-queue-contract-rate",
+            )?,
+            replacement_bump_percent: self.args.arg_tx_queue_replace_bump,
+            ban_threshold: self.args.arg_tx_queue_ban_count.into(),
+            ban_duration: Duration::from_secs(self.args.arg_tx_queue_ban_time.into()),
+        })
+    }
+
     fn secretstore_config(&self) -> Result<SecretStoreConfiguration, String> {
         Ok(SecretStoreConfiguration {
             enabled: self.secretstore_enabled(),
@@ -815,6 +859,7 @@ impl Configuration {
         let mut ret = NetworkConfiguration::new();
         ret.nat_enabled = self.args.arg_nat == "any" || self.args.arg_nat == "upnp";
         ret.boot_nodes = to_bootnodes(&self.args.arg_bootnodes)?;
+        ret.dns_discovery = to_dns_discovery(&self.args.arg_dns_discovery)?;
         let (listen, public) = self.net_addresses()?;
         ret.listen_address = Some(format!("{}", listen));
         ret.public_address = public.map(|p| format!("{}", p));
@@ -828,6 +873,7 @@ impl Configuration {
             Some(Err(err)) => return Err(err),
         };
         ret.discovery_enabled = !self.args.flag_no_discovery;
+        ret.discovery_v5_enabled = self.args.flag_discovery_v5;
         ret.max_peers = self.max_peers();
         ret.min_peers = self.min_peers();
         ret.snapshot_peers = self.snapshot_peers();
@@ -939,6 +985,7 @@ impl Configuration {
                 _ => 5usize,
             },
             keep_alive: !self.args.flag_jsonrpc_no_keep_alive,
+            jwt: self.jwt_config()?,
         };
 
         Ok(conf)
@@ -960,11 +1007,22 @@ impl Configuration {
             support_token_api,
             max_connections: self.args.arg_ws_max_connections,
             max_payload: self.args.arg_ws_max_payload,
+            jwt: self.jwt_config()?,
         };
 
         Ok(conf)
     }
 
+    fn jwt_config(&self) -> Result<Option<JwtConfiguration>, String> {
+        match self.args.arg_jwt_secret {
+            Some(ref path) => Ok(Some(JwtConfiguration {
+                secret_path: replace_home(&self.directories().base, path).into(),
+                apis: self.args.arg_jwt_apis.parse()?,
+            })),
+            None => Ok(None),
+        }
+    }
+
     fn metrics_config(&self) -> Result<MetricsConfiguration, String> {
         let conf = MetricsConfiguration {
             enabled: self.metrics_enabled(),
@@ -1500,6 +1558,7 @@ mod tests {
                     support_token_api: true,
                     max_connections: 100,
                     max_payload: 5,
+                    jwt: None,
                 },
                 LogConfig {
                     color: !cfg!(windows),
@@ -1524,6 +1583,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_jwt_config() {
+        let args = vec![
+            "openethereum",
+            "--jwt-secret",
+            "/tmp/jwt.hex",
+            "--jwt-apis",
+            "personal,parity_set",
+        ];
+        let conf = parse(&args);
+
+        let expected = Some(JwtConfiguration {
+            secret_path: "/tmp/jwt.hex".into(),
+            apis: "personal,parity_set".parse().unwrap(),
+        });
+        assert_eq!(conf.http_config().unwrap().jwt, expected);
+        assert_eq!(conf.ws_config().unwrap().jwt, expected);
+        assert_eq!(parse(&["openethereum"]).http_config().unwrap().jwt, None);
+    }
+
     #[test]
     fn test_run_cmd() {
         let args = vec!["openethereum"];
@@ -1548,6 +1627,9 @@ mod tests {
             network_id: None,
             warp_sync: true,
             warp_barrier: None,
+            snap_sync: false,
+            checkpoint_sync: None,
+            tx_propagation: sync::PropagationMode::Default,
             acc_conf: Default::default(),
             gas_pricer_conf: Default::default(),
             miner_extras: Default::default(),
@@ -1560,14 +1642,18 @@ mod tests {
             secretstore_conf: Default::default(),
             name: "".into(),
             custom_bootnodes: false,
+            custom_dns_discovery: false,
             fat_db: Default::default(),
             snapshot_conf: Default::default(),
             stratum: None,
             check_seal: true,
             download_old_blocks: true,
+            ancient_blocks_from: None,
             new_transactions_stats_period: 0,
             verifier_settings: Default::default(),
             no_persistent_txqueue: false,
+            tx_queue_journal: false,
+            tx_queue_events_log: None,
             max_round_blocks_to_import: 1,
             metrics_conf: MetricsConfiguration::default(),
         };
@@ -1591,6 +1677,40 @@ mod tests {
         assert_eq!(conf2.miner_options().unwrap(), mining_options);
     }
 
+    #[test]
+    fn should_parse_spam_protection_options() {
+        // given
+        let conf = parse(&[
+            "openethereum",
+            "--tx-queue-sender-rate",
+            "30",
+            "--tx-queue-replace-bump",
+            "25",
+            "--tx-queue-ban-count",
+            "3",
+            "--tx-queue-ban-time",
+            "60",
+        ]);
+
+        // then
+        assert_eq!(
+            conf.miner_options().unwrap().pool_spam_protection,
+            pool::spam::Options {
+                sender_rate_limit: Some(pool::spam::RateLimit {
+                    per_minute: 30,
+                    burst: 30,
+                }),
+                recipient_rate_limit: None,
+                replacement_bump_percent: 25,
+                ban_threshold: 3,
+                ban_duration: Duration::from_secs(60),
+            }
+        );
+        assert!(parse(&["openethereum", "--tx-queue-contract-rate", "0"])
+            .miner_options()
+            .is_err());
+    }
+
     #[test]
     fn should_fail_on_force_reseal_and_reseal_min_period() {
         let conf = parse(&[
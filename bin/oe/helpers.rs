@@ -18,7 +18,7 @@ use crate::{
     cache::CacheConfig,
     db::migrate,
     miner::pool::PrioritizationStrategy,
-    sync::{self, validate_node_url},
+    sync::{self, validate_enrtree_url, validate_node_url},
     upgrade::{upgrade, upgrade_data_paths},
 };
 use dir::{helpers::replace_home, DatabaseDirectories};
@@ -26,7 +26,7 @@ use ethcore::{
     client::{BlockId, ClientConfig, DatabaseCompactionProfile, Mode, VMType, VerifierType},
     miner::{Penalization, PendingSet},
 };
-use ethereum_types::{Address, U256};
+use ethereum_types::{Address, H512, U256};
 use ethkey::Password;
 use journaldb::Algorithm;
 use std::{
@@ -134,6 +134,7 @@ pub fn to_pending_set(s: &str) -> Result<PendingSet, String> {
 pub fn to_queue_strategy(s: &str) -> Result<PrioritizationStrategy, String> {
     match s {
         "gas_price" => Ok(PrioritizationStrategy::GasPriceOnly),
+        "profit" => Ok(PrioritizationStrategy::ProfitMaximizing),
         other => Err(format!("Invalid queue strategy: {}", other)),
     }
 }
@@ -224,6 +225,89 @@ pub fn to_bootnodes(bootnodes: &Option<String>) -> Result<Vec<String>, String> {
     }
 }
 
+pub fn to_dns_discovery(urls: &Option<String>) -> Result<Vec<String>, String> {
+    match *urls {
+        Some(ref x) if !x.is_empty() => x
+            .split(',')
+            .map(|s| match validate_enrtree_url(s) {
+                None => Ok(s.to_owned()),
+                Some(_) => Err(format!("Invalid DNS discovery URL given: {}", s)),
+            })
+            .collect(),
+        Some(_) => Ok(vec![]),
+        None => Ok(vec![]),
+    }
+}
+
+/// Parses the transaction propagation mode together with the comma-separated enodes
+/// used by the `broadcast` and `private` modes.
+pub fn to_propagation_mode(
+    mode: &str,
+    peers: &Option<String>,
+) -> Result<sync::PropagationMode, String> {
+    let node_ids = || -> Result<HashSet<H512>, String> {
+        let peers = match *peers {
+            Some(ref x) if !x.is_empty() => x,
+            _ => {
+                return Err(format!(
+                    "Transaction propagation mode {} requires --tx-propagation-peers",
+                    mode
+                ))
+            }
+        };
+        peers
+            .split(',')
+            .map(|s| {
+                let invalid = || format!("Invalid enode given for transaction propagation: {}", s);
+                if validate_node_url(s).is_some() {
+                    return Err(invalid());
+                }
+                s.trim_start_matches("enode://")
+                    .split('@')
+                    .next()
+                    .and_then(|id| id.parse().ok())
+                    .ok_or_else(invalid)
+            })
+            .collect()
+    };
+    match mode {
+        "default" => Ok(sync::PropagationMode::Default),
+        "announce" => Ok(sync::PropagationMode::AnnounceOnly),
+        "broadcast" => Ok(sync::PropagationMode::Broadcast(node_ids()?)),
+        "no-local" => Ok(sync::PropagationMode::NoLocal),
+        "private" => Ok(sync::PropagationMode::PrivateRelay(node_ids()?)),
+        other => Err(format!("Invalid transaction propagation mode: {}", other)),
+    }
+}
+
+/// Parses a checkpoint given as `NUMBER:HASH:TOTAL_DIFFICULTY`.
+pub fn to_checkpoint(s: &str) -> Result<sync::Checkpoint, String> {
+    let err = || {
+        format!(
+            "Invalid checkpoint, expected NUMBER:HASH:TOTAL_DIFFICULTY: {}",
+            s
+        )
+    };
+    let mut parts = s.split(':');
+    let number = parts.next().and_then(|n| n.parse().ok()).ok_or_else(err)?;
+    let hash = parts
+        .next()
+        .and_then(|h| clean_0x(h).parse().ok())
+        .ok_or_else(err)?;
+    let total_difficulty = parts
+        .next()
+        .and_then(|td| to_u256(td).ok())
+        .ok_or_else(err)?;
+    if parts.next().is_some() {
+        return Err(err());
+    }
+    Ok(sync::Checkpoint {
+        number,
+        hash,
+        total_difficulty,
+    })
+}
+
 #[cfg(test)]
 pub fn default_network_config() -> crate::sync::NetworkConfiguration {
     use super::network::IpFilter;
@@ -236,7 +320,9 @@ pub fn default_network_config() -> crate::sync::NetworkConfiguration {
         udp_port: None,
         nat_enabled: true,
         discovery_enabled: true,
+        discovery_v5_enabled: false,
         boot_nodes: Vec::new(),
+        dns_discovery: Vec::new(),
         use_secret: None,
         max_peers: 50,
         min_peers: 25,
@@ -377,8 +463,10 @@ pub fn passwords_from_files(files: &[String]) -> Result<Vec<Password>, String> {
 mod tests {
     use super::{
         join_set, password_from_file, to_address, to_addresses, to_block_id, to_bootnodes,
-        to_duration, to_mode, to_pending_set, to_price, to_u256,
+        to_checkpoint, to_dns_discovery, to_duration, to_mode, to_pending_set, to_price,
+        to_propagation_mode, to_u256,
     };
+    use crate::sync;
     use ethcore::{
         client::{BlockId, Mode},
         miner::PendingSet,
@@ -583,6 +671,56 @@ but the first password is trimmed
         );
     }
 
+    #[test]
+    fn test_to_checkpoint() {
+        let hash = "0x88e96d4537bea4d9c05d12549907b32561d3bf31f45aae734cdc119f13406cb6";
+        let checkpoint = to_checkpoint(&format!("1:{}:0x1000", hash)).unwrap();
+        assert_eq!(checkpoint.number, 1);
+        assert_eq!(checkpoint.hash, hash[2..].parse().unwrap());
+        assert_eq!(checkpoint.total_difficulty, 0x1000.into());
+        assert!(to_checkpoint("1").is_err());
+        assert!(to_checkpoint(&format!("1:{}", hash)).is_err());
+        assert!(to_checkpoint(&format!("one:{}", hash)).is_err());
+        assert!(to_checkpoint("1:0x1234").is_err());
+        assert!(to_checkpoint(&format!("1:{}:1:2", hash)).is_err());
+    }
+
+    #[test]
+    fn test_to_propagation_mode() {
+        let id = "a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c";
+        let enode = format!("enode://{}@22.99.55.44:7770", id);
+
+        assert_eq!(
+            to_propagation_mode("default", &None),
+            Ok(sync::PropagationMode::Default)
+        );
+        assert_eq!(
+            to_propagation_mode("no-local", &None),
+            Ok(sync::PropagationMode::NoLocal)
+        );
+        assert_eq!(
+            to_propagation_mode("private", &Some(enode)),
+            Ok(sync::PropagationMode::PrivateRelay(
+                vec![id.parse().unwrap()].into_iter().collect()
+            ))
+        );
+        assert!(to_propagation_mode("broadcast", &None).is_err());
+        assert!(
+            to_propagation_mode("private", &Some("enode://1234@22.99.55.44:7770".into())).is_err()
+        );
+        assert!(to_propagation_mode("everyone", &None).is_err());
+    }
+
+    #[test]
+    fn test_to_dns_discovery() {
+        let url = "enrtree://AKA3AM6LPBYEUDMVNU3BSVQJ5AD45Y7YPOHJLEF6W26QOE4VTUDPE@all.mainnet.ethdisco.net";
+
+        assert_eq!(to_dns_discovery(&Some("".into())), Ok(vec![]));
+        assert_eq!(to_dns_discovery(&None), Ok(vec![]));
+        assert_eq!(to_dns_discovery(&Some(url.into())), Ok(vec![url.into()]));
+        assert!(to_dns_discovery(&Some("enrtree://invalid@nodes.example.org".into())).is_err());
+    }
+
     #[test]
     fn test_join_set() {
         let mut test_set = HashSet::new();
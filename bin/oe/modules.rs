@@ -17,7 +17,9 @@
 use std::sync::{mpsc, Arc};
 
 use crate::{
-    sync::{self, ConnectionFilter, NetworkConfiguration, Params, SyncConfig},
+    sync::{
+        self, ConnectionFilter, NetworkConfiguration, Params, SyncConfig, TransactionPropagation,
+    },
     types::BlockNumber,
 };
 use ethcore::{client::BlockChainClient, snapshot::SnapshotService};
@@ -42,6 +44,7 @@ pub fn sync(
     chain: Arc<dyn BlockChainClient>,
     forks: BTreeSet<BlockNumber>,
     snapshot_service: Arc<dyn SnapshotService>,
+    propagation: Arc<dyn TransactionPropagation>,
     _log_settings: &LogConfig,
     connection_filter: Option<Arc<dyn ConnectionFilter>>,
 ) -> Result<SyncModules, sync::Error> {
@@ -52,6 +55,7 @@ pub fn sync(
             forks,
             snapshot_service,
             network_config,
+            propagation,
         },
         connection_filter,
     )?;
@@ -47,6 +47,7 @@ pub struct HttpConfiguration {
     pub processing_threads: usize,
     pub max_payload: usize,
     pub keep_alive: bool,
+    pub jwt: Option<JwtConfiguration>,
 }
 
 impl Default for HttpConfiguration {
@@ -62,10 +63,27 @@ impl Default for HttpConfiguration {
             processing_threads: 4,
             max_payload: 5,
             keep_alive: true,
+            jwt: None,
         }
     }
 }
 
+/// JWT authentication of the HTTP and WebSockets servers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JwtConfiguration {
+    /// File with the hex encoded shared secret, generated if missing.
+    pub secret_path: PathBuf,
+    /// APIs requiring a token. WebSockets connections always need a token in the handshake.
+    pub apis: ApiSet,
+}
+
+impl JwtConfiguration {
+    fn auth(&self) -> Result<Arc<rpc::JwtAuth>, String> {
+        let secret = rpc::JwtSecret::load_or_generate(&self.secret_path)?;
+        Ok(Arc::new(rpc::JwtAuth::new(secret)))
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct IpcConfiguration {
     pub enabled: bool,
@@ -100,6 +118,7 @@ pub struct WsConfiguration {
     pub signer_path: PathBuf,
     pub support_token_api: bool,
     pub max_payload: usize,
+    pub jwt: Option<JwtConfiguration>,
 }
 
 impl Default for WsConfiguration {
@@ -120,6 +139,7 @@ impl Default for WsConfiguration {
             signer_path: replace_home(&data_dir, "$BASE/signer").into(),
             support_token_api: true,
             max_payload: 5,
+            jwt: None,
         }
     }
 }
@@ -166,6 +186,13 @@ pub fn new_ws<D: rpc_apis::Dependencies>(
         .parse()
         .map_err(|_| format!("Invalid WebSockets listen host/port given: {}", url))?;
 
+    // Unlike HTTP, WebSockets can't gate single APIs: the token is checked once in the
+    // handshake and `jwt.apis` is ignored, so a connection is either refused or gets every API.
+    let jwt = match conf.jwt {
+        Some(ref jwt) => Some(jwt.auth()?),
+        None => None,
+    };
+
     let full_handler = setup_apis(rpc_apis::ApiSet::All, deps);
     let handler = {
         let mut handler = MetaIoHandler::with_middleware((
@@ -189,14 +216,18 @@ pub fn new_ws<D: rpc_apis::Dependencies>(
         }
         false => None,
     };
+    let extractor = || match jwt {
+        Some(ref auth) => rpc::WsExtractor::new(path.clone()).with_jwt(auth.clone()),
+        None => rpc::WsExtractor::new(path.clone()),
+    };
     let start_result = rpc::start_ws(
         &addr,
         handler,
         allowed_origins,
         allowed_hosts,
         conf.max_connections,
-        rpc::WsExtractor::new(path.clone()),
-        rpc::WsExtractor::new(path.clone()),
+        extractor(),
+        extractor(),
         rpc::WsStats::new(deps.stats.clone()),
         conf.max_payload,
     );
@@ -234,21 +265,58 @@ pub fn new_http<D: rpc_apis::Dependencies>(
     let addr = url
         .parse()
         .map_err(|_| format!("Invalid {} listen host/port given: {}", id, url))?;
-    let handler = setup_apis(conf.apis, deps);
 
     let cors_domains = into_domains(conf.cors);
     let allowed_hosts = into_domains(with_domain(conf.hosts, domain, &Some(url.clone().into())));
 
-    let start_result = rpc::start_http(
-        &addr,
-        cors_domains,
-        allowed_hosts,
-        handler,
-        rpc::RpcExtractor,
-        conf.server_threads,
-        conf.max_payload,
-        conf.keep_alive,
-    );
+    let start_result = match conf.jwt {
+        None => rpc::start_http(
+            &addr,
+            cors_domains,
+            allowed_hosts,
+            setup_apis(conf.apis, deps),
+            rpc::RpcExtractor,
+            conf.server_threads,
+            conf.max_payload,
+            conf.keep_alive,
+        ),
+        Some(ref jwt) => {
+            let auth = jwt.auth()?;
+            let apis = conf.apis.list_apis();
+            let public_apis = &apis - &jwt.apis.list_apis();
+            if public_apis.is_empty() {
+                // every request needs a token, reject the others before parsing
+                rpc::start_http_with_middleware(
+                    &addr,
+                    cors_domains,
+                    allowed_hosts,
+                    setup_apis(conf.apis, deps),
+                    rpc::RpcExtractor,
+                    rpc::HttpJwtMiddleware::new(auth),
+                    conf.server_threads,
+                    conf.max_payload,
+                    conf.keep_alive,
+                )
+            } else {
+                let full_handler = setup_apis(conf.apis, deps);
+                let mut handler = MetaIoHandler::with_middleware((
+                    rpc::JwtDispatcher::new(full_handler),
+                    Middleware::new(deps.stats.clone(), deps.apis.activity_notifier()),
+                ));
+                deps.apis.extend_with_set(&mut handler, &public_apis);
+                rpc::start_http(
+                    &addr,
+                    cors_domains,
+                    allowed_hosts,
+                    handler,
+                    rpc::HttpJwtExtractor::new(auth),
+                    conf.server_threads,
+                    conf.max_payload,
+                    conf.keep_alive,
+                )
+            }
+        }
+    };
 
     match start_result {
 		Ok(server) => Ok(Some(server)),
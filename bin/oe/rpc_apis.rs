@@ -35,7 +35,7 @@ use jsonrpc_core::{self as core, MetaIoHandler};
 use parity_rpc::{
     dispatch::FullDispatcher,
     informant::{ActivityNotifier, ClientNotifier},
-    Host, Metadata, NetworkSettings,
+    Host, Metadata, NetworkSettings, PoolJournal,
 };
 use parity_runtime::Executor;
 use parking_lot::Mutex;
@@ -71,6 +71,8 @@ pub enum Api {
     /// Geth-compatible (best-effort) debug API (Potentially UNSAFE)
     /// NOTE We don't aim to support all methods, only the ones that are useful.
     Debug,
+    /// Geth-compatible transaction pool inspection API (Safe)
+    TxPool,
 }
 
 impl FromStr for Api {
@@ -93,6 +95,7 @@ impl FromStr for Api {
             "secretstore" => Ok(SecretStore),
             "signer" => Ok(Signer),
             "traces" => Ok(Traces),
+            "txpool" => Ok(TxPool),
             "web3" => Ok(Web3),
             api => Err(format!("Unknown api: {}", api)),
         }
@@ -173,6 +176,7 @@ fn to_modules(apis: &HashSet<Api>) -> BTreeMap<String, String> {
             Api::SecretStore => ("secretstore", "1.0"),
             Api::Signer => ("signer", "1.0"),
             Api::Traces => ("traces", "1.0"),
+            Api::TxPool => ("txpool", "1.0"),
             Api::Web3 => ("web3", "1.0"),
         };
         modules.insert(name.into(), version.into());
@@ -226,6 +230,7 @@ pub struct FullDependencies {
     pub logger: Arc<RotatingLogger>,
     pub settings: Arc<NetworkSettings>,
     pub net_service: Arc<dyn ManageNetwork>,
+    pub pool_journal: Option<Arc<dyn PoolJournal>>,
     pub experimental_rpcs: bool,
     pub ws_address: Option<Host>,
     pub fetch: FetchClient,
@@ -305,7 +310,7 @@ impl FullDependencies {
                 }
                 Api::EthPubSub => {
                     if !for_generic_pubsub {
-                        let client =
+                        let mut client =
                             EthPubSubClient::new(self.client.clone(), self.executor.clone());
                         let h = client.handler();
                         self.miner
@@ -314,6 +319,30 @@ impl FullDependencies {
                                     h.notify_new_transactions(hashes);
                                 }
                             }));
+                        // only listen to pool events while someone is subscribed to them.
+                        let h = client.handler();
+                        let miner = self.miner.clone();
+                        let registered = Mutex::new(None);
+                        client.on_transaction_events_subscribers_changed(Box::new(move || {
+                            let mut registered = registered.lock();
+                            let has_subscribers = h
+                                .upgrade()
+                                .map_or(false, |h| h.has_transaction_events_subscribers());
+                            match (has_subscribers, registered.take()) {
+                                (true, None) => {
+                                    let h = h.clone();
+                                    *registered = Some(miner.add_transaction_events_listener(
+                                        Box::new(move |events| {
+                                            if let Some(h) = h.upgrade() {
+                                                h.notify_transaction_events(events);
+                                            }
+                                        }),
+                                    ));
+                                }
+                                (false, Some(id)) => miner.remove_transaction_events_listener(id),
+                                (_, id) => *registered = id,
+                            }
+                        }));
 
                         if let Some(h) = client.handler().upgrade() {
                             self.client.add_notify(h);
@@ -400,6 +429,7 @@ impl FullDependencies {
                             &self.client,
                             &self.miner,
                             &self.net_service,
+                            self.pool_journal.clone(),
                             self.fetch.clone(),
                         )
                         .to_delegate(),
@@ -410,6 +440,9 @@ impl FullDependencies {
                     );
                 }
                 Api::Traces => handler.extend_with(TracesClient::new(&self.client).to_delegate()),
+                Api::TxPool => handler.extend_with(
+                    TxPoolClient::new(self.client.clone(), self.miner.clone()).to_delegate(),
+                ),
                 Api::Rpc => {
                     let modules = to_modules(&apis);
                     handler.extend_with(RpcClient::new(modules).to_delegate());
@@ -474,6 +507,7 @@ impl ApiSet {
             }
             ApiSet::All => {
                 public_list.insert(Api::Debug);
+                public_list.insert(Api::TxPool);
                 public_list.insert(Api::Traces);
                 public_list.insert(Api::ParityPubSub);
                 public_list.insert(Api::ParityAccounts);
@@ -514,6 +548,7 @@ mod test {
         assert_eq!(Api::ParityAccounts, "parity_accounts".parse().unwrap());
         assert_eq!(Api::ParitySet, "parity_set".parse().unwrap());
         assert_eq!(Api::Traces, "traces".parse().unwrap());
+        assert_eq!(Api::TxPool, "txpool".parse().unwrap());
         assert_eq!(Api::Rpc, "rpc".parse().unwrap());
         assert_eq!(Api::SecretStore, "secretstore".parse().unwrap());
         assert!("rp".parse::<Api>().is_err());
@@ -590,6 +625,7 @@ mod test {
                     Api::Signer,
                     Api::Personal,
                     Api::Debug,
+                    Api::TxPool,
                 ]
                 .into_iter()
                 .collect()
@@ -616,6 +652,7 @@ mod test {
                     Api::ParitySet,
                     Api::Signer,
                     Api::Debug,
+                    Api::TxPool,
                 ]
                 .into_iter()
                 .collect()
@@ -90,6 +90,9 @@ pub struct RunCmd {
     pub network_id: Option<u64>,
     pub warp_sync: bool,
     pub warp_barrier: Option<u64>,
+    pub snap_sync: bool,
+    pub checkpoint_sync: Option<sync::Checkpoint>,
+    pub tx_propagation: sync::PropagationMode,
     pub acc_conf: AccountsConfig,
     pub gas_pricer_conf: GasPricerConfig,
     pub miner_extras: MinerExtras,
@@ -103,14 +106,18 @@ pub struct RunCmd {
     pub secretstore_conf: secretstore::Configuration,
     pub name: String,
     pub custom_bootnodes: bool,
+    pub custom_dns_discovery: bool,
     pub stratum: Option<stratum::Options>,
     pub snapshot_conf: SnapshotConfiguration,
     pub check_seal: bool,
     pub allow_missing_blocks: bool,
     pub download_old_blocks: bool,
+    pub ancient_blocks_from: Option<u64>,
     pub new_transactions_stats_period: u64,
     pub verifier_settings: VerifierSettings,
     pub no_persistent_txqueue: bool,
+    pub tx_queue_journal: bool,
+    pub tx_queue_events_log: Option<String>,
     pub max_round_blocks_to_import: usize,
     pub metrics_conf: MetricsConfiguration,
 }
@@ -138,6 +145,41 @@ impl crate::local_store::NodeInfo for FullNodeInfo {
             })
             .collect()
     }
+
+    fn pool_transactions(&self) -> Vec<crate::local_store::PooledTransaction> {
+        use crate::{
+            local_store::{PooledTransaction, Priority},
+            miner::pool::{self, ScoredTransaction},
+        };
+
+        let miner = match self.miner.as_ref() {
+            Some(m) => m,
+            None => return Vec::new(),
+        };
+
+        let mut transactions = miner.queued_transactions();
+        transactions.sort_by_key(|tx| tx.insertion_id());
+        transactions
+            .into_iter()
+            .map(|tx| PooledTransaction {
+                transaction: tx.pending().clone(),
+                priority: match tx.priority() {
+                    pool::Priority::Local => Priority::Local,
+                    pool::Priority::Retracted => Priority::Retracted,
+                    pool::Priority::Regular => Priority::Regular,
+                },
+            })
+            .collect()
+    }
+}
+
+// exposes the pool journal of the local store to the rpc.
+struct StorePoolJournal(Arc<crate::local_store::LocalDataStore<FullNodeInfo>>);
+
+impl parity_rpc::PoolJournal for StorePoolJournal {
+    fn dump(&self) -> Result<usize, String> {
+        self.0.dump_pool().map_err(|e| e.to_string())
+    }
 }
 
 /// Executes the given run command.
@@ -254,8 +296,17 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
         (true, _) => sync::WarpSync::Enabled,
         _ => sync::WarpSync::Disabled,
     };
+    sync_config.snap_sync = warp_sync && cmd.snap_sync;
+    sync_config.checkpoint = match cmd.checkpoint_sync {
+        Some(_) if !warp_sync => {
+            warn!("Warning: Checkpoint sync is disabled because warp sync is disabled.");
+            None
+        }
+        checkpoint => checkpoint,
+    };
     sync_config.download_old_blocks = cmd.download_old_blocks;
-    sync_config.eip1559_transition = spec.params().eip1559_transition;
+    sync_config.ancient_blocks_from = cmd.ancient_blocks_from;
+    sync_config.header_transitions = spec.params().header_transitions();
     sync_config.new_transactions_stats_period = cmd.new_transactions_stats_period;
 
     let passwords = passwords_from_files(&cmd.acc_conf.password_files)?;
@@ -340,6 +391,9 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
     if !cmd.custom_bootnodes {
         net_conf.boot_nodes = spec.nodes.clone();
     }
+    if !cmd.custom_dns_discovery {
+        net_conf.dns_discovery = spec.dns_discovery.clone();
+    }
 
     // set network path.
     net_conf.net_config_path = Some(db_dirs.network_path().to_string_lossy().into_owned());
@@ -403,7 +457,8 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
             db.key_value().clone(),
             ::ethcore_db::COL_NODE_INFO,
             node_info,
-        );
+        )
+        .with_pool_journal(cmd.tx_queue_journal && !cmd.no_persistent_txqueue);
 
         if cmd.no_persistent_txqueue {
             info!("Running without a persistent transaction queue.");
@@ -425,14 +480,58 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
             Err(e) => warn!("Error loading cached pending transactions from disk: {}", e),
         }
 
+        // re-queue the journaled pool, stale transactions are dropped on import.
+        if store.has_pool_journal() {
+            match store.pool_transactions() {
+                Ok(journaled) => {
+                    use crate::{local_store::Priority, miner::pool::verifier::Transaction};
+
+                    let total = journaled.len();
+                    let transactions = journaled
+                        .into_iter()
+                        .map(|tx| match tx.priority {
+                            Priority::Local => Transaction::Local(tx.transaction),
+                            Priority::Retracted => {
+                                Transaction::Retracted(tx.transaction.transaction.into())
+                            }
+                            Priority::Regular => {
+                                Transaction::Unverified(tx.transaction.transaction.into())
+                            }
+                        })
+                        .collect();
+                    let imported = miner
+                        .import_journaled_transactions(&*client, transactions)
+                        .into_iter()
+                        .filter(Result::is_ok)
+                        .count();
+                    info!("Restored {} of {} journaled transactions.", imported, total);
+                }
+                Err(e) => warn!("Error loading transaction pool journal from disk: {}", e),
+            }
+        } else if let Err(e) = store.clear_pool() {
+            warn!("Error clearing transaction pool journal: {}", e);
+        }
+
         Arc::new(store)
     };
 
     // register it as an IO service to update periodically.
     service
-        .register_io_handler(store)
+        .register_io_handler(store.clone())
         .map_err(|_| "Unable to register local store handler".to_owned())?;
 
+    let pool_journal = match store.has_pool_journal() {
+        true => Some(Arc::new(StorePoolJournal(store)) as Arc<dyn parity_rpc::PoolJournal>),
+        false => None,
+    };
+
+    // log lifecycle events of pool transactions.
+    if let Some(ref path) = cmd.tx_queue_events_log {
+        let sink = crate::miner::pool::events::JsonLinesSink::open(path)
+            .map_err(|e| format!("Unable to open transaction events log {}: {}", path, e))?;
+        miner.add_transaction_events_listener(Box::new(move |events| sink.write(events)));
+    }
+
     // create external miner
     let external_miner = Arc::new(ExternalMiner::default());
 
@@ -450,6 +549,7 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
             client.clone(),
             forks,
             snapshot_service.clone(),
+            cmd.tx_propagation.clone().policy(),
             &cmd.logger_config,
             connection_filter
                 .clone()
@@ -507,6 +607,7 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
         logger: logger.clone(),
         settings: Arc::new(cmd.net_settings.clone()),
         net_service: manage_network.clone(),
+        pool_journal,
         experimental_rpcs: cmd.experimental_rpcs,
         ws_address: cmd.ws_conf.address(),
         fetch: fetch.clone(),
@@ -664,6 +765,7 @@ impl RunningClient {
         let metadata = Metadata {
             origin: Origin::CApi,
             session,
+            authenticated: false,
         };
 
         match self.inner {
@@ -14,7 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-//! Manages local node data: pending local transactions, sync security level
+//! Manages local node data: pending local transactions, the transaction pool journal,
+//! sync security level
 
 use std::{fmt, sync::Arc, time::Duration};
 
@@ -31,6 +32,7 @@ extern crate ethcore_io as io;
 extern crate kvdb;
 extern crate parity_crypto as crypto;
 extern crate rlp;
+extern crate rustc_hex;
 extern crate serde;
 extern crate serde_json;
 
@@ -46,9 +48,12 @@ extern crate ethkey;
 extern crate kvdb_memorydb;
 
 const LOCAL_TRANSACTIONS_KEY: &'static [u8] = &*b"LOCAL_TXS";
+const POOL_TRANSACTIONS_KEY: &'static [u8] = &*b"POOL_TXS";
 
 const UPDATE_TIMER: ::io::TimerToken = 0;
 const UPDATE_TIMEOUT: Duration = Duration::from_secs(15 * 60); // once every 15 minutes.
+const JOURNAL_TIMER: ::io::TimerToken = 1;
+const JOURNAL_TIMEOUT: Duration = Duration::from_secs(60); // once every minute.
 
 /// Errors which can occur while using the local data store.
 #[derive(Debug)]
@@ -94,6 +99,7 @@ impl Into<TransactionCondition> for Condition {
 
 #[derive(Serialize, Deserialize)]
 struct TransactionEntry {
+    #[serde(with = "hex_bytes")]
     rlp_bytes: Vec<u8>,
     condition: Option<Condition>,
 }
@@ -128,10 +134,97 @@ impl From<PendingTransaction> for TransactionEntry {
     }
 }
 
+// transactions are stored as `0x` prefixed hex,
+// entries written by older versions hold an array of numbers instead.
+mod hex_bytes {
+    use rustc_hex::{FromHex, ToHex};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Hex(String),
+        Numbers(Vec<u8>),
+    }
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{}", bytes.to_hex()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        match Repr::deserialize(deserializer)? {
+            Repr::Hex(hex) => {
+                let hex = if hex.starts_with("0x") {
+                    &hex[2..]
+                } else {
+                    &hex[..]
+                };
+                hex.from_hex()
+                    .map_err(|e| D::Error::custom(format!("invalid hex: {}", e)))
+            }
+            Repr::Numbers(bytes) => Ok(bytes),
+        }
+    }
+}
+
+/// Priority a transaction had in the pool when it was journaled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Priority {
+    /// Local transaction.
+    Local,
+    /// Transaction from a retracted block.
+    Retracted,
+    /// Regular transaction received over the network.
+    Regular,
+}
+
+/// A transaction from the pool journal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PooledTransaction {
+    /// The transaction itself.
+    pub transaction: PendingTransaction,
+    /// Priority of the transaction in the pool.
+    pub priority: Priority,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PoolEntry {
+    transaction: TransactionEntry,
+    priority: Priority,
+}
+
+impl PoolEntry {
+    fn into_pooled(self) -> Option<PooledTransaction> {
+        let priority = self.priority;
+        self.transaction
+            .into_pending()
+            .map(|transaction| PooledTransaction {
+                transaction,
+                priority,
+            })
+    }
+}
+
+impl From<PooledTransaction> for PoolEntry {
+    fn from(pooled: PooledTransaction) -> Self {
+        PoolEntry {
+            transaction: pooled.transaction.into(),
+            priority: pooled.priority,
+        }
+    }
+}
+
 /// Something which can provide information about the local node.
 pub trait NodeInfo: Send + Sync {
     /// Get all pending transactions of local origin.
     fn pending_transactions(&self) -> Vec<PendingTransaction>;
+
+    /// Get all transactions in the pool, in the order they were inserted.
+    ///
+    /// Only used when the pool journal is enabled.
+    fn pool_transactions(&self) -> Vec<PooledTransaction> {
+        Vec::new()
+    }
 }
 
 /// Create a new local data store, given a database, a column to write to, and a node.
@@ -145,6 +238,7 @@ pub fn create<T: NodeInfo>(
         db: db,
         col: col,
         node: node,
+        pool_journal: false,
     }
 }
 
@@ -156,9 +250,23 @@ pub struct LocalDataStore<T: NodeInfo> {
     db: Arc<dyn KeyValueDB>,
     col: Option<u32>,
     node: T,
+    pool_journal: bool,
 }
 
 impl<T: NodeInfo> LocalDataStore<T> {
+    /// Enable or disable the journal of the whole transaction pool.
+    ///
+    /// When enabled, all pooled transactions are written out periodically and on shutdown.
+    pub fn with_pool_journal(mut self, enabled: bool) -> Self {
+        self.pool_journal = enabled;
+        self
+    }
+
+    /// Whether the pool journal is enabled.
+    pub fn has_pool_journal(&self) -> bool {
+        self.pool_journal
+    }
+
     /// Attempt to read pending transactions out of the local store.
     pub fn pending_transactions(&self) -> Result<Vec<PendingTransaction>, Error> {
         if let Some(val) = self
@@ -189,24 +297,80 @@ impl<T: NodeInfo> LocalDataStore<T> {
             .map(Into::into)
             .collect();
 
-        self.write_txs(&local_entries)
+        self.write_txs(&local_entries)?;
+
+        if self.pool_journal {
+            self.dump_pool()?;
+        }
+
+        Ok(())
+    }
+
+    /// Attempt to read the pool journal out of the local store.
+    ///
+    /// Transactions are returned in the order they were inserted into the pool.
+    pub fn pool_transactions(&self) -> Result<Vec<PooledTransaction>, Error> {
+        if let Some(val) = self
+            .db
+            .get(self.col, POOL_TRANSACTIONS_KEY)
+            .map_err(Error::Io)?
+        {
+            let pool_txs: Vec<_> = ::serde_json::from_slice::<Vec<PoolEntry>>(&val)
+                .map_err(Error::Json)?
+                .into_iter()
+                .filter_map(PoolEntry::into_pooled)
+                .collect();
+
+            Ok(pool_txs)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Write all transactions currently in the pool to the journal.
+    ///
+    /// Returns the number of journaled transactions.
+    pub fn dump_pool(&self) -> Result<usize, Error> {
+        trace!(target: "local_store", "Writing transaction pool journal.");
+
+        let pool_entries: Vec<PoolEntry> = self
+            .node
+            .pool_transactions()
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        self.write_entries(POOL_TRANSACTIONS_KEY, &pool_entries)?;
+        Ok(pool_entries.len())
+    }
+
+    /// Clear the transaction pool journal.
+    pub fn clear_pool(&self) -> Result<(), Error> {
+        trace!(target: "local_store", "Clearing transaction pool journal.");
+
+        self.write_entries::<PoolEntry>(POOL_TRANSACTIONS_KEY, &[])
     }
 
     /// Clear data in this column.
     pub fn clear(&self) -> Result<(), Error> {
         trace!(target: "local_store", "Clearing local store entries.");
 
-        self.write_txs(&[])
+        self.write_txs(&[])?;
+        self.clear_pool()
     }
 
     // helper for writing a vector of transaction entries to disk.
     fn write_txs(&self, txs: &[TransactionEntry]) -> Result<(), Error> {
+        self.write_entries(LOCAL_TRANSACTIONS_KEY, txs)
+    }
+
+    fn write_entries<E: ::serde::Serialize>(&self, key: &[u8], entries: &[E]) -> Result<(), Error> {
         let mut batch = self.db.transaction();
 
-        let local_json = ::serde_json::to_value(txs).map_err(Error::Json)?;
-        let json_str = format!("{}", local_json);
+        let json = ::serde_json::to_value(entries).map_err(Error::Json)?;
+        let json_str = format!("{}", json);
 
-        batch.put_vec(self.col, LOCAL_TRANSACTIONS_KEY, json_str.into_bytes());
+        batch.put_vec(self.col, key, json_str.into_bytes());
         self.db.write(batch).map_err(Error::Io)
     }
 }
@@ -216,13 +380,27 @@ impl<T: NodeInfo, M: Send + Sync + 'static> IoHandler<M> for LocalDataStore<T> {
         if let Err(e) = io.register_timer(UPDATE_TIMER, UPDATE_TIMEOUT) {
             warn!(target: "local_store", "Error registering local store update timer: {}", e);
         }
+
+        if self.pool_journal {
+            if let Err(e) = io.register_timer(JOURNAL_TIMER, JOURNAL_TIMEOUT) {
+                warn!(target: "local_store", "Error registering pool journal timer: {}", e);
+            }
+        }
     }
 
     fn timeout(&self, _io: &::io::IoContext<M>, timer: ::io::TimerToken) {
-        if let UPDATE_TIMER = timer {
-            if let Err(e) = self.update() {
-                debug!(target: "local_store", "Error updating local store: {}", e);
+        match timer {
+            UPDATE_TIMER => {
+                if let Err(e) = self.update() {
+                    debug!(target: "local_store", "Error updating local store: {}", e);
+                }
+            }
+            JOURNAL_TIMER => {
+                if let Err(e) = self.dump_pool() {
+                    debug!(target: "local_store", "Error writing pool journal: {}", e);
+                }
             }
+            _ => {}
         }
     }
 }
@@ -237,9 +415,11 @@ impl<T: NodeInfo> Drop for LocalDataStore<T> {
 
 #[cfg(test)]
 mod tests {
-    use super::NodeInfo;
+    use super::{NodeInfo, PooledTransaction, Priority, TransactionEntry};
 
     use ethkey::Brain;
+    use rustc_hex::ToHex;
+    use serde_json;
     use std::sync::Arc;
     use types::transaction::{Condition, PendingTransaction, Transaction, TypedTransaction};
 
@@ -305,6 +485,59 @@ mod tests {
         }
     }
 
+    struct Pool(Vec<PooledTransaction>);
+    impl NodeInfo for Pool {
+        fn pending_transactions(&self) -> Vec<PendingTransaction> {
+            Vec::new()
+        }
+
+        fn pool_transactions(&self) -> Vec<PooledTransaction> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn pool_journal() {
+        let keypair = Brain::new("abcd".into()).generate();
+        let transactions: Vec<_> = (0..4u64)
+            .map(|nonce| {
+                let mut tx = TypedTransaction::Legacy(Transaction::default());
+                tx.tx_mut().nonce = nonce.into();
+
+                let signed = tx.sign(keypair.secret(), None);
+                let priority = match nonce {
+                    0 => Priority::Local,
+                    1 => Priority::Retracted,
+                    _ => Priority::Regular,
+                };
+
+                PooledTransaction {
+                    transaction: PendingTransaction::new(signed, None),
+                    priority,
+                }
+            })
+            .collect();
+
+        let db = Arc::new(ethcore_db::InMemoryWithMetrics::create(0));
+        {
+            // journal disabled, will write nothing.
+            let _store = super::create(db.clone(), None, Pool(transactions.clone()));
+        }
+        {
+            // nothing journaled yet, will write the pool on drop.
+            let store =
+                super::create(db.clone(), None, Pool(transactions.clone())).with_pool_journal(true);
+            assert_eq!(store.pool_transactions().unwrap(), vec![]);
+        }
+        {
+            // journal written, dump overwrites it with an empty pool.
+            let store = super::create(db.clone(), None, Pool(vec![]));
+            assert_eq!(store.pool_transactions().unwrap(), transactions);
+            assert_eq!(store.dump_pool().unwrap(), 0);
+            assert_eq!(store.pool_transactions().unwrap(), vec![]);
+        }
+    }
+
     #[test]
     fn skips_bad_transactions() {
         let keypair = Brain::new("abcd".into()).generate();
@@ -341,4 +574,25 @@ mod tests {
             assert_eq!(loaded, transactions);
         }
     }
+
+    #[test]
+    fn reads_legacy_entries() {
+        let keypair = Brain::new("abcd".into()).generate();
+        let signed = TypedTransaction::Legacy(Transaction::default()).sign(keypair.secret(), None);
+        let pending = PendingTransaction::new(signed, None);
+        let rlp_bytes = pending.transaction.encode();
+
+        let entry = serde_json::to_value(TransactionEntry::from(pending.clone())).unwrap();
+        assert_eq!(
+            entry["rlp_bytes"],
+            serde_json::Value::String(format!("0x{}", rlp_bytes.to_hex()))
+        );
+
+        // older versions wrote the bytes as an array of numbers
+        let legacy = serde_json::json!({ "rlp_bytes": rlp_bytes, "condition": null });
+        for entry in vec![entry, legacy] {
+            let entry: TransactionEntry = serde_json::from_value(entry).unwrap();
+            assert_eq!(entry.into_pending(), Some(pending.clone()));
+        }
+    }
 }
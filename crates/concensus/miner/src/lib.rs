@@ -35,6 +35,7 @@ extern crate parking_lot;
 #[cfg(feature = "price-info")]
 extern crate price_info;
 extern crate rlp;
+extern crate serde_json;
 extern crate txpool;
 
 #[macro_use]
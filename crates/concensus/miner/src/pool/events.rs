@@ -0,0 +1,440 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Lifecycle events of pool transactions.
+//!
+//! Unlike `local_transactions`, which keeps the status of local transactions only,
+//! the event stream reports what happens to every transaction in the pool.
+
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+};
+
+use ethereum_types::{Address, H256, U256};
+use parking_lot::Mutex;
+use serde_json;
+use txpool::{self, VerifiedTransaction};
+
+use pool::VerifiedTransaction as Transaction;
+
+/// Reason for a transaction to be dropped from the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DropReason {
+    /// Pushed out because the pool is full.
+    Limit,
+    /// Occupied the pool for too long without being included.
+    Stale,
+    /// Marked as invalid during block production.
+    Invalid,
+    /// Removed on request.
+    Canceled,
+    /// Removed together with all other transactions when the pool was cleared.
+    Cleared,
+}
+
+/// What happened to a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "event")]
+pub enum EventKind {
+    /// Transaction entered the pool.
+    Added,
+    /// Transaction was replaced by another one with the same sender and nonce.
+    #[serde(rename_all = "camelCase")]
+    Replaced {
+        /// Hash of the replacement.
+        replaced_by: H256,
+    },
+    /// Transaction was removed from the pool without being mined.
+    Dropped {
+        /// Why the transaction was dropped.
+        reason: DropReason,
+    },
+    /// Some other transaction with the same nonce got mined.
+    Culled,
+    /// Transaction was included in a block.
+    Mined,
+}
+
+/// Lifecycle event of a single pool transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Event {
+    /// Transaction hash.
+    pub hash: H256,
+    /// Transaction sender.
+    pub from: Address,
+    /// Transaction nonce.
+    pub nonce: U256,
+    /// What happened.
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+impl Event {
+    fn new(tx: &Transaction, kind: EventKind) -> Self {
+        Event {
+            hash: *tx.hash(),
+            from: *tx.sender(),
+            nonce: tx.signed().tx().nonce,
+            kind,
+        }
+    }
+}
+
+type Listener = Box<dyn Fn(&[Event]) + Send + Sync>;
+
+/// Identifies a listener added to the `EventStream`.
+pub type ListenerId = usize;
+
+/// Collects lifecycle events of all transactions and passes them to listeners.
+///
+/// Events are buffered while the pool is modified and dispatched on `notify`.
+#[derive(Default)]
+pub struct EventStream {
+    listeners: Vec<(ListenerId, Listener)>,
+    next_listener_id: ListenerId,
+    pending: Vec<Event>,
+    in_chain: Option<Arc<dyn Fn(&H256) -> bool + Send + Sync>>,
+    stale_id: Option<usize>,
+}
+
+impl fmt::Debug for EventStream {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("EventStream")
+            .field("listeners", &self.listeners.len())
+            .field("pending", &self.pending)
+            .field("in_chain", &self.in_chain.is_some())
+            .field("stale_id", &self.stale_id)
+            .finish()
+    }
+}
+
+impl EventStream {
+    /// Add new listener to receive events.
+    pub fn add(&mut self, f: Listener) -> ListenerId {
+        let id = self.next_listener_id;
+        self.next_listener_id += 1;
+        self.listeners.push((id, f));
+        id
+    }
+
+    /// Remove a previously added listener.
+    pub fn remove(&mut self, id: ListenerId) {
+        self.listeners.retain(|&(listener_id, _)| listener_id != id);
+        if self.listeners.is_empty() {
+            self.pending.clear();
+        }
+    }
+
+    /// Set blockchain checker, used to tell mined transactions from culled ones.
+    pub fn set_in_chain_checker(&mut self, checker: Arc<dyn Fn(&H256) -> bool + Send + Sync>) {
+        self.in_chain = Some(checker);
+    }
+
+    /// Set the insertion id below which culled transactions are considered stale.
+    pub fn set_stale_id(&mut self, stale_id: Option<usize>) {
+        self.stale_id = stale_id;
+    }
+
+    /// Dispatch all buffered events to listeners.
+    pub fn notify(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        for (_, l) in &self.listeners {
+            (l)(&self.pending);
+        }
+
+        self.pending.clear();
+    }
+
+    fn push(&mut self, tx: &Transaction, kind: EventKind) {
+        // nobody is listening, don't buffer anything.
+        if self.listeners.is_empty() {
+            return;
+        }
+
+        self.pending.push(Event::new(tx, kind));
+    }
+}
+
+impl txpool::Listener<Transaction> for EventStream {
+    fn added(&mut self, tx: &Arc<Transaction>, old: Option<&Arc<Transaction>>) {
+        self.push(tx, EventKind::Added);
+
+        if let Some(old) = old {
+            self.push(
+                old,
+                EventKind::Replaced {
+                    replaced_by: *tx.hash(),
+                },
+            );
+        }
+    }
+
+    fn dropped(&mut self, tx: &Arc<Transaction>, by: Option<&Transaction>) {
+        // the pool reports dropped transactions without a replacement only when it's cleared.
+        let reason = match by {
+            Some(_) => DropReason::Limit,
+            None => DropReason::Cleared,
+        };
+        self.push(tx, EventKind::Dropped { reason });
+    }
+
+    fn invalid(&mut self, tx: &Arc<Transaction>) {
+        self.push(
+            tx,
+            EventKind::Dropped {
+                reason: DropReason::Invalid,
+            },
+        );
+    }
+
+    fn canceled(&mut self, tx: &Arc<Transaction>) {
+        self.push(
+            tx,
+            EventKind::Dropped {
+                reason: DropReason::Canceled,
+            },
+        );
+    }
+
+    fn culled(&mut self, tx: &Arc<Transaction>) {
+        // nobody is listening, skip the blockchain lookup.
+        if self.listeners.is_empty() {
+            return;
+        }
+
+        let is_in_chain = self
+            .in_chain
+            .as_ref()
+            .map(|checker| checker(tx.hash()))
+            .unwrap_or(false);
+        let is_stale = self
+            .stale_id
+            .map(|id| tx.insertion_id() < id)
+            .unwrap_or(false);
+
+        let kind = match (is_in_chain, is_stale) {
+            (true, _) => EventKind::Mined,
+            (false, true) => EventKind::Dropped {
+                reason: DropReason::Stale,
+            },
+            (false, false) => EventKind::Culled,
+        };
+        self.push(tx, kind);
+    }
+}
+
+/// Maximal number of event batches waiting to be written to the file.
+const MAX_QUEUED_BATCHES: usize = 1024;
+
+/// Writes transaction events to a file, one JSON object per line.
+///
+/// The file is written from a separate thread, so that disk IO never
+/// happens while the pool is locked. If the writer falls behind, new events
+/// are dropped and counted instead of piling up in memory.
+pub struct JsonLinesSink {
+    sender: Mutex<mpsc::SyncSender<Vec<Event>>>,
+    dropped: AtomicUsize,
+}
+
+impl JsonLinesSink {
+    /// Open (or create) the file at given path and start appending events to it.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let (sender, receiver) = mpsc::sync_channel(MAX_QUEUED_BATCHES);
+
+        thread::Builder::new()
+            .name("tx-events".into())
+            .spawn(move || Self::run(file, receiver))?;
+
+        Ok(JsonLinesSink {
+            sender: Mutex::new(sender),
+            dropped: AtomicUsize::new(0),
+        })
+    }
+
+    /// Queue events to be written. Never blocks; events are dropped if the queue is full.
+    pub fn write(&self, events: &[Event]) {
+        match self.sender.lock().try_send(events.to_vec()) {
+            Ok(()) => {
+                let dropped = self.dropped.swap(0, Ordering::Relaxed);
+                if dropped > 0 {
+                    warn!(target: "txqueue", "Dropped {} transaction events, the events log can't keep up.", dropped);
+                }
+            }
+            Err(mpsc::TrySendError::Full(events)) => {
+                self.dropped.fetch_add(events.len(), Ordering::Relaxed);
+            }
+            // the writer thread only stops when the sink is dropped.
+            Err(mpsc::TrySendError::Disconnected(_)) => {}
+        }
+    }
+
+    /// Number of events dropped since the queue was last accepting events.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn run(file: File, receiver: mpsc::Receiver<Vec<Event>>) {
+        let mut out = BufWriter::new(file);
+        for events in receiver {
+            let written = events
+                .iter()
+                .map(|event| {
+                    serde_json::to_writer(&mut out, event)?;
+                    out.write_all(b"\n")
+                })
+                .collect::<io::Result<()>>()
+                .and_then(|_| out.flush());
+
+            if let Err(e) = written {
+                warn!(target: "txqueue", "Unable to write transaction events: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_types::H160;
+    use txpool::Listener;
+    use types::transaction;
+
+    #[test]
+    fn should_tell_mined_from_culled_and_stale() {
+        // given
+        let received = Arc::new(Mutex::new(vec![]));
+        let r = received.clone();
+        let mut events = EventStream::default();
+        events.add(Box::new(move |events: &[Event]| {
+            r.lock().extend(events.iter().map(|e| e.kind));
+        }));
+        let mined = new_tx(5);
+        events.set_in_chain_checker(Arc::new(move |hash: &H256| *hash == *mined.hash()));
+        events.set_stale_id(Some(1));
+
+        // when
+        events.culled(&new_tx(5));
+        events.culled(&new_tx(6));
+        assert_eq!(*received.lock(), vec![]);
+        events.notify();
+
+        // then
+        assert_eq!(
+            *received.lock(),
+            vec![
+                EventKind::Mined,
+                EventKind::Dropped {
+                    reason: DropReason::Stale
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_tell_cleared_from_limit_drops() {
+        // given
+        let received = Arc::new(Mutex::new(vec![]));
+        let r = received.clone();
+        let mut events = EventStream::default();
+        events.add(Box::new(move |events: &[Event]| {
+            r.lock().extend(events.iter().map(|e| e.kind));
+        }));
+
+        // when
+        events.dropped(&new_tx(5), Some(&new_tx(6)));
+        events.dropped(&new_tx(7), None);
+        events.notify();
+
+        // then
+        assert_eq!(
+            *received.lock(),
+            vec![
+                EventKind::Dropped {
+                    reason: DropReason::Limit
+                },
+                EventKind::Dropped {
+                    reason: DropReason::Cleared
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_not_check_chain_without_listeners() {
+        // given
+        let checked = Arc::new(AtomicUsize::new(0));
+        let c = checked.clone();
+        let mut events = EventStream::default();
+        events.set_in_chain_checker(Arc::new(move |_: &H256| {
+            c.fetch_add(1, Ordering::SeqCst);
+            false
+        }));
+
+        // when
+        events.culled(&new_tx(5));
+        let id = events.add(Box::new(|_: &[Event]| {}));
+        events.culled(&new_tx(6));
+        events.remove(id);
+        events.culled(&new_tx(7));
+
+        // then
+        assert_eq!(checked.load(Ordering::SeqCst), 1);
+        assert!(events.pending.is_empty());
+    }
+
+    #[test]
+    fn should_serialize_event() {
+        let tx = new_tx(5);
+        let event = Event::new(
+            &tx,
+            EventKind::Replaced {
+                replaced_by: H256::zero(),
+            },
+        );
+
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"hash":"0xde96bdcdf864c95eb7f81eff1e3290be24a0f327732e0c4251c1896a565a80db","from":"0x0000000000000000000000000000000000000005","nonce":"0x5","event":"replaced","replacedBy":"0x0000000000000000000000000000000000000000000000000000000000000000"}"#
+        );
+    }
+
+    fn new_tx(nonce: u64) -> Arc<Transaction> {
+        let signed = transaction::TypedTransaction::Legacy(transaction::Transaction {
+            action: transaction::Action::Create,
+            data: vec![1, 2, 3],
+            nonce: nonce.into(),
+            gas: 21_000.into(),
+            gas_price: 5.into(),
+            value: 0.into(),
+        })
+        .fake_sign(H160::from_low_u64_be(5));
+
+        Arc::new(Transaction::from_pending_block_transaction(signed))
+    }
+}
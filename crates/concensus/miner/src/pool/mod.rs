@@ -26,9 +26,11 @@ mod queue;
 mod ready;
 
 pub mod client;
+pub mod events;
 pub mod local_transactions;
 pub mod replace;
 pub mod scoring;
+pub mod spam;
 pub mod transaction_filter;
 pub mod verifier;
 
@@ -47,6 +49,9 @@ pub use self::{
 pub enum PrioritizationStrategy {
     /// Simple gas-price based prioritization.
     GasPriceOnly,
+    /// Gas-price based prioritization in the pool. Blocks are built from the transactions
+    /// earning the most per gas actually used, found by executing them first.
+    ProfitMaximizing,
 }
 
 /// Transaction ordering when requesting pending set.
@@ -112,7 +117,8 @@ pub enum Priority {
 }
 
 impl Priority {
-    fn is_local(&self) -> bool {
+    /// Whether the transaction was submitted to this node.
+    pub fn is_local(&self) -> bool {
         match *self {
             Priority::Local => true,
             _ => false,
@@ -169,7 +175,7 @@ impl VerifiedTransaction {
     }
 
     /// Gets transaction insertion id.
-    pub(crate) fn insertion_id(&self) -> usize {
+    pub fn insertion_id(&self) -> usize {
         self.insertion_id
     }
 
@@ -29,23 +29,34 @@ use std::{
 use self::scoring::ScoringEvent;
 use ethereum_types::{Address, H256, U256};
 use parking_lot::RwLock;
-use txpool::{self, Verifier};
+use txpool::{self, Ready, Verifier};
 use types::transaction;
 
 use pool::{
-    self, client, listener,
+    self, client, events, listener,
     local_transactions::LocalTransactionsList,
-    ready, replace, scoring,
+    ready, replace, scoring, spam,
     transaction_filter::{match_filter, TransactionFilter},
-    verifier, PendingOrdering, PendingSettings, PrioritizationStrategy,
+    verifier, PendingOrdering, PendingSettings, PrioritizationStrategy, ScoredTransaction,
 };
 
 type Listener = (
     LocalTransactionsList,
-    (listener::Notifier, listener::Logger),
+    (
+        listener::Notifier,
+        (
+            listener::Logger,
+            (spam::OffenceListener, events::EventStream),
+        ),
+    ),
 );
 type Pool = txpool::Pool<pool::VerifiedTransaction, scoring::NonceAndGasPrice, Listener>;
 
+// the event stream is the innermost pool listener.
+fn event_stream(listener: &mut Listener) -> &mut events::EventStream {
+    &mut (((listener.1).1).1).1
+}
+
 /// Max cache time in milliseconds for pending transactions.
 ///
 /// Pending transactions are cached and will only be computed again
@@ -235,6 +246,7 @@ pub struct TransactionQueue {
     /// Cached pending transactions got *without* priority fee enforcement.
     cached_non_enforced_pending: RwLock<CachedPending>,
     recently_rejected: RecentlyRejected,
+    spam: Arc<spam::SpamProtection>,
 }
 
 impl TransactionQueue {
@@ -245,10 +257,20 @@ impl TransactionQueue {
         strategy: PrioritizationStrategy,
     ) -> Self {
         let max_count = limits.max_count;
+        let spam = Arc::new(spam::SpamProtection::default());
         TransactionQueue {
             insertion_id: Default::default(),
             pool: RwLock::new(txpool::Pool::new(
-                Default::default(),
+                (
+                    Default::default(),
+                    (
+                        Default::default(),
+                        (
+                            listener::Logger,
+                            (spam::OffenceListener(spam.clone()), Default::default()),
+                        ),
+                    ),
+                ),
                 scoring::NonceAndGasPrice {
                     strategy,
                     block_base_fee: verification_options.block_base_fee,
@@ -262,6 +284,7 @@ impl TransactionQueue {
                 MIN_REJECTED_CACHE_SIZE,
                 max_count / 4,
             )),
+            spam,
         }
     }
 
@@ -293,12 +316,23 @@ impl TransactionQueue {
         *self.options.write() = options;
     }
 
+    /// Update spam protection settings.
+    pub fn set_spam_protection(&self, options: spam::Options) {
+        self.spam.set_options(options);
+    }
+
     /// Sets the in-chain transaction checker for pool listener.
     pub fn set_in_chain_checker<F>(&self, f: F)
     where
         F: Fn(&H256) -> bool + Send + Sync + 'static,
     {
-        self.pool.write().listener_mut().0.set_in_chain_checker(f)
+        let f = Arc::new(f);
+        let checker = f.clone();
+        let mut pool = self.pool.write();
+        pool.listener_mut()
+            .0
+            .set_in_chain_checker(move |hash: &H256| checker(hash));
+        event_stream(pool.listener_mut()).set_in_chain_checker(f);
     }
 
     // t_nb 10.2
@@ -310,10 +344,34 @@ impl TransactionQueue {
         &self,
         client: C,
         transactions: Vec<verifier::Transaction>,
+    ) -> Vec<Result<(), transaction::Error>> {
+        self.import_with(client, transactions, true)
+    }
+
+    /// Import a set of transactions restored from disk.
+    ///
+    /// The transactions were already accepted once, so they are verified again
+    /// but not counted against the rate limits and bans of the spam protection.
+    pub fn import_restored<
+        C: client::Client + client::NonceClient + client::BalanceClient + Clone,
+    >(
+        &self,
+        client: C,
+        transactions: Vec<verifier::Transaction>,
+    ) -> Vec<Result<(), transaction::Error>> {
+        self.import_with(client, transactions, false)
+    }
+
+    fn import_with<C: client::Client + client::NonceClient + client::BalanceClient + Clone>(
+        &self,
+        client: C,
+        transactions: Vec<verifier::Transaction>,
+        check_spam: bool,
     ) -> Vec<Result<(), transaction::Error>> {
         // Run verification
         trace_time!("pool::verify_and_import");
         let options = self.options.read().clone();
+        let block_base_fee = options.block_base_fee;
 
         let transaction_to_replace = {
             if options.no_early_reject {
@@ -359,11 +417,27 @@ impl TransactionQueue {
                 let imported = verifier
                     .verify_transaction(transaction)
                     .and_then(|verified| {
+                        if check_spam {
+                            let replaced = if self.spam.checks_replacements() {
+                                self.find_by_nonce(&verified.sender, verified.nonce())
+                            } else {
+                                None
+                            };
+                            self.spam.check(
+                                &verified,
+                                replaced.as_ref().map(|tx| &**tx),
+                                block_base_fee,
+                            )?;
+                        }
                         self.pool.write().import(verified, &mut replace).map_err(convert_error)
                     });
 
                 match imported {
                     Ok(_) => Ok(()),
+                    // rate limits and bans are temporary, don't remember the rejection
+                    Err(err @ transaction::Error::SenderRateLimited)
+                    | Err(err @ transaction::Error::RecipientRateLimited)
+                    | Err(err @ transaction::Error::SenderBanned) => Err(err),
                     Err(err) => {
                         self.recently_rejected.insert(hash, &err);
                         Err(err)
@@ -373,7 +447,11 @@ impl TransactionQueue {
             .collect::<Vec<_>>();
 
         // Notify about imported transactions.
-        (self.pool.write().listener_mut().1).0.notify();
+        {
+            let mut pool = self.pool.write();
+            (pool.listener_mut().1).0.notify();
+            event_stream(pool.listener_mut()).notify();
+        }
 
         if results.iter().any(|r| r.is_ok()) {
             self.cached_enforced_pending.write().clear();
@@ -392,6 +470,34 @@ impl TransactionQueue {
             .collect()
     }
 
+    /// Returns all transactions in the queue split into ready ones, which could be included
+    /// in the next block given the current nonces, and future ones blocked by a nonce gap.
+    ///
+    /// Stale transactions are omitted. Both lists are grouped by sender in nonce order.
+    pub fn ready_and_future<C>(
+        &self,
+        client: C,
+    ) -> (
+        Vec<Arc<pool::VerifiedTransaction>>,
+        Vec<Arc<pool::VerifiedTransaction>>,
+    )
+    where
+        C: client::NonceClient,
+    {
+        let all = |_tx: &pool::VerifiedTransaction| txpool::Readiness::Ready;
+        let mut state = ready::State::new(client, None, None);
+        let mut ready = Vec::new();
+        let mut future = Vec::new();
+        for tx in self.pool.read().unordered_pending(all, Default::default()) {
+            match state.is_ready(&tx) {
+                txpool::Readiness::Ready => ready.push(tx),
+                txpool::Readiness::Future => future.push(tx),
+                txpool::Readiness::Stale => {}
+            }
+        }
+        (ready, future)
+    }
+
     /// Returns all transaction hashes in the queue without explicit ordering.
     pub fn all_transaction_hashes(&self) -> Vec<H256> {
         let ready = |_tx: &pool::VerifiedTransaction| txpool::Readiness::Ready;
@@ -621,6 +727,8 @@ impl TransactionQueue {
         };
 
         self.recently_rejected.clear();
+        self.spam.prune();
+        event_stream(self.pool.write().listener_mut()).set_stale_id(stale_id);
 
         let mut removed = 0;
         let senders: Vec<_> = {
@@ -633,6 +741,7 @@ impl TransactionQueue {
             let state_readiness = ready::State::new(client.clone(), stale_id, nonce_cap);
             removed += self.pool.write().cull(Some(chunk), state_readiness);
         }
+        event_stream(self.pool.write().listener_mut()).notify();
         debug!(target: "txqueue", "Removed {} stalled transactions. {}", removed, self.status());
     }
 
@@ -653,6 +762,19 @@ impl TransactionQueue {
             .map(|tx| tx.signed().tx().nonce.saturating_add(U256::from(1)))
     }
 
+    /// Finds a transaction from `sender` with given nonce.
+    fn find_by_nonce(
+        &self,
+        sender: &Address,
+        nonce: U256,
+    ) -> Option<Arc<pool::VerifiedTransaction>> {
+        let all = |_tx: &pool::VerifiedTransaction| txpool::Readiness::Ready;
+        self.pool
+            .read()
+            .pending_from_sender(all, sender, Default::default())
+            .find(|tx| tx.nonce() == nonce)
+    }
+
     /// Retrieve a transaction from the pool.
     ///
     /// Given transaction hash looks up that transaction in the pool
@@ -675,10 +797,12 @@ impl TransactionQueue {
         let results = {
             let mut pool = self.pool.write();
 
-            hashes
+            let results = hashes
                 .into_iter()
                 .map(|hash| pool.remove(hash, is_invalid))
-                .collect::<Vec<_>>()
+                .collect::<Vec<_>>();
+            event_stream(pool.listener_mut()).notify();
+            results
         };
 
         if results.iter().any(Option::is_some) {
@@ -691,7 +815,9 @@ impl TransactionQueue {
 
     /// Clear the entire pool.
     pub fn clear(&self) {
-        self.pool.write().clear();
+        let mut pool = self.pool.write();
+        pool.clear();
+        event_stream(pool.listener_mut()).notify();
     }
 
     /// Penalize given senders.
@@ -772,6 +898,21 @@ impl TransactionQueue {
         (pool.listener_mut().1).0.add(f);
     }
 
+    /// Add a callback to be notified about lifecycle events of all pool transactions.
+    pub fn add_event_listener(
+        &self,
+        f: Box<dyn Fn(&[events::Event]) + Send + Sync>,
+    ) -> events::ListenerId {
+        let mut pool = self.pool.write();
+        event_stream(pool.listener_mut()).add(f)
+    }
+
+    /// Remove a callback added with `add_event_listener`.
+    pub fn remove_event_listener(&self, id: events::ListenerId) {
+        let mut pool = self.pool.write();
+        event_stream(pool.listener_mut()).remove(id);
+    }
+
     /// Check if pending set is cached.
     #[cfg(test)]
     pub fn is_enforced_pending_cached(&self) -> bool {
@@ -0,0 +1,414 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Spam protection for the transaction pool.
+//!
+//! Rate limits transactions per sender and per called contract, requires a minimal gas price
+//! bump to replace a transaction and temporarily bans senders whose transactions keep getting
+//! evicted from the pool or fail when building blocks.
+//!
+//! Local and retracted transactions are exempt from all the checks.
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use ethereum_types::{Address, U256};
+use parking_lot::{Mutex, RwLock};
+use txpool::{self, VerifiedTransaction as PoolVerifiedTransaction};
+use types::transaction::{self, Action};
+
+use pool::{Priority, ScoredTransaction, VerifiedTransaction};
+
+/// Token bucket rate limit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Transactions accepted per minute on average.
+    pub per_minute: u32,
+    /// Transactions accepted at once after a quiet period.
+    pub burst: u32,
+}
+
+/// Spam protection settings. Everything is disabled by default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// Limit of transactions from a single sender.
+    pub sender_rate_limit: Option<RateLimit>,
+    /// Limit of transactions calling a single contract (or sending to a single account).
+    pub recipient_rate_limit: Option<RateLimit>,
+    /// Percentage by which the gas price of a transaction replacing another one with the same
+    /// sender and nonce has to be higher. Applied on top of the pool's own replacement rule.
+    pub replacement_bump_percent: u32,
+    /// Number of evictions and block building failures after which a sender gets banned.
+    /// Offences older than `ban_duration` are forgotten. Zero disables banning.
+    pub ban_threshold: u32,
+    /// How long the ban lasts.
+    pub ban_duration: Duration,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            sender_rate_limit: None,
+            recipient_rate_limit: None,
+            replacement_bump_percent: 0,
+            ban_threshold: 0,
+            ban_duration: Duration::from_secs(180),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(limit: &RateLimit, now: Instant) -> Self {
+        Bucket {
+            tokens: limit.burst as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * limit.per_minute as f64 / 60.0).min(limit.burst as f64);
+        self.updated = now;
+    }
+}
+
+#[derive(Debug)]
+struct Offences {
+    count: u32,
+    last: Instant,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    senders: HashMap<Address, Bucket>,
+    recipients: HashMap<Address, Bucket>,
+    offences: HashMap<Address, Offences>,
+    banned: HashMap<Address, Instant>,
+}
+
+/// Rate limits and bans shared by the queue and its listener.
+#[derive(Debug, Default)]
+pub struct SpamProtection {
+    options: RwLock<Options>,
+    state: Mutex<State>,
+}
+
+impl SpamProtection {
+    /// Replaces the settings. Existing bans are kept.
+    pub fn set_options(&self, options: Options) {
+        *self.options.write() = options;
+    }
+
+    /// Whether replacements need the extra gas price bump.
+    pub fn checks_replacements(&self) -> bool {
+        self.options.read().replacement_bump_percent > 0
+    }
+
+    /// Checks a verified transaction about to be imported, consuming rate limit tokens
+    /// if it's accepted. `replaced` is the pooled transaction with the same sender and nonce.
+    pub fn check(
+        &self,
+        tx: &VerifiedTransaction,
+        replaced: Option<&VerifiedTransaction>,
+        block_base_fee: Option<U256>,
+    ) -> Result<(), transaction::Error> {
+        self.check_at(tx, replaced, block_base_fee, Instant::now())
+    }
+
+    fn check_at(
+        &self,
+        tx: &VerifiedTransaction,
+        replaced: Option<&VerifiedTransaction>,
+        block_base_fee: Option<U256>,
+        now: Instant,
+    ) -> Result<(), transaction::Error> {
+        if tx.priority() != Priority::Regular {
+            return Ok(());
+        }
+
+        let options = self.options.read();
+        let mut state = self.state.lock();
+        let sender = *tx.sender();
+
+        match state.banned.get(&sender) {
+            Some(until) if *until > now => {
+                trace!(target: "txqueue", "[{:?}] Rejected tx from banned sender {}", tx.hash(), sender);
+                return Err(transaction::Error::SenderBanned);
+            }
+            Some(_) => {
+                state.banned.remove(&sender);
+            }
+            None => {}
+        }
+
+        if let Some(old) = replaced {
+            let prev = old.effective_gas_price(block_base_fee);
+            let new = tx.effective_gas_price(block_base_fee);
+            let minimal = prev.saturating_add(prev * options.replacement_bump_percent / 100);
+            if new < minimal {
+                trace!(target: "txqueue", "[{:?}] Rejected replacement below required bump: {} < {}", tx.hash(), new, minimal);
+                return Err(transaction::Error::TooCheapToReplace {
+                    prev: Some(prev),
+                    new: Some(new),
+                });
+            }
+        }
+
+        let State {
+            ref mut senders,
+            ref mut recipients,
+            ..
+        } = *state;
+        let sender_bucket = match options.sender_rate_limit {
+            Some(limit) => {
+                let bucket = senders
+                    .entry(sender)
+                    .or_insert_with(|| Bucket::full(&limit, now));
+                bucket.refill(&limit, now);
+                Some(bucket)
+            }
+            None => None,
+        };
+        if sender_bucket.as_ref().map_or(false, |b| b.tokens < 1.0) {
+            debug!(target: "txqueue", "[{:?}] Rejected tx, sender {} exceeded its rate limit", tx.hash(), sender);
+            return Err(transaction::Error::SenderRateLimited);
+        }
+
+        let recipient = match tx.signed().tx().action {
+            Action::Call(ref to) => Some(*to),
+            Action::Create => None,
+        };
+        let recipient_bucket = match (options.recipient_rate_limit, recipient) {
+            (Some(limit), Some(recipient)) => {
+                let bucket = recipients
+                    .entry(recipient)
+                    .or_insert_with(|| Bucket::full(&limit, now));
+                bucket.refill(&limit, now);
+                Some(bucket)
+            }
+            _ => None,
+        };
+        if recipient_bucket.as_ref().map_or(false, |b| b.tokens < 1.0) {
+            debug!(target: "txqueue", "[{:?}] Rejected tx, recipient {:?} exceeded its rate limit", tx.hash(), recipient);
+            return Err(transaction::Error::RecipientRateLimited);
+        }
+
+        for bucket in sender_bucket.into_iter().chain(recipient_bucket) {
+            bucket.tokens -= 1.0;
+        }
+        Ok(())
+    }
+
+    /// Records that a transaction from `sender` was evicted or failed in a block,
+    /// banning the sender once it happens too often.
+    pub fn note_offence(&self, sender: Address) {
+        self.note_offence_at(sender, Instant::now())
+    }
+
+    fn note_offence_at(&self, sender: Address, now: Instant) {
+        let options = self.options.read();
+        if options.ban_threshold == 0 {
+            return;
+        }
+
+        let mut state = self.state.lock();
+        let banned = {
+            let offences = state.offences.entry(sender).or_insert(Offences {
+                count: 0,
+                last: now,
+            });
+            if now.saturating_duration_since(offences.last) > options.ban_duration {
+                offences.count = 0;
+            }
+            offences.count += 1;
+            offences.last = now;
+            offences.count >= options.ban_threshold
+        };
+
+        if banned {
+            debug!(target: "txqueue", "Banning sender {} for {:?}", sender, options.ban_duration);
+            state.offences.remove(&sender);
+            state.banned.insert(sender, now + options.ban_duration);
+        }
+    }
+
+    /// Forgets expired bans and offences and rate limit buckets which are full again.
+    pub fn prune(&self) {
+        self.prune_at(Instant::now())
+    }
+
+    fn prune_at(&self, now: Instant) {
+        let options = self.options.read();
+        let mut state = self.state.lock();
+        let is_full = |limit: Option<RateLimit>, bucket: &mut Bucket| match limit {
+            Some(limit) => {
+                bucket.refill(&limit, now);
+                bucket.tokens >= limit.burst as f64
+            }
+            None => true,
+        };
+        state
+            .senders
+            .retain(|_, bucket| !is_full(options.sender_rate_limit, bucket));
+        state
+            .recipients
+            .retain(|_, bucket| !is_full(options.recipient_rate_limit, bucket));
+        state.offences.retain(|_, offences| {
+            now.saturating_duration_since(offences.last) <= options.ban_duration
+        });
+        state.banned.retain(|_, until| *until > now);
+    }
+}
+
+/// Pool listener reporting evicted and invalid transactions as offences of their senders.
+#[derive(Debug)]
+pub struct OffenceListener(pub Arc<SpamProtection>);
+
+impl txpool::Listener<VerifiedTransaction> for OffenceListener {
+    fn dropped(&mut self, tx: &Arc<VerifiedTransaction>, by: Option<&VerifiedTransaction>) {
+        // `None` means the pool was cleared
+        if by.is_some() && tx.priority() == Priority::Regular {
+            self.0.note_offence(*tx.sender());
+        }
+    }
+
+    fn invalid(&mut self, tx: &Arc<VerifiedTransaction>) {
+        if tx.priority() == Priority::Regular {
+            self.0.note_offence(*tx.sender());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_types::H160;
+    use types::transaction::{Transaction, TypedTransaction};
+
+    fn tx(sender: u64, to: u64, nonce: u64, gas_price: u64) -> VerifiedTransaction {
+        let signed = TypedTransaction::Legacy(Transaction {
+            action: Action::Call(H160::from_low_u64_be(to)),
+            data: vec![],
+            nonce: nonce.into(),
+            gas: 21_000.into(),
+            gas_price: gas_price.into(),
+            value: 0.into(),
+        })
+        .fake_sign(H160::from_low_u64_be(sender));
+        let mut tx = VerifiedTransaction::from_pending_block_transaction(signed);
+        tx.priority = Priority::Regular;
+        tx
+    }
+
+    fn protection(options: Options) -> SpamProtection {
+        let protection = SpamProtection::default();
+        protection.set_options(options);
+        protection
+    }
+
+    #[test]
+    fn should_rate_limit_senders_and_recipients() {
+        let spam = protection(Options {
+            sender_rate_limit: Some(RateLimit {
+                per_minute: 60,
+                burst: 2,
+            }),
+            recipient_rate_limit: Some(RateLimit {
+                per_minute: 60,
+                burst: 3,
+            }),
+            ..Default::default()
+        });
+        let now = Instant::now();
+
+        assert_eq!(spam.check_at(&tx(1, 10, 0, 1), None, None, now), Ok(()));
+        assert_eq!(spam.check_at(&tx(1, 10, 1, 1), None, None, now), Ok(()));
+        assert_eq!(
+            spam.check_at(&tx(1, 10, 2, 1), None, None, now),
+            Err(transaction::Error::SenderRateLimited)
+        );
+        assert_eq!(spam.check_at(&tx(2, 10, 0, 1), None, None, now), Ok(()));
+        assert_eq!(
+            spam.check_at(&tx(3, 10, 0, 1), None, None, now),
+            Err(transaction::Error::RecipientRateLimited)
+        );
+        // the rejected transaction didn't use up the sender's token
+        assert_eq!(spam.check_at(&tx(3, 11, 0, 1), None, None, now), Ok(()));
+
+        // one token per second is refilled
+        let later = now + Duration::from_secs(1);
+        assert_eq!(spam.check_at(&tx(1, 11, 2, 1), None, None, later), Ok(()));
+        assert_eq!(
+            spam.check_at(&tx(1, 11, 3, 1), None, None, later),
+            Err(transaction::Error::SenderRateLimited)
+        );
+    }
+
+    #[test]
+    fn should_require_replacement_bump() {
+        let spam = protection(Options {
+            replacement_bump_percent: 20,
+            ..Default::default()
+        });
+        let old = tx(1, 10, 0, 100);
+
+        assert_eq!(
+            spam.check(&tx(1, 10, 0, 119), Some(&old), None),
+            Err(transaction::Error::TooCheapToReplace {
+                prev: Some(100.into()),
+                new: Some(119.into()),
+            })
+        );
+        assert_eq!(spam.check(&tx(1, 10, 0, 120), Some(&old), None), Ok(()));
+    }
+
+    #[test]
+    fn should_ban_repeated_offenders() {
+        let spam = protection(Options {
+            ban_threshold: 2,
+            ban_duration: Duration::from_secs(60),
+            ..Default::default()
+        });
+        let sender = H160::from_low_u64_be(1);
+        let now = Instant::now();
+
+        spam.note_offence_at(sender, now);
+        assert_eq!(spam.check_at(&tx(1, 10, 0, 1), None, None, now), Ok(()));
+        // offences older than the ban duration are forgotten
+        spam.note_offence_at(sender, now + Duration::from_secs(61));
+        assert_eq!(spam.check_at(&tx(1, 10, 0, 1), None, None, now), Ok(()));
+
+        spam.note_offence_at(sender, now + Duration::from_secs(62));
+        assert_eq!(
+            spam.check_at(&tx(1, 10, 0, 1), None, None, now + Duration::from_secs(62)),
+            Err(transaction::Error::SenderBanned)
+        );
+        assert_eq!(
+            spam.check_at(&tx(1, 10, 0, 1), None, None, now + Duration::from_secs(123)),
+            Ok(())
+        );
+    }
+}
@@ -14,14 +14,18 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethereum_types::U256;
+use ethereum_types::{H256, U256};
 use hash::KECCAK_EMPTY;
+use parking_lot::Mutex;
+use std::sync::Arc;
 use txpool;
 use types::transaction::{self, PendingTransaction};
 
 use pool::{
-    transaction_filter::TransactionFilter, verifier, PendingOrdering, PendingSettings,
-    PrioritizationStrategy, TransactionQueue,
+    events::{DropReason, Event, EventKind},
+    spam,
+    transaction_filter::TransactionFilter,
+    verifier, PendingOrdering, PendingSettings, PrioritizationStrategy, TransactionQueue,
 };
 
 pub mod client;
@@ -118,6 +122,97 @@ fn should_return_correct_nonces_when_dropped_because_of_limit() {
     assert_eq!(txq.next_nonce(TestClient::new(), &sender), None);
 }
 
+#[test]
+fn should_rate_limit_senders() {
+    // given
+    let txq = new_queue();
+    txq.set_spam_protection(spam::Options {
+        sender_rate_limit: Some(spam::RateLimit {
+            per_minute: 1,
+            burst: 1,
+        }),
+        ..Default::default()
+    });
+    let (tx1, tx2) = Tx::default().signed_pair();
+
+    // when
+    let res = txq.import(TestClient::new(), vec![tx1, tx2.clone()].unverified());
+
+    // then
+    assert_eq!(
+        res,
+        vec![Ok(()), Err(transaction::Error::SenderRateLimited)]
+    );
+
+    // the rejection is not remembered once the limit is lifted
+    txq.set_spam_protection(Default::default());
+    let res = txq.import(TestClient::new(), vec![tx2.unverified()]);
+    assert_eq!(res, vec![Ok(())]);
+    assert_eq!(txq.status().status.transaction_count, 2);
+}
+
+#[test]
+fn should_not_rate_limit_restored_transactions() {
+    // given
+    let txq = new_queue();
+    txq.set_spam_protection(spam::Options {
+        sender_rate_limit: Some(spam::RateLimit {
+            per_minute: 1,
+            burst: 1,
+        }),
+        ..Default::default()
+    });
+    let (tx1, tx2) = Tx::default().signed_pair();
+
+    // when
+    let res = txq.import_restored(TestClient::new(), vec![tx1, tx2].unverified());
+
+    // then
+    assert_eq!(res, vec![Ok(()), Ok(())]);
+    assert_eq!(txq.status().status.transaction_count, 2);
+}
+
+#[test]
+fn should_report_transaction_lifecycle_events() {
+    // given
+    let txq = new_queue();
+    let received = Arc::new(Mutex::new(vec![]));
+    let r = received.clone();
+    txq.add_event_listener(Box::new(move |events: &[Event]| {
+        r.lock().extend(events.iter().map(|e| (e.hash, e.kind)));
+    }));
+    let (tx1, tx2) = Tx::default().signed_replacement();
+    let tx3 = Tx::default().signed();
+    let (hash1, hash2, hash3) = (tx1.hash(), tx2.hash(), tx3.hash());
+    txq.set_in_chain_checker(move |hash: &H256| *hash == hash2);
+
+    // when
+    let res = txq.import(TestClient::new(), vec![tx1, tx3].unverified());
+    assert_eq!(res, vec![Ok(()), Ok(())]);
+    let res = txq.import(TestClient::new(), vec![tx2.unverified()]);
+    assert_eq!(res, vec![Ok(())]);
+    txq.remove(vec![&hash3], false);
+    txq.cull(TestClient::new().with_nonce(124));
+
+    // then
+    assert_eq!(
+        *received.lock(),
+        vec![
+            (hash1, EventKind::Added),
+            (hash3, EventKind::Added),
+            (hash2, EventKind::Added),
+            (hash1, EventKind::Replaced { replaced_by: hash2 }),
+            (
+                hash3,
+                EventKind::Dropped {
+                    reason: DropReason::Canceled
+                }
+            ),
+            (hash2, EventKind::Mined),
+        ]
+    );
+}
+
 #[test]
 fn should_never_drop_local_transactions_from_different_senders() {
     // given
@@ -216,6 +311,34 @@ fn should_move_all_transactions_from_future() {
     assert_eq!(top[1].hash, hash2);
 }
 
+#[test]
+fn should_split_transactions_into_ready_and_future() {
+    // given
+    let txq = new_queue();
+    let (tx1, tx2) = Tx::default().signed_pair();
+    let (hash1, hash2) = (tx1.hash(), tx2.hash());
+    let res = txq.import(TestClient::new(), vec![tx1, tx2].local());
+    assert_eq!(res, vec![Ok(()), Ok(())]);
+    let split = |client: TestClient| {
+        let (ready, future) = txq.ready_and_future(client);
+        (
+            ready.iter().map(|tx| tx.hash).collect::<Vec<_>>(),
+            future.iter().map(|tx| tx.hash).collect::<Vec<_>>(),
+        )
+    };
+
+    // then
+    assert_eq!(split(TestClient::new()), (vec![hash1, hash2], vec![]));
+    assert_eq!(
+        split(TestClient::new().with_nonce(122)),
+        (vec![], vec![hash1, hash2])
+    );
+    assert_eq!(
+        split(TestClient::new().with_nonce(124)),
+        (vec![hash2], vec![])
+    );
+}
+
 #[test]
 fn should_drop_transactions_from_senders_without_balance() {
     // given
@@ -237,6 +360,23 @@ fn should_drop_transactions_from_senders_without_balance() {
     assert_eq!(txq.status().status.transaction_count, 0);
 }
 
+#[test]
+fn should_reject_blob_transactions() {
+    // given
+    let txq = new_queue();
+    let tx = Tx::default().blob_one();
+
+    // when
+    let res = txq.import(TestClient::new(), vec![tx.unverified()]);
+
+    // then
+    assert_eq!(
+        res,
+        vec![Err(transaction::Error::TransactionTypeNotEnabled)]
+    );
+    assert_eq!(txq.status().status.transaction_count, 0);
+}
+
 #[test]
 fn should_not_import_transaction_below_min_gas_price_threshold_if_external() {
     // given
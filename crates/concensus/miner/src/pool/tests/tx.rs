@@ -18,8 +18,8 @@ use crypto::publickey::{Generator, Random};
 use ethereum_types::{H256, U256};
 use rustc_hex::FromHex;
 use types::transaction::{
-    self, AccessListTx, EIP1559TransactionTx, SignedTransaction, Transaction, TypedTransaction,
-    UnverifiedTransaction,
+    self, AccessListTx, BlobTransactionTx, EIP1559TransactionTx, SignedTransaction, Transaction,
+    TypedTransaction, UnverifiedTransaction,
 };
 
 use pool::{verifier, VerifiedTransaction};
@@ -130,6 +130,29 @@ impl Tx {
         });
         tx.sign(keypair.secret(), None)
     }
+
+    pub fn blob_one(self) -> SignedTransaction {
+        let keypair = Random.generate();
+        let tx = TypedTransaction::BlobTransaction(BlobTransactionTx {
+            transaction: EIP1559TransactionTx {
+                transaction: AccessListTx {
+                    transaction: Transaction {
+                        action: transaction::Action::Call(Default::default()),
+                        value: self.value.into(),
+                        data: vec![],
+                        gas: self.gas.into(),
+                        gas_price: self.gas_price.into(),
+                        nonce: self.nonce.into(),
+                    },
+                    access_list: vec![],
+                },
+                max_priority_fee_per_gas: self.gas_price.into(),
+            },
+            max_fee_per_blob_gas: 1.into(),
+            blob_versioned_hashes: vec![H256::from_low_u64_be(1)],
+        });
+        tx.sign(keypair.secret(), None)
+    }
 }
 pub trait TxExt: Sized {
     type Out;
@@ -225,6 +225,13 @@ impl<C: Client> txpool::Verifier<Transaction>
             bail!(transaction::Error::AlreadyImported)
         }
 
+        // Blob sidecars are not stored, so blob transactions couldn't be propagated or
+        // included in a block from the pool.
+        if tx.transaction().tx_type() == transaction::TypedTxId::BlobTransaction {
+            debug!(target: "txqueue", "[{:?}] Rejected blob transaction, sidecars are not supported", hash);
+            bail!(transaction::Error::TransactionTypeNotEnabled)
+        }
+
         let gas_limit = cmp::min(self.options.tx_gas_limit, self.options.block_gas_limit);
         if tx.gas() > &gas_limit {
             debug!(
@@ -32,7 +32,7 @@ use common_types::{
         epoch::{PendingTransition as PendingEpochTransition, Transition as EpochTransition},
         ForkChoice,
     },
-    header::{ExtendedHeader, Header},
+    header::{ExtendedHeader, Header, HeaderTransitions},
     log_entry::{LocalizedLogEntry, LogEntry},
     receipt::TypedReceipt,
     transaction::LocalizedTransaction,
@@ -153,9 +153,8 @@ pub trait BlockProvider {
 
     /// Get a list of uncles for a given block.
     /// Returns None if block does not exist.
-    fn uncles(&self, hash: &H256, eip1559_transition: BlockNumber) -> Option<Vec<Header>> {
-        self.block_body(hash)
-            .map(|body| body.uncles(eip1559_transition))
+    fn uncles(&self, hash: &H256, transitions: HeaderTransitions) -> Option<Vec<Header>> {
+        self.block_body(hash).map(|body| body.uncles(transitions))
     }
 
     /// Get a list of uncle hashes for a given block.
@@ -274,8 +273,8 @@ pub struct BlockChain {
     pending_block_details: RwLock<HashMap<H256, BlockDetails>>,
     pending_transaction_addresses: RwLock<HashMap<H256, Option<TransactionAddress>>>,
 
-    /// Number of first block where EIP-1559 rules begin. New encoding/decoding block format.
-    pub eip1559_transition: BlockNumber,
+    /// Blocks from which the optional header fields are present. Needed to decode headers.
+    pub header_transitions: HeaderTransitions,
 }
 
 impl BlockProvider for BlockChain {
@@ -563,7 +562,7 @@ impl<'a> Iterator for AncestryWithMetadataIter<'a> {
         } else {
             let details = self.chain.block_details(&self.current);
             let header = self.chain.block_header_data(&self.current).map(|h| {
-                h.decode(self.chain.eip1559_transition)
+                h.decode(self.chain.header_transitions)
                     .expect("Stored block header data is valid RLP; qed")
             });
 
@@ -638,7 +637,7 @@ impl BlockChain {
         config: Config,
         genesis: &[u8],
         db: Arc<dyn BlockChainDB>,
-        eip1559_transition: BlockNumber,
+        header_transitions: HeaderTransitions,
     ) -> BlockChain {
         // 400 is the average size of the key
         let cache_man = CacheManager::new(config.pref_cache_size, config.max_cache_size, 400);
@@ -665,7 +664,7 @@ impl BlockChain {
             pending_block_hashes: RwLock::new(HashMap::new()),
             pending_block_details: RwLock::new(HashMap::new()),
             pending_transaction_addresses: RwLock::new(HashMap::new()),
-            eip1559_transition,
+            header_transitions,
         };
 
         // load best block
@@ -725,7 +724,7 @@ impl BlockChain {
             let mut best_block = bc.best_block.write();
             *best_block = BestBlock {
                 total_difficulty: best_block_total_difficulty,
-                header: best_block_rlp.decode_header(eip1559_transition),
+                header: best_block_rlp.decode_header(header_transitions),
                 block: best_block_rlp,
             };
         }
@@ -1028,6 +1027,114 @@ impl BlockChain {
         }
     }
 
+    /// Inserts a verified, known-to-be-canonical ancient header without its body or receipts.
+    /// The parent of the header must already be in the chain.
+    ///
+    /// Only the header, its details and the canonical hash index are written, so the block
+    /// can be looked up by number but its body stays unavailable until `insert_ancient_body`
+    /// is called for it.
+    pub fn insert_unordered_header(&self, batch: &mut DBTransaction, header: encoded::Header) {
+        let number = header.number();
+        let parent_hash = header.parent_hash();
+        let hash = header.hash();
+
+        if self.is_known(&hash) {
+            return;
+        }
+
+        assert!(self.pending_best_block.read().is_none());
+
+        let parent_details = self
+            .uncommitted_block_details(&parent_hash)
+            .expect("ancient headers are only imported on top of a known parent; qed");
+        let info = BlockInfo {
+            hash,
+            number,
+            total_difficulty: parent_details.total_difficulty + header.difficulty(),
+            location: BlockLocation::CanonChain,
+        };
+
+        let compressed_header = compress(header.rlp().as_raw(), blocks_swapper());
+        batch.put(db::COL_HEADERS, hash.as_bytes(), &compressed_header);
+
+        {
+            let mut write_hashes = self.pending_block_hashes.write();
+            let mut write_details = self.pending_block_details.write();
+
+            batch.extend_with_cache(
+                db::COL_EXTRA,
+                &mut *write_details,
+                self.prepare_block_details_update(parent_hash, &info, false),
+                CacheUpdatePolicy::Overwrite,
+            );
+            batch.extend_with_cache(
+                db::COL_EXTRA,
+                &mut *write_hashes,
+                self.prepare_block_hashes_update(&info),
+                CacheUpdatePolicy::Overwrite,
+            );
+        }
+
+        self.set_best_ancient_block(number, &hash, batch);
+    }
+
+    /// Inserts the body and receipts of a canonical block whose header was previously
+    /// inserted with `insert_unordered_header`. Returns false if the header is unknown
+    /// or the body is already present.
+    pub fn insert_ancient_body(
+        &self,
+        batch: &mut DBTransaction,
+        block: encoded::Block,
+        receipts: Vec<TypedReceipt>,
+    ) -> bool {
+        let hash = block.header_view().hash();
+        let details = match self.block_details(&hash) {
+            Some(details) => details,
+            None => return false,
+        };
+        if self.block_body(&hash).is_some() {
+            return false;
+        }
+
+        let info = BlockInfo {
+            hash,
+            number: details.number,
+            total_difficulty: details.total_difficulty,
+            location: BlockLocation::CanonChain,
+        };
+
+        let compressed_body = compress(&Self::block_to_body(block.raw()), blocks_swapper());
+        batch.put(db::COL_BODIES, hash.as_bytes(), &compressed_body);
+
+        {
+            let mut write_receipts = self.block_receipts.write();
+            batch.extend_with_cache(
+                db::COL_EXTRA,
+                &mut *write_receipts,
+                self.prepare_block_receipts_update(receipts, &info),
+                CacheUpdatePolicy::Remove,
+            );
+        }
+
+        if let Some((number, blooms)) =
+            self.prepare_block_blooms_update(block.header_view().log_bloom(), &info)
+        {
+            self.db
+                .blooms()
+                .insert_blooms(number, blooms.iter())
+                .expect("Low level database error when updating blooms. Some issue with disk?");
+        }
+
+        let mut write_txs = self.pending_transaction_addresses.write();
+        batch.extend_with_option_cache(
+            db::COL_EXTRA,
+            &mut *write_txs,
+            self.prepare_transaction_addresses_update(block.view().transaction_hashes(), &info),
+            CacheUpdatePolicy::Overwrite,
+        );
+        true
+    }
+
     /// clears all caches, re-loads best block from disk for testing purposes
     pub fn clear_cache(&self) {
         self.block_bodies.write().clear();
@@ -1054,7 +1161,7 @@ impl BlockChain {
         let mut best_block = self.best_block.write();
         *best_block = BestBlock {
             total_difficulty: best_block_total_difficulty,
-            header: best_block_rlp.decode_header(self.eip1559_transition),
+            header: best_block_rlp.decode_header(self.header_transitions),
             block: best_block_rlp,
         };
     }
@@ -1473,7 +1580,7 @@ impl BlockChain {
                 batch.put(db::COL_EXTRA, b"best", update.info.hash.as_bytes());
                 *best_block = Some(BestBlock {
                     total_difficulty: update.info.total_difficulty,
-                    header: update.block.decode_header(self.eip1559_transition),
+                    header: update.block.decode_header(self.header_transitions),
                     block: update.block,
                 });
             }
@@ -1909,10 +2016,14 @@ impl BlockChain {
 
     /// Create a block body from a block.
     pub fn block_to_body(block: &[u8]) -> Bytes {
-        let mut body = RlpStream::new_list(2);
         let block_view = view!(BlockView, block);
+        let withdrawals = block_view.withdrawals_rlp();
+        let mut body = RlpStream::new_list(2 + withdrawals.is_some() as usize);
         body.append_raw(block_view.transactions_rlp().as_raw(), 1);
         body.append_raw(block_view.uncles_rlp().as_raw(), 1);
+        if let Some(withdrawals) = withdrawals {
+            body.append_raw(withdrawals.as_raw(), 1);
+        }
         body.out()
     }
 
@@ -2004,9 +2115,9 @@ mod tests {
     fn new_chain(
         genesis: encoded::Block,
         db: Arc<dyn BlockChainDB>,
-        eip1559_transition: BlockNumber,
+        header_transitions: HeaderTransitions,
     ) -> BlockChain {
-        BlockChain::new(Config::default(), genesis.raw(), db, eip1559_transition)
+        BlockChain::new(Config::default(), genesis.raw(), db, header_transitions)
     }
 
     fn insert_block(
@@ -2075,7 +2186,7 @@ mod tests {
         let bc = new_chain(
             genesis.last().encoded(),
             db.clone(),
-            BlockNumber::max_value(),
+            HeaderTransitions::default(),
         );
         assert_eq!(bc.best_block_number(), 0);
 
@@ -2104,7 +2215,7 @@ mod tests {
         let first_hash = first.hash();
 
         let db = new_db();
-        let bc = new_chain(genesis.encoded(), db.clone(), BlockNumber::max_value());
+        let bc = new_chain(genesis.encoded(), db.clone(), HeaderTransitions::default());
 
         assert_eq!(bc.genesis_hash(), genesis_hash);
         assert_eq!(bc.best_block_hash(), genesis_hash);
@@ -2139,7 +2250,7 @@ mod tests {
         let bc = new_chain(
             genesis.last().encoded(),
             db.clone(),
-            BlockNumber::max_value(),
+            HeaderTransitions::default(),
         );
 
         let mut block_hashes = vec![genesis.last().hash()];
@@ -2190,7 +2301,7 @@ mod tests {
         let bc = new_chain(
             genesis.last().encoded(),
             db.clone(),
-            BlockNumber::max_value(),
+            HeaderTransitions::default(),
         );
 
         for b in generator {
@@ -2233,7 +2344,7 @@ mod tests {
         let bc = new_chain(
             genesis.last().encoded(),
             db.clone(),
-            BlockNumber::max_value(),
+            HeaderTransitions::default(),
         );
 
         let mut batch = db.key_value().transaction();
@@ -2324,7 +2435,7 @@ mod tests {
         let bc = new_chain(
             genesis.last().encoded(),
             db.clone(),
-            BlockNumber::max_value(),
+            HeaderTransitions::default(),
         );
 
         let mut batch = db.key_value().transaction();
@@ -2401,7 +2512,7 @@ mod tests {
         let bc = new_chain(
             genesis.last().encoded(),
             db.clone(),
-            BlockNumber::max_value(),
+            HeaderTransitions::default(),
         );
 
         let mut batch = db.key_value().transaction();
@@ -2531,7 +2642,7 @@ mod tests {
             let bc = new_chain(
                 genesis.last().encoded(),
                 db.clone(),
-                BlockNumber::max_value(),
+                HeaderTransitions::default(),
             );
             assert_eq!(bc.best_block_hash(), genesis_hash);
             let mut batch = db.key_value().transaction();
@@ -2545,7 +2656,7 @@ mod tests {
             let bc = new_chain(
                 genesis.last().encoded(),
                 db.clone(),
-                BlockNumber::max_value(),
+                HeaderTransitions::default(),
             );
 
             assert_eq!(bc.best_block_hash(), first_hash);
@@ -2564,7 +2675,7 @@ mod tests {
         let bc = new_chain(
             encoded::Block::new(genesis),
             db.clone(),
-            BlockNumber::max_value(),
+            HeaderTransitions::default(),
         );
         let mut batch = db.key_value().transaction();
         insert_block_batch(&mut batch, &bc, encoded::Block::new(b1), vec![]);
@@ -2652,7 +2763,7 @@ mod tests {
         let bc = new_chain(
             genesis.last().encoded(),
             db.clone(),
-            BlockNumber::max_value(),
+            HeaderTransitions::default(),
         );
         insert_block(
             &db,
@@ -2840,7 +2951,7 @@ mod tests {
         let bc = new_chain(
             genesis.last().encoded(),
             db.clone(),
-            BlockNumber::max_value(),
+            HeaderTransitions::default(),
         );
 
         let blocks_b1 = bc.blocks_with_bloom(Some(&bloom_b1), 0, 5);
@@ -2906,7 +3017,7 @@ mod tests {
         let bc = new_chain(
             genesis.last().encoded(),
             db.clone(),
-            BlockNumber::max_value(),
+            HeaderTransitions::default(),
         );
         let mut batch = db.key_value().transaction();
         bc.insert_unordered_block(
@@ -2938,6 +3049,78 @@ mod tests {
         assert_eq!(blocks_b3, vec![3]);
     }
 
+    #[test]
+    fn test_insert_ancient_headers_then_bodies() {
+        let t1 = TypedTransaction::Legacy(Transaction {
+            nonce: 0.into(),
+            gas_price: 0.into(),
+            gas: 100_000.into(),
+            action: Action::Create,
+            value: 100.into(),
+            data: "601080600c6000396000f3006000355415600957005b60203560003555"
+                .from_hex()
+                .unwrap(),
+        })
+        .sign(&secret(), None);
+        let t1_hash = t1.hash();
+
+        let genesis = BlockBuilder::genesis();
+        let b1 = genesis.add_block_with_transactions(iter::once(t1));
+        let b2 = b1.add_block();
+        let b3 = b2.add_block();
+
+        let db = new_db();
+        let bc = new_chain(
+            genesis.last().encoded(),
+            db.clone(),
+            HeaderTransitions::default(),
+        );
+        // a warp restored chain: only the top block is present
+        let b2_total_difficulty =
+            genesis.last().difficulty() + b1.last().difficulty() + b2.last().difficulty();
+        let mut batch = db.key_value().transaction();
+        bc.insert_unordered_block(
+            &mut batch,
+            b3.last().encoded(),
+            vec![],
+            Some(b2_total_difficulty),
+            true,
+            false,
+        );
+        db.key_value().write(batch).unwrap();
+        bc.commit();
+
+        let mut batch = db.key_value().transaction();
+        bc.insert_unordered_header(&mut batch, b1.last().encoded().header());
+        bc.insert_unordered_header(&mut batch, b2.last().encoded().header());
+        db.key_value().write(batch).unwrap();
+        bc.commit();
+
+        assert_eq!(bc.block_hash(1), Some(b1.last().hash()));
+        assert_eq!(bc.block_hash(2), Some(b2.last().hash()));
+        assert_eq!(bc.best_ancient_block(), None);
+        assert!(bc.block_header_data(&b1.last().hash()).is_some());
+        assert!(bc.block_body(&b1.last().hash()).is_none());
+        assert_eq!(bc.transaction_address(&t1_hash), None);
+
+        let mut batch = db.key_value().transaction();
+        assert!(bc.insert_ancient_body(&mut batch, b1.last().encoded(), vec![]));
+        db.key_value().write(batch).unwrap();
+        bc.commit();
+
+        assert!(bc.block_body(&b1.last().hash()).is_some());
+        assert_eq!(
+            bc.transaction_address(&t1_hash),
+            Some(TransactionAddress {
+                block_hash: b1.last().hash(),
+                index: 0,
+            })
+        );
+
+        let mut batch = db.key_value().transaction();
+        assert!(!bc.insert_ancient_body(&mut batch, b1.last().encoded(), vec![]));
+    }
+
     #[test]
     fn test_best_block_update() {
         let genesis = BlockBuilder::genesis();
@@ -2950,7 +3133,7 @@ mod tests {
             let bc = new_chain(
                 genesis.last().encoded(),
                 db.clone(),
-                BlockNumber::max_value(),
+                HeaderTransitions::default(),
             );
 
             let mut batch = db.key_value().transaction();
@@ -2967,7 +3150,7 @@ mod tests {
         }
 
         // re-loading the blockchain should load the correct best block.
-        let bc = new_chain(genesis.last().encoded(), db, BlockNumber::max_value());
+        let bc = new_chain(genesis.last().encoded(), db, HeaderTransitions::default());
         assert_eq!(bc.best_block_number(), 5);
     }
 
@@ -2985,7 +3168,7 @@ mod tests {
             let bc = new_chain(
                 genesis.last().encoded(),
                 db.clone(),
-                BlockNumber::max_value(),
+                HeaderTransitions::default(),
             );
 
             let mut batch = db.key_value().transaction();
@@ -3028,7 +3211,7 @@ mod tests {
         }
 
         // re-loading the blockchain should load the correct best block.
-        let bc = new_chain(genesis.last().encoded(), db, BlockNumber::max_value());
+        let bc = new_chain(genesis.last().encoded(), db, HeaderTransitions::default());
 
         assert_eq!(bc.best_block_number(), 5);
         assert_eq!(
@@ -3055,7 +3238,7 @@ mod tests {
         let bc = new_chain(
             genesis.last().encoded(),
             db.clone(),
-            BlockNumber::max_value(),
+            HeaderTransitions::default(),
         );
 
         let mut batch = db.key_value().transaction();
@@ -3144,7 +3327,7 @@ mod tests {
             let bc = new_chain(
                 genesis.last().encoded(),
                 db.clone(),
-                BlockNumber::max_value(),
+                HeaderTransitions::default(),
             );
             let mut batch = db.key_value().transaction();
             for block in blocks {
@@ -203,6 +203,12 @@ impl IoHandler<ClientIoMessage> for ClientIoHandler {
             ClientIoMessage::FeedBlockChunk(ref hash, ref chunk) => {
                 self.snapshot.feed_block_chunk(*hash, chunk)
             }
+            ClientIoMessage::BeginSnapRestoration(ref pivot) => {
+                if let Err(e) = self.snapshot.init_snap_restore(pivot.clone()) {
+                    warn!("Failed to initialize snap state restoration: {}", e);
+                }
+            }
+            ClientIoMessage::FeedSnapData(ref data) => self.snapshot.feed_snap_data(data.clone()),
             ClientIoMessage::TakeSnapshot(num) => {
                 let client = self.client.clone();
                 let snapshot = self.snapshot.clone();
@@ -38,8 +38,11 @@ use ethereum_types::{Address, Bloom, H256, U256};
 
 use engines::EthEngine;
 use error::{BlockError, Error};
+use executed::ExecutionError;
+use executive::{Executive, TransactOptions};
 use factory::Factories;
-use state::State;
+use machine::{Machine, MAX_BLOB_GAS_PER_BLOCK};
+use state::{CleanupMode, State};
 use state_db::StateDB;
 use trace::Tracing;
 use triehash::ordered_trie_root;
@@ -53,12 +56,14 @@ use types::{
     header::{ExtendedHeader, Header},
     receipt::{TransactionOutcome, TypedReceipt},
     transaction::{Error as TransactionError, SignedTransaction},
+    withdrawal::Withdrawal,
 };
 
 /// Block that is ready for transactions to be added.
 ///
 /// It's a bit like a Vec<Transaction>, except that whenever a transaction is pushed, we execute it and
 /// maintain the system `state()`. We also archive execution receipts in preparation for later block creation.
+#[derive(Clone)]
 pub struct OpenBlock<'x> {
     block: ExecutedBlock,
     engine: &'x dyn EthEngine,
@@ -98,6 +103,8 @@ pub struct ExecutedBlock {
     pub transactions: Vec<SignedTransaction>,
     /// Uncles.
     pub uncles: Vec<Header>,
+    /// Withdrawals, present only once EIP-4895 is active.
+    pub withdrawals: Option<Vec<Withdrawal>>,
     /// Transaction receipts.
     pub receipts: Vec<TypedReceipt>,
     /// Hashes of already executed transactions.
@@ -117,6 +124,7 @@ impl ExecutedBlock {
             header: Default::default(),
             transactions: Default::default(),
             uncles: Default::default(),
+            withdrawals: None,
             receipts: Default::default(),
             transactions_set: Default::default(),
             state: state,
@@ -141,6 +149,7 @@ impl ExecutedBlock {
             gas_used: self.receipts.last().map_or(U256::zero(), |r| r.gas_used),
             gas_limit: *self.header.gas_limit(),
             base_fee: self.header.base_fee(),
+            blob_base_fee: self.header.blob_base_fee(),
         }
     }
 
@@ -200,6 +209,13 @@ impl<'x> OpenBlock<'x> {
         r.block
             .header
             .set_base_fee(engine.calculate_base_fee(parent));
+        if number >= engine.params().eip4895_transition {
+            r.block.withdrawals = Some(Vec::new());
+        }
+        if let Some(excess_blob_gas) = engine.machine().calc_excess_blob_gas(parent) {
+            r.block.header.set_blob_gas_used(Some(0));
+            r.block.header.set_excess_blob_gas(Some(excess_blob_gas));
+        }
 
         let gas_floor_target = cmp::max(gas_range_target.0, engine.params().min_gas_limit);
         let gas_ceil_target = cmp::max(gas_range_target.1, gas_floor_target);
@@ -263,10 +279,9 @@ impl<'x> OpenBlock<'x> {
         t: SignedTransaction,
         h: Option<H256>,
     ) -> Result<&TypedReceipt, Error> {
-        if self.block.transactions_set.contains(&t.hash()) {
-            return Err(TransactionError::AlreadyImported.into());
-        }
+        self.check_transaction(&t)?;
 
+        let blob_gas = t.blob_gas();
         let env_info = self.block.env_info();
         let outcome = self.block.state.apply(
             &env_info,
@@ -275,6 +290,12 @@ impl<'x> OpenBlock<'x> {
             self.block.traces.is_enabled(),
         )?;
 
+        if let Some(blob_gas_used) = self.block.header.blob_gas_used() {
+            self.block
+                .header
+                .set_blob_gas_used(Some(blob_gas_used + blob_gas));
+        }
+
         self.block
             .transactions_set
             .insert(h.unwrap_or_else(|| t.hash()));
@@ -290,6 +311,76 @@ impl<'x> OpenBlock<'x> {
             .expect("receipt just pushed; qed"))
     }
 
+    /// Execute a transaction on top of the block without including it.
+    ///
+    /// Returns the gas the transaction would use and the amount the block author would earn
+    /// from it. The transaction runs under a state checkpoint which is reverted afterwards.
+    pub fn simulate_transaction(&mut self, t: &SignedTransaction) -> Result<(U256, U256), Error> {
+        self.check_transaction(t)?;
+
+        let env_info = self.block.env_info();
+        let machine = self.engine.machine();
+        let state = &mut self.block.state;
+
+        state.checkpoint();
+        let result = Self::simulate_on(state, &env_info, machine, t);
+        state.revert_to_checkpoint();
+        result
+    }
+
+    fn simulate_on(
+        state: &mut State<StateDB>,
+        env_info: &EnvInfo,
+        machine: &Machine,
+        t: &SignedTransaction,
+    ) -> Result<(U256, U256), Error> {
+        let schedule = machine.schedule(env_info.number);
+        let author_balance = state.balance(&env_info.author)?;
+        let executed = Executive::new(state, env_info, machine, &schedule)
+            .transact(t, TransactOptions::with_no_tracing())?;
+        let reward = state
+            .balance(&env_info.author)?
+            .saturating_sub(author_balance);
+        Ok((executed.gas_used, reward))
+    }
+
+    fn check_transaction(&self, t: &SignedTransaction) -> Result<(), Error> {
+        if self.block.transactions_set.contains(&t.hash()) {
+            return Err(TransactionError::AlreadyImported.into());
+        }
+
+        if let Some(blob_gas_used) = self.block.header.blob_gas_used() {
+            let blob_gas = t.blob_gas();
+            if blob_gas_used + blob_gas > MAX_BLOB_GAS_PER_BLOCK {
+                return Err(ExecutionError::BlockBlobGasLimitReached {
+                    blob_gas_limit: MAX_BLOB_GAS_PER_BLOCK,
+                    blob_gas_used,
+                    blob_gas,
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// t_nb 8.4.1 Push withdrawals onto the block, crediting each recipient.
+    ///
+    /// Withdrawals are processed after all transactions and don't consume any gas.
+    pub fn push_withdrawals(&mut self, withdrawals: Vec<Withdrawal>) -> Result<(), Error> {
+        for withdrawal in &withdrawals {
+            self.block.state.add_balance(
+                &withdrawal.address,
+                &withdrawal.amount_in_wei(),
+                CleanupMode::NoEmpty,
+            )?;
+        }
+        self.block
+            .withdrawals
+            .get_or_insert_with(Vec::new)
+            .extend(withdrawals);
+        Ok(())
+    }
+
     /// Push transactions onto the block.
     #[cfg(not(feature = "slow-blocks"))]
     fn push_transactions(&mut self, transactions: Vec<SignedTransaction>) -> Result<(), Error> {
@@ -327,6 +418,19 @@ impl<'x> OpenBlock<'x> {
         Ok(())
     }
 
+    /// Commit to the root of the parent beacon block and store it in the beacon roots contract
+    /// (EIP-4788). Must be called before any transaction is pushed.
+    pub fn set_parent_beacon_block_root(&mut self, root: H256) -> Result<(), Error> {
+        debug_assert!(
+            self.block.transactions.is_empty(),
+            "beacon root is stored before transactions; qed"
+        );
+        self.block.header.set_parent_beacon_block_root(Some(root));
+        self.engine
+            .machine()
+            .push_parent_beacon_block_root(&mut self.block)
+    }
+
     /// Populate self from a header.
     fn populate_from(&mut self, header: &Header) {
         self.block.header.set_difficulty(*header.difficulty());
@@ -373,6 +477,12 @@ impl<'x> OpenBlock<'x> {
         ));
         let uncle_bytes = encode_list(&s.block.uncles);
         s.block.header.set_uncles_hash(keccak(&uncle_bytes));
+        let withdrawals_root = s
+            .block
+            .withdrawals
+            .as_ref()
+            .map(|w| ordered_trie_root(w.iter().map(rlp::encode)));
+        s.block.header.set_withdrawals_root(withdrawals_root);
         s.block.header.set_state_root(s.block.state.root().clone());
         s.block.header.set_receipts_root(ordered_trie_root(
             s.block.receipts.iter().map(|r| r.encode()),
@@ -510,10 +620,13 @@ impl Drain for LockedBlock {
 impl SealedBlock {
     /// Get the RLP-encoding of the block.
     pub fn rlp_bytes(&self) -> Bytes {
-        let mut block_rlp = RlpStream::new_list(3);
+        let mut block_rlp = RlpStream::new_list(3 + self.block.withdrawals.is_some() as usize);
         block_rlp.append(&self.block.header);
         SignedTransaction::rlp_append_list(&mut block_rlp, &self.block.transactions);
         block_rlp.append_list(&self.block.uncles);
+        if let Some(ref withdrawals) = self.block.withdrawals {
+            block_rlp.append_list(withdrawals);
+        }
         block_rlp.out()
     }
 }
@@ -529,6 +642,7 @@ pub(crate) fn enact(
     header: Header,
     transactions: Vec<SignedTransaction>,
     uncles: Vec<Header>,
+    withdrawals: Option<Vec<Withdrawal>>,
     engine: &dyn EthEngine,
     tracing: bool,
     db: StateDB,
@@ -576,6 +690,11 @@ pub(crate) fn enact(
     // t_nb 8.2 transfer all field from current header to OpenBlock header that we created
     b.populate_from(&header);
 
+    // t_nb 8.2.1 store the parent beacon block root before executing transactions (EIP-4788)
+    if let Some(root) = header.parent_beacon_block_root() {
+        b.set_parent_beacon_block_root(*root)?;
+    }
+
     // t_nb 8.3 execute transactions one by one
     b.push_transactions(transactions)?;
 
@@ -584,6 +703,12 @@ pub(crate) fn enact(
         b.push_uncle(u)?;
     }
 
+    // t_nb 8.4.1 Credit withdrawals. Their presence was checked against the fork rules
+    // during block verification.
+    if let Some(withdrawals) = withdrawals {
+        b.push_withdrawals(withdrawals)?;
+    }
+
     // t_nb 8.5 close block
     b.close_and_lock()
 }
@@ -604,6 +729,7 @@ pub fn enact_verified(
         block.header,
         block.transactions,
         block.uncles,
+        block.withdrawals,
         engine,
         tracing,
         db,
@@ -639,7 +765,7 @@ mod tests {
         last_hashes: Arc<LastHashes>,
         factories: Factories,
     ) -> Result<LockedBlock, Error> {
-        let block = Unverified::from_rlp(block_bytes, engine.params().eip1559_transition)?;
+        let block = Unverified::from_rlp(block_bytes, engine.params().header_transitions())?;
         let header = block.header;
         let transactions: Result<Vec<_>, Error> = block
             .transactions
@@ -677,12 +803,19 @@ mod tests {
         )?;
 
         b.populate_from(&header);
+        if let Some(root) = header.parent_beacon_block_root() {
+            b.set_parent_beacon_block_root(*root)?;
+        }
         b.push_transactions(transactions)?;
 
         for u in block.uncles {
             b.push_uncle(u)?;
         }
 
+        if let Some(withdrawals) = block.withdrawals {
+            b.push_withdrawals(withdrawals)?;
+        }
+
         b.close_and_lock()
     }
 
@@ -697,7 +830,7 @@ mod tests {
         factories: Factories,
     ) -> Result<SealedBlock, Error> {
         let header =
-            Unverified::from_rlp(block_bytes.clone(), engine.params().eip1559_transition)?.header;
+            Unverified::from_rlp(block_bytes.clone(), engine.params().header_transitions())?.header;
         Ok(enact_bytes(
             block_bytes,
             engine,
@@ -854,7 +987,7 @@ mod tests {
 
         let bytes = e.rlp_bytes();
         assert_eq!(bytes, orig_bytes);
-        let uncles = view!(BlockView, &bytes).uncles(engine.params().eip1559_transition);
+        let uncles = view!(BlockView, &bytes).uncles(engine.params().header_transitions());
         assert_eq!(uncles[1].extra_data(), b"uncle2");
 
         let db = e.drain().state.drop().1;
@@ -869,4 +1002,66 @@ mod tests {
                 == None
         );
     }
+
+    #[test]
+    fn stores_parent_beacon_block_root_before_transactions() {
+        use machine::BEACON_ROOTS_ADDRESS;
+        use rustc_hex::FromHex;
+
+        // Runtime code of the EIP-4788 beacon roots contract.
+        const BEACON_ROOTS_CODE: &str = "3373fffffffffffffffffffffffffffffffffffffffe14604d57602036146024575f5ffd5b5f35801560495762001fff810690815414603c575f5ffd5b62001fff01545f5260205ff35b5f5ffd5b62001fff42064281555f359062001fff015500";
+        const HISTORY_BUFFER_LENGTH: u64 = 8191;
+
+        let spec = ::ethereum::new_cancun_test();
+        let engine = &*spec.engine;
+        let genesis_header = spec.genesis_header();
+        let db = spec
+            .ensure_db_good(get_temp_state_db(), &Default::default())
+            .unwrap();
+        let mut b = OpenBlock::new(
+            engine,
+            Default::default(),
+            false,
+            db,
+            &genesis_header,
+            Arc::new(vec![genesis_header.hash()]),
+            Address::zero(),
+            (3141562.into(), 31415620.into()),
+            vec![],
+            false,
+            None,
+        )
+        .unwrap();
+        assert_eq!(b.header.parent_beacon_block_root(), None);
+        b.block
+            .state
+            .init_code(&BEACON_ROOTS_ADDRESS, BEACON_ROOTS_CODE.from_hex().unwrap())
+            .unwrap();
+
+        let root = H256::from_low_u64_be(0xbeac04);
+        b.set_parent_beacon_block_root(root).unwrap();
+
+        let timestamp = b.header.timestamp();
+        let slot = timestamp % HISTORY_BUFFER_LENGTH;
+        let state = &b.block.state;
+        assert_eq!(
+            state
+                .storage_at(&BEACON_ROOTS_ADDRESS, &H256::from_low_u64_be(slot))
+                .unwrap(),
+            H256::from_low_u64_be(timestamp)
+        );
+        assert_eq!(
+            state
+                .storage_at(
+                    &BEACON_ROOTS_ADDRESS,
+                    &H256::from_low_u64_be(slot + HISTORY_BUFFER_LENGTH)
+                )
+                .unwrap(),
+            root
+        );
+
+        let b = b.close_and_lock().unwrap();
+        assert_eq!(b.header.parent_beacon_block_root(), Some(&root));
+        assert_eq!(b.header.gas_used(), &U256::zero());
+    }
 }
@@ -0,0 +1,207 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State and block overrides of virtual calls.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use bytes::Bytes;
+use ethereum_types::{Address, H256, U256};
+use ethtrie::Result as TrieResult;
+use state::{Backend, State};
+use vm::EnvInfo;
+
+/// Account fields replaced before executing a virtual call.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AccountOverride {
+    /// Balance.
+    pub balance: Option<U256>,
+    /// Nonce.
+    pub nonce: Option<U256>,
+    /// Code.
+    pub code: Option<Bytes>,
+    /// Storage replacing the whole storage of the account.
+    pub state: Option<BTreeMap<H256, H256>>,
+    /// Storage slots replaced individually, the rest of the storage is kept.
+    pub state_diff: Option<BTreeMap<H256, H256>>,
+}
+
+impl AccountOverride {
+    /// Apply the override to the account at `address`, creating it if needed.
+    pub fn apply<B: Backend>(&self, address: &Address, state: &mut State<B>) -> TrieResult<()> {
+        if let Some(balance) = self.balance {
+            state.set_balance(address, balance)?;
+        }
+        if let Some(nonce) = self.nonce {
+            state.set_nonce(address, nonce)?;
+        }
+        if let Some(ref code) = self.code {
+            state.reset_code(address, code.clone())?;
+        }
+        if let Some(ref storage) = self.state {
+            let code = state.code(address)?.unwrap_or_else(|| Arc::new(Vec::new()));
+            state.patch_account(
+                address,
+                code,
+                storage.iter().map(|(key, value)| (*key, *value)).collect(),
+            )?;
+        }
+        if let Some(ref storage) = self.state_diff {
+            for (key, value) in storage {
+                state.set_storage(address, *key, *value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Accounts replaced before executing a virtual call.
+pub type StateOverride = BTreeMap<Address, AccountOverride>;
+
+/// Block environment fields replaced before executing a virtual call.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BlockOverride {
+    /// Block number.
+    pub number: Option<u64>,
+    /// Block timestamp.
+    pub timestamp: Option<u64>,
+    /// Block gas limit.
+    pub gas_limit: Option<U256>,
+    /// Block author.
+    pub coinbase: Option<Address>,
+    /// Block base fee.
+    pub base_fee: Option<U256>,
+}
+
+impl BlockOverride {
+    /// Apply the override to the environment of the call. The base fee is left to the caller, as
+    /// it depends on the gas price of the call.
+    pub fn apply(&self, env_info: &mut EnvInfo) {
+        if let Some(number) = self.number {
+            env_info.number = number;
+        }
+        if let Some(timestamp) = self.timestamp {
+            env_info.timestamp = timestamp;
+        }
+        if let Some(gas_limit) = self.gas_limit {
+            env_info.gas_limit = gas_limit;
+        }
+        if let Some(coinbase) = self.coinbase {
+            env_info.author = coinbase;
+        }
+    }
+}
+
+/// State and block overrides of a virtual call.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CallOverrides {
+    /// Accounts to replace.
+    pub state: StateOverride,
+    /// Block environment to replace.
+    pub block: BlockOverride,
+}
+
+impl CallOverrides {
+    /// Apply the account overrides to `state`.
+    pub fn apply_state<B: Backend>(&self, state: &mut State<B>) -> TrieResult<()> {
+        for (address, account) in &self.state {
+            account.apply(address, state)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_helpers::get_temp_state;
+
+    #[test]
+    fn account_override_should_replace_storage() {
+        let address = Address::from_low_u64_be(1);
+        let kept = H256::from_low_u64_be(1);
+        let dropped = H256::from_low_u64_be(2);
+        let mut state = get_temp_state();
+        state
+            .set_storage(&address, kept, H256::from_low_u64_be(10))
+            .unwrap();
+        state
+            .set_storage(&address, dropped, H256::from_low_u64_be(20))
+            .unwrap();
+        state.commit().unwrap();
+
+        let mut overrides = CallOverrides::default();
+        overrides.state.insert(
+            address,
+            AccountOverride {
+                balance: Some(5.into()),
+                nonce: Some(7.into()),
+                code: Some(vec![0x60, 0x00]),
+                state: Some(
+                    vec![(kept, H256::from_low_u64_be(11))]
+                        .into_iter()
+                        .collect(),
+                ),
+                state_diff: None,
+            },
+        );
+        overrides.apply_state(&mut state).unwrap();
+
+        assert_eq!(state.balance(&address).unwrap(), 5.into());
+        assert_eq!(state.nonce(&address).unwrap(), 7.into());
+        assert_eq!(*state.code(&address).unwrap().unwrap(), vec![0x60, 0x00]);
+        assert_eq!(
+            state.storage_at(&address, &kept).unwrap(),
+            H256::from_low_u64_be(11)
+        );
+        assert_eq!(state.storage_at(&address, &dropped).unwrap(), H256::zero());
+    }
+
+    #[test]
+    fn account_override_should_patch_storage() {
+        let address = Address::from_low_u64_be(1);
+        let kept = H256::from_low_u64_be(1);
+        let patched = H256::from_low_u64_be(2);
+        let mut state = get_temp_state();
+        state
+            .set_storage(&address, kept, H256::from_low_u64_be(10))
+            .unwrap();
+        state.commit().unwrap();
+
+        let mut overrides = CallOverrides::default();
+        overrides.state.insert(
+            address,
+            AccountOverride {
+                state_diff: Some(
+                    vec![(patched, H256::from_low_u64_be(20))]
+                        .into_iter()
+                        .collect(),
+                ),
+                ..Default::default()
+            },
+        );
+        overrides.apply_state(&mut state).unwrap();
+
+        assert_eq!(
+            state.storage_at(&address, &kept).unwrap(),
+            H256::from_low_u64_be(10)
+        );
+        assert_eq!(
+            state.storage_at(&address, &patched).unwrap(),
+            H256::from_low_u64_be(20)
+        );
+    }
+}
@@ -16,7 +16,7 @@
 
 use std::{
     cmp,
-    collections::{BTreeMap, HashSet, VecDeque},
+    collections::{BTreeMap, BTreeSet, HashSet, VecDeque},
     convert::TryFrom,
     io::{BufRead, BufReader},
     str::{from_utf8, FromStr},
@@ -48,7 +48,7 @@ use types::{
     data_format::DataFormat,
     encoded,
     filter::Filter,
-    header::{ExtendedHeader, Header},
+    header::{calc_blob_base_fee, ExtendedHeader, Header},
     log_entry::LocalizedLogEntry,
     receipt::{LocalizedReceipt, TypedReceipt},
     transaction::{
@@ -57,6 +57,7 @@ use types::{
     },
     BlockNumber,
 };
+use unexpected::OutOfBounds;
 use vm::{EnvInfo, LastHashes};
 
 use ansi_term::Colour;
@@ -68,11 +69,12 @@ use client::{
     traits::{ForceUpdateSealing, TransactionRequest},
     AccountData, BadBlocks, Balance, BlockChain as BlockChainTrait, BlockChainClient,
     BlockChainReset, BlockId, BlockInfo, BlockProducer, BroadcastProposalBlock, Call,
-    CallAnalytics, ChainInfo, ChainMessageType, ChainNotify, ChainRoute, ClientConfig,
-    ClientIoMessage, EngineInfo, ImportBlock, ImportExportBlocks, ImportSealedBlock, IoClient,
-    Mode, NewBlocks, Nonce, PrepareOpenBlock, ProvingBlockChainClient, PruningInfo, ReopenBlock,
-    ScheduleInfo, SealedBlockImporter, StateClient, StateInfo, StateOrBlock, TraceFilter, TraceId,
-    TransactionId, TransactionInfo, UncleId,
+    CallAnalytics, CallOverrides, ChainInfo, ChainMessageType, ChainNotify, ChainRoute,
+    ClientConfig, ClientIoMessage, EngineInfo, ImportBlock, ImportExportBlocks, ImportSealedBlock,
+    IoClient, Mode, NewBlocks, Nonce, PrepareOpenBlock, ProvingBlockChainClient, PruningInfo,
+    ReopenBlock, ScheduleInfo, SealedBlockImporter, SimulatedBlock, SimulationBlock, StateClient,
+    StateInfo, StateOrBlock, TraceFilter, TraceId, TransactionId, TransactionInfo, UncleId,
+    SIMULATED_BLOCK_INTERVAL,
 };
 use engines::{
     epoch::PendingTransition, EngineError, EpochTransition, EthEngine, ForkChoice, SealingState,
@@ -86,13 +88,18 @@ use executive::{contract_address, Executed, Executive, TransactOptions};
 use factory::{Factories, VmFactory};
 use io::IoChannel;
 use miner::{Miner, MinerService};
-use snapshot::{self, io as snapshot_io, SnapshotClient};
+use snapshot::{
+    self, io as snapshot_io,
+    snap::{self, AccountRange, StorageRanges},
+    SnapshotClient,
+};
 use spec::Spec;
 use state::{self, State};
 use state_db::StateDB;
 use stats::{PrometheusMetrics, PrometheusRegistry};
 use trace::{
-    self, Database as TraceDatabase, ImportRequest as TraceImportRequest, LocalizedTrace, TraceDB,
+    self, geth, Database as TraceDatabase, GethTrace, GethTracer,
+    ImportRequest as TraceImportRequest, LocalizedTrace, TraceDB,
 };
 use transaction_ext::Transaction;
 use verification::{
@@ -115,6 +122,16 @@ const ANCIENT_BLOCKS_BATCH_SIZE: usize = 4;
 const MAX_QUEUE_SIZE_TO_SLEEP_ON: usize = 2;
 const MIN_HISTORY_SIZE: u64 = 8;
 
+/// Ancient chain data waiting in the ancient import queue.
+enum AncientImport {
+    /// A full block with its receipts.
+    Block(Unverified, Bytes),
+    /// A header without its body, imported ahead of the body.
+    Header(Header),
+    /// Body and receipts of a block whose header is already in the chain.
+    Body(Unverified, Bytes),
+}
+
 /// Report on the status of a client.
 #[derive(Default, Clone, Debug, Eq, PartialEq)]
 pub struct ClientReport {
@@ -236,7 +253,7 @@ pub struct Client {
     /// Ancient blocks import queue
     /// Queued ancient blocks, make sure they are imported in order.
     queued_ancient_blocks: Arc<RwLock<HashSet<H256>>>,
-    queued_ancient_blocks_executer: Mutex<Option<ExecutionQueue<(Unverified, Bytes)>>>,
+    queued_ancient_blocks_executer: Mutex<Option<ExecutionQueue<AncientImport>>>,
     /// Consensus messages import queue
     queue_consensus_message: IoChannelQueue,
 
@@ -358,7 +375,7 @@ impl Importer {
                         self.bad_blocks.report(
                             bytes,
                             format!("{:?}", err),
-                            self.engine.params().eip1559_transition,
+                            self.engine.params().header_transitions(),
                         );
                         invalid_blocks.insert(hash);
                     }
@@ -628,6 +645,65 @@ impl Importer {
         Ok(())
     }
 
+    /// Import an ancient header without its body.
+    ///
+    /// The header is guaranteed to be the next header in the first block sequence.
+    /// Its body and receipts can be imported later with `import_old_body`.
+    fn import_old_header(
+        &self,
+        header: Header,
+        db: &dyn KeyValueDB,
+        chain: &BlockChain,
+    ) -> EthcoreResult<()> {
+        let _import_lock = self.import_lock.lock();
+
+        let best_number = chain.best_block_header().number();
+        if header.number() >= best_number {
+            return Err(BlockError::RidiculousNumber(OutOfBounds {
+                min: None,
+                max: Some(best_number.saturating_sub(1)),
+                found: header.number(),
+            })
+            .into());
+        }
+
+        trace_time!("import_old_header");
+        let mut rng = OsRng;
+        self.ancient_verifier.verify(&mut rng, &header, &chain)?;
+
+        let mut batch = DBTransaction::new();
+        chain.insert_unordered_header(&mut batch, header.encoded());
+        // headers are small, leave flushing to the next block import
+        db.write_buffered(batch);
+        chain.commit();
+        Ok(())
+    }
+
+    /// Import the body and receipts of an ancient block imported earlier with `import_old_header`.
+    ///
+    /// The block must be canonical; its body was matched against the header by sync.
+    fn import_old_body(
+        &self,
+        unverified: Unverified,
+        receipts_bytes: &[u8],
+        db: &dyn KeyValueDB,
+        chain: &BlockChain,
+    ) -> EthcoreResult<()> {
+        let receipts = TypedReceipt::decode_rlp_list(&Rlp::new(receipts_bytes))
+            .unwrap_or_else(|e| panic!("Receipt bytes should be valid: {:?}", e));
+        let _import_lock = self.import_lock.lock();
+
+        {
+            trace_time!("import_old_body");
+            let mut batch = DBTransaction::new();
+            chain.insert_ancient_body(&mut batch, encoded::Block::new(unverified.bytes), receipts);
+            db.write_buffered(batch);
+            chain.commit();
+        }
+        db.flush().expect("DB flush failed.");
+        Ok(())
+    }
+
     // NOTE: the header of the block passed here is not necessarily sealed, as
     // it is for reconstructing the state transition.
     //
@@ -679,7 +755,7 @@ impl Importer {
             let header = chain
                 .block_header_data(&hash)
                 .expect("Best block is in the database; qed")
-                .decode(self.engine.params().eip1559_transition)
+                .decode(self.engine.params().header_transitions())
                 .expect("Stored block header is valid RLP; qed");
             let details = chain
                 .block_details(&hash)
@@ -818,6 +894,7 @@ impl Importer {
                             gas_used: U256::default(),
                             gas_limit: u64::max_value().into(),
                             base_fee: header.base_fee(),
+                            blob_base_fee: header.blob_base_fee(),
                         };
 
                         let call = move |addr, data| {
@@ -960,7 +1037,7 @@ impl Client {
             config.blockchain.clone(),
             &gb,
             db.clone(),
-            spec.params().eip1559_transition,
+            spec.params().header_transitions(),
         ));
         let tracedb = RwLock::new(TraceDB::new(
             config.tracing.clone(),
@@ -1046,20 +1123,45 @@ impl Client {
         let queued_ancient_blocks_executer = ExecutionQueue::new(
             ANCIENT_BLOCKS_QUEUE_SIZE,
             ANCIENT_BLOCKS_BATCH_SIZE,
-            move |ancient_block: Vec<(Unverified, Bytes)>| {
+            move |ancient_block: Vec<AncientImport>| {
                 trace_time!("import_ancient_block");
-                for (unverified, receipts_bytes) in ancient_block {
-                    let hash = unverified.hash();
-                    if !exec_client.chain.read().is_known(&unverified.parent_hash()) {
+                for item in ancient_block {
+                    let (hash, parent_hash) = match item {
+                        AncientImport::Block(ref unverified, _)
+                        | AncientImport::Body(ref unverified, _) => {
+                            (unverified.hash(), *unverified.parent_hash())
+                        }
+                        AncientImport::Header(ref header) => (header.hash(), *header.parent_hash()),
+                    };
+                    if !exec_client.chain.read().is_known(&parent_hash) {
                         queued.write().remove(&hash);
                         continue;
                     }
-                    let result = exec_client.importer.import_old_block(
-                        unverified,
-                        &receipts_bytes,
-                        &**exec_client.db.read().key_value(),
-                        &*exec_client.chain.read(),
-                    );
+                    let result = {
+                        let db = exec_client.db.read();
+                        let chain = exec_client.chain.read();
+                        match item {
+                            AncientImport::Block(unverified, receipts_bytes) => {
+                                exec_client.importer.import_old_block(
+                                    unverified,
+                                    &receipts_bytes,
+                                    &**db.key_value(),
+                                    &*chain,
+                                )
+                            }
+                            AncientImport::Header(header) => exec_client
+                                .importer
+                                .import_old_header(header, &**db.key_value(), &*chain),
+                            AncientImport::Body(unverified, receipts_bytes) => {
+                                exec_client.importer.import_old_body(
+                                    unverified,
+                                    &receipts_bytes,
+                                    &**db.key_value(),
+                                    &*chain,
+                                )
+                            }
+                        }
+                    };
                     if let Err(e) = result {
                         error!(target: "client", "Error importing ancient block: {}", e);
 
@@ -1134,6 +1236,44 @@ impl Client {
         *abe = None;
     }
 
+    /// Check that an ancient block or header with the given parent can be queued next.
+    fn check_ancient_order(&self, hash: H256, parent_hash: H256) -> EthcoreResult<()> {
+        if self.chain.read().is_known(&hash) {
+            bail!(EthcoreErrorKind::Import(ImportErrorKind::AlreadyInChain));
+        }
+        // NOTE To prevent race condition with import, make sure to check queued blocks first
+        // (and attempt to acquire lock)
+        let is_parent_pending = self.queued_ancient_blocks.read().contains(&parent_hash);
+        if !is_parent_pending && !self.chain.read().is_known(&parent_hash) {
+            bail!(EthcoreErrorKind::Block(BlockError::UnknownParent(
+                parent_hash
+            )));
+        }
+        Ok(())
+    }
+
+    /// Hand ancient chain data over to the ancient import executer.
+    fn enqueue_ancient(&self, hash: H256, item: AncientImport) -> EthcoreResult<H256> {
+        // we queue blocks here and trigger an Executer.
+        {
+            let mut queued = self.queued_ancient_blocks.write();
+            queued.insert(hash);
+        }
+
+        // see content of executer in Client::new()
+        match self.queued_ancient_blocks_executer.lock().as_ref() {
+            Some(queue) => {
+                if !queue.enqueue(item) {
+                    bail!(EthcoreErrorKind::Queue(QueueErrorKind::Full(
+                        ANCIENT_BLOCKS_QUEUE_SIZE
+                    )));
+                }
+            }
+            None => (),
+        }
+        Ok(hash)
+    }
+
     /// Wakes up client if it's a sleep.
     pub fn keep_alive(&self) {
         let should_wake = match *self.mode.lock() {
@@ -1201,10 +1341,11 @@ impl Client {
             gas_used: U256::default(),
             gas_limit: header.gas_limit(),
             base_fee: if header.number() >= self.engine.params().eip1559_transition {
-                Some(header.base_fee())
+                Some(header.base_fee(self.engine.params().header_transitions()))
             } else {
                 None
             },
+            blob_base_fee: header.blob_base_fee(self.engine.params().header_transitions()),
         })
     }
 
@@ -1623,6 +1764,7 @@ impl Client {
             env_info: &EnvInfo,
             machine: &::machine::EthereumMachine,
             state_diff: bool,
+            access_list: bool,
             transaction: &SignedTransaction,
             options: TransactOptions<T, V>,
         ) -> Result<Executed<T::Output, V::Output>, CallError>
@@ -1631,6 +1773,11 @@ impl Client {
             V: trace::VMTracer,
         {
             let options = options.dont_check_nonce().save_output_from_contract();
+            let options = if access_list {
+                options.record_access_list()
+            } else {
+                options
+            };
             let original_state = if state_diff {
                 Some(state.clone())
             } else {
@@ -1648,6 +1795,7 @@ impl Client {
         }
 
         let state_diff = analytics.state_diffing;
+        let access_list = analytics.access_list_recording;
 
         match (analytics.transaction_tracing, analytics.vm_tracing) {
             (true, true) => call(
@@ -1655,6 +1803,7 @@ impl Client {
                 env_info,
                 machine,
                 state_diff,
+                access_list,
                 t,
                 TransactOptions::with_tracing_and_vm_tracing(),
             ),
@@ -1663,6 +1812,7 @@ impl Client {
                 env_info,
                 machine,
                 state_diff,
+                access_list,
                 t,
                 TransactOptions::with_tracing(),
             ),
@@ -1671,6 +1821,7 @@ impl Client {
                 env_info,
                 machine,
                 state_diff,
+                access_list,
                 t,
                 TransactOptions::with_vm_tracing(),
             ),
@@ -1679,12 +1830,118 @@ impl Client {
                 env_info,
                 machine,
                 state_diff,
+                access_list,
                 t,
                 TransactOptions::with_no_tracing(),
             ),
         }
     }
 
+    fn do_geth_trace(
+        machine: &::machine::EthereumMachine,
+        env_info: &EnvInfo,
+        state: &mut State<StateDB>,
+        t: &SignedTransaction,
+        tracer: &GethTracer,
+    ) -> Result<(GethTrace, U256), CallError> {
+        let schedule = machine.schedule(env_info.number);
+
+        match *tracer {
+            GethTracer::StructLogger(config) => {
+                let options =
+                    TransactOptions::new(trace::NoopTracer, trace::StructLogger::new(config))
+                        .dont_check_nonce()
+                        .save_output_from_contract();
+                let executed = Executive::new(state, env_info, machine, &schedule)
+                    .transact_virtual(t, options)?;
+
+                let trace = GethTrace::StructLogs(geth::StructLogs {
+                    gas: executed.gas_used,
+                    failed: executed.exception.is_some(),
+                    return_value: executed.output,
+                    struct_logs: executed.vm_trace.unwrap_or_default(),
+                });
+                Ok((trace, executed.gas_used))
+            }
+            GethTracer::Call { only_top_call } => {
+                let options = TransactOptions::new(
+                    trace::CallTracer::new(only_top_call),
+                    trace::NoopVMTracer,
+                )
+                .dont_check_nonce()
+                .save_output_from_contract();
+                let executed = Executive::new(state, env_info, machine, &schedule)
+                    .transact_virtual(t, options)?;
+
+                let mut frame = executed
+                    .trace
+                    .into_iter()
+                    .next()
+                    .expect("Executive always traces the top-level call or create; qed");
+                // The top-level frame reports the gas of the whole transaction, not only of the
+                // execution.
+                frame.gas = t.tx().gas;
+                frame.gas_used = executed.gas_used;
+                Ok((GethTrace::Call(frame), executed.gas_used))
+            }
+            GethTracer::Prestate { diff_mode } => {
+                let original = state.clone();
+                let options = TransactOptions::new(
+                    trace::PrestateTracer::default(),
+                    trace::StorageAccessTracer::default(),
+                )
+                .dont_check_nonce()
+                .save_output_from_contract();
+                let executed = Executive::new(state, env_info, machine, &schedule)
+                    .transact_virtual(t, options)?;
+                let diff = state
+                    .diff_from(original.clone())
+                    .map_err(ExecutionError::from)?;
+
+                if diff_mode {
+                    return Ok((GethTrace::PrestateDiff(diff.into()), executed.gas_used));
+                }
+
+                let mut accessed = executed.vm_trace.unwrap_or_default();
+                for (address, account) in &diff.raw {
+                    accessed
+                        .entry(*address)
+                        .or_default()
+                        .extend(account.storage.keys().cloned());
+                }
+
+                let mut addresses = executed.trace.into_iter().collect::<BTreeSet<_>>();
+                addresses.insert(t.sender());
+                addresses.insert(env_info.author);
+                addresses.extend(accessed.keys().cloned());
+
+                let mut prestate = BTreeMap::new();
+                for address in addresses {
+                    let nonce = original.nonce(&address).map_err(ExecutionError::from)?;
+                    let code = original.code(&address).map_err(ExecutionError::from)?;
+                    let mut storage = BTreeMap::new();
+                    for key in accessed.get(&address).into_iter().flatten() {
+                        let value = original
+                            .storage_at(&address, key)
+                            .map_err(ExecutionError::from)?;
+                        storage.insert(*key, value);
+                    }
+
+                    let account = geth::PrestateAccount {
+                        balance: Some(original.balance(&address).map_err(ExecutionError::from)?),
+                        nonce: Some(nonce).filter(|nonce| !nonce.is_zero()),
+                        code: code
+                            .map(|code| (*code).clone())
+                            .filter(|code| !code.is_empty()),
+                        storage,
+                    };
+                    prestate.insert(address, account);
+                }
+                Ok((GethTrace::Prestate(prestate), executed.gas_used))
+            }
+        }
+    }
+
     fn block_number_ref(&self, id: &BlockId) -> Option<BlockNumber> {
         match *id {
             BlockId::Number(number) => Some(number),
@@ -1709,7 +1966,7 @@ impl Client {
             }
             _ => self
                 .block_header(id)
-                .and_then(|h| h.decode(self.engine.params().eip1559_transition).ok()),
+                .and_then(|h| h.decode(self.engine.params().header_transitions()).ok()),
         }
     }
 }
@@ -1736,7 +1993,7 @@ impl snapshot::DatabaseRestore for Client {
             self.config.blockchain.clone(),
             &[],
             db.clone(),
-            self.engine.params().eip1559_transition,
+            self.engine.params().header_transitions(),
         ));
         *tracedb = TraceDB::new(self.config.tracing.clone(), db.clone(), chain.clone());
         Ok(())
@@ -1951,7 +2208,7 @@ impl ImportBlock for Client {
                 self.importer.bad_blocks.report(
                     block.bytes,
                     err.to_string(),
-                    self.engine.params().eip1559_transition,
+                    self.engine.params().header_transitions(),
                 );
                 bail!(EthcoreErrorKind::Block(err))
             }
@@ -1992,6 +2249,48 @@ impl Call for Client {
         state: &mut Self::State,
         header: &Header,
     ) -> Result<Executed, CallError> {
+        self.call_with_overrides(transaction, analytics, &Default::default(), state, header)
+    }
+
+    fn call_with_overrides(
+        &self,
+        transaction: &SignedTransaction,
+        analytics: CallAnalytics,
+        overrides: &CallOverrides,
+        state: &mut Self::State,
+        header: &Header,
+    ) -> Result<Executed, CallError> {
+        let base_fee = overrides.block.base_fee.or_else(|| header.base_fee());
+        let mut env_info = EnvInfo {
+            number: header.number(),
+            author: header.author().clone(),
+            timestamp: header.timestamp(),
+            difficulty: header.difficulty().clone(),
+            last_hashes: self.build_last_hashes(header.parent_hash()),
+            gas_used: U256::default(),
+            gas_limit: U256::max_value(),
+            //if gas pricing is not defined, force base_fee to zero
+            base_fee: if transaction.effective_gas_price(base_fee).is_zero() {
+                Some(0.into())
+            } else {
+                base_fee
+            },
+            blob_base_fee: header.blob_base_fee(),
+        };
+        overrides.block.apply(&mut env_info);
+        overrides.apply_state(state).map_err(ExecutionError::from)?;
+        let machine = self.engine.machine();
+
+        Self::do_virtual_call(&machine, &env_info, state, transaction, analytics)
+    }
+
+    fn debug_trace_call(
+        &self,
+        transaction: &SignedTransaction,
+        tracer: &GethTracer,
+        state: &mut Self::State,
+        header: &Header,
+    ) -> Result<GethTrace, CallError> {
         let env_info = EnvInfo {
             number: header.number(),
             author: header.author().clone(),
@@ -2006,10 +2305,11 @@ impl Call for Client {
             } else {
                 header.base_fee()
             },
+            blob_base_fee: header.blob_base_fee(),
         };
         let machine = self.engine.machine();
 
-        Self::do_virtual_call(&machine, &env_info, state, transaction, analytics)
+        Self::do_geth_trace(&machine, &env_info, state, transaction, tracer).map(|(trace, _)| trace)
     }
 
     fn call_many(
@@ -2027,6 +2327,7 @@ impl Call for Client {
             gas_used: U256::default(),
             gas_limit: U256::max_value(),
             base_fee: header.base_fee(),
+            blob_base_fee: header.blob_base_fee(),
         };
 
         let mut results = Vec::with_capacity(transactions.len());
@@ -2048,6 +2349,121 @@ impl Call for Client {
         Ok(results)
     }
 
+    fn simulate(
+        &self,
+        blocks: &[SimulationBlock],
+        state: &mut Self::State,
+        header: &Header,
+    ) -> Result<Vec<SimulatedBlock>, CallError> {
+        let machine = self.engine.machine();
+        let mut last_hashes = (*self.build_last_hashes(&header.hash())).clone();
+        let mut parent = header.clone();
+        let mut simulated = Vec::with_capacity(blocks.len());
+
+        for block in blocks {
+            let overrides = &block.overrides.block;
+            let next_number = parent.number().checked_add(1).ok_or_else(|| {
+                ExecutionError::Internal("simulated block number overflows".into())
+            })?;
+            let number = overrides.number.unwrap_or(next_number);
+            let gas_limit = overrides.gas_limit.unwrap_or(*parent.gas_limit());
+            // the base fee of the following block is derived from the gas limit
+            if gas_limit < self.engine.params().min_gas_limit {
+                return Err(ExecutionError::Internal(format!(
+                    "simulated block gas limit {} is below the minimum of {}",
+                    gas_limit,
+                    self.engine.params().min_gas_limit
+                ))
+                .into());
+            }
+            let base_fee = overrides
+                .base_fee
+                .or_else(|| self.engine.calculate_base_fee(&parent));
+            let excess_blob_gas = machine.calc_excess_blob_gas(&parent);
+
+            // Blocks skipped by a number override are unknown to `BLOCKHASH`, which only looks
+            // 256 blocks back anyway.
+            let skipped = ::std::cmp::min(number.saturating_sub(next_number), 256) as usize;
+            last_hashes.splice(0..0, vec![H256::zero(); skipped]);
+            last_hashes.truncate(256);
+
+            let mut env_info = EnvInfo {
+                number,
+                author: *parent.author(),
+                timestamp: parent
+                    .timestamp()
+                    .checked_add(SIMULATED_BLOCK_INTERVAL)
+                    .ok_or_else(|| {
+                        ExecutionError::Internal("simulated block timestamp overflows".into())
+                    })?,
+                difficulty: *parent.difficulty(),
+                last_hashes: Arc::new(last_hashes.clone()),
+                gas_used: U256::default(),
+                gas_limit,
+                base_fee,
+                blob_base_fee: excess_blob_gas.map(calc_blob_base_fee),
+            };
+            overrides.apply(&mut env_info);
+            block
+                .overrides
+                .apply_state(state)
+                .map_err(ExecutionError::from)?;
+
+            let mut results = Vec::with_capacity(block.transactions.len());
+            let mut blob_gas_used = 0u64;
+            for t in &block.transactions {
+                //if gas pricing is not defined, force base_fee to zero
+                env_info.base_fee = if t.effective_gas_price(base_fee).is_zero() {
+                    Some(0.into())
+                } else {
+                    base_fee
+                };
+
+                // calls get at most the gas left in the block
+                let gas_left = env_info.gas_limit.saturating_sub(env_info.gas_used);
+                let capped;
+                let t = if t.tx().gas > gas_left {
+                    let mut tx = t.as_unsigned().clone();
+                    tx.tx_mut().gas = gas_left;
+                    capped = tx.fake_sign(t.sender());
+                    &capped
+                } else {
+                    t
+                };
+
+                let ret = Self::do_virtual_call(machine, &env_info, state, t, Default::default())?;
+                env_info.gas_used = ret.cumulative_gas_used;
+                blob_gas_used = blob_gas_used.saturating_add(t.blob_gas());
+                results.push(ret);
+            }
+
+            let mut simulated_header = Header::new();
+            simulated_header.set_parent_hash(parent.hash());
+            simulated_header.set_number(number);
+            simulated_header.set_timestamp(env_info.timestamp);
+            simulated_header.set_author(env_info.author);
+            simulated_header.set_difficulty(env_info.difficulty);
+            simulated_header.set_gas_limit(env_info.gas_limit);
+            simulated_header.set_gas_used(env_info.gas_used);
+            simulated_header.set_base_fee(base_fee);
+            if excess_blob_gas.is_some() {
+                simulated_header.set_blob_gas_used(Some(blob_gas_used));
+                simulated_header.set_excess_blob_gas(excess_blob_gas);
+            }
+
+            last_hashes.insert(0, simulated_header.hash());
+            last_hashes.truncate(256);
+
+            parent = simulated_header.clone();
+            simulated.push(SimulatedBlock {
+                header: simulated_header,
+                results,
+            });
+        }
+
+        Ok(simulated)
+    }
+
     fn estimate_gas(
         &self,
         t: &SignedTransaction,
@@ -2071,6 +2487,7 @@ impl Call for Client {
                 } else {
                     header.base_fee()
                 },
+                blob_base_fee: header.blob_base_fee(),
             };
 
             (init, max, env_info)
@@ -2156,7 +2573,7 @@ impl BadBlocks for Client {
     fn bad_blocks(&self) -> Vec<(Unverified, String)> {
         self.importer
             .bad_blocks
-            .bad_blocks(self.engine.params().eip1559_transition)
+            .bad_blocks(self.engine.params().header_transitions())
     }
 }
 
@@ -2204,6 +2621,67 @@ impl BlockChainClient for Client {
         })))
     }
 
+    fn debug_trace_transaction(
+        &self,
+        id: TransactionId,
+        tracer: &GethTracer,
+    ) -> Result<GethTrace, CallError> {
+        let address = self
+            .transaction_address(id)
+            .ok_or(CallError::TransactionNotFound)?;
+        let block = BlockId::Hash(address.block_hash);
+
+        let mut env_info = self.env_info(block).ok_or(CallError::StatePruned)?;
+        let body = self.block_body(block).ok_or(CallError::StatePruned)?;
+        let mut state = self
+            .state_at_beginning(block)
+            .ok_or(CallError::StatePruned)?;
+        let machine = self.engine.machine();
+
+        const PROOF: &'static str =
+            "Transactions fetched from blockchain; blockchain transactions are valid; qed";
+
+        for (index, t) in body.transactions().into_iter().enumerate() {
+            let t = SignedTransaction::new(t).expect(PROOF);
+            if index == address.index {
+                return Self::do_geth_trace(machine, &env_info, &mut state, &t, tracer)
+                    .map(|(trace, _)| trace);
+            }
+            let x = Self::do_virtual_call(machine, &env_info, &mut state, &t, Default::default())?;
+            env_info.gas_used = env_info.gas_used + x.gas_used;
+        }
+
+        Err(CallError::TransactionNotFound)
+    }
+
+    fn debug_trace_block(
+        &self,
+        block: BlockId,
+        tracer: &GethTracer,
+    ) -> Result<Vec<(H256, GethTrace)>, CallError> {
+        let mut env_info = self.env_info(block).ok_or(CallError::StatePruned)?;
+        let body = self.block_body(block).ok_or(CallError::StatePruned)?;
+        let mut state = self
+            .state_at_beginning(block)
+            .ok_or(CallError::StatePruned)?;
+        let machine = self.engine.machine();
+
+        const PROOF: &'static str =
+            "Transactions fetched from blockchain; blockchain transactions are valid; qed";
+
+        body.transactions()
+            .into_iter()
+            .map(|t| {
+                let transaction_hash = t.hash();
+                let t = SignedTransaction::new(t).expect(PROOF);
+                let (trace, gas_used) =
+                    Self::do_geth_trace(machine, &env_info, &mut state, &t, tracer)?;
+                env_info.gas_used = env_info.gas_used + gas_used;
+                Ok((transaction_hash, trace))
+            })
+            .collect()
+    }
+
     fn mode(&self) -> Mode {
         let r = self.mode.lock().clone().into();
         trace!(target: "mode", "Asked for mode = {:?}. returning {:?}", &*self.mode.lock(), r);
@@ -2468,7 +2946,7 @@ impl BlockChainClient for Client {
             .map(|receipt| receipt.logs.len())
             .sum::<usize>();
         let base_fee = if number >= self.engine().params().eip1559_transition {
-            Some(header.base_fee())
+            Some(header.base_fee(self.engine().params().header_transitions()))
         } else {
             None
         };
@@ -2494,7 +2972,7 @@ impl BlockChainClient for Client {
         let header = chain.block_header_data(&hash)?;
         let engine = self.engine.clone();
         let base_fee = if number >= engine.params().eip1559_transition {
-            Some(header.base_fee())
+            Some(header.base_fee(engine.params().header_transitions()))
         } else {
             None
         };
@@ -2760,7 +3238,7 @@ impl BlockChainClient for Client {
 
     fn uncle_extra_info(&self, id: UncleId) -> Option<BTreeMap<String, String>> {
         self.uncle(id).and_then(|h| {
-            h.decode(self.engine.params().eip1559_transition)
+            h.decode(self.engine.params().header_transitions())
                 .map(|dh| self.engine.extra_info(&dh))
                 .ok()
         })
@@ -2830,6 +3308,65 @@ impl BlockChainClient for Client {
     fn state_data(&self, hash: &H256) -> Option<Bytes> {
         self.state_db.read().journal_db().state(hash)
     }
+
+    fn snap_account_range(
+        &self,
+        root: &H256,
+        origin: &H256,
+        limit: &H256,
+        max_bytes: usize,
+    ) -> Option<AccountRange> {
+        let state_db = self.state_db.read();
+        snap::account_range(
+            state_db.journal_db().as_hash_db(),
+            root,
+            origin,
+            limit,
+            max_bytes,
+        )
+        .ok()
+    }
+
+    fn snap_storage_ranges(
+        &self,
+        root: &H256,
+        accounts: &[H256],
+        origin: &H256,
+        limit: &H256,
+        max_bytes: usize,
+    ) -> Option<StorageRanges> {
+        let state_db = self.state_db.read();
+        snap::storage_ranges(
+            state_db.journal_db().as_hash_db(),
+            root,
+            accounts,
+            origin,
+            limit,
+            max_bytes,
+        )
+        .ok()
+    }
+
+    fn snap_bytecodes(&self, hashes: &[H256], max_bytes: usize) -> Vec<Bytes> {
+        let state_db = self.state_db.read();
+        snap::bytecodes(state_db.journal_db().as_hash_db(), hashes, max_bytes)
+    }
+
+    fn snap_trie_nodes(
+        &self,
+        root: &H256,
+        path_sets: &[Vec<Bytes>],
+        max_bytes: usize,
+    ) -> Option<Vec<Bytes>> {
+        let state_db = self.state_db.read();
+        snap::trie_nodes(
+            state_db.journal_db().as_hash_db(),
+            root,
+            path_sets,
+            max_bytes,
+        )
+        .ok()
+    }
 }
 
 impl IoClient for Client {
@@ -2872,40 +3409,37 @@ impl IoClient for Client {
         trace_time!("queue_ancient_block");
 
         let hash = unverified.hash();
-        {
-            // check block order
-            if self.chain.read().is_known(&hash) {
-                bail!(EthcoreErrorKind::Import(ImportErrorKind::AlreadyInChain));
-            }
-            let parent_hash = unverified.parent_hash();
-            // NOTE To prevent race condition with import, make sure to check queued blocks first
-            // (and attempt to acquire lock)
-            let is_parent_pending = self.queued_ancient_blocks.read().contains(&parent_hash);
-            if !is_parent_pending && !self.chain.read().is_known(&parent_hash) {
-                bail!(EthcoreErrorKind::Block(BlockError::UnknownParent(
-                    parent_hash
-                )));
-            }
-        }
+        self.check_ancient_order(hash, *unverified.parent_hash())?;
+        self.enqueue_ancient(hash, AncientImport::Block(unverified, receipts_bytes))
+    }
 
-        // we queue blocks here and trigger an Executer.
-        {
-            let mut queued = self.queued_ancient_blocks.write();
-            queued.insert(hash);
-        }
+    fn queue_ancient_header(&self, header: Header) -> EthcoreResult<H256> {
+        trace_time!("queue_ancient_header");
 
-        // see content of executer in Client::new()
-        match self.queued_ancient_blocks_executer.lock().as_ref() {
-            Some(queue) => {
-                if !queue.enqueue((unverified, receipts_bytes)) {
-                    bail!(EthcoreErrorKind::Queue(QueueErrorKind::Full(
-                        ANCIENT_BLOCKS_QUEUE_SIZE
-                    )));
-                }
+        let hash = header.hash();
+        self.check_ancient_order(hash, *header.parent_hash())?;
+        self.enqueue_ancient(hash, AncientImport::Header(header))
+    }
+
+    fn queue_ancient_body(
+        &self,
+        unverified: Unverified,
+        receipts_bytes: Bytes,
+    ) -> EthcoreResult<H256> {
+        trace_time!("queue_ancient_body");
+
+        let hash = unverified.hash();
+        {
+            let chain = self.chain.read();
+            if chain.block_hash(unverified.header.number()) != Some(hash) {
+                // the header was not imported by us, the peer is on another chain
+                bail!(EthcoreErrorKind::Import(ImportErrorKind::KnownBad));
+            }
+            if chain.block_body(&hash).is_some() {
+                bail!(EthcoreErrorKind::Import(ImportErrorKind::AlreadyInChain));
             }
-            None => (),
         }
-        Ok(hash)
+        self.enqueue_ancient(hash, AncientImport::Body(unverified, receipts_bytes))
     }
 
     fn ancient_block_queue_fullness(&self) -> f32 {
@@ -2950,7 +3484,7 @@ impl ReopenBlock for Client {
                         .block_header_data(&h)
                         .expect("find_uncle_hashes only returns hashes for existing headers; qed");
                     let uncle = uncle
-                        .decode(self.engine.params().eip1559_transition)
+                        .decode(self.engine.params().header_transitions())
                         .expect("decoding failure");
                     block.push_uncle(uncle).expect(
                         "pushing up to maximum_uncle_count;
@@ -2995,6 +3529,12 @@ impl PrepareOpenBlock for Client {
             chain.ancestry_with_metadata_iter(best_header.hash()),
         )?;
 
+        // Locally authored blocks have no consensus layer to take the parent beacon block root
+        // from, so they commit to the zero root, as the genesis does.
+        if open_block.header.number() >= engine.params().eip4844_transition {
+            open_block.set_parent_beacon_block_root(H256::zero())?;
+        }
+
         // Add uncles
         chain
             .find_uncle_headers(&h, MAX_UNCLE_AGE)
@@ -3004,7 +3544,7 @@ impl PrepareOpenBlock for Client {
             .foreach(|h| {
                 open_block
                     .push_uncle(
-                        h.decode(engine.params().eip1559_transition)
+                        h.decode(engine.params().header_transitions())
                             .expect("decoding failure"),
                     )
                     .expect(
@@ -3042,7 +3582,7 @@ impl ImportSealedBlock for Client {
                 self.importer.bad_blocks.report(
                     block.rlp_bytes(),
                     format!("Detected an issue with locally sealed block: {}", e),
-                    self.engine.params().eip1559_transition,
+                    self.engine.params().header_transitions(),
                 );
                 return Err(e.into());
             }
@@ -3273,7 +3813,7 @@ impl ImportExportBlocks for Client {
         };
 
         let do_import = |bytes: Vec<u8>| {
-            let block = Unverified::from_rlp(bytes, self.engine.params().eip1559_transition)
+            let block = Unverified::from_rlp(bytes, self.engine.params().header_transitions())
                 .map_err(|_| "Invalid block rlp")?;
             let number = block.header.number();
             while self.queue_info().is_full() {
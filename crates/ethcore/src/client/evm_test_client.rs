@@ -109,6 +109,8 @@ impl<'a> EvmTestClient<'a> {
             ForkSpec::Berlin => Some(ethereum::new_berlin_test()),
             ForkSpec::London => Some(ethereum::new_london_test()),
             ForkSpec::BerlinToLondonAt5 => Some(ethereum::new_berlin_to_london_test()),
+            ForkSpec::Shanghai => Some(ethereum::new_shanghai_test()),
+            ForkSpec::Cancun => Some(ethereum::new_cancun_test()),
             ForkSpec::FrontierToHomesteadAt5
             | ForkSpec::HomesteadToDaoAt5
             | ForkSpec::HomesteadToEIP150At5
@@ -251,6 +253,7 @@ impl<'a> EvmTestClient<'a> {
             gas_used: 0.into(),
             gas_limit: *genesis.gas_limit(),
             base_fee: genesis.base_fee(),
+            blob_base_fee: genesis.blob_base_fee(),
         };
         self.call_envinfo(params, tracer, vm_tracer, info)
     }
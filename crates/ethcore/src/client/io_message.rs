@@ -17,7 +17,10 @@
 use bytes::Bytes;
 use client::Client;
 use ethereum_types::H256;
-use snapshot::ManifestData;
+use snapshot::{
+    snap::{SnapData, SnapPivot},
+    ManifestData,
+};
 use std::fmt;
 
 /// Message type for external and internal events
@@ -33,6 +36,10 @@ pub enum ClientIoMessage {
     FeedStateChunk(H256, Bytes),
     /// Feed a block chunk to the snapshot service
     FeedBlockChunk(H256, Bytes),
+    /// Begin restoring the state of a pivot block from `snap/1` data
+    BeginSnapRestoration(SnapPivot),
+    /// Feed `snap/1` state data to the snapshot service
+    FeedSnapData(SnapData),
     /// Take a snapshot for the block with given number.
     TakeSnapshot(u64),
     /// Execute wrapped closure
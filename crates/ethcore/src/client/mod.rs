@@ -18,11 +18,13 @@
 
 mod ancient_import;
 mod bad_blocks;
+mod call_overrides;
 mod client;
 mod config;
 #[cfg(any(test, feature = "test-helpers"))]
 mod evm_test_client;
 mod io_message;
+mod simulation;
 #[cfg(any(test, feature = "test-helpers"))]
 pub mod test_client;
 mod trace;
@@ -32,10 +34,12 @@ pub use self::evm_test_client::{EvmTestClient, EvmTestError, TransactErr, Transa
 #[cfg(any(test, feature = "test-helpers"))]
 pub use self::test_client::{EachBlockWith, TestBlockChainClient};
 pub use self::{
+    call_overrides::{AccountOverride, BlockOverride, CallOverrides, StateOverride},
     chain_notify::{ChainMessageType, ChainNotify, ChainRoute, ChainRouteType, NewBlocks},
     client::*,
     config::{BlockChainConfig, ClientConfig, DatabaseCompactionProfile, Mode, VMType},
     io_message::ClientIoMessage,
+    simulation::{SimulatedBlock, SimulationBlock, SIMULATED_BLOCK_INTERVAL},
     traits::{
         AccountData, BadBlocks, Balance, BlockChain, BlockChainClient, BlockChainReset, BlockInfo,
         BlockProducer, BroadcastProposalBlock, Call, ChainInfo, EngineClient, EngineInfo,
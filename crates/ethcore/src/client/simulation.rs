@@ -0,0 +1,41 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Multi-block simulation of virtual calls.
+
+use client::{CallOverrides, Executed};
+use types::{header::Header, transaction::SignedTransaction};
+
+/// Seconds between simulated blocks when the timestamp is not overridden.
+pub const SIMULATED_BLOCK_INTERVAL: u64 = 12;
+
+/// Calls to execute in a single simulated block.
+#[derive(Debug, Default, Clone)]
+pub struct SimulationBlock {
+    /// State and block environment overrides, applied before the first call of the block.
+    pub overrides: CallOverrides,
+    /// Calls to execute in order.
+    pub transactions: Vec<SignedTransaction>,
+}
+
+/// Result of a simulated block.
+#[derive(Debug, Clone)]
+pub struct SimulatedBlock {
+    /// Synthetic header of the block.
+    pub header: Header,
+    /// Results of the calls, in order.
+    pub results: Vec<Executed>,
+}
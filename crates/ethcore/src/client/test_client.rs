@@ -17,7 +17,7 @@
 //! Test client.
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, VecDeque},
     str::FromStr,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrder},
@@ -42,7 +42,7 @@ use types::{
     basic_account::BasicAccount,
     encoded,
     filter::Filter,
-    header::Header,
+    header::{Header, HeaderTransitions},
     log_entry::LocalizedLogEntry,
     pruning_info::PruningInfo,
     receipt::{LegacyReceipt, LocalizedReceipt, TransactionOutcome, TypedReceipt},
@@ -61,10 +61,11 @@ use call_contract::{CallContract, RegistryInfo};
 use client::{
     traits::{ForceUpdateSealing, TransactionRequest},
     AccountData, BadBlocks, Balance, BlockChain, BlockChainClient, BlockChainInfo, BlockId,
-    BlockInfo, BlockProducer, BlockStatus, BroadcastProposalBlock, Call, CallAnalytics, ChainInfo,
-    EngineInfo, ImportBlock, ImportSealedBlock, IoClient, LastHashes, Mode, Nonce,
-    PrepareOpenBlock, ProvingBlockChainClient, ReopenBlock, ScheduleInfo, SealedBlockImporter,
-    StateClient, StateOrBlock, TraceFilter, TraceId, TransactionId, TransactionInfo, UncleId,
+    BlockInfo, BlockProducer, BlockStatus, BroadcastProposalBlock, Call, CallAnalytics,
+    CallOverrides, ChainInfo, EngineInfo, ImportBlock, ImportSealedBlock, IoClient, LastHashes,
+    Mode, Nonce, PrepareOpenBlock, ProvingBlockChainClient, ReopenBlock, ScheduleInfo,
+    SealedBlockImporter, SimulatedBlock, SimulationBlock, StateClient, StateOrBlock, TraceFilter,
+    TraceId, TransactionId, TransactionInfo, UncleId,
 };
 use engines::EthEngine;
 use error::{Error, EthcoreResult};
@@ -72,11 +73,12 @@ use executed::CallError;
 use executive::Executed;
 use journaldb;
 use miner::{self, Miner, MinerService};
+use snapshot::snap::{AccountRange, StorageRanges};
 use spec::Spec;
 use state::StateInfo;
 use state_db::StateDB;
 use stats::{PrometheusMetrics, PrometheusRegistry};
-use trace::LocalizedTrace;
+use trace::{GethTrace, GethTracer, LocalizedTrace};
 use verification::queue::{kind::blocks::Unverified, QueueInfo};
 
 /// Test client.
@@ -103,6 +105,8 @@ pub struct TestBlockChainClient {
     pub code: RwLock<HashMap<Address, Bytes>>,
     /// Execution result.
     pub execution_result: RwLock<Option<Result<Executed, CallError>>>,
+    /// Execution results returned by consecutive calls in a cycle, take precedence over `execution_result`.
+    pub execution_results: RwLock<VecDeque<Result<Executed, CallError>>>,
     /// Transaction receipts.
     pub receipts: RwLock<HashMap<TransactionId, LocalizedReceipt>>,
     /// Logs
@@ -123,6 +127,10 @@ pub struct TestBlockChainClient {
     pub first_block: RwLock<Option<(H256, u64)>>,
     /// Traces to return
     pub traces: RwLock<Option<Vec<LocalizedTrace>>>,
+    /// Geth-style trace returned by the `debug_trace_*` methods for every transaction.
+    pub geth_trace: RwLock<Option<Result<GethTrace, CallError>>>,
+    /// Tracer of the last `debug_trace_*` call.
+    pub geth_tracer: RwLock<Option<GethTracer>>,
     /// Pruning history size to report.
     pub history: RwLock<Option<u64>>,
     /// Is disabled
@@ -186,6 +194,7 @@ impl TestBlockChainClient {
             storage: RwLock::new(HashMap::new()),
             code: RwLock::new(HashMap::new()),
             execution_result: RwLock::new(None),
+            execution_results: RwLock::new(VecDeque::new()),
             receipts: RwLock::new(HashMap::new()),
             logs: RwLock::new(Vec::new()),
             queue_size: AtomicUsize::new(0),
@@ -195,6 +204,8 @@ impl TestBlockChainClient {
             ancient_block: RwLock::new(None),
             first_block: RwLock::new(None),
             traces: RwLock::new(None),
+            geth_trace: RwLock::new(None),
+            geth_tracer: RwLock::new(None),
             history: RwLock::new(None),
             disabled: AtomicBool::new(false),
             error_on_logs: RwLock::new(None),
@@ -219,6 +230,21 @@ impl TestBlockChainClient {
         *self.execution_result.write() = Some(result);
     }
 
+    /// Set execution results returned by consecutive calls, repeating from the first one.
+    pub fn set_execution_results(&self, results: Vec<Result<Executed, CallError>>) {
+        *self.execution_results.write() = results.into();
+    }
+
+    /// Set the geth-style trace returned by the `debug_trace_*` methods.
+    pub fn set_geth_trace(&self, result: Result<GethTrace, CallError>) {
+        *self.geth_trace.write() = Some(result);
+    }
+
+    fn traced(&self, tracer: &GethTracer) -> Result<GethTrace, CallError> {
+        *self.geth_tracer.write() = Some(tracer.clone());
+        self.geth_trace.read().clone().unwrap()
+    }
+
     /// Set the balance of account `address` to `balance`.
     pub fn set_balance(&self, address: Address, balance: U256) {
         self.balances.write().insert(address, balance);
@@ -325,7 +351,7 @@ impl TestBlockChainClient {
         rlp.append(&header);
         rlp.append_raw(&txs, 1);
         rlp.append_raw(uncles.as_raw(), 1);
-        let unverified = Unverified::from_rlp(rlp.out(), BlockNumber::max_value()).unwrap();
+        let unverified = Unverified::from_rlp(rlp.out(), HeaderTransitions::default()).unwrap();
         self.import_block(unverified).unwrap();
     }
 
@@ -342,7 +368,7 @@ impl TestBlockChainClient {
         let mut header: Header = self
             .block_header(BlockId::Number(n))
             .unwrap()
-            .decode(BlockNumber::max_value())
+            .decode(HeaderTransitions::default())
             .expect("decoding failed");
         header.set_parent_hash(H256::from_low_u64_be(42));
         let mut rlp = RlpStream::new_list(3);
@@ -374,6 +400,15 @@ impl TestBlockChainClient {
 
     /// Inserts a transaction with given gas price to miners transactions queue.
     pub fn insert_transaction_with_gas_price_to_queue(&self, gas_price: U256) -> H256 {
+        self.insert_transaction(gas_price, false)
+    }
+
+    /// Inserts a transaction to miners transactions queue as if it was submitted to this node.
+    pub fn insert_local_transaction_to_queue(&self) -> H256 {
+        self.insert_transaction(U256::from(20_000_000_000u64), true)
+    }
+
+    fn insert_transaction(&self, gas_price: U256, local: bool) -> H256 {
         let keypair = Random.generate();
         let tx = TypedTransaction::Legacy(Transaction {
             action: Action::Create,
@@ -386,10 +421,14 @@ impl TestBlockChainClient {
         let signed_tx = tx.sign(keypair.secret(), None);
         self.set_balance(signed_tx.sender(), 10_000_000_000_000_000_000u64.into());
         let hash = signed_tx.hash();
-        let res = self
-            .miner
-            .import_external_transactions(self, vec![signed_tx.into()]);
-        let res = res.into_iter().next().unwrap();
+        let res = if local {
+            self.miner.import_own_transaction(self, signed_tx.into())
+        } else {
+            let res = self
+                .miner
+                .import_external_transactions(self, vec![signed_tx.into()]);
+            res.into_iter().next().unwrap()
+        };
         assert!(res.is_ok());
 
         // if new_transaction_hashes producer channel exists, send the transaction hash
@@ -568,7 +607,7 @@ impl BlockInfo for TestBlockChainClient {
     fn best_block_header(&self) -> Header {
         self.block_header(BlockId::Hash(self.chain_info().best_block_hash))
             .expect("Best block always has header.")
-            .decode(BlockNumber::max_value())
+            .decode(HeaderTransitions::default())
             .expect("decoding failed")
     }
 
@@ -626,7 +665,7 @@ impl ImportBlock for TestBlockChainClient {
         if number > 0 {
             match self.blocks.read().get(header.parent_hash()) {
                 Some(parent) => {
-                    let parent = view!(BlockView, parent).header(BlockNumber::max_value());
+                    let parent = view!(BlockView, parent).header(HeaderTransitions::default());
                     if parent.number() != (header.number() - 1) {
                         panic!("Unexpected block parent");
                     }
@@ -656,7 +695,7 @@ impl ImportBlock for TestBlockChainClient {
                     *self.numbers.write().get_mut(&n).unwrap() = parent_hash.clone();
                     n -= 1;
                     parent_hash = view!(BlockView, &self.blocks.read()[&parent_hash])
-                        .header(BlockNumber::max_value())
+                        .header(HeaderTransitions::default())
                         .parent_hash()
                         .clone();
                 }
@@ -679,9 +718,25 @@ impl Call for TestBlockChainClient {
         _state: &mut Self::State,
         _header: &Header,
     ) -> Result<Executed, CallError> {
+        let mut results = self.execution_results.write();
+        if let Some(result) = results.pop_front() {
+            results.push_back(result.clone());
+            return result;
+        }
         self.execution_result.read().clone().unwrap()
     }
 
+    fn call_with_overrides(
+        &self,
+        t: &SignedTransaction,
+        analytics: CallAnalytics,
+        _overrides: &CallOverrides,
+        state: &mut Self::State,
+        header: &Header,
+    ) -> Result<Executed, CallError> {
+        self.call(t, analytics, state, header)
+    }
+
     fn call_many(
         &self,
         txs: &[(SignedTransaction, CallAnalytics)],
@@ -695,6 +750,28 @@ impl Call for TestBlockChainClient {
         Ok(res)
     }
 
+    fn simulate(
+        &self,
+        blocks: &[SimulationBlock],
+        state: &mut Self::State,
+        header: &Header,
+    ) -> Result<Vec<SimulatedBlock>, CallError> {
+        let mut simulated = Vec::with_capacity(blocks.len());
+        for (i, block) in blocks.iter().enumerate() {
+            let mut results = Vec::with_capacity(block.transactions.len());
+            for tx in &block.transactions {
+                results.push(self.call(tx, Default::default(), state, header)?);
+            }
+            let mut simulated_header = Header::new();
+            simulated_header.set_number(header.number() + i as u64 + 1);
+            simulated.push(SimulatedBlock {
+                header: simulated_header,
+                results,
+            });
+        }
+        Ok(simulated)
+    }
+
     fn estimate_gas(
         &self,
         _t: &SignedTransaction,
@@ -703,6 +780,16 @@ impl Call for TestBlockChainClient {
     ) -> Result<U256, CallError> {
         Ok(21000.into())
     }
+
+    fn debug_trace_call(
+        &self,
+        _t: &SignedTransaction,
+        tracer: &GethTracer,
+        _state: &mut Self::State,
+        _header: &Header,
+    ) -> Result<GethTrace, CallError> {
+        self.traced(tracer)
+    }
 }
 
 /// NewType wrapper around `()` to impersonate `State` in trait impls. State will not be used by
@@ -749,6 +836,7 @@ impl BadBlocks for TestBlockChainClient {
                 header: Default::default(),
                 transactions: vec![],
                 uncles: vec![],
+                withdrawals: None,
                 bytes: vec![1, 2, 3],
             },
             "Invalid block".into(),
@@ -777,6 +865,28 @@ impl BlockChainClient for TestBlockChainClient {
         ))
     }
 
+    fn debug_trace_transaction(
+        &self,
+        _id: TransactionId,
+        tracer: &GethTracer,
+    ) -> Result<GethTrace, CallError> {
+        self.traced(tracer)
+    }
+
+    fn debug_trace_block(
+        &self,
+        block: BlockId,
+        tracer: &GethTracer,
+    ) -> Result<Vec<(H256, GethTrace)>, CallError> {
+        let body = self.block_body(block).ok_or(CallError::StatePruned)?;
+        let trace = self.traced(tracer)?;
+        Ok(body
+            .transaction_hashes()
+            .into_iter()
+            .map(|hash| (hash, trace.clone()))
+            .collect())
+    }
+
     fn block_total_difficulty(&self, _id: BlockId) -> Option<U256> {
         Some(U256::zero())
     }
@@ -900,7 +1010,7 @@ impl BlockChainClient for TestBlockChainClient {
 
     fn block_extra_info(&self, id: BlockId) -> Option<BTreeMap<String, String>> {
         self.block(id)
-            .map(|block| block.view().header(BlockNumber::max_value()))
+            .map(|block| block.view().header(HeaderTransitions::default()))
             .map(|header| self.spec.engine.extra_info(&header))
     }
 
@@ -1120,6 +1230,40 @@ impl BlockChainClient for TestBlockChainClient {
         None
     }
 
+    fn snap_account_range(
+        &self,
+        _root: &H256,
+        _origin: &H256,
+        _limit: &H256,
+        _max_bytes: usize,
+    ) -> Option<AccountRange> {
+        None
+    }
+
+    fn snap_storage_ranges(
+        &self,
+        _root: &H256,
+        _accounts: &[H256],
+        _origin: &H256,
+        _limit: &H256,
+        _max_bytes: usize,
+    ) -> Option<StorageRanges> {
+        None
+    }
+
+    fn snap_bytecodes(&self, _hashes: &[H256], _max_bytes: usize) -> Vec<Bytes> {
+        Vec::new()
+    }
+
+    fn snap_trie_nodes(
+        &self,
+        _root: &H256,
+        _path_sets: &[Vec<Bytes>],
+        _max_bytes: usize,
+    ) -> Option<Vec<Bytes>> {
+        None
+    }
+
     fn transaction(&self, tx_hash: &H256) -> Option<Arc<VerifiedTransaction>> {
         self.miner.transaction(tx_hash)
     }
@@ -1143,6 +1287,20 @@ impl IoClient for TestBlockChainClient {
         self.import_block(unverified)
     }
 
+    fn queue_ancient_header(&self, header: Header) -> EthcoreResult<H256> {
+        // stored with an empty body until the body is queued
+        let mut rlp = RlpStream::new_list(3);
+        rlp.append(&header);
+        rlp.append_raw(&::rlp::EMPTY_LIST_RLP, 1);
+        rlp.append_raw(&::rlp::EMPTY_LIST_RLP, 1);
+        let unverified = Unverified::from_rlp(rlp.out(), HeaderTransitions::default()).unwrap();
+        self.import_block(unverified)
+    }
+
+    fn queue_ancient_body(&self, unverified: Unverified, _r: Bytes) -> EthcoreResult<H256> {
+        self.import_block(unverified)
+    }
+
     fn queue_consensus_message(&self, message: Bytes) {
         self.spec.engine.handle_message(&message).unwrap();
     }
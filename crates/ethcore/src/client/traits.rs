@@ -34,7 +34,7 @@ use types::{
     data_format::DataFormat,
     encoded,
     filter::Filter,
-    header::Header,
+    header::{Header, HeaderTransitions},
     ids::*,
     log_entry::LocalizedLogEntry,
     pruning_info::PruningInfo,
@@ -46,13 +46,14 @@ use types::{
 use vm::LastHashes;
 
 use block::{ClosedBlock, OpenBlock, SealedBlock};
-use client::Mode;
+use client::{CallOverrides, Mode, SimulatedBlock, SimulationBlock};
 use engines::EthEngine;
 use error::{Error, EthcoreResult};
 use executed::CallError;
 use executive::Executed;
+use snapshot::snap::{AccountRange, StorageRanges};
 use state::StateInfo;
-use trace::LocalizedTrace;
+use trace::{GethTrace, GethTracer, LocalizedTrace};
 use verification::queue::{kind::blocks::Unverified, QueueInfo as BlockQueueInfo};
 
 /// State information to be used during client query
@@ -184,6 +185,17 @@ pub trait Call {
         header: &Header,
     ) -> Result<Executed, CallError>;
 
+    /// Makes a non-persistent transaction call with the given accounts and block environment
+    /// replaced.
+    fn call_with_overrides(
+        &self,
+        tx: &SignedTransaction,
+        analytics: CallAnalytics,
+        overrides: &CallOverrides,
+        state: &mut Self::State,
+        header: &Header,
+    ) -> Result<Executed, CallError>;
+
     /// Makes multiple non-persistent but dependent transaction calls.
     /// Returns a vector of successes or a failure if any of the transaction fails.
     fn call_many(
@@ -193,6 +205,17 @@ pub trait Call {
         header: &Header,
     ) -> Result<Vec<Executed>, CallError>;
 
+    /// Simulates consecutive blocks of non-persistent calls on top of the given state and header.
+    /// Each block sees the state changes of the previous ones. Unless overridden, the gas limit
+    /// is taken from the parent block and the base fee and blob base fee are derived from it;
+    /// calls get at most the gas left in their block.
+    fn simulate(
+        &self,
+        blocks: &[SimulationBlock],
+        state: &mut Self::State,
+        header: &Header,
+    ) -> Result<Vec<SimulatedBlock>, CallError>;
+
     /// Estimates how much gas will be necessary for a call.
     fn estimate_gas(
         &self,
@@ -200,6 +223,15 @@ pub trait Call {
         state: &Self::State,
         header: &Header,
     ) -> Result<U256, CallError>;
+
+    /// Makes a non-persistent transaction call and traces it with a geth-style tracer.
+    fn debug_trace_call(
+        &self,
+        tx: &SignedTransaction,
+        tracer: &GethTracer,
+        state: &mut Self::State,
+        header: &Header,
+    ) -> Result<GethTrace, CallError>;
 }
 
 /// Provides `engine` method
@@ -220,6 +252,17 @@ pub trait IoClient: Sync + Send {
         receipts_bytes: Bytes,
     ) -> EthcoreResult<H256>;
 
+    /// Queue an ancient header without its body. The body and receipts may be queued
+    /// later with `queue_ancient_body`.
+    fn queue_ancient_header(&self, header: Header) -> EthcoreResult<H256>;
+
+    /// Queue body and receipts of an ancient block whose header is already in the chain.
+    fn queue_ancient_body(
+        &self,
+        block_bytes: Unverified,
+        receipts_bytes: Bytes,
+    ) -> EthcoreResult<H256>;
+
     /// Return percentage of how full is queue that handles ancient blocks. 0 if empty, 1 if full.
     fn ancient_block_queue_fullness(&self) -> f32;
 
@@ -336,6 +379,36 @@ pub trait BlockChainClient:
     /// Get latest state node
     fn state_data(&self, hash: &H256) -> Option<Bytes>;
 
+    /// Get a range of accounts of the state with the given root, for `snap/1`.
+    fn snap_account_range(
+        &self,
+        root: &H256,
+        origin: &H256,
+        limit: &H256,
+        max_bytes: usize,
+    ) -> Option<AccountRange>;
+
+    /// Get ranges of storage slots of accounts of the state with the given root, for `snap/1`.
+    fn snap_storage_ranges(
+        &self,
+        root: &H256,
+        accounts: &[H256],
+        origin: &H256,
+        limit: &H256,
+        max_bytes: usize,
+    ) -> Option<StorageRanges>;
+
+    /// Get contract code by hash, for `snap/1`.
+    fn snap_bytecodes(&self, hashes: &[H256], max_bytes: usize) -> Vec<Bytes>;
+
+    /// Get trie nodes of the state with the given root by path, for `snap/1`.
+    fn snap_trie_nodes(
+        &self,
+        root: &H256,
+        path_sets: &[Vec<Bytes>],
+        max_bytes: usize,
+    ) -> Option<Vec<Bytes>>;
+
     /// Get block receipts data by block header hash.
     fn block_receipts(&self, hash: &H256) -> Option<BlockReceipts>;
 
@@ -366,6 +439,20 @@ pub trait BlockChainClient:
         analytics: CallAnalytics,
     ) -> Result<Box<dyn Iterator<Item = (H256, Executed)>>, CallError>;
 
+    /// Replays a given transaction with a geth-style tracer.
+    fn debug_trace_transaction(
+        &self,
+        t: TransactionId,
+        tracer: &GethTracer,
+    ) -> Result<GethTrace, CallError>;
+
+    /// Replays all the transactions in a given block with a geth-style tracer.
+    fn debug_trace_block(
+        &self,
+        block: BlockId,
+        tracer: &GethTracer,
+    ) -> Result<Vec<(H256, GethTrace)>, CallError>;
+
     /// Returns traces matching given filter.
     fn filter_traces(&self, filter: TraceFilter) -> Option<Vec<LocalizedTrace>>;
 
@@ -406,7 +493,9 @@ pub trait BlockChainClient:
                         match t.transaction_type() {
                             TypedTxId::Legacy => None,
                             TypedTxId::AccessList => None,
-                            TypedTxId::EIP1559Transaction => Some(block.header().base_fee()),
+                            TypedTxId::EIP1559Transaction | TypedTxId::BlobTransaction => {
+                                Some(block.header().base_fee())
+                            }
                         }
                     }))
                 });
@@ -420,7 +509,7 @@ pub trait BlockChainClient:
     fn priority_gas_price_corpus(
         &self,
         sample_size: usize,
-        eip1559_transition: BlockNumber,
+        transitions: HeaderTransitions,
     ) -> ::stats::Corpus<U256> {
         let mut h = self.chain_info().best_block_hash;
         let mut corpus = Vec::new();
@@ -431,7 +520,7 @@ pub trait BlockChainClient:
                     None => return corpus.into(),
                 };
 
-                if block.number() == 0 || block.number() < eip1559_transition {
+                if block.number() == 0 || block.number() < transitions.eip1559_transition {
                     return corpus.into();
                 }
                 block
@@ -442,7 +531,9 @@ pub trait BlockChainClient:
                     )
                     .foreach(|t| {
                         // As block.number() >= eip_1559_transition, the base_fee should exist
-                        corpus.push(t.effective_priority_gas_price(Some(block.header().base_fee())))
+                        corpus.push(t.effective_priority_gas_price(Some(
+                            block.header().base_fee(transitions),
+                        )))
                     });
                 h = block.parent_hash().clone();
             }
@@ -78,7 +78,7 @@ use rlp::{encode, Decodable, DecoderError, Encodable, Rlp, RlpStream};
 use time_utils::CheckedSystemTime;
 use types::{
     ancestry_action::AncestryAction,
-    header::{ExtendedHeader, Header},
+    header::{ExtendedHeader, Header, HeaderTransitions},
     ids::BlockId,
     transaction::SignedTransaction,
     BlockNumber,
@@ -693,7 +693,7 @@ struct EpochVerifier {
     empty_steps_transition: u64,
     /// First block for which a 2/3 quorum (instead of 1/2) is required.
     two_thirds_majority_transition: BlockNumber,
-    eip1559_transition: BlockNumber,
+    header_transitions: HeaderTransitions,
 }
 
 impl super::EpochVerifier<EthereumMachine> for EpochVerifier {
@@ -720,7 +720,7 @@ impl super::EpochVerifier<EthereumMachine> for EpochVerifier {
 
         let proof_rlp = Rlp::new(proof);
         let headers: Vec<Header> =
-            Header::decode_rlp_list(&proof_rlp, self.eip1559_transition).ok()?;
+            Header::decode_rlp_list(&proof_rlp, self.header_transitions).ok()?;
 
         {
             let mut push_header = |parent_header: &Header, header: Option<&Header>| {
@@ -1846,7 +1846,7 @@ impl Engine<EthereumMachine> for AuthorityRound {
                 let parent = client
                     .block_header(::client::BlockId::Hash(*block.header.parent_hash()))
                     .expect("hash is from parent; parent header must exist; qed")
-                    .decode(self.params().eip1559_transition)?;
+                    .decode(self.params().header_transitions())?;
 
                 let parent_step = header_step(&parent, self.empty_steps_transition)?;
                 let current_step = self.step.inner.load();
@@ -2241,7 +2241,7 @@ impl Engine<EthereumMachine> for AuthorityRound {
                     subchain_validators: list,
                     empty_steps_transition: self.empty_steps_transition,
                     two_thirds_majority_transition: self.two_thirds_majority_transition,
-                    eip1559_transition: self.params().eip1559_transition,
+                    header_transitions: self.params().header_transitions(),
                 });
 
                 match finalize {
@@ -289,7 +289,7 @@ mod tests {
                 .import_block(
                     Unverified::from_rlp(
                         client.block(BlockId::Number(i)).unwrap().into_inner(),
-                        client.engine().params().eip1559_transition,
+                        client.engine().params().header_transitions(),
                     )
                     .unwrap(),
                 )
@@ -30,8 +30,11 @@ use memory_cache::MemoryLruCache;
 use parking_lot::{Mutex, RwLock};
 use rlp::{Rlp, RlpStream};
 use types::{
-    header::Header, ids::BlockId, log_entry::LogEntry, receipt::TypedReceipt, transaction,
-    BlockNumber,
+    header::{Header, HeaderTransitions},
+    ids::BlockId,
+    log_entry::LogEntry,
+    receipt::TypedReceipt,
+    transaction, BlockNumber,
 };
 use unexpected::Mismatch;
 
@@ -71,7 +74,7 @@ impl ::engines::StateDependentProof<EthereumMachine> for StateProof {
 
     fn check_proof(&self, machine: &EthereumMachine, proof: &[u8]) -> Result<(), String> {
         let (header, state_items) =
-            decode_first_proof(&Rlp::new(proof), machine.params().eip1559_transition)
+            decode_first_proof(&Rlp::new(proof), machine.params().header_transitions())
                 .map_err(|e| format!("proof incorrectly encoded: {}", e))?;
         if &header != &self.header {
             return Err("wrong header in proof".into());
@@ -131,6 +134,7 @@ fn check_first_proof(
         },
         gas_used: 0.into(),
         base_fee: old_header.base_fee(),
+        blob_base_fee: old_header.blob_base_fee(),
     };
 
     // check state proof using given machine.
@@ -167,9 +171,9 @@ fn check_first_proof(
 
 fn decode_first_proof(
     rlp: &Rlp,
-    eip1559_transition: BlockNumber,
+    transitions: HeaderTransitions,
 ) -> Result<(Header, Vec<DBValue>), ::error::Error> {
-    let header = Header::decode_rlp(&rlp.at(0)?, eip1559_transition)?;
+    let header = Header::decode_rlp(&rlp.at(0)?, transitions)?;
     let state_items = rlp
         .at(1)?
         .iter()
@@ -195,10 +199,10 @@ fn encode_proof(header: &Header, receipts: &[TypedReceipt]) -> Bytes {
 
 fn decode_proof(
     rlp: &Rlp,
-    eip1559_transition: BlockNumber,
+    transitions: HeaderTransitions,
 ) -> Result<(Header, Vec<TypedReceipt>), ::error::Error> {
     Ok((
-        Header::decode_rlp(&rlp.at(0)?, eip1559_transition)?,
+        Header::decode_rlp(&rlp.at(0)?, transitions)?,
         TypedReceipt::decode_rlp_list(&rlp.at(1)?)?,
     ))
 }
@@ -566,7 +570,7 @@ impl ValidatorSet for ValidatorSafeContract {
             trace!(target: "engine", "Recovering initial epoch set");
 
             let (old_header, state_items) =
-                decode_first_proof(&rlp, machine.params().eip1559_transition)?;
+                decode_first_proof(&rlp, machine.params().header_transitions())?;
             let number = old_header.number();
             let old_hash = old_header.hash();
             let addresses =
@@ -578,7 +582,7 @@ impl ValidatorSet for ValidatorSafeContract {
 
             Ok((SimpleList::new(addresses), Some(old_hash)))
         } else {
-            let (old_header, receipts) = decode_proof(&rlp, machine.params().eip1559_transition)?;
+            let (old_header, receipts) = decode_proof(&rlp, machine.params().header_transitions())?;
 
             // ensure receipts match header.
             // TODO: optimize? these were just decoded.
@@ -844,7 +848,7 @@ mod tests {
                 .import_block(
                     Unverified::from_rlp(
                         client.block(BlockId::Number(i)).unwrap().into_inner(),
-                        client.engine().params().eip1559_transition,
+                        client.engine().params().header_transitions(),
                     )
                     .unwrap(),
                 )
@@ -68,6 +68,18 @@ pub enum BlockError {
     InvalidGasUsed(Mismatch<U256>),
     /// Transactions root header field is invalid.
     InvalidTransactionsRoot(Mismatch<H256>),
+    /// Withdrawals root header field is invalid.
+    InvalidWithdrawalsRoot(Mismatch<H256>),
+    /// Withdrawals are present before EIP-4895 or missing after it.
+    InvalidWithdrawalsPresence(Mismatch<bool>),
+    /// Blob gas header fields are present before EIP-4844 or missing after it.
+    InvalidBlobGasFieldsPresence(Mismatch<bool>),
+    /// Blob gas used is above the limit.
+    TooMuchBlobGasUsed(OutOfBounds<u64>),
+    /// Blob gas used header field is invalid.
+    InvalidBlobGasUsed(Mismatch<u64>),
+    /// Excess blob gas header field is invalid.
+    IncorrectExcessBlobGas(Mismatch<u64>),
     /// Difficulty is out of range; this can be used as an looser error prior to getting a definitive
     /// value for difficulty. This error needs only provide bounds of which it is out.
     DifficultyOutOfBounds(OutOfBounds<U256>),
@@ -133,6 +145,24 @@ impl fmt::Display for BlockError {
             InvalidTransactionsRoot(ref mis) => {
                 format!("Invalid transactions root in header: {}", mis)
             }
+            InvalidWithdrawalsRoot(ref mis) => {
+                format!("Invalid withdrawals root in header: {}", mis)
+            }
+            InvalidWithdrawalsPresence(ref mis) => {
+                format!(
+                    "Withdrawals presence does not match EIP-4895 rules: {}",
+                    mis
+                )
+            }
+            InvalidBlobGasFieldsPresence(ref mis) => {
+                format!(
+                    "Blob gas fields presence does not match EIP-4844 rules: {}",
+                    mis
+                )
+            }
+            TooMuchBlobGasUsed(ref oob) => format!("Block has too much blob gas used: {}", oob),
+            InvalidBlobGasUsed(ref mis) => format!("Invalid blob gas used in header: {}", mis),
+            IncorrectExcessBlobGas(ref mis) => format!("Incorrect excess blob gas: {}", mis),
             DifficultyOutOfBounds(ref oob) => format!("Invalid block difficulty: {}", oob),
             InvalidDifficulty(ref mis) => format!("Invalid block difficulty: {}", mis),
             MismatchedH256SealElement(ref mis) => format!("Seal element out of bounds: {}", mis),
@@ -282,6 +282,22 @@ pub fn new_london_test() -> Spec {
     )
 }
 
+/// Create a new Foundation Shanghai era spec.
+pub fn new_shanghai_test() -> Spec {
+    load(
+        None,
+        include_bytes!("../../res/chainspec/test/shanghai_test.json"),
+    )
+}
+
+/// Create a new Foundation Cancun era spec.
+pub fn new_cancun_test() -> Spec {
+    load(
+        None,
+        include_bytes!("../../res/chainspec/test/cancun_test.json"),
+    )
+}
+
 /// Create a new BerlinToLondonAt5 era spec.
 pub fn new_berlin_to_london_test() -> Spec {
     load(
@@ -319,6 +335,18 @@ pub fn new_london_test_machine() -> EthereumMachine {
     load_machine(include_bytes!("../../res/chainspec/test/london_test.json"))
 }
 
+/// Create a new Foundation Shanghai era chain spec.
+pub fn new_shanghai_test_machine() -> EthereumMachine {
+    load_machine(include_bytes!(
+        "../../res/chainspec/test/shanghai_test.json"
+    ))
+}
+
+/// Create a new Foundation Cancun era chain spec.
+pub fn new_cancun_test_machine() -> EthereumMachine {
+    load_machine(include_bytes!("../../res/chainspec/test/cancun_test.json"))
+}
+
 /// Create a new Foundation Homestead-EIP210-era chain spec as though it never changed from Homestead/Frontier.
 pub fn new_eip210_test_machine() -> EthereumMachine {
     load_machine(include_bytes!("../../res/chainspec/test/eip210_test.json"))
@@ -20,7 +20,7 @@ use bytes::Bytes;
 use ethereum_types::{Address, U256, U512};
 use ethtrie;
 use trace::{FlatTrace, VMTrace};
-use types::{log_entry::LogEntry, state_diff::StateDiff};
+use types::{log_entry::LogEntry, state_diff::StateDiff, transaction::AccessList};
 use vm;
 
 use std::{error, fmt};
@@ -66,6 +66,8 @@ pub struct Executed<T = FlatTrace, V = VMTrace> {
     pub vm_trace: Option<V>,
     /// The state diff, if we traced it.
     pub state_diff: Option<StateDiff>,
+    /// The accessed addresses and storage keys, if we recorded them.
+    pub access_list: Option<AccessList>,
 }
 
 /// Result of executing the transaction.
@@ -91,6 +93,17 @@ pub enum ExecutionError {
         /// Amount of gas in block.
         gas: U256,
     },
+    /// Returned when block (blob_gas_used + blob_gas) > max blob gas per block.
+    ///
+    /// Upstream may try to execute the transaction in next block.
+    BlockBlobGasLimitReached {
+        /// Blob gas limit of block.
+        blob_gas_limit: u64,
+        /// Blob gas used in block prior to transaction.
+        blob_gas_used: u64,
+        /// Amount of blob gas in transaction.
+        blob_gas: u64,
+    },
     /// Transaction's max gas price is lower then block base fee.
     GasPriceLowerThanBaseFee {
         /// Max gas price of the transaction.
@@ -98,6 +111,13 @@ pub enum ExecutionError {
         /// Block base fee.
         base_fee: U256,
     },
+    /// Transaction's max fee per blob gas is lower then block blob base fee.
+    BlobGasPriceLowerThanBlobBaseFee {
+        /// Max fee per blob gas of the transaction.
+        max_fee_per_blob_gas: U256,
+        /// Block blob base fee.
+        blob_base_fee: U256,
+    },
     /// Returned when transaction nonce does not match state nonce.
     InvalidNonce {
         /// Nonce expected.
@@ -155,6 +175,15 @@ impl fmt::Display for ExecutionError {
 					already been used, and {} more is required",
                 gas_limit, gas_used, gas
             ),
+            BlockBlobGasLimitReached {
+                ref blob_gas_limit,
+                ref blob_gas_used,
+                ref blob_gas,
+            } => format!(
+                "Block blob gas limit reached. The limit is {}, {} has \
+					already been used, and {} more is required",
+                blob_gas_limit, blob_gas_used, blob_gas
+            ),
             GasPriceLowerThanBaseFee {
                 ref gas_price,
                 ref base_fee,
@@ -162,6 +191,13 @@ impl fmt::Display for ExecutionError {
                 "Max gas price is lowert than block base fee. Gas price is {}, while base fee is {}",
                 gas_price, base_fee
             ),
+            BlobGasPriceLowerThanBlobBaseFee {
+                ref max_fee_per_blob_gas,
+                ref blob_base_fee,
+            } => format!(
+                "Max fee per blob gas is lower than block blob base fee. Max fee per blob gas is {}, while blob base fee is {}",
+                max_fee_per_blob_gas, blob_base_fee
+            ),
             InvalidNonce {
                 ref expected,
                 ref got,
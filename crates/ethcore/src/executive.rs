@@ -131,6 +131,8 @@ pub struct TransactOptions<T, V> {
     pub check_nonce: bool,
     /// Records the output from init contract calls.
     pub output_from_init_contract: bool,
+    /// Records the addresses and storage keys accessed by the transaction.
+    pub record_access_list: bool,
 }
 
 impl<T, V> TransactOptions<T, V> {
@@ -141,6 +143,7 @@ impl<T, V> TransactOptions<T, V> {
             vm_tracer,
             check_nonce: true,
             output_from_init_contract: false,
+            record_access_list: false,
         }
     }
 
@@ -155,6 +158,12 @@ impl<T, V> TransactOptions<T, V> {
         self.output_from_init_contract = true;
         self
     }
+
+    /// Records the accessed addresses and storage keys.
+    pub fn record_access_list(mut self) -> Self {
+        self.record_access_list = true;
+        self
+    }
 }
 
 impl TransactOptions<trace::ExecutiveTracer, trace::ExecutiveVMTracer> {
@@ -165,6 +174,7 @@ impl TransactOptions<trace::ExecutiveTracer, trace::ExecutiveVMTracer> {
             vm_tracer: trace::ExecutiveVMTracer::toplevel(),
             check_nonce: true,
             output_from_init_contract: false,
+            record_access_list: false,
         }
     }
 }
@@ -177,6 +187,7 @@ impl TransactOptions<trace::ExecutiveTracer, trace::NoopVMTracer> {
             vm_tracer: trace::NoopVMTracer,
             check_nonce: true,
             output_from_init_contract: false,
+            record_access_list: false,
         }
     }
 }
@@ -189,6 +200,7 @@ impl TransactOptions<trace::NoopTracer, trace::ExecutiveVMTracer> {
             vm_tracer: trace::ExecutiveVMTracer::toplevel(),
             check_nonce: true,
             output_from_init_contract: false,
+            record_access_list: false,
         }
     }
 }
@@ -201,6 +213,7 @@ impl TransactOptions<trace::NoopTracer, trace::NoopVMTracer> {
             vm_tracer: trace::NoopVMTracer,
             check_nonce: true,
             output_from_init_contract: false,
+            record_access_list: false,
         }
     }
 }
@@ -237,13 +250,15 @@ pub struct CallCreateExecutive<'a> {
 impl<'a> CallCreateExecutive<'a> {
     /// Create new state with access list.
     pub fn new_substate(params: &ActionParams, schedule: &'a Schedule) -> Substate {
-        if schedule.eip2929 {
+        let mut substate = if schedule.eip2929 {
             let mut substate = Substate::from_access_list(&params.access_list);
             substate.access_list.insert_address(params.address);
             substate
         } else {
             Substate::default()
-        }
+        };
+        substate.transient_state = params.transient_state.clone();
+        substate
     }
 
     /// Create a new call executive using raw data.
@@ -455,6 +470,7 @@ impl<'a> CallCreateExecutive<'a> {
                 }
                 state.revert_to_checkpoint();
                 un_substate.access_list.rollback();
+                un_substate.transient_state.rollback();
             }
             Ok(_) | Err(vm::Error::Internal(_)) => {
                 state.discard_checkpoint();
@@ -672,6 +688,9 @@ impl<'a> CallCreateExecutive<'a> {
                         Err(err) => return Ok(Err(err)),
                     }
                 }
+                unconfirmed_substate
+                    .transient_state
+                    .insert_created(params.address);
 
                 let origin_info = OriginInfo::from(&params);
                 let exec = self.factory.create(params, self.schedule, self.depth);
@@ -914,8 +933,11 @@ impl<'a> CallCreateExecutive<'a> {
 											address
 										);
 									},
-									Ok(_) => {
-										tracer.done_trace_failed(&vm::Error::Reverted);
+									Ok(ref val) => {
+										tracer.done_trace_reverted(
+											gas - val.gas_left,
+											&val.return_data,
+										);
 									},
 									Err(ref err) => {
 										tracer.done_trace_failed(err);
@@ -946,8 +968,11 @@ impl<'a> CallCreateExecutive<'a> {
 											&val.return_data,
 										);
 									},
-									Ok(_) => {
-										tracer.done_trace_failed(&vm::Error::Reverted);
+									Ok(ref val) => {
+										tracer.done_trace_reverted(
+											gas - val.gas_left,
+											&val.return_data,
+										);
 									},
 									Err(ref err) => {
 										tracer.done_trace_failed(err);
@@ -976,7 +1001,7 @@ impl<'a> CallCreateExecutive<'a> {
 				},
 				Some((_, _, Err(TrapError::Call(subparams, resume)))) => {
 					tracer.prepare_trace_call(&subparams, resume.depth + 1, resume.machine.builtin(&subparams.address, resume.info.number).is_some());
-					vm_tracer.prepare_subtrace(subparams.code.as_ref().map_or_else(|| &[] as &[u8], |d| &*d as &[u8]));
+					vm_tracer.prepare_subtrace(&subparams);
 
 					let sub_exec = CallCreateExecutive::new_call_raw(
 						subparams,
@@ -995,7 +1020,7 @@ impl<'a> CallCreateExecutive<'a> {
 				},
 				Some((_, _, Err(TrapError::Create(subparams, address, resume)))) => {
 					tracer.prepare_trace_create(&subparams);
-					vm_tracer.prepare_subtrace(subparams.code.as_ref().map_or_else(|| &[] as &[u8], |d| &*d as &[u8]));
+					vm_tracer.prepare_subtrace(&subparams);
 
 					let sub_exec = CallCreateExecutive::new_create_raw(
 						subparams,
@@ -1078,6 +1103,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             t,
             options.check_nonce,
             options.output_from_init_contract,
+            options.record_access_list,
             options.tracer,
             options.vm_tracer,
         )
@@ -1116,6 +1142,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
         t: &SignedTransaction,
         check_nonce: bool,
         output_from_create: bool,
+        record_access_list: bool,
         mut tracer: T,
         mut vm_tracer: V,
     ) -> Result<Executed<T::Output, V::Output>, ExecutionError>
@@ -1141,9 +1168,27 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
                     ));
                 }
             }
+            TypedTransaction::BlobTransaction(_) => {
+                if !schedule.eip4844 {
+                    return Err(ExecutionError::TransactionMalformed(
+                        "Blob transactions EIP-4844 not enabled".into(),
+                    ));
+                }
+            }
             TypedTransaction::Legacy(_) => (), //legacy transactions are allways valid
         };
 
+        if schedule.eip3860
+            && t.tx().action == Action::Create
+            && t.tx().data.len() > schedule.max_initcode_size()
+        {
+            return Err(ExecutionError::TransactionMalformed(format!(
+                "Initcode size {} exceeds the limit of {} bytes",
+                t.tx().data.len(),
+                schedule.max_initcode_size()
+            )));
+        }
+
         let sender = t.sender();
         let nonce = self.state.nonce(&sender)?;
 
@@ -1153,6 +1198,9 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
 
         if schedule.eip2929 {
             access_list.insert_address(sender);
+            if schedule.eip3651 {
+                access_list.insert_address(self.info.author);
+            }
             for (address, builtin) in self.machine.builtins() {
                 if builtin.is_active(self.info.number) {
                     access_list.insert_address(*address);
@@ -1221,6 +1269,16 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             ));
         }
 
+        // ensure that the user was willing to at least pay the blob base fee
+        let blob_base_fee = self.info.blob_base_fee.unwrap_or_default();
+        let max_fee_per_blob_gas = t.max_fee_per_blob_gas().unwrap_or_default();
+        if t.blob_gas() > 0 && max_fee_per_blob_gas < blob_base_fee {
+            return Err(ExecutionError::BlobGasPriceLowerThanBlobBaseFee {
+                max_fee_per_blob_gas,
+                blob_base_fee,
+            });
+        }
+
         // TODO: we might need bigints here, or at least check overflows.
         let balance = self.state.balance(&sender)?;
         let gas_cost_effective = t
@@ -1228,7 +1286,10 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             .gas
             .full_mul(t.effective_gas_price(self.info.base_fee));
         let gas_cost_max = t.tx().gas.full_mul(t.tx().gas_price);
-        let needed_balance = U512::from(t.tx().value) + gas_cost_max;
+        // blob gas is paid upfront and burned, it is not refunded if execution fails
+        let blob_gas_cost = U256::from(t.blob_gas()).full_mul(blob_base_fee);
+        let blob_gas_cost_max = U256::from(t.blob_gas()).full_mul(max_fee_per_blob_gas);
+        let needed_balance = U512::from(t.tx().value) + gas_cost_max + blob_gas_cost_max;
 
         // avoid unaffordable transactions
         let balance512 = U512::from(balance);
@@ -1250,6 +1311,18 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             &U256::try_from(gas_cost_effective).expect("Total cost (value + gas_cost_effective) is lower than max allowed balance (U256); gas_cost has to fit U256; qed"),
             &mut substate.to_cleanup_mode(&schedule),
         )?;
+        if !blob_gas_cost.is_zero() {
+            self.state.sub_balance(
+                &sender,
+                &U256::try_from(blob_gas_cost)
+                    .expect("Blob gas cost is lower than needed balance which fits U256; qed"),
+                &mut substate.to_cleanup_mode(&schedule),
+            )?;
+        }
+
+        if record_access_list {
+            access_list.record_accessed();
+        }
 
         let (result, output) = match t.tx().action {
             Action::Create => {
@@ -1274,6 +1347,8 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
                     call_type: CallType::None,
                     params_type: vm::ParamsType::Embedded,
                     access_list: access_list,
+                    transient_state: substate.transient_state.clone(),
+                    blob_versioned_hashes: t.blob_versioned_hashes().cloned().unwrap_or_default(),
                 };
                 let res = self.create(params, &mut substate, &mut tracer, &mut vm_tracer);
                 let out = match &res {
@@ -1298,6 +1373,8 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
                     call_type: CallType::Call,
                     params_type: vm::ParamsType::Separate,
                     access_list: access_list,
+                    transient_state: substate.transient_state.clone(),
+                    blob_versioned_hashes: t.blob_versioned_hashes().cloned().unwrap_or_default(),
                 };
                 let res = self.call(params, &mut substate, &mut tracer, &mut vm_tracer);
                 let out = match &res {
@@ -1308,15 +1385,43 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             }
         };
 
+        // The sender, the recipient and the builtins are always warm, so they are listed only
+        // along with accessed storage keys.
+        let accessed = substate.access_list.accessed().map(|accessed| {
+            let recipient = match t.tx().action {
+                Action::Create => {
+                    contract_address(
+                        self.machine.create_address_scheme(self.info.number),
+                        &sender,
+                        &nonce,
+                        &t.tx().data,
+                    )
+                    .0
+                }
+                Action::Call(ref address) => *address,
+            };
+            accessed
+                .into_iter()
+                .filter(|&(ref address, ref keys)| {
+                    let is_warm = *address == sender
+                        || *address == recipient
+                        || self.machine.builtin(address, self.info.number).is_some();
+                    !is_warm || !keys.is_empty()
+                })
+                .collect()
+        });
+
         // finalize here!
-        Ok(self.finalize(
+        let mut executed = self.finalize(
             t,
             substate,
             result,
             output,
             tracer.drain(),
             vm_tracer.drain(),
-        )?)
+        )?;
+        executed.access_list = accessed;
+        Ok(executed)
     }
 
     /// Calls contract function with given contract params and stack depth.
@@ -1342,12 +1447,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
                 .builtin(&params.address, self.info.number)
                 .is_some(),
         );
-        vm_tracer.prepare_subtrace(
-            params
-                .code
-                .as_ref()
-                .map_or_else(|| &[] as &[u8], |d| &*d as &[u8]),
-        );
+        vm_tracer.prepare_subtrace(&params);
 
         let gas = params.gas;
 
@@ -1368,8 +1468,8 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             Ok(ref val) if val.apply_state => {
                 tracer.done_trace_call(gas - val.gas_left, &val.return_data);
             }
-            Ok(_) => {
-                tracer.done_trace_failed(&vm::Error::Reverted);
+            Ok(ref val) => {
+                tracer.done_trace_reverted(gas - val.gas_left, &val.return_data);
             }
             Err(ref err) => {
                 tracer.done_trace_failed(err);
@@ -1411,12 +1511,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
         V: VMTracer,
     {
         tracer.prepare_trace_create(&params);
-        vm_tracer.prepare_subtrace(
-            params
-                .code
-                .as_ref()
-                .map_or_else(|| &[] as &[u8], |d| &*d as &[u8]),
-        );
+        vm_tracer.prepare_subtrace(&params);
 
         let address = params.address;
         let gas = params.gas;
@@ -1438,8 +1533,8 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             Ok(ref val) if val.apply_state => {
                 tracer.done_trace_create(gas - val.gas_left, &val.return_data, address);
             }
-            Ok(_) => {
-                tracer.done_trace_failed(&vm::Error::Reverted);
+            Ok(ref val) => {
+                tracer.done_trace_reverted(gas - val.gas_left, &val.return_data);
             }
             Err(ref err) => {
                 tracer.done_trace_failed(err);
@@ -1601,6 +1696,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
                 trace: trace,
                 vm_trace: vm_trace,
                 state_diff: None,
+                access_list: None,
             }),
             Ok(r) => Ok(Executed {
                 exception: if r.apply_state {
@@ -1618,6 +1714,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
                 trace: trace,
                 vm_trace: vm_trace,
                 state_diff: None,
+                access_list: None,
             }),
         }
     }
@@ -1637,11 +1734,13 @@ mod tests {
     use std::{str::FromStr, sync::Arc};
     use test_helpers::{get_temp_state, get_temp_state_with_factory};
     use trace::{
-        trace, ExecutiveTracer, ExecutiveVMTracer, FlatTrace, MemoryDiff, NoopTracer, NoopVMTracer,
-        StorageDiff, Tracer, VMExecutedOperation, VMOperation, VMTrace, VMTracer,
+        geth::CallFrameType, trace, CallTracer, ExecutiveTracer, ExecutiveVMTracer, FlatTrace,
+        MemoryDiff, NoopTracer, NoopVMTracer, StorageDiff, StructLogger, Tracer,
+        VMExecutedOperation, VMOperation, VMTrace, VMTracer,
     };
     use types::transaction::{
-        AccessListTx, Action, EIP1559TransactionTx, Transaction, TypedTransaction,
+        kzg_to_versioned_hash, AccessListTx, Action, BlobTransactionTx, EIP1559TransactionTx,
+        Transaction, TypedTransaction, GAS_PER_BLOB,
     };
     use vm::{ActionParams, ActionValue, CallType, CreateContractAddress, EnvInfo};
 
@@ -2099,6 +2198,72 @@ mod tests {
         assert_eq!(tracer.drain(), expected_trace);
     }
 
+    #[test]
+    fn test_geth_trace_reverted_create() {
+        // same code as in test_trace_reverted_create
+        let code = "6460016000fd6000526005601b6017f0600055".from_hex().unwrap();
+
+        let sender = Address::from_str("cd1722f3947def4cf144679da39c4c32bdc35681").unwrap();
+        let address = contract_address(
+            CreateContractAddress::FromSenderAndNonce,
+            &sender,
+            &U256::zero(),
+            &[],
+        )
+        .0;
+        let mut params = ActionParams::default();
+        params.address = address.clone();
+        params.code_address = address.clone();
+        params.sender = sender.clone();
+        params.origin = sender.clone();
+        params.gas = U256::from(100_000);
+        params.code = Some(Arc::new(code));
+        params.value = ActionValue::Transfer(U256::from(100));
+        params.call_type = CallType::Call;
+        let mut state = get_temp_state();
+        state
+            .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
+            .unwrap();
+        let info = EnvInfo::default();
+        let machine = ::ethereum::new_byzantium_test_machine();
+        let schedule = machine.schedule(info.number);
+        let mut substate = Substate::new();
+        let mut tracer = CallTracer::default();
+        let mut vm_tracer = StructLogger::default();
+
+        {
+            let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
+            ex.call(params, &mut substate, &mut tracer, &mut vm_tracer)
+                .unwrap();
+        }
+
+        let frames = tracer.drain();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].from, sender);
+        assert_eq!(frames[0].to, address);
+        assert_eq!(frames[0].gas_used, U256::from(37_033));
+        assert_eq!(frames[0].calls.len(), 1);
+        assert_eq!(frames[0].calls[0].call_type, CallFrameType::Create);
+        assert_eq!(frames[0].calls[0].from, address);
+        assert_eq!(frames[0].calls[0].input, vec![0x60, 0x01, 0x60, 0x00, 0xfd]);
+        assert_eq!(frames[0].calls[0].output, vec![0]);
+        assert_eq!(frames[0].calls[0].error, Some("execution reverted".into()));
+
+        let logs = vm_tracer.drain().unwrap();
+        let ops = logs.iter().map(|log| log.op.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            ops,
+            vec![
+                "PUSH5", "PUSH1", "MSTORE", "PUSH1", "PUSH1", "PUSH1", "CREATE", "PUSH1", "PUSH1",
+                "REVERT", "PUSH1", "SSTORE"
+            ]
+        );
+        assert_eq!(logs[6].depth, 1);
+        assert_eq!(logs[9].depth, 2);
+        assert_eq!(logs[9].stack, Some(vec![U256::from(1), U256::from(0)]));
+        assert_eq!(logs[11].stack, Some(vec![U256::from(0), U256::from(0)]));
+    }
+
     #[test]
     fn test_create_contract() {
         // Tracing is not supported in JIT
@@ -2721,6 +2886,69 @@ mod tests {
         assert_eq!(res.gas_used, U256::from(83873));
     }
 
+    evm_test! {test_transact_blob_tx: test_transact_blob_tx_int}
+    fn test_transact_blob_tx(factory: Factory) {
+        let keypair = Random.generate();
+        let t = TypedTransaction::BlobTransaction(BlobTransactionTx {
+            transaction: EIP1559TransactionTx {
+                transaction: AccessListTx::new(
+                    Transaction {
+                        action: Action::Call(H160::from_low_u64_be(0xbb)),
+                        value: U256::from(1),
+                        data: vec![],
+                        gas: U256::from(21_000),
+                        gas_price: U256::from(100),
+                        nonce: U256::zero(),
+                    },
+                    vec![],
+                ),
+                max_priority_fee_per_gas: U256::zero(),
+            },
+            max_fee_per_blob_gas: U256::from(7),
+            blob_versioned_hashes: vec![kzg_to_versioned_hash(&[1u8; 48])],
+        })
+        .sign(keypair.secret(), None);
+        let sender = t.sender();
+
+        let mut state = get_temp_state_with_factory(factory);
+        // value + gas * max_fee_per_gas + blob_gas * max_fee_per_blob_gas
+        let needed_balance = 1 + 21_000 * 100 + GAS_PER_BLOB * 7;
+        state
+            .add_balance(&sender, &U256::from(needed_balance), CleanupMode::NoEmpty)
+            .unwrap();
+        let mut info = EnvInfo::default();
+        info.gas_limit = U256::from(100_000);
+        info.base_fee = Some(U256::from(100));
+        info.blob_base_fee = Some(U256::from(8));
+        let machine = ::ethereum::new_cancun_test_machine();
+        let schedule = machine.schedule(info.number);
+
+        let res = {
+            let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
+            ex.transact(&t, TransactOptions::with_no_tracing())
+        };
+        match res {
+            Err(ExecutionError::BlobGasPriceLowerThanBlobBaseFee {
+                max_fee_per_blob_gas,
+                blob_base_fee,
+            }) if max_fee_per_blob_gas == U256::from(7) && blob_base_fee == U256::from(8) => (),
+            _ => assert!(false, "Expected blob gas price error. {:?}", res),
+        }
+
+        info.blob_base_fee = Some(U256::from(1));
+        let res = {
+            let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
+            ex.transact(&t, TransactOptions::with_no_tracing()).unwrap()
+        };
+
+        assert_eq!(res.gas_used, U256::from(21_000));
+        // blob gas is charged at the blob base fee, not at the max fee per blob gas
+        assert_eq!(
+            state.balance(&sender).unwrap(),
+            U256::from(needed_balance - 1 - 21_000 * 100 - GAS_PER_BLOB)
+        );
+    }
+
     evm_test! {test_not_enough_cash: test_not_enough_cash_int}
     fn test_not_enough_cash(factory: Factory) {
         let keypair = Random.generate();
@@ -2760,6 +2988,117 @@ mod tests {
         }
     }
 
+    evm_test! {test_initcode_size_limit: test_initcode_size_limit_int}
+    fn test_initcode_size_limit(factory: Factory) {
+        let keypair = Random.generate();
+        let machine = ::ethereum::new_shanghai_test_machine();
+        let info = EnvInfo::default();
+        let schedule = machine.schedule(info.number);
+        let t = TypedTransaction::Legacy(Transaction {
+            action: Action::Create,
+            value: U256::zero(),
+            data: vec![0; schedule.max_initcode_size() + 1],
+            gas: U256::from(1_000_000),
+            gas_price: U256::zero(),
+            nonce: U256::zero(),
+        })
+        .sign(keypair.secret(), None);
+
+        let mut state = get_temp_state_with_factory(factory);
+        let res = {
+            let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
+            let opts = TransactOptions::with_no_tracing();
+            ex.transact(&t, opts)
+        };
+
+        match res {
+            Err(ExecutionError::TransactionMalformed(_)) => (),
+            _ => assert!(false, "Expected malformed transaction error. {:?}", res),
+        }
+    }
+
+    evm_test! {test_eip6780_suicide: test_eip6780_suicide_int}
+    fn test_eip6780_suicide(factory: Factory) {
+        let keypair = Random.generate();
+        let sender = keypair.address();
+        let machine = ::ethereum::new_cancun_test_machine();
+        let info = EnvInfo::default();
+        let schedule = machine.schedule(info.number);
+        let contract = Address::from_low_u64_be(0x1234);
+
+        let mut state = get_temp_state_with_factory(factory);
+        state
+            .add_balance(&sender, &U256::from(100_000), CleanupMode::NoEmpty)
+            .unwrap();
+        // ORIGIN SELFDESTRUCT
+        state
+            .init_code(&contract, "32ff".from_hex().unwrap())
+            .unwrap();
+        state
+            .add_balance(&contract, &U256::from(10), CleanupMode::NoEmpty)
+            .unwrap();
+        let t = TypedTransaction::Legacy(Transaction {
+            action: Action::Call(contract),
+            value: U256::zero(),
+            data: vec![],
+            gas: U256::from(100_000),
+            gas_price: U256::zero(),
+            nonce: U256::zero(),
+        })
+        .sign(keypair.secret(), None);
+
+        {
+            let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
+            let opts = TransactOptions::with_no_tracing();
+            ex.transact(&t, opts).unwrap();
+        }
+
+        // the contract predates the transaction, so it only sends its balance away
+        assert!(state.code(&contract).unwrap().is_some());
+        assert_eq!(state.balance(&contract).unwrap(), U256::zero());
+        assert_eq!(state.balance(&sender).unwrap(), U256::from(100_010));
+    }
+
+    evm_test! {test_eip6780_suicide_in_creation: test_eip6780_suicide_in_creation_int}
+    fn test_eip6780_suicide_in_creation(factory: Factory) {
+        let keypair = Random.generate();
+        let sender = keypair.address();
+        let machine = ::ethereum::new_cancun_test_machine();
+        let info = EnvInfo::default();
+        let schedule = machine.schedule(info.number);
+        let contract = contract_address(
+            CreateContractAddress::FromSenderAndNonce,
+            &sender,
+            &U256::zero(),
+            &[],
+        )
+        .0;
+
+        let mut state = get_temp_state_with_factory(factory);
+        state
+            .add_balance(&sender, &U256::from(100_000), CleanupMode::NoEmpty)
+            .unwrap();
+        let t = TypedTransaction::Legacy(Transaction {
+            action: Action::Create,
+            value: U256::from(10),
+            // ORIGIN SELFDESTRUCT
+            data: "32ff".from_hex().unwrap(),
+            gas: U256::from(100_000),
+            gas_price: U256::zero(),
+            nonce: U256::zero(),
+        })
+        .sign(keypair.secret(), None);
+
+        {
+            let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
+            let opts = TransactOptions::with_no_tracing();
+            ex.transact(&t, opts).unwrap();
+        }
+
+        assert!(!state.exists(&contract).unwrap());
+        assert_eq!(state.balance(&sender).unwrap(), U256::from(100_000));
+    }
+
     evm_test! {test_too_big_max_priority_fee_with_not_enough_cash: test_too_big_max_priority_fee_with_not_enough_cash_int}
     fn test_too_big_max_priority_fee_with_not_enough_cash(factory: Factory) {
         let keypair = Random.generate();
@@ -25,7 +25,8 @@ use trace::{Tracer, VMTracer};
 use types::transaction::UNSIGNED_SENDER;
 use vm::{
     self, AccessList, ActionParams, ActionValue, CallType, ContractCreateResult,
-    CreateContractAddress, EnvInfo, Ext, MessageCallResult, ReturnData, Schedule, TrapKind,
+    CreateContractAddress, EnvInfo, Ext, MessageCallResult, ReturnData, Schedule, TransientState,
+    TrapKind,
 };
 
 /// Policy for handling output data on `RETURN` opcode.
@@ -43,6 +44,7 @@ pub struct OriginInfo {
     origin: Address,
     gas_price: U256,
     value: U256,
+    blob_versioned_hashes: Vec<H256>,
 }
 
 impl OriginInfo {
@@ -55,6 +57,7 @@ impl OriginInfo {
             value: match params.value {
                 ActionValue::Transfer(val) | ActionValue::Apparent(val) => val,
             },
+            blob_versioned_hashes: params.blob_versioned_hashes.clone(),
         }
     }
 }
@@ -150,6 +153,24 @@ where
         }
     }
 
+    fn transient_storage_at(&self, key: &H256) -> vm::Result<H256> {
+        Ok(self
+            .substate
+            .transient_state
+            .storage_at(&self.origin_info.address, key))
+    }
+
+    fn set_transient_storage(&mut self, key: H256, value: H256) -> vm::Result<()> {
+        if self.static_flag {
+            Err(vm::Error::MutableCallInStaticContext)
+        } else {
+            self.substate
+                .transient_state
+                .set_storage(self.origin_info.address, key, value);
+            Ok(())
+        }
+    }
+
     fn is_static(&self) -> bool {
         return self.static_flag;
     }
@@ -203,6 +224,8 @@ where
                 call_type: CallType::Call,
                 params_type: vm::ParamsType::Separate,
                 access_list: AccessList::default(),
+                transient_state: TransientState::default(),
+                blob_versioned_hashes: Vec::new(),
             };
 
             let mut ex = Executive::new(self.state, self.env_info, self.machine, self.schedule);
@@ -292,6 +315,8 @@ where
             call_type: CallType::None,
             params_type: vm::ParamsType::Embedded,
             access_list: self.substate.access_list.clone(),
+            transient_state: self.substate.transient_state.clone(),
+            blob_versioned_hashes: self.origin_info.blob_versioned_hashes.clone(),
         };
 
         if !self.static_flag {
@@ -372,6 +397,8 @@ where
             call_type: call_type,
             params_type: vm::ParamsType::Separate,
             access_list: self.substate.access_list.clone(),
+            transient_state: self.substate.transient_state.clone(),
+            blob_versioned_hashes: self.origin_info.blob_versioned_hashes.clone(),
         };
 
         if let Some(value) = value {
@@ -469,10 +496,15 @@ where
 
         let address = self.origin_info.address.clone();
         let balance = self.balance(&address)?;
+        // EIP-6780: only contracts created in the same transaction are deleted,
+        // other ones just send their balance away.
+        let destroy = !self.schedule.eip6780 || self.substate.transient_state.is_created(&address);
         if &address == refund_address {
             // TODO [todr] To be consistent with CPP client we set balance to 0 in that case.
-            self.state
-                .sub_balance(&address, &balance, &mut CleanupMode::NoEmpty)?;
+            if destroy {
+                self.state
+                    .sub_balance(&address, &balance, &mut CleanupMode::NoEmpty)?;
+            }
         } else {
             trace!(target: "ext", "Suiciding {} -> {} (xfer: {})", address, refund_address, balance);
             self.state.transfer_balance(
@@ -485,7 +517,9 @@ where
 
         self.tracer
             .trace_suicide(address, balance, refund_address.clone());
-        self.substate.suicides.insert(address);
+        if destroy {
+            self.substate.suicides.insert(address);
+        }
 
         Ok(())
     }
@@ -576,6 +610,7 @@ mod tests {
             origin: Address::zero(),
             gas_price: U256::zero(),
             value: U256::zero(),
+            blob_versioned_hashes: Vec::new(),
         }
     }
 
@@ -589,6 +624,7 @@ mod tests {
             gas_used: 0.into(),
             gas_limit: 0.into(),
             base_fee: None,
+            blob_base_fee: None,
         }
     }
 
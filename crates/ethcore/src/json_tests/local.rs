@@ -5,8 +5,8 @@ use log::warn;
 use rlp::RlpStream;
 use std::path::Path;
 use types::{
+    header::HeaderTransitions,
     transaction::{TypedTransaction, TypedTxId, UnverifiedTransaction},
-    BlockNumber,
 };
 use verification::queue::kind::blocks::Unverified;
 
@@ -25,7 +25,7 @@ pub fn json_local_block_en_de_test<H: FnMut(&str, HookType)>(
     for (name, ref_block) in tests.into_iter() {
         start_stop_hook(&name, HookType::OnStart);
 
-        let block = Unverified::from_rlp(ref_block.rlp(), BlockNumber::max_value());
+        let block = Unverified::from_rlp(ref_block.rlp(), HeaderTransitions::default());
         let block = match block {
             Ok(block) => block,
             Err(decoder_err) => {
@@ -46,10 +46,13 @@ pub fn json_local_block_en_de_test<H: FnMut(&str, HookType)>(
 
 fn rlp_append_block(block: &Unverified) -> Vec<u8> {
     let mut rlps = RlpStream::new();
-    rlps.begin_list(3);
+    rlps.begin_list(3 + block.withdrawals.is_some() as usize);
     rlps.append(&block.header);
     UnverifiedTransaction::rlp_append_list(&mut rlps, &block.transactions);
     rlps.append_list(&block.uncles);
+    if let Some(ref withdrawals) = block.withdrawals {
+        rlps.append_list(withdrawals);
+    }
     rlps.out()
 }
 
@@ -167,11 +170,16 @@ pub fn is_same_block(ref_block: &Block, block: &Unverified) -> bool {
                     TypedTxId::Legacy => {
                         test_exp(tx.legacy_v() == ref_tx.v.0.as_u64(), "Original Sig V")
                     }
-                    TypedTxId::AccessList | TypedTxId::EIP1559Transaction => {
+                    TypedTxId::AccessList
+                    | TypedTxId::EIP1559Transaction
+                    | TypedTxId::BlobTransaction => {
                         test_exp(tx.standard_v() as u64 == ref_tx.v.0.as_u64(), "Sig V");
                         let al = match tx.as_unsigned() {
                             TypedTransaction::AccessList(tx) => &tx.access_list,
                             TypedTransaction::EIP1559Transaction(tx) => &tx.transaction.access_list,
+                            TypedTransaction::BlobTransaction(tx) => {
+                                &tx.transaction.transaction.access_list
+                            }
                             _ => {
                                 println!("Wrong data in tx type");
                                 continue;
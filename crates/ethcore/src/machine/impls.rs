@@ -22,7 +22,7 @@ use std::{
     sync::Arc,
 };
 
-use ethereum_types::{Address, H256, U256};
+use ethereum_types::{Address, H160, H256, U256};
 use types::{
     header::Header,
     transaction::{
@@ -33,7 +33,7 @@ use types::{
 };
 use vm::{
     AccessList, ActionParams, ActionValue, CallType, CreateContractAddress, EnvInfo, ParamsType,
-    Schedule,
+    Schedule, TransientState,
 };
 
 use block::ExecutedBlock;
@@ -47,6 +47,18 @@ use state::{CleanupMode, Substate};
 use trace::{NoopTracer, NoopVMTracer};
 use tx_filter::TransactionFilter;
 
+/// Target amount of blob gas consumed per block (EIP-4844).
+pub const TARGET_BLOB_GAS_PER_BLOCK: u64 = 393_216;
+/// Maximum amount of blob gas consumed per block (EIP-4844).
+pub const MAX_BLOB_GAS_PER_BLOCK: u64 = 786_432;
+/// Address of the contract keeping the parent beacon block roots (EIP-4788).
+pub const BEACON_ROOTS_ADDRESS: Address = H160([
+    0x00, 0x0f, 0x3d, 0xf6, 0xd7, 0x32, 0x80, 0x7e, 0xf1, 0x31, 0x9f, 0xb7, 0xb8, 0xbb, 0x85, 0x22,
+    0xd0, 0xbe, 0xac, 0x02,
+]);
+/// Gas available to the system call storing the parent beacon block root (EIP-4788).
+pub const BEACON_ROOTS_CALL_GAS: u64 = 30_000_000;
+
 /// Ethash-specific extensions.
 #[derive(Debug, Clone)]
 pub struct EthashExtensions {
@@ -200,6 +212,8 @@ impl EthereumMachine {
             call_type: call_type.unwrap_or(CallType::Call),
             params_type: ParamsType::Separate,
             access_list: AccessList::default(),
+            transient_state: TransientState::default(),
+            blob_versioned_hashes: Vec::new(),
         };
         let schedule = self.schedule(env_info.number);
         let mut ex = Executive::new(&mut state, &env_info, self, &schedule);
@@ -235,6 +249,28 @@ impl EthereumMachine {
         Ok(())
     }
 
+    /// Store the parent beacon block root committed to by the block in the beacon roots
+    /// contract (EIP-4788). Has no effect if the contract is not deployed.
+    pub fn push_parent_beacon_block_root(&self, block: &mut ExecutedBlock) -> Result<(), Error> {
+        let root = match block.header.parent_beacon_block_root() {
+            Some(root) => *root,
+            None => return Ok(()),
+        };
+        let deployed = block
+            .state
+            .code(&BEACON_ROOTS_ADDRESS)?
+            .map_or(false, |code| !code.is_empty());
+        if deployed {
+            let _ = self.execute_as_system(
+                block,
+                BEACON_ROOTS_ADDRESS,
+                BEACON_ROOTS_CALL_GAS.into(),
+                Some(root.as_bytes().to_vec()),
+            )?;
+        }
+        Ok(())
+    }
+
     // t_nb 8.1.3 Logic to perform on a new block: updating last hashes and the DAO
     /// fork, for ethash.
     pub fn on_new_block(&self, block: &mut ExecutedBlock) -> Result<(), Error> {
@@ -456,6 +492,9 @@ impl EthereumMachine {
             transaction::TypedTxId::EIP1559Transaction if !schedule.eip1559 => {
                 return Err(transaction::Error::TransactionTypeNotEnabled)
             }
+            transaction::TypedTxId::BlobTransaction if !schedule.eip4844 => {
+                return Err(transaction::Error::TransactionTypeNotEnabled)
+            }
             _ => (),
         };
 
@@ -527,6 +566,23 @@ impl EthereumMachine {
 
         Some(max(result, base_fee_min_value))
     }
+
+    /// Calculates excess blob gas for the block that should be mined next.
+    /// Blocks before `eip4844_transition` don't have blob gas fields.
+    ///
+    /// Introduced by EIP4844 to price blob gas independently of execution gas.
+    pub fn calc_excess_blob_gas(&self, parent: &Header) -> Option<u64> {
+        if parent.number() + 1 < self.params().eip4844_transition {
+            return None;
+        }
+
+        let parent_excess_blob_gas = parent.excess_blob_gas().unwrap_or_default();
+        let parent_blob_gas_used = parent.blob_gas_used().unwrap_or_default();
+        Some(
+            (parent_excess_blob_gas + parent_blob_gas_used)
+                .saturating_sub(TARGET_BLOB_GAS_PER_BLOCK),
+        )
+    }
 }
 
 /// Auxiliary data fetcher for an Ethereum machine. In Ethereum-like machines
@@ -578,7 +634,7 @@ impl super::Machine for EthereumMachine {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ethereum::new_london_test_machine;
+    use crate::ethereum::{new_cancun_test_machine, new_london_test_machine};
     use ethereum_types::H160;
     use std::str::FromStr;
 
@@ -679,4 +735,25 @@ mod tests {
             assert_eq!(expected_base_fee[i], base_fee.unwrap());
         }
     }
+
+    #[test]
+    fn calculate_excess_blob_gas() {
+        let machine = new_cancun_test_machine();
+        let mut parent_header = Header::default();
+        assert_eq!(machine.calc_excess_blob_gas(&parent_header), Some(0));
+
+        parent_header.set_blob_gas_used(Some(MAX_BLOB_GAS_PER_BLOCK));
+        parent_header.set_excess_blob_gas(Some(0));
+        assert_eq!(
+            machine.calc_excess_blob_gas(&parent_header),
+            Some(MAX_BLOB_GAS_PER_BLOCK - TARGET_BLOB_GAS_PER_BLOCK)
+        );
+
+        parent_header.set_blob_gas_used(Some(0));
+        parent_header.set_excess_blob_gas(Some(100_000));
+        assert_eq!(machine.calc_excess_blob_gas(&parent_header), Some(0));
+
+        let london_machine = new_london_test_machine();
+        assert_eq!(london_machine.calc_excess_blob_gas(&parent_header), None);
+    }
 }
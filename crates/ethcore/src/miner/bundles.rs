@@ -0,0 +1,368 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction bundles.
+//!
+//! A bundle is a list of transactions targeting a single block which is included at the
+//! top of that block either as a whole or not at all. Bundles are kept apart from the
+//! transaction queue: they are never propagated and they don't affect queue ordering.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+};
+
+use ethereum_types::H256;
+use hash::keccak;
+use types::{
+    header::Header,
+    receipt::TransactionOutcome,
+    transaction::{self, SignedTransaction},
+    BlockNumber,
+};
+
+use block::OpenBlock;
+use error::Error;
+
+/// Maximal number of bundles kept in the pool.
+pub const MAX_BUNDLES: usize = 256;
+/// Maximal number of transactions in a single bundle.
+pub const MAX_BUNDLE_TRANSACTIONS: usize = 64;
+/// Maximal number of bundles targeting the same block.
+pub const MAX_BUNDLES_PER_BLOCK: usize = 32;
+/// How many blocks ahead of the best block a bundle may target.
+pub const MAX_BUNDLE_HORIZON: BlockNumber = 32;
+
+/// Transactions to be included atomically at the top of a block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bundle {
+    /// Transactions in the order of inclusion.
+    pub transactions: Vec<SignedTransaction>,
+    /// Number of the only block the bundle may be included in.
+    pub block_number: BlockNumber,
+    /// Lowest timestamp of the block the bundle may be included in.
+    pub min_timestamp: Option<u64>,
+    /// Highest timestamp of the block the bundle may be included in.
+    pub max_timestamp: Option<u64>,
+    /// Transactions which are allowed to revert without discarding the bundle.
+    pub reverting_hashes: HashSet<H256>,
+}
+
+impl Bundle {
+    /// Bundle hash, the hash of concatenated transaction hashes.
+    pub fn hash(&self) -> H256 {
+        let hashes: Vec<u8> = self
+            .transactions
+            .iter()
+            .flat_map(|tx| tx.hash().as_bytes().to_vec())
+            .collect();
+        keccak(hashes)
+    }
+
+    /// Checks if the bundle may be included in block with given number and timestamp.
+    pub fn is_includable(&self, block_number: BlockNumber, timestamp: u64) -> bool {
+        self.block_number == block_number
+            && self.min_timestamp.map_or(true, |min| timestamp >= min)
+            && self.max_timestamp.map_or(true, |max| timestamp <= max)
+    }
+}
+
+/// Errors returned when importing a bundle.
+#[derive(Debug, PartialEq)]
+pub enum BundleError {
+    /// Bundle doesn't contain any transactions.
+    Empty,
+    /// Bundle contains more than `MAX_BUNDLE_TRANSACTIONS` transactions.
+    TooManyTransactions,
+    /// Bundle targets a block which is already imported.
+    StaleBlock {
+        /// Block the bundle targets.
+        target: BlockNumber,
+        /// Current best block.
+        best: BlockNumber,
+    },
+    /// Bundle targets a block more than `MAX_BUNDLE_HORIZON` blocks ahead.
+    FutureBlock {
+        /// Block the bundle targets.
+        target: BlockNumber,
+        /// Current best block.
+        best: BlockNumber,
+    },
+    /// Minimal timestamp is higher than the maximal one.
+    InvalidTimestampRange,
+    /// The pool already contains `MAX_BUNDLES` bundles.
+    Full,
+    /// The pool already contains `MAX_BUNDLES_PER_BLOCK` bundles for the target block.
+    BlockFull,
+    /// Reverts can't be detected in the target block because EIP-658 is not active.
+    RevertsUndetectable,
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BundleError::Empty => write!(f, "Bundle contains no transactions"),
+            BundleError::TooManyTransactions => write!(
+                f,
+                "Bundle contains more than {} transactions",
+                MAX_BUNDLE_TRANSACTIONS
+            ),
+            BundleError::StaleBlock { target, best } => write!(
+                f,
+                "Bundle targets block #{}, but the best block is already #{}",
+                target, best
+            ),
+            BundleError::FutureBlock { target, best } => write!(
+                f,
+                "Bundle targets block #{}, more than {} blocks ahead of the best block #{}",
+                target, MAX_BUNDLE_HORIZON, best
+            ),
+            BundleError::InvalidTimestampRange => {
+                write!(f, "Minimal timestamp is higher than the maximal one")
+            }
+            BundleError::Full => write!(f, "Too many bundles, limit is {}", MAX_BUNDLES),
+            BundleError::BlockFull => write!(
+                f,
+                "Too many bundles for a single block, limit is {}",
+                MAX_BUNDLES_PER_BLOCK
+            ),
+            BundleError::RevertsUndetectable => write!(
+                f,
+                "Bundles are not supported before transaction status codes (EIP-658) are enabled"
+            ),
+        }
+    }
+}
+
+/// Why a bundle was left out of a block.
+#[derive(Debug)]
+pub enum Rejection {
+    /// Transaction couldn't be verified or pushed to the block.
+    Invalid(H256, Error),
+    /// Transaction reverted and it isn't allowed to.
+    Reverted(H256),
+}
+
+/// Bundles waiting for their block.
+#[derive(Debug, Default)]
+pub struct BundlePool {
+    // bundles by target block, in the order of submission
+    bundles: BTreeMap<BlockNumber, Vec<Bundle>>,
+}
+
+impl BundlePool {
+    /// Adds a bundle to the pool. Submitting the same bundle again is a no-op.
+    pub fn import(&mut self, bundle: Bundle, best_block: BlockNumber) -> Result<H256, BundleError> {
+        if bundle.transactions.is_empty() {
+            return Err(BundleError::Empty);
+        }
+        if bundle.transactions.len() > MAX_BUNDLE_TRANSACTIONS {
+            return Err(BundleError::TooManyTransactions);
+        }
+        if bundle.block_number <= best_block {
+            return Err(BundleError::StaleBlock {
+                target: bundle.block_number,
+                best: best_block,
+            });
+        }
+        if bundle.block_number - best_block > MAX_BUNDLE_HORIZON {
+            return Err(BundleError::FutureBlock {
+                target: bundle.block_number,
+                best: best_block,
+            });
+        }
+        if let (Some(min), Some(max)) = (bundle.min_timestamp, bundle.max_timestamp) {
+            if min > max {
+                return Err(BundleError::InvalidTimestampRange);
+            }
+        }
+
+        let hash = bundle.hash();
+        let for_block = self
+            .bundles
+            .get(&bundle.block_number)
+            .map_or(&[][..], |bundles| &bundles[..]);
+        if for_block.iter().any(|b| b.hash() == hash) {
+            return Ok(hash);
+        }
+        if for_block.len() >= MAX_BUNDLES_PER_BLOCK {
+            return Err(BundleError::BlockFull);
+        }
+        if self.len() >= MAX_BUNDLES {
+            return Err(BundleError::Full);
+        }
+
+        self.bundles
+            .entry(bundle.block_number)
+            .or_insert_with(Vec::new)
+            .push(bundle);
+        Ok(hash)
+    }
+
+    /// Returns bundles which may be included in block with given number and timestamp.
+    pub fn includable(&self, block_number: BlockNumber, timestamp: u64) -> Vec<Bundle> {
+        self.bundles
+            .get(&block_number)
+            .map(|bundles| {
+                bundles
+                    .iter()
+                    .filter(|bundle| bundle.is_includable(block_number, timestamp))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Removes bundles targeting blocks up to `best_block`.
+    pub fn cull(&mut self, best_block: BlockNumber) {
+        self.bundles = self.bundles.split_off(&(best_block + 1));
+    }
+
+    /// Number of bundles in the pool.
+    pub fn len(&self) -> usize {
+        self.bundles.values().map(Vec::len).sum()
+    }
+}
+
+/// Pushes all transactions of the bundle to the block or none of them.
+///
+/// `verify` is called with the current header before every transaction is pushed. The block
+/// is left untouched if any transaction fails or reverts without being listed in `reverting_hashes`.
+/// Without EIP-658 status codes a revert can't be told apart from success, so every transaction
+/// which is not allowed to revert is rejected in that case.
+pub fn push_bundle<F>(
+    open_block: &mut OpenBlock,
+    bundle: &Bundle,
+    verify: F,
+) -> Result<(), Rejection>
+where
+    F: Fn(&SignedTransaction, &Header) -> Result<(), transaction::Error>,
+{
+    let mut block = open_block.clone();
+    for transaction in &bundle.transactions {
+        let hash = transaction.hash();
+        let receipt = verify(transaction, &block.header)
+            .map_err(Into::into)
+            .and_then(|_| block.push_transaction(transaction.clone(), None))
+            .map_err(|e| Rejection::Invalid(hash, e))?;
+        let succeeded = match receipt.outcome {
+            TransactionOutcome::StatusCode(status) => status != 0,
+            _ => false,
+        };
+        if !succeeded && !bundle.reverting_hashes.contains(&hash) {
+            return Err(Rejection::Reverted(hash));
+        }
+    }
+    *open_block = block;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::publickey::{Generator, Random};
+    use ethereum_types::U256;
+    use types::transaction::{Action, Transaction, TypedTransaction};
+
+    fn bundle(block_number: BlockNumber, transactions: usize) -> Bundle {
+        let keypair = Random.generate();
+        Bundle {
+            transactions: (0..transactions)
+                .map(|nonce| {
+                    TypedTransaction::Legacy(Transaction {
+                        action: Action::Create,
+                        value: U256::zero(),
+                        data: Vec::new(),
+                        gas: 100_000.into(),
+                        gas_price: U256::zero(),
+                        nonce: nonce.into(),
+                    })
+                    .sign(keypair.secret(), None)
+                })
+                .collect(),
+            block_number,
+            min_timestamp: None,
+            max_timestamp: None,
+            reverting_hashes: HashSet::new(),
+        }
+    }
+
+    #[test]
+    fn should_reject_invalid_bundles() {
+        let mut pool = BundlePool::default();
+        assert_eq!(pool.import(bundle(11, 0), 10), Err(BundleError::Empty));
+        assert_eq!(
+            pool.import(bundle(11, MAX_BUNDLE_TRANSACTIONS + 1), 10),
+            Err(BundleError::TooManyTransactions)
+        );
+        assert_eq!(
+            pool.import(bundle(10, 1), 10),
+            Err(BundleError::StaleBlock {
+                target: 10,
+                best: 10
+            })
+        );
+        assert_eq!(
+            pool.import(bundle(10 + MAX_BUNDLE_HORIZON + 1, 1), 10),
+            Err(BundleError::FutureBlock {
+                target: 10 + MAX_BUNDLE_HORIZON + 1,
+                best: 10
+            })
+        );
+        let mut reversed = bundle(11, 1);
+        reversed.min_timestamp = Some(2);
+        reversed.max_timestamp = Some(1);
+        assert_eq!(
+            pool.import(reversed, 10),
+            Err(BundleError::InvalidTimestampRange)
+        );
+        assert_eq!(pool.len(), 0);
+    }
+
+    #[test]
+    fn should_limit_bundles_per_block() {
+        let mut pool = BundlePool::default();
+        for _ in 0..MAX_BUNDLES_PER_BLOCK {
+            pool.import(bundle(11, 1), 10).unwrap();
+        }
+        assert_eq!(pool.import(bundle(11, 1), 10), Err(BundleError::BlockFull));
+        assert!(pool.import(bundle(12, 1), 10).is_ok());
+        assert_eq!(pool.len(), MAX_BUNDLES_PER_BLOCK + 1);
+    }
+
+    #[test]
+    fn should_return_bundles_for_block_and_timestamp() {
+        let mut pool = BundlePool::default();
+        let mut early = bundle(11, 2);
+        early.max_timestamp = Some(100);
+        let late = bundle(11, 1);
+        let next = bundle(12, 1);
+        assert_eq!(pool.import(early.clone(), 10), Ok(early.hash()));
+        pool.import(late.clone(), 10).unwrap();
+        pool.import(next.clone(), 10).unwrap();
+        // importing the same bundle again doesn't duplicate it
+        pool.import(late.clone(), 10).unwrap();
+        assert_eq!(pool.len(), 3);
+
+        assert_eq!(pool.includable(11, 100), vec![early, late.clone()]);
+        assert_eq!(pool.includable(11, 101), vec![late]);
+        assert_eq!(pool.includable(12, 101), vec![next.clone()]);
+
+        pool.cull(11);
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.includable(12, 101), vec![next]);
+    }
+}
@@ -40,9 +40,11 @@ use ethereum_types::{Address, H256, U256};
 use io::IoChannel;
 use miner::{
     self,
+    bundles::{push_bundle, BundlePool, Rejection},
     cache::Cache,
     pool_client::{CachedNonceClient, PoolClient},
-    MinerService,
+    profit::ProfitOrdering,
+    Bundle, BundleError, MinerService,
 };
 use parking_lot::{Mutex, RwLock};
 use rayon::prelude::*;
@@ -123,6 +125,10 @@ const DEFAULT_MINIMAL_GAS_PRICE: u64 = 20_000_000_000;
 /// in case we have only a fraction of available block gas limit left.
 const MAX_SKIPPED_TRANSACTIONS: usize = 128;
 
+/// Maximal number of transactions simulated by the profit-maximizing strategy
+/// while preparing a single block. The rest is pushed in gas price order.
+const MAX_PROFIT_SIMULATIONS: usize = 1024;
+
 /// Configures the behaviour of the miner.
 #[derive(Debug, PartialEq)]
 pub struct MinerOptions {
@@ -161,6 +167,8 @@ pub struct MinerOptions {
     pub pool_limits: pool::Options,
     /// Initial transaction verification options.
     pub pool_verification_options: pool::verifier::Options,
+    /// Rate limits and bans protecting the pool from spam.
+    pub pool_spam_protection: pool::spam::Options,
 }
 
 impl Default for MinerOptions {
@@ -193,6 +201,7 @@ impl Default for MinerOptions {
                 no_early_reject: false,
                 allow_non_eoa_sender: false,
             },
+            pool_spam_protection: Default::default(),
         }
     }
 }
@@ -260,6 +269,8 @@ pub struct Miner {
     accounts: Arc<dyn LocalAccounts>,
     io_channel: RwLock<Option<IoChannel<ClientIoMessage>>>,
     service_transaction_checker: Option<ServiceTransactionChecker>,
+    bundles: RwLock<BundlePool>,
+    expected_block_reward: RwLock<Option<(BlockNumber, U256)>>,
 }
 
 impl Miner {
@@ -275,6 +286,25 @@ impl Miner {
         self.transaction_queue.add_listener(f);
     }
 
+    /// Set a callback to be notified about lifecycle events of all pooled transactions.
+    pub fn add_transaction_events_listener(
+        &self,
+        f: Box<dyn Fn(&[pool::events::Event]) + Send + Sync>,
+    ) -> pool::events::ListenerId {
+        self.transaction_queue.add_event_listener(f)
+    }
+
+    /// Remove a callback added with `add_transaction_events_listener`.
+    pub fn remove_transaction_events_listener(&self, id: pool::events::ListenerId) {
+        self.transaction_queue.remove_event_listener(id);
+    }
+
+    /// Number of the last block prepared with the profit-maximizing strategy and the reward its
+    /// transactions are expected to earn the author, fees and direct payments included.
+    pub fn expected_block_reward(&self) -> Option<(BlockNumber, U256)> {
+        *self.expected_block_reward.read()
+    }
+
     /// Creates new instance of miner Arc.
     pub fn new<A: LocalAccounts + 'static>(
         options: MinerOptions,
@@ -289,6 +319,8 @@ impl Miner {
         let balance_cache_size = cmp::max(4096, limits.max_count / 4);
         let refuse_service_transactions = options.refuse_service_transactions;
         let engine = spec.engine.clone();
+        let transaction_queue = TransactionQueue::new(limits, verifier_options, tx_queue_strategy);
+        transaction_queue.set_spam_protection(options.pool_spam_protection.clone());
 
         Miner {
             sealing: Mutex::new(SealingWork {
@@ -306,11 +338,7 @@ impl Miner {
             nonce_cache: Cache::<Address, U256>::new("Nonce", nonce_cache_size),
             balance_cache: Cache::<Address, U256>::new("Balance", balance_cache_size),
             options,
-            transaction_queue: Arc::new(TransactionQueue::new(
-                limits,
-                verifier_options,
-                tx_queue_strategy,
-            )),
+            transaction_queue: Arc::new(transaction_queue),
             accounts: Arc::new(accounts),
             engine,
             io_channel: RwLock::new(None),
@@ -319,6 +347,8 @@ impl Miner {
             } else {
                 Some(ServiceTransactionChecker::default())
             },
+            bundles: RwLock::new(BundlePool::default()),
+            expected_block_reward: RwLock::new(None),
         }
     }
 
@@ -413,6 +443,21 @@ impl Miner {
         self.service_transaction_checker.clone()
     }
 
+    /// Re-imports transactions restored from the pool journal.
+    ///
+    /// Transactions are verified again against the current state,
+    /// so the ones which became stale in the meantime are rejected.
+    /// They were already accepted once, so they don't count against the spam protection.
+    pub fn import_journaled_transactions<C: miner::BlockChainClient>(
+        &self,
+        chain: &C,
+        transactions: Vec<pool::verifier::Transaction>,
+    ) -> Vec<Result<(), transaction::Error>> {
+        trace!(target: "miner", "Importing {} journaled transactions", transactions.len());
+        let client = self.pool_client(chain);
+        self.transaction_queue.import_restored(client, transactions)
+    }
+
     /// Retrieves an existing pending block iff it's not older than given block number.
     ///
     /// NOTE: This will not prepare a new pending block if it's not existing.
@@ -454,7 +499,8 @@ impl Miner {
         let chain_info = chain.chain_info();
 
         // Some engines add transactions to the block for their own purposes, e.g. AuthorityRound RANDAO.
-        let (mut open_block, original_work_hash, engine_txs) = {
+        // Bundles are only considered for fresh blocks, since they have to go at the top.
+        let (mut open_block, original_work_hash, engine_txs, bundles) = {
             let mut sealing = self.sealing.lock();
             let last_work_hash = sealing.queue.peek_last_ref().map(|pb| pb.header.hash());
             let best_hash = chain_info.best_block_hash;
@@ -472,7 +518,12 @@ impl Miner {
                 Some(old_block) => {
                     trace!(target: "miner", "prepare_block: Already have previous work; updating and returning");
                     // add transactions to old_block
-                    (chain.reopen_block(old_block), last_work_hash, Vec::new())
+                    (
+                        chain.reopen_block(old_block),
+                        last_work_hash,
+                        Vec::new(),
+                        Vec::new(),
+                    )
                 }
                 None => {
                     // block not found - create it.
@@ -491,9 +542,13 @@ impl Miner {
                             return None;
                         }
                     };
+                    let bundles = self
+                        .bundles
+                        .read()
+                        .includable(block.header.number(), block.header.timestamp());
                     // Before adding from the queue to the new block, give the engine a chance to add transactions.
                     match self.engine.generate_engine_transactions(&block) {
-                        Ok(transactions) => (block, last_work_hash, transactions),
+                        Ok(transactions) => (block, last_work_hash, transactions, bundles),
                         Err(err) => {
                             error!(target: "miner", "Failed to prepare engine transactions for new block: {:?}. \
 								   This is likely an error in chain specification or on-chain consensus smart \
@@ -559,22 +614,81 @@ impl Miner {
         };
 
         let block_start = Instant::now();
-        debug!(target: "miner", "Attempting to push {} transactions.", engine_txs.len() + queue_txs.len());
+        debug!(target: "miner", "Attempting to push {} transactions and {} bundles.", engine_txs.len() + queue_txs.len(), bundles.len());
+
+        let (mut by_profit, queue_txs) = match self.options.tx_queue_strategy {
+            PrioritizationStrategy::ProfitMaximizing => (
+                Some(ProfitOrdering::new(
+                    queue_txs.into_iter().map(|tx| tx.signed().clone()),
+                    MAX_PROFIT_SIMULATIONS,
+                )),
+                Vec::new(),
+            ),
+            PrioritizationStrategy::GasPriceOnly => (None, queue_txs),
+        };
+        let mut engine_txs = engine_txs.into_iter();
+        let mut queue_txs = queue_txs.into_iter().map(|tx| tx.signed().clone());
+        let mut bundles = Some(bundles);
+
+        let author = *open_block.header.author();
+        loop {
+            // engine transactions always go first, followed by bundles
+            let (transaction, by_ordering) = match engine_txs.next() {
+                Some(transaction) => (transaction, false),
+                None => {
+                    for bundle in bundles.take().unwrap_or_default() {
+                        let bundle_hash = bundle.hash();
+                        match push_bundle(&mut open_block, &bundle, |tx, header| {
+                            client.verify_for_pending_block(tx, header)
+                        }) {
+                            Ok(()) => {
+                                debug!(target: "miner", "Included bundle {:?} with {} transactions", bundle_hash, bundle.transactions.len());
+                                tx_count += bundle.transactions.len();
+                            }
+                            Err(Rejection::Reverted(hash)) => {
+                                debug!(target: "miner", "Skipping bundle {:?}: transaction {:?} reverted", bundle_hash, hash);
+                            }
+                            Err(Rejection::Invalid(hash, e)) => {
+                                debug!(target: "miner", "Skipping bundle {:?}: transaction {:?} failed: {:?}", bundle_hash, hash, e);
+                            }
+                        }
+                    }
 
-        for transaction in engine_txs
-            .into_iter()
-            .chain(queue_txs.into_iter().map(|tx| tx.signed().clone()))
-        {
+                    match (queue_txs.next(), by_profit.as_mut()) {
+                        (Some(transaction), _) => (transaction, false),
+                        (None, Some(ordering)) => {
+                            let gas_used = open_block
+                                .receipts
+                                .last()
+                                .map_or_else(U256::zero, |receipt| receipt.gas_used);
+                            let gas_left = gas_limit.saturating_sub(gas_used);
+                            match ordering.next(gas_left, |tx| open_block.simulate_transaction(tx))
+                            {
+                                Some(transaction) => (transaction, true),
+                                None => break,
+                            }
+                        }
+                        (None, None) => break,
+                    }
+                }
+            };
             let start = Instant::now();
 
             let hash = transaction.hash();
             let sender = transaction.sender();
+            // the reward includes direct payments to the author, not only the fees
+            let author_balance = if by_ordering {
+                open_block.state.balance(&author).ok()
+            } else {
+                None
+            };
 
             // Re-verify transaction again vs current state.
             let result = client
                 .verify_for_pending_block(&transaction, &open_block.header)
                 .map_err(|e| e.into())
-                .and_then(|_| open_block.push_transaction(transaction, None));
+                .and_then(|_| open_block.push_transaction(transaction, None))
+                .map(|_| ());
 
             let took = start.elapsed();
 
@@ -600,6 +714,9 @@ impl Miner {
                     _,
                 )) => {
                     debug!(target: "miner", "Skipping adding transaction to block because of gas limit: {:?} (limit: {:?}, used: {:?}, gas: {:?})", hash, gas_limit, gas_used, gas);
+                    if let Some(ref mut ordering) = by_profit {
+                        ordering.skipped();
+                    }
 
                     // Penalize transaction if it's above current gas limit
                     if gas > gas_limit {
@@ -615,12 +732,29 @@ impl Miner {
                     }
 
                     // Avoid iterating over the entire queue in case block is almost full.
+                    // Profit ordering goes through all candidates to find the ones which fit.
                     skipped_transactions += 1;
-                    if skipped_transactions > MAX_SKIPPED_TRANSACTIONS {
+                    if by_profit.is_none() && skipped_transactions > MAX_SKIPPED_TRANSACTIONS {
                         debug!(target: "miner", "Reached skipped transactions threshold. Assuming block is full.");
                         break;
                     }
                 }
+                Err(Error(
+                    ErrorKind::Execution(ExecutionError::BlockBlobGasLimitReached {
+                        blob_gas_limit,
+                        blob_gas_used,
+                        blob_gas,
+                    }),
+                    _,
+                )) => {
+                    debug!(target: "miner", "Skipping adding transaction to block because of blob gas limit: {:?} (limit: {:?}, used: {:?}, blob gas: {:?})", hash, blob_gas_limit, blob_gas_used, blob_gas);
+
+                    // Penalize transaction if it can't fit into any block
+                    if blob_gas > blob_gas_limit {
+                        debug!(target: "txqueue", "[{:?}] Transaction above block blob gas limit.", hash);
+                        invalid_transactions.insert(hash);
+                    }
+                }
                 // Invalid nonce error can happen only if previous transaction is skipped because of gas limit.
                 // If there is errornous state of transaction queue it will be fixed when next block is imported.
                 Err(Error(
@@ -643,11 +777,25 @@ impl Miner {
                     invalid_transactions.insert(hash);
                 }
                 // imported ok
-                _ => tx_count += 1,
+                Ok(()) => {
+                    if let (Some(ordering), Some(balance)) = (by_profit.as_mut(), author_balance) {
+                        let reward = open_block
+                            .state
+                            .balance(&author)
+                            .map(|after| after.saturating_sub(balance))
+                            .unwrap_or_default();
+                        ordering.included(reward);
+                    }
+                    tx_count += 1
+                }
             }
         }
         let elapsed = block_start.elapsed();
         debug!(target: "miner", "Pushed {} transactions in {} ms", tx_count, took_ms(&elapsed));
+        if let Some(ordering) = by_profit {
+            debug!(target: "miner", "Prepared block #{} with {} transactions, expected reward from transactions: {} wei", block_number, tx_count, ordering.reward());
+            *self.expected_block_reward.write() = Some((block_number, ordering.reward()));
+        }
 
         let block = match open_block.close() {
             Ok(block) => block,
@@ -760,7 +908,7 @@ impl Miner {
         trace!(target: "miner", "seal_block_internally: attempting internal seal.");
 
         let parent_header = match chain.block_header(BlockId::Hash(*block.header.parent_hash())) {
-            Some(h) => match h.decode(self.engine.params().eip1559_transition) {
+            Some(h) => match h.decode(self.engine.params().header_transitions()) {
                 Ok(decoded_hdr) => decoded_hdr,
                 Err(_) => return false,
             },
@@ -1148,6 +1296,17 @@ impl miner::MinerService for Miner {
         self.transaction_queue.all_transaction_hashes()
     }
 
+    fn ready_and_future_transactions<C>(
+        &self,
+        chain: &C,
+    ) -> (Vec<Arc<VerifiedTransaction>>, Vec<Arc<VerifiedTransaction>>)
+    where
+        C: Nonce + Sync,
+    {
+        self.transaction_queue
+            .ready_and_future(CachedNonceClient::new(chain, &self.nonce_cache))
+    }
+
     fn pending_transaction_hashes<C>(&self, chain: &C) -> BTreeSet<H256>
     where
         C: ChainInfo + Sync,
@@ -1258,6 +1417,20 @@ impl miner::MinerService for Miner {
         self.transaction_queue.find(hash)
     }
 
+    fn submit_bundle<C>(&self, chain: &C, bundle: Bundle) -> Result<H256, BundleError>
+    where
+        C: ChainInfo + Sync,
+    {
+        // Reverted transactions are only detectable through receipt status codes.
+        if bundle.block_number < self.engine.params().eip658_transition {
+            return Err(BundleError::RevertsUndetectable);
+        }
+        let best_block = chain.chain_info().best_block_number;
+        let hash = self.bundles.write().import(bundle, best_block)?;
+        debug!(target: "miner", "Imported bundle {:?}", hash);
+        Ok(hash)
+    }
+
     fn remove_transaction(&self, hash: &H256) -> Option<Arc<VerifiedTransaction>> {
         self.transaction_queue
             .remove(::std::iter::once(hash), false)
@@ -1464,6 +1637,10 @@ impl miner::MinerService for Miner {
             // Clear nonce cache
             self.nonce_cache.clear();
             self.balance_cache.clear();
+            // Bundles can't be included in any of the imported blocks anymore
+            self.bundles
+                .write()
+                .cull(chain.chain_info().best_block_number);
         }
 
         // t_nb 10.1 First update gas limit in transaction queue and minimal gas price.
@@ -1596,6 +1773,7 @@ impl miner::MinerService for Miner {
                 header: b.header.clone(),
                 transactions: b.transactions.iter().cloned().map(Into::into).collect(),
                 uncles: b.uncles.to_vec(),
+                withdrawals: b.withdrawals.clone(),
             },
             latest_block_number,
         )
@@ -1618,7 +1796,7 @@ mod tests {
 
     use super::*;
     use accounts::AccountProvider;
-    use crypto::publickey::{Generator, Random};
+    use crypto::publickey::{Generator, KeyPair, Random};
     use hash::keccak;
     use rustc_hex::FromHex;
     use types::BlockNumber;
@@ -1627,6 +1805,7 @@ mod tests {
     use miner::{MinerService, PendingOrdering};
     use test_helpers::{
         dummy_engine_signer_with_address, generate_dummy_client, generate_dummy_client_with_spec,
+        push_block_with_transactions,
     };
     use types::transaction::{Transaction, TypedTransaction};
 
@@ -1664,6 +1843,10 @@ mod tests {
     }
 
     fn miner() -> Miner {
+        miner_with_spec(&Spec::new_test())
+    }
+
+    fn miner_with_spec(spec: &Spec) -> Miner {
         Miner::new(
             MinerOptions {
                 force_sealing: false,
@@ -1689,9 +1872,10 @@ mod tests {
                     no_early_reject: false,
                     allow_non_eoa_sender: false,
                 },
+                pool_spam_protection: Default::default(),
             },
             GasPricer::new_fixed(0u64.into()),
-            &Spec::new_test(),
+            spec,
             ::std::collections::HashSet::new(), // local accounts
         )
     }
@@ -1794,6 +1978,81 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_drop_stale_journaled_transactions() {
+        // given
+        let client = TestBlockChainClient::default();
+        let miner = miner();
+        let fresh = transaction();
+        let stale = transaction();
+        client.set_nonce(stale.sender(), 1.into());
+
+        // when
+        let res = miner.import_journaled_transactions(
+            &client,
+            vec![
+                pool::verifier::Transaction::Local(PendingTransaction::new(fresh.clone(), None)),
+                pool::verifier::Transaction::Unverified(stale.into()),
+            ],
+        );
+
+        // then
+        assert_eq!(res[0], Ok(()));
+        assert_eq!(res[1], Err(transaction::Error::Old));
+        assert_eq!(miner.queued_transaction_hashes(), vec![fresh.hash()]);
+        assert_eq!(miner.local_transactions().len(), 1);
+    }
+
+    #[test]
+    fn should_not_rate_limit_journaled_transactions() {
+        // given
+        let client = TestBlockChainClient::default();
+        let miner = miner();
+        miner
+            .transaction_queue
+            .set_spam_protection(pool::spam::Options {
+                sender_rate_limit: Some(pool::spam::RateLimit {
+                    per_minute: 1,
+                    burst: 1,
+                }),
+                ..Default::default()
+            });
+        let keypair = Random.generate();
+        let transaction = |nonce: u64| {
+            TypedTransaction::Legacy(Transaction {
+                action: Action::Create,
+                value: U256::zero(),
+                data: vec![],
+                gas: U256::from(100_000),
+                gas_price: U256::zero(),
+                nonce: nonce.into(),
+            })
+            .sign(keypair.secret(), Some(TEST_CHAIN_ID))
+        };
+
+        // when
+        let restored = miner.import_journaled_transactions(
+            &client,
+            vec![
+                pool::verifier::Transaction::Unverified(transaction(0).into()),
+                pool::verifier::Transaction::Unverified(transaction(1).into()),
+            ],
+        );
+        let external = miner.import_external_transactions(
+            &client,
+            vec![transaction(2).into(), transaction(3).into()],
+        );
+
+        // then
+        assert_eq!(restored, vec![Ok(()), Ok(())]);
+        // the restored transactions didn't use up the rate limit of the sender
+        assert_eq!(
+            external,
+            vec![Ok(()), Err(transaction::Error::SenderRateLimited)]
+        );
+        assert_eq!(miner.queued_transactions().len(), 3);
+    }
+
     #[test]
     fn should_import_external_transaction() {
         // given
@@ -2010,6 +2269,133 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_build_pending_block_by_profit() {
+        let client = TestBlockChainClient::default();
+        let miner = Miner::new(
+            MinerOptions {
+                tx_queue_strategy: PrioritizationStrategy::ProfitMaximizing,
+                ..miner().options
+            },
+            GasPricer::new_fixed(0u64.into()),
+            &Spec::new_test(),
+            ::std::collections::HashSet::new(),
+        );
+        let best_block = 0;
+
+        // the transaction is simulated against the pending block before it's pushed
+        let res =
+            miner.import_own_transaction(&client, PendingTransaction::new(transaction(), None));
+
+        assert_eq!(res.unwrap(), ());
+        assert_eq!(miner.pending_transactions(best_block).unwrap().len(), 1);
+        assert_eq!(miner.pending_receipts(best_block).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn should_order_pending_block_by_realized_reward() {
+        // given
+        let client = generate_dummy_client_with_spec(Spec::new_null);
+        let funded = KeyPair::from_secret(keccak("").into()).unwrap();
+        let other = Random.generate();
+        let author = Address::from_low_u64_be(0xa);
+        let transfer = |from: &KeyPair, nonce: u64, gas_price: u64, to: Address, value: u64| {
+            TypedTransaction::Legacy(Transaction {
+                action: Action::Call(to),
+                value: value.into(),
+                data: Vec::new(),
+                gas: 21_000.into(),
+                gas_price: gas_price.into(),
+                nonce: nonce.into(),
+            })
+            .sign(from.secret(), Some(TEST_CHAIN_ID))
+        };
+        push_block_with_transactions(
+            &client,
+            &[transfer(&funded, 0, 0, other.address(), 1_000_000_000)],
+        );
+        let miner = Miner::new(
+            MinerOptions {
+                tx_queue_strategy: PrioritizationStrategy::ProfitMaximizing,
+                ..miner().options
+            },
+            GasPricer::new_fixed(0u64.into()),
+            &Spec::new_null(),
+            ::std::collections::HashSet::new(),
+        );
+        miner.set_author(Author::External(author));
+        // the lower gas price is more than made up for by the payment to the author
+        let paying = transfer(&funded, 1, 1, author, 1_000_000);
+        let bidding = transfer(&other, 0, 2, Address::from_low_u64_be(0xb), 0);
+
+        // when
+        let results = miner.import_external_transactions(
+            &*client,
+            vec![paying.clone().into(), bidding.clone().into()],
+        );
+        assert!(results.into_iter().all(|res| res.is_ok()));
+        miner.prepare_pending_block(&*client);
+
+        // then
+        let by_gas_price: Vec<_> = miner
+            .ready_transactions(&*client, 10, PendingOrdering::Priority)
+            .iter()
+            .map(|tx| tx.signed().clone())
+            .collect();
+        assert_eq!(by_gas_price, vec![bidding.clone(), paying.clone()]);
+        let best_block = client.chain_info().best_block_number;
+        assert_eq!(
+            miner.pending_transactions(best_block),
+            Some(vec![paying, bidding])
+        );
+        // fees of both transactions and the payment
+        assert_eq!(
+            miner.expected_block_reward(),
+            Some((best_block + 1, (21_000 + 1_000_000 + 42_000).into()))
+        );
+    }
+
+    #[test]
+    fn should_include_bundle_before_queued_transactions() {
+        let client = TestBlockChainClient::default();
+        let bundled = transaction();
+        let bundle = Bundle {
+            transactions: vec![bundled.clone()],
+            block_number: 1,
+            min_timestamp: None,
+            max_timestamp: None,
+            reverting_hashes: Default::default(),
+        };
+        // reverts are undetectable without EIP-658
+        assert_eq!(
+            miner().submit_bundle(&client, bundle.clone()),
+            Err(BundleError::RevertsUndetectable)
+        );
+        let miner = miner_with_spec(&Spec::new_null());
+
+        assert_eq!(
+            miner.submit_bundle(&client, bundle.clone()),
+            Ok(bundle.hash())
+        );
+        assert_eq!(
+            miner.submit_bundle(
+                &client,
+                Bundle {
+                    block_number: 0,
+                    ..bundle
+                }
+            ),
+            Err(BundleError::StaleBlock { target: 0, best: 0 })
+        );
+
+        let res =
+            miner.import_own_transaction(&client, PendingTransaction::new(transaction(), None));
+        assert_eq!(res.unwrap(), ());
+        let pending = miner.pending_transactions(0).unwrap();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0], bundled);
+    }
+
     #[test]
     fn should_not_seal_unless_enabled() {
         let miner = miner();
@@ -19,13 +19,19 @@
 //! Miner module
 //! Keeps track of transactions and currently sealed pending block.
 
+mod bundles;
 mod cache;
 mod miner;
+mod profit;
 
 pub mod pool_client;
 #[cfg(feature = "stratum")]
 pub mod stratum;
 
+pub use self::bundles::{
+    Bundle, BundleError, MAX_BUNDLES, MAX_BUNDLES_PER_BLOCK, MAX_BUNDLE_HORIZON,
+    MAX_BUNDLE_TRANSACTIONS,
+};
 pub use self::miner::{Author, AuthoringParams, Miner, MinerOptions, Penalization, PendingSet};
 pub use ethcore_miner::{
     local_accounts::LocalAccounts,
@@ -193,6 +199,13 @@ pub trait MinerService: Send + Sync {
     /// NOTE: The transaction is not removed from pending block if there is one.
     fn remove_transaction(&self, hash: &H256) -> Option<Arc<VerifiedTransaction>>;
 
+    /// Adds a bundle of transactions to be included atomically at the top of its target block.
+    ///
+    /// Bundles are kept separately from the transaction queue. Returns the bundle hash.
+    fn submit_bundle<C>(&self, chain: &C, bundle: Bundle) -> Result<H256, BundleError>
+    where
+        C: ChainInfo + Sync;
+
     /// Query transaction from the pool given it's hash.
     fn transaction(&self, hash: &H256) -> Option<Arc<VerifiedTransaction>>;
 
@@ -248,6 +261,15 @@ pub trait MinerService: Send + Sync {
     /// Get a list of all transaction hashes in the pool (some of them might not be ready for inclusion yet).
     fn queued_transaction_hashes(&self) -> Vec<H256>;
 
+    /// Get all transactions in the pool split into ready ones and future ones,
+    /// which are waiting for a transaction with a lower nonce.
+    fn ready_and_future_transactions<C>(
+        &self,
+        chain: &C,
+    ) -> (Vec<Arc<VerifiedTransaction>>, Vec<Arc<VerifiedTransaction>>)
+    where
+        C: Nonce + Sync;
+
     /// Get a list of local transactions with statuses.
     fn local_transactions(&self) -> BTreeMap<H256, local_transactions::Status>;
 
@@ -0,0 +1,483 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Profit-maximizing transaction selection for pending blocks.
+//!
+//! Candidates are executed against the open block before they are pushed and the one
+//! earning the block author the most per unit of gas actually used goes first. Only the
+//! lowest pending nonce of every sender is a candidate at a time. Every included transaction
+//! changes the state the others run on, so the best candidate is simulated again before it's
+//! yielded and put back if it fell behind. Once the simulation budget is spent, the remaining
+//! transactions follow in the order they were given.
+
+use std::{
+    cmp::{self, Ordering},
+    collections::{BTreeMap, BinaryHeap, HashMap, VecDeque},
+};
+
+use ethereum_types::{Address, H256, U256};
+use types::transaction::SignedTransaction;
+
+use error::Error;
+
+/// A transaction together with its position in the given order.
+type Indexed = (usize, SignedTransaction);
+
+/// A simulated transaction.
+struct Candidate {
+    index: usize,
+    transaction: SignedTransaction,
+    gas_used: U256,
+    reward: U256,
+    /// Number of transactions included when the candidate was simulated.
+    simulated_at: usize,
+}
+
+impl Candidate {
+    fn simulated<F>((index, transaction): Indexed, simulated_at: usize, simulate: F) -> Self
+    where
+        F: FnOnce(&SignedTransaction) -> Result<(U256, U256), Error>,
+    {
+        // a failed simulation is ranked last, pushing the transaction reports the failure
+        let (gas_used, reward) = simulate(&transaction).unwrap_or((U256::one(), U256::zero()));
+        Candidate {
+            index,
+            transaction,
+            gas_used: cmp::max(gas_used, U256::one()),
+            reward,
+            simulated_at,
+        }
+    }
+
+    fn hash(&self) -> H256 {
+        self.transaction.hash()
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // compare reward per gas without losing precision in the division
+        (self.reward.full_mul(other.gas_used))
+            .cmp(&other.reward.full_mul(self.gas_used))
+            .then_with(|| other.hash().cmp(&self.hash()))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+/// Orders pending transactions by the reward they realize per gas used.
+///
+/// Call `included` after a yielded transaction made it into the block and `skipped` when it
+/// didn't fit under the block gas limit. Candidates whose gas limit exceeds the gas left in the
+/// block are skipped without being simulated. Skipped transactions are retried once all the
+/// other candidates were tried, but only those which fit under the gas left at that point; any
+/// other failure drops the sender's remaining transactions.
+pub struct ProfitOrdering {
+    /// Not yet simulated transactions of every sender in nonce order.
+    pending: HashMap<Address, VecDeque<Indexed>>,
+    /// Senders whose next transaction needs to be simulated.
+    to_simulate: Vec<Address>,
+    /// Simulated candidates, best first.
+    candidates: BinaryHeap<Candidate>,
+    /// Candidates left once the simulation budget was spent, by their position.
+    unsimulated: BTreeMap<usize, SignedTransaction>,
+    /// Last yielded transaction.
+    current: Option<Indexed>,
+    /// Candidates skipped because of the block gas limit.
+    skipped: Vec<Indexed>,
+    retried: bool,
+    simulations_left: usize,
+    /// Number of transactions included so far.
+    included: usize,
+    reward: U256,
+}
+
+impl ProfitOrdering {
+    /// Create the ordering of the given transactions. Transactions of each sender have to
+    /// be sorted by nonce. At most `max_simulations` transactions are simulated.
+    pub fn new<I: IntoIterator<Item = SignedTransaction>>(
+        transactions: I,
+        max_simulations: usize,
+    ) -> Self {
+        let mut pending = HashMap::<_, VecDeque<_>>::new();
+        let mut to_simulate = Vec::new();
+        for (index, tx) in transactions.into_iter().enumerate() {
+            let sender = tx.sender();
+            let queue = pending.entry(sender).or_default();
+            if queue.is_empty() {
+                to_simulate.push(sender);
+            }
+            queue.push_back((index, tx));
+        }
+        ProfitOrdering {
+            pending,
+            to_simulate,
+            candidates: BinaryHeap::new(),
+            unsimulated: BTreeMap::new(),
+            current: None,
+            skipped: Vec::new(),
+            retried: false,
+            simulations_left: max_simulations,
+            included: 0,
+            reward: U256::zero(),
+        }
+    }
+
+    /// Next transaction to push given the gas left in the block. `simulate` returns the gas
+    /// used and the reward of a transaction executed on top of the block built so far.
+    pub fn next<F>(&mut self, gas_left: U256, mut simulate: F) -> Option<SignedTransaction>
+    where
+        F: FnMut(&SignedTransaction) -> Result<(U256, U256), Error>,
+    {
+        // the previous transaction neither made it nor was skipped, so its sender's later
+        // transactions would fail on nonce
+        if let Some((_, current)) = self.current.take() {
+            self.pending.remove(&current.sender());
+        }
+
+        loop {
+            for sender in self.to_simulate.drain(..) {
+                let next = match self.pending.get_mut(&sender).and_then(|q| q.pop_front()) {
+                    Some(next) => next,
+                    None => continue,
+                };
+                if next.1.tx().gas > gas_left {
+                    if !self.retried {
+                        self.skipped.push(next);
+                    }
+                    continue;
+                }
+                if self.simulations_left == 0 {
+                    self.unsimulated.insert(next.0, next.1);
+                    continue;
+                }
+                self.simulations_left -= 1;
+                self.candidates
+                    .push(Candidate::simulated(next, self.included, &mut simulate));
+            }
+
+            let mut next = None;
+            while let Some(candidate) = self.candidates.pop() {
+                if candidate.transaction.tx().gas > gas_left {
+                    if !self.retried {
+                        self.skipped.push((candidate.index, candidate.transaction));
+                    }
+                    continue;
+                }
+                if candidate.simulated_at == self.included || self.simulations_left == 0 {
+                    next = Some((candidate.index, candidate.transaction));
+                    break;
+                }
+                // the state changed since the simulation, keep the candidate only if it's
+                // still ahead of the next best one
+                self.simulations_left -= 1;
+                let candidate = Candidate::simulated(
+                    (candidate.index, candidate.transaction),
+                    self.included,
+                    &mut simulate,
+                );
+                if self
+                    .candidates
+                    .peek()
+                    .map_or(true, |best| candidate >= *best)
+                {
+                    next = Some((candidate.index, candidate.transaction));
+                    break;
+                }
+                self.candidates.push(candidate);
+            }
+            let next = next.or_else(|| {
+                self.unsimulated.keys().next().cloned().and_then(|index| {
+                    self.unsimulated
+                        .remove(&index)
+                        .map(|transaction| (index, transaction))
+                })
+            });
+            if let Some((index, transaction)) = next {
+                self.current = Some((index, transaction.clone()));
+                return Some(transaction);
+            }
+
+            if self.retried || self.skipped.is_empty() {
+                return None;
+            }
+            self.retried = true;
+            // the block only fills up, so most of the skipped transactions still won't fit
+            let (fitting, too_big): (Vec<_>, Vec<_>) = self
+                .skipped
+                .drain(..)
+                .partition(|(_, transaction)| transaction.tx().gas <= gas_left);
+            trace!(target: "miner", "Retrying {} transactions skipped because of the block gas limit, {} still don't fit.", fitting.len(), too_big.len());
+            for (index, transaction) in fitting {
+                let sender = transaction.sender();
+                self.pending
+                    .entry(sender)
+                    .or_default()
+                    .push_front((index, transaction));
+                self.to_simulate.push(sender);
+            }
+        }
+    }
+
+    /// The last yielded transaction was included in the block, paying `reward` to the
+    /// block author.
+    pub fn included(&mut self, reward: U256) {
+        if let Some((_, transaction)) = self.current.take() {
+            self.included += 1;
+            self.reward = self.reward.saturating_add(reward);
+            self.to_simulate.push(transaction.sender());
+        }
+    }
+
+    /// The last yielded transaction didn't fit under the block gas limit.
+    pub fn skipped(&mut self) {
+        if let Some(current) = self.current.take() {
+            if !self.retried {
+                self.skipped.push(current);
+            }
+        }
+    }
+
+    /// Reward the included transactions earned the block author.
+    pub fn reward(&self) -> U256 {
+        self.reward
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use block::OpenBlock;
+    use crypto::publickey::{Generator, KeyPair, Random};
+    use spec::Spec;
+    use state::CleanupMode;
+    use std::{cell::Cell, sync::Arc};
+    use test_helpers::get_temp_state_db;
+    use types::transaction::{Action, Transaction, TypedTransaction};
+
+    fn transaction(keypair: &KeyPair, nonce: u64, gas: u64) -> SignedTransaction {
+        TypedTransaction::Legacy(Transaction {
+            action: Action::Create,
+            value: U256::zero(),
+            data: Vec::new(),
+            gas: gas.into(),
+            gas_price: U256::zero(),
+            nonce: nonce.into(),
+        })
+        .sign(keypair.secret(), None)
+    }
+
+    #[test]
+    fn orders_by_reward_per_gas_used() {
+        let (a, b) = (Random.generate(), Random.generate());
+        let a0 = transaction(&a, 0, 100_000);
+        let a1 = transaction(&a, 1, 100_000);
+        let b0 = transaction(&b, 0, 100_000);
+        // gas used and reward of every transaction
+        let outcomes: HashMap<H256, (u64, u64)> = vec![
+            (a0.hash(), (50_000, 100_000)),
+            (a1.hash(), (21_000, 210_000)),
+            (b0.hash(), (21_000, 63_000)),
+        ]
+        .into_iter()
+        .collect();
+        let simulate = |tx: &SignedTransaction| {
+            let (gas_used, reward) = outcomes[&tx.hash()];
+            Ok((gas_used.into(), reward.into()))
+        };
+
+        let mut ordering =
+            ProfitOrdering::new(vec![a0.clone(), a1.clone(), b0.clone()], usize::max_value());
+        // b0 earns 3 per gas, a0 only 2, a1 can't go before a0
+        assert_eq!(ordering.next(U256::max_value(), simulate), Some(b0));
+        ordering.included(63_000.into());
+        assert_eq!(ordering.next(U256::max_value(), simulate), Some(a0));
+        ordering.included(100_000.into());
+        assert_eq!(ordering.next(U256::max_value(), simulate), Some(a1));
+        ordering.included(200_000.into());
+        assert_eq!(ordering.next(U256::max_value(), simulate), None);
+        assert_eq!(ordering.reward(), 363_000.into());
+    }
+
+    #[test]
+    fn keeps_given_order_once_out_of_simulations() {
+        let (a, b, c) = (Random.generate(), Random.generate(), Random.generate());
+        let a0 = transaction(&a, 0, 100_000);
+        let b0 = transaction(&b, 0, 100_000);
+        let c0 = transaction(&c, 0, 100_000);
+        let simulated = Cell::new(0);
+        let simulate = |tx: &SignedTransaction| {
+            simulated.set(simulated.get() + 1);
+            // the later a transaction is given, the more it earns
+            let reward = if *tx == a0 { 1 } else { 2 };
+            Ok((21_000.into(), reward.into()))
+        };
+
+        let mut ordering = ProfitOrdering::new(vec![a0.clone(), b0.clone(), c0.clone()], 1);
+        // only a0 is simulated, the others follow in the given order
+        assert_eq!(ordering.next(U256::max_value(), simulate), Some(a0.clone()));
+        ordering.included(1.into());
+        assert_eq!(ordering.next(U256::max_value(), simulate), Some(b0.clone()));
+        ordering.included(2.into());
+        assert_eq!(ordering.next(U256::max_value(), simulate), Some(c0.clone()));
+        ordering.included(2.into());
+        assert_eq!(ordering.next(U256::max_value(), simulate), None);
+        assert_eq!(simulated.get(), 1);
+    }
+
+    #[test]
+    fn drops_sender_after_failure() {
+        let a = Random.generate();
+        let a0 = transaction(&a, 0, 100_000);
+        let a1 = transaction(&a, 1, 100_000);
+        let simulate = |_: &SignedTransaction| Ok((21_000.into(), 0.into()));
+
+        let mut ordering = ProfitOrdering::new(vec![a0.clone(), a1], usize::max_value());
+        assert_eq!(ordering.next(U256::max_value(), simulate), Some(a0.clone()));
+        assert_eq!(ordering.next(U256::max_value(), simulate), None);
+        assert_eq!(ordering.reward(), 0.into());
+    }
+
+    #[test]
+    fn simulates_again_after_inclusion() {
+        let (a, b, c) = (Random.generate(), Random.generate(), Random.generate());
+        let a0 = transaction(&a, 0, 100_000);
+        let b0 = transaction(&b, 0, 100_000);
+        let c0 = transaction(&c, 0, 100_000);
+        let c0_included = Cell::new(false);
+        let simulate = |tx: &SignedTransaction| {
+            // a0 competes with c0 for the same opportunity
+            let reward = match tx {
+                tx if *tx == c0 => 5,
+                tx if *tx == a0 && c0_included.get() => 1,
+                tx if *tx == a0 => 3,
+                _ => 2,
+            };
+            Ok((1.into(), reward.into()))
+        };
+
+        let mut ordering =
+            ProfitOrdering::new(vec![a0.clone(), b0.clone(), c0.clone()], usize::max_value());
+        assert_eq!(ordering.next(U256::max_value(), simulate), Some(c0.clone()));
+        ordering.included(5.into());
+        c0_included.set(true);
+        assert_eq!(ordering.next(U256::max_value(), simulate), Some(b0.clone()));
+        ordering.included(2.into());
+        assert_eq!(ordering.next(U256::max_value(), simulate), Some(a0.clone()));
+        ordering.included(1.into());
+        assert_eq!(ordering.next(U256::max_value(), simulate), None);
+    }
+
+    fn next(
+        ordering: &mut ProfitOrdering,
+        block: &mut OpenBlock,
+        simulations: &Cell<usize>,
+    ) -> Option<SignedTransaction> {
+        let gas_used = block
+            .receipts
+            .last()
+            .map_or_else(U256::zero, |receipt| receipt.gas_used);
+        let gas_left = block.header.gas_limit() - gas_used;
+        ordering.next(gas_left, |tx| {
+            simulations.set(simulations.get() + 1);
+            block.simulate_transaction(tx)
+        })
+    }
+
+    #[test]
+    fn retries_skipped_transactions_only_if_they_fit() {
+        let spec = Spec::new_null();
+        let genesis_header = spec.genesis_header();
+        let db = spec
+            .ensure_db_good(get_temp_state_db(), &Default::default())
+            .unwrap();
+        let mut block = OpenBlock::new(
+            &*spec.engine,
+            Default::default(),
+            false,
+            db,
+            &genesis_header,
+            Arc::new(vec![genesis_header.hash()]),
+            Address::zero(),
+            (3141562.into(), 31415620.into()),
+            vec![],
+            false,
+            None,
+        )
+        .unwrap();
+        block.block_mut().header.set_gas_limit(50_000.into());
+        let (a, b, c) = (Random.generate(), Random.generate(), Random.generate());
+        for keypair in &[&a, &b, &c] {
+            block
+                .block_mut()
+                .state
+                .add_balance(
+                    &keypair.address(),
+                    &1_000_000_000.into(),
+                    CleanupMode::NoEmpty,
+                )
+                .unwrap();
+        }
+        let transfer = |keypair: &KeyPair, gas: u64, gas_price: u64| {
+            TypedTransaction::Legacy(Transaction {
+                action: Action::Call(Address::from_low_u64_be(0xb)),
+                value: U256::zero(),
+                data: Vec::new(),
+                gas: gas.into(),
+                gas_price: gas_price.into(),
+                nonce: U256::zero(),
+            })
+            .sign(keypair.secret(), None)
+        };
+        // a0 fits only into an empty block
+        let a0 = transfer(&a, 40_000, 1);
+        let b0 = transfer(&b, 21_000, 3);
+        let c0 = transfer(&c, 21_000, 2);
+        let simulations = Cell::new(0);
+
+        let mut ordering =
+            ProfitOrdering::new(vec![a0, b0.clone(), c0.clone()], usize::max_value());
+        assert_eq!(
+            next(&mut ordering, &mut block, &simulations),
+            Some(b0.clone())
+        );
+        block.push_transaction(b0, None).unwrap();
+        ordering.included(63_000.into());
+        // a0 doesn't fit anymore, so it's skipped without simulating it again
+        assert_eq!(
+            next(&mut ordering, &mut block, &simulations),
+            Some(c0.clone())
+        );
+        block.push_transaction(c0, None).unwrap();
+        ordering.included(42_000.into());
+        // and it isn't retried, as there is even less gas left now
+        assert_eq!(next(&mut ordering, &mut block, &simulations), None);
+        assert_eq!(simulations.get(), 4);
+        assert_eq!(ordering.reward(), 105_000.into());
+    }
+}
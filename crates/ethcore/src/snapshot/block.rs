@@ -22,7 +22,10 @@ use hash::keccak;
 use rlp::{DecoderError, Rlp, RlpStream};
 use triehash::ordered_trie_root;
 use types::{
-    block::Block, header::Header, transaction::TypedTransaction, views::BlockView, BlockNumber,
+    block::Block,
+    header::{Header, HeaderTransitions},
+    transaction::TypedTransaction,
+    views::BlockView,
 };
 
 const HEADER_FIELDS: usize = 8;
@@ -45,10 +48,10 @@ impl AbridgedBlock {
 
     /// Given a full block view, trim out the parent hash and block number,
     /// producing new rlp.
-    pub fn from_block_view(block_view: &BlockView, eip1559_transition: BlockNumber) -> Self {
+    pub fn from_block_view(block_view: &BlockView, transitions: HeaderTransitions) -> Self {
         let header = block_view.header_view();
-        let eip1559 = header.number() >= eip1559_transition;
-        let seal_fields = header.seal(eip1559);
+        let eip1559 = header.number() >= transitions.eip1559_transition;
+        let seal_fields = header.seal(transitions);
 
         let nmb_of_elements = if eip1559 {
             HEADER_FIELDS + seal_fields.len() + BLOCK_FIELDS + 1
@@ -73,7 +76,7 @@ impl AbridgedBlock {
         // write block values.
 
         TypedTransaction::rlp_append_list(&mut stream, &block_view.transactions());
-        stream.append_list(&block_view.uncles(eip1559_transition));
+        stream.append_list(&block_view.uncles(transitions));
 
         // write seal fields.
         for field in seal_fields {
@@ -81,7 +84,7 @@ impl AbridgedBlock {
         }
 
         if eip1559 {
-            stream.append(&header.base_fee());
+            stream.append(&header.base_fee(transitions));
         }
 
         AbridgedBlock { rlp: stream.out() }
@@ -95,7 +98,7 @@ impl AbridgedBlock {
         parent_hash: H256,
         number: u64,
         receipts_root: H256,
-        eip1559_transition: BlockNumber,
+        transitions: HeaderTransitions,
     ) -> Result<Block, DecoderError> {
         let rlp = Rlp::new(&self.rlp);
 
@@ -112,7 +115,7 @@ impl AbridgedBlock {
         header.set_extra_data(rlp.val_at(7)?);
 
         let transactions = TypedTransaction::decode_rlp_list(&rlp.at(8)?)?;
-        let uncles = Header::decode_rlp_list(&rlp.at(9)?, eip1559_transition)?;
+        let uncles = Header::decode_rlp_list(&rlp.at(9)?, transitions)?;
 
         header.set_transactions_root(ordered_trie_root(rlp.at(8)?.iter().map(|r| {
             if r.is_list() {
@@ -129,7 +132,7 @@ impl AbridgedBlock {
         header.set_uncles_hash(keccak(uncles_rlp.as_raw()));
 
         let mut seal_fields = Vec::new();
-        let last_seal_index = if number >= eip1559_transition {
+        let last_seal_index = if number >= transitions.eip1559_transition {
             rlp.item_count()? - 1
         } else {
             rlp.item_count()?
@@ -140,7 +143,7 @@ impl AbridgedBlock {
         }
         header.set_seal(seal_fields);
 
-        if number >= eip1559_transition {
+        if number >= transitions.eip1559_transition {
             header.set_base_fee(Some(rlp.val_at::<U256>(rlp.item_count()? - 1)?));
         }
 
@@ -148,6 +151,7 @@ impl AbridgedBlock {
             header: header,
             transactions: transactions,
             uncles: uncles,
+            withdrawals: None,
         })
     }
 }
@@ -160,27 +164,41 @@ mod tests {
     use ethereum_types::{Address, H256, U256};
     use types::{
         block::Block,
+        header::HeaderTransitions,
         transaction::{Action, Transaction, TypedTransaction},
         view,
         views::BlockView,
-        BlockNumber,
     };
 
     fn encode_block(b: &Block) -> Bytes {
         b.rlp_bytes()
     }
 
+    fn london() -> HeaderTransitions {
+        HeaderTransitions {
+            eip1559_transition: 0,
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn empty_block_abridging() {
         let b = Block::default();
         let receipts_root = b.header.receipts_root().clone();
         let encoded = encode_block(&b);
 
-        let abridged =
-            AbridgedBlock::from_block_view(&view!(BlockView, &encoded), BlockNumber::max_value());
+        let abridged = AbridgedBlock::from_block_view(
+            &view!(BlockView, &encoded),
+            HeaderTransitions::default(),
+        );
         assert_eq!(
             abridged
-                .to_block(H256::default(), 0, receipts_root, BlockNumber::max_value())
+                .to_block(
+                    H256::default(),
+                    0,
+                    receipts_root,
+                    HeaderTransitions::default()
+                )
                 .unwrap(),
             b
         );
@@ -194,11 +212,10 @@ mod tests {
         let receipts_root = b.header.receipts_root().clone();
         let encoded = encode_block(&b);
 
-        let abridged =
-            AbridgedBlock::from_block_view(&view!(BlockView, &encoded), BlockNumber::default());
+        let abridged = AbridgedBlock::from_block_view(&view!(BlockView, &encoded), london());
         assert_eq!(
             abridged
-                .to_block(H256::default(), 0, receipts_root, BlockNumber::default())
+                .to_block(H256::default(), 0, receipts_root, london())
                 .unwrap(),
             b
         );
@@ -211,11 +228,18 @@ mod tests {
         let receipts_root = b.header.receipts_root().clone();
         let encoded = encode_block(&b);
 
-        let abridged =
-            AbridgedBlock::from_block_view(&view!(BlockView, &encoded), BlockNumber::max_value());
+        let abridged = AbridgedBlock::from_block_view(
+            &view!(BlockView, &encoded),
+            HeaderTransitions::default(),
+        );
         assert_eq!(
             abridged
-                .to_block(H256::default(), 2, receipts_root, BlockNumber::max_value())
+                .to_block(
+                    H256::default(),
+                    2,
+                    receipts_root,
+                    HeaderTransitions::default()
+                )
                 .unwrap(),
             b
         );
@@ -258,11 +282,16 @@ mod tests {
 
         let abridged = AbridgedBlock::from_block_view(
             &view!(BlockView, &encoded[..]),
-            BlockNumber::max_value(),
+            HeaderTransitions::default(),
         );
         assert_eq!(
             abridged
-                .to_block(H256::default(), 0, receipts_root, BlockNumber::max_value())
+                .to_block(
+                    H256::default(),
+                    0,
+                    receipts_root,
+                    HeaderTransitions::default()
+                )
                 .unwrap(),
             b
         );
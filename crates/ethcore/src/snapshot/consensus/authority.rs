@@ -37,8 +37,11 @@ use ethereum_types::{H256, U256};
 use itertools::{Itertools, Position};
 use rlp::{Rlp, RlpStream};
 use types::{
-    encoded, header::Header, ids::BlockId, receipt::TypedReceipt, transaction::TypedTransaction,
-    BlockNumber,
+    encoded,
+    header::{Header, HeaderTransitions},
+    ids::BlockId,
+    receipt::TypedReceipt,
+    transaction::TypedTransaction,
 };
 
 /// Snapshot creation and restoration for PoA chains.
@@ -62,7 +65,7 @@ impl SnapshotComponents for PoaSnapshot {
         sink: &mut ChunkSink,
         _progress: &Progress,
         preferred_size: usize,
-        eip1559_transition: BlockNumber,
+        transitions: HeaderTransitions,
     ) -> Result<(), Error> {
         let number = chain
             .block_number(&block_at)
@@ -109,7 +112,7 @@ impl SnapshotComponents for PoaSnapshot {
             .block(&block_at)
             .and_then(|b| chain.block_receipts(&block_at).map(|r| (b, r)))
             .ok_or_else(|| Error::BlockNotFound(block_at))?;
-        let block = block.decode(eip1559_transition)?;
+        let block = block.decode(transitions)?;
 
         let parent_td = chain
             .block_details(block.header.parent_hash())
@@ -203,7 +206,7 @@ impl ChunkRebuilder {
 
         // decode.
         let header =
-            Header::decode_rlp(&transition_rlp.at(0)?, engine.params().eip1559_transition)?;
+            Header::decode_rlp(&transition_rlp.at(0)?, engine.params().header_transitions())?;
         let epoch_data: Bytes = transition_rlp.val_at(1)?;
 
         trace!(target: "snapshot", "verifying transition to epoch at block {}", header.number());
@@ -353,12 +356,13 @@ impl Rebuilder for ChunkRebuilder {
 
             let last_rlp = rlp.at(num_items - 1)?;
             let block = Block {
-                header: Header::decode_rlp(&last_rlp.at(0)?, engine.params().eip1559_transition)?,
+                header: Header::decode_rlp(&last_rlp.at(0)?, engine.params().header_transitions())?,
                 transactions: TypedTransaction::decode_rlp_list(&last_rlp.at(1)?)?,
                 uncles: Header::decode_rlp_list(
                     &last_rlp.at(2)?,
-                    engine.params().eip1559_transition,
+                    engine.params().header_transitions(),
                 )?,
+                withdrawals: None,
             };
             let block_data = block.rlp_bytes();
             let receipts = TypedReceipt::decode_rlp_list(&last_rlp.at(3)?)?;
@@ -38,7 +38,7 @@ use ethereum_types::H256;
 use rand::rngs::OsRng;
 use rlp::{Rlp, RlpStream};
 use snapshot::{block::AbridgedBlock, Error, ManifestData, Progress};
-use types::{encoded, BlockNumber};
+use types::{encoded, header::HeaderTransitions};
 
 /// Snapshot creation and restoration for PoW chains.
 /// This includes blocks from the head of the chain as a
@@ -70,7 +70,7 @@ impl SnapshotComponents for PowSnapshot {
         chunk_sink: &mut ChunkSink,
         progress: &Progress,
         preferred_size: usize,
-        eip1559_transition: BlockNumber,
+        transitions: HeaderTransitions,
     ) -> Result<(), Error> {
         PowWorker {
             chain: chain,
@@ -80,7 +80,7 @@ impl SnapshotComponents for PowSnapshot {
             progress: progress,
             preferred_size: preferred_size,
         }
-        .chunk_all(self.blocks, eip1559_transition)
+        .chunk_all(self.blocks, transitions)
     }
 
     fn rebuilder(
@@ -123,7 +123,7 @@ impl<'a> PowWorker<'a> {
     fn chunk_all(
         &mut self,
         snapshot_blocks: u64,
-        eip1559_transition: BlockNumber,
+        transitions: HeaderTransitions,
     ) -> Result<(), Error> {
         let mut loaded_size = 0;
         let mut last = self.current_hash;
@@ -146,7 +146,7 @@ impl<'a> PowWorker<'a> {
                 .ok_or_else(|| Error::BlockNotFound(self.current_hash))?;
 
             let abridged_rlp =
-                AbridgedBlock::from_block_view(&block.view(), eip1559_transition).into_inner();
+                AbridgedBlock::from_block_view(&block.view(), transitions).into_inner();
 
             let pair = {
                 let mut pair_stream = RlpStream::new_list(2);
@@ -311,7 +311,7 @@ impl Rebuilder for PowRebuilder {
                 parent_hash,
                 cur_number,
                 receipts_root,
-                engine.params().eip1559_transition,
+                engine.params().header_transitions(),
             )?;
             let block_bytes = encoded::Block::new(block.rlp_bytes());
             let is_best = cur_number == self.best_number;
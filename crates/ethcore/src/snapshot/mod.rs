@@ -67,6 +67,7 @@ pub use types::{
 
 pub mod io;
 pub mod service;
+pub mod snap;
 
 mod account;
 mod block;
@@ -273,7 +274,7 @@ pub fn chunk_secondary<'a>(
             &mut chunk_sink,
             progress,
             PREFERRED_CHUNK_SIZE,
-            chain.eip1559_transition,
+            chain.header_transitions,
         )?;
     }
 
@@ -622,8 +623,10 @@ pub fn verify_old_block(
     if always || rng.gen::<f32>() <= POW_VERIFY_RATE {
         engine.verify_block_unordered(header)?;
         match chain.block_header_data(header.parent_hash()) {
-            Some(parent) => engine
-                .verify_block_family(header, &parent.decode(engine.params().eip1559_transition)?),
+            Some(parent) => engine.verify_block_family(
+                header,
+                &parent.decode(engine.params().header_transitions())?,
+            ),
             None => Ok(()),
         }
     } else {
@@ -30,17 +30,20 @@ use std::{
 
 use super::{
     io::{LooseReader, LooseWriter, SnapshotReader, SnapshotWriter},
+    snap::{MissingNode, SnapData, SnapPivot, SnapRebuilder},
     CreationStatus, ManifestData, Rebuilder, RestorationStatus, SnapshotService, StateRebuilder,
     MAX_CHUNK_SIZE,
 };
 
-use blockchain::{BlockChain, BlockChainDB, BlockChainDBHandler};
+use blockchain::{BlockChain, BlockChainDB, BlockChainDBHandler, BlockProvider};
 use client::{BlockChainClient, BlockInfo, ChainInfo, Client, ClientIoMessage};
 use engines::EthEngine;
-use error::{Error, ErrorKind as SnapshotErrorKind};
+use error::{BlockError, Error, ErrorKind as SnapshotErrorKind};
 use hash::keccak;
 use snapshot::Error as SnapshotError;
-use types::ids::BlockId;
+use triehash::ordered_trie_root;
+use types::{header::Header, ids::BlockId, receipt::TypedReceipt};
+use unexpected::Mismatch;
 
 use io::IoChannel;
 
@@ -49,6 +52,7 @@ use ethereum_types::H256;
 use journaldb::Algorithm;
 use kvdb::DBTransaction;
 use parking_lot::{Mutex, RwLock, RwLockReadGuard};
+use rand::rngs::OsRng;
 use snappy;
 
 /// Helper for removing directories in case of error.
@@ -121,7 +125,7 @@ impl Restoration {
             Default::default(),
             params.genesis,
             raw_db.clone(),
-            params.engine.params().eip1559_transition,
+            params.engine.params().header_transitions(),
         );
         let components = params
             .engine
@@ -235,6 +239,109 @@ impl Restoration {
     }
 }
 
+// Number of missing trie nodes to look for ahead of the requests for them.
+const SNAP_HEAL_LOOKAHEAD: usize = 4096;
+
+/// `snap/1` state restoration manager.
+struct SnapRestoration {
+    pivot: SnapPivot,
+    header: Header,
+    state: SnapRebuilder,
+    chain: BlockChain,
+    guard: Guard,
+    db: Arc<dyn BlockChainDB>,
+}
+
+impl SnapRestoration {
+    // make a new restoration of the pivot block's state, checking the block first.
+    fn new(
+        pivot: SnapPivot,
+        pruning: Algorithm,
+        db: Arc<dyn BlockChainDB>,
+        genesis: &[u8],
+        guard: Guard,
+        engine: &dyn EthEngine,
+    ) -> Result<Self, Error> {
+        let transitions = engine.params().header_transitions();
+        let header = pivot.block.decode_header(transitions);
+
+        let chain = BlockChain::new(Default::default(), genesis, db.clone(), transitions);
+        ::snapshot::verify_old_block(&mut OsRng, &header, engine, &chain, true)?;
+
+        let receipts_root = ordered_trie_root(pivot.receipts.iter().map(TypedReceipt::encode));
+        if receipts_root != *header.receipts_root() {
+            return Err(BlockError::InvalidReceiptsRoot(Mismatch {
+                expected: *header.receipts_root(),
+                found: receipts_root,
+            })
+            .into());
+        }
+
+        Ok(SnapRestoration {
+            pivot,
+            header,
+            state: SnapRebuilder::new(db.key_value().clone(), pruning),
+            chain,
+            guard,
+            db,
+        })
+    }
+
+    // feeds state data, returns whether the whole state has been restored.
+    fn feed(&mut self, data: SnapData) -> Result<bool, Error> {
+        match data {
+            SnapData::Accounts(accounts) => self.state.feed_accounts(&accounts)?,
+            SnapData::Storage {
+                account,
+                slots,
+                complete,
+            } => self.state.feed_storage(account, &slots, complete)?,
+            SnapData::Code(codes) => self.state.feed_code(&codes)?,
+            SnapData::TrieNodes(nodes) => {
+                let accepted = self.state.feed_nodes(&nodes)?;
+                trace!(target: "snapshot", "Accepted {} of {} trie nodes", accepted, nodes.len());
+            }
+            SnapData::RangesComplete => self.state.begin_heal(*self.header.state_root()),
+        }
+        self.state.heal_step(SNAP_HEAL_LOOKAHEAD)?;
+        Ok(self.state.is_complete())
+    }
+
+    // finish up restoration, making the pivot block the best block.
+    fn finalize(self) -> Result<(), Error> {
+        self.state.finalize(
+            *self.header.state_root(),
+            self.header.number(),
+            self.header.hash(),
+        )?;
+
+        let mut batch = self.db.key_value().transaction();
+        self.chain.insert_unordered_block(
+            &mut batch,
+            self.pivot.block,
+            self.pivot.receipts,
+            Some(self.pivot.parent_total_difficulty),
+            true,
+            false,
+        );
+        let genesis_hash = self.chain.genesis_hash();
+        self.chain.insert_epoch_transition(
+            &mut batch,
+            0,
+            ::engines::EpochTransition {
+                block_number: 0,
+                block_hash: genesis_hash,
+                proof: vec![],
+            },
+        );
+        self.db.key_value().write_buffered(batch);
+        self.chain.commit();
+
+        self.guard.disarm();
+        Ok(())
+    }
+}
+
 /// Type alias for client io channel.
 pub type Channel = IoChannel<ClientIoMessage>;
 
@@ -264,6 +371,7 @@ pub struct ServiceParams {
 /// This controls taking snapshots and restoring from them.
 pub struct Service {
     restoration: Mutex<Option<Restoration>>,
+    snap_restoration: Mutex<Option<SnapRestoration>>,
     restoration_db_handler: Box<dyn BlockChainDBHandler>,
     snapshot_root: PathBuf,
     io_channel: Mutex<Channel>,
@@ -286,6 +394,7 @@ impl Service {
     pub fn new(params: ServiceParams) -> Result<Self, Error> {
         let mut service = Service {
             restoration: Mutex::new(None),
+            snap_restoration: Mutex::new(None),
             restoration_db_handler: params.restoration_db_handler,
             snapshot_root: params.snapshot_root,
             io_channel: Mutex::new(params.channel),
@@ -395,7 +504,7 @@ impl Service {
             Default::default(),
             &[],
             next_db.clone(),
-            self.engine.params().eip1559_transition,
+            self.engine.params().header_transitions(),
         );
         let next_chain_info = next_chain.chain_info();
 
@@ -612,8 +721,9 @@ impl Service {
         self.state_chunks.store(0, Ordering::SeqCst);
         self.block_chunks.store(0, Ordering::SeqCst);
 
-        // tear down existing restoration.
+        // tear down existing restorations.
         *res = None;
+        *self.snap_restoration.lock() = None;
 
         // delete and restore the restoration dir.
         if let Err(e) = fs::remove_dir_all(&rest_dir) {
@@ -852,6 +962,106 @@ impl Service {
         Ok(())
     }
 
+    /// Initialize the restoration of the pivot block's state from `snap/1` data synchronously.
+    pub fn init_snap_restore(&self, pivot: SnapPivot) -> Result<(), Error> {
+        let mut res = self.snap_restoration.lock();
+
+        let rest_dir = self.restoration_dir();
+        let rest_db = self.restoration_db();
+
+        self.state_chunks.store(0, Ordering::SeqCst);
+        self.block_chunks.store(0, Ordering::SeqCst);
+
+        // tear down existing restorations.
+        *res = None;
+        *self.restoration.lock() = None;
+
+        if let Err(e) = fs::remove_dir_all(&rest_dir) {
+            match e.kind() {
+                ErrorKind::NotFound => {}
+                _ => return Err(e.into()),
+            }
+        }
+
+        *self.status.lock() = RestorationStatus::Initializing { chunks_done: 0 };
+
+        fs::create_dir_all(&rest_dir)?;
+
+        let restoration = SnapRestoration::new(
+            pivot,
+            self.pruning,
+            self.restoration_db_handler.open(&rest_db)?,
+            &self.genesis_block,
+            Guard::new(rest_db),
+            &*self.engine,
+        );
+        let restoration = match restoration {
+            Ok(restoration) => restoration,
+            Err(e) => {
+                *self.status.lock() = RestorationStatus::Failed;
+                return Err(e);
+            }
+        };
+
+        info!(target: "snapshot", "Restoring state of block #{} over snap", restoration.header.number());
+        *self.status.lock() = RestorationStatus::Ongoing {
+            block_number: restoration.header.number(),
+            state_chunks: 0,
+            block_chunks: 0,
+            state_chunks_done: 0,
+            block_chunks_done: 0,
+        };
+        *res = Some(restoration);
+
+        self.restoring_snapshot.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Feed `snap/1` state data to be processed synchronously. no-op if not restoring from
+    /// `snap/1` data.
+    pub fn feed_snap_data(&self, data: SnapData) {
+        let mut restoration = self.snap_restoration.lock();
+        let result = match *restoration {
+            Some(ref mut rest) => rest.feed(data).and_then(|is_done| {
+                rest.db.key_value().flush()?;
+                Ok(is_done)
+            }),
+            None => return,
+        };
+        self.state_chunks.fetch_add(1, Ordering::SeqCst);
+
+        let result = match result {
+            Ok(true) => self.finalize_snap_restoration(&mut *restoration),
+            other => other.map(drop),
+        };
+        if let Err(e) = result {
+            warn!("Encountered error during snap state restoration: {}", e);
+            *restoration = None;
+            *self.status.lock() = RestorationStatus::Failed;
+            let _ = fs::remove_dir_all(self.restoration_dir());
+        }
+    }
+
+    // finalize the `snap/1` restoration. this accepts an already-locked
+    // restoration as an argument -- so acquiring it again _will_
+    // lead to deadlock.
+    fn finalize_snap_restoration(&self, rest: &mut Option<SnapRestoration>) -> Result<(), Error> {
+        trace!(target: "snapshot", "finalizing snap restoration");
+
+        if let Some(rest) = rest.take() {
+            let db = rest.db.clone();
+            rest.finalize()?;
+            db.key_value().flush()?;
+        }
+
+        self.replace_client_db()?;
+
+        let _ = fs::remove_dir_all(self.restoration_dir());
+        *self.status.lock() = RestorationStatus::Inactive;
+
+        Ok(())
+    }
+
     /// Feed a state chunk to be processed synchronously.
     pub fn feed_state_chunk(&self, hash: H256, chunk: &[u8]) {
         self.feed_chunk(hash, chunk, true);
@@ -959,6 +1169,7 @@ impl SnapshotService for Service {
         trace!(target: "snapshot", "Aborting restore");
         self.restoring_snapshot.store(false, Ordering::SeqCst);
         *self.restoration.lock() = None;
+        *self.snap_restoration.lock() = None;
         *self.status.lock() = RestorationStatus::Inactive;
     }
 
@@ -982,6 +1193,33 @@ impl SnapshotService for Service {
         }
     }
 
+    fn begin_snap_restore(&self, pivot: SnapPivot) {
+        if let Err(e) = self
+            .io_channel
+            .lock()
+            .send(ClientIoMessage::BeginSnapRestoration(pivot))
+        {
+            trace!("Error sending snapshot service message: {:?}", e);
+        }
+    }
+
+    fn restore_snap_data(&self, data: SnapData) {
+        if let Err(e) = self
+            .io_channel
+            .lock()
+            .send(ClientIoMessage::FeedSnapData(data))
+        {
+            trace!("Error sending snapshot service message: {:?}", e);
+        }
+    }
+
+    fn snap_missing_data(&self, max: usize) -> Option<(Vec<MissingNode>, Vec<H256>)> {
+        self.snap_restoration
+            .lock()
+            .as_ref()
+            .map(|rest| (rest.state.missing_nodes(max), rest.state.missing_code(max)))
+    }
+
     fn abort_snapshot(&self) {
         if self.taking_snapshot.load(Ordering::SeqCst) {
             trace!(target: "snapshot", "Aborting snapshot – Snapshot under way");
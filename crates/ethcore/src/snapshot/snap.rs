@@ -0,0 +1,914 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `snap/1` state serving and restoration.
+//!
+//! Serves ranges of accounts and storage slots, contract code and individual
+//! trie nodes of the state at a given root, and rebuilds a state trie from
+//! such data. Ranges are downloaded first; whatever they leave inconsistent
+//! (the state moves on while ranges are fetched from different peers) is then
+//! repaired by "healing" the trie node by node, starting from the root.
+
+use std::{
+    cmp::{self, Ordering},
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
+
+use account_db::{AccountDB, AccountDBMut};
+use bytes::Bytes;
+use db::{DBValue, KeyValueDB};
+use ethereum_types::{H256, U256};
+use ethtrie::{RlpCodec, TrieDB, TrieDBMut};
+use hash::{keccak, KECCAK_EMPTY, KECCAK_NULL_RLP};
+use hash_db::HashDB;
+use journaldb::{self, Algorithm, JournalDB};
+use keccak_hasher::KeccakHasher;
+use rlp::{DecoderError, Rlp, RlpStream};
+use trie::{node::Node, NibbleSlice, NodeCodec, Recorder, Trie, TrieMut};
+use types::{basic_account::BasicAccount, encoded, receipt::TypedReceipt};
+
+use super::Error;
+
+/// Block whose state is restored over `snap/1`, along with the data needed to
+/// make it the best block of the restored chain.
+#[derive(Debug, Clone)]
+pub struct SnapPivot {
+    /// The pivot block.
+    pub block: encoded::Block,
+    /// Receipts of the pivot block.
+    pub receipts: Vec<TypedReceipt>,
+    /// Total difficulty of the parent of the pivot block.
+    pub parent_total_difficulty: U256,
+}
+
+/// State data downloaded over `snap/1`.
+#[derive(Debug, Clone)]
+pub enum SnapData {
+    /// Hashed addresses and slim encodings of consecutive accounts.
+    Accounts(Vec<(H256, Bytes)>),
+    /// Consecutive storage slots of an account.
+    Storage {
+        /// Hashed address of the account.
+        account: H256,
+        /// Hashed slot keys and slot values.
+        slots: Vec<(H256, Bytes)>,
+        /// Whether these are the last slots of the account.
+        complete: bool,
+    },
+    /// Contract code.
+    Code(Vec<Bytes>),
+    /// Trie nodes requested for healing.
+    TrieNodes(Vec<Bytes>),
+    /// All account ranges have been downloaded, healing can start.
+    RangesComplete,
+}
+
+/// Accounts of a contiguous range of the account trie.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AccountRange {
+    /// Hashed addresses and slim encodings of the accounts, in ascending order.
+    pub accounts: Vec<(H256, Bytes)>,
+    /// Trie nodes proving the first and the last key of the range.
+    pub proof: Vec<Bytes>,
+}
+
+/// Storage slots of consecutive accounts.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StorageRanges {
+    /// Hashed slot keys and slot values, in ascending order, for each account served.
+    pub slots: Vec<Vec<(H256, Bytes)>>,
+    /// Trie nodes proving the edges of the last range, if it does not cover
+    /// the whole storage of its account.
+    pub proof: Vec<Bytes>,
+}
+
+/// A trie node missing from the state being restored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingNode {
+    /// Hashed address of the account owning the storage trie, `None` for the account trie.
+    pub account: Option<H256>,
+    /// Path of the node from the root of its trie, in nibbles.
+    pub path: Vec<u8>,
+    /// Hash of the node.
+    pub hash: H256,
+}
+
+impl MissingNode {
+    /// Path set identifying this node in a `GetTrieNodes` request: the compact
+    /// path for account trie nodes, the account hash followed by the compact
+    /// path for storage trie nodes.
+    pub fn path_set(&self) -> Vec<Bytes> {
+        match self.account {
+            None => vec![encode_path(&self.path)],
+            Some(account) => vec![account.as_bytes().to_vec(), encode_path(&self.path)],
+        }
+    }
+}
+
+/// Encode an account in the slim format, in which an empty storage root and
+/// an empty code hash are replaced with empty strings.
+pub fn to_slim_account(account: &BasicAccount) -> Bytes {
+    let mut stream = RlpStream::new_list(4);
+    stream.append(&account.nonce).append(&account.balance);
+    if account.storage_root == KECCAK_NULL_RLP {
+        stream.append_empty_data();
+    } else {
+        stream.append(&account.storage_root);
+    }
+    if account.code_hash == KECCAK_EMPTY {
+        stream.append_empty_data();
+    } else {
+        stream.append(&account.code_hash);
+    }
+    stream.out()
+}
+
+/// Decode an account in the slim format.
+pub fn from_slim_account(data: &[u8]) -> Result<BasicAccount, DecoderError> {
+    let rlp = Rlp::new(data);
+    if rlp.item_count()? != 4 {
+        return Err(DecoderError::RlpIncorrectListLen);
+    }
+    Ok(BasicAccount {
+        nonce: rlp.val_at(0)?,
+        balance: rlp.val_at(1)?,
+        storage_root: if rlp.at(2)?.is_empty() {
+            KECCAK_NULL_RLP
+        } else {
+            rlp.val_at(2)?
+        },
+        code_hash: if rlp.at(3)?.is_empty() {
+            KECCAK_EMPTY
+        } else {
+            rlp.val_at(3)?
+        },
+    })
+}
+
+/// Compact (hex-prefix) encoding of a path of nibbles.
+pub fn encode_path(nibbles: &[u8]) -> Bytes {
+    let mut out = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        out.push(0x10 | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        out.push(0);
+        nibbles
+    };
+    out.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+    out
+}
+
+/// Decode a compact (hex-prefix) encoded path into nibbles.
+pub fn decode_path(encoded: &[u8]) -> Vec<u8> {
+    if encoded.is_empty() {
+        return Vec::new();
+    }
+    slice_nibbles(&NibbleSlice::from_encoded(encoded).0)
+}
+
+fn slice_nibbles(slice: &NibbleSlice) -> Vec<u8> {
+    (0..slice.len()).map(|i| slice.at(i)).collect()
+}
+
+fn key_from_nibbles(nibbles: &[u8]) -> Option<H256> {
+    if nibbles.len() != 64 {
+        return None;
+    }
+    let bytes: Vec<u8> = nibbles
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair[1])
+        .collect();
+    Some(H256::from_slice(&bytes))
+}
+
+// record the nodes on the paths to the given keys, each node once.
+fn prove_keys<'a, I>(trie: &TrieDB, keys: I) -> Result<Vec<Bytes>, Error>
+where
+    I: IntoIterator<Item = &'a H256>,
+{
+    let mut proof: Vec<Bytes> = Vec::new();
+    for key in keys {
+        let mut recorder = Recorder::new();
+        trie.get_with(key.as_bytes(), (&mut recorder, |_: &[u8]| ()))?;
+        for record in recorder.drain() {
+            if !proof.contains(&record.data) {
+                proof.push(record.data);
+            }
+        }
+    }
+    Ok(proof)
+}
+
+/// Collect the accounts of the state trie with the given root, starting at
+/// `origin` and stopping after the first account at or beyond `limit` or once
+/// `max_bytes` have been collected. The range always starts at the first account
+/// at or after `origin`, and is proven by the nodes on the paths to `origin` and
+/// to the last account returned.
+pub fn account_range(
+    db: &dyn HashDB<KeccakHasher, DBValue>,
+    root: &H256,
+    origin: &H256,
+    limit: &H256,
+    max_bytes: usize,
+) -> Result<AccountRange, Error> {
+    let trie = TrieDB::new(&db, root)?;
+
+    let mut accounts = Vec::new();
+    let mut size = 0;
+    let mut iter = trie.iter()?;
+    iter.seek(origin.as_bytes())?;
+    for item in iter {
+        let (key, value) = item?;
+        let hash = H256::from_slice(&key);
+        let account: BasicAccount = ::rlp::decode(&*value)?;
+        let slim = to_slim_account(&account);
+
+        size += hash.as_bytes().len() + slim.len();
+        accounts.push((hash, slim));
+        if hash >= *limit || size >= max_bytes {
+            break;
+        }
+    }
+
+    let proof = prove_keys(
+        &trie,
+        Some(origin)
+            .into_iter()
+            .chain(accounts.last().map(|a| &a.0)),
+    )?;
+    Ok(AccountRange { accounts, proof })
+}
+
+/// Collect the storage slots of the given accounts of the state trie with the
+/// given root. `origin` and `limit` bound the slots of the first and of the last
+/// account respectively; collection stops once `max_bytes` have been collected,
+/// possibly in the middle of an account's storage. The last range is proven if
+/// it does not start at the first slot or does not end at the last one.
+pub fn storage_ranges(
+    db: &dyn HashDB<KeccakHasher, DBValue>,
+    root: &H256,
+    accounts: &[H256],
+    origin: &H256,
+    limit: &H256,
+    max_bytes: usize,
+) -> Result<StorageRanges, Error> {
+    let account_trie = TrieDB::new(&db, root)?;
+
+    let mut ranges = StorageRanges::default();
+    let mut size = 0;
+    for (index, account_hash) in accounts.iter().enumerate() {
+        if size >= max_bytes {
+            break;
+        }
+        let account: BasicAccount = match account_trie.get(account_hash.as_bytes())? {
+            Some(value) => ::rlp::decode(&*value)?,
+            None => break,
+        };
+
+        let origin = if index == 0 { *origin } else { H256::zero() };
+        let limit = if index == accounts.len() - 1 {
+            *limit
+        } else {
+            H256::repeat_byte(0xff)
+        };
+
+        let account_db = AccountDB::from_hash(db, *account_hash);
+        let account_db = &(&account_db as &dyn HashDB<_, _>);
+        let storage_trie = TrieDB::new(account_db, &account.storage_root)?;
+
+        let mut slots = Vec::new();
+        let mut truncated = false;
+        let mut iter = storage_trie.iter()?;
+        iter.seek(origin.as_bytes())?;
+        for item in iter {
+            if size >= max_bytes {
+                truncated = true;
+                break;
+            }
+            let (key, value) = item?;
+            let hash = H256::from_slice(&key);
+
+            size += hash.as_bytes().len() + value.len();
+            slots.push((hash, value.to_vec()));
+            if hash >= limit {
+                break;
+            }
+        }
+
+        let needs_proof = truncated || !origin.is_zero() || limit != H256::repeat_byte(0xff);
+        if needs_proof {
+            ranges.proof = prove_keys(
+                &storage_trie,
+                Some(&origin).into_iter().chain(slots.last().map(|s| &s.0)),
+            )?;
+        }
+        ranges.slots.push(slots);
+        if needs_proof {
+            break;
+        }
+    }
+
+    Ok(ranges)
+}
+
+/// Look up contract code by hash, stopping once `max_bytes` have been collected.
+///
+/// Code is stored under account-specific keys (see `account_db`), so only code
+/// also present under its plain hash can be found; unknown hashes are skipped.
+pub fn bytecodes(
+    db: &dyn HashDB<KeccakHasher, DBValue>,
+    hashes: &[H256],
+    max_bytes: usize,
+) -> Vec<Bytes> {
+    let mut codes = Vec::new();
+    let mut size = 0;
+    for hash in hashes {
+        if size >= max_bytes {
+            break;
+        }
+        let code = if *hash == KECCAK_EMPTY {
+            Vec::new()
+        } else {
+            match db.get(hash) {
+                Some(code) => code.to_vec(),
+                None => continue,
+            }
+        };
+        size += code.len();
+        codes.push(code);
+    }
+    codes
+}
+
+/// Look up trie nodes of the state trie with the given root by path. Each path
+/// set is either a single compact path into the account trie, or an account hash
+/// followed by compact paths into the storage trie of that account. Stops at the
+/// first node which can't be found or once `max_bytes` have been collected.
+pub fn trie_nodes(
+    db: &dyn HashDB<KeccakHasher, DBValue>,
+    root: &H256,
+    path_sets: &[Vec<Bytes>],
+    max_bytes: usize,
+) -> Result<Vec<Bytes>, Error> {
+    let account_trie = TrieDB::new(&db, root)?;
+
+    let mut nodes = Vec::new();
+    let mut size = 0;
+    'sets: for path_set in path_sets {
+        match path_set.len() {
+            0 => break,
+            1 => match node_at_path(db, root, &decode_path(&path_set[0])) {
+                Some(node) => {
+                    size += node.len();
+                    nodes.push(node);
+                }
+                None => break,
+            },
+            _ => {
+                if path_set[0].len() != 32 {
+                    break;
+                }
+                let account_hash = H256::from_slice(&path_set[0]);
+                let account: BasicAccount = match account_trie.get(account_hash.as_bytes())? {
+                    Some(value) => ::rlp::decode(&*value)?,
+                    None => break,
+                };
+                let account_db = AccountDB::from_hash(db, account_hash);
+                for path in &path_set[1..] {
+                    match node_at_path(&account_db, &account.storage_root, &decode_path(path)) {
+                        Some(node) => {
+                            size += node.len();
+                            nodes.push(node);
+                        }
+                        None => break 'sets,
+                    }
+                    if size >= max_bytes {
+                        break 'sets;
+                    }
+                }
+            }
+        }
+        if size >= max_bytes {
+            break;
+        }
+    }
+
+    Ok(nodes)
+}
+
+// walk down from `root` along `path`, returning the node found at the end of it.
+fn node_at_path(db: &dyn HashDB<KeccakHasher, DBValue>, root: &H256, path: &[u8]) -> Option<Bytes> {
+    let mut node = db.get(root)?.to_vec();
+    let mut remaining = path;
+    while !remaining.is_empty() {
+        let child = match RlpCodec::decode(&node).ok()? {
+            Node::Extension(partial, child) => {
+                let partial = slice_nibbles(&partial);
+                if !remaining.starts_with(&partial) {
+                    return None;
+                }
+                remaining = &remaining[partial.len()..];
+                child.to_vec()
+            }
+            Node::Branch(children, _) => {
+                let child = children[remaining[0] as usize]?.to_vec();
+                remaining = &remaining[1..];
+                child
+            }
+            Node::Leaf(..) | Node::Empty => return None,
+        };
+        node = match RlpCodec::try_decode_hash(&child) {
+            Some(hash) => db.get(&hash)?.to_vec(),
+            None => child,
+        };
+    }
+    Some(node)
+}
+
+/// Check that `proof` proves `value` to be stored under `key` in the trie with
+/// the given root.
+pub fn verify_proof(root: &H256, key: &H256, value: &[u8], proof: &[Bytes]) -> bool {
+    let mut db = journaldb::new_memory_db();
+    for node in proof {
+        db.insert(node);
+    }
+    let db = &db as &dyn HashDB<KeccakHasher, DBValue>;
+    match TrieDB::new(&db, root).and_then(|trie| trie.get(key.as_bytes())) {
+        Ok(Some(stored)) => &*stored == value,
+        _ => false,
+    }
+}
+
+/// Whether the trie with the given root holds keys after `key`, judging from
+/// the nodes proving `key`. Parts of the trie missing from the proof are
+/// assumed to hold more keys.
+pub fn has_keys_after(root: &H256, key: &H256, proof: &[Bytes]) -> bool {
+    let nodes: HashMap<H256, &Bytes> = proof.iter().map(|node| (keccak(node), node)).collect();
+    let path = slice_nibbles(&NibbleSlice::new(key.as_bytes()));
+    let mut remaining = &path[..];
+    let mut node = match nodes.get(root) {
+        Some(node) => node.to_vec(),
+        None => return true,
+    };
+    loop {
+        let child = match RlpCodec::decode(&node) {
+            Ok(Node::Extension(partial, child)) => {
+                let partial = slice_nibbles(&partial);
+                let len = cmp::min(partial.len(), remaining.len());
+                match partial[..].cmp(&remaining[..len]) {
+                    Ordering::Greater => return true,
+                    Ordering::Less => return false,
+                    Ordering::Equal => remaining = &remaining[len..],
+                }
+                child.to_vec()
+            }
+            Ok(Node::Branch(children, _)) => {
+                let index = match remaining.first() {
+                    Some(index) => *index as usize,
+                    None => return children.iter().any(Option::is_some),
+                };
+                if children[index + 1..].iter().any(Option::is_some) {
+                    return true;
+                }
+                remaining = &remaining[1..];
+                match children[index] {
+                    Some(child) => child.to_vec(),
+                    None => return false,
+                }
+            }
+            Ok(Node::Leaf(..)) | Ok(Node::Empty) => return false,
+            Err(_) => return true,
+        };
+        node = match RlpCodec::try_decode_hash(&child) {
+            Some(hash) => match nodes.get(&hash) {
+                Some(node) => node.to_vec(),
+                None => return true,
+            },
+            None => child,
+        };
+    }
+}
+
+/// Rebuilds the state trie from `snap/1` data: first from account and storage
+/// ranges, then by healing the result against the pivot state root.
+pub struct SnapRebuilder {
+    db: Box<dyn JournalDB>,
+    state_root: H256,
+    // roots of the storage tries still being filled, by account hash.
+    storage_roots: HashMap<H256, H256>,
+    // expected storage roots of accounts whose storage isn't complete yet.
+    pending_storage: HashMap<H256, H256>,
+    known_code: HashMap<H256, H256>, // code hashes mapped to first account with this code.
+    missing_code: HashMap<H256, Vec<H256>>, // maps code hashes to lists of accounts missing that code.
+    // nodes still to be checked while healing, along with whether their children
+    // must be checked too.
+    heal_queue: Vec<(MissingNode, bool)>,
+    // accounts whose storage or code must be checked while healing.
+    heal_accounts: BTreeSet<H256>,
+    // nodes requested for healing, by hash.
+    requested: HashMap<H256, Vec<MissingNode>>,
+    healing: bool,
+}
+
+impl SnapRebuilder {
+    /// Create a new rebuilder to write into the given backing DB.
+    pub fn new(db: Arc<dyn KeyValueDB>, pruning: Algorithm) -> Self {
+        SnapRebuilder {
+            db: journaldb::new(db, pruning, ::db::COL_STATE),
+            state_root: KECCAK_NULL_RLP,
+            storage_roots: HashMap::new(),
+            pending_storage: HashMap::new(),
+            known_code: HashMap::new(),
+            missing_code: HashMap::new(),
+            heal_queue: Vec::new(),
+            heal_accounts: BTreeSet::new(),
+            requested: HashMap::new(),
+            healing: false,
+        }
+    }
+
+    /// Feed a range of accounts in the slim format.
+    pub fn feed_accounts(&mut self, accounts: &[(H256, Bytes)]) -> Result<(), Error> {
+        let mut decoded = Vec::with_capacity(accounts.len());
+        for (hash, slim) in accounts {
+            let account = from_slim_account(slim)?;
+            if account.storage_root != KECCAK_NULL_RLP {
+                self.pending_storage.insert(*hash, account.storage_root);
+            }
+            if account.code_hash != KECCAK_EMPTY {
+                self.require_code(*hash, account.code_hash);
+            }
+            decoded.push((*hash, ::rlp::encode(&account)));
+        }
+
+        {
+            let mut account_trie = if self.state_root != KECCAK_NULL_RLP {
+                TrieDBMut::from_existing(self.db.as_hash_db_mut(), &mut self.state_root)?
+            } else {
+                TrieDBMut::new(self.db.as_hash_db_mut(), &mut self.state_root)
+            };
+            for (hash, account) in decoded {
+                account_trie.insert(hash.as_bytes(), &account)?;
+            }
+        }
+        trace!(target: "snapshot", "current snap state root: {:?}", self.state_root);
+
+        self.commit()
+    }
+
+    /// Feed a range of storage slots of an account. `complete` marks the last
+    /// range of the account's storage.
+    pub fn feed_storage(
+        &mut self,
+        account: H256,
+        slots: &[(H256, Bytes)],
+        complete: bool,
+    ) -> Result<(), Error> {
+        let mut storage_root = self
+            .storage_roots
+            .get(&account)
+            .cloned()
+            .unwrap_or(KECCAK_NULL_RLP);
+        {
+            let mut account_db = AccountDBMut::from_hash(self.db.as_hash_db_mut(), account);
+            let mut storage_trie = if storage_root != KECCAK_NULL_RLP {
+                TrieDBMut::from_existing(&mut account_db, &mut storage_root)?
+            } else {
+                TrieDBMut::new(&mut account_db, &mut storage_root)
+            };
+            for (key, value) in slots {
+                storage_trie.insert(key.as_bytes(), value)?;
+            }
+        }
+
+        if complete {
+            self.storage_roots.remove(&account);
+            // a mismatch is left for healing.
+            if self.pending_storage.get(&account) == Some(&storage_root) {
+                self.pending_storage.remove(&account);
+            }
+        } else {
+            self.storage_roots.insert(account, storage_root);
+        }
+
+        self.commit()
+    }
+
+    /// Feed contract code. Code nobody is waiting for is ignored.
+    pub fn feed_code(&mut self, codes: &[Bytes]) -> Result<(), Error> {
+        for code in codes {
+            let code_hash = keccak(code);
+            if let Some(accounts) = self.missing_code.remove(&code_hash) {
+                for account in &accounts {
+                    AccountDBMut::from_hash(self.db.as_hash_db_mut(), *account)
+                        .emplace(code_hash, DBValue::from_slice(code));
+                }
+                self.known_code.insert(code_hash, accounts[0]);
+            }
+        }
+
+        self.commit()
+    }
+
+    /// Start healing the trie built so far against the given state root.
+    /// Storage not downloaded completely and missing code are healed as well,
+    /// as found in the accounts of the healed trie.
+    pub fn begin_heal(&mut self, root: H256) {
+        self.heal_queue.push((
+            MissingNode {
+                account: None,
+                path: Vec::new(),
+                hash: root,
+            },
+            false,
+        ));
+        self.heal_accounts
+            .extend(self.pending_storage.drain().map(|(account, _)| account));
+        self.heal_accounts
+            .extend(self.missing_code.drain().flat_map(|(_, accounts)| accounts));
+        self.storage_roots.clear();
+        self.healing = true;
+    }
+
+    /// Look for missing trie nodes until `max_requested` are known.
+    ///
+    /// A node present in the database is assumed to have its whole subtrie
+    /// present, which holds for nodes written from ranges; the children of
+    /// healed nodes, and the paths to accounts left incomplete by the ranges,
+    /// are always checked.
+    pub fn heal_step(&mut self, max_requested: usize) -> Result<(), Error> {
+        while self.requested.values().map(Vec::len).sum::<usize>() < max_requested {
+            let (node, expand) = match self.heal_queue.pop() {
+                Some(next) => next,
+                None => break,
+            };
+            let already_requested = self.requested.get(&node.hash).map_or(false, |nodes| {
+                nodes.iter().any(|n| n.account == node.account)
+            });
+            if already_requested {
+                continue;
+            }
+
+            match self.node_data(&node) {
+                Some(data) => {
+                    if expand || self.leads_to_heal_account(&node) {
+                        self.expand(&node, &data)?;
+                    }
+                }
+                None => self
+                    .requested
+                    .entry(node.hash)
+                    .or_insert_with(Vec::new)
+                    .push(node),
+            }
+        }
+        Ok(())
+    }
+
+    /// Feed trie nodes requested for healing. Nodes which weren't requested are
+    /// ignored. Returns the number of nodes accepted.
+    pub fn feed_nodes(&mut self, nodes: &[Bytes]) -> Result<usize, Error> {
+        let mut accepted = 0;
+        for data in nodes {
+            let hash = keccak(data);
+            for node in self.requested.remove(&hash).unwrap_or_else(Vec::new) {
+                match node.account {
+                    None => self
+                        .db
+                        .as_hash_db_mut()
+                        .emplace(hash, DBValue::from_slice(data)),
+                    Some(account) => AccountDBMut::from_hash(self.db.as_hash_db_mut(), account)
+                        .emplace(hash, DBValue::from_slice(data)),
+                }
+                self.heal_queue.push((node, true));
+                accepted += 1;
+            }
+        }
+
+        self.commit()?;
+        Ok(accepted)
+    }
+
+    /// Trie nodes requested for healing and not yet received.
+    pub fn missing_nodes(&self, max: usize) -> Vec<MissingNode> {
+        self.requested
+            .values()
+            .flatten()
+            .take(max)
+            .cloned()
+            .collect()
+    }
+
+    /// Hashes of contract code not yet received.
+    pub fn missing_code(&self, max: usize) -> Vec<H256> {
+        self.missing_code.keys().take(max).cloned().collect()
+    }
+
+    /// Whether healing has found the whole state to be present.
+    pub fn is_complete(&self) -> bool {
+        self.healing
+            && self.heal_queue.is_empty()
+            && self.requested.is_empty()
+            && self.missing_code.is_empty()
+    }
+
+    /// Finalize the restoration of the state with the given root, making a dummy
+    /// journal entry.
+    pub fn finalize(mut self, root: H256, era: u64, id: H256) -> Result<Box<dyn JournalDB>, Error> {
+        let missing = self.missing_code.keys().cloned().collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(Error::MissingCode(missing));
+        }
+        if !self.db.as_hash_db().contains(&root) {
+            return Err(Error::WrongStateRoot(root, self.state_root));
+        }
+
+        let mut batch = self.db.backing().transaction();
+        self.db.journal_under(&mut batch, era, &id)?;
+        self.db.backing().write_buffered(batch);
+
+        Ok(self.db)
+    }
+
+    fn require_code(&mut self, account: H256, code_hash: H256) {
+        match self.known_code.get(&code_hash) {
+            Some(&first_with) => {
+                let code = AccountDB::from_hash(self.db.as_hash_db(), first_with).get(&code_hash);
+                if let Some(code) = code {
+                    AccountDBMut::from_hash(self.db.as_hash_db_mut(), account)
+                        .emplace(code_hash, code);
+                    return;
+                }
+            }
+            None => (),
+        }
+        self.missing_code
+            .entry(code_hash)
+            .or_insert_with(Vec::new)
+            .push(account);
+    }
+
+    fn leads_to_heal_account(&self, node: &MissingNode) -> bool {
+        if node.account.is_some() || self.heal_accounts.is_empty() {
+            return false;
+        }
+        let bound = |fill| {
+            let mut path = node.path.clone();
+            path.resize(64, fill);
+            key_from_nibbles(&path)
+        };
+        match (bound(0), bound(0xf)) {
+            (Some(first), Some(last)) => self.heal_accounts.range(first..=last).next().is_some(),
+            _ => false,
+        }
+    }
+
+    fn node_data(&self, node: &MissingNode) -> Option<DBValue> {
+        match node.account {
+            None => self.db.as_hash_db().get(&node.hash),
+            Some(account) => AccountDB::from_hash(self.db.as_hash_db(), account).get(&node.hash),
+        }
+    }
+
+    // queue the children of a node, and the storage and code of an account leaf.
+    fn expand(&mut self, node: &MissingNode, data: &[u8]) -> Result<(), Error> {
+        match RlpCodec::decode(data)? {
+            Node::Empty => (),
+            Node::Leaf(partial, value) => {
+                if node.account.is_some() {
+                    return Ok(());
+                }
+                let mut path = node.path.clone();
+                path.extend(slice_nibbles(&partial));
+                let account_hash = match key_from_nibbles(&path) {
+                    Some(hash) => hash,
+                    None => return Ok(()),
+                };
+                let account: BasicAccount = ::rlp::decode(value)?;
+                self.heal_accounts.remove(&account_hash);
+                if account.storage_root != KECCAK_NULL_RLP {
+                    self.heal_queue.push((
+                        MissingNode {
+                            account: Some(account_hash),
+                            path: Vec::new(),
+                            hash: account.storage_root,
+                        },
+                        false,
+                    ));
+                }
+                let has_code = AccountDB::from_hash(self.db.as_hash_db(), account_hash)
+                    .contains(&account.code_hash);
+                if account.code_hash != KECCAK_EMPTY && !has_code {
+                    self.require_code(account_hash, account.code_hash);
+                }
+            }
+            Node::Extension(partial, child) => {
+                let mut path = node.path.clone();
+                path.extend(slice_nibbles(&partial));
+                self.expand_child(node.account, path, child)?;
+            }
+            Node::Branch(children, _) => {
+                for (index, child) in children.iter().enumerate() {
+                    if let Some(child) = child {
+                        let mut path = node.path.clone();
+                        path.push(index as u8);
+                        self.expand_child(node.account, path, child)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn expand_child(
+        &mut self,
+        account: Option<H256>,
+        path: Vec<u8>,
+        child: &[u8],
+    ) -> Result<(), Error> {
+        match RlpCodec::try_decode_hash(child) {
+            Some(hash) => {
+                self.heal_queue.push((
+                    MissingNode {
+                        account,
+                        path,
+                        hash,
+                    },
+                    false,
+                ));
+                Ok(())
+            }
+            // inline nodes are part of their parent.
+            None => self.expand(
+                &MissingNode {
+                    account,
+                    path,
+                    hash: keccak(child),
+                },
+                child,
+            ),
+        }
+    }
+
+    fn commit(&mut self) -> Result<(), Error> {
+        let backing = self.db.backing().clone();
+        let mut batch = backing.transaction();
+        // Drain the transaction overlay and put the data into the batch.
+        self.db.inject(&mut batch)?;
+        backing.write_buffered(batch);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slim_account_roundtrip() {
+        let account = BasicAccount {
+            nonce: 1.into(),
+            balance: 2.into(),
+            storage_root: KECCAK_NULL_RLP,
+            code_hash: KECCAK_EMPTY,
+        };
+        let slim = to_slim_account(&account);
+        assert_eq!(Rlp::new(&slim).at(2).unwrap().data().unwrap(), &[] as &[u8]);
+        assert_eq!(from_slim_account(&slim).unwrap(), account);
+
+        let account = BasicAccount {
+            storage_root: H256::repeat_byte(1),
+            code_hash: H256::repeat_byte(2),
+            ..account
+        };
+        assert_eq!(
+            from_slim_account(&to_slim_account(&account)).unwrap(),
+            account
+        );
+    }
+
+    #[test]
+    fn compact_path_roundtrip() {
+        assert_eq!(encode_path(&[]), vec![0x00]);
+        assert_eq!(encode_path(&[1, 2, 3]), vec![0x11, 0x23]);
+        assert_eq!(encode_path(&[1, 2, 3, 4]), vec![0x00, 0x12, 0x34]);
+        for path in &[vec![], vec![0xf], vec![1, 2, 3], vec![0, 1, 2, 3]] {
+            assert_eq!(&decode_path(&encode_path(path)), path);
+        }
+    }
+}
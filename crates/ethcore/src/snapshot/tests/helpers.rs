@@ -175,7 +175,7 @@ pub fn restore(
             Default::default(),
             genesis,
             db.clone(),
-            engine.params().eip1559_transition,
+            engine.params().header_transitions(),
         );
         components.rebuilder(chain, db, manifest).unwrap()
     };
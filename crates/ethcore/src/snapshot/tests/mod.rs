@@ -19,6 +19,7 @@
 mod proof_of_authority;
 mod proof_of_work;
 mod service;
+mod snap;
 mod state;
 
 pub mod helpers;
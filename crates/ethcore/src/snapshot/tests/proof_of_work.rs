@@ -55,7 +55,7 @@ fn chunk_and_restore(amount: u64) {
         Default::default(),
         genesis.encoded().raw(),
         old_db.clone(),
-        engine.params().eip1559_transition,
+        engine.params().header_transitions(),
     );
 
     // build the blockchain.
@@ -105,7 +105,7 @@ fn chunk_and_restore(amount: u64) {
         Default::default(),
         genesis.encoded().raw(),
         new_db.clone(),
-        engine.params().eip1559_transition,
+        engine.params().header_transitions(),
     );
     let mut rebuilder = SNAPSHOT_MODE
         .rebuilder(new_chain, new_db.clone(), &manifest)
@@ -127,7 +127,7 @@ fn chunk_and_restore(amount: u64) {
         Default::default(),
         genesis.encoded().raw(),
         new_db,
-        engine.params().eip1559_transition,
+        engine.params().header_transitions(),
     );
     assert_eq!(new_chain.best_block_hash(), best_hash);
 }
@@ -165,7 +165,7 @@ fn checks_flag() {
         Default::default(),
         genesis.last().encoded().raw(),
         db.clone(),
-        engine.params().eip1559_transition,
+        engine.params().header_transitions(),
     );
 
     let manifest = ::snapshot::ManifestData {
@@ -241,7 +241,8 @@ fn keep_ancient_blocks() {
         let block = bc.block(&block_hash).unwrap();
         client2
             .import_block(
-                Unverified::from_rlp(block.into_inner(), spec.params().eip1559_transition).unwrap(),
+                Unverified::from_rlp(block.into_inner(), spec.params().header_transitions())
+                    .unwrap(),
             )
             .unwrap();
     }
@@ -0,0 +1,351 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `snap/1` state serving and restoration tests.
+
+extern crate rand_xorshift;
+
+use hash::{keccak, KECCAK_EMPTY, KECCAK_NULL_RLP};
+use std::sync::Arc;
+
+use super::helpers::StateProducer;
+use account_db::{AccountDB, AccountDBMut};
+use snapshot::snap::{self, SnapRebuilder};
+use types::basic_account::BasicAccount;
+
+use self::rand_xorshift::XorShiftRng;
+use ethereum_types::{BigEndianHash, H256, U256};
+use ethtrie::{TrieDB, TrieDBMut};
+use hash_db::HashDB;
+use journaldb::{self, Algorithm};
+use keccak_hasher::KeccakHasher;
+use kvdb::DBValue;
+use kvdb_rocksdb::{Database, DatabaseConfig};
+use rand::SeedableRng;
+use tempdir::TempDir;
+use trie::{Trie, TrieMut};
+
+const RNG_SEED: [u8; 16] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+
+fn next_key(key: &H256) -> Option<H256> {
+    key.into_uint()
+        .checked_add(U256::one())
+        .map(|next| BigEndianHash::from_uint(&next))
+}
+
+fn trie_items(db: &dyn HashDB<KeccakHasher, DBValue>, root: &H256) -> Vec<(Vec<u8>, DBValue)> {
+    TrieDB::new(&db, root)
+        .unwrap()
+        .iter()
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+}
+
+// download the whole state at `root` through account and storage ranges.
+fn feed_ranges(
+    db: &dyn HashDB<KeccakHasher, DBValue>,
+    root: &H256,
+    rebuilder: &mut SnapRebuilder,
+    max_bytes: usize,
+) {
+    let limit = H256::repeat_byte(0xff);
+    let accounts = trie_items(db, root);
+    let mut origin = H256::zero();
+    loop {
+        let range = snap::account_range(db, root, &origin, &limit, max_bytes).unwrap();
+        let (last, _) = range.accounts.last().cloned().unwrap();
+        let (_, last_value) = accounts
+            .iter()
+            .find(|(key, _)| &key[..] == last.as_bytes())
+            .unwrap();
+        assert!(snap::verify_proof(root, &last, last_value, &range.proof));
+
+        rebuilder.feed_accounts(&range.accounts).unwrap();
+        for (hash, slim) in &range.accounts {
+            let account = snap::from_slim_account(slim).unwrap();
+            if account.storage_root == KECCAK_NULL_RLP {
+                continue;
+            }
+            let mut slot_origin = H256::zero();
+            loop {
+                let ranges =
+                    snap::storage_ranges(db, root, &[*hash], &slot_origin, &limit, max_bytes)
+                        .unwrap();
+                let slots = &ranges.slots[0];
+                let complete = ranges.proof.is_empty()
+                    || !snap::has_keys_after(
+                        &account.storage_root,
+                        &slots.last().unwrap().0,
+                        &ranges.proof,
+                    );
+                rebuilder.feed_storage(*hash, slots, complete).unwrap();
+                match slots.last().and_then(|s| next_key(&s.0)) {
+                    Some(next) if !complete => slot_origin = next,
+                    _ => break,
+                }
+            }
+        }
+
+        if !snap::has_keys_after(root, &last, &range.proof) {
+            assert_eq!(&accounts.last().unwrap().0[..], last.as_bytes());
+            break;
+        }
+        origin = next_key(&last).unwrap();
+    }
+}
+
+// heal the rebuilt state against `root`, serving nodes and code from `db`.
+fn heal(db: &dyn HashDB<KeccakHasher, DBValue>, root: &H256, rebuilder: &mut SnapRebuilder) {
+    rebuilder.begin_heal(*root);
+    loop {
+        rebuilder.heal_step(64).unwrap();
+        let nodes = rebuilder.missing_nodes(64);
+        let code = rebuilder.missing_code(64);
+        if nodes.is_empty() && code.is_empty() {
+            break;
+        }
+        let path_sets: Vec<_> = nodes.iter().map(|n| n.path_set()).collect();
+        let served = snap::trie_nodes(db, root, &path_sets, usize::max_value()).unwrap();
+        assert_eq!(served.len(), nodes.len());
+        assert_eq!(rebuilder.feed_nodes(&served).unwrap(), nodes.len());
+        rebuilder
+            .feed_code(&snap::bytecodes(db, &code, usize::max_value()))
+            .unwrap();
+    }
+    assert!(rebuilder.is_complete());
+}
+
+fn assert_same_state(
+    old_db: &dyn HashDB<KeccakHasher, DBValue>,
+    new_db: &dyn HashDB<KeccakHasher, DBValue>,
+    root: &H256,
+) {
+    let accounts = trie_items(old_db, root);
+    assert_eq!(accounts, trie_items(new_db, root));
+    for (key, value) in accounts {
+        let hash = H256::from_slice(&key);
+        let account: BasicAccount = ::rlp::decode(&value).unwrap();
+        assert_eq!(
+            trie_items(&AccountDB::from_hash(old_db, hash), &account.storage_root),
+            trie_items(&AccountDB::from_hash(new_db, hash), &account.storage_root)
+        );
+        if account.code_hash != KECCAK_EMPTY {
+            assert!(AccountDB::from_hash(new_db, hash).contains(&account.code_hash));
+        }
+    }
+}
+
+fn restoration_db(tempdir: &TempDir) -> Arc<dyn::ethcore_db::KeyValueDB> {
+    let db_cfg = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+    let db = Database::open(&db_cfg, &tempdir.path().to_string_lossy()).unwrap();
+    Arc::new(::ethcore_db::DatabaseWithMetrics::new(db))
+}
+
+#[test]
+fn serves_account_range_with_proof() {
+    let mut producer = StateProducer::new();
+    let mut rng = XorShiftRng::from_seed(RNG_SEED);
+    let mut db = journaldb::new_memory_db();
+    for _ in 0..50 {
+        producer.tick(&mut rng, &mut db);
+    }
+    let root = producer.state_root();
+    let accounts = trie_items(&db, &root);
+    let limit = H256::repeat_byte(0xff);
+
+    let range = snap::account_range(&db, &root, &H256::zero(), &limit, usize::max_value()).unwrap();
+    assert_eq!(range.accounts.len(), accounts.len());
+    for ((hash, slim), (key, value)) in range.accounts.iter().zip(&accounts) {
+        assert_eq!(hash.as_bytes(), &key[..]);
+        assert_eq!(
+            snap::from_slim_account(slim).unwrap(),
+            ::rlp::decode::<BasicAccount>(value).unwrap()
+        );
+    }
+
+    // a range starting in the middle, cut by size.
+    let origin = H256::from_slice(&accounts[accounts.len() / 2].0);
+    let range = snap::account_range(&db, &root, &origin, &limit, 1).unwrap();
+    assert_eq!(range.accounts.len(), 1);
+    assert_eq!(range.accounts[0].0, origin);
+    let full = ::rlp::encode(&snap::from_slim_account(&range.accounts[0].1).unwrap());
+    assert!(snap::verify_proof(&root, &origin, &full, &range.proof));
+    assert!(!snap::verify_proof(&root, &origin, b"bogus", &range.proof));
+    assert!(!snap::verify_proof(
+        &keccak(b"other root"),
+        &origin,
+        &full,
+        &range.proof
+    ));
+
+    // the range is cut after the first account beyond the limit.
+    let range =
+        snap::account_range(&db, &root, &H256::zero(), &origin, usize::max_value()).unwrap();
+    assert_eq!(range.accounts.len(), accounts.len() / 2 + 1);
+    assert!(snap::has_keys_after(&root, &origin, &range.proof));
+
+    let last = H256::from_slice(&accounts[accounts.len() - 1].0);
+    let range = snap::account_range(&db, &root, &last, &limit, usize::max_value()).unwrap();
+    assert_eq!(range.accounts.len(), 1);
+    assert!(!snap::has_keys_after(&root, &last, &range.proof));
+}
+
+#[test]
+fn serves_storage_ranges_and_trie_nodes() {
+    let mut producer = StateProducer::new();
+    let mut rng = XorShiftRng::from_seed(RNG_SEED);
+    let mut db = journaldb::new_memory_db();
+    for _ in 0..50 {
+        producer.tick(&mut rng, &mut db);
+    }
+    let root = producer.state_root();
+    let (hash, account) = trie_items(&db, &root)
+        .into_iter()
+        .map(|(key, value)| {
+            (
+                H256::from_slice(&key),
+                ::rlp::decode::<BasicAccount>(&value).unwrap(),
+            )
+        })
+        .find(|(_, account)| account.storage_root != KECCAK_NULL_RLP)
+        .unwrap();
+    let slots = trie_items(&AccountDB::from_hash(&db, hash), &account.storage_root);
+    let limit = H256::repeat_byte(0xff);
+
+    // complete storage needs no proof.
+    let ranges = snap::storage_ranges(
+        &db,
+        &root,
+        &[hash],
+        &H256::zero(),
+        &limit,
+        usize::max_value(),
+    )
+    .unwrap();
+    assert_eq!(ranges.slots.len(), 1);
+    assert_eq!(ranges.slots[0].len(), slots.len());
+    assert!(ranges.proof.is_empty());
+
+    // partial storage is proven.
+    let ranges = snap::storage_ranges(&db, &root, &[hash], &H256::zero(), &limit, 1).unwrap();
+    assert_eq!(ranges.slots[0].len(), 1);
+    let (key, value) = ranges.slots[0][0].clone();
+    assert!(snap::verify_proof(
+        &account.storage_root,
+        &key,
+        &value,
+        &ranges.proof
+    ));
+
+    // the root node of the account trie and of the storage trie.
+    let nodes = snap::trie_nodes(
+        &db,
+        &root,
+        &[
+            vec![snap::encode_path(&[])],
+            vec![hash.as_bytes().to_vec(), snap::encode_path(&[])],
+        ],
+        usize::max_value(),
+    )
+    .unwrap();
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(keccak(&nodes[0]), root);
+    assert_eq!(keccak(&nodes[1]), account.storage_root);
+
+    // serving stops at an unknown path.
+    let nodes = snap::trie_nodes(
+        &db,
+        &root,
+        &[
+            vec![snap::encode_path(&[0; 63])],
+            vec![snap::encode_path(&[])],
+        ],
+        usize::max_value(),
+    )
+    .unwrap();
+    assert!(nodes.is_empty());
+}
+
+#[test]
+fn rebuilds_state_from_ranges() {
+    let mut producer = StateProducer::new();
+    let mut rng = XorShiftRng::from_seed(RNG_SEED);
+    let mut old_db = journaldb::new_memory_db();
+    for _ in 0..100 {
+        producer.tick(&mut rng, &mut old_db);
+    }
+    let root = producer.state_root();
+
+    let tempdir = TempDir::new("").unwrap();
+    let db = restoration_db(&tempdir);
+    {
+        let mut rebuilder = SnapRebuilder::new(db.clone(), Algorithm::OverlayRecent);
+        feed_ranges(&old_db, &root, &mut rebuilder, 4096);
+        heal(&old_db, &root, &mut rebuilder);
+        rebuilder.finalize(root, 1000, H256::default()).unwrap();
+    }
+
+    let new_db = journaldb::new(db, Algorithm::OverlayRecent, ::db::COL_STATE);
+    assert_eq!(new_db.earliest_era(), Some(1000));
+    assert_same_state(&old_db, new_db.as_hash_db(), &root);
+}
+
+#[test]
+fn heals_state_which_moved_during_download() {
+    let mut producer = StateProducer::new();
+    let mut rng = XorShiftRng::from_seed(RNG_SEED);
+    let mut old_db = journaldb::new_memory_db();
+    for _ in 0..100 {
+        producer.tick(&mut rng, &mut old_db);
+    }
+
+    let tempdir = TempDir::new("").unwrap();
+    let db = restoration_db(&tempdir);
+    let mut rebuilder = SnapRebuilder::new(db.clone(), Algorithm::OverlayRecent);
+    feed_ranges(&old_db, &producer.state_root(), &mut rebuilder, 4096);
+
+    // the state changes once the ranges are downloaded.
+    for _ in 0..20 {
+        producer.tick(&mut rng, &mut old_db);
+    }
+
+    // and gains an account with code, available under its plain hash as well.
+    let code = b"this is definitely code".to_vec();
+    let code_hash = old_db.insert(&code);
+    let code_account = H256::repeat_byte(0x42);
+    AccountDBMut::from_hash(&mut old_db, code_account)
+        .emplace(code_hash, DBValue::from_slice(&code));
+    let mut root = producer.state_root();
+    {
+        let mut trie = TrieDBMut::from_existing(&mut old_db, &mut root).unwrap();
+        let account = BasicAccount {
+            nonce: 0.into(),
+            balance: 1.into(),
+            storage_root: KECCAK_NULL_RLP,
+            code_hash,
+        };
+        trie.insert(code_account.as_bytes(), &::rlp::encode(&account))
+            .unwrap();
+    }
+
+    assert!(!rebuilder.is_complete());
+    heal(&old_db, &root, &mut rebuilder);
+    rebuilder.finalize(root, 1000, H256::default()).unwrap();
+
+    let new_db = journaldb::new(db, Algorithm::OverlayRecent, ::db::COL_STATE);
+    assert_same_state(&old_db, new_db.as_hash_db(), &root);
+}
@@ -14,7 +14,10 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use super::{CreationStatus, ManifestData, RestorationStatus};
+use super::{
+    snap::{MissingNode, SnapData, SnapPivot},
+    CreationStatus, ManifestData, RestorationStatus,
+};
 use bytes::Bytes;
 use ethereum_types::H256;
 
@@ -61,6 +64,18 @@ pub trait SnapshotService: Sync + Send {
     /// no-op if currently restoring.
     fn restore_block_chunk(&self, hash: H256, chunk: Bytes);
 
+    /// Begin restoring the state of the given pivot block from `snap/1` data.
+    /// Any restoration in progress is reset.
+    fn begin_snap_restore(&self, pivot: SnapPivot);
+
+    /// Feed `snap/1` state data to the service to be processed asynchronously.
+    /// no-op if not currently restoring from `snap/1` data.
+    fn restore_snap_data(&self, data: SnapData);
+
+    /// Trie nodes and code hashes still missing from the `snap/1` restoration,
+    /// at most `max` of each. `None` if not currently restoring from `snap/1` data.
+    fn snap_missing_data(&self, max: usize) -> Option<(Vec<MissingNode>, Vec<H256>)>;
+
     /// Abort in-progress snapshotting if there is one.
     fn abort_snapshot(&self);
 
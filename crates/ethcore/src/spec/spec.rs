@@ -31,8 +31,11 @@ use hash::{keccak, KECCAK_NULL_RLP};
 use parking_lot::RwLock;
 use rlp::{Rlp, RlpStream};
 use rustc_hex::FromHex;
-use types::{header::Header, BlockNumber};
-use vm::{AccessList, ActionParams, ActionValue, CallType, EnvInfo, ParamsType};
+use types::{
+    header::{Header, HeaderTransitions},
+    BlockNumber,
+};
+use vm::{AccessList, ActionParams, ActionValue, CallType, EnvInfo, ParamsType, TransientState};
 
 use builtin::Builtin;
 use engines::{
@@ -149,6 +152,24 @@ pub struct CommonParams {
     pub eip3541_transition: BlockNumber,
     /// Number of first block where EIP-3607 rule begins.
     pub eip3607_transition: BlockNumber,
+    /// Number of first block where EIP-3651 rules begin. Warm COINBASE.
+    pub eip3651_transition: BlockNumber,
+    /// Number of first block where EIP-3855 rules begin. PUSH0 opcode.
+    pub eip3855_transition: BlockNumber,
+    /// Number of first block where EIP-3860 rules begin. Initcode size limit and metering.
+    pub eip3860_transition: BlockNumber,
+    /// Number of first block where EIP-4895 rules begin. Beacon chain withdrawals.
+    pub eip4895_transition: BlockNumber,
+    /// Number of first block where EIP-1153 rules begin. Transient storage opcodes.
+    pub eip1153_transition: BlockNumber,
+    /// Number of first block where EIP-5656 rules begin. MCOPY opcode.
+    pub eip5656_transition: BlockNumber,
+    /// Number of first block where EIP-6780 rules begin. SELFDESTRUCT only in same transaction.
+    pub eip6780_transition: BlockNumber,
+    /// Number of first block where EIP-4844 rules begin. Blob transactions and blob gas.
+    pub eip4844_transition: BlockNumber,
+    /// Number of first block where EIP-7516 rules begin. BLOBBASEFEE opcode.
+    pub eip7516_transition: BlockNumber,
     /// Number of first block where dust cleanup rules (EIP-168 and EIP169) begin.
     pub dust_protection_transition: BlockNumber,
     /// Nonce cap increase per block. Nonce cap is only checked if dust protection is enabled.
@@ -244,6 +265,16 @@ impl CommonParams {
         schedule.eip3541 = block_number >= self.eip3541_transition;
         schedule.eip1559 = block_number >= self.eip1559_transition;
         schedule.eip3198 = block_number >= self.eip3198_transition;
+        schedule.eip3651 = block_number >= self.eip3651_transition;
+        schedule.eip3855 = block_number >= self.eip3855_transition;
+        schedule.eip3860 = block_number >= self.eip3860_transition;
+        schedule.eip1153 = block_number >= self.eip1153_transition;
+        schedule.eip5656 = block_number >= self.eip5656_transition;
+        schedule.eip6780 = block_number >= self.eip6780_transition;
+        schedule.eip4844 = block_number >= self.eip4844_transition;
+        schedule.eip7516 = block_number >= self.eip7516_transition;
+        // EIP-1153 and EIP-5656 reuse the opcodes of EIP-2315
+        schedule.have_subs = schedule.have_subs && !schedule.eip1153 && !schedule.eip5656;
         if schedule.eip1559 {
             schedule.eip1559_elasticity_multiplier = self.eip1559_elasticity_multiplier.as_usize();
 
@@ -309,6 +340,15 @@ impl CommonParams {
         }
     }
 
+    /// Blocks from which the optional header fields are present.
+    pub fn header_transitions(&self) -> HeaderTransitions {
+        HeaderTransitions {
+            eip1559_transition: self.eip1559_transition,
+            eip4895_transition: self.eip4895_transition,
+            eip4844_transition: self.eip4844_transition,
+        }
+    }
+
     /// Return Some if the current parameters contain a bugfix hard fork not on block 0.
     pub fn nonzero_bugfix_hard_fork(&self) -> Option<&str> {
         if self.eip155_transition != 0 {
@@ -433,6 +473,33 @@ impl From<ethjson::spec::Params> for CommonParams {
             eip3541_transition: p
                 .eip3541_transition
                 .map_or_else(BlockNumber::max_value, Into::into),
+            eip3651_transition: p
+                .eip3651_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
+            eip3855_transition: p
+                .eip3855_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
+            eip3860_transition: p
+                .eip3860_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
+            eip4895_transition: p
+                .eip4895_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
+            eip1153_transition: p
+                .eip1153_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
+            eip5656_transition: p
+                .eip5656_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
+            eip6780_transition: p
+                .eip6780_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
+            eip4844_transition: p
+                .eip4844_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
+            eip7516_transition: p
+                .eip7516_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
             dust_protection_transition: p
                 .dust_protection_transition
                 .map_or_else(BlockNumber::max_value, Into::into),
@@ -537,6 +604,9 @@ pub struct Spec {
     /// Known nodes on the network in enode format.
     pub nodes: Vec<String>,
 
+    /// Node lists published over DNS (EIP-1459), as `enrtree://` URLs.
+    pub dns_discovery: Vec<String>,
+
     /// The genesis block's parent hash field.
     pub parent_hash: H256,
     /// The genesis block's author field.
@@ -581,6 +651,7 @@ impl Clone for Spec {
             engine: self.engine.clone(),
             data_dir: self.data_dir.clone(),
             nodes: self.nodes.clone(),
+            dns_discovery: self.dns_discovery.clone(),
             parent_hash: self.parent_hash.clone(),
             transactions_root: self.transactions_root.clone(),
             receipts_root: self.receipts_root.clone(),
@@ -644,6 +715,7 @@ fn load_from(spec_params: SpecParams, s: ethjson::spec::Spec) -> Result<Spec, Er
         engine,
         data_dir: s.data_dir.unwrap_or(s.name).into(),
         nodes: s.nodes.unwrap_or_else(Vec::new),
+        dns_discovery: s.dns_discovery.unwrap_or_else(Vec::new),
         parent_hash: g.parent_hash,
         transactions_root: g.transactions_root,
         receipts_root: g.receipts_root,
@@ -751,6 +823,15 @@ impl Spec {
             params.eip3198_transition,
             params.eip3529_transition,
             params.eip3541_transition,
+            params.eip3651_transition,
+            params.eip3855_transition,
+            params.eip3860_transition,
+            params.eip4895_transition,
+            params.eip1153_transition,
+            params.eip5656_transition,
+            params.eip6780_transition,
+            params.eip4844_transition,
+            params.eip7516_transition,
             params.dust_protection_transition,
             params.wasm_activation_transition,
             params.wasm_disable_transition,
@@ -859,6 +940,7 @@ impl Spec {
                 gas_used: U256::zero(),
                 gas_limit: U256::max_value(),
                 base_fee: None,
+                blob_base_fee: None,
             };
 
             if !self.constructors.is_empty() {
@@ -880,6 +962,8 @@ impl Spec {
                         call_type: CallType::None,
                         params_type: ParamsType::Embedded,
                         access_list: AccessList::default(),
+                        transient_state: TransientState::default(),
+                        blob_versioned_hashes: Vec::new(),
                     };
 
                     let mut substate = Substate::new();
@@ -968,6 +1052,14 @@ impl Spec {
             r.iter().map(|f| f.as_raw().to_vec()).collect()
         });
         header.set_base_fee(self.base_fee.clone());
+        if self.params().eip4895_transition == 0 {
+            header.set_withdrawals_root(Some(KECCAK_NULL_RLP));
+        }
+        if self.params().eip4844_transition == 0 {
+            header.set_blob_gas_used(Some(0));
+            header.set_excess_blob_gas(Some(0));
+            header.set_parent_beacon_block_root(Some(H256::zero()));
+        }
         trace!(target: "spec", "Header hash is {}", header.hash());
         header
     }
@@ -976,10 +1068,14 @@ impl Spec {
     pub fn genesis_block(&self) -> Bytes {
         let empty_list = RlpStream::new_list(0).out();
         let header = self.genesis_header();
-        let mut ret = RlpStream::new_list(3);
+        let with_withdrawals = header.withdrawals_root().is_some();
+        let mut ret = RlpStream::new_list(3 + with_withdrawals as usize);
         ret.append(&header);
         ret.append_raw(&empty_list, 1);
         ret.append_raw(&empty_list, 1);
+        if with_withdrawals {
+            ret.append_raw(&empty_list, 1);
+        }
         ret.out()
     }
 
@@ -1081,6 +1177,7 @@ impl Spec {
                 last_hashes: Arc::new(Vec::new()),
                 gas_used: 0.into(),
                 base_fee: genesis.base_fee(),
+                blob_base_fee: genesis.blob_base_fee(),
             };
 
             let from = Address::default();
@@ -527,6 +527,16 @@ impl Account {
         self.balance = self.balance - *x;
     }
 
+    /// Set account balance.
+    pub fn set_balance(&mut self, balance: U256) {
+        self.balance = balance;
+    }
+
+    /// Set account nonce.
+    pub fn set_nonce(&mut self, nonce: U256) {
+        self.nonce = nonce;
+    }
+
     /// Commit the `storage_changes` to the backing DB and update `storage_root`.
     pub fn commit_storage(
         &mut self,
@@ -867,6 +867,16 @@ impl<B: Backend> State<B> {
         self.require(a, false).map(|mut x| x.inc_nonce())
     }
 
+    /// Set the balance of account `a`. Creates the account if it does not exist.
+    pub fn set_balance(&mut self, a: &Address, balance: U256) -> TrieResult<()> {
+        self.require(a, false).map(|mut x| x.set_balance(balance))
+    }
+
+    /// Set the nonce of account `a`. Creates the account if it does not exist.
+    pub fn set_nonce(&mut self, a: &Address, nonce: U256) -> TrieResult<()> {
+        self.require(a, false).map(|mut x| x.set_nonce(nonce))
+    }
+
     /// Mutate storage of account `a` so that it is `value` for `key`.
     pub fn set_storage(&mut self, a: &Address, key: H256, value: H256) -> TrieResult<()> {
         trace!(target: "state", "set_storage({}:{:x} to {:x})", a, key, value);
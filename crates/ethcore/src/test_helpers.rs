@@ -35,11 +35,10 @@ use rlp::{self, RlpStream};
 use tempdir::TempDir;
 use types::{
     encoded,
-    header::Header,
+    header::{Header, HeaderTransitions},
     transaction::{Action, SignedTransaction, Transaction, TypedTransaction},
     view,
     views::BlockView,
-    BlockNumber,
 };
 
 use block::{Drain, OpenBlock};
@@ -226,7 +225,7 @@ where
             .unwrap();
 
         if let Err(e) = client.import_block(
-            Unverified::from_rlp(b.rlp_bytes(), test_engine.params().eip1559_transition).unwrap(),
+            Unverified::from_rlp(b.rlp_bytes(), test_engine.params().header_transitions()).unwrap(),
         ) {
             panic!(
                 "error importing block which is valid by definition: {:?}",
@@ -235,7 +234,7 @@ where
         }
 
         last_header =
-            view!(BlockView, &b.rlp_bytes()).header(test_engine.params().eip1559_transition);
+            view!(BlockView, &b.rlp_bytes()).header(test_engine.params().header_transitions());
         db = b.drain().state.drop().1;
     }
     client.flush_queue();
@@ -275,7 +274,7 @@ pub fn push_blocks_to_client(
         if let Err(e) = client.import_block(
             Unverified::from_rlp(
                 create_test_block(&header),
-                test_spec.params().eip1559_transition,
+                test_spec.params().header_transitions(),
             )
             .unwrap(),
         ) {
@@ -308,7 +307,7 @@ pub fn push_block_with_transactions(client: &Arc<Client>, transactions: &[Signed
         .unwrap();
 
     if let Err(e) = client.import_block(
-        Unverified::from_rlp(b.rlp_bytes(), test_spec.params().eip1559_transition).unwrap(),
+        Unverified::from_rlp(b.rlp_bytes(), test_spec.params().header_transitions()).unwrap(),
     ) {
         panic!(
             "error importing block which is valid by definition: {:?}",
@@ -336,7 +335,7 @@ pub fn get_test_client_with_blocks(blocks: Vec<Bytes>) -> Arc<Client> {
 
     for block in blocks {
         if let Err(e) = client.import_block(
-            Unverified::from_rlp(block, test_spec.params().eip1559_transition).unwrap(),
+            Unverified::from_rlp(block, test_spec.params().header_transitions()).unwrap(),
         ) {
             panic!("error importing block which is well-formed: {:?}", e);
         }
@@ -470,7 +469,7 @@ pub fn generate_dummy_blockchain(block_number: u32) -> BlockChain {
         BlockChainConfig::default(),
         &create_unverifiable_block(0, H256::zero()),
         db.clone(),
-        BlockNumber::max_value(),
+        HeaderTransitions::default(),
     );
 
     let mut batch = db.key_value().transaction();
@@ -498,7 +497,7 @@ pub fn generate_dummy_blockchain_with_extra(block_number: u32) -> BlockChain {
         BlockChainConfig::default(),
         &create_unverifiable_block(0, H256::zero()),
         db.clone(),
-        BlockNumber::max_value(),
+        HeaderTransitions::default(),
     );
 
     let mut batch = db.key_value().transaction();
@@ -530,7 +529,7 @@ pub fn generate_dummy_empty_blockchain() -> BlockChain {
         BlockChainConfig::default(),
         &create_unverifiable_block(0, H256::zero()),
         db.clone(),
-        BlockNumber::max_value(),
+        HeaderTransitions::default(),
     );
     bc
 }
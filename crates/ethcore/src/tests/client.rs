@@ -104,7 +104,7 @@ fn imports_good_block() {
     .unwrap();
     let good_block = get_good_dummy_block();
     if client
-        .import_block(Unverified::from_rlp(good_block, spec.params().eip1559_transition).unwrap())
+        .import_block(Unverified::from_rlp(good_block, spec.params().header_transitions()).unwrap())
         .is_err()
     {
         panic!("error importing block being good by definition");
@@ -150,7 +150,7 @@ fn returns_chain_info() {
     assert_eq!(
         info.best_block_hash,
         block
-            .header(client.engine().params().eip1559_transition)
+            .header(client.engine().params().header_transitions())
             .hash()
     );
 }
@@ -195,7 +195,7 @@ fn returns_block_body() {
     let body = client
         .block_body(BlockId::Hash(
             block
-                .header(client.engine().params().eip1559_transition)
+                .header(client.engine().params().header_transitions())
                 .hash(),
         ))
         .unwrap();
@@ -600,3 +600,295 @@ fn returns_state_root_basic() {
 
     assert!(client.state_data(genesis_header.state_root()).is_some());
 }
+
+#[test]
+fn debug_trace_call_should_report_read_storage() {
+    use client::Call;
+    use ethereum_types::H256;
+    use trace::{GethTrace, GethTracer};
+
+    let client = generate_dummy_client(0);
+    let (mut state, header) = client.latest_state_and_header();
+    let contract = Address::from_low_u64_be(0x1234);
+    let sender = Address::from_low_u64_be(1);
+    // PUSH1 0 SLOAD PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN
+    state
+        .init_code(
+            &contract,
+            vec![
+                0x60, 0x00, 0x54, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+            ],
+        )
+        .unwrap();
+    state
+        .set_storage(&contract, H256::zero(), H256::from_low_u64_be(7))
+        .unwrap();
+    state.commit().unwrap();
+    let transaction = TypedTransaction::Legacy(Transaction {
+        nonce: 0.into(),
+        gas_price: 0.into(),
+        gas: 100_000.into(),
+        action: Action::Call(contract),
+        value: 0.into(),
+        data: Vec::new(),
+    })
+    .fake_sign(sender);
+    let trace = |tracer: GethTracer| {
+        client
+            .debug_trace_call(&transaction, &tracer, &mut state.clone(), &header)
+            .unwrap()
+    };
+
+    match trace(GethTracer::Call {
+        only_top_call: false,
+    }) {
+        GethTrace::Call(frame) => {
+            assert_eq!(frame.from, sender);
+            assert_eq!(frame.to, contract);
+            assert_eq!(frame.output, H256::from_low_u64_be(7).as_bytes().to_vec());
+            assert_eq!(frame.error, None);
+            assert!(frame.calls.is_empty());
+        }
+        other => panic!("Unexpected trace: {:?}", other),
+    }
+
+    // the slot is only read, but is part of the prestate
+    match trace(GethTracer::Prestate { diff_mode: false }) {
+        GethTrace::Prestate(accounts) => {
+            assert!(accounts.contains_key(&sender));
+            assert_eq!(
+                accounts[&contract].storage.get(&H256::zero()),
+                Some(&H256::from_low_u64_be(7))
+            );
+            assert_eq!(accounts[&contract].code.as_ref().map(Vec::len), Some(11));
+        }
+        other => panic!("Unexpected trace: {:?}", other),
+    }
+
+    // only the nonce of the sender changes
+    match trace(GethTracer::Prestate { diff_mode: true }) {
+        GethTrace::PrestateDiff(diff) => {
+            assert!(!diff.pre.contains_key(&contract));
+            assert!(!diff.post.contains_key(&contract));
+            assert_eq!(diff.post[&sender].nonce, Some(1.into()));
+        }
+        other => panic!("Unexpected trace: {:?}", other),
+    }
+}
+
+#[test]
+fn simulate_should_derive_block_fields_from_parent() {
+    use client::{AccountOverride, BlockOverride, Call, CallOverrides, SimulationBlock};
+    use types::header::calc_blob_base_fee;
+
+    let client = test_helpers::generate_dummy_client_with_spec(ethereum::new_cancun_test);
+    let engine = client.engine();
+    let (mut state, header) = client.latest_state_and_header();
+    let contract = Address::from_low_u64_be(0x1234);
+    let call = TypedTransaction::Legacy(Transaction {
+        nonce: 0.into(),
+        gas_price: 0.into(),
+        // more than the block gas limit, only the gas left in the block is given
+        gas: 500_000_000.into(),
+        action: Action::Call(contract),
+        value: 0.into(),
+        data: Vec::new(),
+    })
+    .fake_sign(Address::from_low_u64_be(1));
+
+    let mut overrides = CallOverrides {
+        block: BlockOverride {
+            gas_limit: Some(1_000_000.into()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    overrides.state.insert(
+        contract,
+        AccountOverride {
+            // GASLIMIT PUSH1 0 MSTORE BLOBBASEFEE PUSH1 32 MSTORE PUSH1 64 PUSH1 0 RETURN
+            code: Some(vec![
+                0x45, 0x60, 0x00, 0x52, 0x4a, 0x60, 0x20, 0x52, 0x60, 0x40, 0x60, 0x00, 0xf3,
+            ]),
+            ..Default::default()
+        },
+    );
+    let blocks = vec![
+        SimulationBlock {
+            overrides,
+            transactions: vec![call.clone()],
+        },
+        SimulationBlock {
+            overrides: Default::default(),
+            transactions: vec![call],
+        },
+    ];
+
+    let simulated = client.simulate(&blocks, &mut state, &header).unwrap();
+
+    assert!(simulated[0].header.base_fee().is_some());
+    let parents = [header, simulated[0].header.clone()];
+    for (block, parent) in simulated.iter().zip(parents.iter()) {
+        // the gas limit override is inherited by the following block
+        assert_eq!(block.header.gas_limit(), &U256::from(1_000_000));
+        assert_eq!(block.header.base_fee(), engine.calculate_base_fee(parent));
+        let excess_blob_gas = engine.machine().calc_excess_blob_gas(parent);
+        assert!(excess_blob_gas.is_some());
+        assert_eq!(block.header.excess_blob_gas(), excess_blob_gas);
+        // the call sees the same environment as the returned header
+        let output = &block.results[0].output;
+        assert_eq!(U256::from(&output[..32]), U256::from(1_000_000));
+        assert_eq!(
+            U256::from(&output[32..]),
+            calc_blob_base_fee(excess_blob_gas.unwrap())
+        );
+    }
+}
+
+#[test]
+fn call_with_overrides_should_replace_state_and_block() {
+    use client::{AccountOverride, BlockOverride, Call, CallAnalytics, CallOverrides};
+    use ethereum_types::H256;
+
+    let client = generate_dummy_client(0);
+    let (mut state, header) = client.latest_state_and_header();
+    let contract = Address::from_low_u64_be(0x1234);
+    state
+        .set_storage(
+            &contract,
+            H256::from_low_u64_be(1),
+            H256::from_low_u64_be(9),
+        )
+        .unwrap();
+    state.commit().unwrap();
+    let transaction = TypedTransaction::Legacy(Transaction {
+        nonce: 0.into(),
+        gas_price: 0.into(),
+        gas: 100_000.into(),
+        action: Action::Call(contract),
+        value: 0.into(),
+        data: Vec::new(),
+    })
+    .fake_sign(Address::from_low_u64_be(1));
+
+    let mut overrides = CallOverrides {
+        block: BlockOverride {
+            number: Some(1_000),
+            timestamp: Some(1_700_000_000),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    overrides.state.insert(
+        contract,
+        AccountOverride {
+            balance: Some(500.into()),
+            // ADDRESS BALANCE PUSH1 0 MSTORE
+            // PUSH1 0 SLOAD PUSH1 32 MSTORE
+            // PUSH1 1 SLOAD PUSH1 64 MSTORE
+            // NUMBER PUSH1 96 MSTORE
+            // TIMESTAMP PUSH1 128 MSTORE
+            // PUSH1 160 PUSH1 0 RETURN
+            code: Some(vec![
+                0x30, 0x31, 0x60, 0x00, 0x52, 0x60, 0x00, 0x54, 0x60, 0x20, 0x52, 0x60, 0x01, 0x54,
+                0x60, 0x40, 0x52, 0x43, 0x60, 0x60, 0x52, 0x42, 0x60, 0x80, 0x52, 0x60, 0xa0, 0x60,
+                0x00, 0xf3,
+            ]),
+            state: Some(
+                vec![(H256::zero(), H256::from_low_u64_be(42))]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        },
+    );
+
+    let executed = client
+        .call_with_overrides(
+            &transaction,
+            CallAnalytics::default(),
+            &overrides,
+            &mut state.clone(),
+            &header,
+        )
+        .unwrap();
+
+    let words: Vec<U256> = executed.output.chunks(32).map(U256::from).collect();
+    // The storage override replaces the whole storage, so slot 1 is cleared.
+    assert_eq!(
+        words,
+        vec![
+            U256::from(500),
+            U256::from(42),
+            U256::zero(),
+            U256::from(1_000),
+            U256::from(1_700_000_000),
+        ]
+    );
+    assert_eq!(
+        state
+            .storage_at(&contract, &H256::from_low_u64_be(1))
+            .unwrap(),
+        H256::from_low_u64_be(9)
+    );
+}
+
+#[test]
+fn simulate_should_chain_state_between_blocks() {
+    use client::{AccountOverride, Call, CallOverrides, SimulationBlock, SIMULATED_BLOCK_INTERVAL};
+
+    let client = generate_dummy_client(0);
+    let (mut state, header) = client.latest_state_and_header();
+    let contract = Address::from_low_u64_be(0x1234);
+    let call = |value: U256| {
+        TypedTransaction::Legacy(Transaction {
+            nonce: 0.into(),
+            gas_price: 0.into(),
+            gas: 100_000.into(),
+            action: Action::Call(contract),
+            value,
+            data: Vec::new(),
+        })
+        .fake_sign(Address::from_low_u64_be(1))
+    };
+
+    let mut overrides = CallOverrides::default();
+    overrides.state.insert(
+        contract,
+        AccountOverride {
+            // ADDRESS BALANCE PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN
+            code: Some(vec![
+                0x30, 0x31, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+            ]),
+            ..Default::default()
+        },
+    );
+    let blocks = vec![
+        SimulationBlock {
+            overrides,
+            transactions: vec![call(5.into())],
+        },
+        SimulationBlock {
+            overrides: Default::default(),
+            transactions: vec![call(0.into())],
+        },
+    ];
+
+    let simulated = client.simulate(&blocks, &mut state, &header).unwrap();
+
+    assert_eq!(simulated.len(), 2);
+    assert_eq!(simulated[0].header.parent_hash(), &header.hash());
+    assert_eq!(
+        simulated[1].header.parent_hash(),
+        &simulated[0].header.hash()
+    );
+    assert_eq!(simulated[1].header.number(), header.number() + 2);
+    assert_eq!(
+        simulated[1].header.timestamp(),
+        header.timestamp() + 2 * SIMULATED_BLOCK_INTERVAL
+    );
+    assert_eq!(
+        U256::from(&simulated[1].results[0].output[..]),
+        U256::from(5)
+    );
+}
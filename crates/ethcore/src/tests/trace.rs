@@ -101,7 +101,7 @@ fn can_trace_block_and_uncle_reward() {
         .unwrap();
 
     if let Err(e) = client.import_block(
-        Unverified::from_rlp(root_block.rlp_bytes(), spec.params().eip1559_transition).unwrap(),
+        Unverified::from_rlp(root_block.rlp_bytes(), spec.params().header_transitions()).unwrap(),
     ) {
         panic!(
             "error importing block which is valid by definition: {:?}",
@@ -110,7 +110,7 @@ fn can_trace_block_and_uncle_reward() {
     }
 
     last_header =
-        view!(BlockView, &root_block.rlp_bytes()).header(spec.params().eip1559_transition);
+        view!(BlockView, &root_block.rlp_bytes()).header(spec.params().header_transitions());
     let root_header = last_header.clone();
     db = root_block.drain().state.drop().1;
 
@@ -141,7 +141,7 @@ fn can_trace_block_and_uncle_reward() {
         .unwrap();
 
     if let Err(e) = client.import_block(
-        Unverified::from_rlp(parent_block.rlp_bytes(), spec.params().eip1559_transition).unwrap(),
+        Unverified::from_rlp(parent_block.rlp_bytes(), spec.params().header_transitions()).unwrap(),
     ) {
         panic!(
             "error importing block which is valid by definition: {:?}",
@@ -150,7 +150,7 @@ fn can_trace_block_and_uncle_reward() {
     }
 
     last_header =
-        view!(BlockView, &parent_block.rlp_bytes()).header(spec.params().eip1559_transition);
+        view!(BlockView, &parent_block.rlp_bytes()).header(spec.params().header_transitions());
     db = parent_block.drain().state.drop().1;
 
     last_hashes.push(last_header.hash());
@@ -208,7 +208,7 @@ fn can_trace_block_and_uncle_reward() {
         .unwrap();
 
     let res = client.import_block(
-        Unverified::from_rlp(block.rlp_bytes(), spec.params().eip1559_transition).unwrap(),
+        Unverified::from_rlp(block.rlp_bytes(), spec.params().header_transitions()).unwrap(),
     );
     if res.is_err() {
         panic!("error importing block: {:#?}", res.err().unwrap());
@@ -0,0 +1,513 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tracers producing geth-compatible traces.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use ethereum_types::{Address, BigEndianHash, H256, U256};
+use evm::{CallType, Instruction};
+use trace::{
+    geth::{CallFrame, CallFrameType, StructLog, StructLoggerConfig},
+    trace::{Call, Create, RewardType},
+    Tracer, VMTracer,
+};
+use vm::{ActionParams, Error as VmError};
+
+/// Returns the error message geth reports for a failed call.
+fn geth_error(error: &VmError) -> String {
+    match *error {
+        VmError::OutOfGas => "out of gas".into(),
+        VmError::BadJumpDestination { .. } => "invalid jump destination".into(),
+        VmError::BadInstruction { instruction } => {
+            format!("invalid opcode: opcode {:#x} not defined", instruction)
+        }
+        VmError::StackUnderflow {
+            wanted, on_stack, ..
+        } => format!("stack underflow ({} <=> {})", on_stack, wanted),
+        VmError::OutOfStack { limit, .. } => format!("stack limit reached {}", limit),
+        VmError::MutableCallInStaticContext => "write protection".into(),
+        VmError::OutOfBounds => "return data out of bounds".into(),
+        VmError::Reverted => "execution reverted".into(),
+        ref e => format!("{}", e),
+    }
+}
+
+/// Call tracer. Builds the tree of calls of a transaction, including builtin calls.
+#[derive(Default)]
+pub struct CallTracer {
+    only_top_call: bool,
+    stack: Vec<CallFrame>,
+    root: Option<CallFrame>,
+}
+
+impl CallTracer {
+    /// Creates a new call tracer. If `only_top_call` is set, subcalls are not reported.
+    pub fn new(only_top_call: bool) -> Self {
+        CallTracer {
+            only_top_call,
+            ..Default::default()
+        }
+    }
+
+    fn done(&mut self, gas_used: U256, output: &[u8], error: Option<String>) {
+        let mut frame = self
+            .stack
+            .pop()
+            .expect("Executive invoked prepare_trace_create/call before this function; stack is never empty; qed");
+        frame.gas_used = gas_used;
+        frame.output = output.into();
+        frame.error = error;
+
+        match self.stack.last_mut() {
+            Some(parent) => {
+                if !self.only_top_call {
+                    parent.calls.push(frame);
+                }
+            }
+            None => self.root = Some(frame),
+        }
+    }
+}
+
+impl Tracer for CallTracer {
+    type Output = CallFrame;
+
+    fn prepare_trace_call(&mut self, params: &ActionParams, _depth: usize, _is_builtin: bool) {
+        let call_type = match params.call_type {
+            CallType::CallCode => CallFrameType::CallCode,
+            CallType::DelegateCall => CallFrameType::DelegateCall,
+            CallType::StaticCall => CallFrameType::StaticCall,
+            CallType::Call | CallType::None => CallFrameType::Call,
+        };
+        let call = Call::from(params.clone());
+        self.stack.push(CallFrame {
+            call_type,
+            from: call.from,
+            to: call.to,
+            value: match call_type {
+                CallFrameType::DelegateCall | CallFrameType::StaticCall => None,
+                _ => Some(call.value),
+            },
+            gas: call.gas,
+            gas_used: U256::zero(),
+            input: call.input,
+            output: Vec::new(),
+            error: None,
+            calls: Vec::new(),
+        });
+    }
+
+    fn prepare_trace_create(&mut self, params: &ActionParams) {
+        let to = params.address;
+        let create = Create::from(params.clone());
+        self.stack.push(CallFrame {
+            call_type: CallFrameType::Create,
+            from: create.from,
+            to,
+            value: Some(create.value),
+            gas: create.gas,
+            gas_used: U256::zero(),
+            input: create.init,
+            output: Vec::new(),
+            error: None,
+            calls: Vec::new(),
+        });
+    }
+
+    fn done_trace_call(&mut self, gas_used: U256, output: &[u8]) {
+        self.done(gas_used, output, None);
+    }
+
+    fn done_trace_create(&mut self, gas_used: U256, code: &[u8], _address: Address) {
+        self.done(gas_used, code, None);
+    }
+
+    fn done_trace_reverted(&mut self, gas_used: U256, output: &[u8]) {
+        self.done(gas_used, output, Some(geth_error(&VmError::Reverted)));
+    }
+
+    fn done_trace_failed(&mut self, error: &VmError) {
+        let gas = self.stack.last().map_or_else(U256::zero, |frame| frame.gas);
+        self.done(gas, &[], Some(geth_error(error)));
+    }
+
+    fn trace_suicide(&mut self, address: Address, balance: U256, refund_address: Address) {
+        if self.only_top_call {
+            return;
+        }
+        if let Some(parent) = self.stack.last_mut() {
+            parent.calls.push(CallFrame {
+                call_type: CallFrameType::SelfDestruct,
+                from: address,
+                to: refund_address,
+                value: Some(balance),
+                gas: U256::zero(),
+                gas_used: U256::zero(),
+                input: Vec::new(),
+                output: Vec::new(),
+                error: None,
+                calls: Vec::new(),
+            });
+        }
+    }
+
+    fn trace_reward(&mut self, _: Address, _: U256, _: RewardType) {}
+
+    fn drain(self) -> Vec<CallFrame> {
+        self.root.into_iter().collect()
+    }
+}
+
+/// Collects the addresses of the accounts touched by the calls and creates of a transaction.
+#[derive(Default)]
+pub struct PrestateTracer {
+    touched: BTreeSet<Address>,
+}
+
+impl Tracer for PrestateTracer {
+    type Output = Address;
+
+    fn prepare_trace_call(&mut self, params: &ActionParams, _depth: usize, _is_builtin: bool) {
+        self.touched.insert(params.sender);
+        self.touched.insert(params.address);
+        self.touched.insert(params.code_address);
+    }
+
+    fn prepare_trace_create(&mut self, params: &ActionParams) {
+        self.touched.insert(params.sender);
+        self.touched.insert(params.address);
+    }
+
+    fn done_trace_call(&mut self, _: U256, _: &[u8]) {}
+    fn done_trace_create(&mut self, _: U256, _: &[u8], _: Address) {}
+    fn done_trace_failed(&mut self, _: &VmError) {}
+
+    fn trace_suicide(&mut self, address: Address, _balance: U256, refund_address: Address) {
+        self.touched.insert(address);
+        self.touched.insert(refund_address);
+    }
+
+    fn trace_reward(&mut self, _: Address, _: U256, _: RewardType) {}
+
+    fn drain(self) -> Vec<Address> {
+        self.touched.into_iter().collect()
+    }
+}
+
+#[derive(Default)]
+struct StorageAccessFrame {
+    address: Address,
+    stack: Vec<U256>,
+    instruction: u8,
+}
+
+/// Collects the storage slots read or written by a transaction, keyed by the account owning them.
+#[derive(Default)]
+pub struct StorageAccessTracer {
+    frames: Vec<StorageAccessFrame>,
+    accessed: BTreeMap<Address, BTreeSet<H256>>,
+}
+
+impl VMTracer for StorageAccessTracer {
+    type Output = BTreeMap<Address, BTreeSet<H256>>;
+
+    fn trace_next_instruction(&mut self, _pc: usize, _instruction: u8, _current_gas: U256) -> bool {
+        true
+    }
+
+    fn trace_prepare_execute(
+        &mut self,
+        _pc: usize,
+        instruction: u8,
+        _gas_cost: U256,
+        _mem_written: Option<(usize, usize)>,
+        _store_written: Option<(U256, U256)>,
+    ) {
+        let frame = self
+            .frames
+            .last_mut()
+            .expect("prepare_subtrace is called before any instruction is executed; qed");
+        frame.instruction = instruction;
+
+        match Instruction::from_u8(instruction) {
+            Some(Instruction::SLOAD) | Some(Instruction::SSTORE) => {
+                if let Some(key) = frame.stack.last() {
+                    self.accessed
+                        .entry(frame.address)
+                        .or_default()
+                        .insert(BigEndianHash::from_uint(key));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn trace_executed(&mut self, _gas_used: U256, stack_push: &[U256], _mem: &[u8]) {
+        let frame = self
+            .frames
+            .last_mut()
+            .expect("trace_executed is always called after a trace_prepare_execute; qed");
+
+        let args = Instruction::from_u8(frame.instruction).map_or(0, |op| op.info().args);
+        let len = frame.stack.len();
+        frame.stack.truncate(len.saturating_sub(args));
+        frame.stack.extend_from_slice(stack_push);
+    }
+
+    fn prepare_subtrace(&mut self, params: &ActionParams) {
+        self.frames.push(StorageAccessFrame {
+            address: params.address,
+            ..Default::default()
+        });
+    }
+
+    fn done_subtrace(&mut self) {
+        self.frames.pop();
+    }
+
+    fn drain(self) -> Option<Self::Output> {
+        Some(self.accessed)
+    }
+}
+
+#[derive(Default)]
+struct StructLoggerFrame {
+    stack: Vec<U256>,
+    memory: Vec<u8>,
+    storage: BTreeMap<H256, H256>,
+    instruction: u8,
+    log_index: usize,
+}
+
+/// Struct logger. Reports every executed instruction together with the stack, memory and
+/// storage of the call it belongs to.
+#[derive(Default)]
+pub struct StructLogger {
+    config: StructLoggerConfig,
+    current_gas: U256,
+    frames: Vec<StructLoggerFrame>,
+    logs: Vec<StructLog>,
+}
+
+impl StructLogger {
+    /// Creates a new struct logger.
+    pub fn new(config: StructLoggerConfig) -> Self {
+        StructLogger {
+            config,
+            ..Default::default()
+        }
+    }
+}
+
+impl VMTracer for StructLogger {
+    type Output = Vec<StructLog>;
+
+    fn trace_next_instruction(&mut self, _pc: usize, _instruction: u8, current_gas: U256) -> bool {
+        self.current_gas = current_gas;
+        true
+    }
+
+    fn trace_prepare_execute(
+        &mut self,
+        pc: usize,
+        instruction: u8,
+        gas_cost: U256,
+        _mem_written: Option<(usize, usize)>,
+        store_written: Option<(U256, U256)>,
+    ) {
+        let depth = self.frames.len();
+        let config = self.config;
+        let frame = self
+            .frames
+            .last_mut()
+            .expect("prepare_subtrace is called before any instruction is executed; qed");
+
+        let op = Instruction::from_u8(instruction);
+        if let Some((key, value)) = store_written {
+            frame.storage.insert(
+                BigEndianHash::from_uint(&key),
+                BigEndianHash::from_uint(&value),
+            );
+        }
+        let storage = match op {
+            Some(Instruction::SSTORE) | Some(Instruction::SLOAD) if !config.disable_storage => {
+                Some(frame.storage.clone())
+            }
+            _ => None,
+        };
+
+        frame.instruction = instruction;
+        frame.log_index = self.logs.len();
+        self.logs.push(StructLog {
+            pc,
+            op: op.map_or_else(
+                || format!("opcode {:#x} not defined", instruction),
+                |op| op.info().name.into(),
+            ),
+            gas: self.current_gas,
+            gas_cost,
+            depth,
+            stack: if config.disable_stack {
+                None
+            } else {
+                Some(frame.stack.clone())
+            },
+            memory: if config.enable_memory {
+                Some(frame.memory.clone())
+            } else {
+                None
+            },
+            storage,
+        });
+    }
+
+    fn trace_executed(&mut self, _gas_used: U256, stack_push: &[U256], mem: &[u8]) {
+        let config = self.config;
+        let frame = self
+            .frames
+            .last_mut()
+            .expect("trace_executed is always called after a trace_prepare_execute; qed");
+
+        let op = Instruction::from_u8(frame.instruction);
+        if op == Some(Instruction::SLOAD) && !config.disable_storage {
+            if let (Some(key), Some(value)) = (frame.stack.last(), stack_push.first()) {
+                frame.storage.insert(
+                    BigEndianHash::from_uint(key),
+                    BigEndianHash::from_uint(value),
+                );
+                self.logs[frame.log_index].storage = Some(frame.storage.clone());
+            }
+        }
+
+        let args = op.map_or(0, |op| op.info().args);
+        let len = frame.stack.len();
+        frame.stack.truncate(len.saturating_sub(args));
+        frame.stack.extend_from_slice(stack_push);
+        if config.enable_memory {
+            frame.memory = mem.to_vec();
+        }
+    }
+
+    fn prepare_subtrace(&mut self, _params: &ActionParams) {
+        self.frames.push(StructLoggerFrame::default());
+    }
+
+    fn done_subtrace(&mut self) {
+        self.frames.pop();
+    }
+
+    fn drain(self) -> Option<Vec<StructLog>> {
+        Some(self.logs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn call_tracer_should_nest_calls() {
+        let mut tracer = CallTracer::default();
+
+        tracer.prepare_trace_call(&ActionParams::default(), 0, false);
+        tracer.prepare_trace_call(&ActionParams::default(), 1, false);
+        tracer.done_trace_reverted(U256::from(10), &[1]);
+        tracer.prepare_trace_create(&ActionParams::default());
+        tracer.done_trace_failed(&VmError::OutOfGas);
+        tracer.trace_suicide(Address::zero(), U256::from(5), Address::from_low_u64_be(1));
+        tracer.done_trace_call(U256::from(100), &[]);
+
+        let drained = tracer.drain();
+        assert_eq!(drained.len(), 1);
+        let root = &drained[0];
+        assert_eq!(root.gas_used, U256::from(100));
+        assert_eq!(root.calls.len(), 3);
+        assert_eq!(root.calls[0].error, Some("execution reverted".into()));
+        assert_eq!(root.calls[0].output, vec![1]);
+        assert_eq!(root.calls[1].call_type, CallFrameType::Create);
+        assert_eq!(root.calls[1].error, Some("out of gas".into()));
+        assert_eq!(root.calls[2].call_type, CallFrameType::SelfDestruct);
+        assert_eq!(root.calls[2].value, Some(U256::from(5)));
+    }
+
+    #[test]
+    fn struct_logger_should_track_stack_and_storage() {
+        let mut tracer = StructLogger::default();
+
+        tracer.prepare_subtrace(&ActionParams::default());
+        // PUSH1 0x01
+        tracer.trace_next_instruction(0, 0x60, U256::from(100));
+        tracer.trace_prepare_execute(0, 0x60, U256::from(3), None, None);
+        tracer.trace_executed(U256::from(97), &[U256::from(1)], &[]);
+        // SLOAD
+        tracer.trace_next_instruction(2, 0x54, U256::from(97));
+        tracer.trace_prepare_execute(2, 0x54, U256::from(2100), None, None);
+        tracer.trace_executed(U256::from(0), &[U256::from(7)], &[]);
+        tracer.done_subtrace();
+
+        let logs = tracer.drain().unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].op, "PUSH1");
+        assert_eq!(logs[0].depth, 1);
+        assert_eq!(logs[0].stack, Some(vec![]));
+        assert_eq!(logs[0].storage, None);
+        assert_eq!(logs[1].op, "SLOAD");
+        assert_eq!(logs[1].gas, U256::from(97));
+        assert_eq!(logs[1].stack, Some(vec![U256::from(1)]));
+        assert_eq!(
+            logs[1].storage.as_ref().unwrap()[&H256::from_low_u64_be(1)],
+            H256::from_low_u64_be(7)
+        );
+    }
+
+    #[test]
+    fn storage_access_tracer_should_record_reads_by_storage_owner() {
+        let mut tracer = StorageAccessTracer::default();
+        let caller = Address::from_low_u64_be(1);
+        let library = Address::from_low_u64_be(2);
+
+        tracer.prepare_subtrace(&ActionParams {
+            address: caller,
+            ..Default::default()
+        });
+        // PUSH1 0x05, SLOAD
+        tracer.trace_prepare_execute(0, 0x60, U256::from(3), None, None);
+        tracer.trace_executed(U256::from(3), &[U256::from(5)], &[]);
+        tracer.trace_prepare_execute(2, 0x54, U256::from(2100), None, None);
+        tracer.trace_executed(U256::from(2100), &[U256::from(0)], &[]);
+        // DELEGATECALL runs the code of the library on the storage of the caller
+        tracer.prepare_subtrace(&ActionParams {
+            address: caller,
+            code_address: library,
+            ..Default::default()
+        });
+        // PUSH1 0x09, SLOAD
+        tracer.trace_prepare_execute(0, 0x60, U256::from(3), None, None);
+        tracer.trace_executed(U256::from(3), &[U256::from(9)], &[]);
+        tracer.trace_prepare_execute(2, 0x54, U256::from(2100), None, None);
+        tracer.trace_executed(U256::from(2100), &[U256::from(0)], &[]);
+        tracer.done_subtrace();
+        tracer.done_subtrace();
+
+        let accessed = tracer.drain().unwrap();
+        assert_eq!(accessed.len(), 1);
+        assert_eq!(
+            accessed[&caller].iter().cloned().collect::<Vec<_>>(),
+            vec![H256::from_low_u64_be(5), H256::from_low_u64_be(9)]
+        );
+    }
+}
@@ -19,6 +19,7 @@
 mod config;
 mod db;
 mod executive_tracer;
+mod geth_tracer;
 mod import;
 mod noop_tracer;
 mod types;
@@ -27,6 +28,7 @@ pub use self::{
     config::Config,
     db::TraceDB,
     executive_tracer::{ExecutiveTracer, ExecutiveVMTracer},
+    geth_tracer::{CallTracer, PrestateTracer, StorageAccessTracer, StructLogger},
     import::ImportRequest,
     localized::LocalizedTrace,
     noop_tracer::{NoopTracer, NoopVMTracer},
@@ -38,6 +40,8 @@ pub use self::types::{
     filter::{AddressesFilter, Filter},
     flat,
     flat::{FlatBlockTraces, FlatTrace, FlatTransactionTraces},
+    geth,
+    geth::{GethTrace, GethTracer, StructLoggerConfig},
     localized, trace,
     trace::{MemoryDiff, RewardType, StorageDiff, VMExecutedOperation, VMOperation, VMTrace},
     Tracing,
@@ -65,6 +69,11 @@ pub trait Tracer: Send {
     /// Finishes a successful create trace. Would panic if prepare/done_trace are not balanced.
     fn done_trace_create(&mut self, gas_used: U256, code: &[u8], address: Address);
 
+    /// Finishes a reverted trace. Would panic if prepare/done_trace are not balanced.
+    fn done_trace_reverted(&mut self, _gas_used: U256, _output: &[u8]) {
+        self.done_trace_failed(&VmError::Reverted);
+    }
+
     /// Finishes a failed trace. Would panic if prepare/done_trace are not balanced.
     fn done_trace_failed(&mut self, error: &VmError);
 
@@ -108,7 +117,7 @@ pub trait VMTracer: Send {
     fn trace_executed(&mut self, _gas_used: U256, _stack_push: &[U256], _mem: &[u8]) {}
 
     /// Spawn subtracer which will be used to trace deeper levels of execution.
-    fn prepare_subtrace(&mut self, _code: &[u8]) {}
+    fn prepare_subtrace(&mut self, _params: &ActionParams) {}
 
     /// Finalize subtracer.
     fn done_subtrace(&mut self) {}
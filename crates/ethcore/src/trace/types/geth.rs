@@ -0,0 +1,269 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Geth-compatible (`debug_trace*`) trace types.
+
+use std::collections::BTreeMap;
+
+use bytes::Bytes;
+use ethereum_types::{Address, H256, U256};
+use types::{account_diff::Diff, state_diff::StateDiff};
+
+/// Built-in tracer used to replay a transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GethTracer {
+    /// Default struct logger, reports every executed instruction.
+    StructLogger(StructLoggerConfig),
+    /// `callTracer`, reports the tree of calls made by the transaction.
+    Call {
+        /// Report only the top-level call.
+        only_top_call: bool,
+    },
+    /// `prestateTracer`, reports the accounts touched by the transaction.
+    Prestate {
+        /// Report the state before and after the transaction, limited to changed fields.
+        diff_mode: bool,
+    },
+}
+
+impl Default for GethTracer {
+    fn default() -> Self {
+        GethTracer::StructLogger(Default::default())
+    }
+}
+
+/// Struct logger options.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct StructLoggerConfig {
+    /// Do not report the stack.
+    pub disable_stack: bool,
+    /// Do not report the storage.
+    pub disable_storage: bool,
+    /// Report the memory.
+    pub enable_memory: bool,
+}
+
+/// Single step of the struct logger.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructLog {
+    /// Program counter.
+    pub pc: usize,
+    /// Instruction name.
+    pub op: String,
+    /// Gas left before the instruction.
+    pub gas: U256,
+    /// Gas cost of the instruction.
+    pub gas_cost: U256,
+    /// Call depth, starting at 1.
+    pub depth: usize,
+    /// Stack before the instruction.
+    pub stack: Option<Vec<U256>>,
+    /// Memory before the instruction.
+    pub memory: Option<Bytes>,
+    /// Storage slots of the current call accessed so far, reported for `SLOAD` and `SSTORE`.
+    pub storage: Option<BTreeMap<H256, H256>>,
+}
+
+/// Result of the struct logger.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructLogs {
+    /// Gas used by the transaction.
+    pub gas: U256,
+    /// Whether the transaction failed.
+    pub failed: bool,
+    /// Output of the transaction.
+    pub return_value: Bytes,
+    /// Executed instructions.
+    pub struct_logs: Vec<StructLog>,
+}
+
+/// Type of a call frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CallFrameType {
+    /// CALL, also used for the top-level call.
+    Call,
+    /// CALLCODE.
+    CallCode,
+    /// DELEGATECALL.
+    DelegateCall,
+    /// STATICCALL.
+    StaticCall,
+    /// CREATE or CREATE2, also used for contract creation transactions.
+    Create,
+    /// SELFDESTRUCT.
+    SelfDestruct,
+}
+
+/// Single call of the `callTracer` result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallFrame {
+    /// Type of the call.
+    pub call_type: CallFrameType,
+    /// Caller.
+    pub from: Address,
+    /// Callee, or address of the created contract.
+    pub to: Address,
+    /// Transferred value, `None` for calls that can't transfer value.
+    pub value: Option<U256>,
+    /// Gas given to the call.
+    pub gas: U256,
+    /// Gas used by the call.
+    pub gas_used: U256,
+    /// Call data or init code.
+    pub input: Bytes,
+    /// Output data or created code.
+    pub output: Bytes,
+    /// Error, if the call failed.
+    pub error: Option<String>,
+    /// Subcalls.
+    pub calls: Vec<CallFrame>,
+}
+
+/// Account state reported by the `prestateTracer`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PrestateAccount {
+    /// Balance.
+    pub balance: Option<U256>,
+    /// Nonce.
+    pub nonce: Option<U256>,
+    /// Code.
+    pub code: Option<Bytes>,
+    /// Storage slots.
+    pub storage: BTreeMap<H256, H256>,
+}
+
+/// Result of the `prestateTracer` in diff mode.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PrestateDiff {
+    /// Changed fields of the modified accounts, before the transaction.
+    pub pre: BTreeMap<Address, PrestateAccount>,
+    /// Changed fields of the modified accounts, after the transaction.
+    pub post: BTreeMap<Address, PrestateAccount>,
+}
+
+impl From<StateDiff> for PrestateDiff {
+    fn from(diff: StateDiff) -> Self {
+        fn split<T: Clone>(diff: &Diff<T>) -> (Option<T>, Option<T>) {
+            match *diff {
+                Diff::Same => (None, None),
+                Diff::Born(ref post) => (None, Some(post.clone())),
+                Diff::Changed(ref pre, ref post) => (Some(pre.clone()), Some(post.clone())),
+                Diff::Died(ref pre) => (Some(pre.clone()), None),
+            }
+        }
+
+        let mut result = PrestateDiff::default();
+        for (address, account) in diff.raw {
+            let mut pre = PrestateAccount::default();
+            let mut post = PrestateAccount::default();
+
+            let (pre_balance, post_balance) = split(&account.balance);
+            let (pre_nonce, post_nonce) = split(&account.nonce);
+            let (pre_code, post_code) = split(&account.code);
+            pre.balance = pre_balance;
+            post.balance = post_balance;
+            pre.nonce = pre_nonce;
+            post.nonce = post_nonce;
+            pre.code = pre_code.filter(|code| !code.is_empty());
+            post.code = post_code.filter(|code| !code.is_empty());
+
+            for (key, value) in &account.storage {
+                let (pre_value, post_value) = split(value);
+                if let Some(value) = pre_value.filter(|value| !value.is_zero()) {
+                    pre.storage.insert(*key, value);
+                }
+                if let Some(value) = post_value.filter(|value| !value.is_zero()) {
+                    post.storage.insert(*key, value);
+                }
+            }
+
+            if pre != PrestateAccount::default() {
+                result.pre.insert(address, pre);
+            }
+            if post != PrestateAccount::default() {
+                result.post.insert(address, post);
+            }
+        }
+        result
+    }
+}
+
+/// Result of a geth-style trace of a single transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GethTrace {
+    /// Result of the struct logger.
+    StructLogs(StructLogs),
+    /// Result of the `callTracer`.
+    Call(CallFrame),
+    /// Result of the `prestateTracer`.
+    Prestate(BTreeMap<Address, PrestateAccount>),
+    /// Result of the `prestateTracer` in diff mode.
+    PrestateDiff(PrestateDiff),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::account_diff::AccountDiff;
+
+    #[test]
+    fn prestate_diff_reports_changed_fields_only() {
+        let sender = Address::from_low_u64_be(1);
+        let created = Address::from_low_u64_be(2);
+        let key = H256::from_low_u64_be(3);
+
+        let mut raw = BTreeMap::new();
+        raw.insert(
+            sender,
+            AccountDiff {
+                balance: Diff::Changed(100.into(), 90.into()),
+                nonce: Diff::Changed(0.into(), 1.into()),
+                code: Diff::Same,
+                storage: BTreeMap::new(),
+            },
+        );
+        raw.insert(
+            created,
+            AccountDiff {
+                balance: Diff::Born(0.into()),
+                nonce: Diff::Born(1.into()),
+                code: Diff::Born(vec![0x60, 0x00]),
+                storage: vec![(key, Diff::Born(H256::from_low_u64_be(4)))]
+                    .into_iter()
+                    .collect(),
+            },
+        );
+
+        let diff = PrestateDiff::from(StateDiff { raw });
+
+        assert_eq!(diff.pre.len(), 1);
+        assert_eq!(
+            diff.pre[&sender],
+            PrestateAccount {
+                balance: Some(100.into()),
+                nonce: Some(0.into()),
+                code: None,
+                storage: BTreeMap::new(),
+            }
+        );
+        assert_eq!(diff.post[&sender].balance, Some(90.into()));
+        assert_eq!(diff.post[&created].code, Some(vec![0x60, 0x00]));
+        assert_eq!(
+            diff.post[&created].storage.get(&key),
+            Some(&H256::from_low_u64_be(4))
+        );
+    }
+}
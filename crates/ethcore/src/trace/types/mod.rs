@@ -19,6 +19,7 @@
 pub mod error;
 pub mod filter;
 pub mod flat;
+pub mod geth;
 pub mod localized;
 pub mod trace;
 
@@ -40,7 +40,7 @@ impl Transaction for transaction::Transaction {
 
 /// Get the transaction cost in gas for the given params.
 fn gas_required_for(is_create: bool, data: &[u8], schedule: &Schedule) -> u64 {
-    data.iter().fold(
+    let data_gas = data.iter().fold(
         (if is_create {
             schedule.tx_create_gas
         } else {
@@ -52,5 +52,12 @@ fn gas_required_for(is_create: bool, data: &[u8], schedule: &Schedule) -> u64 {
                 _ => schedule.tx_data_non_zero_gas,
             }) as u64
         },
-    )
+    );
+
+    // EIP-3860: contract creation also pays for every word of initcode
+    if is_create && schedule.eip3860 {
+        data_gas + (data.len() as u64 + 31) / 32 * schedule.initcode_word_gas as u64
+    } else {
+        data_gas
+    }
 }
@@ -81,9 +81,9 @@ pub mod blocks {
     use engines::EthEngine;
     use error::{BlockError, Error, ErrorKind};
     use types::{
-        header::Header,
+        header::{Header, HeaderTransitions},
         transaction::{TypedTransaction, UnverifiedTransaction},
-        BlockNumber,
+        withdrawal::Withdrawal,
     };
     use verification::{verify_block_basic, verify_block_unordered, PreverifiedBlock};
 
@@ -144,6 +144,8 @@ pub mod blocks {
         pub transactions: Vec<UnverifiedTransaction>,
         /// Unverified block uncles.
         pub uncles: Vec<Header>,
+        /// Unverified block withdrawals. Present only after EIP-4895.
+        pub withdrawals: Option<Vec<Withdrawal>>,
         /// Raw block bytes.
         pub bytes: Bytes,
     }
@@ -152,21 +154,27 @@ pub mod blocks {
         /// Create an `Unverified` from raw bytes.
         pub fn from_rlp(
             bytes: Bytes,
-            eip1559_transition: BlockNumber,
+            transitions: HeaderTransitions,
         ) -> Result<Self, ::rlp::DecoderError> {
             use rlp::Rlp;
-            let (header, transactions, uncles) = {
+            let (header, transactions, uncles, withdrawals) = {
                 let rlp = Rlp::new(&bytes);
-                let header = Header::decode_rlp(&rlp.at(0)?, eip1559_transition)?;
+                let header = Header::decode_rlp(&rlp.at(0)?, transitions)?;
                 let transactions = TypedTransaction::decode_rlp_list(&rlp.at(1)?)?;
-                let uncles = Header::decode_rlp_list(&rlp.at(2)?, eip1559_transition)?;
-                (header, transactions, uncles)
+                let uncles = Header::decode_rlp_list(&rlp.at(2)?, transitions)?;
+                let withdrawals = if rlp.item_count()? > 3 {
+                    Some(rlp.list_at(3)?)
+                } else {
+                    None
+                };
+                (header, transactions, uncles, withdrawals)
             };
 
             Ok(Unverified {
                 header,
                 transactions,
                 uncles,
+                withdrawals,
                 bytes,
             })
         }
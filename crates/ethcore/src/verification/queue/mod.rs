@@ -876,7 +876,7 @@ mod tests {
     use io::*;
     use spec::Spec;
     use test_helpers::{get_good_dummy_block, get_good_dummy_block_seq};
-    use types::{view, views::BlockView, BlockNumber};
+    use types::{header::HeaderTransitions, view, views::BlockView};
 
     // create a test block queue.
     // auto_scaling enables verifier adjustment.
@@ -897,7 +897,7 @@ mod tests {
     }
 
     fn new_unverified(bytes: Bytes) -> Unverified {
-        Unverified::from_rlp(bytes, BlockNumber::max_value()).expect("Should be valid rlp")
+        Unverified::from_rlp(bytes, HeaderTransitions::default()).expect("Should be valid rlp")
     }
 
     #[test]
@@ -942,7 +942,7 @@ mod tests {
         let queue = get_test_queue(false);
         let block = get_good_dummy_block();
         let hash = view!(BlockView, &block)
-            .header(BlockNumber::max_value())
+            .header(HeaderTransitions::default())
             .hash()
             .clone();
         if let Err(e) = queue.import(new_unverified(block)) {
@@ -961,7 +961,7 @@ mod tests {
         let queue = get_test_queue(false);
         let block = get_good_dummy_block();
         let hash = view!(BlockView, &block)
-            .header(BlockNumber::max_value())
+            .header(HeaderTransitions::default())
             .hash()
             .clone();
         if let Err(e) = queue.import(new_unverified(block)) {
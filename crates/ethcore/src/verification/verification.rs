@@ -38,7 +38,8 @@ use call_contract::CallContract;
 use client::BlockInfo;
 use engines::{EthEngine, MAX_UNCLE_AGE};
 use error::{BlockError, Error};
-use types::{header::Header, transaction::SignedTransaction, BlockNumber};
+use machine::MAX_BLOB_GAS_PER_BLOCK;
+use types::{header::Header, transaction::SignedTransaction, withdrawal::Withdrawal, BlockNumber};
 use verification::queue::kind::blocks::Unverified;
 
 use time_utils::CheckedSystemTime;
@@ -52,6 +53,8 @@ pub struct PreverifiedBlock {
     pub transactions: Vec<SignedTransaction>,
     /// Populated block uncles
     pub uncles: Vec<Header>,
+    /// Populated block withdrawals
+    pub withdrawals: Option<Vec<Withdrawal>>,
     /// Block bytes
     pub bytes: Bytes,
 }
@@ -147,6 +150,7 @@ pub fn verify_block_unordered(
         header,
         transactions,
         uncles: block.uncles,
+        withdrawals: block.withdrawals,
         bytes: block.bytes,
     })
 }
@@ -295,7 +299,7 @@ fn verify_uncles(
                 )));
             }
 
-            let uncle_parent = uncle_parent.decode(engine.params().eip1559_transition)?;
+            let uncle_parent = uncle_parent.decode(engine.params().header_transitions())?;
             verify_parent(&uncle, &uncle_parent, engine)?;
             engine.verify_block_family(&uncle, &uncle_parent)?;
             verified.insert(uncle.hash());
@@ -333,6 +337,12 @@ pub fn verify_block_final(expected: &Header, got: &Header) -> Result<(), Error>
             found: *got.receipts_root(),
         })));
     }
+    if expected.blob_gas_used() != got.blob_gas_used() {
+        return Err(From::from(BlockError::InvalidBlobGasUsed(Mismatch {
+            expected: expected.blob_gas_used().unwrap_or_default(),
+            found: got.blob_gas_used().unwrap_or_default(),
+        })));
+    }
     Ok(())
 }
 
@@ -389,6 +399,38 @@ pub fn verify_header_params(
         }
     }
 
+    let expect_withdrawals = header.number() >= engine.params().eip4895_transition;
+    if header.withdrawals_root().is_some() != expect_withdrawals {
+        return Err(From::from(BlockError::InvalidWithdrawalsPresence(
+            Mismatch {
+                expected: expect_withdrawals,
+                found: header.withdrawals_root().is_some(),
+            },
+        )));
+    }
+
+    let expect_blob_gas_fields = header.number() >= engine.params().eip4844_transition;
+    let has_blob_gas_fields = header.blob_gas_used().is_some()
+        && header.excess_blob_gas().is_some()
+        && header.parent_beacon_block_root().is_some();
+    if has_blob_gas_fields != expect_blob_gas_fields {
+        return Err(From::from(BlockError::InvalidBlobGasFieldsPresence(
+            Mismatch {
+                expected: expect_blob_gas_fields,
+                found: has_blob_gas_fields,
+            },
+        )));
+    }
+    if let Some(blob_gas_used) = header.blob_gas_used() {
+        if blob_gas_used > MAX_BLOB_GAS_PER_BLOCK {
+            return Err(From::from(BlockError::TooMuchBlobGasUsed(OutOfBounds {
+                min: None,
+                max: Some(MAX_BLOB_GAS_PER_BLOCK),
+                found: blob_gas_used,
+            })));
+        }
+    }
+
     let maximum_extra_data_size = engine.maximum_extra_data_size();
     if header.number() != 0 && header.extra_data().len() > maximum_extra_data_size {
         return Err(From::from(BlockError::ExtraDataOutOfBounds(OutOfBounds {
@@ -503,10 +545,19 @@ fn verify_parent(header: &Header, parent: &Header, engine: &dyn EthEngine) -> Re
         })));
     };
 
+    // check if the excess blob gas is correct
+    let expected_excess_blob_gas = engine.machine().calc_excess_blob_gas(parent);
+    if expected_excess_blob_gas != header.excess_blob_gas() {
+        return Err(From::from(BlockError::IncorrectExcessBlobGas(Mismatch {
+            expected: expected_excess_blob_gas.unwrap_or_default(),
+            found: header.excess_blob_gas().unwrap_or_default(),
+        })));
+    }
+
     Ok(())
 }
 
-/// Verify block data against header: transactions root and uncles hash.
+/// Verify block data against header: transactions root, uncles hash and withdrawals root.
 fn verify_block_integrity(block: &Unverified) -> Result<(), Error> {
     let block_rlp = Rlp::new(&block.bytes);
     let tx = block_rlp.at(1)?;
@@ -533,6 +584,21 @@ fn verify_block_integrity(block: &Unverified) -> Result<(), Error> {
             found: *block.header.uncles_hash(),
         }));
     }
+    if block.withdrawals.is_some() != block.header.withdrawals_root().is_some() {
+        bail!(BlockError::InvalidWithdrawalsPresence(Mismatch {
+            expected: block.header.withdrawals_root().is_some(),
+            found: block.withdrawals.is_some(),
+        }));
+    }
+    if let Some(withdrawals_root) = block.header.withdrawals_root() {
+        let expected_root = ordered_trie_root(block_rlp.at(3)?.iter().map(|r| r.as_raw()));
+        if &expected_root != withdrawals_root {
+            bail!(BlockError::InvalidWithdrawalsRoot(Mismatch {
+                expected: expected_root,
+                found: *withdrawals_root,
+            }));
+        }
+    }
     Ok(())
 }
 
@@ -556,6 +622,7 @@ mod tests {
     use triehash::ordered_trie_root;
     use types::{
         encoded,
+        header::HeaderTransitions,
         log_entry::{LocalizedLogEntry, LogEntry},
         transaction::{Action, SignedTransaction, Transaction, TypedTransaction},
     };
@@ -612,7 +679,7 @@ mod tests {
         }
 
         pub fn insert(&mut self, bytes: Bytes) {
-            let header = Unverified::from_rlp(bytes.clone(), BlockNumber::max_value())
+            let header = Unverified::from_rlp(bytes.clone(), HeaderTransitions::default())
                 .unwrap()
                 .header;
             let hash = header.hash();
@@ -654,7 +721,7 @@ mod tests {
         /// Get the familial details concerning a block.
         fn block_details(&self, hash: &H256) -> Option<BlockDetails> {
             self.blocks.get(hash).map(|bytes| {
-                let header = Unverified::from_rlp(bytes.to_vec(), BlockNumber::max_value())
+                let header = Unverified::from_rlp(bytes.to_vec(), HeaderTransitions::default())
                     .unwrap()
                     .header;
                 BlockDetails {
@@ -710,7 +777,8 @@ mod tests {
     }
 
     fn basic_test(bytes: &[u8], engine: &dyn EthEngine) -> Result<(), Error> {
-        let unverified = Unverified::from_rlp(bytes.to_vec(), engine.params().eip1559_transition)?;
+        let unverified =
+            Unverified::from_rlp(bytes.to_vec(), engine.params().header_transitions())?;
         verify_block_basic(&unverified, engine, true)
     }
 
@@ -719,7 +787,7 @@ mod tests {
         BC: BlockProvider,
     {
         let block =
-            Unverified::from_rlp(bytes.to_vec(), engine.params().eip1559_transition).unwrap();
+            Unverified::from_rlp(bytes.to_vec(), engine.params().header_transitions()).unwrap();
         let header = block.header;
         let transactions: Vec<_> = block
             .transactions
@@ -735,12 +803,13 @@ mod tests {
         let parent = bc
             .block_header_data(header.parent_hash())
             .ok_or(BlockError::UnknownParent(*header.parent_hash()))?
-            .decode(engine.params().eip1559_transition)?;
+            .decode(engine.params().header_transitions())?;
 
         let block = PreverifiedBlock {
             header,
             transactions,
             uncles: block.uncles,
+            withdrawals: block.withdrawals,
             bytes: bytes.to_vec(),
         };
 
@@ -753,7 +822,7 @@ mod tests {
     }
 
     fn unordered_test(bytes: &[u8], engine: &dyn EthEngine) -> Result<(), Error> {
-        let un = Unverified::from_rlp(bytes.to_vec(), engine.params().eip1559_transition)?;
+        let un = Unverified::from_rlp(bytes.to_vec(), engine.params().header_transitions())?;
         verify_block_unordered(un, engine, false)?;
         Ok(())
     }
@@ -20,7 +20,7 @@ use devp2p::NetworkService;
 use network::{
     client_version::ClientVersion, ConnectionFilter, Error, ErrorKind,
     NetworkConfiguration as BasicNetworkConfiguration, NetworkContext, NetworkProtocolHandler,
-    NonReservedPeerMode, PeerId, ProtocolId,
+    NonReservedPeerMode, PeerId, ProtocolId, TrafficCounter, TrafficStats,
 };
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
@@ -31,9 +31,12 @@ use std::{
 };
 
 use chain::{
-    fork_filter::ForkFilterApi, ChainSyncApi, SyncState, SyncStatus as EthSyncStatus,
-    ETH_PROTOCOL_VERSION_63, ETH_PROTOCOL_VERSION_64, ETH_PROTOCOL_VERSION_65,
-    ETH_PROTOCOL_VERSION_66, PAR_PROTOCOL_VERSION_1, PAR_PROTOCOL_VERSION_2,
+    fork_filter::{ForkFilterApi, ForkIdRecordFilter},
+    sync_packet::{packet_name, protocol_name},
+    ChainSyncApi, SyncState, SyncStatus as EthSyncStatus, ETH_PROTOCOL_VERSION_63,
+    ETH_PROTOCOL_VERSION_64, ETH_PROTOCOL_VERSION_65, ETH_PROTOCOL_VERSION_66,
+    ETH_PROTOCOL_VERSION_67, ETH_PROTOCOL_VERSION_68, PAR_PROTOCOL_VERSION_1,
+    PAR_PROTOCOL_VERSION_2, SNAP_PROTOCOL_VERSION_1,
 };
 use ethcore::{
     client::{BlockChainClient, ChainMessageType, ChainNotify, NewBlocks},
@@ -43,6 +46,7 @@ use ethereum_types::{H256, H512, U256, U64};
 use io::TimerToken;
 use network::IpFilter;
 use parking_lot::{Mutex, RwLock};
+use propagation::TransactionPropagation;
 use stats::{PrometheusMetrics, PrometheusRegistry};
 
 use std::{
@@ -51,14 +55,16 @@ use std::{
 };
 use sync_io::NetSyncIo;
 use types::{
-    creation_status::CreationStatus, restoration_status::RestorationStatus,
-    transaction::UnverifiedTransaction, BlockNumber,
+    creation_status::CreationStatus, header::HeaderTransitions,
+    restoration_status::RestorationStatus, transaction::UnverifiedTransaction, BlockNumber,
 };
 
 /// OpenEthereum sync protocol
 pub const PAR_PROTOCOL: ProtocolId = U64([0x706172]); // hexadecimal number of "par";
 /// Ethereum sync protocol
 pub const ETH_PROTOCOL: ProtocolId = U64([0x657468]); // hexadecimal number of "eth";
+/// Ethereum state snapshot protocol
+pub const SNAP_PROTOCOL: ProtocolId = U64([0x736e6170]); // hexadecimal number of "snap";
 
 /// Determine warp sync status.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -102,6 +108,9 @@ pub struct SyncConfig {
     pub max_download_ahead_blocks: usize,
     /// Enable ancient block download.
     pub download_old_blocks: bool,
+    /// Ancient blocks are downloaded as headers first. Bodies and receipts are then
+    /// downloaded only for ancient blocks from this number on, `None` for all of them.
+    pub ancient_blocks_from: Option<BlockNumber>,
     /// Network ID
     pub network_id: u64,
     /// Main "eth" subprotocol name.
@@ -110,8 +119,15 @@ pub struct SyncConfig {
     pub fork_block: Option<(BlockNumber, H256)>,
     /// Enable snapshot sync
     pub warp_sync: WarpSync,
-    /// Number of first block where EIP-1559 rules begin. New encoding/decoding block format.
-    pub eip1559_transition: BlockNumber,
+    /// Sync the state over `snap/1` when no warp snapshot is available. Requires `warp_sync`.
+    /// The restored chain starts at the pivot block, so this is only suitable for engines
+    /// which don't need epoch transition proofs.
+    pub snap_sync: bool,
+    /// Trusted block to start the chain at, restoring its state over `snap/1`
+    /// instead of importing the blocks before it.
+    pub checkpoint: Option<Checkpoint>,
+    /// Blocks from which the optional header fields are present. Needed to decode headers.
+    pub header_transitions: HeaderTransitions,
     /// Number of blocks for which new transactions will be returned in a result of `parity_newTransactionsStats` RPC call
     pub new_transactions_stats_period: u64,
 }
@@ -121,16 +137,30 @@ impl Default for SyncConfig {
         SyncConfig {
             max_download_ahead_blocks: 20000,
             download_old_blocks: true,
+            ancient_blocks_from: None,
             network_id: 1,
             subprotocol_name: ETH_PROTOCOL,
             fork_block: None,
             warp_sync: WarpSync::Disabled,
-            eip1559_transition: BlockNumber::max_value(),
+            snap_sync: false,
+            checkpoint: None,
+            header_transitions: HeaderTransitions::default(),
             new_transactions_stats_period: 0,
         }
     }
 }
 
+/// Trusted block for checkpoint sync.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Checkpoint {
+    /// Block number
+    pub number: BlockNumber,
+    /// Block hash
+    pub hash: H256,
+    /// Total difficulty of the chain up to and including the block.
+    pub total_difficulty: U256,
+}
+
 /// Current sync status
 pub trait SyncProvider: Send + Sync + PrometheusMetrics {
     /// Get sync status
@@ -173,6 +203,59 @@ pub struct PeerInfo {
     pub local_address: String,
     /// Eth protocol info.
     pub eth_info: Option<EthProtocolInfo>,
+    /// Traffic exchanged with the peer.
+    pub traffic: PeerTraffic,
+}
+
+/// Traffic exchanged with a peer.
+#[derive(Debug, Default)]
+pub struct PeerTraffic {
+    /// Everything received from the peer
+    pub ingress: TrafficCounter,
+    /// Everything sent to the peer
+    pub egress: TrafficCounter,
+    /// Bytes per second recently received from the peer
+    pub ingress_rate: f64,
+    /// Bytes per second recently sent to the peer
+    pub egress_rate: f64,
+    /// Traffic by protocol packet
+    pub packets: Vec<PacketTraffic>,
+}
+
+/// Traffic of a single protocol packet type.
+#[derive(Debug)]
+pub struct PacketTraffic {
+    /// Protocol name
+    pub protocol: String,
+    /// Packet name
+    pub packet: String,
+    /// Received packets
+    pub ingress: TrafficCounter,
+    /// Sent packets
+    pub egress: TrafficCounter,
+}
+
+impl From<TrafficStats> for PeerTraffic {
+    fn from(stats: TrafficStats) -> Self {
+        PeerTraffic {
+            ingress: stats.ingress,
+            egress: stats.egress,
+            ingress_rate: stats.ingress_rate(),
+            egress_rate: stats.egress_rate(),
+            packets: stats
+                .packets
+                .iter()
+                .map(
+                    |(&(protocol, packet_id), &(ingress, egress))| PacketTraffic {
+                        protocol: protocol_name(protocol),
+                        packet: packet_name(protocol, packet_id),
+                        ingress,
+                        egress,
+                    },
+                )
+                .collect(),
+        }
+    }
 }
 
 /// Ethereum protocol info.
@@ -184,6 +267,8 @@ pub struct EthProtocolInfo {
     pub head: H256,
     /// Peer total difficulty if known
     pub difficulty: Option<U256>,
+    /// Reputation score of the peer
+    pub reputation: i32,
 }
 
 /// A prioritized tasks run in a specialised timer.
@@ -231,6 +316,8 @@ pub struct Params {
     pub snapshot_service: Arc<dyn SnapshotService>,
     /// Network layer configuration.
     pub network_config: NetworkConfiguration,
+    /// Transaction propagation policy.
+    pub propagation: Arc<dyn TransactionPropagation>,
 }
 
 /// Ethereum network protocol handler
@@ -255,6 +342,7 @@ impl EthSync {
     ) -> Result<Arc<EthSync>, Error> {
         let (priority_tasks_tx, priority_tasks_rx) = mpsc::channel();
         let (new_transaction_hashes_tx, new_transaction_hashes_rx) = crossbeam_channel::unbounded();
+        let record_filter = ForkIdRecordFilter::new(params.chain.clone(), params.forks.clone());
         let fork_filter = ForkFilterApi::new(&*params.chain, params.forks);
 
         let sync = ChainSyncApi::new(
@@ -263,11 +351,13 @@ impl EthSync {
             fork_filter,
             priority_tasks_rx,
             new_transaction_hashes_rx,
+            params.propagation,
         );
         let service = NetworkService::new(
             params.network_config.clone().into_basic()?,
             connection_filter,
         )?;
+        service.set_node_record_filter(Arc::new(record_filter));
 
         let sync = Arc::new(EthSync {
             network: service,
@@ -326,6 +416,7 @@ impl SyncProvider for EthSync {
                             remote_address: session_info.remote_address,
                             local_address: session_info.local_address,
                             eth_info: peer_info,
+                            traffic: session_info.traffic.into(),
                         })
                     })
                     .collect()
@@ -353,7 +444,7 @@ impl PrometheusMetrics for EthSync {
 
         r.register_gauge(
 			"sync_status",
-			"WaitingPeers(0), SnapshotManifest(1), SnapshotData(2), SnapshotWaiting(3), Blocks(4), Idle(5), Waiting(6), NewBlocks(7)", 
+			"WaitingPeers(0), SnapshotManifest(1), SnapshotData(2), SnapshotWaiting(3), Blocks(4), Idle(5), Waiting(6), NewBlocks(7), SnapPivot(8), SnapState(9)", 
 			match self.eth_handler.sync.status().state {
 			SyncState::WaitingPeers => 0,
 			SyncState::SnapshotManifest => 1,
@@ -363,6 +454,8 @@ impl PrometheusMetrics for EthSync {
 			SyncState::Idle => 5,
 			SyncState::Waiting => 6,
 			SyncState::NewBlocks => 7,
+			SyncState::SnapPivot => 8,
+			SyncState::SnapState => 9,
         });
 
         for (key, value) in sync_status.item_sizes.iter() {
@@ -383,6 +476,98 @@ impl PrometheusMetrics for EthSync {
             "Total number of active peers",
             sync_status.num_active_peers as i64,
         );
+
+        let (mut scores, disabled) = self.eth_handler.sync.reputation_stats();
+        scores.sort();
+        r.register_gauge(
+            "sync_peer_reputation_min",
+            "Lowest reputation score of the connected peers",
+            scores.first().cloned().unwrap_or(0) as i64,
+        );
+        r.register_gauge(
+            "sync_peer_reputation_max",
+            "Highest reputation score of the connected peers",
+            scores.last().cloned().unwrap_or(0) as i64,
+        );
+        r.register_gauge(
+            "sync_peer_reputation_median",
+            "Median reputation score of the connected peers",
+            scores.get(scores.len() / 2).cloned().unwrap_or(0) as i64,
+        );
+        r.register_counter(
+            "sync_peers_disabled_reputation",
+            "Number of peers disabled because of their reputation",
+            disabled as i64,
+        );
+        let mut packets = BTreeMap::<(String, String), (TrafficCounter, TrafficCounter)>::new();
+        for peer in self.peers() {
+            let traffic = peer.traffic;
+            for p in traffic.packets {
+                let total = packets.entry((p.protocol, p.packet)).or_default();
+                total.0.bytes += p.ingress.bytes;
+                total.0.messages += p.ingress.messages;
+                total.1.bytes += p.egress.bytes;
+                total.1.messages += p.egress.messages;
+            }
+            // peers are told apart by their id, which is known once the handshake is done
+            let id = match peer.id {
+                Some(id) => id,
+                None => continue,
+            };
+            let labels = [("peer", id.as_str())];
+            r.register_labeled_gauge(
+                "net_peer_ingress_bytes",
+                "Bytes received from the peer",
+                &labels,
+                traffic.ingress.bytes as i64,
+            );
+            r.register_labeled_gauge(
+                "net_peer_egress_bytes",
+                "Bytes sent to the peer",
+                &labels,
+                traffic.egress.bytes as i64,
+            );
+            r.register_labeled_gauge(
+                "net_peer_ingress_rate",
+                "Bytes per second recently received from the peer",
+                &labels,
+                traffic.ingress_rate as i64,
+            );
+            r.register_labeled_gauge(
+                "net_peer_egress_rate",
+                "Bytes per second recently sent to the peer",
+                &labels,
+                traffic.egress_rate as i64,
+            );
+        }
+        for ((protocol, packet), (ingress, egress)) in &packets {
+            let labels = [("protocol", protocol.as_str()), ("packet", packet.as_str())];
+            r.register_labeled_gauge(
+                "net_packet_ingress_bytes",
+                "Bytes of the packet type received from the connected peers",
+                &labels,
+                ingress.bytes as i64,
+            );
+            r.register_labeled_gauge(
+                "net_packet_ingress_messages",
+                "Packets of the type received from the connected peers",
+                &labels,
+                ingress.messages as i64,
+            );
+            r.register_labeled_gauge(
+                "net_packet_egress_bytes",
+                "Bytes of the packet type sent to the connected peers",
+                &labels,
+                egress.bytes as i64,
+            );
+            r.register_labeled_gauge(
+                "net_packet_egress_messages",
+                "Packets of the type sent to the connected peers",
+                &labels,
+                egress.messages as i64,
+            );
+        }
+
         r.register_counter(
             "sync_blocks_recieved",
             "Number of blocks downloaded so far",
@@ -471,7 +656,7 @@ struct SyncProtocolHandler {
 
 impl NetworkProtocolHandler for SyncProtocolHandler {
     fn initialize(&self, io: &dyn NetworkContext) {
-        if io.subprotocol_name() != PAR_PROTOCOL {
+        if io.subprotocol_name() != PAR_PROTOCOL && io.subprotocol_name() != SNAP_PROTOCOL {
             io.register_timer(PEERS_TIMER, Duration::from_millis(700))
                 .expect("Error registering peers timer");
             io.register_timer(MAINTAIN_SYNC_TIMER, Duration::from_millis(1100))
@@ -489,6 +674,15 @@ impl NetworkProtocolHandler for SyncProtocolHandler {
     }
 
     fn read(&self, io: &dyn NetworkContext, peer: &PeerId, packet_id: u8, data: &[u8]) {
+        if io.subprotocol_name() == SNAP_PROTOCOL {
+            self.sync.dispatch_snap_packet(
+                &mut NetSyncIo::new(io, &*self.chain, &*self.snapshot_service, &self.overlay),
+                *peer,
+                packet_id,
+                data,
+            );
+            return;
+        }
         self.sync.dispatch_packet(
             &mut NetSyncIo::new(io, &*self.chain, &*self.snapshot_service, &self.overlay),
             *peer,
@@ -499,6 +693,10 @@ impl NetworkProtocolHandler for SyncProtocolHandler {
 
     fn connected(&self, io: &dyn NetworkContext, peer: &PeerId) {
         trace_time!("sync::connected");
+        // `snap` runs side by side with `eth` and has no handshake of its own
+        if io.subprotocol_name() == SNAP_PROTOCOL {
+            return;
+        }
         // If warp protocol is supported only allow warp handshake
         let warp_protocol = io.protocol_version(PAR_PROTOCOL, *peer).unwrap_or(0) != 0;
         let warp_context = io.subprotocol_name() == PAR_PROTOCOL;
@@ -512,7 +710,7 @@ impl NetworkProtocolHandler for SyncProtocolHandler {
 
     fn disconnected(&self, io: &dyn NetworkContext, peer: &PeerId) {
         trace_time!("sync::disconnected");
-        if io.subprotocol_name() != PAR_PROTOCOL {
+        if io.subprotocol_name() != PAR_PROTOCOL && io.subprotocol_name() != SNAP_PROTOCOL {
             self.sync.write().on_peer_aborting(
                 &mut NetSyncIo::new(io, &*self.chain, &*self.snapshot_service, &self.overlay),
                 *peer,
@@ -592,6 +790,8 @@ impl ChainNotify for EthSync {
                     ETH_PROTOCOL_VERSION_64,
                     ETH_PROTOCOL_VERSION_65,
                     ETH_PROTOCOL_VERSION_66,
+                    ETH_PROTOCOL_VERSION_67,
+                    ETH_PROTOCOL_VERSION_68,
                 ],
             )
             .unwrap_or_else(|e| warn!("Error registering ethereum protocol: {:?}", e));
@@ -603,6 +803,14 @@ impl ChainNotify for EthSync {
                 &[PAR_PROTOCOL_VERSION_1, PAR_PROTOCOL_VERSION_2],
             )
             .unwrap_or_else(|e| warn!("Error registering snapshot sync protocol: {:?}", e));
+        // register the state snapshot protocol
+        self.network
+            .register_protocol(
+                self.eth_handler.clone(),
+                SNAP_PROTOCOL,
+                &[SNAP_PROTOCOL_VERSION_1],
+            )
+            .unwrap_or_else(|e| warn!("Error registering snap protocol: {:?}", e));
     }
 
     fn stop(&self) {
@@ -721,8 +929,12 @@ pub struct NetworkConfiguration {
     pub nat_enabled: bool,
     /// Enable discovery
     pub discovery_enabled: bool,
+    /// Enable discovery v5
+    pub discovery_v5_enabled: bool,
     /// List of initial node addresses
     pub boot_nodes: Vec<String>,
+    /// EIP-1459 node tree URLs to discover nodes from
+    pub dns_discovery: Vec<String>,
     /// Use provided node key instead of default
     pub use_secret: Option<Secret>,
     /// Max number of connected peers to maintain
@@ -770,7 +982,9 @@ impl NetworkConfiguration {
             udp_port: self.udp_port,
             nat_enabled: self.nat_enabled,
             discovery_enabled: self.discovery_enabled,
+            discovery_v5_enabled: self.discovery_v5_enabled,
             boot_nodes: self.boot_nodes,
+            dns_discovery: self.dns_discovery,
             use_secret: self.use_secret,
             max_peers: self.max_peers,
             min_peers: self.min_peers,
@@ -802,7 +1016,9 @@ impl From<BasicNetworkConfiguration> for NetworkConfiguration {
             udp_port: other.udp_port,
             nat_enabled: other.nat_enabled,
             discovery_enabled: other.discovery_enabled,
+            discovery_v5_enabled: other.discovery_v5_enabled,
             boot_nodes: other.boot_nodes,
+            dns_discovery: other.dns_discovery,
             use_secret: other.use_secret,
             max_peers: other.max_peers,
             min_peers: other.min_peers,
@@ -26,13 +26,13 @@ use ethcore::{
 use ethereum_types::H256;
 use network::{client_version::ClientCapabilities, PeerId};
 use rlp::{self, Rlp};
-use std::cmp;
 ///
 /// Blockchain downloader
 ///
-use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::{cmp, mem};
 use sync_io::SyncIo;
-use types::BlockNumber;
+use types::{header::HeaderTransitions, BlockNumber};
 
 const MAX_HEADERS_TO_REQUEST: usize = 128;
 const MAX_BODIES_TO_REQUEST_LARGE: usize = 128;
@@ -129,12 +129,18 @@ pub struct BlockDownloader {
     round_parents: VecDeque<(H256, H256)>,
     /// Do we need to download block recetips.
     download_receipts: bool,
+    /// Do we need to download block bodies.
+    download_bodies: bool,
     /// Sync up to the block with this hash.
     target_hash: Option<H256>,
     /// Probing range for seeking common best block.
     retract_step: u64,
     /// consecutive useless headers this round
     useless_headers_count: usize,
+    /// Peers the downloaded headers came from
+    header_sources: HashMap<H256, PeerId>,
+    /// Peers that sent headers of blocks which failed to import
+    bad_block_sources: Vec<PeerId>,
 }
 
 impl BlockDownloader {
@@ -157,9 +163,12 @@ impl BlockDownloader {
             imported_this_round: None,
             round_parents: VecDeque::new(),
             download_receipts: sync_receipts,
+            download_bodies: true,
             target_hash: None,
             retract_step: 1,
             useless_headers_count: 0,
+            header_sources: HashMap::new(),
+            bad_block_sources: Vec::new(),
         }
     }
 
@@ -167,6 +176,7 @@ impl BlockDownloader {
     pub fn reset(&mut self) {
         self.blocks.clear();
         self.useless_headers_count = 0;
+        self.header_sources.clear();
         self.state = State::Idle;
     }
 
@@ -196,6 +206,13 @@ impl BlockDownloader {
         self.target_hash = Some(hash.clone());
     }
 
+    /// Download headers only, leaving bodies and receipts for a later pass.
+    pub fn set_headers_only(&mut self) {
+        self.download_bodies = false;
+        self.download_receipts = false;
+        self.blocks = BlockCollection::headers_only();
+    }
+
     /// Unmark header as being downloaded.
     pub fn clear_header_download(&mut self, hash: &H256) {
         self.blocks.clear_header_download(hash)
@@ -244,13 +261,14 @@ impl BlockDownloader {
         self.last_imported_block
     }
 
-    /// Add new block headers.
+    /// Add new block headers received from `peer_id`.
     pub fn import_headers(
         &mut self,
         io: &mut dyn SyncIo,
+        peer_id: PeerId,
         r: &Rlp,
         expected_hash: H256,
-        eip1559_transition: BlockNumber,
+        transitions: HeaderTransitions,
     ) -> Result<DownloadAction, BlockDownloaderImportError> {
         let item_count = r.item_count().unwrap_or(0);
         if self.state == State::Idle {
@@ -277,7 +295,7 @@ impl BlockDownloader {
         let mut hashes = Vec::new();
         let mut last_header = None;
         for i in 0..item_count {
-            let info = SyncHeader::from_rlp(r.at(i)?.as_raw().to_vec(), eip1559_transition)?;
+            let info = SyncHeader::from_rlp(r.at(i)?.as_raw().to_vec(), transitions)?;
             let number = BlockNumber::from(info.header.number());
             let hash = info.header.hash();
 
@@ -408,6 +426,9 @@ impl BlockDownloader {
                     }
                     return Err(BlockDownloaderImportError::Useless);
                 }
+                for hash in hashes {
+                    self.header_sources.insert(hash, peer_id);
+                }
                 self.blocks.insert_headers(headers);
                 trace_sync!(self, "Inserted {} headers", count);
             }
@@ -422,7 +443,7 @@ impl BlockDownloader {
         &mut self,
         r: &Rlp,
         expected_hashes: &[H256],
-        eip1559_transition: BlockNumber,
+        transitions: HeaderTransitions,
     ) -> Result<(), BlockDownloaderImportError> {
         let item_count = r.item_count().unwrap_or(0);
         if item_count == 0 {
@@ -432,7 +453,7 @@ impl BlockDownloader {
         } else {
             let mut bodies = Vec::with_capacity(item_count);
             for i in 0..item_count {
-                let body = SyncBody::from_rlp(r.at(i)?.as_raw(), eip1559_transition)?;
+                let body = SyncBody::from_rlp(r.at(i)?.as_raw(), transitions)?;
                 bodies.push(body);
             }
 
@@ -668,6 +689,7 @@ impl BlockDownloader {
             let h = block.header.hash();
             let number = block.header.number();
             let parent = *block.header.parent_hash();
+            let source = self.header_sources.remove(&h);
 
             if self.target_hash.as_ref().map_or(false, |t| t == &h) {
                 self.state = State::Complete;
@@ -678,8 +700,10 @@ impl BlockDownloader {
                 return download_action;
             }
 
-            let result = if let Some(receipts) = receipts {
-                io.chain().queue_ancient_block(block, receipts)
+            let result = if !self.download_bodies {
+                io.chain().queue_ancient_header(block.header)
+            } else if let Some(receipts) = receipts {
+                io.chain().queue_ancient_body(block, receipts)
             } else {
                 io.chain().import_block(block)
             };
@@ -721,6 +745,7 @@ impl BlockDownloader {
                 }
                 Err(e) => {
                     debug_sync!(self, "Bad block {:?} : {:?}", h, e);
+                    self.bad_block_sources.extend(source);
                     download_action = DownloadAction::Reset;
                     break;
                 }
@@ -737,6 +762,11 @@ impl BlockDownloader {
         download_action
     }
 
+    /// Take the peers that sent headers of blocks which failed to import.
+    pub fn take_bad_block_sources(&mut self) -> Vec<PeerId> {
+        mem::replace(&mut self.bad_block_sources, Vec::new())
+    }
+
     fn block_imported(&mut self, hash: &H256, number: BlockNumber, parent: &H256) {
         self.last_imported_block = number;
         self.last_imported_hash = hash.clone();
@@ -798,23 +828,23 @@ mod tests {
         headers: &[BlockHeader],
         downloader: &mut BlockDownloader,
         io: &mut dyn SyncIo,
-        eip1559_transition: BlockNumber,
+        transitions: HeaderTransitions,
     ) -> Result<DownloadAction, BlockDownloaderImportError> {
         let mut stream = RlpStream::new();
         stream.append_list(headers);
         let bytes = stream.out();
         let rlp = Rlp::new(&bytes);
         let expected_hash = headers.first().unwrap().hash();
-        downloader.import_headers(io, &rlp, expected_hash, eip1559_transition)
+        downloader.import_headers(io, 0, &rlp, expected_hash, transitions)
     }
 
     fn import_headers_ok(
         headers: &[BlockHeader],
         downloader: &mut BlockDownloader,
         io: &mut dyn SyncIo,
-        eip1559_transition: BlockNumber,
+        transitions: HeaderTransitions,
     ) {
-        let res = import_headers(headers, downloader, io, eip1559_transition);
+        let res = import_headers(headers, downloader, io, transitions);
         assert!(res.is_ok());
     }
 
@@ -844,9 +874,10 @@ mod tests {
 
         match downloader.import_headers(
             &mut io,
+            0,
             &valid_rlp,
             genesis_hash,
-            spec.params().eip1559_transition,
+            spec.params().header_transitions(),
         ) {
             Ok(DownloadAction::Reset) => assert_eq!(downloader.state, State::Blocks),
             _ => panic!("expected transition to Blocks state"),
@@ -863,9 +894,10 @@ mod tests {
 
         match downloader.import_headers(
             &mut io,
+            0,
             &invalid_start_block_rlp,
             genesis_hash,
-            spec.params().eip1559_transition,
+            spec.params().header_transitions(),
         ) {
             Err(BlockDownloaderImportError::Invalid) => (),
             _ => panic!("expected BlockDownloaderImportError"),
@@ -882,9 +914,10 @@ mod tests {
 
         match downloader.import_headers(
             &mut io,
+            0,
             &invalid_skip_rlp,
             genesis_hash,
-            spec.params().eip1559_transition,
+            spec.params().header_transitions(),
         ) {
             Err(BlockDownloaderImportError::Invalid) => (),
             _ => panic!("expected BlockDownloaderImportError"),
@@ -904,9 +937,10 @@ mod tests {
         let too_many_rlp = Rlp::new(&rlp_data);
         match downloader.import_headers(
             &mut io,
+            0,
             &too_many_rlp,
             genesis_hash,
-            spec.params().eip1559_transition,
+            spec.params().header_transitions(),
         ) {
             Err(BlockDownloaderImportError::Invalid) => (),
             _ => panic!("expected BlockDownloaderImportError"),
@@ -918,7 +952,10 @@ mod tests {
         ::env_logger::try_init().ok();
 
         let mut chain = TestBlockChainClient::new();
-        let eip1559_transition = BlockNumber::default();
+        let transitions = HeaderTransitions {
+            eip1559_transition: 0,
+            ..Default::default()
+        };
         let snapshot_service = TestSnapshotService::new();
         let queue = RwLock::new(VecDeque::new());
         let mut io = TestIo::new(&mut chain, &snapshot_service, &queue, None);
@@ -938,12 +975,7 @@ mod tests {
         let rlp_data = encode_list(&headers);
         let headers_rlp = Rlp::new(&rlp_data);
 
-        match downloader.import_headers(
-            &mut io,
-            &headers_rlp,
-            headers[0].hash(),
-            eip1559_transition,
-        ) {
+        match downloader.import_headers(&mut io, 0, &headers_rlp, headers[0].hash(), transitions) {
             Ok(DownloadAction::None) => (),
             _ => panic!("expected successful import"),
         };
@@ -953,12 +985,7 @@ mod tests {
         let rlp_data = encode_list(&headers);
         let headers_rlp = Rlp::new(&rlp_data);
 
-        match downloader.import_headers(
-            &mut io,
-            &headers_rlp,
-            headers[0].hash(),
-            eip1559_transition,
-        ) {
+        match downloader.import_headers(&mut io, 0, &headers_rlp, headers[0].hash(), transitions) {
             Err(BlockDownloaderImportError::Invalid) => (),
             _ => panic!("expected BlockDownloaderImportError"),
         };
@@ -968,12 +995,7 @@ mod tests {
         let rlp_data = encode_list(&headers);
         let headers_rlp = Rlp::new(&rlp_data);
 
-        match downloader.import_headers(
-            &mut io,
-            &headers_rlp,
-            headers[0].hash(),
-            eip1559_transition,
-        ) {
+        match downloader.import_headers(&mut io, 0, &headers_rlp, headers[0].hash(), transitions) {
             Err(BlockDownloaderImportError::Invalid) => (),
             _ => panic!("expected BlockDownloaderImportError"),
         };
@@ -984,7 +1006,7 @@ mod tests {
         ::env_logger::try_init().ok();
 
         let mut chain = TestBlockChainClient::new();
-        let eip1559_transition = chain.spec.params().eip1559_transition;
+        let transitions = chain.spec.params().header_transitions();
         let snapshot_service = TestSnapshotService::new();
         let queue = RwLock::new(VecDeque::new());
         let mut io = TestIo::new(&mut chain, &snapshot_service, &queue, None);
@@ -1033,7 +1055,7 @@ mod tests {
         let rlp_data = encode_list(&headers[0..3]);
         let headers_rlp = Rlp::new(&rlp_data);
         assert!(downloader
-            .import_headers(&mut io, &headers_rlp, headers[0].hash(), eip1559_transition)
+            .import_headers(&mut io, 0, &headers_rlp, headers[0].hash(), transitions)
             .is_ok());
 
         // Import first body successfully.
@@ -1044,7 +1066,7 @@ mod tests {
             .import_bodies(
                 &bodies_rlp,
                 &[headers[0].hash(), headers[1].hash()],
-                eip1559_transition
+                transitions
             )
             .is_ok());
 
@@ -1056,7 +1078,7 @@ mod tests {
             .import_bodies(
                 &bodies_rlp,
                 &[headers[0].hash(), headers[1].hash()],
-                eip1559_transition
+                transitions
             )
             .is_ok());
 
@@ -1067,7 +1089,7 @@ mod tests {
         match downloader.import_bodies(
             &bodies_rlp,
             &[headers[0].hash(), headers[1].hash()],
-            eip1559_transition,
+            transitions,
         ) {
             Err(BlockDownloaderImportError::Invalid) => (),
             _ => panic!("expected BlockDownloaderImportError"),
@@ -1079,7 +1101,7 @@ mod tests {
         ::env_logger::try_init().ok();
 
         let mut chain = TestBlockChainClient::new();
-        let eip1559_transition = chain.spec.params().eip1559_transition;
+        let transitions = chain.spec.params().header_transitions();
         let snapshot_service = TestSnapshotService::new();
         let queue = RwLock::new(VecDeque::new());
         let mut io = TestIo::new(&mut chain, &snapshot_service, &queue, None);
@@ -1123,7 +1145,7 @@ mod tests {
         let rlp_data = encode_list(&headers[0..3]);
         let headers_rlp = Rlp::new(&rlp_data);
         assert!(downloader
-            .import_headers(&mut io, &headers_rlp, headers[0].hash(), eip1559_transition)
+            .import_headers(&mut io, 0, &headers_rlp, headers[0].hash(), transitions)
             .is_ok());
 
         // Import second and third receipts successfully.
@@ -1142,7 +1164,7 @@ mod tests {
         match downloader.import_bodies(
             &bodies_rlp,
             &[headers[1].hash(), headers[2].hash()],
-            eip1559_transition,
+            transitions,
         ) {
             Err(BlockDownloaderImportError::Invalid) => (),
             _ => panic!("expected BlockDownloaderImportError"),
@@ -1176,13 +1198,13 @@ mod tests {
             &heads,
             &mut downloader,
             &mut io,
-            spec.params().eip1559_transition,
+            spec.params().header_transitions(),
         );
         import_headers_ok(
             &short_subchain,
             &mut downloader,
             &mut io,
-            spec.params().eip1559_transition,
+            spec.params().header_transitions(),
         );
 
         assert_eq!(downloader.state, State::Blocks);
@@ -1195,7 +1217,7 @@ mod tests {
                 &head,
                 &mut downloader,
                 &mut io,
-                spec.params().eip1559_transition,
+                spec.params().header_transitions(),
             );
             assert!(res.is_err());
         }
@@ -1227,13 +1249,13 @@ mod tests {
             &heads,
             &mut downloader,
             &mut io,
-            spec.params().eip1559_transition,
+            spec.params().header_transitions(),
         );
         import_headers_ok(
             &short_subchain,
             &mut downloader,
             &mut io,
-            spec.params().eip1559_transition,
+            spec.params().header_transitions(),
         );
 
         assert_eq!(downloader.state, State::Blocks);
@@ -1246,7 +1268,7 @@ mod tests {
                 &head,
                 &mut downloader,
                 &mut io,
-                spec.params().eip1559_transition,
+                spec.params().header_transitions(),
             );
             assert!(res.is_err());
         }
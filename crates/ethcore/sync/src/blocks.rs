@@ -24,9 +24,9 @@ use rlp::{DecoderError, Rlp, RlpStream};
 use std::collections::{hash_map, BTreeMap, HashMap, HashSet};
 use triehash_ethereum::ordered_trie_root;
 use types::{
-    header::Header as BlockHeader,
+    header::{Header as BlockHeader, HeaderTransitions},
     transaction::{TypedTransaction, UnverifiedTransaction},
-    BlockNumber,
+    withdrawal::Withdrawal,
 };
 
 malloc_size_of_is_0!(HeaderId);
@@ -38,10 +38,10 @@ pub struct SyncHeader {
 }
 
 impl SyncHeader {
-    pub fn from_rlp(bytes: Bytes, eip1559_transition: BlockNumber) -> Result<Self, DecoderError> {
+    pub fn from_rlp(bytes: Bytes, transitions: HeaderTransitions) -> Result<Self, DecoderError> {
         let rlp = Rlp::new(&bytes);
         let result = SyncHeader {
-            header: BlockHeader::decode_rlp(&rlp, eip1559_transition)?,
+            header: BlockHeader::decode_rlp(&rlp, transitions)?,
             bytes,
         };
 
@@ -55,30 +55,52 @@ pub struct SyncBody {
     pub transactions: Vec<UnverifiedTransaction>,
     pub uncles_bytes: Bytes,
     pub uncles: Vec<BlockHeader>,
+    pub withdrawals_bytes: Option<Bytes>,
+    pub withdrawals: Option<Vec<Withdrawal>>,
 }
 
 impl SyncBody {
-    pub fn from_rlp(bytes: &[u8], eip1559_transition: BlockNumber) -> Result<Self, DecoderError> {
+    pub fn from_rlp(bytes: &[u8], transitions: HeaderTransitions) -> Result<Self, DecoderError> {
         let rlp = Rlp::new(bytes);
         let transactions_rlp = rlp.at(0)?;
         let uncles_rlp = rlp.at(1)?;
+        let withdrawals_rlp = if rlp.item_count()? > 2 {
+            Some(rlp.at(2)?)
+        } else {
+            None
+        };
 
         let result = SyncBody {
             transactions_bytes: transactions_rlp.as_raw().to_vec(),
             transactions: TypedTransaction::decode_rlp_list(&transactions_rlp)?,
             uncles_bytes: uncles_rlp.as_raw().to_vec(),
-            uncles: BlockHeader::decode_rlp_list(&uncles_rlp, eip1559_transition)?,
+            uncles: BlockHeader::decode_rlp_list(&uncles_rlp, transitions)?,
+            withdrawals_bytes: withdrawals_rlp.as_ref().map(|r| r.as_raw().to_vec()),
+            withdrawals: match withdrawals_rlp {
+                Some(ref r) => Some(r.as_list()?),
+                None => None,
+            },
         };
 
         Ok(result)
     }
 
-    fn empty_body() -> Self {
+    fn empty_body(with_withdrawals: bool) -> Self {
         SyncBody {
             transactions_bytes: ::rlp::EMPTY_LIST_RLP.to_vec(),
             transactions: Vec::with_capacity(0),
             uncles_bytes: ::rlp::EMPTY_LIST_RLP.to_vec(),
             uncles: Vec::with_capacity(0),
+            withdrawals_bytes: if with_withdrawals {
+                Some(::rlp::EMPTY_LIST_RLP.to_vec())
+            } else {
+                None
+            },
+            withdrawals: if with_withdrawals {
+                Some(Vec::with_capacity(0))
+            } else {
+                None
+            },
         }
     }
 }
@@ -92,18 +114,29 @@ struct SyncBlock {
     receipts_root: H256,
 }
 
-fn unverified_from_sync(header: SyncHeader, body: Option<SyncBody>) -> Unverified {
-    let mut stream = RlpStream::new_list(3);
+/// Block RLP made of a header and its body.
+pub fn block_bytes(header: &SyncHeader, body: &SyncBody) -> Bytes {
+    let mut stream = RlpStream::new_list(3 + body.withdrawals_bytes.is_some() as usize);
     stream.append_raw(&header.bytes, 1);
-    let body = body.unwrap_or_else(SyncBody::empty_body);
     stream.append_raw(&body.transactions_bytes, 1);
     stream.append_raw(&body.uncles_bytes, 1);
+    if let Some(ref withdrawals_bytes) = body.withdrawals_bytes {
+        stream.append_raw(withdrawals_bytes, 1);
+    }
+    stream.out()
+}
+
+fn unverified_from_sync(header: SyncHeader, body: Option<SyncBody>) -> Unverified {
+    let body =
+        body.unwrap_or_else(|| SyncBody::empty_body(header.header.withdrawals_root().is_some()));
+    let bytes = block_bytes(&header, &body);
 
     Unverified {
         header: header.header,
         transactions: body.transactions,
         uncles: body.uncles,
-        bytes: stream.out().to_vec(),
+        withdrawals: body.withdrawals,
+        bytes,
     }
 }
 
@@ -115,11 +148,12 @@ pub struct BlockAndReceipts {
     pub receipts: Option<Bytes>,
 }
 
-/// Used to identify header by transactions and uncles hashes
+/// Used to identify header by transactions and uncles hashes and the withdrawals root
 #[derive(Eq, PartialEq, Hash)]
 struct HeaderId {
     transactions_root: H256,
     uncles: H256,
+    withdrawals_root: Option<H256>,
 }
 
 /// A collection of blocks and subchain pointers being downloaded. This keeps track of
@@ -129,6 +163,8 @@ struct HeaderId {
 pub struct BlockCollection {
     /// Does this collection need block receipts.
     need_receipts: bool,
+    /// Does this collection only collect headers, without bodies and receipts.
+    headers_only: bool,
     /// Heads of subchains to download
     heads: Vec<H256>,
     /// Downloaded blocks.
@@ -154,6 +190,7 @@ impl BlockCollection {
     pub fn new(download_receipts: bool) -> BlockCollection {
         BlockCollection {
             need_receipts: download_receipts,
+            headers_only: false,
             blocks: HashMap::new(),
             header_ids: HashMap::new(),
             receipt_ids: HashMap::new(),
@@ -166,6 +203,14 @@ impl BlockCollection {
         }
     }
 
+    /// Create a new instance that downloads headers only.
+    pub fn headers_only() -> BlockCollection {
+        BlockCollection {
+            headers_only: true,
+            ..BlockCollection::new(false)
+        }
+    }
+
     /// Clear everything.
     pub fn clear(&mut self) {
         self.blocks.clear();
@@ -224,7 +269,7 @@ impl BlockCollection {
 
     /// Returns a set of block hashes that require a body download. The returned set is marked as being downloaded.
     pub fn needed_bodies(&mut self, count: usize, _ignore_downloading: bool) -> Vec<H256> {
-        if self.head.is_none() {
+        if self.head.is_none() || self.headers_only {
             return Vec::new();
         }
         let mut needed_bodies: Vec<H256> = Vec::new();
@@ -352,8 +397,9 @@ impl BlockCollection {
                 if let Some(head) = head {
                     match self.blocks.remove(&head) {
                         Some(block) => {
-                            if block.body.is_some()
-                                && (!self.need_receipts || block.receipts.is_some())
+                            if self.headers_only
+                                || (block.body.is_some()
+                                    && (!self.need_receipts || block.receipts.is_some()))
                             {
                                 blocks.push(block);
                                 hashes.push(head);
@@ -449,9 +495,14 @@ impl BlockCollection {
                 }
             }));
             let uncles = keccak(&body.uncles_bytes);
+            let withdrawals_root = body
+                .withdrawals_bytes
+                .as_ref()
+                .map(|w| ordered_trie_root(Rlp::new(w).iter().map(|r| r.as_raw())));
             HeaderId {
                 transactions_root: tx_root,
                 uncles: uncles,
+                withdrawals_root,
             }
         };
 
@@ -539,13 +590,19 @@ impl BlockCollection {
         let header_id = HeaderId {
             transactions_root: *info.header.transactions_root(),
             uncles: *info.header.uncles_hash(),
+            withdrawals_root: info.header.withdrawals_root().cloned(),
         };
 
-        let body = if header_id.transactions_root == KECCAK_NULL_RLP
+        let body = if self.headers_only {
+            None
+        } else if header_id.transactions_root == KECCAK_NULL_RLP
             && header_id.uncles == KECCAK_EMPTY_LIST_RLP
+            && header_id
+                .withdrawals_root
+                .map_or(true, |root| root == KECCAK_NULL_RLP)
         {
             // empty body, just mark as downloaded
-            Some(SyncBody::empty_body())
+            Some(SyncBody::empty_body(header_id.withdrawals_root.is_some()))
         } else {
             trace!(
                 "Queueing body tx_root = {:?}, uncles = {:?}, block = {:?}, number = {}",
@@ -677,7 +734,7 @@ mod test {
             .map(|b| {
                 SyncHeader::from_rlp(
                     Rlp::new(b).at(0).unwrap().as_raw().to_vec(),
-                    client.spec.params().eip1559_transition,
+                    client.spec.params().header_transitions(),
                 )
                 .unwrap()
             })
@@ -715,10 +772,11 @@ mod test {
             bc.drain().into_iter().map(|b| b.block).collect::<Vec<_>>(),
             blocks[0..6]
                 .iter()
-                .map(
-                    |b| Unverified::from_rlp(b.to_vec(), client.spec.params().eip1559_transition)
-                        .unwrap()
+                .map(|b| Unverified::from_rlp(
+                    b.to_vec(),
+                    client.spec.params().header_transitions()
                 )
+                .unwrap())
                 .collect::<Vec<_>>()
         );
         assert!(!bc.contains(&hashes[0]));
@@ -735,10 +793,11 @@ mod test {
             bc.drain().into_iter().map(|b| b.block).collect::<Vec<_>>(),
             blocks[6..16]
                 .iter()
-                .map(
-                    |b| Unverified::from_rlp(b.to_vec(), client.spec.params().eip1559_transition)
-                        .unwrap()
+                .map(|b| Unverified::from_rlp(
+                    b.to_vec(),
+                    client.spec.params().header_transitions()
                 )
+                .unwrap())
                 .collect::<Vec<_>>()
         );
 
@@ -769,7 +828,7 @@ mod test {
             .map(|b| {
                 SyncHeader::from_rlp(
                     Rlp::new(b).at(0).unwrap().as_raw().to_vec(),
-                    client.spec.params().eip1559_transition,
+                    client.spec.params().header_transitions(),
                 )
                 .unwrap()
             })
@@ -811,7 +870,7 @@ mod test {
             .map(|b| {
                 SyncHeader::from_rlp(
                     Rlp::new(b).at(0).unwrap().as_raw().to_vec(),
-                    client.spec.params().eip1559_transition,
+                    client.spec.params().header_transitions(),
                 )
                 .unwrap()
             })
@@ -829,4 +888,39 @@ mod test {
         bc.insert_headers(headers[0..1].into_iter().map(Clone::clone).collect());
         assert_eq!(bc.drain().len(), 2);
     }
+
+    #[test]
+    fn insert_headers_only() {
+        let mut bc = BlockCollection::headers_only();
+        assert!(is_empty(&bc));
+        let client = TestBlockChainClient::new();
+        let nblocks = 20;
+        client.add_blocks(nblocks, EachBlockWith::UncleAndTransaction);
+        let headers: Vec<_> = (0..nblocks)
+            .map(|i| {
+                let block = (&client as &dyn BlockChainClient)
+                    .block(BlockId::Number(i as BlockNumber))
+                    .unwrap()
+                    .into_inner();
+                SyncHeader::from_rlp(
+                    Rlp::new(&block).at(0).unwrap().as_raw().to_vec(),
+                    client.spec.params().header_transitions(),
+                )
+                .unwrap()
+            })
+            .collect();
+        let hashes: Vec<_> = headers.iter().map(|h| h.header.hash()).collect();
+        bc.reset_to(vec![hashes[0]]);
+
+        bc.insert_headers(headers[0..10].into_iter().map(Clone::clone).collect());
+        assert!(bc.needed_bodies(10, false).is_empty());
+        assert!(bc.needed_receipts(10, false).is_empty());
+        assert_eq!(
+            bc.drain()
+                .into_iter()
+                .map(|b| b.block.header.hash())
+                .collect::<Vec<_>>(),
+            hashes[0..10].to_vec()
+        );
+    }
 }
@@ -4,8 +4,12 @@
 // Re-export ethereum-forkid crate contents here.
 pub use ethereum_forkid::{BlockNumber, ForkId, RejectReason};
 
-use ethcore::client::ChainInfo;
+use ethcore::client::{BlockChainClient, ChainInfo};
 use ethereum_forkid::ForkFilter;
+use network::NodeRecordFilter;
+use parking_lot::Mutex;
+use rlp::{Rlp, RlpStream};
+use std::sync::Arc;
 
 /// Wrapper around fork filter that provides integration with `ForkFilter`.
 pub struct ForkFilterApi {
@@ -59,6 +63,45 @@ impl ForkFilterApi {
     }
 }
 
+/// Advertises the current `FORK_ID` as the `eth` entry of the local node record (EIP-2124) and
+/// keeps nodes on other forks from being dialed.
+pub struct ForkIdRecordFilter {
+    chain: Arc<dyn BlockChainClient>,
+    fork_filter: Mutex<ForkFilterApi>,
+}
+
+impl ForkIdRecordFilter {
+    /// Create `ForkIdRecordFilter` from the chain client and an `Iterator` over the hard forks.
+    pub fn new<I: IntoIterator<Item = BlockNumber>>(
+        chain: Arc<dyn BlockChainClient>,
+        forks: I,
+    ) -> Self {
+        let fork_filter = Mutex::new(ForkFilterApi::new(&*chain, forks));
+        Self { chain, fork_filter }
+    }
+}
+
+impl NodeRecordFilter for ForkIdRecordFilter {
+    fn local_eth_entry(&self) -> Vec<u8> {
+        let fork_id = self.fork_filter.lock().current(&*self.chain);
+        let mut stream = RlpStream::new_list(1);
+        stream.append(&fork_id);
+        stream.out()
+    }
+
+    fn is_compatible(&self, eth_entry: &[u8]) -> bool {
+        // `[[fork_hash, fork_next], ...]`, later elements are reserved for extensions
+        match Rlp::new(eth_entry).val_at::<ForkId>(0) {
+            Ok(fork_id) => self
+                .fork_filter
+                .lock()
+                .is_compatible(&*self.chain, fork_id)
+                .is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -111,6 +154,25 @@ mod tests {
         )
     }
 
+    #[test]
+    fn record_filter() {
+        let spec = ethereum::new_foundation(&String::new());
+        let forks = spec.hard_forks.clone();
+        let client = Arc::new(TestBlockChainClient::new_with_spec(spec));
+        let filter = ForkIdRecordFilter::new(client, forks);
+
+        let eth_entry = filter.local_eth_entry();
+        assert!(filter.is_compatible(&eth_entry));
+
+        let mut stream = RlpStream::new_list(1);
+        stream
+            .begin_list(2)
+            .append(&&[0xdeu8, 0xad, 0xbe, 0xef][..])
+            .append(&0u64);
+        assert!(!filter.is_compatible(&stream.out()));
+        assert!(!filter.is_compatible(&[0xc0]));
+    }
+
     #[test]
     fn goerli_spec() {
         test_spec(
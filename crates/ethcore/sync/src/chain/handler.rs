@@ -26,11 +26,13 @@ use ethcore::{
 use ethereum_types::{H256, U256};
 use hash::keccak;
 use network::{client_version::ClientVersion, PeerId};
-use rlp::Rlp;
+use rlp::{Rlp, RlpStream};
 use snapshot::ChunkType;
 use std::{cmp, mem, time::Instant};
 use sync_io::SyncIo;
-use types::{block_status::BlockStatus, ids::BlockId, BlockNumber};
+use types::{block_status::BlockStatus, ids::BlockId, transaction::TypedTxId, BlockNumber};
+
+use reputation::Behaviour;
 
 use super::{
     request_id::strip_request_id,
@@ -41,9 +43,11 @@ use super::{
 };
 
 use super::{
-    BlockSet, ChainSync, ForkConfirmation, PacketProcessError, PeerAsking, PeerInfo, SyncRequester,
-    SyncState, ETH_PROTOCOL_VERSION_63, ETH_PROTOCOL_VERSION_64, ETH_PROTOCOL_VERSION_66,
-    MAX_NEW_BLOCK_AGE, MAX_NEW_HASHES, PAR_PROTOCOL_VERSION_1, PAR_PROTOCOL_VERSION_2,
+    fork_filter::ForkId, snap::SnapHandler, BlockSet, ChainSync, ForkConfirmation,
+    PacketProcessError, PeerAsking, PeerInfo, SyncRequester, SyncState, TransactionAnnouncement,
+    ETH_PROTOCOL_VERSION_63, ETH_PROTOCOL_VERSION_64, ETH_PROTOCOL_VERSION_66,
+    ETH_PROTOCOL_VERSION_68, MAX_NEW_BLOCK_AGE, MAX_NEW_HASHES, PAR_PROTOCOL_VERSION_1,
+    PAR_PROTOCOL_VERSION_2,
 };
 
 /// The Chain Sync Handler: handles responses from peers
@@ -60,6 +64,18 @@ impl SyncHandler {
     ) {
         if let Some(packet_id) = SyncPacket::from_u8(packet_id) {
             let rlp_result = strip_request_id(data, sync, &peer, &packet_id);
+            let asked_at = match packet_id {
+                BlockHeadersPacket
+                | BlockBodiesPacket
+                | ReceiptsPacket
+                | SnapshotManifestPacket
+                | SnapshotDataPacket => sync
+                    .peers
+                    .get(&peer)
+                    .filter(|p| p.asking != PeerAsking::Nothing)
+                    .map(|p| p.ask_time),
+                _ => None,
+            };
 
             let result = match rlp_result {
                 Ok((rlp, _)) => match packet_id {
@@ -90,13 +106,16 @@ impl SyncHandler {
             match result {
                 Err(DownloaderImportError::Invalid) => {
                     debug!(target:"sync", "{} -> Invalid packet {}", peer, packet_id.id());
-                    io.disable_peer(peer);
-                    sync.deactivate_peer(io, peer);
+                    sync.note_peer(io, peer, Behaviour::InvalidPacket);
                 }
                 Err(DownloaderImportError::Useless) => {
                     sync.deactivate_peer(io, peer);
+                    sync.note_peer(io, peer, Behaviour::UselessResponse);
                 }
                 Ok(()) => {
+                    if let Some(asked_at) = asked_at {
+                        sync.note_response(io, peer, asked_at);
+                    }
                     // give a task to the same peer first
                     sync.sync_peer(io, peer, false);
                 }
@@ -116,6 +135,8 @@ impl SyncHandler {
     pub fn on_peer_aborting(sync: &mut ChainSync, io: &mut dyn SyncIo, peer_id: PeerId) {
         trace!(target: "sync", "== Disconnecting {}: {}", peer_id, io.peer_version(peer_id));
         sync.handshaking_peers.remove(&peer_id);
+        let node_id = sync.peers.get(&peer_id).and_then(|p| p.node_id);
+        sync.reputation.disconnected(peer_id, node_id);
         if sync.peers.contains_key(&peer_id) {
             debug!(target: "sync", "Disconnected {}", peer_id);
             sync.clear_peer_download(peer_id);
@@ -138,7 +159,8 @@ impl SyncHandler {
                     .is_none();
 
                 if still_asking_manifest {
-                    sync.state = ChainSync::get_init_state(sync.warp_sync, io.chain());
+                    sync.state =
+                        ChainSync::get_init_state(sync.warp_sync, sync.checkpoint, io.chain());
                 }
             }
             sync.continue_sync(io);
@@ -168,7 +190,7 @@ impl SyncHandler {
             return Ok(());
         }
         // t_nb 1.0 decode RLP
-        let block = Unverified::from_rlp(r.at(0)?.as_raw().to_vec(), sync.eip1559_transition)?;
+        let block = Unverified::from_rlp(r.at(0)?.as_raw().to_vec(), sync.header_transitions)?;
         let hash = block.header.hash();
         let number = block.header.number();
         trace!(target: "sync", "{} -> NewBlock ({})", peer_id, hash);
@@ -320,6 +342,12 @@ impl SyncHandler {
         Ok(())
     }
 
+    fn is_asking_snap_pivot(sync: &ChainSync, peer_id: PeerId) -> bool {
+        sync.peers
+            .get(&peer_id)
+            .map_or(false, |p| p.asking == PeerAsking::SnapPivot)
+    }
+
     /// Called by peer once it has new block bodies
     fn on_peer_block_bodies(
         sync: &mut ChainSync,
@@ -327,6 +355,9 @@ impl SyncHandler {
         peer_id: PeerId,
         r: &Rlp,
     ) -> Result<(), DownloaderImportError> {
+        if SyncHandler::is_asking_snap_pivot(sync, peer_id) {
+            return SnapHandler::on_pivot_body(sync, io, peer_id, r);
+        }
         sync.clear_peer_download(peer_id);
         let block_set = sync
             .peers
@@ -369,7 +400,7 @@ impl SyncHandler {
                         Some(ref mut blocks) => blocks,
                     },
                 };
-                downloader.import_bodies(r, expected_blocks.as_slice(), sync.eip1559_transition)?;
+                downloader.import_bodies(r, expected_blocks.as_slice(), sync.header_transitions)?;
             }
             sync.collect_blocks(io, block_set);
             Ok(())
@@ -434,6 +465,9 @@ impl SyncHandler {
         if is_fork_header_request {
             return SyncHandler::on_peer_fork_header(sync, io, peer_id, r);
         }
+        if SyncHandler::is_asking_snap_pivot(sync, peer_id) {
+            return SnapHandler::on_pivot_headers(sync, io, peer_id, r);
+        }
 
         sync.clear_peer_download(peer_id);
         let expected_hash = sync.peers.get(&peer_id).and_then(|p| p.asking_hash);
@@ -488,7 +522,7 @@ impl SyncHandler {
                     Some(ref mut blocks) => blocks,
                 },
             };
-            downloader.import_headers(io, r, expected_hash, sync.eip1559_transition)?
+            downloader.import_headers(io, peer_id, r, expected_hash, sync.header_transitions)?
         };
 
         if result == DownloadAction::Reset {
@@ -506,6 +540,9 @@ impl SyncHandler {
         peer_id: PeerId,
         r: &Rlp,
     ) -> Result<(), DownloaderImportError> {
+        if SyncHandler::is_asking_snap_pivot(sync, peer_id) {
+            return SnapHandler::on_pivot_receipts(sync, io, peer_id, r);
+        }
         sync.clear_peer_download(peer_id);
         let block_set = sync
             .peers
@@ -655,6 +692,7 @@ impl SyncHandler {
             }
             Err(()) => {
                 trace!(target: "sync", "{}: Got bad snapshot chunk", peer_id);
+                sync.note_peer(io, peer_id, Behaviour::BadSnapshotChunk);
                 io.disconnect_peer(peer_id);
                 return Ok(());
             }
@@ -704,10 +742,13 @@ impl SyncHandler {
             .ok_or(rlp::DecoderError::RlpIsTooShort)?
             .as_val()?;
         let forkid_validation_error = if eth_protocol_version >= ETH_PROTOCOL_VERSION_64.0 {
-            let fork_id = r_iter
+            let fork_id: ForkId = r_iter
                 .next()
                 .ok_or(rlp::DecoderError::RlpIsTooShort)?
                 .as_val()?;
+            let mut eth_entry = RlpStream::new_list(1);
+            eth_entry.append(&fork_id);
+            io.set_peer_fork_id(peer_id, eth_entry.out());
             sync.fork_filter
                 .is_compatible(io.chain(), fork_id)
                 .err()
@@ -755,10 +796,12 @@ impl SyncHandler {
                 ForkConfirmation::Unconfirmed
             },
             asking_snapshot_data: None,
+            asking_snap: None,
             snapshot_hash,
             snapshot_number,
             block_set: None,
             client_version: ClientVersion::from(io.peer_version(peer_id)),
+            node_id: io.peer_session_info(peer_id).and_then(|info| info.id),
         };
 
         trace!(target: "sync", "New peer {} (\
@@ -806,7 +849,7 @@ impl SyncHandler {
                     || peer.protocol_version > PAR_PROTOCOL_VERSION_2.0))
             || (!warp_protocol
                 && (peer.protocol_version < ETH_PROTOCOL_VERSION_63.0
-                    || peer.protocol_version > ETH_PROTOCOL_VERSION_66.0))
+                    || peer.protocol_version > ETH_PROTOCOL_VERSION_68.0))
         {
             trace!(target: "sync", "Peer {} unsupported eth protocol ({})", peer_id, peer.protocol_version);
             return Err(DownloaderImportError::Invalid);
@@ -816,7 +859,10 @@ impl SyncHandler {
             sync.sync_start_time = Some(Instant::now());
         }
 
+        let node_id = peer.node_id;
         sync.peers.insert(peer_id.clone(), peer);
+        sync.reputation.connected(peer_id, node_id);
+        io.set_peer_reputation(peer_id, sync.reputation.score(peer_id));
         // Don't activate peer immediatelly when searching for common block.
         // Let the current sync round complete first.
         sync.active_peers.insert(peer_id.clone());
@@ -836,21 +882,60 @@ impl SyncHandler {
         peer_id: PeerId,
         tx_rlp: &Rlp,
     ) -> Result<(), DownloaderImportError> {
-        for item in tx_rlp {
-            let hash = item
-                .as_val::<H256>()
-                .map_err(|_| DownloaderImportError::Invalid)?;
+        let protocol_version = match sync.peers.get(&peer_id) {
+            Some(peer) => peer.protocol_version,
+            None => return Ok(()),
+        };
+        let announcements = if protocol_version >= ETH_PROTOCOL_VERSION_68.0 {
+            SyncHandler::decode_pooled_transaction_announcements(tx_rlp)?
+        } else {
+            let mut announcements = Vec::new();
+            for item in tx_rlp {
+                let hash = item
+                    .as_val::<H256>()
+                    .map_err(|_| DownloaderImportError::Invalid)?;
+                announcements.push((hash, None));
+            }
+            announcements
+        };
 
+        for (hash, announcement) in announcements {
             if io.chain().queued_transaction(hash).is_none() {
-                sync.peers
-                    .get_mut(&peer_id)
-                    .map(|peer| peer.unfetched_pooled_transactions.insert(hash));
+                sync.peers.get_mut(&peer_id).map(|peer| {
+                    peer.unfetched_pooled_transactions
+                        .insert(hash, announcement)
+                });
             }
         }
 
         Ok(())
     }
 
+    /// Decodes an eth/68 `NewPooledTransactionHashes` packet: `[types: B, [size: P, ...], [hash: B_32, ...]]`.
+    /// Announcements of unknown transaction types are skipped.
+    pub fn decode_pooled_transaction_announcements(
+        tx_rlp: &Rlp,
+    ) -> Result<Vec<(H256, Option<TransactionAnnouncement>)>, DownloaderImportError> {
+        let types: Vec<u8> = tx_rlp.val_at(0)?;
+        let sizes: Vec<u64> = tx_rlp.list_at(1)?;
+        let hashes: Vec<H256> = tx_rlp.list_at(2)?;
+        if types.len() != sizes.len() || types.len() != hashes.len() {
+            trace!(target: "sync", "Mismatched NewPooledTransactionHashes fields: {} types, {} sizes, {} hashes", types.len(), sizes.len(), hashes.len());
+            return Err(DownloaderImportError::Invalid);
+        }
+
+        Ok(types
+            .into_iter()
+            .zip(sizes)
+            .zip(hashes)
+            .filter(|((tx_type, _), _)| TypedTxId::from_u8_id(*tx_type).is_some())
+            .map(|((tx_type, size), hash)| {
+                let size = size as usize;
+                (hash, Some(TransactionAnnouncement { tx_type, size }))
+            })
+            .collect())
+    }
+
     /// Called when peer sends us a list of pooled transactions
     pub fn on_peer_pooled_transactions(
         sync: &ChainSync,
@@ -872,6 +957,10 @@ impl SyncHandler {
             return Err(DownloaderImportError::Invalid);
         }
         trace!(target: "sync", "{:02} -> PooledTransactions ({} entries)", peer_id, item_count);
+        let has_announcements = peer
+            .asking_pooled_transactions
+            .iter()
+            .any(|(_, announcement)| announcement.is_some());
         let mut transactions = Vec::with_capacity(item_count);
         for i in 0..item_count {
             let rlp = tx_rlp.at(i)?;
@@ -881,6 +970,28 @@ impl SyncHandler {
                 rlp.data()?
             }
             .to_vec();
+
+            // eth/68 peers have told us the type and size upfront, the delivered transaction must match.
+            let announcement = if has_announcements {
+                let hash = keccak(&tx);
+                peer.asking_pooled_transactions
+                    .iter()
+                    .find(|(asked, _)| *asked == hash)
+                    .and_then(|(_, announcement)| *announcement)
+            } else {
+                None
+            };
+            if let Some(announcement) = announcement {
+                let tx_type = if rlp.is_list() {
+                    TypedTxId::Legacy as u8
+                } else {
+                    tx.first().copied().unwrap_or_default()
+                };
+                if announcement.tx_type != tx_type || announcement.size != tx.len() {
+                    trace!(target: "sync", "{} Peer sent a transaction that doesn't match its announcement", peer_id);
+                    return Err(DownloaderImportError::Invalid);
+                }
+            }
             transactions.push(tx);
         }
         io.chain().queue_transactions(transactions, peer_id);
@@ -92,12 +92,15 @@ mod handler;
 mod propagator;
 pub mod request_id;
 mod requester;
+mod snap;
 mod supplier;
 pub mod sync_packet;
 
 pub use self::fork_filter::ForkFilterApi;
-use super::{SyncConfig, WarpSync};
-use api::{EthProtocolInfo as PeerInfoDigest, PriorityTask, ETH_PROTOCOL, PAR_PROTOCOL};
+use super::{Checkpoint, SyncConfig, WarpSync};
+use api::{
+    EthProtocolInfo as PeerInfoDigest, PriorityTask, ETH_PROTOCOL, PAR_PROTOCOL, SNAP_PROTOCOL,
+};
 use block_sync::{BlockDownloader, DownloadAction};
 use bytes::Bytes;
 use derive_more::Display;
@@ -108,23 +111,28 @@ use ethcore::{
 use ethereum_types::{H256, U256};
 use fastmap::{H256FastMap, H256FastSet};
 use hash::keccak;
-use network::{self, client_version::ClientVersion, PeerId};
+use network::{self, client_version::ClientVersion, NodeId, PeerId};
 use parking_lot::{Mutex, RwLock, RwLockWriteGuard};
+use propagation::{DefaultPropagation, TransactionPropagation};
 use rand::{seq::SliceRandom, Rng};
+use reputation::{Behaviour, PeerReputation, DISABLE_THRESHOLD, SLOW_RESPONSE};
 use rlp::{DecoderError, RlpStream};
+use snap_sync::{SnapDownloader, SnapRequest};
 use snapshot::Snapshot;
 use std::{
     cmp,
     collections::{BTreeMap, HashMap, HashSet},
-    sync::mpsc,
+    sync::{mpsc, Arc},
     time::{Duration, Instant},
 };
 use sync_io::SyncIo;
 use transactions_stats::{Stats as TransactionStats, TransactionsStats};
-use types::{transaction::UnverifiedTransaction, BlockNumber};
+use types::{header::HeaderTransitions, transaction::UnverifiedTransaction, BlockNumber};
 
 use self::{
     handler::SyncHandler,
+    request_id::RequestId,
+    snap::SnapHandler,
     sync_packet::{
         PacketInfo,
         SyncPacket::{self, NewBlockPacket, StatusPacket},
@@ -154,6 +162,10 @@ impl From<DecoderError> for PacketProcessError {
     }
 }
 
+/// Version 68 of the Ethereum protocol (transaction announcements carry types and sizes).
+pub const ETH_PROTOCOL_VERSION_68: (u8, u8) = (68, 0x11);
+/// Version 67 of the Ethereum protocol (`GetNodeData` and `NodeData` removed).
+pub const ETH_PROTOCOL_VERSION_67: (u8, u8) = (67, 0x11);
 /// Version 66 of the Ethereum protocol and number of packet IDs reserved by the protocol (packet count).
 pub const ETH_PROTOCOL_VERSION_66: (u8, u8) = (66, 0x11);
 /// Version 65 of the Ethereum protocol and number of packet IDs reserved by the protocol (packet count).
@@ -166,12 +178,16 @@ pub const ETH_PROTOCOL_VERSION_63: (u8, u8) = (63, 0x11);
 pub const PAR_PROTOCOL_VERSION_1: (u8, u8) = (1, 0x15);
 /// 2 version of OpenEthereum protocol (consensus messages added).
 pub const PAR_PROTOCOL_VERSION_2: (u8, u8) = (2, 0x16);
+/// 1 version of the Ethereum state snapshot protocol and the packet count.
+pub const SNAP_PROTOCOL_VERSION_1: (u8, u8) = (1, 0x08);
 
 pub const MAX_BODIES_TO_SEND: usize = 256;
 pub const MAX_HEADERS_TO_SEND: usize = 512;
 pub const MAX_NODE_DATA_TO_SEND: usize = 1024;
 pub const MAX_RECEIPTS_HEADERS_TO_SEND: usize = 256;
 pub const MAX_TRANSACTIONS_TO_REQUEST: usize = 256;
+// Upper bound on the total announced size of transactions asked for in a single request.
+pub const MAX_POOLED_TRANSACTIONS_REQUEST_SIZE: usize = 128 * 1024;
 const MIN_PEERS_PROPAGATION: usize = 4;
 const MAX_PEERS_PROPAGATION: usize = 128;
 const MAX_PEER_LAG_PROPAGATION: BlockNumber = 20;
@@ -185,6 +201,13 @@ const SNAPSHOT_RESTORE_THRESHOLD: BlockNumber = 30000;
 const SNAPSHOT_MIN_PEERS: usize = 3;
 
 const MAX_SNAPSHOT_CHUNKS_DOWNLOAD_AHEAD: usize = 3;
+// Distance of the `snap` pivot block from the head of the chain. Peers only keep
+// the state of the most recent blocks.
+const SNAP_PIVOT_DISTANCE: BlockNumber = 64;
+// Number of most recent blocks peers serve the state of over `snap`.
+const SNAP_SERVE_WINDOW: BlockNumber = 128;
+// Max size of the `snap` responses we ask for.
+const SNAP_RESPONSE_BYTES: usize = 512 * 1024;
 
 const WAIT_PEERS_TIMEOUT: Duration = Duration::from_secs(5);
 const STATUS_TIMEOUT: Duration = Duration::from_secs(5);
@@ -195,6 +218,8 @@ const POOLED_TRANSACTIONS_TIMEOUT: Duration = Duration::from_secs(10);
 const FORK_HEADER_TIMEOUT: Duration = Duration::from_secs(3);
 const SNAPSHOT_MANIFEST_TIMEOUT: Duration = Duration::from_secs(5);
 const SNAPSHOT_DATA_TIMEOUT: Duration = Duration::from_secs(120);
+const SNAP_PIVOT_TIMEOUT: Duration = Duration::from_secs(15);
+const SNAP_STATE_TIMEOUT: Duration = Duration::from_secs(20);
 
 /// Defines how much time we have to complete priority transaction or block propagation.
 /// after the deadline is reached the task is considered finished
@@ -212,6 +237,10 @@ pub enum SyncState {
     SnapshotData,
     /// Waiting for snapshot restoration progress.
     SnapshotWaiting,
+    /// Downloading the pivot block of a `snap` sync
+    SnapPivot,
+    /// Downloading state over `snap`
+    SnapState,
     /// Downloading new blocks
     Blocks,
     /// Initial chain sync complete. Waiting for new packets
@@ -259,9 +288,11 @@ impl SyncStatus {
     /// Indicates if snapshot download is in progress
     pub fn is_snapshot_syncing(&self) -> bool {
         match self.state {
-            SyncState::SnapshotManifest | SyncState::SnapshotData | SyncState::SnapshotWaiting => {
-                true
-            }
+            SyncState::SnapshotManifest
+            | SyncState::SnapshotData
+            | SyncState::SnapshotWaiting
+            | SyncState::SnapPivot
+            | SyncState::SnapState => true,
             _ => false,
         }
     }
@@ -297,6 +328,8 @@ pub enum PeerAsking {
     PooledTransactions,
     SnapshotManifest,
     SnapshotData,
+    SnapPivot,
+    SnapState,
 }
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
@@ -327,6 +360,15 @@ pub enum ForkConfirmation {
     Confirmed,
 }
 
+/// Transaction type and size announced by an eth/68 peer alongside the transaction hash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransactionAnnouncement {
+    /// EIP-2718 transaction type
+    pub tx_type: u8,
+    /// Size of the consensus encoding of the transaction
+    pub size: usize,
+}
+
 #[derive(Clone)]
 /// Syncing peer information
 pub struct PeerInfo {
@@ -346,12 +388,14 @@ pub struct PeerInfo {
     asking_blocks: Vec<H256>,
     /// Holds requested header hash if currently requesting block header by hash
     asking_hash: Option<H256>,
-    /// Hashes of transactions to be requested.
-    unfetched_pooled_transactions: H256FastSet,
+    /// Hashes of transactions to be requested, with their announcement if the peer sent one.
+    unfetched_pooled_transactions: H256FastMap<Option<TransactionAnnouncement>>,
     /// Hashes of the transactions we're requesting.
-    asking_pooled_transactions: Vec<H256>,
+    asking_pooled_transactions: Vec<(H256, Option<TransactionAnnouncement>)>,
     /// Holds requested snapshot chunk hash if any.
     asking_snapshot_data: Option<H256>,
+    /// Holds the `snap` request in flight, its id and the state root it is for.
+    asking_snap: Option<(RequestId, H256, SnapRequest)>,
     /// Request timestamp
     ask_time: Instant,
     /// Holds a set of transactions recently sent to this peer to avoid spamming.
@@ -368,6 +412,8 @@ pub struct PeerInfo {
     block_set: Option<BlockSet>,
     /// Version of the software the peer is running
     client_version: ClientVersion,
+    /// Node id of the peer, if known
+    node_id: Option<NodeId>,
 }
 
 impl PeerInfo {
@@ -379,6 +425,31 @@ impl PeerInfo {
         self.confirmation != ForkConfirmation::Unconfirmed && !self.expired
     }
 
+    /// Moves a batch of unfetched transactions to the in-flight request. The batch is bounded
+    /// by `MAX_TRANSACTIONS_TO_REQUEST` and, for transactions announced with their size,
+    /// by `MAX_POOLED_TRANSACTIONS_REQUEST_SIZE`. At least one transaction is always selected.
+    fn take_pooled_transactions_to_request(&mut self) -> Vec<H256> {
+        let mut total_size = 0;
+        let mut to_request = Vec::new();
+        for (hash, announcement) in &self.unfetched_pooled_transactions {
+            if to_request.len() >= MAX_TRANSACTIONS_TO_REQUEST {
+                break;
+            }
+            let size = announcement.map_or(0, |a| a.size);
+            if !to_request.is_empty() && total_size + size > MAX_POOLED_TRANSACTIONS_REQUEST_SIZE {
+                continue;
+            }
+            total_size += size;
+            to_request.push((*hash, *announcement));
+        }
+        for (hash, _) in &to_request {
+            self.unfetched_pooled_transactions.remove(hash);
+        }
+        let hashes = to_request.iter().map(|(hash, _)| *hash).collect();
+        self.asking_pooled_transactions = to_request;
+        hashes
+    }
+
     fn reset_asking(&mut self) {
         self.asking_blocks.clear();
         self.asking_hash = None;
@@ -427,14 +498,12 @@ impl ChainSyncApi {
         fork_filter: ForkFilterApi,
         priority_tasks: mpsc::Receiver<PriorityTask>,
         new_transaction_hashes: crossbeam_channel::Receiver<H256>,
+        transaction_propagation: Arc<dyn TransactionPropagation>,
     ) -> Self {
+        let mut sync = ChainSync::new(config, chain, fork_filter, new_transaction_hashes);
+        sync.set_transaction_propagation(transaction_propagation);
         ChainSyncApi {
-            sync: RwLock::new(ChainSync::new(
-                config,
-                chain,
-                fork_filter,
-                new_transaction_hashes,
-            )),
+            sync: RwLock::new(sync),
             priority_tasks: Mutex::new(priority_tasks),
         }
     }
@@ -455,6 +524,11 @@ impl ChainSyncApi {
         self.sync.read().status()
     }
 
+    /// Returns peer reputation scores and the number of peers disabled for a low score
+    pub fn reputation_stats(&self) -> (Vec<i32>, usize) {
+        self.sync.read().reputation_stats()
+    }
+
     /// Returns pending transactions propagation statistics
     pub fn pending_transactions_stats(&self) -> BTreeMap<H256, ::TransactionStats> {
         self.sync
@@ -480,6 +554,17 @@ impl ChainSyncApi {
         SyncSupplier::dispatch_packet(&self.sync, io, peer, packet_id, data)
     }
 
+    /// Dispatch incoming `snap` requests and responses
+    pub fn dispatch_snap_packet(
+        &self,
+        io: &mut dyn SyncIo,
+        peer: PeerId,
+        packet_id: u8,
+        data: &[u8],
+    ) {
+        SnapHandler::dispatch_packet(&self.sync, io, peer, packet_id, data)
+    }
+
     /// Process the queue with requests, that were delayed with response.
     pub fn process_delayed_requests(&self, io: &mut dyn SyncIo) {
         let requests = self.sync.write().retrieve_delayed_requests();
@@ -654,8 +739,15 @@ impl ChainSync {
         peers
     }
 
-    fn get_init_state(warp_sync: WarpSync, chain: &dyn BlockChainClient) -> SyncState {
+    fn get_init_state(
+        warp_sync: WarpSync,
+        checkpoint: Option<Checkpoint>,
+        chain: &dyn BlockChainClient,
+    ) -> SyncState {
         let best_block = chain.chain_info().best_block_number;
+        if checkpoint.map_or(false, |c| c.number > best_block) {
+            return SyncState::WaitingPeers;
+        }
         match warp_sync {
             WarpSync::Enabled => SyncState::WaitingPeers,
             WarpSync::OnlyAndAfter(block) if block > best_block => SyncState::WaitingPeers,
@@ -699,6 +791,12 @@ pub struct ChainSync {
     fork_filter: ForkFilterApi,
     /// Snapshot downloader.
     snapshot: Snapshot,
+    /// `snap` state downloader.
+    snap: SnapDownloader,
+    /// Enable `snap` state sync.
+    snap_sync: bool,
+    /// Trusted block to start the chain at.
+    checkpoint: Option<Checkpoint>,
     /// Connected peers pending Status message.
     /// Value is request timestamp.
     handshaking_peers: HashMap<PeerId, Instant>,
@@ -712,12 +810,18 @@ pub struct ChainSync {
     new_transaction_hashes: crossbeam_channel::Receiver<H256>,
     /// Transactions propagation statistics
     transactions_stats: TransactionsStats,
+    /// Decides which peers receive which transactions
+    transaction_propagation: Arc<dyn TransactionPropagation>,
+    /// Reputation of the connected peers
+    reputation: PeerReputation,
     /// Enable ancient block downloading
     download_old_blocks: bool,
+    /// First ancient block to download the body and receipts for
+    ancient_blocks_from: Option<BlockNumber>,
     /// Enable warp sync.
     warp_sync: WarpSync,
-    /// New block encoding/decoding format is introduced by the EIP1559
-    eip1559_transition: BlockNumber,
+    /// Blocks from which the optional header fields are present. Needed to decode headers.
+    header_transitions: HeaderTransitions,
     /// Number of blocks for which new transactions will be returned in a result of `parity_newTransactionsStats` RPC call
     new_transactions_stats_period: BlockNumber,
 }
@@ -780,7 +884,7 @@ impl ChainSync {
     ) -> Self {
         let chain_info = chain.chain_info();
         let best_block = chain.chain_info().best_block_number;
-        let state = Self::get_init_state(config.warp_sync, chain);
+        let state = Self::get_init_state(config.warp_sync, config.checkpoint, chain);
 
         let mut sync = ChainSync {
             state,
@@ -802,18 +906,29 @@ impl ChainSync {
             fork_block: config.fork_block,
             fork_filter,
             download_old_blocks: config.download_old_blocks,
+            ancient_blocks_from: config.ancient_blocks_from,
             snapshot: Snapshot::new(),
+            snap: SnapDownloader::new(),
+            snap_sync: config.snap_sync,
+            checkpoint: config.checkpoint,
             sync_start_time: None,
             new_transaction_hashes,
             transactions_stats: TransactionsStats::default(),
+            transaction_propagation: Arc::new(DefaultPropagation),
+            reputation: PeerReputation::default(),
             warp_sync: config.warp_sync,
-            eip1559_transition: config.eip1559_transition,
+            header_transitions: config.header_transitions,
             new_transactions_stats_period: config.new_transactions_stats_period,
         };
         sync.update_targets(chain);
         sync
     }
 
+    /// Replace the transaction propagation policy.
+    pub fn set_transaction_propagation(&mut self, policy: Arc<dyn TransactionPropagation>) {
+        self.transaction_propagation = policy;
+    }
+
     /// Returns synchonization status
     pub fn status(&self) -> SyncStatus {
         let last_imported_number = self.new_blocks.last_imported_block_number();
@@ -863,6 +978,7 @@ impl ChainSync {
             version: peer_data.protocol_version as u32,
             difficulty: peer_data.difficulty,
             head: peer_data.latest_hash,
+            reputation: self.reputation.score(*peer_id),
         })
     }
 
@@ -909,24 +1025,24 @@ impl ChainSync {
         // Remove imported txs from all request queues
         let imported = txs.iter().map(|tx| tx.hash()).collect::<H256FastSet>();
         for (pid, peer_info) in &mut self.peers {
-            peer_info.unfetched_pooled_transactions = peer_info
+            peer_info
                 .unfetched_pooled_transactions
-                .difference(&imported)
-                .copied()
-                .collect();
+                .retain(|hash, _| !imported.contains(hash));
             if *pid == peer_id {
+                let asking =
+                    std::mem::replace(&mut peer_info.asking_pooled_transactions, Vec::new());
                 match GetPooledTransactionsReport::generate(
-                    std::mem::replace(&mut peer_info.asking_pooled_transactions, Vec::new()),
+                    asking.iter().map(|(hash, _)| *hash).collect(),
                     txs.iter().map(UnverifiedTransaction::hash),
                 ) {
                     Ok(report) => {
                         // Some transactions were not received in this batch because of size.
                         // Add them back to request feed.
-                        peer_info.unfetched_pooled_transactions = peer_info
-                            .unfetched_pooled_transactions
-                            .union(&report.not_sent)
-                            .copied()
-                            .collect();
+                        peer_info.unfetched_pooled_transactions.extend(
+                            asking
+                                .into_iter()
+                                .filter(|(hash, _)| report.not_sent.contains(hash)),
+                        );
                     }
                     Err(_unknown_tx) => {
                         // punish peer?
@@ -960,7 +1076,8 @@ impl ChainSync {
                 }
             }
         }
-        self.state = state.unwrap_or_else(|| Self::get_init_state(self.warp_sync, io.chain()));
+        self.state = state
+            .unwrap_or_else(|| Self::get_init_state(self.warp_sync, self.checkpoint, io.chain()));
         // Reactivate peers only if some progress has been made
         // since the last sync round of if starting fresh.
         self.active_peers = self.peers.keys().cloned().collect();
@@ -985,11 +1102,12 @@ impl ChainSync {
     /// Restart sync
     pub fn reset_and_continue(&mut self, io: &mut dyn SyncIo) {
         trace!(target: "sync", "Restarting");
-        if self.state == SyncState::SnapshotData {
+        if self.state == SyncState::SnapshotData || self.state == SyncState::SnapState {
             debug!(target:"sync", "Aborting snapshot restore");
             io.snapshot_service().abort_restore();
         }
         self.snapshot.clear();
+        self.snap.clear();
         self.reset(io, None);
         self.continue_sync(io);
     }
@@ -1001,7 +1119,43 @@ impl ChainSync {
         self.active_peers.remove(&peer_id);
     }
 
+    /// Update the reputation of a peer, disabling it once the score drops too low.
+    fn note_peer(&mut self, io: &mut dyn SyncIo, peer_id: PeerId, behaviour: Behaviour) {
+        let score = self.reputation.note(peer_id, behaviour);
+        trace!(target: "sync", "{}: {:?}, reputation {}", peer_id, behaviour, score);
+        io.set_peer_reputation(peer_id, score);
+        if score <= DISABLE_THRESHOLD {
+            debug!(target: "sync", "Disabling peer {} with reputation {}", peer_id, score);
+            self.reputation.note_disabled();
+            io.disable_peer(peer_id);
+            self.deactivate_peer(io, peer_id);
+        }
+    }
+
+    /// Score a response to the request sent at `asked_at`.
+    fn note_response(&mut self, io: &mut dyn SyncIo, peer_id: PeerId, asked_at: Instant) {
+        let behaviour = if asked_at.elapsed() > SLOW_RESPONSE {
+            Behaviour::SlowResponse
+        } else {
+            Behaviour::UsefulResponse
+        };
+        self.note_peer(io, peer_id, behaviour);
+    }
+
+    /// Reputation scores of the connected peers and the number of peers disabled because of
+    /// their score.
+    pub fn reputation_stats(&self) -> (Vec<i32>, usize) {
+        (self.reputation.scores(), self.reputation.disabled_count())
+    }
+
     fn maybe_start_snapshot_sync(&mut self, io: &mut dyn SyncIo) {
+        if let Some(checkpoint) = self.pending_checkpoint(io.chain()) {
+            // nothing but the checkpoint until the chain has reached it
+            if self.state == SyncState::WaitingPeers {
+                self.maybe_start_checkpoint_sync(io, checkpoint);
+            }
+            return;
+        }
         if !self.warp_sync.is_enabled() || io.snapshot_service().supported_versions().is_none() {
             trace!(target: "sync", "Skipping warp sync. Disabled or not supported.");
             return;
@@ -1074,6 +1228,11 @@ impl ChainSync {
                 trace!(target: "sync", "Starting unconfirmed snapshot sync {:?} with {:?}", hash, peers);
                 self.start_snapshot_sync(io, peers);
             }
+        } else if self.snap_sync
+            && self.state == SyncState::WaitingPeers
+            && self.maybe_start_snap_sync(io, timeout)
+        {
+            trace!(target: "sync", "No snapshots found, starting snap sync");
         } else if timeout && !self.warp_sync.is_warp_only() {
             trace!(target: "sync", "No snapshots found, starting full sync");
             self.state = SyncState::Idle;
@@ -1100,6 +1259,62 @@ impl ChainSync {
         }
     }
 
+    // Look for the pivot block of a `snap` sync at the head of the best `snap` peer.
+    fn maybe_start_snap_sync(&mut self, io: &mut dyn SyncIo, timeout: bool) -> bool {
+        let snap_peers: Vec<(PeerId, U256)> = self
+            .peers
+            .iter()
+            .filter(|&(id, p)| {
+                p.can_sync() && io.protocol_version(SNAP_PROTOCOL, *id) >= SNAP_PROTOCOL_VERSION_1.0
+            })
+            .filter_map(|(id, p)| p.difficulty.map(|difficulty| (*id, difficulty)))
+            .collect();
+        if snap_peers.is_empty() || (snap_peers.len() < SNAPSHOT_MIN_PEERS && !timeout) {
+            return false;
+        }
+
+        let best = snap_peers
+            .iter()
+            .filter(|&(id, _)| self.peers[id].asking == PeerAsking::Nothing)
+            .max_by_key(|&(_, difficulty)| *difficulty);
+        match best {
+            Some(&(peer_id, _)) => {
+                trace!(target: "sync", "Fetching snap pivot from {}", peer_id);
+                self.snap.clear();
+                SyncRequester::request_snap_pivot_headers(self, io, peer_id, SNAP_PIVOT_DISTANCE);
+                self.state = SyncState::SnapPivot;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the checkpoint if the chain has not reached it yet.
+    fn pending_checkpoint(&self, chain: &dyn BlockChainClient) -> Option<Checkpoint> {
+        let best_block = chain.chain_info().best_block_number;
+        self.checkpoint.filter(|c| c.number > best_block)
+    }
+
+    // Fetch the checkpoint block from the best `snap` peer to restore its state.
+    fn maybe_start_checkpoint_sync(&mut self, io: &mut dyn SyncIo, checkpoint: Checkpoint) {
+        let best = self
+            .peers
+            .iter()
+            .filter(|&(id, p)| {
+                p.can_sync()
+                    && p.asking == PeerAsking::Nothing
+                    && io.protocol_version(SNAP_PROTOCOL, *id) >= SNAP_PROTOCOL_VERSION_1.0
+            })
+            .filter_map(|(id, p)| p.difficulty.map(|difficulty| (*id, difficulty)))
+            .max_by_key(|&(_, difficulty)| difficulty);
+        if let Some((peer_id, _)) = best {
+            trace!(target: "sync", "Fetching checkpoint #{} from {}", checkpoint.number, peer_id);
+            self.snap.clear();
+            SyncRequester::request_checkpoint_header(self, io, peer_id, checkpoint.hash);
+            self.state = SyncState::SnapPivot;
+        }
+    }
+
     /// Restart sync disregarding the block queue status. May end up re-downloading up to QUEUE_SIZE blocks
     pub fn restart(&mut self, io: &mut dyn SyncIo) {
         self.update_targets(io.chain());
@@ -1109,34 +1324,95 @@ impl ChainSync {
     /// Update sync after the blockchain has been changed externally.
     pub fn update_targets(&mut self, chain: &dyn BlockChainClient) {
         // Do not assume that the block queue/chain still has our last_imported_block
-        let chain = chain.chain_info();
+        let chain_info = chain.chain_info();
         self.new_blocks = BlockDownloader::new(
             BlockSet::NewBlocks,
-            &chain.best_block_hash,
-            chain.best_block_number,
+            &chain_info.best_block_hash,
+            chain_info.best_block_number,
         );
         self.old_blocks = None;
         if self.download_old_blocks {
-            if let (Some(ancient_block_hash), Some(ancient_block_number)) =
-                (chain.ancient_block_hash, chain.ancient_block_number)
-            {
-                info!(target: "sync", "Downloading old blocks from {:?} (#{}) till {:?} (#{:?})", ancient_block_hash, ancient_block_number, chain.first_block_hash, chain.first_block_number);
+            if let (Some(ancient_block_hash), Some(ancient_block_number)) = (
+                chain_info.ancient_block_hash,
+                chain_info.ancient_block_number,
+            ) {
+                info!(target: "sync", "Downloading old block headers from {:?} (#{}) till {:?} (#{:?})", ancient_block_hash, ancient_block_number, chain_info.first_block_hash, chain_info.first_block_number);
                 let mut downloader = BlockDownloader::new(
                     BlockSet::OldBlocks,
                     &ancient_block_hash,
                     ancient_block_number,
                 );
-                if let Some(hash) = chain.first_block_hash {
+                downloader.set_headers_only();
+                if let Some(hash) = chain_info.first_block_hash {
                     trace!(target: "sync", "Downloader target for old blocks is set to {:?}", hash);
                     downloader.set_target(&hash);
                 } else {
                     trace!(target: "sync", "Downloader target could not be found");
                 }
                 self.old_blocks = Some(downloader);
+            } else {
+                self.old_blocks = self.ancient_bodies_downloader(chain);
             }
         }
     }
 
+    /// Create a downloader for the bodies and receipts of ancient blocks once all of their
+    /// headers are in the chain. Returns `None` if there are no bodies left to download.
+    fn ancient_bodies_downloader(&self, chain: &dyn BlockChainClient) -> Option<BlockDownloader> {
+        let chain_info = chain.chain_info();
+        let (first_hash, first_number) =
+            match (chain_info.first_block_hash, chain_info.first_block_number) {
+                (Some(hash), Some(number)) => (hash, number),
+                _ => return None,
+            };
+        let from = self.ancient_blocks_from.unwrap_or(0);
+        let has_body = |number| chain.block_body(BlockId::Number(number)).is_some();
+        if from >= first_number || has_body(first_number - 1) {
+            return None;
+        }
+
+        // Bodies are imported in order, so the ones present from `from` on are contiguous.
+        // Genesis always has its body, so `from` is positive if it has none.
+        let start = if has_body(from) {
+            let (mut low, mut high) = (from, first_number - 1);
+            while high - low > 1 {
+                let mid = low + (high - low) / 2;
+                if has_body(mid) {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+            low
+        } else {
+            from - 1
+        };
+        let start_hash = chain.block_hash(BlockId::Number(start))?;
+
+        info!(target: "sync", "Downloading old block bodies from #{} till {:?} (#{})", start + 1, first_hash, first_number);
+        let mut downloader = BlockDownloader::new(BlockSet::OldBlocks, &start_hash, start);
+        downloader.set_target(&first_hash);
+        Some(downloader)
+    }
+
+    /// Start downloading ancient bodies once the ancient headers have all been imported.
+    fn maybe_start_ancient_bodies(&mut self, io: &mut dyn SyncIo) {
+        if !self.download_old_blocks || self.old_blocks.as_ref().map_or(false, |d| !d.is_complete())
+        {
+            return;
+        }
+        match self.state {
+            SyncState::Idle | SyncState::Blocks | SyncState::NewBlocks => (),
+            _ => return,
+        }
+        if io.chain().chain_info().ancient_block_hash.is_some()
+            || io.chain().ancient_block_queue_fullness() > 0.0
+        {
+            return;
+        }
+        self.old_blocks = self.ancient_bodies_downloader(io.chain());
+    }
+
     /// Resume downloading
     pub fn continue_sync(&mut self, io: &mut dyn SyncIo) {
         if self.state == SyncState::Waiting {
@@ -1167,10 +1443,13 @@ impl ChainSync {
                     self.active_peers.len(), peers.len(), self.peers.len()
                 );
 
-                peers.shuffle(&mut random::new()); // TODO (#646): sort by rating
-                                                   // prefer peers with higher protocol version
-
-                peers.sort_by(|&(_, ref v1), &(_, ref v2)| v1.cmp(v2));
+                peers.shuffle(&mut random::new());
+                // prefer peers with higher protocol version, then the better reputed ones
+                let reputation = &self.reputation;
+                peers.sort_by(|&(p1, v1), &(p2, v2)| {
+                    v1.cmp(&v2)
+                        .then_with(|| reputation.score(p2).cmp(&reputation.score(p1)))
+                });
 
                 for (peer_id, _) in peers {
                     self.sync_peer(io, peer_id, false);
@@ -1223,6 +1502,11 @@ impl ChainSync {
                 return;
             }
         };
+        if self.state == SyncState::SnapState {
+            // the state is not tied to the total difficulty
+            SnapHandler::request_state(self, io, peer_id);
+            return;
+        }
         let chain_info = io.chain().chain_info();
         let syncing_difficulty = chain_info.pending_total_difficulty;
         let num_active_peers = self
@@ -1280,8 +1564,7 @@ impl ChainSync {
 						let mut to_send = Default::default();
 						if let Some(peer) = self.peers.get_mut(&peer_id) {
 							if peer.asking_pooled_transactions.is_empty() {
-								to_send = peer.unfetched_pooled_transactions.drain().take(MAX_TRANSACTIONS_TO_REQUEST).collect::<Vec<_>>();
-								peer.asking_pooled_transactions = to_send.clone();
+								to_send = peer.take_pooled_transactions_to_request();
 							}
 						}
 
@@ -1328,7 +1611,9 @@ impl ChainSync {
 				},
 				SyncState::SnapshotManifest | //already downloading from other peer
 					SyncState::Waiting |
-					SyncState::SnapshotWaiting => ()
+					SyncState::SnapshotWaiting |
+					SyncState::SnapPivot |
+					SyncState::SnapState => ()
 			}
         } else {
             trace!(target: "sync", "Skipping peer {}, force={}, td={:?}, our td={}, state={:?}", peer_id, force, peer_difficulty, syncing_difficulty, self.state);
@@ -1367,6 +1652,15 @@ impl ChainSync {
                 _ => (),
             }
         }
+        let asking_snap = self
+            .peers
+            .get_mut(&peer_id)
+            .and_then(|peer| peer.asking_snap.take());
+        if let Some((_, root, request)) = asking_snap {
+            if root == self.snap.state_root() {
+                self.snap.request_failed(request);
+            }
+        }
     }
 
     /// Checks if there are blocks fully downloaded that can be imported into the blockchain and does the import.
@@ -1403,6 +1697,17 @@ impl ChainSync {
                 }
             }
         };
+
+        let bad_block_sources = match block_set {
+            BlockSet::NewBlocks => self.new_blocks.take_bad_block_sources(),
+            BlockSet::OldBlocks => self
+                .old_blocks
+                .as_mut()
+                .map_or_else(Vec::new, |downloader| downloader.take_bad_block_sources()),
+        };
+        for peer_id in bad_block_sources {
+            self.note_peer(io, peer_id, Behaviour::BadBlock);
+        }
     }
 
     /// Mark all outstanding requests as expired
@@ -1482,6 +1787,8 @@ impl ChainSync {
                 PeerAsking::ForkHeader => elapsed > FORK_HEADER_TIMEOUT,
                 PeerAsking::SnapshotManifest => elapsed > SNAPSHOT_MANIFEST_TIMEOUT,
                 PeerAsking::SnapshotData => elapsed > SNAPSHOT_DATA_TIMEOUT,
+                PeerAsking::SnapPivot => elapsed > SNAP_PIVOT_TIMEOUT,
+                PeerAsking::SnapState => elapsed > SNAP_STATE_TIMEOUT,
             };
             if timeout {
                 debug!(target:"sync", "Timeout {}", peer_id);
@@ -1490,6 +1797,7 @@ impl ChainSync {
             }
         }
         for p in aborting {
+            self.note_peer(io, p, Behaviour::Timeout);
             SyncHandler::on_peer_aborting(self, io, p);
         }
 
@@ -1545,6 +1853,32 @@ impl ChainSync {
                     self.continue_sync(io);
                 }
             },
+            SyncState::SnapPivot
+                if !self
+                    .peers
+                    .values()
+                    .any(|p| p.asking == PeerAsking::SnapPivot) =>
+            {
+                trace!(target: "sync", "Snap pivot download was interrupted");
+                self.snap.clear();
+                self.state = SyncState::WaitingPeers;
+            }
+            SyncState::SnapState => match io.snapshot_service().restoration_status() {
+                // the restoration is only reported once the pivot has been checked.
+                RestorationStatus::Inactive if self.snap.fed() > 0 => {
+                    info!(target: "sync", "Snap state restoration is complete");
+                    // nothing left to abort
+                    self.state = SyncState::Idle;
+                    self.restart(io);
+                }
+                RestorationStatus::Failed => {
+                    trace!(target: "sync", "Snap state restoration aborted");
+                    self.snap.clear();
+                    self.state = SyncState::WaitingPeers;
+                    self.continue_sync(io);
+                }
+                _ => self.continue_sync(io),
+            },
             _ => (),
         }
     }
@@ -1592,6 +1926,7 @@ impl ChainSync {
     pub fn maintain_sync(&mut self, io: &mut dyn SyncIo) {
         self.maybe_start_snapshot_sync(io);
         self.check_resume(io);
+        self.maybe_start_ancient_bodies(io);
     }
 
     // t_nb 11.4 called when block is imported to chain - propagates the blocks and updates transactions sent to peers
@@ -1817,8 +2152,10 @@ pub mod tests {
                 snapshot_number: None,
                 snapshot_hash: None,
                 asking_snapshot_data: None,
+                asking_snap: None,
                 block_set: None,
                 client_version: ClientVersion::from(""),
+                node_id: None,
             },
         );
     }
@@ -1891,6 +2228,67 @@ pub mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn downloads_ancient_headers_before_bodies() {
+        let client = TestBlockChainClient::new();
+        client.add_blocks(100, EachBlockWith::Nothing);
+        let hashes: Vec<_> = (0..100)
+            .map(|n| client.block_hash(BlockId::Number(n)).unwrap())
+            .collect();
+        // blocks before #50 are missing after a warp sync
+        let missing: Vec<_> = (1..50)
+            .map(|n| {
+                let block = client.blocks.write().remove(&hashes[n]).unwrap();
+                (hashes[n], block)
+            })
+            .collect();
+        *client.ancient_block.write() = Some((hashes[0], 0));
+        *client.first_block.write() = Some((hashes[50], 50));
+
+        let (_, transaction_hashes_rx) = crossbeam_channel::unbounded();
+        let config = SyncConfig {
+            ancient_blocks_from: Some(20),
+            ..Default::default()
+        };
+        let mut sync = ChainSync::new(
+            config,
+            &client,
+            ForkFilterApi::new_dummy(&client),
+            transaction_hashes_rx,
+        );
+        let queue = RwLock::new(VecDeque::new());
+        let ss = TestSnapshotService::new();
+        let maybe_start_ancient_bodies = |sync: &mut ChainSync, client: &TestBlockChainClient| {
+            let mut io = TestIo::new(client, &ss, &queue, None);
+            sync.maybe_start_ancient_bodies(&mut io);
+            sync.old_blocks
+                .as_ref()
+                .map(|d| d.last_imported_block_number())
+        };
+
+        // headers are downloaded first, starting from the ancient block
+        sync.update_targets(&client);
+        assert_eq!(maybe_start_ancient_bodies(&mut sync, &client), Some(0));
+
+        // once they are all imported the bodies are downloaded, from #20 on only
+        sync.old_blocks = None;
+        *client.ancient_block.write() = None;
+        assert_eq!(maybe_start_ancient_bodies(&mut sync, &client), Some(19));
+
+        // an interrupted download resumes after the last imported body
+        client
+            .blocks
+            .write()
+            .extend(missing[19..34].iter().cloned());
+        sync.old_blocks = None;
+        assert_eq!(maybe_start_ancient_bodies(&mut sync, &client), Some(34));
+
+        // nothing is left to download once all the bodies from #20 on are imported
+        client.blocks.write().extend(missing[34..].iter().cloned());
+        sync.old_blocks = None;
+        assert_eq!(maybe_start_ancient_bodies(&mut sync, &client), None);
+    }
+
     #[test]
     fn should_add_transactions_to_queue() {
         fn sender(tx: &UnverifiedTransaction) -> Address {
@@ -2012,4 +2410,64 @@ pub mod tests {
             vec![6, 7].into_iter().map(H256::from_low_u64_be).collect()
         );
     }
+
+    #[test]
+    fn pooled_transactions_request_is_bounded_by_announced_size() {
+        let client = TestBlockChainClient::new();
+        let mut sync = dummy_sync_with_peer(H256::zero(), &client);
+        let peer = sync.peers.get_mut(&0).unwrap();
+        for i in 1..=3 {
+            let announcement = TransactionAnnouncement {
+                tx_type: 2,
+                size: MAX_POOLED_TRANSACTIONS_REQUEST_SIZE / 2,
+            };
+            peer.unfetched_pooled_transactions
+                .insert(H256::from_low_u64_be(i), Some(announcement));
+        }
+
+        let requested = peer.take_pooled_transactions_to_request();
+
+        assert_eq!(requested.len(), 2);
+        assert_eq!(peer.asking_pooled_transactions.len(), 2);
+        assert_eq!(peer.unfetched_pooled_transactions.len(), 1);
+        assert!(requested
+            .iter()
+            .all(|hash| !peer.unfetched_pooled_transactions.contains_key(hash)));
+    }
+
+    #[test]
+    fn pooled_transactions_request_includes_single_oversized_transaction() {
+        let client = TestBlockChainClient::new();
+        let mut sync = dummy_sync_with_peer(H256::zero(), &client);
+        let peer = sync.peers.get_mut(&0).unwrap();
+        let hash = H256::from_low_u64_be(1);
+        let announcement = TransactionAnnouncement {
+            tx_type: 0,
+            size: MAX_POOLED_TRANSACTIONS_REQUEST_SIZE * 2,
+        };
+        peer.unfetched_pooled_transactions
+            .insert(hash, Some(announcement));
+
+        assert_eq!(peer.take_pooled_transactions_to_request(), vec![hash]);
+        assert_eq!(
+            peer.asking_pooled_transactions,
+            vec![(hash, Some(announcement))]
+        );
+    }
+
+    #[test]
+    fn pooled_transactions_request_is_bounded_by_count_without_announcements() {
+        let client = TestBlockChainClient::new();
+        let mut sync = dummy_sync_with_peer(H256::zero(), &client);
+        let peer = sync.peers.get_mut(&0).unwrap();
+        for i in 0..(MAX_TRANSACTIONS_TO_REQUEST as u64 + 10) {
+            peer.unfetched_pooled_transactions
+                .insert(H256::from_low_u64_be(i), None);
+        }
+
+        let requested = peer.take_pooled_transactions_to_request();
+
+        assert_eq!(requested.len(), MAX_TRANSACTIONS_TO_REQUEST);
+        assert_eq!(peer.unfetched_pooled_transactions.len(), 10);
+    }
 }
@@ -23,15 +23,20 @@ use network::{client_version::ClientCapabilities, PeerId};
 use rand::RngCore;
 use rlp::RlpStream;
 use sync_io::SyncIo;
-use types::{blockchain_info::BlockChainInfo, transaction::SignedTransaction, BlockNumber};
+use types::{
+    blockchain_info::BlockChainInfo,
+    transaction::{SignedTransaction, TypedTxId},
+    BlockNumber,
+};
 
 use super::sync_packet::SyncPacket::{self, *};
 
 use super::{
-    random, ChainSync, ETH_PROTOCOL_VERSION_65, MAX_PEERS_PROPAGATION, MAX_PEER_LAG_PROPAGATION,
-    MAX_TRANSACTION_PACKET_SIZE, MIN_PEERS_PROPAGATION,
+    random, ChainSync, ETH_PROTOCOL_VERSION_65, ETH_PROTOCOL_VERSION_68, MAX_PEERS_PROPAGATION,
+    MAX_PEER_LAG_PROPAGATION, MAX_TRANSACTION_PACKET_SIZE, MIN_PEERS_PROPAGATION,
 };
-use ethcore_miner::pool::VerifiedTransaction;
+use ethcore_miner::pool::{ScoredTransaction, VerifiedTransaction};
+use propagation::{Propagation, PropagationTarget};
 use std::sync::Arc;
 
 const NEW_POOLED_HASHES_LIMIT: usize = 4096;
@@ -128,23 +133,15 @@ impl SyncPropagator {
         sync: &mut ChainSync,
         io: &mut dyn SyncIo,
         peers: Vec<PeerId>,
-        transactions: Vec<&SignedTransaction>,
+        fanout: HashSet<PeerId>,
+        transactions: Vec<(&SignedTransaction, bool)>,
         are_new: bool,
         mut should_continue: F,
     ) -> HashSet<PeerId> {
         let all_transactions_hashes = transactions
             .iter()
-            .map(|tx| tx.hash())
+            .map(|(tx, _)| tx.hash())
             .collect::<H256FastSet>();
-        let all_transactions_rlp = {
-            let mut packet = RlpStream::new_list(transactions.len());
-            for tx in &transactions {
-                tx.rlp_append(&mut packet);
-            }
-            packet.out()
-        };
-        let all_transactions_hashes_rlp =
-            rlp::encode_list(&all_transactions_hashes.iter().copied().collect::<Vec<_>>());
 
         let block_number = io.chain().chain_info().best_block_number;
 
@@ -175,6 +172,7 @@ impl SyncPropagator {
             trace!(target: "sync", "{:02} <- {} ({} entries; {} bytes)", peer_id, if is_hashes { "NewPooledTransactionHashes" } else { "Transactions" }, sent, size);
         };
 
+        let policy = sync.transaction_propagation.clone();
         let mut sent_to_peers = HashSet::new();
         let mut max_sent = 0;
 
@@ -190,68 +188,62 @@ impl SyncPropagator {
 				.expect("peer_id is form peers; peers is result of select_peers_for_transactions; select_peers_for_transactions selects peers from self.peers; qed");
 
             let is_hashes = peer_info.protocol_version >= ETH_PROTOCOL_VERSION_65.0;
+            let is_announcements = peer_info.protocol_version >= ETH_PROTOCOL_VERSION_68.0;
+            let target = PropagationTarget {
+                node_id: peer_info.node_id,
+                supports_announcements: is_hashes,
+                in_fanout: fanout.contains(&peer_id),
+            };
 
-            // Send all transactions, if the peer doesn't know about anything
-            if peer_info.last_sent_transactions.is_empty() {
-                // update stats
-                for hash in &all_transactions_hashes {
-                    let id = io.peer_session_info(peer_id).and_then(|info| info.id);
-                    stats.propagated(hash, are_new, id, block_number);
+            // Split the transactions the peer doesn't know about by the way they are sent
+            let mut full = Vec::new();
+            let mut announced = Vec::new();
+            for &(tx, is_local) in &transactions {
+                if peer_info.last_sent_transactions.contains(&tx.hash()) {
+                    continue;
+                }
+                // blob transactions are never broadcast in full (EIP-4844)
+                let is_blob = tx.tx_type() == TypedTxId::BlobTransaction;
+                match policy.propagation(&target, is_local) {
+                    Propagation::Full if !is_blob => full.push(tx),
+                    Propagation::Full | Propagation::Announce if is_hashes => announced.push(tx),
+                    Propagation::Full | Propagation::Announce | Propagation::Skip => (),
                 }
-                peer_info.last_sent_transactions = all_transactions_hashes.clone();
-
-                let rlp = {
-                    if is_hashes {
-                        all_transactions_hashes_rlp.clone()
-                    } else {
-                        all_transactions_rlp.clone()
-                    }
-                };
-                send_packet(io, peer_id, is_hashes, all_transactions_hashes.len(), rlp);
-                sent_to_peers.insert(peer_id);
-                max_sent = cmp::max(max_sent, all_transactions_hashes.len());
-                continue;
             }
-
-            // Get hashes of all transactions to send to this peer
-            let to_send = all_transactions_hashes
-                .difference(&peer_info.last_sent_transactions)
-                .cloned()
-                .collect::<HashSet<_>>();
-            if to_send.is_empty() {
+            if full.is_empty() && announced.is_empty() {
                 continue;
             }
 
-            // Construct RLP
-            let (packet, to_send) = {
-                let mut to_send_new = HashSet::new();
+            let mut to_send = HashSet::new();
+            if !full.is_empty() {
                 let mut packet = RlpStream::new();
                 packet.begin_unbounded_list();
-                for tx in &transactions {
-                    let hash = tx.hash();
-                    if to_send.contains(&hash) {
-                        if is_hashes {
-                            if to_send_new.len() >= NEW_POOLED_HASHES_LIMIT {
-                                debug!(target: "sync", "NewPooledTransactionHashes length limit reached. Sending incomplete list of {}/{} transactions.", to_send_new.len(), to_send.len());
-                                break;
-                            }
-                            packet.append(&hash);
-                            to_send_new.insert(hash);
-                        } else {
-                            tx.rlp_append(&mut packet);
-                            to_send_new.insert(hash);
-                            // this is not hard limit and we are okay with it. Max default tx size is 300k.
-                            if packet.as_raw().len() >= MAX_TRANSACTION_PACKET_SIZE {
-                                // Maximal packet size reached just proceed with sending
-                                debug!(target: "sync", "Transaction packet size limit reached. Sending incomplete set of {}/{} transactions.", to_send_new.len(), to_send.len());
-                                break;
-                            }
-                        }
+                for tx in &full {
+                    tx.rlp_append(&mut packet);
+                    to_send.insert(tx.hash());
+                    // this is not hard limit and we are okay with it. Max default tx size is 300k.
+                    if packet.as_raw().len() >= MAX_TRANSACTION_PACKET_SIZE {
+                        // Maximal packet size reached just proceed with sending
+                        debug!(target: "sync", "Transaction packet size limit reached. Sending incomplete set of {}/{} transactions.", to_send.len(), full.len());
+                        break;
                     }
                 }
                 packet.finalize_unbounded_list();
-                (packet, to_send_new)
-            };
+                send_packet(io, peer_id, false, to_send.len(), packet.out());
+            }
+            if !announced.is_empty() {
+                if announced.len() > NEW_POOLED_HASHES_LIMIT {
+                    debug!(target: "sync", "NewPooledTransactionHashes length limit reached. Sending incomplete list of {}/{} transactions.", NEW_POOLED_HASHES_LIMIT, announced.len());
+                    announced.truncate(NEW_POOLED_HASHES_LIMIT);
+                }
+                let packet = if is_announcements {
+                    SyncPropagator::transaction_announcements_rlp(&announced)
+                } else {
+                    rlp::encode_list(&announced.iter().map(|tx| tx.hash()).collect::<Vec<_>>())
+                };
+                to_send.extend(announced.iter().map(|tx| tx.hash()));
+                send_packet(io, peer_id, true, announced.len(), packet);
+            }
 
             // Update stats.
             let id = io.peer_session_info(peer_id).and_then(|info| info.id);
@@ -264,7 +256,6 @@ impl SyncPropagator {
                 .chain(&to_send)
                 .cloned()
                 .collect();
-            send_packet(io, peer_id, is_hashes, to_send.len(), packet.out());
             sent_to_peers.insert(peer_id);
             max_sent = cmp::max(max_sent, to_send.len());
         }
@@ -366,6 +357,25 @@ impl SyncPropagator {
         }
     }
 
+    /// Builds an eth/68 `NewPooledTransactionHashes` payload: `[types: B, [size: P, ...], [hash: B_32, ...]]`.
+    fn transaction_announcements_rlp(transactions: &[&SignedTransaction]) -> Bytes {
+        let types = transactions
+            .iter()
+            .map(|tx| tx.tx_type() as u8)
+            .collect::<Vec<u8>>();
+        let mut packet = RlpStream::new_list(3);
+        packet.append(&types);
+        packet.begin_list(transactions.len());
+        for tx in transactions {
+            packet.append(&(tx.encode().len() as u64));
+        }
+        packet.begin_list(transactions.len());
+        for tx in transactions {
+            packet.append(&tx.hash());
+        }
+        packet.out()
+    }
+
     /// propagates new transactions to all peers
     fn propagate_transactions<'a, F, G>(
         sync: &mut ChainSync,
@@ -394,17 +404,19 @@ impl SyncPropagator {
 
         let (transactions, service_transactions): (Vec<_>, Vec<_>) = transactions
             .iter()
-            .map(|tx| tx.signed())
-            .partition(|tx| !tx.tx().gas_price.is_zero());
+            .map(|tx| (tx.signed(), tx.priority().is_local()))
+            .partition(|(tx, _)| !tx.tx().gas_price.is_zero());
 
         // usual transactions could be propagated to all peers
         let mut affected_peers = HashSet::new();
         if !transactions.is_empty() {
-            let peers = SyncPropagator::select_peers_for_transactions(sync, |_| true, are_new);
+            let fanout = SyncPropagator::select_peers_for_transactions(sync, |_| true, are_new);
+            let peers = sync.peers.keys().cloned().collect();
             affected_peers = SyncPropagator::propagate_transactions_to_peers(
                 sync,
                 io,
                 peers,
+                fanout.into_iter().collect(),
                 transactions,
                 are_new,
                 &mut should_continue,
@@ -414,16 +426,25 @@ impl SyncPropagator {
         // most of times service_transactions will be empty
         // => there's no need to merge packets
         if !service_transactions.is_empty() {
-            let service_transactions_peers = SyncPropagator::select_peers_for_transactions(
+            let accepts_service_transaction =
+                |peer_id: &PeerId| io.peer_version(*peer_id).accepts_service_transaction();
+            let service_transactions_fanout = SyncPropagator::select_peers_for_transactions(
                 sync,
-                |peer_id| io.peer_version(*peer_id).accepts_service_transaction(),
+                accepts_service_transaction,
                 are_new,
             );
+            let service_transactions_peers = sync
+                .peers
+                .keys()
+                .cloned()
+                .filter(accepts_service_transaction)
+                .collect();
             let service_transactions_affected_peers =
                 SyncPropagator::propagate_transactions_to_peers(
                     sync,
                     io,
                     service_transactions_peers,
+                    service_transactions_fanout.into_iter().collect(),
                     service_transactions,
                     are_new,
                     &mut should_continue,
@@ -449,6 +470,8 @@ mod tests {
         *,
     };
     use ethcore::ethereum::new_london_test;
+    use network::NodeId;
+    use propagation::PropagationMode;
 
     #[test]
     fn sends_new_hashes_to_lagging_peer() {
@@ -548,8 +571,10 @@ mod tests {
                 snapshot_number: None,
                 snapshot_hash: None,
                 asking_snapshot_data: None,
+                asking_snap: None,
                 block_set: None,
                 client_version: ClientVersion::from(""),
+                node_id: None,
             },
         );
         let ss = TestSnapshotService::new();
@@ -921,6 +946,91 @@ mod tests {
         assert!(sent_transactions.iter().any(|tx| tx.hash() == tx2_hash));
     }
 
+    #[test]
+    fn should_send_local_transactions_only_to_private_relays() {
+        let mut client = TestBlockChainClient::new();
+        client.add_blocks(100, EachBlockWith::Uncle);
+        let local_hash = client.insert_local_transaction_to_queue();
+        let remote_hash = client.insert_transaction_to_queue();
+        let block_hash = client.block_hash_delta_minus(1);
+        let mut sync = dummy_sync(&client);
+        insert_dummy_peer(&mut sync, 1, block_hash);
+        insert_dummy_peer(&mut sync, 2, block_hash);
+        let relay = NodeId::from_low_u64_be(2);
+        sync.peers.get_mut(&2).unwrap().node_id = Some(relay);
+        sync.set_transaction_propagation(
+            PropagationMode::PrivateRelay(vec![relay].into_iter().collect()).policy(),
+        );
+        let queue = RwLock::new(VecDeque::new());
+        let ss = TestSnapshotService::new();
+        let mut io = TestIo::new(&mut client, &ss, &queue, None);
+
+        SyncPropagator::propagate_ready_transactions(&mut sync, &mut io, || true);
+
+        let sent_to = |peer: PeerId| -> Vec<H256> {
+            io.packets
+                .iter()
+                .filter(|p| p.packet_id == 0x02 && p.recipient == peer)
+                .flat_map(|p| {
+                    Rlp::new(&*p.data)
+                        .iter()
+                        .map(|r| TypedTransaction::decode_rlp(&r).unwrap().hash())
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        };
+        assert_eq!(sent_to(1), vec![remote_hash]);
+        let relayed = sent_to(2);
+        assert_eq!(relayed.len(), 2);
+        assert!(relayed.contains(&local_hash));
+        assert!(relayed.contains(&remote_hash));
+    }
+
+    #[test]
+    fn should_not_propagate_local_transactions_with_no_local_policy() {
+        let mut client = TestBlockChainClient::new();
+        client.add_blocks(100, EachBlockWith::Uncle);
+        client.insert_local_transaction_to_queue();
+        let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(1), &client);
+        sync.set_transaction_propagation(PropagationMode::NoLocal.policy());
+        let queue = RwLock::new(VecDeque::new());
+        let ss = TestSnapshotService::new();
+        let mut io = TestIo::new(&mut client, &ss, &queue, None);
+
+        let peer_count = SyncPropagator::propagate_ready_transactions(&mut sync, &mut io, || true);
+
+        assert_eq!(0, peer_count);
+        assert!(io.packets.is_empty());
+    }
+
+    #[test]
+    fn should_only_announce_transactions_with_announce_only_policy() {
+        let mut client = TestBlockChainClient::new();
+        client.add_blocks(100, EachBlockWith::Uncle);
+        let tx_hash = client.insert_transaction_to_queue();
+        let block_hash = client.block_hash_delta_minus(1);
+        let mut sync = dummy_sync(&client);
+        insert_dummy_peer(&mut sync, 1, block_hash);
+        insert_dummy_peer(&mut sync, 2, block_hash);
+        sync.peers.get_mut(&2).unwrap().protocol_version = ETH_PROTOCOL_VERSION_65.0;
+        sync.set_transaction_propagation(PropagationMode::AnnounceOnly.policy());
+        let queue = RwLock::new(VecDeque::new());
+        let ss = TestSnapshotService::new();
+        let mut io = TestIo::new(&mut client, &ss, &queue, None);
+
+        SyncPropagator::propagate_ready_transactions(&mut sync, &mut io, || true);
+
+        // peer#1 does not support announcements and gets nothing
+        assert_eq!(io.packets.len(), 1);
+        assert_eq!(io.packets[0].recipient, 2);
+        // NEW_POOLED_TRANSACTION_HASHES_PACKET
+        assert_eq!(io.packets[0].packet_id, 0x08);
+        assert_eq!(
+            Rlp::new(&io.packets[0].data).as_list::<H256>().unwrap(),
+            vec![tx_hash]
+        );
+    }
+
     #[test]
     fn should_propagate_transactions_with_max_fee_per_gas_lower_than_base_fee() {
         let (new_transaction_hashes_tx, new_transaction_hashes_rx) = crossbeam_channel::unbounded();
@@ -945,4 +1055,62 @@ mod tests {
         assert_eq!(1, io.packets.len());
         assert_eq!(1, peer_count);
     }
+
+    #[test]
+    fn should_only_announce_blob_transactions() {
+        use crypto::publickey::{Generator, Random};
+        use types::transaction::{
+            AccessListTx, Action, BlobTransactionTx, EIP1559TransactionTx, Transaction,
+        };
+
+        let mut client = TestBlockChainClient::new();
+        client.add_blocks(100, EachBlockWith::Uncle);
+        let block_hash = client.block_hash_delta_minus(1);
+        let mut sync = dummy_sync(&client);
+        insert_dummy_peer(&mut sync, 1, block_hash);
+        insert_dummy_peer(&mut sync, 2, block_hash);
+        sync.peers.get_mut(&1).unwrap().protocol_version = ETH_PROTOCOL_VERSION_64.0;
+        sync.peers.get_mut(&2).unwrap().protocol_version = ETH_PROTOCOL_VERSION_66.0;
+        let queue = RwLock::new(VecDeque::new());
+        let ss = TestSnapshotService::new();
+        let mut io = TestIo::new(&mut client, &ss, &queue, None);
+
+        let blob = TypedTransaction::BlobTransaction(BlobTransactionTx {
+            transaction: EIP1559TransactionTx {
+                transaction: AccessListTx {
+                    transaction: Transaction {
+                        action: Action::Call(Default::default()),
+                        value: 0.into(),
+                        data: vec![],
+                        gas: 21_000.into(),
+                        gas_price: 1.into(),
+                        nonce: 0.into(),
+                    },
+                    access_list: vec![],
+                },
+                max_priority_fee_per_gas: 1.into(),
+            },
+            max_fee_per_blob_gas: 1.into(),
+            blob_versioned_hashes: vec![H256::from_low_u64_be(1)],
+        })
+        .sign(Random.generate().secret(), None);
+        let blob_hash = blob.hash();
+        let transactions = move |_: &dyn SyncIo| {
+            vec![Arc::new(
+                VerifiedTransaction::from_pending_block_transaction(blob.clone()),
+            )]
+        };
+
+        SyncPropagator::propagate_transactions(&mut sync, &mut io, transactions, false, || true);
+
+        // peer#1 does not support announcements and gets nothing
+        assert_eq!(io.packets.len(), 1);
+        assert_eq!(io.packets[0].recipient, 2);
+        // NEW_POOLED_TRANSACTION_HASHES_PACKET
+        assert_eq!(io.packets[0].packet_id, 0x08);
+        assert_eq!(
+            Rlp::new(&io.packets[0].data).as_list::<H256>().unwrap(),
+            vec![blob_hash]
+        );
+    }
 }
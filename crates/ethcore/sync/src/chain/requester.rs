@@ -28,7 +28,7 @@ use super::{
     sync_packet::{SyncPacket::*, *},
 };
 
-use super::{BlockSet, ChainSync, PeerAsking};
+use super::{BlockSet, ChainSync, PeerAsking, SNAP_SERVE_WINDOW};
 
 /// The Chain Sync Requester: requesting data to other peers
 pub struct SyncRequester;
@@ -154,6 +154,105 @@ impl SyncRequester {
         );
     }
 
+    /// Request the `snap` pivot block header from a peer, `distance` blocks below
+    /// its head, along with the headers up to its head.
+    pub fn request_snap_pivot_headers(
+        sync: &mut ChainSync,
+        io: &mut dyn SyncIo,
+        peer_id: PeerId,
+        distance: BlockNumber,
+    ) {
+        let latest_hash = match sync.peers.get_mut(&peer_id) {
+            Some(peer) => {
+                peer.asking_hash = Some(peer.latest_hash);
+                peer.latest_hash
+            }
+            None => return,
+        };
+        trace!(target: "sync", "{} <- GetBlockHeaders: snap pivot {} below {}", peer_id, distance, latest_hash);
+        let mut rlp = RlpStream::new_list(4);
+        rlp.append(&latest_hash);
+        rlp.append(&(distance + 1));
+        rlp.append(&0u32);
+        rlp.append(&1u32);
+        SyncRequester::send_request(
+            sync,
+            io,
+            peer_id,
+            PeerAsking::SnapPivot,
+            GetBlockHeadersPacket,
+            rlp.out(),
+        );
+    }
+
+    /// Request the header of the checkpoint block from a peer, to be used as the `snap` pivot,
+    /// along with the header a serving window later if the peer already has it.
+    pub fn request_checkpoint_header(
+        sync: &mut ChainSync,
+        io: &mut dyn SyncIo,
+        peer_id: PeerId,
+        hash: H256,
+    ) {
+        match sync.peers.get_mut(&peer_id) {
+            Some(peer) => peer.asking_hash = Some(hash),
+            None => return,
+        }
+        trace!(target: "sync", "{} <- GetBlockHeaders: checkpoint {}", peer_id, hash);
+        let mut rlp = RlpStream::new_list(4);
+        rlp.append(&hash);
+        rlp.append(&2u32);
+        rlp.append(&(SNAP_SERVE_WINDOW - 1));
+        rlp.append(&0u32);
+        SyncRequester::send_request(
+            sync,
+            io,
+            peer_id,
+            PeerAsking::SnapPivot,
+            GetBlockHeadersPacket,
+            rlp.out(),
+        );
+    }
+
+    /// Request the body of the `snap` pivot block from a peer.
+    pub fn request_snap_pivot_body(
+        sync: &mut ChainSync,
+        io: &mut dyn SyncIo,
+        peer_id: PeerId,
+        hash: H256,
+    ) {
+        trace!(target: "sync", "{} <- GetBlockBodies: snap pivot {}", peer_id, hash);
+        let mut rlp = RlpStream::new_list(1);
+        rlp.append(&hash);
+        SyncRequester::send_request(
+            sync,
+            io,
+            peer_id,
+            PeerAsking::SnapPivot,
+            GetBlockBodiesPacket,
+            rlp.out(),
+        );
+    }
+
+    /// Request the receipts of the `snap` pivot block from a peer.
+    pub fn request_snap_pivot_receipts(
+        sync: &mut ChainSync,
+        io: &mut dyn SyncIo,
+        peer_id: PeerId,
+        hash: H256,
+    ) {
+        trace!(target: "sync", "{} <- GetReceipts: snap pivot {}", peer_id, hash);
+        let mut rlp = RlpStream::new_list(1);
+        rlp.append(&hash);
+        SyncRequester::send_request(
+            sync,
+            io,
+            peer_id,
+            PeerAsking::SnapPivot,
+            GetReceiptsPacket,
+            rlp.out(),
+        );
+    }
+
     /// Request headers from a peer by block hash
     fn request_headers_by_hash(
         sync: &mut ChainSync,
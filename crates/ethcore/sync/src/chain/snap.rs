@@ -0,0 +1,824 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `snap/1` support: serving state ranges to peers, fetching the pivot block
+//! over `eth` and driving the state download.
+
+use block_sync::BlockDownloaderImportError as DownloaderImportError;
+use blocks::{SyncBody, SyncHeader};
+use bytes::Bytes;
+use enum_primitive::FromPrimitive;
+use ethcore::snapshot::{snap::SnapPivot, RestorationStatus};
+use ethereum_types::{H256, U256};
+use network::PeerId;
+use parking_lot::RwLock;
+use rand;
+use reputation::Behaviour;
+use rlp::{DecoderError, Rlp, RlpStream};
+use snap_sync::SnapRequest;
+use std::{cmp, time::Instant};
+use sync_io::SyncIo;
+use triehash_ethereum::ordered_trie_root;
+use types::{encoded, receipt::TypedReceipt};
+
+use api::{Checkpoint, SNAP_PROTOCOL};
+
+use super::{
+    request_id::RequestId,
+    supplier::PAYLOAD_SOFT_LIMIT,
+    sync_packet::{PacketInfo, SnapPacket, SnapPacket::*},
+    ChainSync, PeerAsking, SyncRequester, SyncState, SNAPSHOT_RESTORE_THRESHOLD,
+    SNAP_PIVOT_DISTANCE, SNAP_PROTOCOL_VERSION_1, SNAP_RESPONSE_BYTES, SNAP_SERVE_WINDOW,
+};
+
+/// Max number of accounts whose storage is served in one response.
+const MAX_STORAGE_ACCOUNTS_TO_SEND: usize = 1024;
+/// Max number of contract codes served in one response.
+const MAX_CODES_TO_SEND: usize = 1024;
+/// Max number of trie node path sets served in one response.
+const MAX_TRIE_NODE_PATHS_TO_SEND: usize = 1024;
+
+/// The `snap/1` handler: answers state requests and handles state responses
+pub struct SnapHandler;
+
+impl SnapHandler {
+    /// Dispatch incoming `snap` requests and responses
+    pub fn dispatch_packet(
+        sync: &RwLock<ChainSync>,
+        io: &mut dyn SyncIo,
+        peer: PeerId,
+        packet_id: u8,
+        data: &[u8],
+    ) {
+        let id = match SnapPacket::from_u8(packet_id) {
+            Some(id) => id,
+            None => {
+                debug!(target: "sync", "{}: Unknown snap packet {}", peer, packet_id);
+                return;
+            }
+        };
+        let rlp = Rlp::new(data);
+        let response = match id {
+            GetAccountRangePacket => SnapHandler::return_account_range(io, &rlp, peer),
+            GetStorageRangesPacket => SnapHandler::return_storage_ranges(io, &rlp, peer),
+            GetByteCodesPacket => SnapHandler::return_byte_codes(io, &rlp, peer),
+            GetTrieNodesPacket => SnapHandler::return_trie_nodes(io, &rlp, peer),
+            AccountRangePacket | StorageRangesPacket | ByteCodesPacket | TrieNodesPacket => {
+                if !sync.read().peers.contains_key(&peer) {
+                    debug!(target: "sync", "Unexpected snap packet {} from unregistered peer: {}:{}", packet_id, peer, io.peer_version(peer));
+                    return;
+                }
+                let mut sync = sync.write();
+                let asked_at = sync
+                    .peers
+                    .get(&peer)
+                    .filter(|p| p.asking == PeerAsking::SnapState)
+                    .map(|p| p.ask_time);
+                let result = match id {
+                    AccountRangePacket => SnapHandler::on_account_range(&mut sync, io, peer, &rlp),
+                    StorageRangesPacket => {
+                        SnapHandler::on_storage_ranges(&mut sync, io, peer, &rlp)
+                    }
+                    ByteCodesPacket => SnapHandler::on_byte_codes(&mut sync, io, peer, &rlp),
+                    _ => SnapHandler::on_trie_nodes(&mut sync, io, peer, &rlp),
+                };
+                match result {
+                    Err(DownloaderImportError::Invalid) => {
+                        debug!(target:"sync", "{} -> Invalid snap packet {}", peer, packet_id);
+                        sync.note_peer(io, peer, Behaviour::InvalidPacket);
+                    }
+                    Err(DownloaderImportError::Useless) => {
+                        sync.deactivate_peer(io, peer);
+                        sync.note_peer(io, peer, Behaviour::UselessResponse);
+                    }
+                    Ok(()) => {
+                        if let Some(asked_at) = asked_at {
+                            sync.note_response(io, peer, asked_at);
+                        }
+                        // give a task to the same peer first
+                        sync.sync_peer(io, peer, false);
+                    }
+                }
+                return;
+            }
+        };
+
+        match response {
+            Ok(Some((packet_id, rlp_stream))) => {
+                io.respond(packet_id.id(), rlp_stream.out()).unwrap_or_else(
+                    |e| debug!(target: "sync", "Error sending snap response: {:?}", e),
+                );
+            }
+            Ok(None) => {}
+            Err(e) => {
+                debug!(target:"sync", "{} -> Malformed snap packet {} : {}", peer, packet_id, e)
+            }
+        }
+    }
+
+    fn response_bytes(requested: u64) -> usize {
+        cmp::min(requested, PAYLOAD_SOFT_LIMIT as u64) as usize
+    }
+
+    fn return_account_range(
+        io: &dyn SyncIo,
+        r: &Rlp,
+        peer_id: PeerId,
+    ) -> Result<Option<(SnapPacket, RlpStream)>, DecoderError> {
+        let request_id: RequestId = r.val_at(0)?;
+        let root: H256 = r.val_at(1)?;
+        let origin: H256 = r.val_at(2)?;
+        let limit: H256 = r.val_at(3)?;
+        let max_bytes = SnapHandler::response_bytes(r.val_at(4)?);
+        trace!(target: "sync", "{} -> GetAccountRange: {} from {} to {}", peer_id, root, origin, limit);
+
+        let range = io
+            .chain()
+            .snap_account_range(&root, &origin, &limit, max_bytes)
+            .unwrap_or_default();
+        let mut rlp = RlpStream::new_list(3);
+        rlp.append(&request_id);
+        rlp.begin_list(range.accounts.len());
+        for (hash, account) in &range.accounts {
+            rlp.begin_list(2);
+            rlp.append(hash);
+            rlp.append_raw(account, 1);
+        }
+        rlp.append_list::<Bytes, Bytes>(&range.proof);
+        trace!(target: "sync", "{} -> GetAccountRange: returned {} accounts", peer_id, range.accounts.len());
+        Ok(Some((AccountRangePacket, rlp)))
+    }
+
+    fn return_storage_ranges(
+        io: &dyn SyncIo,
+        r: &Rlp,
+        peer_id: PeerId,
+    ) -> Result<Option<(SnapPacket, RlpStream)>, DecoderError> {
+        let request_id: RequestId = r.val_at(0)?;
+        let root: H256 = r.val_at(1)?;
+        let mut accounts: Vec<H256> = r.list_at(2)?;
+        accounts.truncate(MAX_STORAGE_ACCOUNTS_TO_SEND);
+        let origin = SnapHandler::hash_or(&r.at(3)?, H256::zero())?;
+        let limit = SnapHandler::hash_or(&r.at(4)?, H256::repeat_byte(0xff))?;
+        let max_bytes = SnapHandler::response_bytes(r.val_at(5)?);
+        trace!(target: "sync", "{} -> GetStorageRanges: {} accounts of {}", peer_id, accounts.len(), root);
+
+        let ranges = io
+            .chain()
+            .snap_storage_ranges(&root, &accounts, &origin, &limit, max_bytes)
+            .unwrap_or_default();
+        let mut rlp = RlpStream::new_list(3);
+        rlp.append(&request_id);
+        rlp.begin_list(ranges.slots.len());
+        for slots in &ranges.slots {
+            rlp.begin_list(slots.len());
+            for (hash, value) in slots {
+                rlp.begin_list(2);
+                rlp.append(hash);
+                rlp.append(value);
+            }
+        }
+        rlp.append_list::<Bytes, Bytes>(&ranges.proof);
+        trace!(target: "sync", "{} -> GetStorageRanges: returned {} accounts", peer_id, ranges.slots.len());
+        Ok(Some((StorageRangesPacket, rlp)))
+    }
+
+    // Origin and limit of storage ranges may be left empty.
+    fn hash_or(r: &Rlp, default: H256) -> Result<H256, DecoderError> {
+        let data = r.data()?;
+        match data.len() {
+            0 => Ok(default),
+            32 => Ok(H256::from_slice(data)),
+            _ => Err(DecoderError::RlpInvalidLength),
+        }
+    }
+
+    fn return_byte_codes(
+        io: &dyn SyncIo,
+        r: &Rlp,
+        peer_id: PeerId,
+    ) -> Result<Option<(SnapPacket, RlpStream)>, DecoderError> {
+        let request_id: RequestId = r.val_at(0)?;
+        let mut hashes: Vec<H256> = r.list_at(1)?;
+        hashes.truncate(MAX_CODES_TO_SEND);
+        let max_bytes = SnapHandler::response_bytes(r.val_at(2)?);
+        trace!(target: "sync", "{} -> GetByteCodes: {} entries", peer_id, hashes.len());
+
+        let codes = io.chain().snap_bytecodes(&hashes, max_bytes);
+        let mut rlp = RlpStream::new_list(2);
+        rlp.append(&request_id);
+        rlp.append_list::<Bytes, Bytes>(&codes);
+        trace!(target: "sync", "{} -> GetByteCodes: returned {} entries", peer_id, codes.len());
+        Ok(Some((ByteCodesPacket, rlp)))
+    }
+
+    fn return_trie_nodes(
+        io: &dyn SyncIo,
+        r: &Rlp,
+        peer_id: PeerId,
+    ) -> Result<Option<(SnapPacket, RlpStream)>, DecoderError> {
+        let request_id: RequestId = r.val_at(0)?;
+        let root: H256 = r.val_at(1)?;
+        let path_sets = r
+            .at(2)?
+            .iter()
+            .take(MAX_TRIE_NODE_PATHS_TO_SEND)
+            .map(|set| set.as_list::<Bytes>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let max_bytes = SnapHandler::response_bytes(r.val_at(3)?);
+        trace!(target: "sync", "{} -> GetTrieNodes: {} path sets of {}", peer_id, path_sets.len(), root);
+
+        let nodes = io
+            .chain()
+            .snap_trie_nodes(&root, &path_sets, max_bytes)
+            .unwrap_or_default();
+        let mut rlp = RlpStream::new_list(2);
+        rlp.append(&request_id);
+        rlp.append_list::<Bytes, Bytes>(&nodes);
+        trace!(target: "sync", "{} -> GetTrieNodes: returned {} nodes", peer_id, nodes.len());
+        Ok(Some((TrieNodesPacket, rlp)))
+    }
+
+    /// Ask a peer for the next piece of the state being downloaded, if any.
+    pub fn request_state(sync: &mut ChainSync, io: &mut dyn SyncIo, peer_id: PeerId) {
+        if io.protocol_version(SNAP_PROTOCOL, peer_id) < SNAP_PROTOCOL_VERSION_1.0 {
+            return;
+        }
+        let processed = match io.snapshot_service().restoration_status() {
+            RestorationStatus::Ongoing {
+                state_chunks_done, ..
+            } => state_chunks_done as usize,
+            _ => return,
+        };
+        let request = match sync.snap.next_request(io.snapshot_service(), processed) {
+            Some(request) => request,
+            None => return,
+        };
+
+        let root = sync.snap.state_root();
+        let request_id: RequestId = rand::random();
+        let bytes = SNAP_RESPONSE_BYTES as u64;
+        let (packet_id, packet) = match request {
+            SnapRequest::AccountRange { origin, limit, .. } => {
+                trace!(target: "sync", "{} <- GetAccountRange: from {} to {}", peer_id, origin, limit);
+                let mut rlp = RlpStream::new_list(5);
+                rlp.append(&request_id);
+                rlp.append(&root);
+                rlp.append(&origin);
+                rlp.append(&limit);
+                rlp.append(&bytes);
+                (GetAccountRangePacket, rlp.out())
+            }
+            SnapRequest::StorageRanges {
+                ref accounts,
+                origin,
+            } => {
+                trace!(target: "sync", "{} <- GetStorageRanges: {} accounts from {}", peer_id, accounts.len(), origin);
+                let mut rlp = RlpStream::new_list(6);
+                rlp.append(&request_id);
+                rlp.append(&root);
+                rlp.begin_list(accounts.len());
+                for (account, _) in accounts {
+                    rlp.append(account);
+                }
+                if origin.is_zero() {
+                    rlp.append_empty_data();
+                } else {
+                    rlp.append(&origin);
+                }
+                rlp.append_empty_data();
+                rlp.append(&bytes);
+                (GetStorageRangesPacket, rlp.out())
+            }
+            SnapRequest::ByteCodes(ref hashes) => {
+                trace!(target: "sync", "{} <- GetByteCodes: {} entries", peer_id, hashes.len());
+                let mut rlp = RlpStream::new_list(3);
+                rlp.append(&request_id);
+                rlp.append_list::<H256, H256>(hashes);
+                rlp.append(&bytes);
+                (GetByteCodesPacket, rlp.out())
+            }
+            SnapRequest::TrieNodes(ref nodes) => {
+                trace!(target: "sync", "{} <- GetTrieNodes: {} entries", peer_id, nodes.len());
+                let mut rlp = RlpStream::new_list(4);
+                rlp.append(&request_id);
+                rlp.append(&root);
+                rlp.begin_list(nodes.len());
+                for node in nodes {
+                    rlp.append_list::<Bytes, Bytes>(&node.path_set());
+                }
+                rlp.append(&bytes);
+                (GetTrieNodesPacket, rlp.out())
+            }
+        };
+
+        match sync.peers.get_mut(&peer_id) {
+            Some(peer) => {
+                if peer.asking != PeerAsking::Nothing {
+                    warn!(target:"sync", "Asking {:?} while requesting snap state", peer.asking);
+                }
+                peer.asking = PeerAsking::SnapState;
+                peer.ask_time = Instant::now();
+                peer.asking_snap = Some((request_id, root, request));
+            }
+            None => {
+                sync.snap.request_failed(request);
+                return;
+            }
+        }
+        if let Err(e) = io.send_snap(peer_id, packet_id, packet) {
+            debug!(target:"sync", "Error sending snap request: {:?}", e);
+            io.disconnect_peer(peer_id);
+        }
+    }
+
+    // Take the request a response from the peer answers. `None` if the response is
+    // unexpected or came too late to be of any use.
+    fn take_request(
+        sync: &mut ChainSync,
+        peer_id: PeerId,
+        request_id: RequestId,
+    ) -> Option<SnapRequest> {
+        let (root, request) = {
+            let peer = sync.peers.get_mut(&peer_id)?;
+            match peer.asking_snap {
+                Some((id, _, _)) if id == request_id => {}
+                _ => {
+                    trace!(target: "sync", "{}: Ignored unexpected snap response", peer_id);
+                    return None;
+                }
+            }
+            let (_, root, request) = peer.asking_snap.take()?;
+            (root, request)
+        };
+        sync.reset_peer_asking(peer_id, PeerAsking::SnapState);
+        if sync.state != SyncState::SnapState || root != sync.snap.state_root() {
+            trace!(target: "sync", "{}: Ignored expired snap response", peer_id);
+            return None;
+        }
+        Some(request)
+    }
+
+    fn on_account_range(
+        sync: &mut ChainSync,
+        io: &mut dyn SyncIo,
+        peer_id: PeerId,
+        r: &Rlp,
+    ) -> Result<(), DownloaderImportError> {
+        let request = match SnapHandler::take_request(sync, peer_id, r.val_at(0)?) {
+            Some(request) => request,
+            None => return Ok(()),
+        };
+        let decoded = r.at(1).and_then(|accounts| {
+            let accounts = accounts
+                .iter()
+                .map(|account| Ok((account.val_at(0)?, account.at(1)?.as_raw().to_vec())))
+                .collect::<Result<Vec<(H256, Bytes)>, DecoderError>>()?;
+            Ok((accounts, r.list_at(2)?))
+        });
+        match (request, decoded) {
+            (SnapRequest::AccountRange { task, origin, .. }, Ok((accounts, proof))) => {
+                trace!(target: "sync", "{} -> AccountRange: {} accounts", peer_id, accounts.len());
+                sync.snap
+                    .on_account_range(io.snapshot_service(), task, origin, accounts, proof)
+            }
+            (request, _) => {
+                sync.snap.request_failed(request);
+                Err(DownloaderImportError::Invalid)
+            }
+        }
+    }
+
+    fn on_storage_ranges(
+        sync: &mut ChainSync,
+        io: &mut dyn SyncIo,
+        peer_id: PeerId,
+        r: &Rlp,
+    ) -> Result<(), DownloaderImportError> {
+        let request = match SnapHandler::take_request(sync, peer_id, r.val_at(0)?) {
+            Some(request) => request,
+            None => return Ok(()),
+        };
+        let decoded = r.at(1).and_then(|ranges| {
+            let slots = ranges
+                .iter()
+                .map(|slots| {
+                    slots
+                        .iter()
+                        .map(|slot| Ok((slot.val_at(0)?, slot.val_at(1)?)))
+                        .collect::<Result<Vec<(H256, Bytes)>, DecoderError>>()
+                })
+                .collect::<Result<Vec<_>, DecoderError>>()?;
+            Ok((slots, r.list_at(2)?))
+        });
+        match (request, decoded) {
+            (SnapRequest::StorageRanges { accounts, origin }, Ok((slots, proof))) => {
+                trace!(target: "sync", "{} -> StorageRanges: {} accounts", peer_id, slots.len());
+                sync.snap
+                    .on_storage_ranges(io.snapshot_service(), accounts, origin, slots, proof)
+            }
+            (request, _) => {
+                sync.snap.request_failed(request);
+                Err(DownloaderImportError::Invalid)
+            }
+        }
+    }
+
+    fn on_byte_codes(
+        sync: &mut ChainSync,
+        io: &mut dyn SyncIo,
+        peer_id: PeerId,
+        r: &Rlp,
+    ) -> Result<(), DownloaderImportError> {
+        let request = match SnapHandler::take_request(sync, peer_id, r.val_at(0)?) {
+            Some(request) => request,
+            None => return Ok(()),
+        };
+        match (request, r.list_at::<Bytes>(1)) {
+            (SnapRequest::ByteCodes(hashes), Ok(codes)) => {
+                trace!(target: "sync", "{} -> ByteCodes: {} entries", peer_id, codes.len());
+                sync.snap
+                    .on_byte_codes(io.snapshot_service(), hashes, codes)
+            }
+            (request, _) => {
+                sync.snap.request_failed(request);
+                Err(DownloaderImportError::Invalid)
+            }
+        }
+    }
+
+    fn on_trie_nodes(
+        sync: &mut ChainSync,
+        io: &mut dyn SyncIo,
+        peer_id: PeerId,
+        r: &Rlp,
+    ) -> Result<(), DownloaderImportError> {
+        let request = match SnapHandler::take_request(sync, peer_id, r.val_at(0)?) {
+            Some(request) => request,
+            None => return Ok(()),
+        };
+        match (request, r.list_at::<Bytes>(1)) {
+            (SnapRequest::TrieNodes(requested), Ok(nodes)) => {
+                trace!(target: "sync", "{} -> TrieNodes: {} entries", peer_id, nodes.len());
+                sync.snap
+                    .on_trie_nodes(io.snapshot_service(), requested, nodes)
+            }
+            (request, _) => {
+                sync.snap.request_failed(request);
+                Err(DownloaderImportError::Invalid)
+            }
+        }
+    }
+
+    // Give up on `snap` and sync blocks instead.
+    fn abandon_pivot(sync: &mut ChainSync, io: &mut dyn SyncIo) {
+        sync.snap.clear();
+        sync.state = if sync.warp_sync.is_warp_only() {
+            SyncState::WaitingPeers
+        } else {
+            SyncState::Idle
+        };
+        sync.continue_sync(io);
+    }
+
+    // Checks a response of the peer fetching the pivot block is expected.
+    fn expect_pivot(sync: &mut ChainSync, peer_id: PeerId) -> bool {
+        let allowed = sync.peers.get(&peer_id).map_or(false, |p| p.is_allowed());
+        sync.reset_peer_asking(peer_id, PeerAsking::SnapPivot)
+            && allowed
+            && sync.state == SyncState::SnapPivot
+    }
+
+    /// Called when the headers from the pivot block up to the head of the peer arrive.
+    pub fn on_pivot_headers(
+        sync: &mut ChainSync,
+        io: &mut dyn SyncIo,
+        peer_id: PeerId,
+        r: &Rlp,
+    ) -> Result<(), DownloaderImportError> {
+        let (expected_hash, peer_difficulty) = match sync.peers.get(&peer_id) {
+            Some(peer) => (peer.asking_hash, peer.difficulty),
+            None => return Ok(()),
+        };
+        if !SnapHandler::expect_pivot(sync, peer_id) || sync.snap.pending_pivot().is_some() {
+            trace!(target: "sync", "{}: Ignored unexpected snap pivot headers", peer_id);
+            return Ok(());
+        }
+        if let Some(checkpoint) = sync.pending_checkpoint(io.chain()) {
+            return SnapHandler::on_checkpoint_header(sync, io, peer_id, r, checkpoint);
+        }
+        let (expected_hash, peer_difficulty) = match (expected_hash, peer_difficulty) {
+            (Some(hash), Some(difficulty)) => (hash, difficulty),
+            _ => return Ok(()),
+        };
+
+        let item_count = r.item_count()?;
+        trace!(target: "sync", "{} -> BlockHeaders: {} snap pivot headers", peer_id, item_count);
+        if item_count == 0 {
+            return Err(DownloaderImportError::Useless);
+        }
+        let mut headers = Vec::with_capacity(item_count);
+        for i in 0..item_count {
+            headers.push(SyncHeader::from_rlp(
+                r.at(i)?.as_raw().to_vec(),
+                sync.header_transitions,
+            )?);
+        }
+        if headers[0].header.hash() != expected_hash
+            || headers.windows(2).any(|pair| {
+                *pair[0].header.parent_hash() != pair[1].header.hash()
+                    || pair[0].header.number() != pair[1].header.number() + 1
+            })
+        {
+            return Err(DownloaderImportError::Invalid);
+        }
+
+        // the total difficulty of the peer is the one of its head.
+        let descendants_difficulty = headers.iter().try_fold(U256::zero(), |total, header| {
+            total.checked_add(*header.header.difficulty())
+        });
+        let parent_total_difficulty =
+            match descendants_difficulty.and_then(|d| peer_difficulty.checked_sub(d)) {
+                Some(difficulty) => difficulty,
+                None => return Err(DownloaderImportError::Invalid),
+            };
+        let pivot = headers.pop().expect("item_count > 0; qed");
+        let our_best = io.chain().chain_info().best_block_number;
+        if item_count as u64 != SNAP_PIVOT_DISTANCE + 1
+            || pivot.header.number() <= our_best + SNAPSHOT_RESTORE_THRESHOLD
+        {
+            debug!(target: "sync", "Chain head #{} is too close for snap sync, syncing blocks", pivot.header.number());
+            SnapHandler::abandon_pivot(sync, io);
+            return Ok(());
+        }
+
+        let hash = pivot.header.hash();
+        trace!(target: "sync", "Snap pivot is #{} ({})", pivot.header.number(), hash);
+        sync.snap.set_pivot_header(pivot, parent_total_difficulty);
+        SyncRequester::request_snap_pivot_body(sync, io, peer_id, hash);
+        Ok(())
+    }
+
+    // Called instead of `on_pivot_headers` when the pivot is the trusted checkpoint.
+    fn on_checkpoint_header(
+        sync: &mut ChainSync,
+        io: &mut dyn SyncIo,
+        peer_id: PeerId,
+        r: &Rlp,
+        checkpoint: Checkpoint,
+    ) -> Result<(), DownloaderImportError> {
+        let item_count = r.item_count()?;
+        trace!(target: "sync", "{} -> BlockHeaders: {} checkpoint headers", peer_id, item_count);
+        if item_count == 0 {
+            return Err(DownloaderImportError::Useless);
+        }
+        let header = SyncHeader::from_rlp(r.at(0)?.as_raw().to_vec(), sync.header_transitions)?;
+        if item_count > 2
+            || header.header.hash() != checkpoint.hash
+            || header.header.number() != checkpoint.number
+        {
+            return Err(DownloaderImportError::Invalid);
+        }
+        if item_count == 2 {
+            let later = SyncHeader::from_rlp(r.at(1)?.as_raw().to_vec(), sync.header_transitions)?;
+            if later.header.number() != checkpoint.number + SNAP_SERVE_WINDOW {
+                return Err(DownloaderImportError::Invalid);
+            }
+            // peers prune the state of older blocks, nobody is going to serve it.
+            warn!(target: "sync", "Checkpoint #{} is too old to restore its state over snap, syncing without it", checkpoint.number);
+            sync.checkpoint = None;
+            SnapHandler::abandon_pivot(sync, io);
+            return Ok(());
+        }
+
+        // the hash is trusted, but not a total difficulty reported by the peer.
+        let parent_total_difficulty = match checkpoint
+            .total_difficulty
+            .checked_sub(*header.header.difficulty())
+        {
+            Some(total) => total,
+            None => return Err(DownloaderImportError::Invalid),
+        };
+        trace!(target: "sync", "Snap pivot is checkpoint #{} ({})", header.header.number(), checkpoint.hash);
+        sync.snap.set_pivot_header(header, parent_total_difficulty);
+        SyncRequester::request_snap_pivot_body(sync, io, peer_id, checkpoint.hash);
+        Ok(())
+    }
+
+    /// Called when the body of the pivot block arrives.
+    pub fn on_pivot_body(
+        sync: &mut ChainSync,
+        io: &mut dyn SyncIo,
+        peer_id: PeerId,
+        r: &Rlp,
+    ) -> Result<(), DownloaderImportError> {
+        let hash = match sync.snap.pending_pivot() {
+            Some((header, true)) => header.hash(),
+            _ => H256::zero(),
+        };
+        if !SnapHandler::expect_pivot(sync, peer_id) || hash.is_zero() {
+            trace!(target: "sync", "{}: Ignored unexpected snap pivot body", peer_id);
+            return Ok(());
+        }
+        if r.item_count()? != 1 {
+            return Err(DownloaderImportError::Useless);
+        }
+        let body = SyncBody::from_rlp(r.at(0)?.as_raw(), sync.header_transitions)?;
+        if !sync.snap.set_pivot_body(body) {
+            return Err(DownloaderImportError::Invalid);
+        }
+        SyncRequester::request_snap_pivot_receipts(sync, io, peer_id, hash);
+        Ok(())
+    }
+
+    /// Called when the receipts of the pivot block arrive. Starts the state download.
+    pub fn on_pivot_receipts(
+        sync: &mut ChainSync,
+        io: &mut dyn SyncIo,
+        peer_id: PeerId,
+        r: &Rlp,
+    ) -> Result<(), DownloaderImportError> {
+        let receipts_root = match sync.snap.pending_pivot() {
+            Some((header, false)) => *header.receipts_root(),
+            _ => H256::zero(),
+        };
+        if !SnapHandler::expect_pivot(sync, peer_id) || receipts_root.is_zero() {
+            trace!(target: "sync", "{}: Ignored unexpected snap pivot receipts", peer_id);
+            return Ok(());
+        }
+        if r.item_count()? != 1 {
+            return Err(DownloaderImportError::Useless);
+        }
+        let receipts = TypedReceipt::decode_rlp_list(&r.at(0)?)?;
+        if ordered_trie_root(receipts.iter().map(TypedReceipt::encode)) != receipts_root {
+            return Err(DownloaderImportError::Invalid);
+        }
+
+        let (block, parent_total_difficulty) = sync
+            .snap
+            .take_pivot()
+            .expect("pending pivot has a body; qed");
+        if let Some((number, hash)) = sync.snap.pivot() {
+            info!(target: "sync", "Starting snap sync of the state of #{} ({})", number, hash);
+        }
+        io.snapshot_service().begin_snap_restore(SnapPivot {
+            block: encoded::Block::new(block),
+            receipts,
+            parent_total_difficulty,
+        });
+        sync.state = SyncState::SnapState;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chain::sync_packet::SyncPacket::GetBlockBodiesPacket;
+    use ethcore::client::{BlockChainClient, EachBlockWith, TestBlockChainClient};
+    use parking_lot::RwLock;
+    use std::collections::VecDeque;
+    use tests::{helpers::TestIo, snapshot::TestSnapshotService};
+    use types::ids::BlockId;
+
+    #[test]
+    fn returns_empty_account_range_for_unknown_state() {
+        let mut client = TestBlockChainClient::new();
+        let sync = RwLock::new(::chain::tests::dummy_sync_with_peer(H256::zero(), &client));
+        let queue = RwLock::new(VecDeque::new());
+        let ss = TestSnapshotService::new();
+        let mut io = TestIo::new(&mut client, &ss, &queue, None);
+
+        let mut request = RlpStream::new_list(5);
+        request.append(&42u64);
+        request.append(&H256::from_low_u64_be(1));
+        request.append(&H256::zero());
+        request.append(&H256::repeat_byte(0xff));
+        request.append(&(1024u64 * 1024));
+        let request = request.out();
+
+        let (packet_id, response) = SnapHandler::return_account_range(&io, &Rlp::new(&request), 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(packet_id, AccountRangePacket);
+        let response = response.out();
+        let rlp = Rlp::new(&response);
+        assert_eq!(rlp.val_at::<u64>(0).unwrap(), 42);
+        assert_eq!(rlp.at(1).unwrap().item_count().unwrap(), 0);
+        assert_eq!(rlp.at(2).unwrap().item_count().unwrap(), 0);
+
+        io.sender = Some(2);
+        SnapHandler::dispatch_packet(&sync, &mut io, 2, GetAccountRangePacket.id(), &request);
+        assert_eq!(io.packets.len(), 1);
+        assert_eq!(io.packets[0].packet_id, AccountRangePacket.id());
+    }
+
+    #[test]
+    fn checkpoint_header_becomes_pivot() {
+        let mut source = TestBlockChainClient::new();
+        source.add_blocks(10, EachBlockWith::Nothing);
+        let header = source.block_header(BlockId::Number(10)).unwrap();
+        let other = source.block_header(BlockId::Number(9)).unwrap();
+
+        let mut client = TestBlockChainClient::new();
+        let mut sync = ::chain::tests::dummy_sync_with_peer(H256::zero(), &client);
+        let checkpoint = Checkpoint {
+            number: 10,
+            hash: header.hash(),
+            total_difficulty: 1_000_000.into(),
+        };
+        let queue = RwLock::new(VecDeque::new());
+        let ss = TestSnapshotService::new();
+        let mut io = TestIo::new(&mut client, &ss, &queue, None);
+
+        // a wrong number is rejected even if the hash matches
+        sync.checkpoint = Some(Checkpoint {
+            number: 11,
+            ..checkpoint
+        });
+        expect_pivot(&mut sync);
+        let result =
+            SnapHandler::on_pivot_headers(&mut sync, &mut io, 0, &Rlp::new(&headers(&[&header])));
+        assert!(result.is_err());
+        assert!(sync.snap.pending_pivot().is_none());
+
+        sync.checkpoint = Some(checkpoint);
+        expect_pivot(&mut sync);
+        let result =
+            SnapHandler::on_pivot_headers(&mut sync, &mut io, 0, &Rlp::new(&headers(&[&other])));
+        assert!(result.is_err());
+        assert!(sync.snap.pending_pivot().is_none());
+
+        expect_pivot(&mut sync);
+        SnapHandler::on_pivot_headers(&mut sync, &mut io, 0, &Rlp::new(&headers(&[&header])))
+            .unwrap();
+        assert_eq!(
+            sync.snap.pending_pivot().map(|(h, _)| h.hash()),
+            Some(header.hash())
+        );
+        assert_eq!(
+            io.packets.last().unwrap().packet_id,
+            GetBlockBodiesPacket.id()
+        );
+    }
+
+    #[test]
+    fn falls_back_when_checkpoint_is_out_of_serving_window() {
+        let mut source = TestBlockChainClient::new();
+        source.add_blocks(10 + SNAP_SERVE_WINDOW as usize, EachBlockWith::Nothing);
+        let header = source.block_header(BlockId::Number(10)).unwrap();
+        let later = source
+            .block_header(BlockId::Number(10 + SNAP_SERVE_WINDOW))
+            .unwrap();
+
+        let mut client = TestBlockChainClient::new();
+        let mut sync = ::chain::tests::dummy_sync_with_peer(H256::zero(), &client);
+        sync.checkpoint = Some(Checkpoint {
+            number: 10,
+            hash: header.hash(),
+            total_difficulty: 1_000_000.into(),
+        });
+        let queue = RwLock::new(VecDeque::new());
+        let ss = TestSnapshotService::new();
+        let mut io = TestIo::new(&mut client, &ss, &queue, None);
+
+        // the peer is a whole serving window past the checkpoint
+        expect_pivot(&mut sync);
+        SnapHandler::on_pivot_headers(
+            &mut sync,
+            &mut io,
+            0,
+            &Rlp::new(&headers(&[&header, &later])),
+        )
+        .unwrap();
+        assert!(sync.snap.pending_pivot().is_none());
+        assert_eq!(sync.checkpoint, None);
+        assert!(sync.state != SyncState::SnapPivot);
+    }
+
+    fn headers(headers: &[&encoded::Header]) -> Bytes {
+        let mut rlp = RlpStream::new_list(headers.len());
+        for header in headers {
+            rlp.append_raw(header.rlp().as_raw(), 1);
+        }
+        rlp.out()
+    }
+
+    fn expect_pivot(sync: &mut ChainSync) {
+        sync.state = SyncState::SnapPivot;
+        sync.peers.get_mut(&0).unwrap().asking = PeerAsking::SnapPivot;
+    }
+}
@@ -17,7 +17,7 @@
 use bytes::Bytes;
 
 #[cfg(not(test))]
-use devp2p::PAYLOAD_SOFT_LIMIT;
+pub use devp2p::PAYLOAD_SOFT_LIMIT;
 #[cfg(test)]
 pub const PAYLOAD_SOFT_LIMIT: usize = 100_000;
 
@@ -25,6 +25,7 @@ use enum_primitive::FromPrimitive;
 use ethereum_types::H256;
 use network::{self, PeerId};
 use parking_lot::RwLock;
+use reputation::Behaviour;
 use rlp::{Rlp, RlpStream};
 use std::cmp;
 use types::{ids::BlockId, BlockNumber};
@@ -37,8 +38,8 @@ use super::{
 };
 
 use super::{
-    ChainSync, PacketProcessError, RlpResponseResult, SyncHandler, MAX_BODIES_TO_SEND,
-    MAX_HEADERS_TO_SEND, MAX_RECEIPTS_HEADERS_TO_SEND,
+    ChainSync, PacketProcessError, RlpResponseResult, SyncHandler, ETH_PROTOCOL_VERSION_67,
+    MAX_BODIES_TO_SEND, MAX_HEADERS_TO_SEND, MAX_RECEIPTS_HEADERS_TO_SEND,
 };
 use chain::MAX_NODE_DATA_TO_SEND;
 use std::borrow::Borrow;
@@ -58,6 +59,19 @@ impl SyncSupplier {
         data: &[u8],
     ) {
         if let Some(id) = SyncPacket::from_u8(packet_id) {
+            if id.is_removed_in_eth_67() {
+                let protocol_version = sync
+                    .read()
+                    .peers
+                    .get(&peer)
+                    .map_or(0, |p| p.protocol_version);
+                if protocol_version >= ETH_PROTOCOL_VERSION_67.0 {
+                    debug!(target: "sync", "{} -> Packet {:?} is not supported by eth/{}", peer, id, protocol_version);
+                    io.disable_peer(peer);
+                    return;
+                }
+            }
+
             let rlp_result = strip_request_id(data, sync.read().borrow(), &peer, &id);
 
             let result = match rlp_result {
@@ -146,8 +160,11 @@ impl SyncSupplier {
                                 };
                                 if res.is_err() {
                                     // peer sent invalid data, disconnect.
-                                    io.disable_peer(peer);
-                                    sync.write().deactivate_peer(io, peer);
+                                    sync.write().note_peer(
+                                        io,
+                                        peer,
+                                        Behaviour::InvalidTransactions,
+                                    );
                                 }
                             }
                             _ => {
@@ -511,6 +528,7 @@ mod test {
     use rlp::{Rlp, RlpStream};
     use std::{collections::VecDeque, str::FromStr};
     use tests::{helpers::TestIo, snapshot::TestSnapshotService};
+    use types::header::HeaderTransitions;
 
     #[test]
     fn return_block_headers() {
@@ -533,16 +551,16 @@ mod test {
         }
         fn to_header_vec(
             rlp: ::chain::RlpResponseResult,
-            eip1559_transition: BlockNumber,
+            transitions: HeaderTransitions,
         ) -> Vec<SyncHeader> {
             Rlp::new(&rlp.unwrap().unwrap().1.out())
                 .iter()
-                .map(|r| SyncHeader::from_rlp(r.as_raw().to_vec(), eip1559_transition).unwrap())
+                .map(|r| SyncHeader::from_rlp(r.as_raw().to_vec(), transitions).unwrap())
                 .collect()
         }
 
         let mut client = TestBlockChainClient::new();
-        let eip1559_transition = client.spec.params().eip1559_transition;
+        let transitions = client.spec.params().header_transitions();
         client.add_blocks(100, EachBlockWith::Nothing);
         let blocks: Vec<_> = (0..100)
             .map(|i| {
@@ -555,11 +573,8 @@ mod test {
         let headers: Vec<_> = blocks
             .iter()
             .map(|b| {
-                SyncHeader::from_rlp(
-                    Rlp::new(b).at(0).unwrap().as_raw().to_vec(),
-                    eip1559_transition,
-                )
-                .unwrap()
+                SyncHeader::from_rlp(Rlp::new(b).at(0).unwrap().as_raw().to_vec(), transitions)
+                    .unwrap()
             })
             .collect();
         let hashes: Vec<_> = headers.iter().map(|h| h.header.hash()).collect();
@@ -574,33 +589,27 @@ mod test {
             &Rlp::new(&make_hash_req(&unknown, 1, 0, false)),
             0,
         );
-        assert!(to_header_vec(result, eip1559_transition).is_empty(),);
+        assert!(to_header_vec(result, transitions).is_empty(),);
         let result = SyncSupplier::return_block_headers(
             &io,
             &Rlp::new(&make_hash_req(&unknown, 1, 0, true)),
             0,
         );
-        assert!(to_header_vec(result, eip1559_transition).is_empty());
+        assert!(to_header_vec(result, transitions).is_empty());
 
         let result = SyncSupplier::return_block_headers(
             &io,
             &Rlp::new(&make_hash_req(&hashes[2], 1, 0, true)),
             0,
         );
-        assert_eq!(
-            to_header_vec(result, eip1559_transition),
-            vec![headers[2].clone()]
-        );
+        assert_eq!(to_header_vec(result, transitions), vec![headers[2].clone()]);
 
         let result = SyncSupplier::return_block_headers(
             &io,
             &Rlp::new(&make_hash_req(&hashes[2], 1, 0, false)),
             0,
         );
-        assert_eq!(
-            to_header_vec(result, eip1559_transition),
-            vec![headers[2].clone()]
-        );
+        assert_eq!(to_header_vec(result, transitions), vec![headers[2].clone()]);
 
         let result = SyncSupplier::return_block_headers(
             &io,
@@ -608,7 +617,7 @@ mod test {
             0,
         );
         assert_eq!(
-            to_header_vec(result, eip1559_transition),
+            to_header_vec(result, transitions),
             vec![
                 headers[50].clone(),
                 headers[56].clone(),
@@ -622,7 +631,7 @@ mod test {
             0,
         );
         assert_eq!(
-            to_header_vec(result, eip1559_transition),
+            to_header_vec(result, transitions),
             vec![
                 headers[50].clone(),
                 headers[44].clone(),
@@ -632,22 +641,16 @@ mod test {
 
         let result =
             SyncSupplier::return_block_headers(&io, &Rlp::new(&make_num_req(2, 1, 0, true)), 0);
-        assert_eq!(
-            to_header_vec(result, eip1559_transition),
-            vec![headers[2].clone()]
-        );
+        assert_eq!(to_header_vec(result, transitions), vec![headers[2].clone()]);
 
         let result =
             SyncSupplier::return_block_headers(&io, &Rlp::new(&make_num_req(2, 1, 0, false)), 0);
-        assert_eq!(
-            to_header_vec(result, eip1559_transition),
-            vec![headers[2].clone()]
-        );
+        assert_eq!(to_header_vec(result, transitions), vec![headers[2].clone()]);
 
         let result =
             SyncSupplier::return_block_headers(&io, &Rlp::new(&make_num_req(50, 3, 5, false)), 0);
         assert_eq!(
-            to_header_vec(result, eip1559_transition),
+            to_header_vec(result, transitions),
             vec![
                 headers[50].clone(),
                 headers[56].clone(),
@@ -658,7 +661,7 @@ mod test {
         let result =
             SyncSupplier::return_block_headers(&io, &Rlp::new(&make_num_req(50, 3, 5, true)), 0);
         assert_eq!(
-            to_header_vec(result, eip1559_transition),
+            to_header_vec(result, transitions),
             vec![
                 headers[50].clone(),
                 headers[44].clone(),
@@ -24,7 +24,8 @@
 
 #![allow(unused_doc_comments)]
 
-use api::{ETH_PROTOCOL, PAR_PROTOCOL};
+use api::{ETH_PROTOCOL, PAR_PROTOCOL, SNAP_PROTOCOL};
+use enum_primitive::FromPrimitive;
 use network::{PacketId, ProtocolId};
 
 // An enum that defines all known packet ids in the context of
@@ -63,12 +64,29 @@ pub enum SyncPacket {
 
 use self::SyncPacket::*;
 
+// Packet ids of the `snap` protocol. They overlap with the ids above,
+// hence the separate enum.
+enum_from_primitive! {
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SnapPacket {
+    GetAccountRangePacket = 0x00,
+    AccountRangePacket = 0x01,
+    GetStorageRangesPacket = 0x02,
+    StorageRangesPacket = 0x03,
+    GetByteCodesPacket = 0x04,
+    ByteCodesPacket = 0x05,
+    GetTrieNodesPacket = 0x06,
+    TrieNodesPacket = 0x07,
+}
+}
+
 /// Provide both subprotocol and packet id information within the
 /// same object.
 pub trait PacketInfo {
     fn id(&self) -> PacketId;
     fn protocol(&self) -> ProtocolId;
     fn has_request_id_in_eth_66(&self) -> bool;
+    fn is_removed_in_eth_67(&self) -> bool;
 }
 
 // The mechanism to match packet ids and protocol may be improved
@@ -119,14 +137,60 @@ impl PacketInfo for SyncPacket {
             _ => false,
         }
     }
+
+    fn is_removed_in_eth_67(&self) -> bool {
+        match self {
+            GetNodeDataPacket | NodeDataPacket => true,
+            _ => false,
+        }
+    }
+}
+
+// `snap` packets carry their request id in the packet itself.
+impl PacketInfo for SnapPacket {
+    fn protocol(&self) -> ProtocolId {
+        SNAP_PROTOCOL
+    }
+
+    fn id(&self) -> PacketId {
+        (*self) as PacketId
+    }
+
+    fn has_request_id_in_eth_66(&self) -> bool {
+        false
+    }
+
+    fn is_removed_in_eth_67(&self) -> bool {
+        false
+    }
+}
+
+/// Name of a subprotocol as announced in the capabilities, e.g. `eth`.
+pub fn protocol_name(protocol: ProtocolId) -> String {
+    let bytes = protocol.as_u64().to_be_bytes();
+    String::from_utf8_lossy(&bytes)
+        .trim_start_matches('\0')
+        .to_owned()
+}
+
+/// Name of a packet of the given subprotocol, e.g. `Transactions`.
+/// Unknown packets are named by their id.
+pub fn packet_name(protocol: ProtocolId, packet_id: PacketId) -> String {
+    let name = if protocol == SNAP_PROTOCOL {
+        SnapPacket::from_u8(packet_id).map(|p| format!("{:?}", p))
+    } else {
+        SyncPacket::from_u8(packet_id).map(|p| format!("{:?}", p))
+    };
+    match name {
+        Some(name) => name.trim_end_matches("Packet").to_owned(),
+        None => format!("{:#04x}", packet_id),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use enum_primitive::FromPrimitive;
-
     #[test]
     fn packet_ids_from_u8_when_from_primitive_zero_then_equals_status_packet() {
         assert_eq!(SyncPacket::from_u8(0x00), Some(StatusPacket));
@@ -153,4 +217,30 @@ mod tests {
         assert_eq!(ConsensusDataPacket.id(), ConsensusDataPacket as PacketId);
         assert_eq!(ConsensusDataPacket.protocol(), PAR_PROTOCOL);
     }
+
+    #[test]
+    fn when_node_data_packets_then_removed_in_eth_67() {
+        assert!(GetNodeDataPacket.is_removed_in_eth_67());
+        assert!(NodeDataPacket.is_removed_in_eth_67());
+        assert!(!GetReceiptsPacket.is_removed_in_eth_67());
+        assert!(!NewPooledTransactionHashesPacket.is_removed_in_eth_67());
+    }
+
+    #[test]
+    fn when_snap_packet_then_id_and_protocol_match() {
+        assert_eq!(SnapPacket::from_u8(0x07), Some(SnapPacket::TrieNodesPacket));
+        assert!(SnapPacket::from_u8(0x08).is_none());
+        assert_eq!(SnapPacket::GetStorageRangesPacket.id(), 0x02);
+        assert_eq!(SnapPacket::GetStorageRangesPacket.protocol(), SNAP_PROTOCOL);
+    }
+
+    #[test]
+    fn names_packets_by_protocol() {
+        assert_eq!(protocol_name(ETH_PROTOCOL), "eth");
+        assert_eq!(protocol_name(SNAP_PROTOCOL), "snap");
+        assert_eq!(packet_name(ETH_PROTOCOL, 0x02), "Transactions");
+        assert_eq!(packet_name(PAR_PROTOCOL, 0x14), "SnapshotData");
+        assert_eq!(packet_name(SNAP_PROTOCOL, 0x02), "GetStorageRanges");
+        assert_eq!(packet_name(ETH_PROTOCOL, 0x99), "0x99");
+    }
 }
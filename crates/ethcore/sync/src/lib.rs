@@ -66,6 +66,9 @@ extern crate ethcore_miner;
 mod block_sync;
 mod blocks;
 mod chain;
+mod propagation;
+mod reputation;
+mod snap_sync;
 mod snapshot;
 mod sync_io;
 mod transactions_stats;
@@ -77,5 +80,11 @@ mod api;
 
 pub use api::*;
 pub use chain::{SyncState, SyncStatus};
-pub use devp2p::validate_node_url;
-pub use network::{ConnectionDirection, ConnectionFilter, Error, ErrorKind, NonReservedPeerMode};
+pub use devp2p::{validate_enrtree_url, validate_node_url};
+pub use network::{
+    ConnectionDirection, ConnectionFilter, Error, ErrorKind, NonReservedPeerMode, TrafficCounter,
+};
+pub use propagation::{
+    AnnounceOnly, BroadcastTo, DefaultPropagation, NoLocal, PrivateRelay, Propagation,
+    PropagationMode, PropagationTarget, TransactionPropagation,
+};
@@ -0,0 +1,261 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction propagation policies.
+//!
+//! A policy decides, per peer and transaction, whether the transaction is sent in full,
+//! only announced by hash, or not sent at all. Peers that don't support hash announcements
+//! (pre eth/65) never receive announced transactions.
+
+use std::{collections::HashSet, sync::Arc};
+
+use network::NodeId;
+
+/// How a transaction reaches a peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Propagation {
+    /// The transaction is not sent to the peer.
+    Skip,
+    /// Only the hash is announced and the peer may request the transaction.
+    Announce,
+    /// The whole transaction is sent.
+    Full,
+}
+
+/// Peer a transaction may be propagated to.
+#[derive(Debug, Clone)]
+pub struct PropagationTarget {
+    /// Node id of the peer, if known.
+    pub node_id: Option<NodeId>,
+    /// The peer supports transaction hash announcements.
+    pub supports_announcements: bool,
+    /// The peer was picked by the random fan-out of this propagation round.
+    /// New transactions go to every peer, others to about the square root of them.
+    pub in_fanout: bool,
+}
+
+/// Decides who sees which transactions, and how.
+pub trait TransactionPropagation: Send + Sync {
+    /// How to propagate a transaction to the given peer. `is_local` tells whether the
+    /// transaction was submitted to this node rather than received from the network.
+    fn propagation(&self, peer: &PropagationTarget, is_local: bool) -> Propagation;
+}
+
+/// Sends to the random fan-out, announcing to the peers which support it.
+#[derive(Debug, Default)]
+pub struct DefaultPropagation;
+
+impl TransactionPropagation for DefaultPropagation {
+    fn propagation(&self, peer: &PropagationTarget, _is_local: bool) -> Propagation {
+        match (peer.in_fanout, peer.supports_announcements) {
+            (false, _) => Propagation::Skip,
+            (true, true) => Propagation::Announce,
+            (true, false) => Propagation::Full,
+        }
+    }
+}
+
+/// Announces every transaction to every peer which supports announcements and never
+/// sends transactions unrequested.
+#[derive(Debug, Default)]
+pub struct AnnounceOnly;
+
+impl TransactionPropagation for AnnounceOnly {
+    fn propagation(&self, peer: &PropagationTarget, _is_local: bool) -> Propagation {
+        if peer.supports_announcements {
+            Propagation::Announce
+        } else {
+            Propagation::Skip
+        }
+    }
+}
+
+/// Sends every transaction in full to a set of peers, propagating to the others as usual.
+#[derive(Debug)]
+pub struct BroadcastTo {
+    peers: HashSet<NodeId>,
+}
+
+impl BroadcastTo {
+    /// Create a new policy broadcasting to the given nodes.
+    pub fn new(peers: HashSet<NodeId>) -> Self {
+        BroadcastTo { peers }
+    }
+}
+
+impl TransactionPropagation for BroadcastTo {
+    fn propagation(&self, peer: &PropagationTarget, is_local: bool) -> Propagation {
+        match peer.node_id {
+            Some(ref id) if self.peers.contains(id) => Propagation::Full,
+            _ => DefaultPropagation.propagation(peer, is_local),
+        }
+    }
+}
+
+/// Propagates only the transactions received from the network.
+#[derive(Debug, Default)]
+pub struct NoLocal;
+
+impl TransactionPropagation for NoLocal {
+    fn propagation(&self, peer: &PropagationTarget, is_local: bool) -> Propagation {
+        if is_local {
+            Propagation::Skip
+        } else {
+            DefaultPropagation.propagation(peer, is_local)
+        }
+    }
+}
+
+/// Sends local transactions in full to a set of relays only. Transactions from the
+/// network are propagated as usual.
+#[derive(Debug)]
+pub struct PrivateRelay {
+    relays: HashSet<NodeId>,
+}
+
+impl PrivateRelay {
+    /// Create a new policy relaying local transactions through the given nodes.
+    pub fn new(relays: HashSet<NodeId>) -> Self {
+        PrivateRelay { relays }
+    }
+}
+
+impl TransactionPropagation for PrivateRelay {
+    fn propagation(&self, peer: &PropagationTarget, is_local: bool) -> Propagation {
+        if !is_local {
+            return DefaultPropagation.propagation(peer, is_local);
+        }
+        match peer.node_id {
+            Some(ref id) if self.relays.contains(id) => Propagation::Full,
+            _ => Propagation::Skip,
+        }
+    }
+}
+
+/// Built-in propagation policies, as configured by the operator.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropagationMode {
+    /// `DefaultPropagation`.
+    Default,
+    /// `AnnounceOnly`.
+    AnnounceOnly,
+    /// `BroadcastTo` the given nodes.
+    Broadcast(HashSet<NodeId>),
+    /// `NoLocal`.
+    NoLocal,
+    /// `PrivateRelay` through the given nodes.
+    PrivateRelay(HashSet<NodeId>),
+}
+
+impl Default for PropagationMode {
+    fn default() -> Self {
+        PropagationMode::Default
+    }
+}
+
+impl PropagationMode {
+    /// Create the policy for this mode.
+    pub fn policy(self) -> Arc<dyn TransactionPropagation> {
+        match self {
+            PropagationMode::Default => Arc::new(DefaultPropagation),
+            PropagationMode::AnnounceOnly => Arc::new(AnnounceOnly),
+            PropagationMode::Broadcast(peers) => Arc::new(BroadcastTo::new(peers)),
+            PropagationMode::NoLocal => Arc::new(NoLocal),
+            PropagationMode::PrivateRelay(relays) => Arc::new(PrivateRelay::new(relays)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(node_id: u64, supports_announcements: bool, in_fanout: bool) -> PropagationTarget {
+        PropagationTarget {
+            node_id: Some(NodeId::from_low_u64_be(node_id)),
+            supports_announcements,
+            in_fanout,
+        }
+    }
+
+    #[test]
+    fn default_follows_fanout() {
+        let policy = DefaultPropagation;
+        assert_eq!(
+            policy.propagation(&target(1, true, true), false),
+            Propagation::Announce
+        );
+        assert_eq!(
+            policy.propagation(&target(1, false, true), false),
+            Propagation::Full
+        );
+        assert_eq!(
+            policy.propagation(&target(1, true, false), true),
+            Propagation::Skip
+        );
+    }
+
+    #[test]
+    fn announce_only_never_sends_full() {
+        let policy = AnnounceOnly;
+        assert_eq!(
+            policy.propagation(&target(1, true, false), true),
+            Propagation::Announce
+        );
+        assert_eq!(
+            policy.propagation(&target(1, false, true), true),
+            Propagation::Skip
+        );
+    }
+
+    #[test]
+    fn broadcast_sends_full_to_configured_peers() {
+        let policy = BroadcastTo::new(vec![NodeId::from_low_u64_be(1)].into_iter().collect());
+        assert_eq!(
+            policy.propagation(&target(1, true, false), false),
+            Propagation::Full
+        );
+        assert_eq!(
+            policy.propagation(&target(2, true, true), false),
+            Propagation::Announce
+        );
+        assert_eq!(
+            policy.propagation(&target(2, true, false), false),
+            Propagation::Skip
+        );
+    }
+
+    #[test]
+    fn private_relay_keeps_local_transactions_private() {
+        let policy = PrivateRelay::new(vec![NodeId::from_low_u64_be(1)].into_iter().collect());
+        assert_eq!(
+            policy.propagation(&target(1, true, false), true),
+            Propagation::Full
+        );
+        assert_eq!(
+            policy.propagation(&target(2, true, true), true),
+            Propagation::Skip
+        );
+        assert_eq!(
+            policy.propagation(&target(2, true, true), false),
+            Propagation::Announce
+        );
+        assert_eq!(
+            NoLocal.propagation(&target(2, true, true), true),
+            Propagation::Skip
+        );
+    }
+}
@@ -0,0 +1,254 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Peer reputation.
+//!
+//! Peers start out neutral. Useful responses raise their score, misbehaviour lowers it and the
+//! score decays back towards neutral over time so that old offences are eventually forgiven.
+//! Scores of disconnected nodes are kept for a while and restored when they reconnect.
+
+use network::{NodeId, PeerId};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Peers scoring this low are disabled.
+pub const DISABLE_THRESHOLD: i32 = -1000;
+/// Responses arriving later than this are considered slow.
+pub const SLOW_RESPONSE: Duration = Duration::from_secs(5);
+
+const MIN_SCORE: f64 = -1000.0;
+const MAX_SCORE: f64 = 200.0;
+/// Time it takes for a score to decay halfway towards neutral.
+const DECAY_HALF_LIFE: Duration = Duration::from_secs(15 * 60);
+/// Maximum number of disconnected nodes whose score is remembered.
+const MAX_DEPARTED: usize = 1024;
+
+/// Peer behaviour affecting its reputation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Behaviour {
+    /// Answered a request with data we could use.
+    UsefulResponse,
+    /// Answered a request, but only after `SLOW_RESPONSE`.
+    SlowResponse,
+    /// Answered a request with nothing we could use.
+    UselessResponse,
+    /// Didn't answer a request in time.
+    Timeout,
+    /// Served a snapshot chunk that doesn't belong to the manifest.
+    BadSnapshotChunk,
+    /// Sent transactions that couldn't be decoded.
+    InvalidTransactions,
+    /// Sent a malformed or inconsistent packet.
+    InvalidPacket,
+    /// Served a block that failed to import.
+    BadBlock,
+}
+
+impl Behaviour {
+    fn score_change(self) -> f64 {
+        match self {
+            Behaviour::UsefulResponse => 5.0,
+            Behaviour::SlowResponse => -20.0,
+            Behaviour::UselessResponse => -50.0,
+            Behaviour::Timeout => -200.0,
+            Behaviour::BadSnapshotChunk => -500.0,
+            // Enough to disable even the best scored peer.
+            Behaviour::InvalidTransactions | Behaviour::InvalidPacket | Behaviour::BadBlock => {
+                MIN_SCORE - MAX_SCORE
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Score {
+    value: f64,
+    updated: Instant,
+}
+
+impl Score {
+    fn neutral(now: Instant) -> Score {
+        Score {
+            value: 0.0,
+            updated: now,
+        }
+    }
+
+    fn at(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.updated);
+        let half_lives = elapsed.as_secs_f64() / DECAY_HALF_LIFE.as_secs_f64();
+        self.value * 0.5f64.powf(half_lives)
+    }
+
+    fn add(&mut self, change: f64, now: Instant) {
+        self.value = (self.at(now) + change).max(MIN_SCORE).min(MAX_SCORE);
+        self.updated = now;
+    }
+}
+
+/// Reputation of the connected peers.
+#[derive(Default)]
+pub struct PeerReputation {
+    peers: HashMap<PeerId, Score>,
+    departed: HashMap<NodeId, Score>,
+    disabled: usize,
+}
+
+impl PeerReputation {
+    /// Start tracking a connected peer, restoring the score it left with.
+    pub fn connected(&mut self, peer: PeerId, node: Option<NodeId>) {
+        let now = Instant::now();
+        let score = node
+            .and_then(|node| self.departed.remove(&node))
+            .unwrap_or_else(|| Score::neutral(now));
+        self.peers.insert(peer, score);
+    }
+
+    /// Stop tracking a peer, remembering its score for when it reconnects.
+    pub fn disconnected(&mut self, peer: PeerId, node: Option<NodeId>) {
+        let (score, node) = match (self.peers.remove(&peer), node) {
+            (Some(score), Some(node)) => (score, node),
+            _ => return,
+        };
+        let now = Instant::now();
+        if self.departed.len() >= MAX_DEPARTED {
+            // forget the nodes which have been forgiven anyway
+            self.departed.retain(|_, score| score.at(now).abs() >= 1.0);
+        }
+        if self.departed.len() >= MAX_DEPARTED {
+            let forgotten = self
+                .departed
+                .iter()
+                .min_by(|(_, a), (_, b)| {
+                    a.at(now)
+                        .abs()
+                        .partial_cmp(&b.at(now).abs())
+                        .expect("scores are finite; qed")
+                })
+                .map(|(node, _)| *node);
+            if let Some(forgotten) = forgotten {
+                self.departed.remove(&forgotten);
+            }
+        }
+        self.departed.insert(node, score);
+    }
+
+    /// Record a behaviour of a peer and return its new score.
+    pub fn note(&mut self, peer: PeerId, behaviour: Behaviour) -> i32 {
+        self.note_at(peer, behaviour, Instant::now())
+    }
+
+    fn note_at(&mut self, peer: PeerId, behaviour: Behaviour, now: Instant) -> i32 {
+        let score = self
+            .peers
+            .entry(peer)
+            .or_insert_with(|| Score::neutral(now));
+        score.add(behaviour.score_change(), now);
+        score.value as i32
+    }
+
+    /// Current score of a peer. Unknown peers are neutral.
+    pub fn score(&self, peer: PeerId) -> i32 {
+        self.score_at(peer, Instant::now())
+    }
+
+    fn score_at(&self, peer: PeerId, now: Instant) -> i32 {
+        self.peers
+            .get(&peer)
+            .map_or(0, |score| score.at(now) as i32)
+    }
+
+    /// Current scores of the connected peers.
+    pub fn scores(&self) -> Vec<i32> {
+        let now = Instant::now();
+        self.peers
+            .values()
+            .map(|score| score.at(now) as i32)
+            .collect()
+    }
+
+    /// Note that a peer was disabled because of its score.
+    pub fn note_disabled(&mut self) {
+        self.disabled += 1;
+    }
+
+    /// Number of peers disabled because of their score so far.
+    pub fn disabled_count(&self) -> usize {
+        self.disabled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn behaviour_changes_score() {
+        let now = Instant::now();
+        let mut reputation = PeerReputation::default();
+        assert_eq!(reputation.score_at(1, now), 0);
+        assert_eq!(reputation.note_at(1, Behaviour::UsefulResponse, now), 5);
+        assert_eq!(reputation.note_at(1, Behaviour::UselessResponse, now), -45);
+        assert_eq!(reputation.note_at(1, Behaviour::Timeout, now), -245);
+        assert_eq!(reputation.score_at(2, now), 0);
+    }
+
+    #[test]
+    fn fatal_behaviour_reaches_threshold() {
+        let now = Instant::now();
+        let mut reputation = PeerReputation::default();
+        for _ in 0..100 {
+            reputation.note_at(1, Behaviour::UsefulResponse, now);
+        }
+        assert_eq!(reputation.score_at(1, now), MAX_SCORE as i32);
+        assert_eq!(
+            reputation.note_at(1, Behaviour::BadBlock, now),
+            DISABLE_THRESHOLD
+        );
+    }
+
+    #[test]
+    fn score_decays_towards_neutral() {
+        let now = Instant::now();
+        let mut reputation = PeerReputation::default();
+        reputation.note_at(1, Behaviour::BadSnapshotChunk, now);
+        assert_eq!(reputation.score_at(1, now + DECAY_HALF_LIFE), -250);
+        assert_eq!(reputation.score_at(1, now + DECAY_HALF_LIFE * 2), -125);
+        assert_eq!(
+            reputation.note_at(1, Behaviour::UsefulResponse, now + DECAY_HALF_LIFE * 2),
+            -120
+        );
+    }
+
+    #[test]
+    fn score_is_restored_on_reconnect() {
+        let node = NodeId::from_low_u64_be(1);
+        let mut reputation = PeerReputation::default();
+        reputation.connected(1, Some(node));
+        reputation.note(1, Behaviour::Timeout);
+        reputation.disconnected(1, Some(node));
+        assert_eq!(reputation.score(1), 0);
+
+        reputation.connected(2, Some(node));
+        assert!(reputation.score(2) < -190);
+        // peers without a known node id start over
+        reputation.disconnected(2, None);
+        reputation.connected(3, Some(node));
+        assert_eq!(reputation.score(3), 0);
+    }
+}
@@ -0,0 +1,665 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `snap/1` state download.
+//!
+//! Keeps track of what is left to download of the state of the pivot block:
+//! account ranges, the storage and code of the accounts downloaded so far,
+//! and, once all ranges are in, the trie nodes the snapshot service still
+//! misses to heal the state.
+//!
+//! Only the last item of a range is checked against the proof sent along with
+//! it. Items which don't belong to the pivot state are never referenced from
+//! its root, so healing replaces whatever they break.
+
+use std::collections::{HashSet, VecDeque};
+
+use block_sync::BlockDownloaderImportError as DownloaderImportError;
+use blocks::{SyncBody, SyncHeader};
+use bytes::Bytes;
+use ethcore::snapshot::{
+    snap::{self, MissingNode, SnapData},
+    SnapshotService,
+};
+use ethereum_types::{BigEndianHash, H256, U256};
+use hash::{keccak, KECCAK_EMPTY, KECCAK_NULL_RLP};
+use types::{header::Header as BlockHeader, BlockNumber};
+
+/// Number of ranges the account trie is split into to be downloaded in parallel.
+const ACCOUNT_TASKS: usize = 16;
+/// Max number of accounts to ask the storage of in one request.
+const MAX_STORAGE_ACCOUNTS_TO_REQUEST: usize = 64;
+/// Max number of contract codes to ask for in one request.
+const MAX_CODES_TO_REQUEST: usize = 64;
+/// Max number of trie nodes to ask for in one request.
+const MAX_TRIE_NODES_TO_REQUEST: usize = 256;
+/// Max number of items handed to the snapshot service and not processed yet.
+const MAX_SNAP_DATA_AHEAD: usize = 32;
+
+/// A `snap/1` request sent to a peer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapRequest {
+    /// Accounts of the account task `task`, from `origin` up to `limit`.
+    AccountRange {
+        task: usize,
+        origin: H256,
+        limit: H256,
+    },
+    /// Storage of accounts, given with their storage root. `origin` bounds the
+    /// slots of the first account.
+    StorageRanges {
+        accounts: Vec<(H256, H256)>,
+        origin: H256,
+    },
+    /// Contract code by hash.
+    ByteCodes(Vec<H256>),
+    /// Trie nodes to heal the state with.
+    TrieNodes(Vec<MissingNode>),
+}
+
+/// Pivot block being fetched over `eth`.
+struct PendingPivot {
+    header: SyncHeader,
+    parent_total_difficulty: U256,
+    body: Option<SyncBody>,
+}
+
+/// A contiguous range of the account trie still to download.
+struct AccountTask {
+    next: H256,
+    last: H256,
+    busy: bool,
+    done: bool,
+}
+
+/// Storage of an account still to download, starting at `origin`.
+struct StorageTask {
+    account: H256,
+    root: H256,
+    origin: H256,
+}
+
+fn next_key(key: &H256) -> Option<H256> {
+    key.into_uint()
+        .checked_add(U256::one())
+        .map(|next| BigEndianHash::from_uint(&next))
+}
+
+fn is_ascending(keys: &[(H256, Bytes)], origin: &H256) -> bool {
+    keys.first().map_or(true, |(first, _)| first >= origin)
+        && keys.windows(2).all(|pair| pair[0].0 < pair[1].0)
+}
+
+/// `snap/1` state downloader.
+pub struct SnapDownloader {
+    pending_pivot: Option<PendingPivot>,
+    pivot: Option<(BlockNumber, H256)>,
+    state_root: H256,
+    account_tasks: Vec<AccountTask>,
+    storage_tasks: VecDeque<StorageTask>,
+    code_tasks: VecDeque<H256>,
+    known_code: HashSet<H256>,
+    /// Storage and code requests in flight while downloading ranges.
+    busy_requests: usize,
+    ranges_complete: bool,
+    /// Nodes and code requested for healing.
+    requested: HashSet<H256>,
+    /// Nodes and code received for healing, and not yet processed by the snapshot service.
+    delivered: HashSet<H256>,
+    /// Number of items handed to the snapshot service.
+    fed: usize,
+}
+
+impl SnapDownloader {
+    /// Create a new instance.
+    pub fn new() -> Self {
+        SnapDownloader {
+            pending_pivot: None,
+            pivot: None,
+            state_root: KECCAK_NULL_RLP,
+            account_tasks: Vec::new(),
+            storage_tasks: VecDeque::new(),
+            code_tasks: VecDeque::new(),
+            known_code: HashSet::new(),
+            busy_requests: 0,
+            ranges_complete: false,
+            requested: HashSet::new(),
+            delivered: HashSet::new(),
+            fed: 0,
+        }
+    }
+
+    /// Clear everything.
+    pub fn clear(&mut self) {
+        *self = SnapDownloader::new();
+    }
+
+    /// Note the header of the pivot block, along with the total difficulty of its parent.
+    pub fn set_pivot_header(&mut self, header: SyncHeader, parent_total_difficulty: U256) {
+        self.pending_pivot = Some(PendingPivot {
+            header,
+            parent_total_difficulty,
+            body: None,
+        });
+    }
+
+    /// Header of the pivot block being fetched and whether its body is still needed.
+    pub fn pending_pivot(&self) -> Option<(&BlockHeader, bool)> {
+        self.pending_pivot
+            .as_ref()
+            .map(|pivot| (&pivot.header.header, pivot.body.is_none()))
+    }
+
+    /// Note the body of the pivot block. Returns `false` if it does not match the header.
+    pub fn set_pivot_body(&mut self, body: SyncBody) -> bool {
+        let pivot = match self.pending_pivot {
+            Some(ref mut pivot) => pivot,
+            None => return false,
+        };
+        let header = &pivot.header.header;
+        if keccak(&body.transactions_bytes) != *header.transactions_root()
+            || keccak(&body.uncles_bytes) != *header.uncles_hash()
+            || body.withdrawals_bytes.as_ref().map(keccak).as_ref() != header.withdrawals_root()
+        {
+            return false;
+        }
+        pivot.body = Some(body);
+        true
+    }
+
+    /// Take the pivot block once its body is known, and start downloading its state.
+    pub fn take_pivot(&mut self) -> Option<(Bytes, U256)> {
+        match self.pending_pivot.take() {
+            Some(PendingPivot {
+                header,
+                parent_total_difficulty,
+                body: Some(body),
+            }) => {
+                self.reset_to(
+                    header.header.number(),
+                    header.header.hash(),
+                    *header.header.state_root(),
+                );
+                let block = ::blocks::block_bytes(&header, &body);
+                Some((block, parent_total_difficulty))
+            }
+            pending => {
+                self.pending_pivot = pending;
+                None
+            }
+        }
+    }
+
+    /// Start downloading the state with the given root, of the given block.
+    pub fn reset_to(&mut self, number: BlockNumber, hash: H256, state_root: H256) {
+        self.clear();
+        self.pivot = Some((number, hash));
+        self.state_root = state_root;
+
+        let step = U256::max_value() / U256::from(ACCOUNT_TASKS);
+        self.account_tasks = (0..ACCOUNT_TASKS)
+            .map(|i| AccountTask {
+                next: BigEndianHash::from_uint(&(step * U256::from(i))),
+                last: if i == ACCOUNT_TASKS - 1 {
+                    H256::repeat_byte(0xff)
+                } else {
+                    BigEndianHash::from_uint(&(step * U256::from(i + 1) - U256::one()))
+                },
+                busy: false,
+                done: false,
+            })
+            .collect();
+    }
+
+    /// Number and hash of the block whose state is downloaded.
+    pub fn pivot(&self) -> Option<(BlockNumber, H256)> {
+        self.pivot
+    }
+
+    /// Root of the state being downloaded.
+    pub fn state_root(&self) -> H256 {
+        self.state_root
+    }
+
+    /// Number of items handed to the snapshot service so far.
+    pub fn fed(&self) -> usize {
+        self.fed
+    }
+
+    /// Whether all account ranges have been downloaded and the state is being healed.
+    pub fn is_healing(&self) -> bool {
+        self.ranges_complete
+    }
+
+    fn feed(&mut self, service: &dyn SnapshotService, data: SnapData) {
+        self.fed += 1;
+        service.restore_snap_data(data);
+    }
+
+    /// Find the next request to send, given the number of items the snapshot service
+    /// has processed so far.
+    pub fn next_request(
+        &mut self,
+        service: &dyn SnapshotService,
+        processed: usize,
+    ) -> Option<SnapRequest> {
+        let backlog = self.fed.saturating_sub(processed);
+        if backlog == 0 {
+            self.delivered.clear();
+        }
+        if backlog > MAX_SNAP_DATA_AHEAD {
+            return None;
+        }
+
+        if !self.ranges_complete {
+            if let Some(request) = self.next_range_request() {
+                return Some(request);
+            }
+            let all_done = self.account_tasks.iter().all(|task| task.done);
+            // healing must not start before the ranges are all processed.
+            if all_done && self.busy_requests == 0 && backlog == 0 {
+                trace!(target: "snap", "Account ranges complete, healing state {}", self.state_root);
+                self.ranges_complete = true;
+                self.feed(service, SnapData::RangesComplete);
+            }
+            return None;
+        }
+
+        let in_flight = self.requested.len() + self.delivered.len();
+        let (nodes, codes) = service.snap_missing_data(in_flight + MAX_TRIE_NODES_TO_REQUEST)?;
+        let codes: Vec<H256> = codes
+            .into_iter()
+            .filter(|hash| !self.requested.contains(hash) && !self.delivered.contains(hash))
+            .take(MAX_CODES_TO_REQUEST)
+            .collect();
+        if !codes.is_empty() {
+            self.requested.extend(codes.iter().cloned());
+            return Some(SnapRequest::ByteCodes(codes));
+        }
+        let nodes: Vec<MissingNode> = nodes
+            .into_iter()
+            .filter(|node| {
+                !self.requested.contains(&node.hash) && !self.delivered.contains(&node.hash)
+            })
+            .take(MAX_TRIE_NODES_TO_REQUEST)
+            .collect();
+        if !nodes.is_empty() {
+            self.requested.extend(nodes.iter().map(|node| node.hash));
+            return Some(SnapRequest::TrieNodes(nodes));
+        }
+        None
+    }
+
+    fn next_range_request(&mut self) -> Option<SnapRequest> {
+        if let Some(task) = self.storage_tasks.pop_front() {
+            let origin = task.origin;
+            let mut accounts = vec![(task.account, task.root)];
+            // a large storage is continued on its own.
+            if origin.is_zero() {
+                while accounts.len() < MAX_STORAGE_ACCOUNTS_TO_REQUEST {
+                    match self.storage_tasks.front() {
+                        Some(next) if next.origin.is_zero() => {}
+                        _ => break,
+                    }
+                    let next = self.storage_tasks.pop_front().expect("front is Some; qed");
+                    accounts.push((next.account, next.root));
+                }
+            }
+            self.busy_requests += 1;
+            return Some(SnapRequest::StorageRanges { accounts, origin });
+        }
+
+        if !self.code_tasks.is_empty() {
+            let count = ::std::cmp::min(self.code_tasks.len(), MAX_CODES_TO_REQUEST);
+            let hashes = self.code_tasks.drain(..count).collect();
+            self.busy_requests += 1;
+            return Some(SnapRequest::ByteCodes(hashes));
+        }
+
+        let (index, task) = self
+            .account_tasks
+            .iter_mut()
+            .enumerate()
+            .find(|(_, task)| !task.busy && !task.done)?;
+        task.busy = true;
+        Some(SnapRequest::AccountRange {
+            task: index,
+            origin: task.next,
+            limit: task.last,
+        })
+    }
+
+    /// Give back the work of a request which won't be answered.
+    pub fn request_failed(&mut self, request: SnapRequest) {
+        match request {
+            SnapRequest::AccountRange { task, .. } => {
+                if let Some(task) = self.account_tasks.get_mut(task) {
+                    task.busy = false;
+                }
+            }
+            SnapRequest::StorageRanges { accounts, origin } => {
+                self.busy_requests = self.busy_requests.saturating_sub(1);
+                self.requeue_storage(&accounts, origin);
+            }
+            SnapRequest::ByteCodes(hashes) => {
+                if self.ranges_complete {
+                    for hash in &hashes {
+                        self.requested.remove(hash);
+                    }
+                } else {
+                    self.busy_requests = self.busy_requests.saturating_sub(1);
+                    self.code_tasks.extend(hashes);
+                }
+            }
+            SnapRequest::TrieNodes(nodes) => {
+                for node in &nodes {
+                    self.requested.remove(&node.hash);
+                }
+            }
+        }
+    }
+
+    fn requeue_storage(&mut self, accounts: &[(H256, H256)], origin: H256) {
+        for (index, (account, root)) in accounts.iter().enumerate().rev() {
+            self.storage_tasks.push_front(StorageTask {
+                account: *account,
+                root: *root,
+                origin: if index == 0 { origin } else { H256::zero() },
+            });
+        }
+    }
+
+    /// Process an `AccountRange` response.
+    pub fn on_account_range(
+        &mut self,
+        service: &dyn SnapshotService,
+        task: usize,
+        origin: H256,
+        accounts: Vec<(H256, Bytes)>,
+        proof: Vec<Bytes>,
+    ) -> Result<(), DownloaderImportError> {
+        let root = self.state_root;
+        let last_key = match self.account_tasks.get_mut(task) {
+            Some(task) => {
+                task.busy = false;
+                task.last
+            }
+            None => return Ok(()),
+        };
+
+        let (last, last_slim) = match accounts.last() {
+            Some(last) => last.clone(),
+            None => {
+                // an empty response without a proof means the peer doesn't have the state.
+                if proof.is_empty() {
+                    return Err(DownloaderImportError::Useless);
+                }
+                if snap::has_keys_after(&root, &origin, &proof) {
+                    return Err(DownloaderImportError::Invalid);
+                }
+                self.account_tasks[task].done = true;
+                return Ok(());
+            }
+        };
+
+        if !is_ascending(&accounts, &origin) {
+            return Err(DownloaderImportError::Invalid);
+        }
+        let last_account = snap::from_slim_account(&last_slim)?;
+        if !snap::verify_proof(&root, &last, &::rlp::encode(&last_account), &proof) {
+            return Err(DownloaderImportError::Invalid);
+        }
+
+        let mut in_task = Vec::with_capacity(accounts.len());
+        for (hash, slim) in accounts {
+            if hash > last_key {
+                break;
+            }
+            let account = snap::from_slim_account(&slim)?;
+            if account.storage_root != KECCAK_NULL_RLP {
+                self.storage_tasks.push_back(StorageTask {
+                    account: hash,
+                    root: account.storage_root,
+                    origin: H256::zero(),
+                });
+            }
+            if account.code_hash != KECCAK_EMPTY && self.known_code.insert(account.code_hash) {
+                self.code_tasks.push_back(account.code_hash);
+            }
+            in_task.push((hash, slim));
+        }
+
+        let next = next_key(&last).filter(|next| *next <= last_key);
+        match next {
+            Some(next) if snap::has_keys_after(&root, &last, &proof) => {
+                self.account_tasks[task].next = next;
+            }
+            _ => self.account_tasks[task].done = true,
+        }
+
+        trace!(target: "snap", "Got {} accounts up to {}", in_task.len(), last);
+        if !in_task.is_empty() {
+            self.feed(service, SnapData::Accounts(in_task));
+        }
+        Ok(())
+    }
+
+    /// Process a `StorageRanges` response.
+    pub fn on_storage_ranges(
+        &mut self,
+        service: &dyn SnapshotService,
+        accounts: Vec<(H256, H256)>,
+        origin: H256,
+        mut slots: Vec<Vec<(H256, Bytes)>>,
+        proof: Vec<Bytes>,
+    ) -> Result<(), DownloaderImportError> {
+        self.busy_requests = self.busy_requests.saturating_sub(1);
+        if slots.is_empty() || slots.len() > accounts.len() {
+            self.requeue_storage(&accounts, origin);
+            return Err(if slots.is_empty() {
+                DownloaderImportError::Useless
+            } else {
+                DownloaderImportError::Invalid
+            });
+        }
+
+        // check everything before feeding anything.
+        let served = slots.len();
+        let mut last_complete = true;
+        for (index, account_slots) in slots.iter().enumerate() {
+            let first_origin = if index == 0 { origin } else { H256::zero() };
+            let valid = is_ascending(account_slots, &first_origin);
+            if valid && index == served - 1 && !proof.is_empty() {
+                let root = &accounts[index].1;
+                last_complete = match account_slots.last() {
+                    Some((key, value)) => {
+                        if !snap::verify_proof(root, key, value, &proof) {
+                            self.requeue_storage(&accounts, origin);
+                            return Err(DownloaderImportError::Invalid);
+                        }
+                        !snap::has_keys_after(root, key, &proof)
+                    }
+                    None => !snap::has_keys_after(root, &first_origin, &proof),
+                };
+            }
+            if !valid {
+                self.requeue_storage(&accounts, origin);
+                return Err(DownloaderImportError::Invalid);
+            }
+        }
+
+        self.requeue_storage(&accounts[served..], H256::zero());
+        let last_origin = match slots[served - 1].last() {
+            Some((key, _)) => next_key(key),
+            None if served == 1 => Some(origin),
+            None => Some(H256::zero()),
+        };
+        if let (false, Some(next)) = (last_complete, last_origin) {
+            let (account, root) = accounts[served - 1];
+            self.storage_tasks.push_front(StorageTask {
+                account,
+                root,
+                origin: next,
+            });
+        } else {
+            last_complete = true;
+        }
+
+        for (index, account_slots) in slots.drain(..).enumerate() {
+            self.feed(
+                service,
+                SnapData::Storage {
+                    account: accounts[index].0,
+                    slots: account_slots,
+                    complete: index < served - 1 || last_complete,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Process a `ByteCodes` response.
+    pub fn on_byte_codes(
+        &mut self,
+        service: &dyn SnapshotService,
+        hashes: Vec<H256>,
+        codes: Vec<Bytes>,
+    ) -> Result<(), DownloaderImportError> {
+        let served: HashSet<H256> = codes.iter().map(keccak).collect();
+        let unrequested = served.iter().any(|hash| !hashes.contains(hash));
+        if self.ranges_complete {
+            for hash in &hashes {
+                self.requested.remove(hash);
+            }
+        } else {
+            self.busy_requests = self.busy_requests.saturating_sub(1);
+            let missing = hashes
+                .iter()
+                .filter(|hash| unrequested || !served.contains(hash));
+            self.code_tasks.extend(missing);
+        }
+
+        if unrequested {
+            return Err(DownloaderImportError::Invalid);
+        }
+        if codes.is_empty() {
+            return Err(DownloaderImportError::Useless);
+        }
+        if self.ranges_complete {
+            self.delivered.extend(served);
+        }
+        self.feed(service, SnapData::Code(codes));
+        Ok(())
+    }
+
+    /// Process a `TrieNodes` response.
+    pub fn on_trie_nodes(
+        &mut self,
+        service: &dyn SnapshotService,
+        requested: Vec<MissingNode>,
+        nodes: Vec<Bytes>,
+    ) -> Result<(), DownloaderImportError> {
+        for node in &requested {
+            self.requested.remove(&node.hash);
+        }
+        if nodes.is_empty() {
+            return Err(DownloaderImportError::Useless);
+        }
+        if nodes.len() > requested.len()
+            || nodes
+                .iter()
+                .zip(&requested)
+                .any(|(node, expected)| keccak(node) != expected.hash)
+        {
+            return Err(DownloaderImportError::Invalid);
+        }
+        self.delivered
+            .extend(requested.iter().take(nodes.len()).map(|node| node.hash));
+        self.feed(service, SnapData::TrieNodes(nodes));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tests::snapshot::TestSnapshotService;
+
+    fn downloader() -> SnapDownloader {
+        let mut snap = SnapDownloader::new();
+        snap.reset_to(100, H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+        snap
+    }
+
+    #[test]
+    fn splits_account_trie_in_tasks() {
+        let service = TestSnapshotService::new();
+        let mut snap = downloader();
+        let mut expected_origin = H256::zero();
+        for i in 0..ACCOUNT_TASKS {
+            match snap.next_request(&service, 0) {
+                Some(SnapRequest::AccountRange {
+                    task,
+                    origin,
+                    limit,
+                }) => {
+                    assert_eq!(task, i);
+                    assert_eq!(origin, expected_origin);
+                    expected_origin = next_key(&limit).unwrap_or_default();
+                }
+                other => panic!("Unexpected request {:?}", other),
+            }
+        }
+        // the last task ends with the keyspace
+        assert_eq!(expected_origin, H256::zero());
+        assert_eq!(snap.next_request(&service, 0), None);
+    }
+
+    #[test]
+    fn empty_account_range_without_proof_is_retried() {
+        let service = TestSnapshotService::new();
+        let mut snap = downloader();
+        let request = snap.next_request(&service, 0).unwrap();
+        assert_eq!(
+            snap.on_account_range(&service, 0, H256::zero(), Vec::new(), Vec::new()),
+            Err(DownloaderImportError::Useless)
+        );
+        assert_eq!(snap.next_request(&service, 0), Some(request));
+        assert_eq!(snap.fed(), 0);
+    }
+
+    #[test]
+    fn unrequested_code_is_rejected() {
+        let service = TestSnapshotService::new();
+        let mut snap = downloader();
+        let hash = keccak(b"code");
+        snap.code_tasks.push_back(hash);
+        assert_eq!(
+            snap.next_request(&service, 0),
+            Some(SnapRequest::ByteCodes(vec![hash]))
+        );
+        assert_eq!(
+            snap.on_byte_codes(&service, vec![hash], vec![b"other".to_vec()]),
+            Err(DownloaderImportError::Invalid)
+        );
+        assert_eq!(snap.fed(), 0);
+        // the code is asked again before any account
+        assert_eq!(
+            snap.next_request(&service, 0),
+            Some(SnapRequest::ByteCodes(vec![hash]))
+        );
+    }
+}
@@ -15,7 +15,7 @@
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
 use bytes::Bytes;
-use chain::sync_packet::{PacketInfo, SyncPacket};
+use chain::sync_packet::{PacketInfo, SnapPacket, SyncPacket};
 use ethcore::{client::BlockChainClient, snapshot::SnapshotService};
 use network::{
     client_version::ClientVersion, Error, NetworkContext, PacketId, PeerId, ProtocolId, SessionInfo,
@@ -26,16 +26,26 @@ use types::BlockNumber;
 
 /// IO interface for the syncing handler.
 /// Provides peer connection management and an interface to the blockchain client.
-// TODO: ratings
 pub trait SyncIo {
     /// Disable a peer
     fn disable_peer(&mut self, peer_id: PeerId);
     /// Disconnect peer
     fn disconnect_peer(&mut self, peer_id: PeerId);
+    /// Report the current reputation score of a peer to the network layer.
+    fn set_peer_reputation(&mut self, _peer_id: PeerId, _score: i32) {}
+    /// Report the RLP-encoded `[fork_id]` announced by a peer to the network layer.
+    fn set_peer_fork_id(&mut self, _peer_id: PeerId, _fork_id: Vec<u8>) {}
     /// Respond to current request with a packet. Can be called from an IO handler for incoming packet.
     fn respond(&mut self, packet_id: PacketId, data: Vec<u8>) -> Result<(), Error>;
     /// Send a packet to a peer using specified protocol.
     fn send(&mut self, peer_id: PeerId, packet_id: SyncPacket, data: Vec<u8>) -> Result<(), Error>;
+    /// Send a `snap` protocol packet to a peer.
+    fn send_snap(
+        &mut self,
+        peer_id: PeerId,
+        packet_id: SnapPacket,
+        data: Vec<u8>,
+    ) -> Result<(), Error>;
     /// Get the blockchain
     fn chain(&self) -> &dyn BlockChainClient;
     /// Get the snapshot service.
@@ -92,6 +102,14 @@ impl<'s> SyncIo for NetSyncIo<'s> {
         self.network.disconnect_peer(peer_id);
     }
 
+    fn set_peer_reputation(&mut self, peer_id: PeerId, score: i32) {
+        self.network.set_peer_reputation(peer_id, score);
+    }
+
+    fn set_peer_fork_id(&mut self, peer_id: PeerId, fork_id: Vec<u8>) {
+        self.network.set_peer_fork_id(peer_id, fork_id);
+    }
+
     fn respond(&mut self, packet_id: PacketId, data: Vec<u8>) -> Result<(), Error> {
         self.network.respond(packet_id, data)
     }
@@ -101,6 +119,16 @@ impl<'s> SyncIo for NetSyncIo<'s> {
             .send_protocol(packet_id.protocol(), peer_id, packet_id.id(), data)
     }
 
+    fn send_snap(
+        &mut self,
+        peer_id: PeerId,
+        packet_id: SnapPacket,
+        data: Vec<u8>,
+    ) -> Result<(), Error> {
+        self.network
+            .send_protocol(packet_id.protocol(), peer_id, packet_id.id(), data)
+    }
+
     fn chain(&self) -> &dyn BlockChainClient {
         self.chain
     }
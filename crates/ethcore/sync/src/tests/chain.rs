@@ -15,10 +15,15 @@
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
 use super::helpers::*;
-use chain::SyncState;
+use chain::{
+    sync_packet::{PacketInfo, SyncPacket::*},
+    SyncState, ETH_PROTOCOL_VERSION_68,
+};
 use ethcore::client::{
     BlockChainClient, BlockId, BlockInfo, ChainInfo, EachBlockWith, TestBlockChainClient,
 };
+use ethereum_types::H256;
+use rlp::{Rlp, RlpStream};
 use std::sync::Arc;
 use SyncConfig;
 use WarpSync;
@@ -299,3 +304,96 @@ fn disconnect_on_unrelated_chain() {
     net.sync();
     assert_eq!(net.disconnect_events, vec![(0, 0)]);
 }
+
+// Both peers speak eth/68, the default test peers stay on eth/66.
+fn eth68_net() -> TestNet<EthPeer<TestBlockChainClient>> {
+    let mut net = TestNet::new(2);
+    for peer in 0..2 {
+        net.peer_mut(peer).eth_protocol_version = ETH_PROTOCOL_VERSION_68.0;
+    }
+    net
+}
+
+fn connect_eth68_peer(net: &TestNet<EthPeer<TestBlockChainClient>>) {
+    let status = net.peer(1).pending_message().unwrap();
+    net.peer(0).receive_message(1, status);
+    net.peer(0).queue.write().clear();
+}
+
+#[test]
+fn propagates_transaction_announcements_to_eth68_peers() {
+    let mut net = eth68_net();
+    let hash = net.peer(0).chain.insert_transaction_to_queue();
+    net.start();
+    connect_eth68_peer(&net);
+    net.sync_step_peer(0);
+
+    let queue = net.peer(0).queue.read();
+    let packet = queue
+        .iter()
+        .find(|p| p.packet_id == NewPooledTransactionHashesPacket.id())
+        .expect("transaction is announced to the peer");
+    let tx = net.peer(0).chain.transactions_to_propagate()[0]
+        .signed()
+        .clone();
+    let rlp = Rlp::new(&packet.data);
+    assert_eq!(rlp.item_count().unwrap(), 3);
+    assert_eq!(rlp.val_at::<Vec<u8>>(0).unwrap(), vec![tx.tx_type() as u8]);
+    assert_eq!(
+        rlp.list_at::<u64>(1).unwrap(),
+        vec![tx.encode().len() as u64]
+    );
+    assert_eq!(rlp.list_at::<H256>(2).unwrap(), vec![hash]);
+}
+
+#[test]
+fn disconnects_peer_on_mismatched_transaction_announcement() {
+    let mut net = eth68_net();
+    net.start();
+    connect_eth68_peer(&net);
+
+    let mut announcement = RlpStream::new_list(3);
+    announcement.append(&vec![0u8, 2u8]);
+    announcement.begin_list(1).append(&100u64);
+    announcement
+        .begin_list(2)
+        .append(&H256::from_low_u64_be(1))
+        .append(&H256::from_low_u64_be(2));
+    let to_disconnect = net.peer(0).receive_message(
+        1,
+        TestPacket {
+            data: announcement.out(),
+            packet_id: NewPooledTransactionHashesPacket.id(),
+            recipient: 0,
+        },
+    );
+
+    assert!(to_disconnect.contains(&1));
+}
+
+#[test]
+fn disconnects_peer_requesting_node_data_after_eth67() {
+    let mut net = eth68_net();
+    net.start();
+    connect_eth68_peer(&net);
+
+    let mut request = RlpStream::new_list(2);
+    request.append(&1u64);
+    request.begin_list(1).append(&H256::zero());
+    let to_disconnect = net.peer(0).receive_message(
+        1,
+        TestPacket {
+            data: request.out(),
+            packet_id: GetNodeDataPacket.id(),
+            recipient: 0,
+        },
+    );
+
+    assert!(to_disconnect.contains(&1));
+    assert!(net
+        .peer(0)
+        .queue
+        .read()
+        .iter()
+        .all(|p| p.packet_id != NodeDataPacket.id()));
+}
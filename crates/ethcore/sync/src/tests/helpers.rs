@@ -14,11 +14,12 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use api::PAR_PROTOCOL;
+use api::{PAR_PROTOCOL, SNAP_PROTOCOL};
 use bytes::Bytes;
 use chain::{
-    sync_packet::{PacketInfo, SyncPacket},
+    sync_packet::{PacketInfo, SnapPacket, SyncPacket},
     ChainSync, ForkFilterApi, SyncSupplier, ETH_PROTOCOL_VERSION_66, PAR_PROTOCOL_VERSION_2,
+    SNAP_PROTOCOL_VERSION_1,
 };
 use ethcore::{
     client::{
@@ -69,6 +70,7 @@ where
     pub to_disconnect: HashSet<PeerId>,
     pub packets: Vec<TestPacket>,
     pub peers_info: HashMap<PeerId, String>,
+    pub eth_protocol_version: u8,
     overlay: RwLock<HashMap<BlockNumber, Bytes>>,
 }
 
@@ -92,6 +94,7 @@ where
             overlay: RwLock::new(HashMap::new()),
             packets: Vec::new(),
             peers_info: HashMap::new(),
+            eth_protocol_version: ETH_PROTOCOL_VERSION_66.0,
         }
     }
 }
@@ -146,6 +149,20 @@ where
         Ok(())
     }
 
+    fn send_snap(
+        &mut self,
+        peer_id: PeerId,
+        packet_id: SnapPacket,
+        data: Vec<u8>,
+    ) -> Result<(), network::Error> {
+        self.packets.push(TestPacket {
+            data: data,
+            packet_id: packet_id.id(),
+            recipient: peer_id,
+        });
+        Ok(())
+    }
+
     fn chain(&self) -> &dyn BlockChainClient {
         &*self.chain
     }
@@ -171,8 +188,10 @@ where
     fn protocol_version(&self, protocol: ProtocolId, _peer_id: PeerId) -> u8 {
         if protocol == PAR_PROTOCOL {
             PAR_PROTOCOL_VERSION_2.0
+        } else if protocol == SNAP_PROTOCOL {
+            SNAP_PROTOCOL_VERSION_1.0
         } else {
-            ETH_PROTOCOL_VERSION_66.0
+            self.eth_protocol_version
         }
     }
 
@@ -252,6 +271,7 @@ where
     pub sync: RwLock<ChainSync>,
     pub queue: RwLock<VecDeque<TestPacket>>,
     pub io_queue: RwLock<VecDeque<ChainMessageType>>,
+    pub eth_protocol_version: u8,
     new_blocks_queue: RwLock<VecDeque<NewBlockMessage>>,
 }
 
@@ -259,6 +279,12 @@ impl<C> EthPeer<C>
 where
     C: FlushingBlockChainClient,
 {
+    fn io(&self, sender: Option<PeerId>) -> TestIo<C> {
+        let mut io = TestIo::new(&*self.chain, &self.snapshot_service, &self.queue, sender);
+        io.eth_protocol_version = self.eth_protocol_version;
+        io
+    }
+
     fn is_io_queue_empty(&self) -> bool {
         self.io_queue.read().is_empty()
     }
@@ -268,7 +294,7 @@ where
     }
 
     fn process_io_message(&self, message: ChainMessageType) {
-        let mut io = TestIo::new(&*self.chain, &self.snapshot_service, &self.queue, None);
+        let mut io = self.io(None);
         match message {
             ChainMessageType::Consensus(data) => {
                 self.sync.write().propagate_consensus_packet(&mut io, data)
@@ -277,7 +303,7 @@ where
     }
 
     fn process_new_block_message(&self, message: NewBlockMessage) {
-        let mut io = TestIo::new(&*self.chain, &self.snapshot_service, &self.queue, None);
+        let mut io = self.io(None);
         self.sync.write().chain_new_blocks(
             &mut io,
             &message.imported,
@@ -295,34 +321,18 @@ impl<C: FlushingBlockChainClient> Peer for EthPeer<C> {
 
     fn on_connect(&self, other: PeerId) {
         self.sync.write().update_targets(&*self.chain);
-        self.sync.write().on_peer_connected(
-            &mut TestIo::new(
-                &*self.chain,
-                &self.snapshot_service,
-                &self.queue,
-                Some(other),
-            ),
-            other,
-        );
+        self.sync
+            .write()
+            .on_peer_connected(&mut self.io(Some(other)), other);
     }
 
     fn on_disconnect(&self, other: PeerId) {
-        let mut io = TestIo::new(
-            &*self.chain,
-            &self.snapshot_service,
-            &self.queue,
-            Some(other),
-        );
+        let mut io = self.io(Some(other));
         self.sync.write().on_peer_aborting(&mut io, other);
     }
 
     fn receive_message(&self, from: PeerId, msg: TestPacket) -> HashSet<PeerId> {
-        let mut io = TestIo::new(
-            &*self.chain,
-            &self.snapshot_service,
-            &self.queue,
-            Some(from),
-        );
+        let mut io = self.io(Some(from));
         SyncSupplier::dispatch_packet(&self.sync, &mut io, from, msg.packet_id, &msg.data);
         self.chain.flush();
         io.to_disconnect.clone()
@@ -338,7 +348,7 @@ impl<C: FlushingBlockChainClient> Peer for EthPeer<C> {
     }
 
     fn sync_step(&self) {
-        let mut io = TestIo::new(&*self.chain, &self.snapshot_service, &self.queue, None);
+        let mut io = self.io(None);
         self.chain.flush();
         self.sync.write().maintain_peers(&mut io);
         self.sync.write().maintain_sync(&mut io);
@@ -347,12 +357,7 @@ impl<C: FlushingBlockChainClient> Peer for EthPeer<C> {
     }
 
     fn restart_sync(&self) {
-        self.sync.write().restart(&mut TestIo::new(
-            &*self.chain,
-            &self.snapshot_service,
-            &self.queue,
-            None,
-        ));
+        self.sync.write().restart(&mut self.io(None));
     }
 
     fn process_all_io_messages(&self) {
@@ -412,6 +417,7 @@ impl TestNet<EthPeer<TestBlockChainClient>> {
                 miner: Arc::new(Miner::new_for_tests(&Spec::new_test(), None)),
                 queue: RwLock::new(VecDeque::new()),
                 io_queue: RwLock::new(VecDeque::new()),
+                eth_protocol_version: ETH_PROTOCOL_VERSION_66.0,
                 new_blocks_queue: RwLock::new(VecDeque::new()),
             }));
         }
@@ -570,15 +576,9 @@ where
 impl<C: FlushingBlockChainClient> TestNet<EthPeer<C>> {
     pub fn trigger_chain_new_blocks(&mut self, peer_id: usize) {
         let peer = &mut self.peers[peer_id];
-        peer.sync.write().chain_new_blocks(
-            &mut TestIo::new(&*peer.chain, &peer.snapshot_service, &peer.queue, None),
-            &[],
-            &[],
-            &[],
-            &[],
-            &[],
-            &[],
-        );
+        peer.sync
+            .write()
+            .chain_new_blocks(&mut peer.io(None), &[], &[], &[], &[], &[], &[]);
     }
 }
 
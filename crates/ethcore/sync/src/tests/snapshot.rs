@@ -18,7 +18,10 @@ use super::helpers::*;
 use bytes::Bytes;
 use ethcore::{
     client::EachBlockWith,
-    snapshot::{CreationStatus, ManifestData, RestorationStatus, SnapshotService},
+    snapshot::{
+        snap::{MissingNode, SnapData, SnapPivot},
+        CreationStatus, ManifestData, RestorationStatus, SnapshotService,
+    },
 };
 use ethereum_types::H256;
 use hash::keccak;
@@ -35,6 +38,8 @@ pub struct TestSnapshotService {
     restoration_manifest: Mutex<Option<ManifestData>>,
     state_restoration_chunks: Mutex<HashMap<H256, Bytes>>,
     block_restoration_chunks: Mutex<HashMap<H256, Bytes>>,
+    pub snap_pivot: Mutex<Option<SnapPivot>>,
+    pub snap_data: Mutex<Vec<SnapData>>,
 }
 
 impl TestSnapshotService {
@@ -45,6 +50,8 @@ impl TestSnapshotService {
             restoration_manifest: Mutex::new(None),
             state_restoration_chunks: Mutex::new(HashMap::new()),
             block_restoration_chunks: Mutex::new(HashMap::new()),
+            snap_pivot: Mutex::new(None),
+            snap_data: Mutex::new(Vec::new()),
         }
     }
 
@@ -80,6 +87,8 @@ impl TestSnapshotService {
             restoration_manifest: Mutex::new(None),
             state_restoration_chunks: Mutex::new(HashMap::new()),
             block_restoration_chunks: Mutex::new(HashMap::new()),
+            snap_pivot: Mutex::new(None),
+            snap_data: Mutex::new(Vec::new()),
         }
     }
 }
@@ -112,6 +121,15 @@ impl SnapshotService for TestSnapshotService {
     }
 
     fn restoration_status(&self) -> RestorationStatus {
+        if self.snap_pivot.lock().is_some() {
+            return RestorationStatus::Ongoing {
+                block_number: 0,
+                state_chunks: 0,
+                block_chunks: 0,
+                state_chunks_done: self.snap_data.lock().len() as u32,
+                block_chunks_done: 0,
+            };
+        }
         match *self.restoration_manifest.lock() {
             Some(ref manifest)
                 if self.state_restoration_chunks.lock().len() == manifest.state_hashes.len()
@@ -147,6 +165,8 @@ impl SnapshotService for TestSnapshotService {
 
     fn abort_restore(&self) {
         *self.restoration_manifest.lock() = None;
+        *self.snap_pivot.lock() = None;
+        self.snap_data.lock().clear();
         self.state_restoration_chunks.lock().clear();
         self.block_restoration_chunks.lock().clear();
     }
@@ -175,6 +195,24 @@ impl SnapshotService for TestSnapshotService {
         }
     }
 
+    fn begin_snap_restore(&self, pivot: SnapPivot) {
+        *self.snap_pivot.lock() = Some(pivot);
+        self.snap_data.lock().clear();
+    }
+
+    fn restore_snap_data(&self, data: SnapData) {
+        if self.snap_pivot.lock().is_some() {
+            self.snap_data.lock().push(data);
+        }
+    }
+
+    fn snap_missing_data(&self, _max: usize) -> Option<(Vec<MissingNode>, Vec<H256>)> {
+        self.snap_pivot
+            .lock()
+            .as_ref()
+            .map(|_| (Vec::new(), Vec::new()))
+    }
+
     fn shutdown(&self) {
         self.abort_restore();
     }
@@ -34,9 +34,9 @@
 use crate::bytes::Bytes;
 
 use crate::{
-    header::Header,
+    header::{Header, HeaderTransitions},
     transaction::{TypedTransaction, UnverifiedTransaction},
-    BlockNumber,
+    withdrawal::Withdrawal,
 };
 use rlp::{DecoderError, Rlp, RlpStream};
 
@@ -49,29 +49,40 @@ pub struct Block {
     pub transactions: Vec<UnverifiedTransaction>,
     /// The uncles of this block.
     pub uncles: Vec<Header>,
+    /// The withdrawals of this block. Present only after EIP-4895.
+    pub withdrawals: Option<Vec<Withdrawal>>,
 }
 
 impl Block {
     /// Get the RLP-encoding of the block with the seal.
     pub fn rlp_bytes(&self) -> Bytes {
-        let mut block_rlp = RlpStream::new_list(3);
+        let mut block_rlp = RlpStream::new_list(3 + self.withdrawals.is_some() as usize);
         block_rlp.append(&self.header);
         TypedTransaction::rlp_append_list(&mut block_rlp, &self.transactions);
         block_rlp.append_list(&self.uncles);
+        if let Some(ref withdrawals) = self.withdrawals {
+            block_rlp.append_list(withdrawals);
+        }
         block_rlp.out()
     }
 
-    pub fn decode_rlp(rlp: &Rlp, eip1559_transition: BlockNumber) -> Result<Self, DecoderError> {
+    pub fn decode_rlp(rlp: &Rlp, transitions: HeaderTransitions) -> Result<Self, DecoderError> {
         if rlp.as_raw().len() != rlp.payload_info()?.total() {
             return Err(DecoderError::RlpIsTooBig);
         }
-        if rlp.item_count()? != 3 {
+        let item_count = rlp.item_count()?;
+        if item_count != 3 && item_count != 4 {
             return Err(DecoderError::RlpIncorrectListLen);
         }
         Ok(Block {
-            header: Header::decode_rlp(&rlp.at(0)?, eip1559_transition)?,
+            header: Header::decode_rlp(&rlp.at(0)?, transitions)?,
             transactions: TypedTransaction::decode_rlp_list(&rlp.at(1)?)?,
-            uncles: Header::decode_rlp_list(&rlp.at(2)?, eip1559_transition)?,
+            uncles: Header::decode_rlp_list(&rlp.at(2)?, transitions)?,
+            withdrawals: if item_count == 4 {
+                Some(rlp.list_at(3)?)
+            } else {
+                None
+            },
         })
     }
 }
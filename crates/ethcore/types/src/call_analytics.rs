@@ -25,4 +25,6 @@ pub struct CallAnalytics {
     pub vm_tracing: bool,
     /// Make a diff.
     pub state_diffing: bool,
+    /// Record the accessed addresses and storage keys.
+    pub access_list_recording: bool,
 }
@@ -26,9 +26,10 @@
 use crate::{
     block::Block as FullBlock,
     hash::keccak,
-    header::Header as FullHeader,
+    header::{Header as FullHeader, HeaderTransitions},
     transaction::UnverifiedTransaction,
     views::{self, BlockView, BodyView, HeaderView},
+    withdrawal::Withdrawal,
     BlockNumber,
 };
 
@@ -49,8 +50,8 @@ impl Header {
     }
 
     /// Upgrade this encoded view to a fully owned `Header` object.
-    pub fn decode(&self, eip1559_transition: BlockNumber) -> Result<FullHeader, rlp::DecoderError> {
-        FullHeader::decode_rlp(&self.rlp(), eip1559_transition)
+    pub fn decode(&self, transitions: HeaderTransitions) -> Result<FullHeader, rlp::DecoderError> {
+        FullHeader::decode_rlp(&self.rlp(), transitions)
     }
 
     /// Get a borrowed header view onto the data.
@@ -144,13 +145,20 @@ impl Header {
     }
 
     /// Engine-specific seal fields.
-    pub fn seal(&self, eip1559: bool) -> Vec<Vec<u8>> {
-        self.view().seal(eip1559)
+    pub fn seal(&self, transitions: HeaderTransitions) -> Vec<Vec<u8>> {
+        self.view().seal(transitions)
     }
 
     /// Base fee.
-    pub fn base_fee(&self) -> U256 {
-        self.view().base_fee()
+    pub fn base_fee(&self, transitions: HeaderTransitions) -> U256 {
+        self.view().base_fee(transitions)
+    }
+
+    /// Blob base fee, derived from the excess blob gas.
+    pub fn blob_base_fee(&self, transitions: HeaderTransitions) -> Option<U256> {
+        self.view()
+            .excess_blob_gas(transitions)
+            .map(crate::header::calc_blob_base_fee)
     }
 }
 
@@ -174,12 +182,9 @@ impl Body {
     /// Fully decode this block body.
     pub fn decode(
         &self,
-        eip1559_transition: BlockNumber,
+        transitions: HeaderTransitions,
     ) -> (Vec<UnverifiedTransaction>, Vec<FullHeader>) {
-        (
-            self.view().transactions(),
-            self.view().uncles(eip1559_transition),
-        )
+        (self.view().transactions(), self.view().uncles(transitions))
     }
 
     /// Get the RLP of this block body.
@@ -227,8 +232,8 @@ impl Body {
     }
 
     /// Decode uncle headers.
-    pub fn uncles(&self, eip1559_transition: BlockNumber) -> Vec<FullHeader> {
-        self.view().uncles(eip1559_transition)
+    pub fn uncles(&self, transitions: HeaderTransitions) -> Vec<FullHeader> {
+        self.view().uncles(transitions)
     }
 
     /// Number of uncles.
@@ -245,6 +250,11 @@ impl Body {
     pub fn uncle_hashes(&self) -> Vec<H256> {
         self.view().uncle_hashes()
     }
+
+    /// Decode withdrawals, if the body has them.
+    pub fn withdrawals(&self) -> Option<Vec<Withdrawal>> {
+        self.view().withdrawals()
+    }
 }
 
 /// Owning block view.
@@ -259,10 +269,14 @@ impl Block {
 
     /// Create a new owning block view by concatenating the encoded header and body
     pub fn new_from_header_and_body(header: &views::HeaderView, body: &views::BodyView) -> Self {
-        let mut stream = RlpStream::new_list(3);
+        let withdrawals = body.withdrawals_rlp();
+        let mut stream = RlpStream::new_list(3 + withdrawals.is_some() as usize);
         stream.append_raw(header.rlp().as_raw(), 1);
         stream.append_raw(body.transactions_rlp().as_raw(), 1);
         stream.append_raw(body.uncles_rlp().as_raw(), 1);
+        if let Some(withdrawals) = withdrawals {
+            stream.append_raw(withdrawals.as_raw(), 1);
+        }
         Block::new(stream.out())
     }
 
@@ -279,20 +293,18 @@ impl Block {
     }
 
     /// Decode to a full block.
-    pub fn decode(&self, eip1559_transition: BlockNumber) -> Result<FullBlock, rlp::DecoderError> {
-        FullBlock::decode_rlp(&self.rlp(), eip1559_transition)
+    pub fn decode(&self, transitions: HeaderTransitions) -> Result<FullBlock, rlp::DecoderError> {
+        FullBlock::decode_rlp(&self.rlp(), transitions)
     }
 
     /// Decode the header.
-    pub fn decode_header(&self, eip1559_transition: BlockNumber) -> FullHeader {
-        FullHeader::decode_rlp(&self.view().rlp().at(0).rlp, eip1559_transition).unwrap_or_else(
-            |e| {
-                panic!(
-                    "block header, view rlp is trusted and should be valid: {:?}",
-                    e
-                )
-            },
-        )
+    pub fn decode_header(&self, transitions: HeaderTransitions) -> FullHeader {
+        FullHeader::decode_rlp(&self.view().rlp().at(0).rlp, transitions).unwrap_or_else(|e| {
+            panic!(
+                "block header, view rlp is trusted and should be valid: {:?}",
+                e
+            )
+        })
     }
 
     /// Clone the encoded header.
@@ -390,8 +402,8 @@ impl Block {
     }
 
     /// Engine-specific seal fields.
-    pub fn seal(&self, eip1559: bool) -> Vec<Vec<u8>> {
-        self.header_view().seal(eip1559)
+    pub fn seal(&self, transitions: HeaderTransitions) -> Vec<Vec<u8>> {
+        self.header_view().seal(transitions)
     }
 }
 
@@ -418,8 +430,8 @@ impl Block {
     }
 
     /// Decode uncle headers.
-    pub fn uncles(&self, eip1559_transition: BlockNumber) -> Vec<FullHeader> {
-        self.view().uncles(eip1559_transition)
+    pub fn uncles(&self, transitions: HeaderTransitions) -> Vec<FullHeader> {
+        self.view().uncles(transitions)
     }
 
     /// Number of uncles.
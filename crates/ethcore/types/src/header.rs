@@ -52,8 +52,10 @@ pub struct ExtendedHeader {
 ///
 /// Doesn't do all that much on its own.
 ///
-/// Two versions of header exist. First one is before EIP1559. Second version is after EIP1559.
-/// EIP1559 version added field base_fee_per_gas.
+/// Four versions of header exist. First one is before EIP1559. Second version is after EIP1559.
+/// EIP1559 version added field base_fee_per_gas. Third version is after EIP4895 and adds
+/// field withdrawals_root after the base fee. Fourth version is after EIP4844 and adds fields
+/// blob_gas_used, excess_blob_gas and (EIP4788) parent_beacon_block_root after the withdrawals root.
 #[derive(Debug, Clone, Eq, MallocSizeOf)]
 pub struct Header {
     /// Parent hash.
@@ -89,6 +91,14 @@ pub struct Header {
 
     /// Base fee per gas. Introduced by EIP1559.
     base_fee_per_gas: Option<U256>,
+    /// Withdrawals root. Introduced by EIP4895.
+    withdrawals_root: Option<H256>,
+    /// Total blob gas consumed by the transactions of the block. Introduced by EIP4844.
+    blob_gas_used: Option<u64>,
+    /// Running total of blob gas consumed in excess of the target. Introduced by EIP4844.
+    excess_blob_gas: Option<u64>,
+    /// Root of the parent beacon block. Introduced by EIP4788.
+    parent_beacon_block_root: Option<H256>,
 
     /// Memoized hash of that header and the seal.
     hash: Option<H256>,
@@ -117,6 +127,10 @@ impl PartialEq for Header {
             && self.difficulty == c.difficulty
             && self.seal == c.seal
             && self.base_fee_per_gas == c.base_fee_per_gas
+            && self.withdrawals_root == c.withdrawals_root
+            && self.blob_gas_used == c.blob_gas_used
+            && self.excess_blob_gas == c.excess_blob_gas
+            && self.parent_beacon_block_root == c.parent_beacon_block_root
     }
 }
 
@@ -142,6 +156,10 @@ impl Default for Header {
             seal: vec![],
             hash: None,
             base_fee_per_gas: None,
+            withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
         }
     }
 }
@@ -227,6 +245,31 @@ impl Header {
         self.base_fee_per_gas
     }
 
+    /// Get the withdrawals root field of the header.
+    pub fn withdrawals_root(&self) -> Option<&H256> {
+        self.withdrawals_root.as_ref()
+    }
+
+    /// Get the blob gas used field of the header.
+    pub fn blob_gas_used(&self) -> Option<u64> {
+        self.blob_gas_used
+    }
+
+    /// Get the excess blob gas field of the header.
+    pub fn excess_blob_gas(&self) -> Option<u64> {
+        self.excess_blob_gas
+    }
+
+    /// Get the parent beacon block root field of the header.
+    pub fn parent_beacon_block_root(&self) -> Option<&H256> {
+        self.parent_beacon_block_root.as_ref()
+    }
+
+    /// Get the blob base fee of the block, derived from the excess blob gas field.
+    pub fn blob_base_fee(&self) -> Option<U256> {
+        self.excess_blob_gas.map(calc_blob_base_fee)
+    }
+
     /// Get the seal field with RLP-decoded values as bytes.
     pub fn decode_seal<'a, T: ::std::iter::FromIterator<&'a [u8]>>(
         &'a self,
@@ -315,6 +358,26 @@ impl Header {
         change_field(&mut self.hash, &mut self.base_fee_per_gas, a);
     }
 
+    /// Set the withdrawals root field of the header.
+    pub fn set_withdrawals_root(&mut self, a: Option<H256>) {
+        change_field(&mut self.hash, &mut self.withdrawals_root, a);
+    }
+
+    /// Set the blob gas used field of the header.
+    pub fn set_blob_gas_used(&mut self, a: Option<u64>) {
+        change_field(&mut self.hash, &mut self.blob_gas_used, a);
+    }
+
+    /// Set the excess blob gas field of the header.
+    pub fn set_excess_blob_gas(&mut self, a: Option<u64>) {
+        change_field(&mut self.hash, &mut self.excess_blob_gas, a);
+    }
+
+    /// Set the parent beacon block root field of the header.
+    pub fn set_parent_beacon_block_root(&mut self, a: Option<H256>) {
+        change_field(&mut self.hash, &mut self.parent_beacon_block_root, a);
+    }
+
     /// Get the hash of this header (keccak of the RLP with seal).
     pub fn hash(&self) -> H256 {
         self.hash.unwrap_or_else(|| keccak(self.rlp(Seal::With)))
@@ -339,11 +402,12 @@ impl Header {
 
     /// Place this header into an RLP stream `s`, optionally `with_seal`.
     fn stream_rlp(&self, s: &mut RlpStream, with_seal: Seal) {
-        let stream_length_without_seal = if self.base_fee_per_gas.is_some() {
-            14
-        } else {
-            13
-        };
+        let stream_length_without_seal = 13
+            + self.base_fee_per_gas.is_some() as usize
+            + self.withdrawals_root.is_some() as usize
+            + self.blob_gas_used.is_some() as usize
+            + self.excess_blob_gas.is_some() as usize
+            + self.parent_beacon_block_root.is_some() as usize;
 
         if let Seal::With = with_seal {
             s.begin_list(stream_length_without_seal + self.seal.len());
@@ -374,9 +438,96 @@ impl Header {
         if self.base_fee_per_gas.is_some() {
             s.append(&self.base_fee_per_gas.unwrap());
         }
+
+        if let Some(ref withdrawals_root) = self.withdrawals_root {
+            s.append(withdrawals_root);
+        }
+
+        if let Some(blob_gas_used) = self.blob_gas_used {
+            s.append(&blob_gas_used);
+        }
+
+        if let Some(excess_blob_gas) = self.excess_blob_gas {
+            s.append(&excess_blob_gas);
+        }
+
+        if let Some(ref parent_beacon_block_root) = self.parent_beacon_block_root {
+            s.append(parent_beacon_block_root);
+        }
+    }
+}
+
+/// Blocks from which the optional trailing header fields are present, needed to tell where the
+/// seal ends when decoding a header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderTransitions {
+    /// Base fee (EIP-1559).
+    pub eip1559_transition: BlockNumber,
+    /// Withdrawals root (EIP-4895).
+    pub eip4895_transition: BlockNumber,
+    /// Blob gas fields (EIP-4844) and parent beacon block root (EIP-4788).
+    pub eip4844_transition: BlockNumber,
+}
+
+impl Default for HeaderTransitions {
+    /// None of the optional fields is ever present.
+    fn default() -> Self {
+        HeaderTransitions {
+            eip1559_transition: BlockNumber::max_value(),
+            eip4895_transition: BlockNumber::max_value(),
+            eip4844_transition: BlockNumber::max_value(),
+        }
+    }
+}
+
+impl HeaderTransitions {
+    /// Number of fields following the seal in the header of given block.
+    pub fn trailing_fields(&self, number: BlockNumber) -> usize {
+        if number < self.eip1559_transition {
+            return 0;
+        }
+        1 + self.has_withdrawals_root(number) as usize
+            + 3 * self.has_blob_gas_fields(number) as usize
+    }
+
+    /// Whether the header of given block carries the withdrawals root.
+    pub fn has_withdrawals_root(&self, number: BlockNumber) -> bool {
+        number >= self.eip1559_transition && number >= self.eip4895_transition
+    }
+
+    /// Whether the header of given block carries the blob gas fields and parent beacon block root.
+    pub fn has_blob_gas_fields(&self, number: BlockNumber) -> bool {
+        number >= self.eip1559_transition && number >= self.eip4844_transition
     }
 }
 
+/// Minimal blob base fee (EIP-4844).
+pub const MIN_BLOB_BASE_FEE: u64 = 1;
+/// Controls the maximal rate of change of the blob base fee (EIP-4844).
+pub const BLOB_BASE_FEE_UPDATE_FRACTION: u64 = 3_338_477;
+
+/// Computes the blob base fee for given excess blob gas, as defined by EIP-4844.
+pub fn calc_blob_base_fee(excess_blob_gas: u64) -> U256 {
+    fake_exponential(
+        MIN_BLOB_BASE_FEE.into(),
+        excess_blob_gas.into(),
+        BLOB_BASE_FEE_UPDATE_FRACTION.into(),
+    )
+}
+
+/// Approximates `factor * e ** (numerator / denominator)` using Taylor expansion.
+fn fake_exponential(factor: U256, numerator: U256, denominator: U256) -> U256 {
+    let mut i = U256::one();
+    let mut output = U256::zero();
+    let mut numerator_accum = factor * denominator;
+    while !numerator_accum.is_zero() {
+        output = output.saturating_add(numerator_accum);
+        numerator_accum = numerator_accum.saturating_mul(numerator) / (denominator * i);
+        i = i + 1;
+    }
+    output / denominator
+}
+
 /// Alter value of given field, reset memoised hash if changed.
 fn change_field<T>(hash: &mut Option<H256>, field: &mut T, value: T)
 where
@@ -389,7 +540,7 @@ where
 }
 
 impl Header {
-    pub fn decode_rlp(r: &Rlp, eip1559_transition: BlockNumber) -> Result<Self, DecoderError> {
+    pub fn decode_rlp(r: &Rlp, transitions: HeaderTransitions) -> Result<Self, DecoderError> {
         let mut blockheader = Header {
             parent_hash: r.val_at(0)?,
             uncles_hash: r.val_at(1)?,
@@ -407,17 +558,33 @@ impl Header {
             seal: vec![],
             hash: keccak(r.as_raw()).into(),
             base_fee_per_gas: None,
+            withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
         };
 
-        if blockheader.number >= eip1559_transition {
-            for i in 13..r.item_count()? - 1 {
-                blockheader.seal.push(r.at(i)?.as_raw().to_vec())
-            }
-            blockheader.base_fee_per_gas = Some(r.val_at(r.item_count()? - 1)?);
-        } else {
-            for i in 13..r.item_count()? {
-                blockheader.seal.push(r.at(i)?.as_raw().to_vec())
-            }
+        let count = r.item_count()?;
+        let seal_end = count
+            .checked_sub(transitions.trailing_fields(blockheader.number))
+            .filter(|end| *end >= 13)
+            .ok_or(DecoderError::RlpIncorrectListLen)?;
+        for i in 13..seal_end {
+            blockheader.seal.push(r.at(i)?.as_raw().to_vec())
+        }
+
+        let number = blockheader.number;
+        if number >= transitions.eip1559_transition {
+            blockheader.base_fee_per_gas = Some(r.val_at(seal_end)?);
+        }
+        if transitions.has_withdrawals_root(number) {
+            blockheader.withdrawals_root = Some(r.val_at(seal_end + 1)?);
+        }
+        if transitions.has_blob_gas_fields(number) {
+            let blob_fields = count - 3;
+            blockheader.blob_gas_used = Some(r.val_at(blob_fields)?);
+            blockheader.excess_blob_gas = Some(r.val_at(blob_fields + 1)?);
+            blockheader.parent_beacon_block_root = Some(r.val_at(blob_fields + 2)?);
         }
 
         Ok(blockheader)
@@ -425,7 +592,7 @@ impl Header {
 
     pub fn decode_rlp_list(
         rlp: &Rlp,
-        eip1559_transition: BlockNumber,
+        transitions: HeaderTransitions,
     ) -> Result<Vec<Self>, DecoderError> {
         if !rlp.is_list() {
             // at least one byte needs to be present
@@ -433,7 +600,7 @@ impl Header {
         }
         let mut output = Vec::with_capacity(rlp.item_count()?);
         for h in rlp.iter() {
-            output.push(Self::decode_rlp(&h, eip1559_transition)?);
+            output.push(Self::decode_rlp(&h, transitions)?);
         }
         Ok(output)
     }
@@ -454,13 +621,18 @@ impl ExtendedHeader {
 
 #[cfg(test)]
 mod tests {
-    use crate::BlockNumber;
-
-    use super::Header;
-    use ethereum_types::U256;
+    use super::{calc_blob_base_fee, Header, HeaderTransitions};
+    use ethereum_types::{H256, U256};
     use rlp::{self, Rlp};
     use rustc_hex::FromHex;
 
+    fn london() -> HeaderTransitions {
+        HeaderTransitions {
+            eip1559_transition: 0,
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn test_header_seal_fields() {
         // that's rlp of block header created with ethash engine.
@@ -476,7 +648,7 @@ mod tests {
 
         let rlp = Rlp::new(&header_rlp);
         let header: Header =
-            Header::decode_rlp(&rlp, BlockNumber::max_value()).expect("error decoding header");
+            Header::decode_rlp(&rlp, HeaderTransitions::default()).expect("error decoding header");
         let seal_fields = header.seal.clone();
         assert_eq!(seal_fields.len(), 2);
         assert_eq!(seal_fields[0], mix_hash);
@@ -492,8 +664,7 @@ mod tests {
     fn test_header_seal_fields_after_1559() {
         let header_rlp = "f901faa0d405da4e66f1445d455195229624e133f5baafe72b5cf7b3c36c12c8146e98b7a01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347948888f1f195afa192cfee860698584c030f4c9db1a05fb2b4bfdef7b314451cb138a534d225c922fc0e5fbe25e451142732c3e25c25a088d2ec6b9860aae1a2c3b299f72b6a5d70d7f7ba4722c78f2c49ba96273c2158a007c6fdfa8eea7e86b81f5b0fc0f78f90cc19f4aa60d323151e0cac660199e9a1b90100000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000008302008011832fefba82524d84568e932a80a0a0349d8c3df71f1a48a9df7d03fd5f14aeee7d91332c009ecaff0a71ead405bd88ab4e252a7e8c2a2364".from_hex().unwrap();
         let rlp = Rlp::new(&header_rlp);
-        let mut header: Header =
-            Header::decode_rlp(&rlp, BlockNumber::default()).expect("error decoding header");
+        let mut header: Header = Header::decode_rlp(&rlp, london()).expect("error decoding header");
 
         assert_eq!(header.seal().len(), 2);
         assert_eq!(header.base_fee().unwrap(), U256::from(100));
@@ -518,7 +689,7 @@ mod tests {
         let rlp = Rlp::new(&header_rlp);
 
         let header: Header =
-            Header::decode_rlp(&rlp, BlockNumber::max_value()).expect("error decoding header");
+            Header::decode_rlp(&rlp, HeaderTransitions::default()).expect("error decoding header");
         let encoded_header = rlp::encode(&header);
 
         assert_eq!(header_rlp, encoded_header);
@@ -530,8 +701,7 @@ mod tests {
         let header_rlp = "f901faa0d405da4e66f1445d455195229624e133f5baafe72b5cf7b3c36c12c8146e98b7a01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347948888f1f195afa192cfee860698584c030f4c9db1a05fb2b4bfdef7b314451cb138a534d225c922fc0e5fbe25e451142732c3e25c25a088d2ec6b9860aae1a2c3b299f72b6a5d70d7f7ba4722c78f2c49ba96273c2158a007c6fdfa8eea7e86b81f5b0fc0f78f90cc19f4aa60d323151e0cac660199e9a1b90100000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000008302008011832fefba82524d84568e932a80a0a0349d8c3df71f1a48a9df7d03fd5f14aeee7d91332c009ecaff0a71ead405bd88ab4e252a7e8c2a2364".from_hex().unwrap();
         let rlp = Rlp::new(&header_rlp);
 
-        let header: Header =
-            Header::decode_rlp(&rlp, BlockNumber::default()).expect("error decoding header");
+        let header: Header = Header::decode_rlp(&rlp, london()).expect("error decoding header");
         let encoded_header = rlp::encode(&header);
 
         assert_eq!(header_rlp, encoded_header);
@@ -545,10 +715,97 @@ mod tests {
         let rlp = Rlp::new(&header_rlp);
 
         // This should fail decoding timestamp
-        let header: Result<Header, _> = Header::decode_rlp(&rlp, BlockNumber::max_value());
+        let header: Result<Header, _> = Header::decode_rlp(&rlp, HeaderTransitions::default());
         assert_eq!(header.unwrap_err(), rlp::DecoderError::RlpIsTooBig);
     }
 
+    #[test]
+    fn decode_and_encode_header_with_withdrawals_root() {
+        let mut header = Header::new();
+        header.set_number(10);
+        header.set_seal(vec![
+            rlp::encode(&H256::from_low_u64_be(1)),
+            rlp::encode(&7u64),
+        ]);
+        header.set_base_fee(Some(U256::from(7)));
+        header.set_withdrawals_root(Some(H256::from_low_u64_be(0xab)));
+
+        let encoded_header = rlp::encode(&header);
+        let shanghai = HeaderTransitions {
+            eip4895_transition: 10,
+            ..london()
+        };
+        let decoded: Header = Header::decode_rlp(&Rlp::new(&encoded_header), shanghai)
+            .expect("error decoding header");
+
+        assert_eq!(decoded.seal().len(), 2);
+        assert_eq!(decoded.base_fee(), Some(U256::from(7)));
+        assert_eq!(
+            decoded.withdrawals_root(),
+            Some(&H256::from_low_u64_be(0xab))
+        );
+        assert_eq!(decoded, header);
+
+        // before the transition the withdrawals root is taken for a seal field
+        let london: Header = Header::decode_rlp(&Rlp::new(&encoded_header), london())
+            .expect("error decoding header");
+        assert_eq!(london.seal().len(), 3);
+        assert_eq!(london.withdrawals_root(), None);
+        assert_eq!(decoded.hash(), header.hash());
+    }
+
+    #[test]
+    fn decode_and_encode_header_with_blob_gas_fields() {
+        let mut header = Header::new();
+        header.set_number(10);
+        header.set_seal(vec![
+            rlp::encode(&H256::from_low_u64_be(1)),
+            rlp::encode(&7u64),
+        ]);
+        header.set_base_fee(Some(U256::from(7)));
+        header.set_withdrawals_root(Some(H256::from_low_u64_be(0xab)));
+        header.set_blob_gas_used(Some(0x40000));
+        header.set_excess_blob_gas(Some(0));
+        header.set_parent_beacon_block_root(Some(H256::from_low_u64_be(0xcd)));
+
+        let encoded_header = rlp::encode(&header);
+        let cancun = HeaderTransitions {
+            eip1559_transition: 0,
+            eip4895_transition: 0,
+            eip4844_transition: 10,
+        };
+        let decoded: Header =
+            Header::decode_rlp(&Rlp::new(&encoded_header), cancun).expect("error decoding header");
+
+        assert_eq!(decoded.seal().len(), 2);
+        assert_eq!(decoded.base_fee(), Some(U256::from(7)));
+        assert_eq!(
+            decoded.withdrawals_root(),
+            Some(&H256::from_low_u64_be(0xab))
+        );
+        assert_eq!(decoded.blob_gas_used(), Some(0x40000));
+        assert_eq!(decoded.excess_blob_gas(), Some(0));
+        assert_eq!(
+            decoded.parent_beacon_block_root(),
+            Some(&H256::from_low_u64_be(0xcd))
+        );
+        assert_eq!(decoded, header);
+        assert_eq!(decoded.hash(), header.hash());
+    }
+
+    #[test]
+    fn blob_base_fee_follows_excess_blob_gas() {
+        assert_eq!(calc_blob_base_fee(0), U256::from(1));
+        assert_eq!(calc_blob_base_fee(2_314_057), U256::from(1));
+        assert_eq!(calc_blob_base_fee(2_314_058), U256::from(2));
+        assert_eq!(calc_blob_base_fee(10 * 1024 * 1024), U256::from(23));
+
+        let mut header = Header::new();
+        assert_eq!(header.blob_base_fee(), None);
+        header.set_excess_blob_gas(Some(10 * 1024 * 1024));
+        assert_eq!(header.blob_base_fee(), Some(U256::from(23)));
+    }
+
     #[test]
     fn hash_should_be_different() {
         let header_legacy = Header::new();
@@ -113,6 +113,7 @@ pub enum TypedReceipt {
     Legacy(LegacyReceipt),
     AccessList(LegacyReceipt),
     EIP1559Transaction(LegacyReceipt),
+    BlobTransaction(LegacyReceipt),
 }
 
 impl TypedReceipt {
@@ -120,6 +121,7 @@ impl TypedReceipt {
     pub fn new(type_id: TypedTxId, legacy_receipt: LegacyReceipt) -> Self {
         //curently we are using same receipt for both legacy and typed transaction
         match type_id {
+            TypedTxId::BlobTransaction => Self::BlobTransaction(legacy_receipt),
             TypedTxId::EIP1559Transaction => Self::EIP1559Transaction(legacy_receipt),
             TypedTxId::AccessList => Self::AccessList(legacy_receipt),
             TypedTxId::Legacy => Self::Legacy(legacy_receipt),
@@ -131,6 +133,7 @@ impl TypedReceipt {
             Self::Legacy(_) => TypedTxId::Legacy,
             Self::AccessList(_) => TypedTxId::AccessList,
             Self::EIP1559Transaction(_) => TypedTxId::EIP1559Transaction,
+            Self::BlobTransaction(_) => TypedTxId::BlobTransaction,
         }
     }
 
@@ -139,6 +142,7 @@ impl TypedReceipt {
             Self::Legacy(receipt) => receipt,
             Self::AccessList(receipt) => receipt,
             Self::EIP1559Transaction(receipt) => receipt,
+            Self::BlobTransaction(receipt) => receipt,
         }
     }
 
@@ -147,6 +151,7 @@ impl TypedReceipt {
             Self::Legacy(receipt) => receipt,
             Self::AccessList(receipt) => receipt,
             Self::EIP1559Transaction(receipt) => receipt,
+            Self::BlobTransaction(receipt) => receipt,
         }
     }
 
@@ -161,6 +166,10 @@ impl TypedReceipt {
         }
         //other transaction types
         match id.unwrap() {
+            TypedTxId::BlobTransaction => {
+                let rlp = Rlp::new(&tx[1..]);
+                Ok(Self::BlobTransaction(LegacyReceipt::decode(&rlp)?))
+            }
             TypedTxId::EIP1559Transaction => {
                 let rlp = Rlp::new(&tx[1..]);
                 Ok(Self::EIP1559Transaction(LegacyReceipt::decode(&rlp)?))
@@ -207,6 +216,11 @@ impl TypedReceipt {
                 receipt.rlp_append(&mut rlps);
                 s.append(&[&[TypedTxId::EIP1559Transaction as u8], rlps.as_raw()].concat());
             }
+            Self::BlobTransaction(receipt) => {
+                let mut rlps = RlpStream::new();
+                receipt.rlp_append(&mut rlps);
+                s.append(&[&[TypedTxId::BlobTransaction as u8], rlps.as_raw()].concat());
+            }
         }
     }
 
@@ -234,6 +248,11 @@ impl TypedReceipt {
                 receipt.rlp_append(&mut rlps);
                 [&[TypedTxId::EIP1559Transaction as u8], rlps.as_raw()].concat()
             }
+            Self::BlobTransaction(receipt) => {
+                let mut rlps = RlpStream::new();
+                receipt.rlp_append(&mut rlps);
+                [&[TypedTxId::BlobTransaction as u8], rlps.as_raw()].concat()
+            }
         }
     }
 }
@@ -81,6 +81,10 @@ pub enum Error {
     RecipientBanned,
     /// Contract creation code is banned.
     CodeBanned,
+    /// Sender submits transactions faster than allowed.
+    SenderRateLimited,
+    /// Transaction recipient receives transactions faster than allowed.
+    RecipientRateLimited,
     /// Invalid chain ID given.
     InvalidChainId,
     /// Not enough permissions given by permission contract.
@@ -95,6 +99,8 @@ pub enum Error {
     TransactionTypeNotEnabled,
     /// Transaction sender is not an EOA (see EIP-3607)
     SenderIsNotEOA,
+    /// Blob transaction has no or malformed blob versioned hashes (see EIP-4844)
+    InvalidBlobVersionedHashes,
 }
 
 impl From<crypto::publickey::Error> for Error {
@@ -146,6 +152,8 @@ impl fmt::Display for Error {
             SenderBanned => "Sender is temporarily banned.".into(),
             RecipientBanned => "Recipient is temporarily banned.".into(),
             CodeBanned => "Contract code is temporarily banned.".into(),
+            SenderRateLimited => "Sender exceeded transaction rate limit.".into(),
+            RecipientRateLimited => "Recipient exceeded transaction rate limit.".into(),
             InvalidChainId => "Transaction of this chain ID is not allowed on this chain.".into(),
             InvalidSignature(ref err) => format!("Transaction has invalid signature: {}.", err),
             NotAllowed => {
@@ -157,6 +165,9 @@ impl fmt::Display for Error {
                 format!("Transaction type is not enabled for current block")
             }
             SenderIsNotEOA => "Transaction sender is not an EOA (see EIP-3607)".into(),
+            InvalidBlobVersionedHashes => {
+                "Blob transaction has invalid blob versioned hashes (see EIP-4844)".into()
+            }
         };
 
         f.write_fmt(format_args!("Transaction error ({})", msg))
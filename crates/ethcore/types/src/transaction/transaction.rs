@@ -17,7 +17,10 @@
 //! Transaction data structure.
 
 use crate::{
-    crypto::publickey::{self, public_to_address, recover, Public, Secret, Signature},
+    crypto::{
+        digest,
+        publickey::{self, public_to_address, recover, Public, Secret, Signature},
+    },
     hash::keccak,
     transaction::error,
 };
@@ -44,6 +47,12 @@ pub const SYSTEM_ADDRESS: Address = H160([
     0xff, 0xff, 0xff, 0xfe,
 ]);
 
+/// Version byte of the blob versioned hashes of KZG commitments (EIP-4844).
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// Blob gas consumed by a single blob (EIP-4844).
+pub const GAS_PER_BLOB: u64 = 1 << 17;
+
 /// Transaction action type.
 #[derive(Debug, Clone, PartialEq, Eq, MallocSizeOf)]
 pub enum Action {
@@ -501,12 +510,209 @@ impl EIP1559TransactionTx {
     }
 }
 
+#[derive(Debug, Clone, Eq, PartialEq, MallocSizeOf)]
+pub struct BlobTransactionTx {
+    pub transaction: EIP1559TransactionTx,
+    pub max_fee_per_blob_gas: U256,
+    pub blob_versioned_hashes: Vec<H256>,
+}
+
+impl BlobTransactionTx {
+    pub fn tx_type(&self) -> TypedTxId {
+        TypedTxId::BlobTransaction
+    }
+
+    pub fn tx(&self) -> &Transaction {
+        self.transaction.tx()
+    }
+
+    pub fn tx_mut(&mut self) -> &mut Transaction {
+        self.transaction.tx_mut()
+    }
+
+    /// Blob gas consumed by this transaction.
+    pub fn blob_gas(&self) -> u64 {
+        GAS_PER_BLOB * self.blob_versioned_hashes.len() as u64
+    }
+
+    // decode bytes by this payload spec: rlp([3, [chainId, nonce, maxPriorityFeePerGas, maxFeePerGas(gasPrice), gasLimit, to, value, data, access_list, maxFeePerBlobGas, blobVersionedHashes, senderV, senderR, senderS]])
+    // or by its network form rlp([3, [[chainId, ..., senderS], blobs, commitments, proofs]]). Blobs and proofs of
+    // the network form are not verified, commitments are only checked against the versioned hashes and the whole
+    // sidecar is dropped afterwards.
+    pub fn decode(tx: &[u8]) -> Result<UnverifiedTransaction, DecoderError> {
+        let tx_rlp = &Rlp::new(tx);
+
+        if tx_rlp.item_count()? == 4 && tx_rlp.at(0)?.is_list() {
+            let transaction = Self::decode_payload(&tx_rlp.at(0)?)?;
+            let hashes = match transaction.unsigned {
+                TypedTransaction::BlobTransaction(ref tx) => &tx.blob_versioned_hashes,
+                _ => unreachable!("decode_payload returns only blob transactions; qed"),
+            };
+            if tx_rlp.at(1)?.item_count()? != hashes.len()
+                || tx_rlp.at(3)?.item_count()? != hashes.len()
+            {
+                return Err(DecoderError::Custom("Invalid blob sidecar length"));
+            }
+            let commitments: Vec<Bytes> = tx_rlp.list_at(2)?;
+            if commitments.len() != hashes.len() {
+                return Err(DecoderError::Custom("Invalid blob sidecar length"));
+            }
+            for (commitment, hash) in commitments.iter().zip(hashes.iter()) {
+                if kzg_to_versioned_hash(commitment) != *hash {
+                    return Err(DecoderError::Custom("Blob commitment mismatch"));
+                }
+            }
+            return Ok(transaction);
+        }
+
+        Self::decode_payload(tx_rlp)
+    }
+
+    fn decode_payload(tx_rlp: &Rlp) -> Result<UnverifiedTransaction, DecoderError> {
+        // we need to have 14 items in this list
+        if tx_rlp.item_count()? != 14 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+
+        let chain_id = Some(tx_rlp.val_at(0)?);
+
+        let max_priority_fee_per_gas = tx_rlp.val_at(2)?;
+
+        let tx = Transaction {
+            nonce: tx_rlp.val_at(1)?,
+            gas_price: tx_rlp.val_at(3)?, //taken from max_fee_per_gas
+            gas: tx_rlp.val_at(4)?,
+            action: tx_rlp.val_at(5)?,
+            value: tx_rlp.val_at(6)?,
+            data: tx_rlp.val_at(7)?,
+        };
+
+        // blob transactions can't create contracts
+        if tx.action == Action::Create {
+            return Err(DecoderError::Custom("Blob transaction without recipient"));
+        }
+
+        // access list we get from here
+        let accl_rlp = tx_rlp.at(8)?;
+
+        // access_list pattern: [[{20 bytes}, [{32 bytes}...]]...]
+        let mut accl: AccessList = Vec::new();
+
+        for i in 0..accl_rlp.item_count()? {
+            let accounts = accl_rlp.at(i)?;
+
+            // check if there is list of 2 items
+            if accounts.item_count()? != 2 {
+                return Err(DecoderError::Custom("Unknown access list length"));
+            }
+            accl.push((accounts.val_at(0)?, accounts.list_at(1)?));
+        }
+
+        let max_fee_per_blob_gas = tx_rlp.val_at(9)?;
+        let blob_versioned_hashes = tx_rlp.list_at(10)?;
+
+        // we get signature part from here
+        let signature = SignatureComponents {
+            standard_v: tx_rlp.val_at(11)?,
+            r: tx_rlp.val_at(12)?,
+            s: tx_rlp.val_at(13)?,
+        };
+
+        // and here we create UnverifiedTransaction and calculate its hash
+        Ok(UnverifiedTransaction::new(
+            TypedTransaction::BlobTransaction(BlobTransactionTx {
+                transaction: EIP1559TransactionTx {
+                    transaction: AccessListTx::new(tx, accl),
+                    max_priority_fee_per_gas,
+                },
+                max_fee_per_blob_gas,
+                blob_versioned_hashes,
+            }),
+            chain_id,
+            signature,
+            H256::zero(),
+        )
+        .compute_hash())
+    }
+
+    fn encode_payload(
+        &self,
+        chain_id: Option<u64>,
+        signature: Option<&SignatureComponents>,
+    ) -> RlpStream {
+        let mut stream = RlpStream::new();
+
+        let list_size = if signature.is_some() { 14 } else { 11 };
+        stream.begin_list(list_size);
+
+        // append chain_id. from EIP-2930: chainId is defined to be an integer of arbitrary size.
+        stream.append(&(if let Some(n) = chain_id { n } else { 0 }));
+
+        stream.append(&self.tx().nonce);
+        stream.append(&self.transaction.max_priority_fee_per_gas);
+        stream.append(&self.tx().gas_price);
+        stream.append(&self.tx().gas);
+        stream.append(&self.tx().action);
+        stream.append(&self.tx().value);
+        stream.append(&self.tx().data);
+
+        // access list
+        stream.begin_list(self.transaction.transaction.access_list.len());
+        for access in self.transaction.transaction.access_list.iter() {
+            stream.begin_list(2);
+            stream.append(&access.0);
+            stream.begin_list(access.1.len());
+            for storage_key in access.1.iter() {
+                stream.append(storage_key);
+            }
+        }
+
+        stream.append(&self.max_fee_per_blob_gas);
+        stream.append_list(&self.blob_versioned_hashes);
+
+        // append signature if any
+        if let Some(signature) = signature {
+            signature.rlp_append(&mut stream);
+        }
+        stream
+    }
+
+    // encode by this payload spec: 0x03 | rlp([3, [chainId, nonce, maxPriorityFeePerGas, maxFeePerGas(gasPrice), gasLimit, to, value, data, access_list, maxFeePerBlobGas, blobVersionedHashes, senderV, senderR, senderS]])
+    pub fn encode(
+        &self,
+        chain_id: Option<u64>,
+        signature: Option<&SignatureComponents>,
+    ) -> Vec<u8> {
+        let stream = self.encode_payload(chain_id, signature);
+        // make as vector of bytes
+        [&[TypedTxId::BlobTransaction as u8], stream.as_raw()].concat()
+    }
+
+    pub fn rlp_append(
+        &self,
+        rlp: &mut RlpStream,
+        chain_id: Option<u64>,
+        signature: &SignatureComponents,
+    ) {
+        rlp.append(&self.encode(chain_id, Some(signature)));
+    }
+}
+
+/// Computes the versioned hash of a KZG commitment, as defined by EIP-4844.
+pub fn kzg_to_versioned_hash(commitment: &[u8]) -> H256 {
+    let mut hash = H256::from_slice(&*digest::sha256(commitment));
+    hash.0[0] = VERSIONED_HASH_VERSION_KZG;
+    hash
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, MallocSizeOf)]
 pub enum TypedTransaction {
     Legacy(Transaction),      // old legacy RLP encoded transaction
     AccessList(AccessListTx), // EIP-2930 Transaction with a list of addresses and storage keys that the transaction plans to access.
     // Accesses outside the list are possible, but become more expensive.
     EIP1559Transaction(EIP1559TransactionTx),
+    // EIP-4844 Transaction that carries versioned hashes of blobs, which are not part of the execution payload.
+    BlobTransaction(BlobTransactionTx),
 }
 
 impl TypedTransaction {
@@ -515,6 +721,7 @@ impl TypedTransaction {
             Self::Legacy(_) => TypedTxId::Legacy,
             Self::AccessList(_) => TypedTxId::AccessList,
             Self::EIP1559Transaction(_) => TypedTxId::EIP1559Transaction,
+            Self::BlobTransaction(_) => TypedTxId::BlobTransaction,
         }
     }
 
@@ -524,6 +731,7 @@ impl TypedTransaction {
             Self::Legacy(tx) => tx.encode(chain_id, None),
             Self::AccessList(tx) => tx.encode(chain_id, None),
             Self::EIP1559Transaction(tx) => tx.encode(chain_id, None),
+            Self::BlobTransaction(tx) => tx.encode(chain_id, None),
         })
     }
 
@@ -613,6 +821,7 @@ impl TypedTransaction {
             Self::Legacy(tx) => tx,
             Self::AccessList(ocl) => ocl.tx(),
             Self::EIP1559Transaction(tx) => tx.tx(),
+            Self::BlobTransaction(tx) => tx.tx(),
         }
     }
 
@@ -621,12 +830,14 @@ impl TypedTransaction {
             Self::Legacy(tx) => tx,
             Self::AccessList(ocl) => ocl.tx_mut(),
             Self::EIP1559Transaction(tx) => tx.tx_mut(),
+            Self::BlobTransaction(tx) => tx.tx_mut(),
         }
     }
 
     pub fn access_list(&self) -> Option<&AccessList> {
         match self {
             Self::EIP1559Transaction(tx) => Some(&tx.transaction.access_list),
+            Self::BlobTransaction(tx) => Some(&tx.transaction.transaction.access_list),
             Self::AccessList(tx) => Some(&tx.access_list),
             Self::Legacy(_) => None,
         }
@@ -634,9 +845,9 @@ impl TypedTransaction {
 
     pub fn effective_gas_price(&self, block_base_fee: Option<U256>) -> U256 {
         match self {
-            Self::EIP1559Transaction(tx) => {
-                let (v2, overflow) = tx
-                    .max_priority_fee_per_gas
+            Self::EIP1559Transaction(_) | Self::BlobTransaction(_) => {
+                let (v2, overflow) = self
+                    .max_priority_fee_per_gas()
                     .overflowing_add(block_base_fee.unwrap_or_default());
                 if overflow {
                     self.tx().gas_price
@@ -652,6 +863,7 @@ impl TypedTransaction {
     pub fn max_priority_fee_per_gas(&self) -> U256 {
         match self {
             Self::EIP1559Transaction(tx) => tx.max_priority_fee_per_gas,
+            Self::BlobTransaction(tx) => tx.transaction.max_priority_fee_per_gas,
             Self::AccessList(tx) => tx.tx().gas_price,
             Self::Legacy(tx) => tx.gas_price,
         }
@@ -668,11 +880,38 @@ impl TypedTransaction {
             Self::EIP1559Transaction(tx) => {
                 tx.tx().gas_price.is_zero() && tx.max_priority_fee_per_gas.is_zero()
             }
+            Self::BlobTransaction(tx) => {
+                tx.tx().gas_price.is_zero() && tx.transaction.max_priority_fee_per_gas.is_zero()
+            }
             Self::AccessList(tx) => tx.tx().gas_price.is_zero(),
             Self::Legacy(tx) => tx.gas_price.is_zero(),
         }
     }
 
+    /// Versioned hashes of the blobs carried by an EIP-4844 transaction.
+    pub fn blob_versioned_hashes(&self) -> Option<&Vec<H256>> {
+        match self {
+            Self::BlobTransaction(tx) => Some(&tx.blob_versioned_hashes),
+            _ => None,
+        }
+    }
+
+    /// Max fee per blob gas of an EIP-4844 transaction.
+    pub fn max_fee_per_blob_gas(&self) -> Option<U256> {
+        match self {
+            Self::BlobTransaction(tx) => Some(tx.max_fee_per_blob_gas),
+            _ => None,
+        }
+    }
+
+    /// Blob gas consumed by the transaction, zero for non-blob transactions.
+    pub fn blob_gas(&self) -> u64 {
+        match self {
+            Self::BlobTransaction(tx) => tx.blob_gas(),
+            _ => 0,
+        }
+    }
+
     fn decode_new(tx: &[u8]) -> Result<UnverifiedTransaction, DecoderError> {
         if tx.is_empty() {
             // at least one byte needs to be present
@@ -684,6 +923,7 @@ impl TypedTransaction {
         }
         // other transaction types
         match id.unwrap() {
+            TypedTxId::BlobTransaction => BlobTransactionTx::decode(&tx[1..]),
             TypedTxId::EIP1559Transaction => EIP1559TransactionTx::decode(&tx[1..]),
             TypedTxId::AccessList => AccessListTx::decode(&tx[1..]),
             TypedTxId::Legacy => return Err(DecoderError::Custom("Unknown transaction legacy")),
@@ -736,6 +976,7 @@ impl TypedTransaction {
             Self::Legacy(tx) => tx.rlp_append(s, chain_id, signature),
             Self::AccessList(opt) => opt.rlp_append(s, chain_id, signature),
             Self::EIP1559Transaction(tx) => tx.rlp_append(s, chain_id, signature),
+            Self::BlobTransaction(tx) => tx.rlp_append(s, chain_id, signature),
         }
     }
 
@@ -752,6 +993,7 @@ impl TypedTransaction {
             Self::Legacy(tx) => tx.encode(chain_id, signature),
             Self::AccessList(opt) => opt.encode(chain_id, signature),
             Self::EIP1559Transaction(tx) => tx.encode(chain_id, signature),
+            Self::BlobTransaction(tx) => tx.encode(chain_id, signature),
         }
     }
 }
@@ -922,6 +1164,11 @@ impl UnverifiedTransaction {
             (Some(n), Some(m)) if n == m => {}
             _ => return Err(error::Error::InvalidChainId),
         };
+        if let Some(hashes) = self.blob_versioned_hashes() {
+            if hashes.is_empty() || hashes.iter().any(|h| h[0] != VERSIONED_HASH_VERSION_KZG) {
+                return Err(error::Error::InvalidBlobVersionedHashes);
+            }
+        }
         Ok(())
     }
 }
@@ -1273,6 +1520,112 @@ mod tests {
         }
     }
 
+    fn blob_tx(action: Action, blob_versioned_hashes: Vec<H256>) -> TypedTransaction {
+        TypedTransaction::BlobTransaction(BlobTransactionTx {
+            transaction: EIP1559TransactionTx {
+                transaction: AccessListTx::new(
+                    Transaction {
+                        action,
+                        nonce: U256::from(42),
+                        gas_price: U256::from(3000),
+                        gas: U256::from(50_000),
+                        value: U256::from(1),
+                        data: b"Hello!".to_vec(),
+                    },
+                    vec![(H160::from_low_u64_be(10), vec![H256::from_low_u64_be(102)])],
+                ),
+                max_priority_fee_per_gas: U256::from(100),
+            },
+            max_fee_per_blob_gas: U256::from(7),
+            blob_versioned_hashes,
+        })
+    }
+
+    #[test]
+    fn should_encode_decode_blob_tx() {
+        use self::publickey::{Generator, Random};
+        let key = Random.generate();
+        let hashes = vec![
+            kzg_to_versioned_hash(&[1u8; 48]),
+            kzg_to_versioned_hash(&[2u8; 48]),
+        ];
+        let t = blob_tx(Action::Call(H160::from_low_u64_be(5)), hashes.clone())
+            .sign(&key.secret(), Some(69));
+        let encoded = t.encode();
+        assert_eq!(encoded[0], TypedTxId::BlobTransaction as u8);
+
+        let t_new =
+            TypedTransaction::decode(&encoded).expect("Error on UnverifiedTransaction decoder");
+        assert_eq!(t_new.unsigned, t.unsigned);
+        assert_eq!(t_new.hash(), t.hash());
+        assert_eq!(t_new.blob_versioned_hashes(), Some(&hashes));
+        assert_eq!(t_new.max_fee_per_blob_gas(), Some(U256::from(7)));
+        assert_eq!(t_new.blob_gas(), 2 * GAS_PER_BLOB);
+        assert!(t_new.verify_basic(true, Some(69)).is_ok());
+    }
+
+    #[test]
+    fn should_decode_blob_tx_network_form() {
+        use self::publickey::{Generator, Random};
+        let key = Random.generate();
+        let commitment = vec![1u8; 48];
+        let t = blob_tx(
+            Action::Call(H160::from_low_u64_be(5)),
+            vec![kzg_to_versioned_hash(&commitment)],
+        )
+        .sign(&key.secret(), Some(69));
+        let encoded = t.encode();
+
+        let wrap = |commitment: &Vec<u8>| {
+            let mut stream = RlpStream::new_list(4);
+            stream.append_raw(&encoded[1..], 1);
+            stream.begin_list(1).append(&vec![0u8; 32]);
+            stream.begin_list(1).append(commitment);
+            stream.begin_list(1).append(&vec![0u8; 48]);
+            [&[TypedTxId::BlobTransaction as u8], stream.as_raw()].concat()
+        };
+
+        let t_new = TypedTransaction::decode(&wrap(&commitment))
+            .expect("Error on UnverifiedTransaction decoder");
+        assert_eq!(t_new.unsigned, t.unsigned);
+        assert_eq!(t_new.hash(), t.hash());
+
+        assert_eq!(
+            TypedTransaction::decode(&wrap(&vec![2u8; 48])),
+            Err(DecoderError::Custom("Blob commitment mismatch"))
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_blob_tx() {
+        use self::publickey::{Generator, Random};
+        let key = Random.generate();
+        let hashes = vec![kzg_to_versioned_hash(&[1u8; 48])];
+
+        let create = blob_tx(Action::Create, hashes).sign(&key.secret(), Some(69));
+        assert_eq!(
+            TypedTransaction::decode(&create.encode()),
+            Err(DecoderError::Custom("Blob transaction without recipient"))
+        );
+
+        let no_blobs =
+            blob_tx(Action::Call(H160::from_low_u64_be(5)), vec![]).sign(&key.secret(), Some(69));
+        assert_eq!(
+            no_blobs.verify_basic(true, Some(69)),
+            Err(error::Error::InvalidBlobVersionedHashes)
+        );
+
+        let wrong_version = blob_tx(
+            Action::Call(H160::from_low_u64_be(5)),
+            vec![H256::from_low_u64_be(1)],
+        )
+        .sign(&key.secret(), Some(69));
+        assert_eq!(
+            wrong_version.verify_basic(true, Some(69)),
+            Err(error::Error::InvalidBlobVersionedHashes)
+        );
+    }
+
     #[test]
     fn should_decode_access_list_in_rlp() {
         use rustc_hex::FromHex;
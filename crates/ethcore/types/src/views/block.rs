@@ -16,15 +16,14 @@
 
 //! View onto block rlp.
 
-use crate::BlockNumber;
-
 use super::ViewRlp;
 use crate::{
     bytes::Bytes,
     hash::keccak,
-    header::Header,
+    header::{Header, HeaderTransitions},
     transaction::{LocalizedTransaction, TypedTransaction, UnverifiedTransaction},
     views::{HeaderView, TypedTransactionView},
+    withdrawal::Withdrawal,
 };
 
 use ethereum_types::H256;
@@ -66,8 +65,8 @@ impl<'a> BlockView<'a> {
     }
 
     /// Create new Header object from header rlp.
-    pub fn header(&self, eip1559_transition: BlockNumber) -> Header {
-        Header::decode_rlp(&self.rlp.at(0).rlp, eip1559_transition).unwrap_or_else(|e| {
+    pub fn header(&self, transitions: HeaderTransitions) -> Header {
+        Header::decode_rlp(&self.rlp.at(0).rlp, transitions).unwrap_or_else(|e| {
             panic!(
                 "block header, view rlp is trusted and should be valid: {:?}",
                 e
@@ -172,8 +171,8 @@ impl<'a> BlockView<'a> {
     }
 
     /// Return list of uncles of given block.
-    pub fn uncles(&self, eip1559_transition: BlockNumber) -> Vec<Header> {
-        Header::decode_rlp_list(&self.rlp.at(2).rlp, eip1559_transition).unwrap_or_else(|e| {
+    pub fn uncles(&self, transitions: HeaderTransitions) -> Vec<Header> {
+        Header::decode_rlp_list(&self.rlp.at(2).rlp, transitions).unwrap_or_else(|e| {
             panic!(
                 "block uncles, view rlp is trusted and should be valid: {:?}",
                 e
@@ -200,9 +199,9 @@ impl<'a> BlockView<'a> {
     }
 
     /// Return nth uncle.
-    pub fn uncle_at(&self, index: usize, eip1559_transition: BlockNumber) -> Option<Header> {
+    pub fn uncle_at(&self, index: usize, transitions: HeaderTransitions) -> Option<Header> {
         self.uncles_rlp().iter().nth(index).map(|rlp| {
-            Header::decode_rlp(&rlp.rlp, eip1559_transition).unwrap_or_else(|e| {
+            Header::decode_rlp(&rlp.rlp, transitions).unwrap_or_else(|e| {
                 panic!(
                     "block uncle_at, view rlp is trusted and should be valid.{:?}",
                     e
@@ -218,6 +217,24 @@ impl<'a> BlockView<'a> {
             .nth(index)
             .map(|rlp| rlp.as_raw().to_vec())
     }
+
+    /// Returns raw rlp for the withdrawals in the given block, if it has any (EIP-4895).
+    pub fn withdrawals_rlp(&self) -> Option<ViewRlp<'a>> {
+        if self.rlp.item_count() > 3 {
+            Some(self.rlp.at(3))
+        } else {
+            None
+        }
+    }
+
+    /// Return list of withdrawals of given block.
+    pub fn withdrawals(&self) -> Option<Vec<Withdrawal>> {
+        if self.rlp.item_count() > 3 {
+            Some(self.rlp.list_at(3))
+        } else {
+            None
+        }
+    }
 }
 
 #[cfg(test)]
@@ -20,9 +20,10 @@ use super::ViewRlp;
 use crate::{
     bytes::Bytes,
     hash::keccak,
-    header::Header,
+    header::{Header, HeaderTransitions},
     transaction::{LocalizedTransaction, TypedTransaction, UnverifiedTransaction},
     views::{HeaderView, TypedTransactionView},
+    withdrawal::Withdrawal,
     BlockNumber,
 };
 use ethereum_types::H256;
@@ -147,8 +148,8 @@ impl<'a> BodyView<'a> {
     }
 
     /// Return list of uncles of given block.
-    pub fn uncles(&self, eip1559_transition: BlockNumber) -> Vec<Header> {
-        Header::decode_rlp_list(&self.rlp.at(1).rlp, eip1559_transition).unwrap_or_else(|e| {
+    pub fn uncles(&self, transitions: HeaderTransitions) -> Vec<Header> {
+        Header::decode_rlp_list(&self.rlp.at(1).rlp, transitions).unwrap_or_else(|e| {
             panic!(
                 "block uncles, view rlp is trusted and should be valid: {:?}",
                 e
@@ -175,9 +176,9 @@ impl<'a> BodyView<'a> {
     }
 
     /// Return nth uncle.
-    pub fn uncle_at(&self, index: usize, eip1559_transition: BlockNumber) -> Option<Header> {
+    pub fn uncle_at(&self, index: usize, transitions: HeaderTransitions) -> Option<Header> {
         self.uncles_rlp().iter().nth(index).map(|rlp| {
-            Header::decode_rlp(&rlp.rlp, eip1559_transition).unwrap_or_else(|e| {
+            Header::decode_rlp(&rlp.rlp, transitions).unwrap_or_else(|e| {
                 panic!(
                     "block uncle_at, view rlp is trusted and should be valid.{:?}",
                     e
@@ -193,6 +194,24 @@ impl<'a> BodyView<'a> {
             .nth(index)
             .map(|rlp| rlp.as_raw().to_vec())
     }
+
+    /// Returns raw rlp for the withdrawals in the given block, if it has any (EIP-4895).
+    pub fn withdrawals_rlp(&self) -> Option<ViewRlp<'a>> {
+        if self.rlp.item_count() > 2 {
+            Some(self.rlp.at(2))
+        } else {
+            None
+        }
+    }
+
+    /// Return list of withdrawals of given block.
+    pub fn withdrawals(&self) -> Option<Vec<Withdrawal>> {
+        if self.rlp.item_count() > 2 {
+            Some(self.rlp.list_at(2))
+        } else {
+            None
+        }
+    }
 }
 
 #[cfg(test)]
@@ -17,7 +17,7 @@
 //! View onto block header rlp
 
 use super::ViewRlp;
-use crate::{bytes::Bytes, hash::keccak, BlockNumber};
+use crate::{bytes::Bytes, hash::keccak, header::HeaderTransitions, BlockNumber};
 use ethereum_types::{Address, Bloom, H256, U256};
 use rlp::{self};
 
@@ -123,15 +123,10 @@ impl<'a> HeaderView<'a> {
     }
 
     /// Returns a vector of post-RLP-encoded seal fields.
-    /// If eip1559 is true, seal contains also base_fee_per_gas. Otherwise, it contains only seal fields.
-    pub fn seal(&self, eip1559: bool) -> Vec<Bytes> {
-        let last_seal_index = if eip1559 {
-            self.rlp.item_count() - 1
-        } else {
-            self.rlp.item_count()
-        };
+    /// Fields that follow the seal under the given fork transitions are not included.
+    pub fn seal(&self, transitions: HeaderTransitions) -> Vec<Bytes> {
         let mut seal = vec![];
-        for i in 13..last_seal_index {
+        for i in 13..self.fee_fields_start(transitions) {
             seal.push(self.rlp.at(i).as_raw().to_vec());
         }
         seal
@@ -139,17 +134,68 @@ impl<'a> HeaderView<'a> {
 
     /// Returns block base fee. Should be called only for EIP1559 headers.
     /// If called for non EIP1559 header, returns garbage
-    pub fn base_fee(&self) -> U256 {
-        match self.rlp.rlp.val_at::<U256>(self.rlp.item_count() - 1) {
+    pub fn base_fee(&self, transitions: HeaderTransitions) -> U256 {
+        match self
+            .rlp
+            .rlp
+            .val_at::<U256>(self.fee_fields_start(transitions))
+        {
             Ok(base_fee) => base_fee,
             Err(_) => Default::default(),
         }
     }
 
+    /// Returns the withdrawals root of EIP4895 headers.
+    pub fn withdrawals_root(&self, transitions: HeaderTransitions) -> Option<H256> {
+        if transitions.has_withdrawals_root(self.number()) {
+            Some(self.rlp.val_at(self.fee_fields_start(transitions) + 1))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the blob gas used of EIP4844 headers.
+    pub fn blob_gas_used(&self, transitions: HeaderTransitions) -> Option<u64> {
+        if transitions.has_blob_gas_fields(self.number()) {
+            Some(self.rlp.val_at(self.rlp.item_count() - 3))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the excess blob gas of EIP4844 headers.
+    pub fn excess_blob_gas(&self, transitions: HeaderTransitions) -> Option<u64> {
+        if transitions.has_blob_gas_fields(self.number()) {
+            Some(self.rlp.val_at(self.rlp.item_count() - 2))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the parent beacon block root of EIP4788 headers.
+    pub fn parent_beacon_block_root(&self, transitions: HeaderTransitions) -> Option<H256> {
+        if transitions.has_blob_gas_fields(self.number()) {
+            Some(self.rlp.val_at(self.rlp.item_count() - 1))
+        } else {
+            None
+        }
+    }
+
+    /// Index of the first field following the seal.
+    fn fee_fields_start(&self, transitions: HeaderTransitions) -> usize {
+        self.rlp
+            .item_count()
+            .saturating_sub(transitions.trailing_fields(self.number()))
+            .max(13)
+    }
+
     /// Returns a vector of seal fields (RLP-decoded).
-    /// If eip1559 is true, seal contains also base_fee_per_gas. Otherwise, it contains only seal fields.
-    pub fn decode_seal(&self, eip1559: bool) -> Result<Vec<Bytes>, rlp::DecoderError> {
-        let seal = self.seal(eip1559);
+    /// Fields that follow the seal under the given fork transitions are not included.
+    pub fn decode_seal(
+        &self,
+        transitions: HeaderTransitions,
+    ) -> Result<Vec<Bytes>, rlp::DecoderError> {
+        let seal = self.seal(transitions);
         seal.into_iter()
             .map(|s| rlp::Rlp::new(&s).data().map(|x| x.to_vec()))
             .collect()
@@ -159,6 +205,7 @@ impl<'a> HeaderView<'a> {
 #[cfg(test)]
 mod tests {
     use super::HeaderView;
+    use crate::header::HeaderTransitions;
     use ethereum_types::{Bloom, H160, H256};
     use rustc_hex::FromHex;
     use std::str::FromStr;
@@ -214,6 +261,9 @@ mod tests {
         assert_eq!(view.gas_used(), 0x524d.into());
         assert_eq!(view.timestamp(), 0x56_8e_93_2a);
         assert_eq!(view.extra_data(), vec![] as Vec<u8>);
-        assert_eq!(view.seal(false), vec![mix_hash, nonce]);
+        assert_eq!(
+            view.seal(HeaderTransitions::default()),
+            vec![mix_hash, nonce]
+        );
     }
 }
@@ -30,6 +30,8 @@ use rlp::Rlp;
 
 /// View onto transaction rlp. Assumption is this is part of block.
 /// Typed Transaction View. It handles raw bytes to search for particular field.
+/// Blob tx:
+/// 3 | [chainId, nonce, maxPriorityFeePerGas, maxFeePerGas(gasPrice), gasLimit, to, value, data, access_list, maxFeePerBlobGas, blobVersionedHashes, senderV, senderR, senderS]
 /// EIP1559 tx:
 /// 2 | [chainId, nonce, maxPriorityFeePerGas, maxFeePerGas(gasPrice), gasLimit, to, value, data, access_list, senderV, senderR, senderS]
 /// Access tx:
@@ -86,9 +88,11 @@ impl<'a> TypedTransactionView<'a> {
             TypedTxId::AccessList => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
                 .rlp
                 .val_at(0),
-            TypedTxId::EIP1559Transaction => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
-                .rlp
-                .val_at(0),
+            TypedTxId::EIP1559Transaction | TypedTxId::BlobTransaction => {
+                view!(Self, &self.rlp.rlp.data().unwrap()[1..])
+                    .rlp
+                    .val_at(0)
+            }
         }
     }
 
@@ -99,9 +103,11 @@ impl<'a> TypedTransactionView<'a> {
             TypedTxId::AccessList => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
                 .rlp
                 .val_at(1),
-            TypedTxId::EIP1559Transaction => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
-                .rlp
-                .val_at(1),
+            TypedTxId::EIP1559Transaction | TypedTxId::BlobTransaction => {
+                view!(Self, &self.rlp.rlp.data().unwrap()[1..])
+                    .rlp
+                    .val_at(1)
+            }
         }
     }
 
@@ -112,9 +118,11 @@ impl<'a> TypedTransactionView<'a> {
             TypedTxId::AccessList => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
                 .rlp
                 .val_at(2),
-            TypedTxId::EIP1559Transaction => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
-                .rlp
-                .val_at(3),
+            TypedTxId::EIP1559Transaction | TypedTxId::BlobTransaction => {
+                view!(Self, &self.rlp.rlp.data().unwrap()[1..])
+                    .rlp
+                    .val_at(3)
+            }
         }
     }
 
@@ -123,7 +131,7 @@ impl<'a> TypedTransactionView<'a> {
         match self.transaction_type {
             TypedTxId::Legacy => self.gas_price(),
             TypedTxId::AccessList => self.gas_price(),
-            TypedTxId::EIP1559Transaction => {
+            TypedTxId::EIP1559Transaction | TypedTxId::BlobTransaction => {
                 let max_priority_fee_per_gas: U256 =
                     view!(Self, &self.rlp.rlp.data().unwrap()[1..])
                         .rlp
@@ -146,7 +154,7 @@ impl<'a> TypedTransactionView<'a> {
             TypedTxId::AccessList => self
                 .gas_price()
                 .saturating_sub(block_base_fee.unwrap_or_default()),
-            TypedTxId::EIP1559Transaction => {
+            TypedTxId::EIP1559Transaction | TypedTxId::BlobTransaction => {
                 let max_priority_fee_per_gas: U256 =
                     view!(Self, &self.rlp.rlp.data().unwrap()[1..])
                         .rlp
@@ -167,9 +175,11 @@ impl<'a> TypedTransactionView<'a> {
             TypedTxId::AccessList => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
                 .rlp
                 .val_at(3),
-            TypedTxId::EIP1559Transaction => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
-                .rlp
-                .val_at(4),
+            TypedTxId::EIP1559Transaction | TypedTxId::BlobTransaction => {
+                view!(Self, &self.rlp.rlp.data().unwrap()[1..])
+                    .rlp
+                    .val_at(4)
+            }
         }
     }
 
@@ -180,9 +190,11 @@ impl<'a> TypedTransactionView<'a> {
             TypedTxId::AccessList => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
                 .rlp
                 .val_at(5),
-            TypedTxId::EIP1559Transaction => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
-                .rlp
-                .val_at(6),
+            TypedTxId::EIP1559Transaction | TypedTxId::BlobTransaction => {
+                view!(Self, &self.rlp.rlp.data().unwrap()[1..])
+                    .rlp
+                    .val_at(6)
+            }
         }
     }
 
@@ -193,9 +205,11 @@ impl<'a> TypedTransactionView<'a> {
             TypedTxId::AccessList => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
                 .rlp
                 .val_at(6),
-            TypedTxId::EIP1559Transaction => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
-                .rlp
-                .val_at(7),
+            TypedTxId::EIP1559Transaction | TypedTxId::BlobTransaction => {
+                view!(Self, &self.rlp.rlp.data().unwrap()[1..])
+                    .rlp
+                    .val_at(7)
+            }
         }
     }
 
@@ -227,6 +241,18 @@ impl<'a> TypedTransactionView<'a> {
                     chain_id,
                 )
             }
+            TypedTxId::BlobTransaction => {
+                let chain_id = match self.chain_id() {
+                    0 => None,
+                    n => Some(n),
+                };
+                signature::add_chain_replay_protection(
+                    view!(Self, &self.rlp.rlp.data().unwrap()[1..])
+                        .rlp
+                        .val_at(11),
+                    chain_id,
+                )
+            }
         };
         r as u8
     }
@@ -240,6 +266,9 @@ impl<'a> TypedTransactionView<'a> {
             TypedTxId::EIP1559Transaction => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
                 .rlp
                 .val_at(9),
+            TypedTxId::BlobTransaction => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
+                .rlp
+                .val_at(11),
         }
     }
 
@@ -253,6 +282,9 @@ impl<'a> TypedTransactionView<'a> {
             TypedTxId::EIP1559Transaction => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
                 .rlp
                 .val_at(10),
+            TypedTxId::BlobTransaction => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
+                .rlp
+                .val_at(12),
         }
     }
 
@@ -266,6 +298,9 @@ impl<'a> TypedTransactionView<'a> {
             TypedTxId::EIP1559Transaction => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
                 .rlp
                 .val_at(11),
+            TypedTxId::BlobTransaction => view!(Self, &self.rlp.rlp.data().unwrap()[1..])
+                .rlp
+                .val_at(13),
         }
     }
 }
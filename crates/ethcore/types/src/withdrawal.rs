@@ -0,0 +1,67 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Beacon chain withdrawal as introduced by EIP-4895.
+
+use ethereum_types::{Address, U256};
+use parity_util_mem::MallocSizeOf;
+
+/// Number of wei in one gwei, the unit withdrawal amounts are expressed in.
+const GWEI_TO_WEI: u64 = 1_000_000_000;
+
+/// Withdrawal of validator funds pushed from the beacon chain into the execution layer.
+#[derive(Debug, Clone, PartialEq, Eq, RlpEncodable, RlpDecodable, MallocSizeOf)]
+pub struct Withdrawal {
+    /// Monotonically increasing index of the withdrawal.
+    pub index: u64,
+    /// Index of the validator the withdrawal belongs to.
+    pub validator_index: u64,
+    /// Recipient of the withdrawn funds.
+    pub address: Address,
+    /// Withdrawn amount in gwei.
+    pub amount: u64,
+}
+
+impl Withdrawal {
+    /// Amount to credit to the recipient, in wei.
+    pub fn amount_in_wei(&self) -> U256 {
+        U256::from(self.amount) * U256::from(GWEI_TO_WEI)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Withdrawal;
+    use ethereum_types::{Address, U256};
+
+    #[test]
+    fn encode_and_decode_withdrawal() {
+        let withdrawal = Withdrawal {
+            index: 7,
+            validator_index: 12,
+            address: Address::from_low_u64_be(0xff),
+            amount: 32_000_000_000,
+        };
+        let encoded = rlp::encode(&withdrawal);
+        let decoded: Withdrawal = rlp::decode(&encoded).unwrap();
+
+        assert_eq!(decoded, withdrawal);
+        assert_eq!(
+            decoded.amount_in_wei(),
+            U256::from(32_000_000_000u64) * U256::from(1_000_000_000u64)
+        );
+    }
+}
@@ -40,6 +40,8 @@ pub enum ForkSpec {
     Berlin,
     London,
     BerlinToLondonAt5,
+    Shanghai,
+    Cancun,
 }
 
 /// Spec deserialization.
@@ -61,6 +63,8 @@ pub struct Spec {
     pub accounts: State,
     /// Boot nodes.
     pub nodes: Option<Vec<String>>,
+    /// EIP-1459 DNS node list URLs.
+    pub dns_discovery: Option<Vec<String>>,
 }
 
 impl Spec {
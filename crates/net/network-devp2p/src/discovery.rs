@@ -15,6 +15,8 @@
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
 use crypto::publickey::{recover, sign, KeyPair, Secret};
+use discv5::Discv5;
+use enr::NodeRecord;
 use ethereum_types::{H256, H520};
 use hash::keccak;
 use lru_cache::LruCache;
@@ -23,6 +25,7 @@ use node_table::*;
 use parity_bytes::Bytes;
 use rlp::{Rlp, RlpStream};
 use std::{
+    cmp::min,
     collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
     default::Default,
     net::SocketAddr,
@@ -42,9 +45,12 @@ const PACKET_PING: u8 = 1;
 const PACKET_PONG: u8 = 2;
 const PACKET_FIND_NODE: u8 = 3;
 const PACKET_NEIGHBOURS: u8 = 4;
+const PACKET_ENR_REQUEST: u8 = 5;
+const PACKET_ENR_RESPONSE: u8 = 6;
 
 const PING_TIMEOUT: Duration = Duration::from_millis(500);
 const FIND_NODE_TIMEOUT: Duration = Duration::from_secs(2);
+const ENR_REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
 const EXPIRY_TIME: Duration = Duration::from_secs(20);
 const MAX_NODES_PING: usize = 32; // Max nodes to add/ping at once
 const REQUEST_BACKOFF: [Duration; 4] = [
@@ -58,6 +64,8 @@ const NODE_LAST_SEEN_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
 
 const OBSERVED_NODES_MAX_SIZE: usize = 10_000;
 
+const RECORD_SEQS_MAX_SIZE: usize = 10_000;
+
 #[derive(Clone, Debug)]
 pub struct NodeEntry {
     pub id: NodeId,
@@ -156,6 +164,13 @@ impl NodeBucket {
     }
 }
 
+struct EnrRequest {
+    // Time when the request was sent
+    sent_at: Instant,
+    // The hash of the request packet, echoed in the response
+    hash: H256,
+}
+
 pub struct Datagram {
     pub payload: Bytes,
     pub address: SocketAddr,
@@ -183,15 +198,30 @@ pub struct Discovery<'a> {
     adding_nodes: Vec<NodeEntry>,
     ip_filter: IpFilter,
     request_backoff: &'a [Duration],
+    // Local node record, served over ENRRequest and discovery v5
+    record: NodeRecord,
+    // Sequence numbers of the node records we already have
+    record_seqs: LruCache<NodeId, u64>,
+    in_flight_enr_requests: HashMap<NodeId, EnrRequest>,
+    discv5: Option<Discv5>,
 }
 
 pub struct TableUpdates {
     pub added: HashMap<NodeId, NodeEntry>,
     pub removed: HashSet<NodeId>,
+    pub records: HashMap<NodeId, NodeRecord>,
 }
 
 impl<'a> Discovery<'a> {
     pub fn new(key: &KeyPair, public: NodeEndpoint, ip_filter: IpFilter) -> Discovery<'static> {
+        // Records are not persisted, so start from the current time to keep sequence numbers
+        // increasing across restarts.
+        let record_seq = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let record = NodeRecord::new(key.secret(), record_seq, &public, None)
+            .expect("a record without eth entry is below the size limit; qed");
         Discovery {
             id: *key.public(),
             id_hash: keccak(key.public()),
@@ -210,6 +240,50 @@ impl<'a> Discovery<'a> {
             adding_nodes: Vec::new(),
             ip_filter,
             request_backoff: &REQUEST_BACKOFF,
+            record,
+            record_seqs: LruCache::new(RECORD_SEQS_MAX_SIZE),
+            in_flight_enr_requests: HashMap::new(),
+            discv5: None,
+        }
+    }
+
+    /// Run discovery v5 next to v4. Packets that are not valid v4 packets are handed over to it.
+    pub fn enable_v5(&mut self) {
+        if self.discv5.is_none() {
+            self.discv5 = Some(Discv5::new(
+                self.id,
+                self.secret.clone(),
+                self.record.clone(),
+                self.ip_filter.clone(),
+            ));
+        }
+    }
+
+    /// Local node record.
+    pub fn record(&self) -> &NodeRecord {
+        &self.record
+    }
+
+    /// Set the `eth` entry of the local node record. The record is signed again with a higher
+    /// sequence number if the entry changed.
+    pub fn set_eth_entry(&mut self, eth_entry: &[u8]) {
+        if self.record.eth_entry() == Some(eth_entry) {
+            return;
+        }
+        match NodeRecord::new(
+            &self.secret,
+            self.record.seq() + 1,
+            &self.public_endpoint,
+            Some(eth_entry),
+        ) {
+            Ok(record) => {
+                trace!(target: "discovery", "Updated local node record, seq={}", record.seq());
+                if let Some(ref mut discv5) = self.discv5 {
+                    discv5.set_record(record.clone());
+                }
+                self.record = record;
+            }
+            Err(e) => warn!(target: "discovery", "Error updating local node record: {:?}", e),
         }
     }
 
@@ -278,6 +352,9 @@ impl<'a> Discovery<'a> {
 
             let mut added = HashMap::with_capacity(1);
             added.insert(node_entry.id, node_entry.clone());
+            if let Some(ref mut discv5) = self.discv5 {
+                discv5.add_node(&node_entry);
+            }
 
 			let node_to_ping = {
 				let bucket = &mut self.node_buckets[bucket_distance];
@@ -293,7 +370,7 @@ impl<'a> Discovery<'a> {
 			};
 
             if node_entry.endpoint.is_valid_sync_node() {
-				Some(TableUpdates { added, removed: HashSet::new() })
+				Some(TableUpdates { added, removed: HashSet::new(), records: HashMap::new() })
 			} else {
 				None
 			}
@@ -401,11 +478,12 @@ impl<'a> Discovery<'a> {
     }
 
     fn ping(&mut self, node: &NodeEntry, reason: PingReason) -> Result<(), Error> {
-        let mut rlp = RlpStream::new_list(4);
+        let mut rlp = RlpStream::new_list(5);
         rlp.append(&PROTOCOL_VERSION);
         self.public_endpoint.to_rlp_list(&mut rlp);
         node.endpoint.to_rlp_list(&mut rlp);
         append_expiration(&mut rlp);
+        rlp.append(&self.record.seq());
         let old_parity_hash = keccak(rlp.as_raw());
         let hash = self.send_packet(PACKET_PING, &node.endpoint.udp_address(), &rlp.drain())?;
 
@@ -443,6 +521,39 @@ impl<'a> Discovery<'a> {
         Ok(())
     }
 
+    /// Request the node record if the node advertised a newer one than we have.
+    fn request_record_if_newer(&mut self, node: &NodeEntry, enr_seq: Option<u64>) {
+        let enr_seq = match enr_seq {
+            Some(seq) => seq,
+            None => return,
+        };
+        let known_seq = self.record_seqs.get_mut(&node.id).map(|seq| *seq);
+        if known_seq.map_or(false, |seq| seq >= enr_seq)
+            || self.in_flight_enr_requests.contains_key(&node.id)
+        {
+            return;
+        }
+        let mut rlp = RlpStream::new_list(1);
+        append_expiration(&mut rlp);
+        match self.send_packet(
+            PACKET_ENR_REQUEST,
+            &node.endpoint.udp_address(),
+            &rlp.drain(),
+        ) {
+            Ok(hash) => {
+                self.in_flight_enr_requests.insert(
+                    node.id,
+                    EnrRequest {
+                        sent_at: Instant::now(),
+                        hash,
+                    },
+                );
+                trace!(target: "discovery", "Sent ENRRequest to {:?}", &node.endpoint);
+            }
+            Err(e) => warn!(target: "discovery", "Error sending ENRRequest packet: {:?}", e),
+        }
+    }
+
     fn send_packet(
         &mut self,
         packet_id: u8,
@@ -516,13 +627,13 @@ impl<'a> Discovery<'a> {
         from: SocketAddr,
     ) -> Result<Option<TableUpdates>, Error> {
         // validate packet
-        if packet.len() < 32 + 65 + 4 + 1 {
-            return Err(ErrorKind::BadProtocol.into());
-        }
-
-        let hash_signed = keccak(&packet[32..]);
-        if hash_signed[..] != packet[0..32] {
-            return Err(ErrorKind::BadProtocol.into());
+        let hash_signed = keccak(&packet[min(32, packet.len())..]);
+        if packet.len() < 32 + 65 + 4 + 1 || hash_signed[..] != packet[0..32] {
+            // Not a v4 packet, discovery v5 shares the socket
+            return match self.discv5 {
+                Some(ref mut discv5) => discv5.on_packet(packet, from),
+                None => Err(ErrorKind::BadProtocol.into()),
+            };
         }
 
         let signed = &packet[(32 + 65)..];
@@ -535,6 +646,10 @@ impl<'a> Discovery<'a> {
             PACKET_PONG => self.on_pong(&rlp, &node_id, &from),
             PACKET_FIND_NODE => self.on_find_node(&rlp, &node_id, &from),
             PACKET_NEIGHBOURS => self.on_neighbours(&rlp, &node_id, &from),
+            PACKET_ENR_REQUEST => {
+                self.on_enr_request(&rlp, &node_id, &from, hash_signed.as_bytes())
+            }
+            PACKET_ENR_RESPONSE => self.on_enr_response(&rlp, &node_id, &from),
             _ => {
                 debug!(target: "discovery", "Unknown UDP packet: {}", packet_id);
                 Ok(None)
@@ -582,7 +697,7 @@ impl<'a> Discovery<'a> {
         let ping_to = NodeEndpoint::from_rlp(&rlp.at(2)?)?;
         let timestamp: u64 = rlp.val_at(3)?;
         self.check_timestamp(timestamp)?;
-        let mut response = RlpStream::new_list(3);
+        let mut response = RlpStream::new_list(4);
         let pong_to = NodeEndpoint {
             address: from.clone(),
             udp_port: ping_from.udp_port,
@@ -597,6 +712,7 @@ impl<'a> Discovery<'a> {
 
         response.append(&echo_hash);
         append_expiration(&mut response);
+        response.append(&self.record.seq());
         self.send_packet(PACKET_PONG, from, &response.drain())?;
 
         let entry = NodeEntry {
@@ -624,6 +740,12 @@ impl<'a> Discovery<'a> {
         let echo_hash: H256 = rlp.val_at(1)?;
         let timestamp: u64 = rlp.val_at(2)?;
         self.check_timestamp(timestamp)?;
+        // EIP-868: the sequence number of the node record is optional
+        let enr_seq: Option<u64> = if rlp.item_count()? > 3 {
+            Some(rlp.val_at(3)?)
+        } else {
+            None
+        };
 
         let expected_node = match self.in_flight_pings.entry(*node_id) {
             Entry::Occupied(entry) => {
@@ -673,6 +795,7 @@ impl<'a> Discovery<'a> {
                 }
                 Ok(None)
             } else {
+                self.request_record_if_newer(&node, enr_seq);
                 Ok(self.update_node(node))
             }
         } else {
@@ -712,6 +835,73 @@ impl<'a> Discovery<'a> {
         Ok(None)
     }
 
+    fn on_enr_request(
+        &mut self,
+        rlp: &Rlp,
+        node_id: &NodeId,
+        from: &SocketAddr,
+        request_hash: &[u8],
+    ) -> Result<Option<TableUpdates>, Error> {
+        trace!(target: "discovery", "Got ENRRequest from {:?}", &from);
+        let timestamp: u64 = rlp.val_at(0)?;
+        self.check_timestamp(timestamp)?;
+
+        let node = NodeEntry {
+            id: *node_id,
+            endpoint: NodeEndpoint {
+                address: *from,
+                udp_port: from.port(),
+            },
+        };
+        match self.check_validity(&node) {
+            NodeValidity::ValidNode(_) => {
+                let mut response = RlpStream::new_list(2);
+                response.append(&request_hash);
+                response.append(&self.record);
+                self.send_packet(PACKET_ENR_RESPONSE, from, &response.drain())?;
+                trace!(target: "discovery", "Sent ENRResponse to {:?}", &from);
+            }
+            // Only nodes which answered our pings get the record
+            _ => {
+                debug!(target: "discovery", "Ignoring ENRRequest from unverified node {:?}", &from)
+            }
+        }
+        Ok(None)
+    }
+
+    fn on_enr_response(
+        &mut self,
+        rlp: &Rlp,
+        node_id: &NodeId,
+        from: &SocketAddr,
+    ) -> Result<Option<TableUpdates>, Error> {
+        trace!(target: "discovery", "Got ENRResponse from {:?}", &from);
+        let request_hash: H256 = rlp.val_at(0)?;
+        match self.in_flight_enr_requests.get(node_id) {
+            Some(request) if request.hash == request_hash => {}
+            _ => {
+                debug!(target: "discovery", "Got unexpected ENRResponse from {:?}", &from);
+                return Ok(None);
+            }
+        }
+        self.in_flight_enr_requests.remove(node_id);
+
+        let record = NodeRecord::from_rlp(rlp.at(1)?.as_raw())?;
+        if record.id() != *node_id {
+            debug!(target: "discovery", "Got ENRResponse from {:?} with a record of another node", &from);
+            return Err(ErrorKind::InvalidNodeId.into());
+        }
+        self.record_seqs.insert(*node_id, record.seq());
+
+        let mut records = HashMap::with_capacity(1);
+        records.insert(*node_id, record);
+        Ok(Some(TableUpdates {
+            added: HashMap::new(),
+            removed: HashSet::new(),
+            records,
+        }))
+    }
+
     fn check_validity(&mut self, node: &NodeEntry) -> NodeValidity {
         let id_hash = keccak(node.id);
         let dist = match Discovery::distance(&self.id_hash, &id_hash) {
@@ -861,6 +1051,14 @@ impl<'a> Discovery<'a> {
 				true
 			}
 		});
+        self.in_flight_enr_requests.retain(|node_id, enr_request| {
+            if time.duration_since(enr_request.sent_at) > ENR_REQUEST_TIMEOUT {
+                debug!(target: "discovery", "Removing expired ENR request for node_id={:#x}", node_id);
+                false
+            } else {
+                true
+            }
+        });
         for node_id in nodes_to_expire {
             self.expire_node_request(node_id);
         }
@@ -896,6 +1094,9 @@ impl<'a> Discovery<'a> {
     pub fn round(&mut self) {
         self.check_expired(Instant::now());
         self.update_new_nodes();
+        if let Some(ref mut discv5) = self.discv5 {
+            discv5.round();
+        }
 
         if self.discovery_round.is_some() {
             self.discover();
@@ -910,14 +1111,20 @@ impl<'a> Discovery<'a> {
         if self.discovery_round.is_none() {
             self.start();
         }
+        if let Some(ref mut discv5) = self.discv5 {
+            discv5.refresh();
+        }
     }
 
     pub fn any_sends_queued(&self) -> bool {
-        !self.send_queue.is_empty()
+        !self.send_queue.is_empty() || self.discv5.as_ref().map_or(false, |d| d.any_sends_queued())
     }
 
     pub fn dequeue_send(&mut self) -> Option<Datagram> {
-        self.send_queue.pop_front()
+        match self.send_queue.pop_front() {
+            Some(datagram) => Some(datagram),
+            None => self.discv5.as_mut().and_then(|d| d.dequeue_send()),
+        }
     }
 
     pub fn requeue_send(&mut self, datagram: Datagram) {
@@ -1503,4 +1710,65 @@ mod tests {
             panic!("Expected no changes to discovery1's table for unexpected pong");
         }
     }
+
+    #[test]
+    fn test_enr_request() {
+        let key1 = Random.generate();
+        let key2 = Random.generate();
+        let ep1 = NodeEndpoint {
+            address: SocketAddr::from_str("127.0.0.1:40347").unwrap(),
+            udp_port: 40347,
+        };
+        let ep2 = NodeEndpoint {
+            address: SocketAddr::from_str("127.0.0.1:40348").unwrap(),
+            udp_port: 40348,
+        };
+        let mut discovery1 = Discovery::new(&key1, ep1.clone(), IpFilter::default());
+        let mut discovery2 = Discovery::new(&key2, ep2.clone(), IpFilter::default());
+        let node2 = NodeEntry {
+            id: discovery2.id,
+            endpoint: ep2.clone(),
+        };
+
+        discovery1.ping(&node2, PingReason::Default).unwrap();
+        let ping = discovery1.dequeue_send().unwrap();
+        discovery2.on_packet(&ping.payload, ep1.address).unwrap();
+        let pong = discovery2.dequeue_send().unwrap();
+        let ping_back = discovery2.dequeue_send().unwrap();
+
+        // The pong carries the record sequence number, so the record gets requested
+        discovery1.on_packet(&pong.payload, ep2.address).unwrap();
+        let enr_request = discovery1.dequeue_send().unwrap();
+        assert_eq!(enr_request.payload[32 + 65], PACKET_ENR_REQUEST);
+
+        // Records are only served to nodes that answered a ping
+        discovery1
+            .on_packet(&ping_back.payload, ep2.address)
+            .unwrap();
+        let pong_back = discovery1.dequeue_send().unwrap();
+        discovery2
+            .on_packet(&pong_back.payload, ep1.address)
+            .unwrap();
+        while discovery2.dequeue_send().is_some() {}
+
+        discovery2
+            .on_packet(&enr_request.payload, ep1.address)
+            .unwrap();
+        let enr_response = discovery2.dequeue_send().unwrap();
+        assert_eq!(enr_response.payload[32 + 65], PACKET_ENR_RESPONSE);
+
+        let table_updates = discovery1
+            .on_packet(&enr_response.payload, ep2.address)
+            .unwrap()
+            .expect("record is reported to the node table");
+        assert_eq!(
+            table_updates.records.get(&discovery2.id),
+            Some(discovery2.record())
+        );
+
+        // A known record is not requested again
+        let seq = discovery2.record().seq();
+        discovery1.request_record_if_newer(&node2, Some(seq));
+        assert!(!discovery1.any_sends_queued());
+    }
 }
@@ -0,0 +1,1156 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Topic-less discovery v5.1.
+//!
+//! Runs next to discovery v4 on the same UDP socket and feeds the same node table: sessions are
+//! established with the WHOAREYOU handshake and nodes are found with FINDNODE/NODES lookups.
+//! Only records of nodes reachable over TCP are reported to the node table.
+
+use crypto::publickey::{Generator, Random, Secret};
+use discovery::{Datagram, NodeEntry, TableUpdates, MAX_DATAGRAM_SIZE};
+use enr::NodeRecord;
+use ethereum_types::H256;
+use hash::keccak;
+use lru_cache::LruCache;
+use network::{Error, ErrorKind, IpFilter};
+use node_table::NodeId;
+use parity_bytes::Bytes;
+use rand;
+use rcrypto::{
+    aead::{AeadDecryptor, AeadEncryptor},
+    aes::{self, KeySize},
+    aes_gcm::AesGcm,
+    digest::Digest,
+    hkdf::{hkdf_expand, hkdf_extract},
+    sha2::Sha256,
+    symmetriccipher::SynchronousStreamCipher,
+};
+use rlp::{self, Rlp, RlpStream};
+use secp256k1::{Message, PublicKey, Secp256k1, SecretKey, Signature};
+use std::{
+    cmp::min,
+    collections::{HashMap, HashSet, VecDeque},
+    net::{IpAddr, SocketAddr},
+    time::{Duration, Instant},
+};
+
+const PROTOCOL_ID: &[u8] = b"discv5";
+const PROTOCOL_VERSION: u16 = 1;
+
+const FLAG_MESSAGE: u8 = 0;
+const FLAG_WHOAREYOU: u8 = 1;
+const FLAG_HANDSHAKE: u8 = 2;
+
+const MASKING_IV_SIZE: usize = 16;
+const NONCE_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+const ID_NONCE_SIZE: usize = 16;
+// protocol-id || version || flag || nonce || authdata-size
+const STATIC_HEADER_SIZE: usize = 6 + 2 + 1 + NONCE_SIZE + 2;
+const MIN_PACKET_SIZE: usize = MASKING_IV_SIZE + STATIC_HEADER_SIZE + 24;
+
+const MESSAGE_PING: u8 = 1;
+const MESSAGE_PONG: u8 = 2;
+const MESSAGE_FIND_NODE: u8 = 3;
+const MESSAGE_NODES: u8 = 4;
+
+const KEY_AGREEMENT_INFO: &[u8] = b"discovery v5 key agreement";
+const ID_SIGNATURE_TEXT: &[u8] = b"discovery v5 identity proof";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+const MAX_SESSIONS: usize = 1024;
+const MAX_CONTACTS: usize = 4096;
+const MAX_NODES_RESPONSE: usize = 16; // Max records returned for one FINDNODE.
+const RECORDS_PER_PACKET: usize = 3; // Records are at most 300 bytes each.
+const LOOKUP_MAX_STEPS: usize = 8;
+const ALPHA: usize = 3; // Number of concurrent FINDNODE requests of a lookup.
+
+type Key = [u8; 16];
+type Nonce = [u8; NONCE_SIZE];
+
+#[derive(Clone)]
+struct Contact {
+    public: NodeId,
+    address: SocketAddr,
+    record: Option<NodeRecord>,
+}
+
+struct Session {
+    write_key: Key,
+    read_key: Key,
+}
+
+struct Request {
+    contact: Contact,
+    request_id: Bytes,
+    // Plain text message, sent again in the handshake if the node asks who we are.
+    message: Bytes,
+    // Nonce of the last packet carrying the message
+    nonce: Nonce,
+    sent_at: Instant,
+    handshake_sent: bool,
+    // Number of NODES packets received and expected
+    responses: u64,
+    total: u64,
+}
+
+struct Challenge {
+    // `masking-iv || header` of the WHOAREYOU packet
+    data: Bytes,
+    address: SocketAddr,
+    sent_at: Instant,
+}
+
+struct Packet {
+    flag: u8,
+    nonce: Nonce,
+    authdata: Bytes,
+    // `masking-iv || header`, the associated data of the message.
+    header_data: Bytes,
+    message: Bytes,
+}
+
+struct Lookup {
+    target: H256,
+    queried: HashSet<H256>,
+    steps: usize,
+}
+
+pub struct Discv5 {
+    id: NodeId,
+    local_id: H256,
+    secret: Secret,
+    record: NodeRecord,
+    contacts: HashMap<H256, Contact>,
+    sessions: LruCache<H256, Session>,
+    requests: HashMap<H256, Request>,
+    challenges: HashMap<H256, Challenge>,
+    lookup: Option<Lookup>,
+    send_queue: VecDeque<Datagram>,
+    ip_filter: IpFilter,
+}
+
+impl Discv5 {
+    pub fn new(id: NodeId, secret: Secret, record: NodeRecord, ip_filter: IpFilter) -> Discv5 {
+        Discv5 {
+            local_id: keccak(id),
+            id,
+            secret,
+            record,
+            contacts: HashMap::new(),
+            sessions: LruCache::new(MAX_SESSIONS),
+            requests: HashMap::new(),
+            challenges: HashMap::new(),
+            lookup: None,
+            send_queue: VecDeque::new(),
+            ip_filter,
+        }
+    }
+
+    /// Replace the local node record. Nodes fetch the new one after seeing the higher sequence number.
+    pub fn set_record(&mut self, record: NodeRecord) {
+        self.record = record;
+    }
+
+    /// Add a node known from discovery v4 or the node table.
+    pub fn add_node(&mut self, entry: &NodeEntry) {
+        if entry.id == self.id
+            || !entry.endpoint.is_allowed(&self.ip_filter)
+            || !entry.endpoint.is_valid_discovery_node()
+            || self.contacts.len() >= MAX_CONTACTS
+        {
+            return;
+        }
+        let address = entry.endpoint.udp_address();
+        self.contacts
+            .entry(keccak(entry.id))
+            .or_insert_with(|| Contact {
+                public: entry.id,
+                address,
+                record: None,
+            });
+    }
+
+    /// Start a lookup towards a random target.
+    pub fn refresh(&mut self) {
+        if self.lookup.is_none() {
+            trace!(target: "discovery", "Starting discv5 lookup");
+            self.lookup = Some(Lookup {
+                target: H256::random(),
+                queried: HashSet::new(),
+                steps: 0,
+            });
+        }
+    }
+
+    pub fn round(&mut self) {
+        self.check_expired(Instant::now());
+
+        let lookup = match self.lookup {
+            Some(ref lookup) => lookup,
+            None => return,
+        };
+        if lookup.steps >= LOOKUP_MAX_STEPS {
+            trace!(target: "discovery", "Completing discv5 lookup");
+            self.lookup = None;
+            return;
+        }
+        let in_flight = self.requests.len();
+        if in_flight >= ALPHA {
+            return;
+        }
+        let target = lookup.target;
+        let mut candidates: Vec<(H256, Contact)> = self
+            .contacts
+            .iter()
+            .filter(|(id, _)| !lookup.queried.contains(*id) && !self.requests.contains_key(*id))
+            .map(|(id, contact)| (*id, contact.clone()))
+            .collect();
+        candidates.sort_unstable_by_key(|(id, _)| *id ^ target);
+        candidates.truncate(ALPHA - in_flight);
+
+        if candidates.is_empty() {
+            if self.requests.is_empty() {
+                self.lookup = None;
+            }
+            return;
+        }
+        for (id, contact) in candidates {
+            let distance = log_distance(&id, &target);
+            let mut distances = vec![distance];
+            if distance < 256 {
+                distances.push(distance + 1);
+            }
+            if distance > 1 {
+                distances.push(distance - 1);
+            }
+            self.send_find_node(contact, &distances);
+            if let Some(ref mut lookup) = self.lookup {
+                lookup.queried.insert(id);
+            }
+        }
+        if let Some(ref mut lookup) = self.lookup {
+            lookup.steps += 1;
+        }
+    }
+
+    pub fn any_sends_queued(&self) -> bool {
+        !self.send_queue.is_empty()
+    }
+
+    pub fn dequeue_send(&mut self) -> Option<Datagram> {
+        self.send_queue.pop_front()
+    }
+
+    pub fn on_packet(
+        &mut self,
+        packet: &[u8],
+        from: SocketAddr,
+    ) -> Result<Option<TableUpdates>, Error> {
+        let packet = decode_packet(&self.local_id, packet)?;
+        match packet.flag {
+            FLAG_MESSAGE => self.on_message_packet(packet, from),
+            FLAG_WHOAREYOU => self.on_whoareyou(packet, from).map(|_| None),
+            FLAG_HANDSHAKE => self.on_handshake(packet, from),
+            _ => Err(ErrorKind::BadProtocol.into()),
+        }
+    }
+
+    fn check_expired(&mut self, time: Instant) {
+        let mut expired = Vec::new();
+        self.requests.retain(|id, request| {
+            if time.duration_since(request.sent_at) > REQUEST_TIMEOUT {
+                expired.push(*id);
+                false
+            } else {
+                true
+            }
+        });
+        for id in expired {
+            debug!(target: "discovery", "discv5 request to {:#x} timed out", id);
+            self.contacts.remove(&id);
+            self.sessions.remove(&id);
+        }
+        self.challenges
+            .retain(|_, challenge| time.duration_since(challenge.sent_at) <= REQUEST_TIMEOUT);
+    }
+
+    fn send_find_node(&mut self, contact: Contact, distances: &[usize]) {
+        let id = keccak(contact.public);
+        if self.requests.contains_key(&id) {
+            return;
+        }
+        let request_id = rand::random::<[u8; 8]>().to_vec();
+        let mut rlp = RlpStream::new_list(2);
+        rlp.append(&request_id);
+        rlp.begin_list(distances.len());
+        for distance in distances {
+            rlp.append(&(*distance as u64));
+        }
+        let message = encode_message(MESSAGE_FIND_NODE, &rlp.out());
+        let nonce = self.send_message(&id, &contact.address, &message);
+        trace!(target: "discovery", "Sent discv5 FindNode to {:?} ; distances={:?}", &contact.address, distances);
+        self.requests.insert(
+            id,
+            Request {
+                contact,
+                request_id,
+                message,
+                nonce,
+                sent_at: Instant::now(),
+                handshake_sent: false,
+                responses: 0,
+                total: 1,
+            },
+        );
+    }
+
+    // Encrypts the message with the session key. Without a session the message is sent with a
+    // random key, so the node answers with WHOAREYOU and the handshake starts.
+    fn send_message(&mut self, id: &H256, address: &SocketAddr, message: &[u8]) -> Nonce {
+        let nonce: Nonce = rand::random();
+        let key = match self.sessions.get_mut(id) {
+            Some(session) => session.write_key,
+            None => rand::random(),
+        };
+        let (packet, _) = encode_packet(
+            id,
+            FLAG_MESSAGE,
+            &nonce,
+            self.local_id.as_bytes(),
+            Some((&key, message)),
+        );
+        self.send_to(packet, *address);
+        nonce
+    }
+
+    fn send_to(&mut self, payload: Bytes, address: SocketAddr) {
+        self.send_queue.push_back(Datagram { payload, address });
+    }
+
+    fn on_message_packet(
+        &mut self,
+        packet: Packet,
+        from: SocketAddr,
+    ) -> Result<Option<TableUpdates>, Error> {
+        if packet.authdata.len() != 32 {
+            return Err(ErrorKind::BadProtocol.into());
+        }
+        let src_id = H256::from_slice(&packet.authdata);
+        let message = self.sessions.get_mut(&src_id).and_then(|session| {
+            decrypt_message(
+                &session.read_key,
+                &packet.nonce,
+                &packet.message,
+                &packet.header_data,
+            )
+        });
+        match message {
+            Some(message) => self.on_message(&src_id, &message, from),
+            None => {
+                self.send_whoareyou(src_id, &packet.nonce, from);
+                Ok(None)
+            }
+        }
+    }
+
+    fn send_whoareyou(&mut self, src_id: H256, nonce: &Nonce, from: SocketAddr) {
+        if self.challenges.contains_key(&src_id) {
+            trace!(target: "discovery", "discv5 challenge already sent to {:?}", &from);
+            return;
+        }
+        let known_seq = self
+            .contacts
+            .get(&src_id)
+            .and_then(|c| c.record.as_ref())
+            .map_or(0, |r| r.seq());
+        let mut authdata = Vec::with_capacity(ID_NONCE_SIZE + 8);
+        authdata.extend_from_slice(&rand::random::<[u8; ID_NONCE_SIZE]>());
+        authdata.extend_from_slice(&known_seq.to_be_bytes());
+        let (packet, challenge_data) =
+            encode_packet(&src_id, FLAG_WHOAREYOU, nonce, &authdata, None);
+        self.challenges.insert(
+            src_id,
+            Challenge {
+                data: challenge_data,
+                address: from,
+                sent_at: Instant::now(),
+            },
+        );
+        trace!(target: "discovery", "Sent discv5 WhoAreYou to {:?}", &from);
+        self.send_to(packet, from);
+    }
+
+    fn on_whoareyou(&mut self, packet: Packet, from: SocketAddr) -> Result<(), Error> {
+        if packet.authdata.len() != ID_NONCE_SIZE + 8 {
+            return Err(ErrorKind::BadProtocol.into());
+        }
+        let id = match self
+            .requests
+            .iter()
+            .find(|(_, r)| r.nonce == packet.nonce && r.contact.address == from)
+        {
+            Some((id, _)) => *id,
+            None => {
+                debug!(target: "discovery", "Got unexpected discv5 WhoAreYou from {:?}", &from);
+                return Ok(());
+            }
+        };
+        let mut seq = [0u8; 8];
+        seq.copy_from_slice(&packet.authdata[ID_NONCE_SIZE..]);
+        let known_seq = u64::from_be_bytes(seq);
+
+        let request = self
+            .requests
+            .get_mut(&id)
+            .expect("id found in requests above; qed");
+        if request.handshake_sent {
+            debug!(target: "discovery", "discv5 handshake with {:?} failed", &from);
+            self.requests.remove(&id);
+            return Ok(());
+        }
+
+        let secp = Secp256k1::new();
+        let remote = compress(&request.contact.public)?;
+        let ephemeral = Random.generate();
+        let ephemeral_key =
+            SecretKey::from_slice(ephemeral.secret().as_bytes()).map_err(|_| auth_error())?;
+        let ephemeral_public = PublicKey::from_secret_key(&secp, &ephemeral_key).serialize();
+        let shared = ecdh(&secp, &remote, ephemeral.secret())?;
+        let (initiator_key, recipient_key) =
+            derive_keys(&shared, &packet.header_data, &self.local_id, &id);
+
+        let digest = id_signature_digest(&packet.header_data, &ephemeral_public, &id);
+        let local_key = SecretKey::from_slice(self.secret.as_bytes()).map_err(|_| auth_error())?;
+        let id_signature = secp
+            .sign(
+                &Message::from_slice(&digest).map_err(|_| auth_error())?,
+                &local_key,
+            )
+            .serialize_compact();
+
+        let mut authdata = Vec::with_capacity(34 + 64 + 33);
+        authdata.extend_from_slice(self.local_id.as_bytes());
+        authdata.push(id_signature.len() as u8);
+        authdata.push(ephemeral_public.len() as u8);
+        authdata.extend_from_slice(&id_signature);
+        authdata.extend_from_slice(&ephemeral_public);
+        if known_seq < self.record.seq() {
+            authdata.extend_from_slice(&rlp::encode(&self.record));
+        }
+
+        let nonce: Nonce = rand::random();
+        let (payload, _) = encode_packet(
+            &id,
+            FLAG_HANDSHAKE,
+            &nonce,
+            &authdata,
+            Some((&initiator_key, &request.message)),
+        );
+        request.nonce = nonce;
+        request.handshake_sent = true;
+        self.sessions.insert(
+            id,
+            Session {
+                write_key: initiator_key,
+                read_key: recipient_key,
+            },
+        );
+        trace!(target: "discovery", "Sent discv5 Handshake to {:?}", &from);
+        self.send_to(payload, from);
+        Ok(())
+    }
+
+    fn on_handshake(
+        &mut self,
+        packet: Packet,
+        from: SocketAddr,
+    ) -> Result<Option<TableUpdates>, Error> {
+        let authdata = &packet.authdata;
+        if authdata.len() < 34 {
+            return Err(ErrorKind::BadProtocol.into());
+        }
+        let src_id = H256::from_slice(&authdata[..32]);
+        let signature_size = authdata[32] as usize;
+        let key_size = authdata[33] as usize;
+        if authdata.len() < 34 + signature_size + key_size {
+            return Err(ErrorKind::BadProtocol.into());
+        }
+        let challenge = match self.challenges.remove(&src_id) {
+            Some(ref challenge) if challenge.address == from => challenge.data.clone(),
+            _ => {
+                debug!(target: "discovery", "Got unexpected discv5 Handshake from {:?}", &from);
+                return Ok(None);
+            }
+        };
+        let id_signature = &authdata[34..34 + signature_size];
+        let ephemeral_public = &authdata[34 + signature_size..34 + signature_size + key_size];
+        let record = if authdata.len() > 34 + signature_size + key_size {
+            let record = NodeRecord::from_rlp(&authdata[34 + signature_size + key_size..])?;
+            if keccak(record.id()) != src_id {
+                return Err(ErrorKind::InvalidNodeId.into());
+            }
+            Some(record)
+        } else {
+            None
+        };
+        let public = match record
+            .as_ref()
+            .map(|r| r.id())
+            .or_else(|| self.contacts.get(&src_id).map(|c| c.public))
+        {
+            Some(public) => public,
+            None => {
+                debug!(target: "discovery", "Unknown public key in discv5 Handshake from {:?}", &from);
+                return Ok(None);
+            }
+        };
+
+        let secp = Secp256k1::new();
+        let digest = id_signature_digest(&challenge, ephemeral_public, &self.local_id);
+        let mut signature = Signature::from_compact(id_signature).map_err(|_| auth_error())?;
+        signature.normalize_s();
+        secp.verify(
+            &Message::from_slice(&digest).map_err(|_| auth_error())?,
+            &signature,
+            &PublicKey::from_slice(&compress(&public)?).map_err(|_| auth_error())?,
+        )
+        .map_err(|_| auth_error())?;
+
+        let shared = ecdh(&secp, ephemeral_public, &self.secret)?;
+        let (initiator_key, recipient_key) =
+            derive_keys(&shared, &challenge, &src_id, &self.local_id);
+        let message = decrypt_message(
+            &initiator_key,
+            &packet.nonce,
+            &packet.message,
+            &packet.header_data,
+        )
+        .ok_or_else(auth_error)?;
+        self.sessions.insert(
+            src_id,
+            Session {
+                write_key: recipient_key,
+                read_key: initiator_key,
+            },
+        );
+
+        let mut updates = self.add_contact(public, from, record);
+        if let Some(more) = self.on_message(&src_id, &message, from)? {
+            updates = Some(merge_updates(updates, more));
+        }
+        Ok(updates)
+    }
+
+    // Adds or refreshes a contact; returns the update for the node table if the record is new.
+    fn add_contact(
+        &mut self,
+        public: NodeId,
+        address: SocketAddr,
+        record: Option<NodeRecord>,
+    ) -> Option<TableUpdates> {
+        let id = keccak(public);
+        if !self.contacts.contains_key(&id) && self.contacts.len() >= MAX_CONTACTS {
+            return None;
+        }
+        let contact = self.contacts.entry(id).or_insert_with(|| Contact {
+            public,
+            address,
+            record: None,
+        });
+        contact.address = address;
+        let record = match record {
+            Some(ref record)
+                if contact
+                    .record
+                    .as_ref()
+                    .map_or(true, |known| known.seq() < record.seq()) =>
+            {
+                contact.record = Some(record.clone());
+                record.clone()
+            }
+            _ => return None,
+        };
+        record
+            .endpoint()
+            .filter(|endpoint| endpoint.is_valid_sync_node())
+            .map(|endpoint| {
+                let mut added = HashMap::with_capacity(1);
+                added.insert(
+                    public,
+                    NodeEntry {
+                        id: public,
+                        endpoint,
+                    },
+                );
+                let mut records = HashMap::with_capacity(1);
+                records.insert(public, record);
+                TableUpdates {
+                    added,
+                    removed: HashSet::new(),
+                    records,
+                }
+            })
+    }
+
+    fn on_message(
+        &mut self,
+        src_id: &H256,
+        message: &[u8],
+        from: SocketAddr,
+    ) -> Result<Option<TableUpdates>, Error> {
+        if message.is_empty() {
+            return Err(ErrorKind::BadProtocol.into());
+        }
+        let rlp = Rlp::new(&message[1..]);
+        match message[0] {
+            MESSAGE_PING => self.on_ping(&rlp, src_id, from).map(|_| None),
+            MESSAGE_PONG => {
+                trace!(target: "discovery", "Got discv5 Pong from {:?}", &from);
+                Ok(None)
+            }
+            MESSAGE_FIND_NODE => self.on_find_node(&rlp, src_id, from).map(|_| None),
+            MESSAGE_NODES => self.on_nodes(&rlp, src_id, from),
+            message_id => {
+                debug!(target: "discovery", "Unknown discv5 message: {}", message_id);
+                Ok(None)
+            }
+        }
+    }
+
+    fn on_ping(&mut self, rlp: &Rlp, src_id: &H256, from: SocketAddr) -> Result<(), Error> {
+        trace!(target: "discovery", "Got discv5 Ping from {:?}", &from);
+        let request_id: Bytes = rlp.val_at(0)?;
+        let enr_seq: u64 = rlp.val_at(1)?;
+
+        let mut response = RlpStream::new_list(4);
+        response.append(&request_id);
+        response.append(&self.record.seq());
+        match from.ip() {
+            IpAddr::V4(ip) => response.append(&&ip.octets()[..]),
+            IpAddr::V6(ip) => response.append(&&ip.octets()[..]),
+        };
+        response.append(&from.port());
+        let message = encode_message(MESSAGE_PONG, &response.out());
+        self.send_message(src_id, &from, &message);
+
+        let contact = self.contacts.get(src_id).cloned();
+        if let Some(contact) = contact {
+            if contact.record.as_ref().map_or(0, |r| r.seq()) < enr_seq {
+                self.send_find_node(contact, &[0]);
+            }
+        }
+        Ok(())
+    }
+
+    fn on_find_node(&mut self, rlp: &Rlp, src_id: &H256, from: SocketAddr) -> Result<(), Error> {
+        trace!(target: "discovery", "Got discv5 FindNode from {:?}", &from);
+        let request_id: Bytes = rlp.val_at(0)?;
+        let distances: Vec<u64> = rlp.list_at(1)?;
+
+        let mut records = Vec::new();
+        for distance in distances {
+            if records.len() >= MAX_NODES_RESPONSE {
+                break;
+            }
+            if distance == 0 {
+                records.push(self.record.clone());
+                continue;
+            }
+            records.extend(
+                self.contacts
+                    .iter()
+                    .filter(|(id, _)| log_distance(&self.local_id, id) as u64 == distance)
+                    .filter_map(|(_, contact)| contact.record.clone()),
+            );
+        }
+        records.truncate(MAX_NODES_RESPONSE);
+
+        let chunks: Vec<&[NodeRecord]> = if records.is_empty() {
+            vec![&[]]
+        } else {
+            records.chunks(RECORDS_PER_PACKET).collect()
+        };
+        let total = chunks.len() as u64;
+        for chunk in chunks {
+            let mut response = RlpStream::new_list(3);
+            response.append(&request_id);
+            response.append(&total);
+            response.append_list::<NodeRecord, NodeRecord>(chunk);
+            let message = encode_message(MESSAGE_NODES, &response.out());
+            self.send_message(src_id, &from, &message);
+        }
+        Ok(())
+    }
+
+    fn on_nodes(
+        &mut self,
+        rlp: &Rlp,
+        src_id: &H256,
+        from: SocketAddr,
+    ) -> Result<Option<TableUpdates>, Error> {
+        let request_id: Bytes = rlp.val_at(0)?;
+        let total: u64 = rlp.val_at(1)?;
+        let done = match self.requests.get_mut(src_id) {
+            Some(ref mut request) if request.request_id == request_id => {
+                request.total = min(total, MAX_NODES_RESPONSE as u64);
+                request.responses += 1;
+                request.responses >= request.total
+            }
+            _ => {
+                debug!(target: "discovery", "Got unexpected discv5 Nodes from {:?}", &from);
+                return Ok(None);
+            }
+        };
+        if done {
+            self.requests.remove(src_id);
+        }
+
+        let mut updates: Option<TableUpdates> = None;
+        trace!(target: "discovery", "Got discv5 Nodes from {:?}", &from);
+        for item in rlp.at(2)?.iter() {
+            let record = match NodeRecord::from_rlp(item.as_raw()) {
+                Ok(record) => record,
+                Err(e) => {
+                    debug!(target: "discovery", "Bad discv5 record from {:?}: {:?}", &from, e);
+                    continue;
+                }
+            };
+            if record.id() == self.id {
+                continue;
+            }
+            let endpoint = match record.endpoint() {
+                Some(ref endpoint)
+                    if endpoint.is_valid_discovery_node()
+                        && endpoint.is_allowed(&self.ip_filter) =>
+                {
+                    endpoint.clone()
+                }
+                _ => continue,
+            };
+            if let Some(update) =
+                self.add_contact(record.id(), endpoint.udp_address(), Some(record))
+            {
+                updates = Some(merge_updates(updates, update));
+            }
+        }
+
+        // Fetch the record of the responding node if it is still unknown
+        if done {
+            let contact = self
+                .contacts
+                .get(src_id)
+                .filter(|c| c.record.is_none())
+                .cloned();
+            if let Some(contact) = contact {
+                self.send_find_node(contact, &[0]);
+            }
+        }
+        Ok(updates)
+    }
+}
+
+fn merge_updates(updates: Option<TableUpdates>, mut other: TableUpdates) -> TableUpdates {
+    if let Some(updates) = updates {
+        other.added.extend(updates.added);
+        other.removed.extend(updates.removed);
+        other.records.extend(updates.records);
+    }
+    other
+}
+
+/// Logarithmic distance between two node ids: 0 if equal, otherwise 1 to 256.
+fn log_distance(a: &H256, b: &H256) -> usize {
+    let distance = *a ^ *b;
+    let zeros = distance
+        .as_bytes()
+        .iter()
+        .position(|byte| *byte != 0)
+        .map_or(256, |i| i * 8 + distance[i].leading_zeros() as usize);
+    256 - zeros
+}
+
+fn auth_error() -> Error {
+    ErrorKind::Auth.into()
+}
+
+fn encode_message(message_id: u8, payload: &[u8]) -> Bytes {
+    let mut message = Vec::with_capacity(payload.len() + 1);
+    message.push(message_id);
+    message.extend_from_slice(payload);
+    message
+}
+
+/// Returns the packet and its unmasked `masking-iv || header`.
+fn encode_packet(
+    dest_id: &H256,
+    flag: u8,
+    nonce: &Nonce,
+    authdata: &[u8],
+    message: Option<(&Key, &[u8])>,
+) -> (Bytes, Bytes) {
+    let masking_iv: [u8; MASKING_IV_SIZE] = rand::random();
+    let mut header = Vec::with_capacity(STATIC_HEADER_SIZE + authdata.len());
+    header.extend_from_slice(PROTOCOL_ID);
+    header.extend_from_slice(&PROTOCOL_VERSION.to_be_bytes());
+    header.push(flag);
+    header.extend_from_slice(nonce);
+    header.extend_from_slice(&(authdata.len() as u16).to_be_bytes());
+    header.extend_from_slice(authdata);
+
+    let mut packet = Vec::with_capacity(MAX_DATAGRAM_SIZE);
+    packet.extend_from_slice(&masking_iv);
+    packet.extend_from_slice(&header);
+    let header_data = packet.clone();
+    aes::ctr(KeySize::KeySize128, &dest_id[..16], &masking_iv)
+        .process(&header, &mut packet[MASKING_IV_SIZE..]);
+    if let Some((key, plain)) = message {
+        packet.extend_from_slice(&encrypt_message(key, nonce, plain, &header_data));
+    }
+    (packet, header_data)
+}
+
+fn decode_packet(local_id: &H256, packet: &[u8]) -> Result<Packet, Error> {
+    if packet.len() < MIN_PACKET_SIZE || packet.len() > MAX_DATAGRAM_SIZE {
+        return Err(ErrorKind::BadProtocol.into());
+    }
+    let masking_iv = &packet[..MASKING_IV_SIZE];
+    let mut cipher = aes::ctr(KeySize::KeySize128, &local_id[..16], masking_iv);
+    let mut header = vec![0u8; STATIC_HEADER_SIZE];
+    cipher.process(
+        &packet[MASKING_IV_SIZE..MASKING_IV_SIZE + STATIC_HEADER_SIZE],
+        &mut header,
+    );
+    if &header[..6] != PROTOCOL_ID || header[6..8] != PROTOCOL_VERSION.to_be_bytes() {
+        return Err(ErrorKind::BadProtocol.into());
+    }
+    let flag = header[8];
+    let mut nonce = [0u8; NONCE_SIZE];
+    nonce.copy_from_slice(&header[9..9 + NONCE_SIZE]);
+    let authdata_size = u16::from_be_bytes([
+        header[STATIC_HEADER_SIZE - 2],
+        header[STATIC_HEADER_SIZE - 1],
+    ]) as usize;
+    let header_end = MASKING_IV_SIZE + STATIC_HEADER_SIZE + authdata_size;
+    if packet.len() < header_end {
+        return Err(ErrorKind::BadProtocol.into());
+    }
+    let mut authdata = vec![0u8; authdata_size];
+    cipher.process(
+        &packet[MASKING_IV_SIZE + STATIC_HEADER_SIZE..header_end],
+        &mut authdata,
+    );
+
+    let mut header_data = Vec::with_capacity(header_end);
+    header_data.extend_from_slice(masking_iv);
+    header_data.extend_from_slice(&header);
+    header_data.extend_from_slice(&authdata);
+    Ok(Packet {
+        flag,
+        nonce,
+        authdata,
+        header_data,
+        message: packet[header_end..].to_vec(),
+    })
+}
+
+fn encrypt_message(key: &Key, nonce: &Nonce, plain: &[u8], ad: &[u8]) -> Bytes {
+    let mut output = vec![0u8; plain.len() + TAG_SIZE];
+    {
+        let (cipher_text, tag) = output.split_at_mut(plain.len());
+        AesGcm::new(KeySize::KeySize128, key, nonce, ad).encrypt(plain, cipher_text, tag);
+    }
+    output
+}
+
+fn decrypt_message(key: &Key, nonce: &Nonce, message: &[u8], ad: &[u8]) -> Option<Bytes> {
+    if message.len() < TAG_SIZE {
+        return None;
+    }
+    let (cipher_text, tag) = message.split_at(message.len() - TAG_SIZE);
+    let mut plain = vec![0u8; cipher_text.len()];
+    if AesGcm::new(KeySize::KeySize128, key, nonce, ad).decrypt(cipher_text, &mut plain, tag) {
+        Some(plain)
+    } else {
+        None
+    }
+}
+
+fn compress(public: &NodeId) -> Result<[u8; 33], Error> {
+    let mut serialized = [4u8; 65];
+    serialized[1..].copy_from_slice(public.as_bytes());
+    PublicKey::from_slice(&serialized)
+        .map(|key| key.serialize())
+        .map_err(|_| auth_error())
+}
+
+/// Compressed shared point of the given compressed public key and secret.
+fn ecdh(
+    secp: &Secp256k1<secp256k1::All>,
+    public: &[u8],
+    secret: &Secret,
+) -> Result<[u8; 33], Error> {
+    let mut point = PublicKey::from_slice(public).map_err(|_| auth_error())?;
+    point
+        .mul_assign(secp, secret.as_bytes())
+        .map_err(|_| auth_error())?;
+    Ok(point.serialize())
+}
+
+fn derive_keys(
+    shared: &[u8],
+    challenge_data: &[u8],
+    initiator_id: &H256,
+    recipient_id: &H256,
+) -> (Key, Key) {
+    let mut prk = [0u8; 32];
+    hkdf_extract(Sha256::new(), challenge_data, shared, &mut prk);
+    let mut info = KEY_AGREEMENT_INFO.to_vec();
+    info.extend_from_slice(initiator_id.as_bytes());
+    info.extend_from_slice(recipient_id.as_bytes());
+    let mut key_data = [0u8; 32];
+    hkdf_expand(Sha256::new(), &prk, &info, &mut key_data);
+
+    let mut initiator_key = Key::default();
+    let mut recipient_key = Key::default();
+    initiator_key.copy_from_slice(&key_data[..16]);
+    recipient_key.copy_from_slice(&key_data[16..]);
+    (initiator_key, recipient_key)
+}
+
+fn id_signature_digest(challenge_data: &[u8], ephemeral_public: &[u8], dest_id: &H256) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.input(ID_SIGNATURE_TEXT);
+    hasher.input(challenge_data);
+    hasher.input(ephemeral_public);
+    hasher.input(dest_id.as_bytes());
+    let mut digest = [0u8; 32];
+    hasher.result(&mut digest);
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use node_table::NodeEndpoint;
+    use rustc_hex::FromHex;
+    use std::str::FromStr;
+
+    // Test vectors of the discv5 wire specification
+    const SRC_NODE_ID: &str = "aaaa8419e9f49d0083561b48287df592939a8d19947d8c0ef88f2a4856a69fbb";
+    const DEST_NODE_ID: &str = "bbbb9d047f0488c0b5a93c1c3f2d8bafc7c8ff337024a55434a0d0555de64db9";
+    const EPHEMERAL_KEY: &str = "fb757dc581730490a1d7a00deea65e9b1936924caaea8f44d476014856b68736";
+    const CHALLENGE_DATA: &str = "000000000000000000000000000000006469736376350001010102030405060708090a0b0c00180102030405060708090a0b0c0d0e0f100000000000000000";
+
+    fn bytes(hex: &str) -> Vec<u8> {
+        hex.from_hex().unwrap()
+    }
+
+    fn new_discv5(port: u16) -> (Discv5, NodeEntry) {
+        let key = Random.generate();
+        let endpoint = NodeEndpoint {
+            address: SocketAddr::from_str(&format!("127.0.0.1:{}", port)).unwrap(),
+            udp_port: port,
+        };
+        let record = NodeRecord::new(key.secret(), 1, &endpoint, None).unwrap();
+        let entry = NodeEntry {
+            id: *key.public(),
+            endpoint,
+        };
+        (
+            Discv5::new(
+                *key.public(),
+                key.secret().clone(),
+                record,
+                IpFilter::default(),
+            ),
+            entry,
+        )
+    }
+
+    #[test]
+    fn log_distance_of_ids() {
+        let a = H256::zero();
+        let mut b = H256::zero();
+        assert_eq!(log_distance(&a, &b), 0);
+        b.as_bytes_mut()[31] = 1;
+        assert_eq!(log_distance(&a, &b), 1);
+        b.as_bytes_mut()[0] = 0x80;
+        assert_eq!(log_distance(&a, &b), 256);
+    }
+
+    #[test]
+    fn packet_round_trip() {
+        let dest_id = H256::random();
+        let key: Key = rand::random();
+        let nonce: Nonce = rand::random();
+        let authdata = H256::random();
+        let (packet, header_data) = encode_packet(
+            &dest_id,
+            FLAG_MESSAGE,
+            &nonce,
+            authdata.as_bytes(),
+            Some((&key, b"message")),
+        );
+
+        assert!(decode_packet(&H256::random(), &packet).is_err());
+        let decoded = decode_packet(&dest_id, &packet).unwrap();
+        assert_eq!(decoded.flag, FLAG_MESSAGE);
+        assert_eq!(decoded.nonce, nonce);
+        assert_eq!(decoded.authdata, authdata.as_bytes().to_vec());
+        assert_eq!(decoded.header_data, header_data);
+        assert_eq!(
+            decrypt_message(&key, &nonce, &decoded.message, &decoded.header_data),
+            Some(b"message".to_vec())
+        );
+        assert_eq!(
+            decrypt_message(
+                &rand::random(),
+                &nonce,
+                &decoded.message,
+                &decoded.header_data
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn handshake_and_find_node() {
+        let (mut a, a_entry) = new_discv5(40501);
+        let (mut b, b_entry) = new_discv5(40502);
+        let a_address = a_entry.endpoint.udp_address();
+        let b_address = b_entry.endpoint.udp_address();
+
+        a.add_node(&b_entry);
+        let contact = a.contacts.values().next().cloned().unwrap();
+        a.send_find_node(contact, &[0]);
+
+        let mut a_updates = Vec::new();
+        let mut b_updates = Vec::new();
+        loop {
+            let mut delivered = false;
+            while let Some(datagram) = a.dequeue_send() {
+                assert_eq!(datagram.address, b_address);
+                b_updates.extend(b.on_packet(&datagram.payload, a_address).unwrap());
+                delivered = true;
+            }
+            while let Some(datagram) = b.dequeue_send() {
+                assert_eq!(datagram.address, a_address);
+                a_updates.extend(a.on_packet(&datagram.payload, b_address).unwrap());
+                delivered = true;
+            }
+            if !delivered {
+                break;
+            }
+        }
+
+        // Both sides learned the record of the other one
+        assert!(a.requests.is_empty());
+        assert_eq!(a_updates.len(), 1);
+        assert_eq!(a_updates[0].records.get(&b_entry.id), Some(&b.record));
+        assert_eq!(b_updates.len(), 1);
+        assert_eq!(b_updates[0].records.get(&a_entry.id), Some(&a.record));
+        assert!(a.sessions.contains_key(&b.local_id));
+        assert!(b.sessions.contains_key(&a.local_id));
+    }
+
+    #[test]
+    fn ecdh_vector() {
+        let secp = Secp256k1::new();
+        let public = bytes("039961e4c2356d61bedb83052c115d311acb3a96f5777296dcf297351130266231");
+        let secret = Secret::from_str(EPHEMERAL_KEY).unwrap();
+        assert_eq!(
+            &ecdh(&secp, &public, &secret).unwrap()[..],
+            &bytes("033b11a2a1f214567e1537ce5e509ffd9b21373247f2a3ff6841f4976f53165e7e")[..]
+        );
+    }
+
+    #[test]
+    fn key_derivation_vector() {
+        let secp = Secp256k1::new();
+        let dest_public =
+            bytes("0317931e6e0840220642f230037d285d122bc59063221ef3226b1f403ddc69ca91");
+        let secret = Secret::from_str(EPHEMERAL_KEY).unwrap();
+        let shared = ecdh(&secp, &dest_public, &secret).unwrap();
+        let (initiator_key, recipient_key) = derive_keys(
+            &shared,
+            &bytes(CHALLENGE_DATA),
+            &H256::from_str(SRC_NODE_ID).unwrap(),
+            &H256::from_str(DEST_NODE_ID).unwrap(),
+        );
+        assert_eq!(
+            &initiator_key[..],
+            &bytes("dccc82d81bd610f4f76d3ebe97a40571")[..]
+        );
+        assert_eq!(
+            &recipient_key[..],
+            &bytes("ac74bb8773749920b0d3a8881c173ec5")[..]
+        );
+    }
+
+    #[test]
+    fn message_encryption_vector() {
+        let mut key = Key::default();
+        key.copy_from_slice(&bytes("9f2d77db7004bf8a1a85107ac686990b"));
+        let mut nonce = Nonce::default();
+        nonce.copy_from_slice(&bytes("27b5af763c446acd2749fe8e"));
+        let ad = bytes("93a7400fa0d6a694ebc24d5cf570f65d04215b6ac00757875e3f3a5f42107903");
+        let encrypted = encrypt_message(&key, &nonce, &bytes("01c20101"), &ad);
+        assert_eq!(encrypted, bytes("a5d12a2d94b8ccb3ba55558229867dc13bfa3648"));
+        assert_eq!(
+            decrypt_message(&key, &nonce, &encrypted, &ad),
+            Some(bytes("01c20101"))
+        );
+    }
+
+    #[test]
+    fn whoareyou_packet_vector() {
+        let packet = bytes("00000000000000000000000000000000088b3d434277464933a1ccc59f5967ad1d6035f15e528627dde75cd68292f9e6c27d6b66c8100a873fcbaed4e16b8d");
+        let decoded = decode_packet(&H256::from_str(DEST_NODE_ID).unwrap(), &packet).unwrap();
+        assert_eq!(decoded.flag, FLAG_WHOAREYOU);
+        assert_eq!(&decoded.nonce[..], &bytes("0102030405060708090a0b0c")[..]);
+        // id-nonce || enr-seq
+        assert_eq!(
+            decoded.authdata,
+            bytes("0102030405060708090a0b0c0d0e0f100000000000000000")
+        );
+        assert_eq!(decoded.header_data, bytes(CHALLENGE_DATA));
+        assert!(decoded.message.is_empty());
+    }
+
+    #[test]
+    fn ping_packet_vector() {
+        let packet = bytes("00000000000000000000000000000000088b3d4342774649325f313964a39e55ea96c005ad52be8c7560413a7008f16c9e6d2f43bbea8814a546b7409ce783d34c4f53245d08dab84102ed931f66d1492acb308fa1c6715b9d139b81acbdcc");
+        let decoded = decode_packet(&H256::from_str(DEST_NODE_ID).unwrap(), &packet).unwrap();
+        assert_eq!(decoded.flag, FLAG_MESSAGE);
+        assert_eq!(&decoded.nonce[..], &bytes("ffffffffffffffffffffffff")[..]);
+        assert_eq!(decoded.authdata, bytes(SRC_NODE_ID));
+
+        let read_key = Key::default();
+        let message = decrypt_message(
+            &read_key,
+            &decoded.nonce,
+            &decoded.message,
+            &decoded.header_data,
+        )
+        .unwrap();
+        assert_eq!(message[0], MESSAGE_PING);
+        let rlp = Rlp::new(&message[1..]);
+        assert_eq!(rlp.val_at::<Bytes>(0).unwrap(), bytes("00000001"));
+        assert_eq!(rlp.val_at::<u64>(1).unwrap(), 2);
+    }
+}
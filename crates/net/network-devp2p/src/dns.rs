@@ -0,0 +1,752 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Node discovery via DNS (EIP-1459).
+//!
+//! A node list is published as a Merkle tree of TXT records under a domain and signed by the
+//! list operator. `enrtree://<key>@<domain>` URLs name the tree and the key its root must be
+//! signed with. Every entry below the root is authenticated by its subdomain, which is the hash
+//! of the entry's text, so a resolver can't alter the list without breaking the root signature.
+
+use base64;
+use enr::NodeRecord;
+use hash::keccak;
+use network::{Error, ErrorKind};
+use parking_lot::Mutex;
+use rand;
+use secp256k1::{Message, PublicKey, Secp256k1, Signature};
+use std::{
+    collections::{HashSet, VecDeque},
+    fs, mem,
+    net::{IpAddr, SocketAddr, UdpSocket},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+const URL_PREFIX: &str = "enrtree://";
+const ROOT_PREFIX: &str = "enrtree-root:v1";
+const BRANCH_PREFIX: &str = "enrtree-branch:";
+const RECORD_PREFIX: &str = "enr:";
+
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+const DNS_PORT: u16 = 53;
+const DNS_TIMEOUT: Duration = Duration::from_secs(5);
+const DNS_MAX_RESPONSE_SIZE: usize = 4096;
+const TYPE_TXT: u16 = 16;
+const CLASS_IN: u16 = 1;
+
+/// Maximum number of entries resolved while walking a single tree.
+const MAX_TREE_QUERIES: usize = 5000;
+/// Maximum number of trees followed, including linked ones.
+const MAX_TREES: usize = 16;
+/// Maximum number of records waiting to be picked up by the host.
+const MAX_PENDING_RECORDS: usize = 4096;
+/// How often tree roots are checked for updates.
+const TREE_REFRESH_INTERVAL: Duration = Duration::from_secs(30 * 60);
+const STOP_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Source of DNS TXT records.
+pub trait TxtResolver: Send + Sync {
+    /// Resolve the TXT records of `name`. The strings of each record are joined together;
+    /// a name that doesn't exist resolves to no records.
+    fn resolve_txt(&self, name: &str) -> Result<Vec<String>, Error>;
+}
+
+/// Resolver querying the first nameserver of `/etc/resolv.conf` over UDP.
+pub struct SystemResolver {
+    nameserver: SocketAddr,
+}
+
+impl SystemResolver {
+    pub fn new() -> Result<SystemResolver, Error> {
+        let conf = fs::read_to_string("/etc/resolv.conf")?;
+        conf.lines()
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                match (parts.next(), parts.next()) {
+                    (Some("nameserver"), Some(address)) => IpAddr::from_str(address).ok(),
+                    _ => None,
+                }
+            })
+            .next()
+            .map(|ip| SystemResolver {
+                nameserver: SocketAddr::new(ip, DNS_PORT),
+            })
+            .ok_or_else(|| ErrorKind::AddressResolve(None).into())
+    }
+}
+
+impl TxtResolver for SystemResolver {
+    fn resolve_txt(&self, name: &str) -> Result<Vec<String>, Error> {
+        let id: u16 = rand::random();
+        let query = encode_query(id, name)?;
+        let local = if self.nameserver.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.set_read_timeout(Some(DNS_TIMEOUT))?;
+        socket.send_to(&query, self.nameserver)?;
+
+        let mut buf = [0u8; DNS_MAX_RESPONSE_SIZE];
+        loop {
+            let (len, from) = socket.recv_from(&mut buf)?;
+            if from != self.nameserver {
+                continue;
+            }
+            if let Some(records) = decode_response(id, &buf[..len])? {
+                return Ok(records);
+            }
+        }
+    }
+}
+
+fn encode_query(id: u16, name: &str) -> Result<Vec<u8>, Error> {
+    let mut query = Vec::with_capacity(name.len() + 18);
+    query.extend_from_slice(&id.to_be_bytes());
+    // recursion desired, one question
+    query.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(ErrorKind::AddressParse.into());
+        }
+        query.push(label.len() as u8);
+        query.extend_from_slice(label.as_bytes());
+    }
+    query.push(0);
+    query.extend_from_slice(&TYPE_TXT.to_be_bytes());
+    query.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(query)
+}
+
+/// Decode the TXT records of a response. Returns `None` if it doesn't answer query `id`.
+fn decode_response(id: u16, data: &[u8]) -> Result<Option<Vec<String>>, Error> {
+    let read_u16 = |pos: usize| -> Result<u16, Error> {
+        data.get(pos..pos + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .ok_or_else(|| ErrorKind::BadProtocol.into())
+    };
+    if data.len() < 12 || read_u16(0)? != id {
+        return Ok(None);
+    }
+    let flags = read_u16(2)?;
+    if flags & 0x8000 == 0 {
+        return Ok(None);
+    }
+    if flags & 0x0200 != 0 {
+        // truncated; tree entries are meant to fit into a single datagram
+        return Err(ErrorKind::OversizedPacket.into());
+    }
+    match flags & 0x000f {
+        0 => (),
+        // name error
+        3 => return Ok(Some(Vec::new())),
+        _ => return Err(ErrorKind::AddressResolve(None).into()),
+    }
+
+    let question_count = read_u16(4)?;
+    let answer_count = read_u16(6)?;
+    let mut pos = 12;
+    for _ in 0..question_count {
+        pos = skip_name(data, pos)? + 4;
+    }
+    let mut records = Vec::new();
+    for _ in 0..answer_count {
+        pos = skip_name(data, pos)?;
+        let record_type = read_u16(pos)?;
+        let data_len = read_u16(pos + 8)? as usize;
+        pos += 10;
+        let record_data = data
+            .get(pos..pos + data_len)
+            .ok_or(ErrorKind::BadProtocol)?;
+        pos += data_len;
+        if record_type != TYPE_TXT {
+            continue;
+        }
+        let mut text = Vec::with_capacity(data_len);
+        let mut string_pos = 0;
+        while string_pos < record_data.len() {
+            let len = record_data[string_pos] as usize;
+            let string = record_data
+                .get(string_pos + 1..string_pos + 1 + len)
+                .ok_or(ErrorKind::BadProtocol)?;
+            text.extend_from_slice(string);
+            string_pos += 1 + len;
+        }
+        records.push(String::from_utf8_lossy(&text).into_owned());
+    }
+    Ok(Some(records))
+}
+
+/// Returns the position right after the (possibly compressed) name at `pos`.
+fn skip_name(data: &[u8], mut pos: usize) -> Result<usize, Error> {
+    loop {
+        let len = *data.get(pos).ok_or(ErrorKind::BadProtocol)? as usize;
+        if len & 0xc0 == 0xc0 {
+            return Ok(pos + 2);
+        }
+        if len == 0 {
+            return Ok(pos + 1);
+        }
+        pos += 1 + len;
+    }
+}
+
+fn base32_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity((data.len() * 8 + 4) / 5);
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    out
+}
+
+fn base32_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 5 / 8);
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for c in text.bytes() {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a == c.to_ascii_uppercase())? as u16;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+fn base64_decode(text: &str) -> Result<Vec<u8>, Error> {
+    base64::decode_config(text, base64::URL_SAFE_NO_PAD)
+        .map_err(|_| Error::from(ErrorKind::BadProtocol))
+}
+
+/// Subdomain an entry is published under.
+fn entry_hash(text: &str) -> String {
+    base32_encode(&keccak(text.as_bytes()).as_bytes()[..16])
+}
+
+/// Location of a signed tree, `enrtree://<base32 compressed key>@<domain>`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnrTreeUrl {
+    domain: String,
+    public: PublicKey,
+}
+
+impl FromStr for EnrTreeUrl {
+    type Err = Error;
+
+    fn from_str(url: &str) -> Result<Self, Error> {
+        if !url.starts_with(URL_PREFIX) {
+            return Err(ErrorKind::AddressParse.into());
+        }
+        let mut parts = url[URL_PREFIX.len()..].splitn(2, '@');
+        let key = parts.next().and_then(base32_decode);
+        let domain = parts.next().unwrap_or("");
+        if domain.is_empty() {
+            return Err(ErrorKind::AddressParse.into());
+        }
+        let public = key
+            .and_then(|key| PublicKey::from_slice(&key).ok())
+            .ok_or(ErrorKind::InvalidNodeId)?;
+        Ok(EnrTreeUrl {
+            domain: domain.to_owned(),
+            public,
+        })
+    }
+}
+
+/// Check if a DNS discovery URL is valid
+pub fn validate_enrtree_url(url: &str) -> Option<Error> {
+    EnrTreeUrl::from_str(url).err()
+}
+
+struct TreeRoot {
+    enr_root: String,
+    link_root: String,
+    seq: u64,
+}
+
+impl TreeRoot {
+    fn parse(text: &str, public: &PublicKey) -> Result<TreeRoot, Error> {
+        let mut parts = text.split_whitespace();
+        let mut field = |name: &str| -> Result<String, Error> {
+            parts
+                .next()
+                .filter(|part| part.starts_with(name))
+                .map(|part| part[name.len()..].to_owned())
+                .ok_or_else(|| ErrorKind::BadProtocol.into())
+        };
+        if !field(ROOT_PREFIX)?.is_empty() {
+            return Err(ErrorKind::BadProtocol.into());
+        }
+        let enr_root = field("e=")?;
+        let link_root = field("l=")?;
+        let seq = field("seq=")?
+            .parse()
+            .map_err(|_| Error::from(ErrorKind::BadProtocol))?;
+        let signature = base64_decode(&field("sig=")?)?;
+        if signature.len() != 65 {
+            return Err(ErrorKind::Auth.into());
+        }
+
+        let signed = format!("{} e={} l={} seq={}", ROOT_PREFIX, enr_root, link_root, seq);
+        let hash = keccak(signed.as_bytes());
+        let message = Message::from_slice(hash.as_bytes()).expect("hash is 32 bytes; qed");
+        let mut signature =
+            Signature::from_compact(&signature[..64]).map_err(|_| Error::from(ErrorKind::Auth))?;
+        signature.normalize_s();
+        Secp256k1::verification_only()
+            .verify(&message, &signature, public)
+            .map_err(|_| Error::from(ErrorKind::Auth))?;
+
+        Ok(TreeRoot {
+            enr_root,
+            link_root,
+            seq,
+        })
+    }
+}
+
+enum TreeEntry {
+    Branch(Vec<String>),
+    Record(NodeRecord),
+    Link(EnrTreeUrl),
+}
+
+impl TreeEntry {
+    fn parse(text: &str) -> Result<TreeEntry, Error> {
+        if text.starts_with(BRANCH_PREFIX) {
+            Ok(TreeEntry::Branch(
+                text[BRANCH_PREFIX.len()..]
+                    .split(',')
+                    .filter(|hash| !hash.is_empty())
+                    .map(str::to_owned)
+                    .collect(),
+            ))
+        } else if text.starts_with(RECORD_PREFIX) {
+            let rlp = base64_decode(&text[RECORD_PREFIX.len()..])?;
+            Ok(TreeEntry::Record(NodeRecord::from_rlp(&rlp)?))
+        } else if text.starts_with(URL_PREFIX) {
+            Ok(TreeEntry::Link(text.parse()?))
+        } else {
+            Err(ErrorKind::BadProtocol.into())
+        }
+    }
+}
+
+struct Tree {
+    url: EnrTreeUrl,
+    /// Sequence number of the last root walked.
+    seq: Option<u64>,
+}
+
+/// Client walking a set of node trees.
+pub struct DnsDiscovery {
+    resolver: Arc<dyn TxtResolver>,
+    trees: Vec<Tree>,
+}
+
+impl DnsDiscovery {
+    pub fn new(urls: &[String], resolver: Arc<dyn TxtResolver>) -> Result<DnsDiscovery, Error> {
+        let trees = urls
+            .iter()
+            .map(|url| {
+                Ok(Tree {
+                    url: url.parse()?,
+                    seq: None,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(DnsDiscovery { resolver, trees })
+    }
+
+    /// Walk every tree whose root changed since the last call and return the records found.
+    /// Trees linked from the configured ones are added on the way.
+    pub fn sync(&mut self, stop: &AtomicBool) -> Vec<NodeRecord> {
+        let mut records = Vec::new();
+        let mut index = 0;
+        while index < self.trees.len() && !stop.load(AtomicOrdering::SeqCst) {
+            match self.sync_tree(index, stop) {
+                Ok((tree_records, links)) => {
+                    records.extend(tree_records);
+                    for link in links {
+                        if self.trees.len() < MAX_TREES
+                            && !self.trees.iter().any(|tree| tree.url == link)
+                        {
+                            self.trees.push(Tree {
+                                url: link,
+                                seq: None,
+                            });
+                        }
+                    }
+                }
+                Err(e) => {
+                    debug!(target: "discovery", "Error syncing node tree {}: {:?}", self.trees[index].url.domain, e)
+                }
+            }
+            index += 1;
+        }
+        records
+    }
+
+    fn sync_tree(
+        &mut self,
+        index: usize,
+        stop: &AtomicBool,
+    ) -> Result<(Vec<NodeRecord>, Vec<EnrTreeUrl>), Error> {
+        let url = self.trees[index].url.clone();
+        let root = self
+            .resolver
+            .resolve_txt(&url.domain)?
+            .into_iter()
+            .find(|text| text.starts_with(ROOT_PREFIX))
+            .ok_or_else(|| Error::from(ErrorKind::AddressResolve(None)))
+            .and_then(|text| TreeRoot::parse(&text, &url.public))?;
+        if self.trees[index].seq == Some(root.seq) {
+            return Ok((Vec::new(), Vec::new()));
+        }
+        trace!(target: "discovery", "Walking node tree {} at seq {}", url.domain, root.seq);
+
+        let mut records = Vec::new();
+        let mut links = Vec::new();
+        for entry in self.walk(&url.domain, &root.enr_root, stop) {
+            match entry {
+                TreeEntry::Record(record) => records.push(record),
+                _ => debug!(target: "discovery", "Unexpected link in records of {}", url.domain),
+            }
+        }
+        for entry in self.walk(&url.domain, &root.link_root, stop) {
+            match entry {
+                TreeEntry::Link(link) => links.push(link),
+                _ => debug!(target: "discovery", "Unexpected record in links of {}", url.domain),
+            }
+        }
+        if !stop.load(AtomicOrdering::SeqCst) {
+            self.trees[index].seq = Some(root.seq);
+        }
+        Ok((records, links))
+    }
+
+    /// Resolve the subtree below `hash`, returning its leaves.
+    fn walk(&self, domain: &str, hash: &str, stop: &AtomicBool) -> Vec<TreeEntry> {
+        let mut leaves = Vec::new();
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(hash.to_owned());
+        while let Some(hash) = queue.pop_front() {
+            if visited.len() >= MAX_TREE_QUERIES || stop.load(AtomicOrdering::SeqCst) {
+                break;
+            }
+            if !visited.insert(hash.clone()) {
+                continue;
+            }
+            match self.resolve_entry(domain, &hash) {
+                Ok(TreeEntry::Branch(children)) => queue.extend(children),
+                Ok(leaf) => leaves.push(leaf),
+                Err(e) => {
+                    debug!(target: "discovery", "Error resolving tree entry {}.{}: {:?}", hash, domain, e)
+                }
+            }
+        }
+        leaves
+    }
+
+    fn resolve_entry(&self, domain: &str, hash: &str) -> Result<TreeEntry, Error> {
+        let text = self
+            .resolver
+            .resolve_txt(&format!("{}.{}", hash, domain))?
+            .into_iter()
+            .find(|text| entry_hash(text).eq_ignore_ascii_case(hash))
+            .ok_or(ErrorKind::AddressResolve(None))?;
+        TreeEntry::parse(&text)
+    }
+}
+
+/// Keeps walking the trees on a background thread and collects the records found, which the
+/// host picks up with `drain`.
+pub struct DnsDiscoveryService {
+    records: Arc<Mutex<Vec<NodeRecord>>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl DnsDiscoveryService {
+    pub fn start(mut discovery: DnsDiscovery) -> Result<DnsDiscoveryService, Error> {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (thread_records, thread_stop) = (records.clone(), stop.clone());
+        thread::Builder::new()
+            .name("dns-discovery".into())
+            .spawn(move || {
+                while !thread_stop.load(AtomicOrdering::SeqCst) {
+                    let found = discovery.sync(&thread_stop);
+                    {
+                        let mut records = thread_records.lock();
+                        let room = MAX_PENDING_RECORDS.saturating_sub(records.len());
+                        records.extend(found.into_iter().take(room));
+                    }
+                    let mut waited = Duration::from_secs(0);
+                    while waited < TREE_REFRESH_INTERVAL
+                        && !thread_stop.load(AtomicOrdering::SeqCst)
+                    {
+                        thread::sleep(STOP_POLL_INTERVAL);
+                        waited += STOP_POLL_INTERVAL;
+                    }
+                }
+            })?;
+        Ok(DnsDiscoveryService { records, stop })
+    }
+
+    /// Take the records found since the last call.
+    pub fn drain(&self) -> Vec<NodeRecord> {
+        mem::replace(&mut *self.records.lock(), Vec::new())
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, AtomicOrdering::SeqCst);
+    }
+}
+
+impl Drop for DnsDiscoveryService {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::publickey::{Generator, KeyPair, Random};
+    use node_table::NodeEndpoint;
+    use rlp;
+    use secp256k1::SecretKey;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct MapResolver {
+        entries: Mutex<HashMap<String, String>>,
+    }
+
+    impl MapResolver {
+        fn insert(&self, name: String, text: String) {
+            self.entries.lock().insert(name, text);
+        }
+
+        /// Publish an entry under its hash and return the hash.
+        fn publish(&self, domain: &str, text: String) -> String {
+            let hash = entry_hash(&text);
+            self.insert(format!("{}.{}", hash, domain), text);
+            hash
+        }
+    }
+
+    impl TxtResolver for MapResolver {
+        fn resolve_txt(&self, name: &str) -> Result<Vec<String>, Error> {
+            Ok(self.entries.lock().get(name).cloned().into_iter().collect())
+        }
+    }
+
+    fn record(port: u16) -> NodeRecord {
+        let endpoint = NodeEndpoint {
+            address: SocketAddr::from_str(&format!("10.0.0.1:{}", port)).unwrap(),
+            udp_port: port,
+        };
+        NodeRecord::new(Random.generate().secret(), 1, &endpoint, None).unwrap()
+    }
+
+    fn record_text(record: &NodeRecord) -> String {
+        format!(
+            "{}{}",
+            RECORD_PREFIX,
+            base64::encode_config(&rlp::encode(record), base64::URL_SAFE_NO_PAD)
+        )
+    }
+
+    fn tree_url(key: &KeyPair, domain: &str) -> String {
+        let secret_key = SecretKey::from_slice(key.secret().as_bytes()).unwrap();
+        let public = PublicKey::from_secret_key(&Secp256k1::signing_only(), &secret_key);
+        format!(
+            "{}{}@{}",
+            URL_PREFIX,
+            base32_encode(&public.serialize()),
+            domain
+        )
+    }
+
+    fn root_text(key: &KeyPair, enr_root: &str, link_root: &str, seq: u64) -> String {
+        let signed = format!("{} e={} l={} seq={}", ROOT_PREFIX, enr_root, link_root, seq);
+        let message = Message::from_slice(keccak(signed.as_bytes()).as_bytes()).unwrap();
+        let secret_key = SecretKey::from_slice(key.secret().as_bytes()).unwrap();
+        let mut signature = Secp256k1::signing_only()
+            .sign(&message, &secret_key)
+            .serialize_compact()
+            .to_vec();
+        signature.push(0);
+        format!(
+            "{} sig={}",
+            signed,
+            base64::encode_config(&signature, base64::URL_SAFE_NO_PAD)
+        )
+    }
+
+    /// Publish a tree holding `records` and `links` and return its url.
+    fn publish_tree(
+        resolver: &MapResolver,
+        key: &KeyPair,
+        domain: &str,
+        records: &[NodeRecord],
+        links: &[String],
+    ) -> String {
+        let record_hashes: Vec<_> = records
+            .iter()
+            .map(|record| resolver.publish(domain, record_text(record)))
+            .collect();
+        let enr_root = resolver.publish(
+            domain,
+            format!("{}{}", BRANCH_PREFIX, record_hashes.join(",")),
+        );
+        let link_hashes: Vec<_> = links
+            .iter()
+            .map(|link| resolver.publish(domain, link.clone()))
+            .collect();
+        let link_root = resolver.publish(
+            domain,
+            format!("{}{}", BRANCH_PREFIX, link_hashes.join(",")),
+        );
+        resolver.insert(domain.to_owned(), root_text(key, &enr_root, &link_root, 1));
+        tree_url(key, domain)
+    }
+
+    #[test]
+    fn base32_round_trip() {
+        let data = b"\x02\x9c\x80\xff\x00node list";
+        let encoded = base32_encode(data);
+        assert!(!encoded.contains('='));
+        assert_eq!(base32_decode(&encoded).unwrap(), &data[..]);
+        assert_eq!(base32_decode(&encoded.to_lowercase()).unwrap(), &data[..]);
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        assert!(base32_decode("MZXW6YTBO1").is_none());
+    }
+
+    #[test]
+    fn txt_response_parsing() {
+        let mut response = encode_query(0x1234, "nodes.example.org").unwrap();
+        response[2] = 0x81;
+        response[3] = 0x80;
+        response[7] = 1;
+        // compressed name, type, class, ttl, data length
+        response.extend_from_slice(&[0xc0, 0x0c, 0, 16, 0, 1, 0, 0, 0x0e, 0x10, 0, 7]);
+        response.extend_from_slice(b"\x03abc\x02de");
+
+        assert_eq!(decode_response(0x4321, &response).unwrap(), None);
+        assert_eq!(
+            decode_response(0x1234, &response).unwrap(),
+            Some(vec!["abcde".to_owned()])
+        );
+        response.truncate(response.len() - 1);
+        assert!(decode_response(0x1234, &response).is_err());
+    }
+
+    #[test]
+    fn sync_tree_with_link() {
+        let resolver = Arc::new(MapResolver::default());
+        let linked_records = vec![record(30303)];
+        let linked = publish_tree(
+            &resolver,
+            &Random.generate(),
+            "linked.example.org",
+            &linked_records,
+            &[],
+        );
+        let records = vec![record(30304), record(30305), record(30306)];
+        let url = publish_tree(
+            &resolver,
+            &Random.generate(),
+            "nodes.example.org",
+            &records,
+            &[linked],
+        );
+
+        let stop = AtomicBool::new(false);
+        let mut discovery = DnsDiscovery::new(&[url], resolver).unwrap();
+        let found = discovery.sync(&stop);
+        assert_eq!(found.len(), 4);
+        for record in records.iter().chain(linked_records.iter()) {
+            assert!(found.contains(record));
+        }
+        // roots didn't change
+        assert!(discovery.sync(&stop).is_empty());
+    }
+
+    #[test]
+    fn tree_signed_by_other_key_is_ignored() {
+        let resolver = Arc::new(MapResolver::default());
+        let domain = "nodes.example.org";
+        publish_tree(&resolver, &Random.generate(), domain, &[record(30303)], &[]);
+        let url = tree_url(&Random.generate(), domain);
+
+        let mut discovery = DnsDiscovery::new(&[url], resolver).unwrap();
+        assert!(discovery.sync(&AtomicBool::new(false)).is_empty());
+    }
+
+    #[test]
+    fn tampered_entry_is_ignored() {
+        let resolver = Arc::new(MapResolver::default());
+        let domain = "nodes.example.org";
+        let records = vec![record(30303), record(30304)];
+        let url = publish_tree(&resolver, &Random.generate(), domain, &records, &[]);
+        // serve another record under the hash of the first one
+        let hash = entry_hash(&record_text(&records[0]));
+        resolver.insert(format!("{}.{}", hash, domain), record_text(&record(30305)));
+
+        let mut discovery = DnsDiscovery::new(&[url], resolver).unwrap();
+        assert_eq!(
+            discovery.sync(&AtomicBool::new(false)),
+            vec![records[1].clone()]
+        );
+    }
+
+    #[test]
+    fn url_validation() {
+        let url = "enrtree://AKA3AM6LPBYEUDMVNU3BSVQJ5AD45Y7YPOHJLEF6W26QOE4VTUDPE@all.mainnet.ethdisco.net";
+        assert!(validate_enrtree_url(url).is_none());
+        assert!(validate_enrtree_url(
+            "enrtree://AKA3AM6LPBYEUDMVNU3BSVQJ5AD45Y7YPOHJLEF6W26QOE4VTUDPE"
+        )
+        .is_some());
+        assert!(validate_enrtree_url("enrtree://AAAA@nodes.example.org").is_some());
+        assert!(validate_enrtree_url("enode://AKA3AM6LPBYEUDMVNU3BSVQJ5AD45Y7YPOHJLEF6W26QOE4VTUDPE@all.mainnet.ethdisco.net").is_some());
+    }
+}
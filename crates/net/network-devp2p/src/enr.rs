@@ -0,0 +1,333 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Ethereum Node Records (EIP-778) using the "v4" identity scheme.
+
+use crypto::publickey::Secret;
+use ethereum_types::H256;
+use hash::keccak;
+use network::{Error, ErrorKind};
+use node_table::{NodeEndpoint, NodeId};
+use parity_bytes::Bytes;
+use rlp::{self, Encodable, Rlp, RlpStream};
+use secp256k1::{Message, PublicKey, Secp256k1, SecretKey, Signature};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// Maximum size of an encoded node record.
+pub const MAX_RECORD_SIZE: usize = 300;
+
+const ID_SCHEME: &[u8] = b"v4";
+
+/// A signed node record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeRecord {
+    seq: u64,
+    /// Key/value pairs sorted by key. Values are kept RLP-encoded.
+    pairs: Vec<(Bytes, Bytes)>,
+    /// Uncompressed public key of the signer.
+    id: NodeId,
+    /// Full signed encoding.
+    raw: Bytes,
+}
+
+impl NodeRecord {
+    /// Create and sign a record advertising `endpoint`, with an optional RLP-encoded `eth` entry.
+    pub fn new(
+        secret: &Secret,
+        seq: u64,
+        endpoint: &NodeEndpoint,
+        eth_entry: Option<&[u8]>,
+    ) -> Result<NodeRecord, Error> {
+        let secp = Secp256k1::signing_only();
+        let secret_key =
+            SecretKey::from_slice(secret.as_bytes()).map_err(|_| Error::from(ErrorKind::Auth))?;
+        let public = PublicKey::from_secret_key(&secp, &secret_key);
+
+        let mut pairs: Vec<(Bytes, Bytes)> = Vec::with_capacity(7);
+        if let Some(eth_entry) = eth_entry {
+            pairs.push((b"eth".to_vec(), eth_entry.to_vec()));
+        }
+        pairs.push((b"id".to_vec(), encode_value(&ID_SCHEME)));
+        match endpoint.address.ip() {
+            IpAddr::V4(ip) => pairs.push((b"ip".to_vec(), encode_value(&&ip.octets()[..]))),
+            IpAddr::V6(ip) => pairs.push((b"ip6".to_vec(), encode_value(&&ip.octets()[..]))),
+        }
+        pairs.push((
+            b"secp256k1".to_vec(),
+            encode_value(&&public.serialize()[..]),
+        ));
+        pairs.push((b"tcp".to_vec(), encode_value(&endpoint.address.port())));
+        pairs.push((b"udp".to_vec(), encode_value(&endpoint.udp_port)));
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let hash = keccak(content(seq, &pairs));
+        let message = Message::from_slice(hash.as_bytes()).expect("hash is 32 bytes; qed");
+        let signature = secp.sign(&message, &secret_key).serialize_compact();
+
+        let mut stream = RlpStream::new_list(2 + pairs.len() * 2);
+        stream.append(&&signature[..]);
+        stream.append(&seq);
+        for (key, value) in &pairs {
+            stream.append(key);
+            stream.append_raw(value, 1);
+        }
+        let raw = stream.out();
+        if raw.len() > MAX_RECORD_SIZE {
+            return Err(ErrorKind::OversizedPacket.into());
+        }
+
+        let mut id = NodeId::default();
+        id.assign_from_slice(&public.serialize_uncompressed()[1..]);
+        Ok(NodeRecord {
+            seq,
+            pairs,
+            id,
+            raw,
+        })
+    }
+
+    /// Decode a record and verify its signature.
+    pub fn from_rlp(bytes: &[u8]) -> Result<NodeRecord, Error> {
+        if bytes.len() > MAX_RECORD_SIZE {
+            return Err(ErrorKind::OversizedPacket.into());
+        }
+        let rlp = Rlp::new(bytes);
+        let item_count = rlp.item_count()?;
+        if item_count < 2 || item_count % 2 != 0 {
+            return Err(ErrorKind::BadProtocol.into());
+        }
+        let signature = rlp.at(0)?.data()?.to_vec();
+        let seq: u64 = rlp.val_at(1)?;
+        let mut pairs: Vec<(Bytes, Bytes)> = Vec::with_capacity(item_count / 2 - 1);
+        for i in (2..item_count).step_by(2) {
+            let key = rlp.at(i)?.data()?.to_vec();
+            if pairs.last().map_or(false, |last| last.0 >= key) {
+                // keys must be sorted and unique
+                return Err(ErrorKind::BadProtocol.into());
+            }
+            pairs.push((key, rlp.at(i + 1)?.as_raw().to_vec()));
+        }
+
+        let mut record = NodeRecord {
+            seq,
+            pairs,
+            id: NodeId::default(),
+            raw: bytes.to_vec(),
+        };
+        if record.get_value::<Bytes>(b"id").as_ref().map(|s| &s[..]) != Some(ID_SCHEME) {
+            return Err(ErrorKind::BadProtocol.into());
+        }
+        let public = record
+            .get_value::<Bytes>(b"secp256k1")
+            .and_then(|key| PublicKey::from_slice(&key).ok())
+            .ok_or(ErrorKind::InvalidNodeId)?;
+        let mut signature =
+            Signature::from_compact(&signature).map_err(|_| Error::from(ErrorKind::Auth))?;
+        signature.normalize_s();
+        let hash = keccak(content(record.seq, &record.pairs));
+        let message = Message::from_slice(hash.as_bytes()).expect("hash is 32 bytes; qed");
+        Secp256k1::verification_only()
+            .verify(&message, &signature, &public)
+            .map_err(|_| Error::from(ErrorKind::Auth))?;
+
+        record
+            .id
+            .assign_from_slice(&public.serialize_uncompressed()[1..]);
+        Ok(record)
+    }
+
+    /// Sequence number of the record.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Public key of the node that signed the record.
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// Compressed public key of the node that signed the record.
+    pub fn compressed_key(&self) -> Option<Bytes> {
+        self.get_value(b"secp256k1")
+    }
+
+    /// RLP-encoded value stored under `key`.
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.pairs
+            .binary_search_by(|(k, _)| k[..].cmp(key))
+            .ok()
+            .map(|i| &self.pairs[i].1[..])
+    }
+
+    /// RLP-encoded `eth` entry, if any.
+    pub fn eth_entry(&self) -> Option<&[u8]> {
+        self.get(b"eth")
+    }
+
+    /// Advertised endpoint. The UDP port defaults to the TCP port when missing.
+    pub fn endpoint(&self) -> Option<NodeEndpoint> {
+        let ip = match (
+            self.get_value::<Bytes>(b"ip"),
+            self.get_value::<Bytes>(b"ip6"),
+        ) {
+            (Some(ref ip), _) if ip.len() == 4 => {
+                IpAddr::V4(Ipv4Addr::new(ip[0], ip[1], ip[2], ip[3]))
+            }
+            (_, Some(ref ip)) if ip.len() == 16 => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(ip);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+            _ => return None,
+        };
+        let tcp_port = self.get_value::<u16>(b"tcp");
+        let udp_port = self.get_value::<u16>(b"udp").or(tcp_port)?;
+        Some(NodeEndpoint {
+            address: SocketAddr::new(ip, tcp_port.unwrap_or(0)),
+            udp_port,
+        })
+    }
+
+    /// Keccak hash of the signed encoding.
+    pub fn hash(&self) -> H256 {
+        keccak(&self.raw)
+    }
+
+    fn get_value<T: rlp::Decodable>(&self, key: &[u8]) -> Option<T> {
+        self.get(key)
+            .and_then(|value| Rlp::new(value).as_val().ok())
+    }
+}
+
+impl Encodable for NodeRecord {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.append_raw(&self.raw, 1);
+    }
+}
+
+fn encode_value<E: Encodable>(value: &E) -> Bytes {
+    let mut stream = RlpStream::new();
+    stream.append(value);
+    stream.out()
+}
+
+/// Encoding of the signed part of a record: `[seq, k, v, ...]`.
+fn content(seq: u64, pairs: &[(Bytes, Bytes)]) -> Bytes {
+    let mut stream = RlpStream::new_list(1 + pairs.len() * 2);
+    stream.append(&seq);
+    for (key, value) in pairs {
+        stream.append(key);
+        stream.append_raw(value, 1);
+    }
+    stream.out()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::publickey::{Generator, KeyPair, Random};
+    use rustc_hex::FromHex;
+    use std::str::FromStr;
+
+    fn endpoint() -> NodeEndpoint {
+        NodeEndpoint {
+            address: SocketAddr::from_str("10.0.0.1:30303").unwrap(),
+            udp_port: 30301,
+        }
+    }
+
+    #[test]
+    fn record_round_trip() {
+        let key = Random.generate();
+        let eth_entry = {
+            let mut s = RlpStream::new_list(1);
+            s.begin_list(2)
+                .append(&&[0xfcu8, 0x64, 0xec, 0x04][..])
+                .append(&1_150_000u64);
+            s.out()
+        };
+        let record = NodeRecord::new(key.secret(), 7, &endpoint(), Some(&eth_entry)).unwrap();
+        assert_eq!(record.id(), *key.public());
+
+        let decoded = NodeRecord::from_rlp(&rlp::encode(&record)).unwrap();
+        assert_eq!(decoded, record);
+        assert_eq!(decoded.seq(), 7);
+        assert_eq!(decoded.id(), *key.public());
+        assert_eq!(decoded.endpoint(), Some(endpoint()));
+        assert_eq!(decoded.eth_entry(), Some(&eth_entry[..]));
+    }
+
+    #[test]
+    fn tampered_record_is_rejected() {
+        let key = Random.generate();
+        let record = NodeRecord::new(key.secret(), 1, &endpoint(), None).unwrap();
+        let mut encoded = rlp::encode(&record).to_vec();
+        // bump the UDP port
+        let last = encoded.len() - 1;
+        encoded[last] ^= 1;
+        assert!(NodeRecord::from_rlp(&encoded).is_err());
+    }
+
+    #[test]
+    fn unsorted_keys_are_rejected() {
+        let mut s = RlpStream::new_list(6);
+        s.append(&&[0u8; 64][..]);
+        s.append(&1u64);
+        s.append(&&b"udp"[..]);
+        s.append(&30303u16);
+        s.append(&&b"id"[..]);
+        s.append(&ID_SCHEME);
+        assert!(NodeRecord::from_rlp(&s.out()).is_err());
+    }
+
+    #[test]
+    fn decodes_eip778_example_record() {
+        let encoded = "f884b8407098ad865b00a582051940cb9cf36836572411a47278783077011599ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1145ccb9c01826964827634826970847f00000189736563703235366b31a103ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd31388375647082765f"
+            .from_hex()
+            .unwrap();
+        let record = NodeRecord::from_rlp(&encoded).unwrap();
+        let key = KeyPair::from_secret(
+            Secret::from_str("b71c71a67e1177ad4e901695e1b4b9ee17ae16c6668d313eac2f96dbcda3f291")
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(record.seq(), 1);
+        assert_eq!(record.id(), *key.public());
+        assert_eq!(
+            keccak(record.id()),
+            H256::from_str("a448f24c6d18e575453db13171562b71999873db5b286df957af199ec94617f7")
+                .unwrap()
+        );
+        assert_eq!(
+            record.compressed_key(),
+            Some(
+                "03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138"
+                    .from_hex()
+                    .unwrap()
+            )
+        );
+        // The record has no TCP port
+        assert_eq!(
+            record.endpoint(),
+            Some(NodeEndpoint {
+                address: SocketAddr::from_str("127.0.0.1:0").unwrap(),
+                udp_port: 30303,
+            })
+        );
+        assert_eq!(rlp::encode(&record).to_vec(), encoded);
+    }
+}
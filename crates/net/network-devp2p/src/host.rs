@@ -36,12 +36,14 @@ use std::{
 };
 
 use discovery::{Discovery, NodeEntry, TableUpdates, MAX_DATAGRAM_SIZE};
+use dns::{DnsDiscovery, DnsDiscoveryService, SystemResolver};
 use io::*;
 use ip_utils::{map_external_address, select_public_address};
 use network::{
     client_version::ClientVersion, ConnectionDirection, ConnectionFilter, DisconnectReason, Error,
     ErrorKind, NetworkConfiguration, NetworkContext as NetworkContextTrait, NetworkIoMessage,
-    NetworkProtocolHandler, NonReservedPeerMode, PacketId, PeerId, ProtocolId, SessionInfo,
+    NetworkProtocolHandler, NodeRecordFilter, NonReservedPeerMode, PacketId, PeerId, ProtocolId,
+    SessionInfo,
 };
 use node_table::*;
 use parity_path::restrict_permissions_owner;
@@ -64,6 +66,7 @@ const DISCOVERY_REFRESH: TimerToken = SYS_TIMER + 4;
 const FAST_DISCOVERY_REFRESH: TimerToken = SYS_TIMER + 5;
 const DISCOVERY_ROUND: TimerToken = SYS_TIMER + 6;
 const NODE_TABLE: TimerToken = SYS_TIMER + 7;
+const DNS_DISCOVERY: TimerToken = SYS_TIMER + 8;
 const FIRST_SESSION: StreamToken = 0;
 const LAST_SESSION: StreamToken = FIRST_SESSION + MAX_SESSIONS - 1;
 const USER_TIMER: TimerToken = LAST_SESSION + 256;
@@ -80,6 +83,11 @@ const FAST_DISCOVERY_REFRESH_TIMEOUT: Duration = Duration::from_secs(10);
 const DISCOVERY_ROUND_TIMEOUT: Duration = Duration::from_millis(300);
 // for NODE_TABLE TimerToken
 const NODE_TABLE_TIMEOUT: Duration = Duration::from_secs(300);
+// for DNS_DISCOVERY TimerToken
+const DNS_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Only peers scored below this by the protocol handlers are evicted to make room for new ones.
+const EVICTION_REPUTATION: i32 = 0;
 
 #[derive(Debug, PartialEq, Eq)]
 /// Protocol info
@@ -184,6 +192,18 @@ impl<'s> NetworkContextTrait for NetworkContext<'s> {
             .unwrap_or_else(|e| warn!("Error sending network IO message: {:?}", e));
     }
 
+    fn set_peer_reputation(&self, peer: PeerId, score: i32) {
+        if let Some(session) = self.resolve_session(peer) {
+            session.lock().reputation = score;
+        }
+    }
+
+    fn set_peer_fork_id(&self, peer: PeerId, fork_id: Vec<u8>) {
+        if let Some(session) = self.resolve_session(peer) {
+            session.lock().fork_id = Some(fork_id);
+        }
+    }
+
     fn is_expired(&self) -> bool {
         self.session.as_ref().map_or(false, |s| s.lock().expired())
     }
@@ -281,6 +301,7 @@ pub struct Host {
     tcp_listener: Mutex<TcpListener>,
     sessions: Arc<RwLock<Slab<SharedSession>>>,
     discovery: Mutex<Option<Discovery<'static>>>,
+    dns_discovery: Mutex<Option<DnsDiscoveryService>>,
     nodes: RwLock<NodeTable>,
     handlers: RwLock<HashMap<ProtocolId, Arc<dyn NetworkProtocolHandler + Sync>>>,
     timers: RwLock<HashMap<TimerToken, ProtocolTimer>>,
@@ -288,6 +309,7 @@ pub struct Host {
     reserved_nodes: RwLock<HashSet<NodeId>>,
     stopping: AtomicBool,
     filter: Option<Arc<dyn ConnectionFilter>>,
+    record_filter: RwLock<Option<Arc<dyn NodeRecordFilter>>>,
 }
 
 impl Host {
@@ -345,6 +367,7 @@ impl Host {
                 local_endpoint,
             }),
             discovery: Mutex::new(None),
+            dns_discovery: Mutex::new(None),
             udp_socket: Mutex::new(None),
             tcp_listener: Mutex::new(tcp_listener),
             sessions: Arc::new(RwLock::new(Slab::new_starting_at(
@@ -358,6 +381,7 @@ impl Host {
             reserved_nodes: RwLock::new(HashSet::new()),
             stopping: AtomicBool::new(false),
             filter,
+            record_filter: RwLock::new(None),
         };
 
         for n in boot_nodes {
@@ -410,6 +434,23 @@ impl Host {
         Ok(())
     }
 
+    /// Set the filter for the `eth` entry of node records. It provides the entry of the local
+    /// record and nodes advertising an incompatible one are not dialed.
+    pub fn set_node_record_filter(&self, filter: Arc<dyn NodeRecordFilter>) {
+        *self.record_filter.write() = Some(filter);
+        self.update_local_record();
+    }
+
+    fn update_local_record(&self) {
+        let eth_entry = match *self.record_filter.read() {
+            Some(ref filter) => filter.local_eth_entry(),
+            None => return,
+        };
+        if let Some(ref mut discovery) = *self.discovery.lock() {
+            discovery.set_eth_entry(&eth_entry);
+        }
+    }
+
     pub fn set_non_reserved_mode(
         &self,
         mode: NonReservedPeerMode,
@@ -465,6 +506,9 @@ impl Host {
 
     pub fn stop(&self, io: &IoContext<NetworkIoMessage>) {
         self.stopping.store(true, AtomicOrdering::SeqCst);
+        if let Some(ref dns_discovery) = *self.dns_discovery.lock() {
+            dns_discovery.stop();
+        }
         let mut to_kill = Vec::new();
         for e in self.sessions.read().iter() {
             let mut s = e.lock();
@@ -544,6 +588,9 @@ impl Host {
         };
 
         if let Some(mut discovery) = discovery {
+            if self.info.read().config.discovery_v5_enabled {
+                discovery.enable_v5();
+            }
             let mut udp_addr = local_endpoint.address;
             udp_addr.set_port(local_endpoint.udp_port);
             let socket = UdpSocket::bind(&udp_addr).expect("Error binding UDP socket");
@@ -551,11 +598,35 @@ impl Host {
 
             discovery.add_node_list(self.nodes.read().entries());
             *self.discovery.lock() = Some(discovery);
+            self.update_local_record();
             io.register_stream(DISCOVERY)?;
             io.register_timer(FAST_DISCOVERY_REFRESH, FAST_DISCOVERY_REFRESH_TIMEOUT)?;
             io.register_timer(DISCOVERY_REFRESH, DISCOVERY_REFRESH_TIMEOUT)?;
             io.register_timer(DISCOVERY_ROUND, DISCOVERY_ROUND_TIMEOUT)?;
         }
+
+        // Initialize DNS discovery. It doesn't need the UDP socket, so it runs with discovery
+        // disabled as well.
+        let dns_urls = {
+            let info = self.info.read();
+            if info.config.non_reserved_mode == NonReservedPeerMode::Accept {
+                info.config.dns_discovery.clone()
+            } else {
+                Vec::new()
+            }
+        };
+        if !dns_urls.is_empty() {
+            match SystemResolver::new()
+                .and_then(|resolver| DnsDiscovery::new(&dns_urls, Arc::new(resolver)))
+                .and_then(DnsDiscoveryService::start)
+            {
+                Ok(dns_discovery) => {
+                    *self.dns_discovery.lock() = Some(dns_discovery);
+                    io.register_timer(DNS_DISCOVERY, DNS_DISCOVERY_TIMEOUT)?;
+                }
+                Err(e) => warn!(target: "network", "Error starting DNS discovery: {:?}", e),
+            }
+        }
         io.register_timer(NODE_TABLE, NODE_TABLE_TIMEOUT)?;
         io.register_stream(TCP_ACCEPT)?;
         Ok(())
@@ -588,6 +659,33 @@ impl Host {
         (handshakes, egress, ingress)
     }
 
+    // returns the ready non-reserved session in the given direction with the lowest reputation
+    // below `EVICTION_REPUTATION`, if any.
+    fn worst_peer(
+        &self,
+        token: StreamToken,
+        originated: bool,
+        reserved_nodes: &HashSet<NodeId>,
+    ) -> Option<StreamToken> {
+        self.sessions
+            .read()
+            .iter()
+            .filter_map(|s| {
+                let s = s.try_lock()?;
+                if s.token() == token
+                    || !s.is_ready()
+                    || s.info.originated != originated
+                    || s.reputation >= EVICTION_REPUTATION
+                    || s.id().map_or(true, |id| reserved_nodes.contains(id))
+                {
+                    return None;
+                }
+                Some((s.reputation, s.token()))
+            })
+            .min()
+            .map(|(_, token)| token)
+    }
+
     fn connecting_to(&self, id: &NodeId) -> bool {
         self.sessions
             .read()
@@ -669,6 +767,7 @@ impl Host {
 
         let max_handshakes_per_round = max_handshakes / 2;
         let mut started: usize = 0;
+        let record_filter = self.record_filter.read().clone();
         for id in nodes
             .filter(|id| {
                 !self.have_session(id)
@@ -677,6 +776,7 @@ impl Host {
                     && self.filter.as_ref().map_or(true, |f| {
                         f.connection_allowed(&self_id, &id, ConnectionDirection::Outbound)
                     })
+                    && self.is_record_compatible(record_filter.as_ref(), id, &reserved_nodes)
             })
             .take(min(
                 max_handshakes_per_round,
@@ -689,6 +789,27 @@ impl Host {
         debug!(target: "network", "Connecting peers: {} sessions, {} pending + {} started", egress_count + ingress_count, handshake_count, started);
     }
 
+    // Nodes without record or `eth` entry are dialed, the eth handshake checks them.
+    fn is_record_compatible(
+        &self,
+        filter: Option<&Arc<dyn NodeRecordFilter>>,
+        id: &NodeId,
+        reserved_nodes: &HashSet<NodeId>,
+    ) -> bool {
+        let filter = match filter {
+            Some(filter) if !reserved_nodes.contains(id) => filter,
+            _ => return true,
+        };
+        let nodes = self.nodes.read();
+        match nodes.eth_entry(id) {
+            Some(eth_entry) if !filter.is_compatible(eth_entry) => {
+                trace!(target: "network", "Not connecting to {:?}: incompatible fork id", id);
+                false
+            }
+            _ => true,
+        }
+    }
+
     fn connect_peer(&self, id: &NodeId, io: &IoContext<NetworkIoMessage>) {
         if self.have_session(id) {
             trace!(target: "network", "Aborted connect. Node already connected.");
@@ -800,6 +921,7 @@ impl Host {
         let mut kill = false;
         let session = { self.sessions.read().get(token).cloned() };
         let mut ready_id = None;
+        let mut evict = None;
         if let Some(session) = session.clone() {
             {
                 loop {
@@ -860,7 +982,15 @@ impl Host {
                                 || (s.info.originated && egress_count > min_peers)
                                 || (!s.info.originated && ingress_count > max_ingress)
                             {
-                                if !reserved_nodes.contains(&id) {
+                                let worst = if reserved_only {
+                                    None
+                                } else {
+                                    self.worst_peer(token, s.info.originated, &reserved_nodes)
+                                };
+                                if let Some(worst) = worst {
+                                    // make room by dropping the worst behaving peer instead.
+                                    evict = Some(worst);
+                                } else if !reserved_nodes.contains(&id) {
                                     // only proceed if the connecting peer is reserved.
                                     trace!(target: "network", "Disconnecting non-reserved peer {:?}", id);
                                     s.disconnect(io, DisconnectReason::TooManyPeers);
@@ -901,7 +1031,11 @@ impl Host {
                             }
 
                             // Note connection success
-                            self.nodes.write().note_success(&id);
+                            {
+                                let mut nodes = self.nodes.write();
+                                nodes.note_success(&id);
+                                nodes.note_session(&id, &s.info, None);
+                            }
 
                             for (p, _) in self.handlers.read().iter() {
                                 if s.have_capability(*p) {
@@ -929,6 +1063,17 @@ impl Host {
                 self.kill_connection(token, io, true);
             }
 
+            if let Some(worst) = evict.filter(|_| !kill) {
+                let evicted = { self.sessions.read().get(worst).cloned() };
+                if let Some(evicted) = evicted {
+                    evicted
+                        .lock()
+                        .disconnect(io, DisconnectReason::TooManyPeers);
+                }
+                trace!(target: "network", "Evicted peer {} with low reputation", worst);
+                self.kill_connection(worst, io, false);
+            }
+
             let handlers = self.handlers.read();
             if !ready_data.is_empty() {
                 let duplicate = self.sessions.read().iter().any(|e| {
@@ -1073,12 +1218,20 @@ impl Host {
                     }
                     s.set_expired();
                     failure_id = s.id().cloned();
+                    if let Some(id) = failure_id.filter(|_| s.is_ready()) {
+                        self.nodes.write().note_session(
+                            &id,
+                            &s.info,
+                            s.fork_id.as_ref().map(|f| &f[..]),
+                        );
+                    }
                 }
                 deregister = remote || s.done();
             }
         }
         if let Some(id) = failure_id {
-            if remote {
+            // disconnecting on shutdown says nothing about the peer
+            if remote && !self.stopping.load(AtomicOrdering::SeqCst) {
                 self.nodes.write().note_failure(&id);
             }
         }
@@ -1103,6 +1256,46 @@ impl Host {
         }
     }
 
+    fn dns_discovered(&self, io: &IoContext<NetworkIoMessage>) {
+        let records = match *self.dns_discovery.lock() {
+            Some(ref dns_discovery) => dns_discovery.drain(),
+            None => return,
+        };
+        if records.is_empty() {
+            return;
+        }
+        let (self_id, allow_ips) = {
+            let info = self.info.read();
+            (*info.id(), info.config.ip_filter.clone())
+        };
+        let mut updates = TableUpdates {
+            added: HashMap::new(),
+            removed: HashSet::new(),
+            records: HashMap::new(),
+        };
+        for record in records {
+            let id = record.id();
+            match record.endpoint() {
+                Some(endpoint)
+                    if id != self_id
+                        && endpoint.is_allowed(&allow_ips)
+                        && endpoint.is_valid_sync_node() =>
+                {
+                    updates.added.insert(id, NodeEntry { id, endpoint });
+                    updates.records.insert(id, record);
+                }
+                _ => (),
+            }
+        }
+        trace!(target: "network", "DNS discovery found {} nodes", updates.added.len());
+        if let Some(ref mut discovery) = *self.discovery.lock() {
+            for entry in updates.added.values() {
+                discovery.add_node(entry.clone());
+            }
+        }
+        self.update_nodes(io, updates);
+    }
+
     fn update_nodes(&self, _io: &IoContext<NetworkIoMessage>, node_changes: TableUpdates) {
         let mut to_remove: Vec<PeerId> = Vec::new();
         {
@@ -1222,10 +1415,13 @@ impl IoHandler<NetworkIoMessage> for Host {
             }
             NODE_TABLE => {
                 trace!(target: "network", "Refreshing node table");
+                self.update_local_record();
                 let mut nodes = self.nodes.write();
                 nodes.clear_useless();
+                nodes.expire(&self.reserved_nodes.read());
                 nodes.save();
             }
+            DNS_DISCOVERY => self.dns_discovered(io),
             _ => match self.timers.read().get(&token).cloned() {
                 Some(timer) => match self.handlers.read().get(&timer.protocol).cloned() {
                     None => {
@@ -63,6 +63,7 @@
 #![allow(deprecated)]
 
 extern crate ansi_term; //TODO: remove this
+extern crate base64;
 extern crate bytes;
 extern crate crypto as rcrypto;
 extern crate ethcore_io as io;
@@ -83,6 +84,7 @@ extern crate parking_lot;
 extern crate rand;
 extern crate rlp;
 extern crate rustc_hex;
+extern crate secp256k1;
 extern crate serde;
 extern crate serde_json;
 extern crate slab;
@@ -105,6 +107,9 @@ extern crate assert_matches;
 
 mod connection;
 mod discovery;
+mod discv5;
+mod dns;
+mod enr;
 mod handshake;
 mod host;
 mod ip_utils;
@@ -116,7 +121,9 @@ pub use host::NetworkContext;
 pub use service::NetworkService;
 
 pub use connection::PAYLOAD_SOFT_LIMIT;
+pub use enr::NodeRecord;
 
+pub use dns::validate_enrtree_url;
 pub use io::TimerToken;
 pub use node_table::{validate_node_url, NodeId};
 
@@ -15,9 +15,10 @@
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
 use discovery::{NodeEntry, TableUpdates};
+use enr::NodeRecord;
 use ethereum_types::H512;
 use ip_utils::*;
-use network::{AllowIP, Error, ErrorKind, IpFilter};
+use network::{AllowIP, Error, ErrorKind, IpFilter, SessionInfo};
 use rand::seq::SliceRandom;
 use rlp::{DecoderError, Rlp, RlpStream};
 use serde_json;
@@ -199,12 +200,42 @@ impl NodeContact {
     }
 }
 
+/// What was learned about a node from the sessions with it. Kept across restarts.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NodeStats {
+    /// Time of the last successful connection
+    pub last_success: Option<SystemTime>,
+    /// Number of failed contacts since the last successful one
+    pub failures: u32,
+    /// Client version reported in the last session
+    pub client_version: Option<String>,
+    /// RLP-encoded `eth` entry (`[fork_id]`) announced by the node in the last session
+    pub fork_id: Option<Vec<u8>>,
+    /// Protocol capabilities reported in the last session
+    pub capabilities: Vec<String>,
+    /// Moving average of the ping latency
+    pub latency: Option<Duration>,
+}
+
+impl NodeStats {
+    /// Whether the node was successfully connected to in the last `NODE_EXPIRY`.
+    fn is_known_good(&self) -> bool {
+        self.last_success
+            .and_then(|t| t.elapsed().ok())
+            .map_or(false, |d| d < NODE_EXPIRY)
+    }
+}
+
 #[derive(Debug)]
 pub struct Node {
     pub id: NodeId,
     pub endpoint: NodeEndpoint,
     pub peer_type: PeerType,
     pub last_contact: Option<NodeContact>,
+    /// Latest node record learned through discovery
+    pub record: Option<NodeRecord>,
+    /// Connection statistics
+    pub stats: NodeStats,
 }
 
 impl Node {
@@ -214,6 +245,8 @@ impl Node {
             endpoint,
             peer_type: PeerType::Optional,
             last_contact: None,
+            record: None,
+            stats: NodeStats::default(),
         }
     }
 }
@@ -250,6 +283,8 @@ impl FromStr for Node {
             endpoint,
             peer_type: PeerType::Optional,
             last_contact: None,
+            record: None,
+            stats: NodeStats::default(),
         })
     }
 }
@@ -272,6 +307,9 @@ impl Hash for Node {
 
 const MAX_NODES: usize = 1024;
 const NODES_FILE: &str = "nodes.json";
+// Nodes failing this many times in a row are dropped, unless they were connected to recently.
+const MAX_FAILURES: u32 = 5;
+const NODE_EXPIRY: Duration = Duration::from_secs(60 * 60 * 24 * 30);
 
 /// Node table backed by disk file.
 pub struct NodeTable {
@@ -291,8 +329,14 @@ impl NodeTable {
 
     /// Add a node to table
     pub fn add_node(&mut self, mut node: Node) {
-        // preserve node last_contact
-        node.last_contact = self.nodes.get(&node.id).and_then(|n| n.last_contact);
+        // preserve node last_contact, record and stats
+        if let Some(known) = self.nodes.remove(&node.id) {
+            node.last_contact = known.last_contact;
+            node.record = node.record.or(known.record);
+            node.stats = known.stats;
+        } else {
+            node.last_contact = None;
+        }
         self.nodes.insert(node.id, node);
     }
 
@@ -301,12 +345,15 @@ impl NodeTable {
     /// is:
     /// - Contacts that aren't recent (older than 1 week) are discarded
     /// - (1) Nodes with a successful contact are ordered (most recent success first)
-    /// - (2) Nodes with unknown contact (older than 1 week or new nodes) are randomly shuffled
-    /// - (3) Nodes with a failed contact are ordered (oldest failure first)
-    /// - The final result is the concatenation of (1), (2) and (3)
+    /// - (2) Nodes with unknown contact that were successfully connected to in the last
+    ///   30 days are ordered (lowest latency first)
+    /// - (3) Other nodes with unknown contact (new nodes) are randomly shuffled
+    /// - (4) Nodes with a failed contact are ordered (oldest failure first)
+    /// - The final result is the concatenation of (1), (2), (3) and (4)
     fn ordered_entries(&self) -> Vec<&Node> {
         let mut success = Vec::new();
         let mut failures = Vec::new();
+        let mut known_good = Vec::new();
         let mut unknown = Vec::new();
 
         let nodes = self
@@ -323,6 +370,9 @@ impl NodeTable {
                 Some(&NodeContact::Failure(_)) => {
                     failures.push(node);
                 }
+                None if node.stats.is_known_good() => {
+                    known_good.push(node);
+                }
                 None => {
                     unknown.push(node);
                 }
@@ -351,9 +401,17 @@ impl NodeTable {
             a.time().cmp(&b.time())
         });
 
+        // nodes without a latency sample come last
+        known_good.sort_by_key(|n| {
+            n.stats
+                .latency
+                .unwrap_or(Duration::from_secs(u64::max_value()))
+        });
+
         let mut rng = rand::thread_rng();
         unknown.shuffle(&mut rng);
 
+        success.append(&mut known_good);
         success.append(&mut unknown);
         success.append(&mut failures);
         success
@@ -391,6 +449,26 @@ impl NodeTable {
         self.nodes.contains_key(id)
     }
 
+    /// Latest known node record of a node.
+    pub fn record(&self, id: &NodeId) -> Option<&NodeRecord> {
+        self.nodes.get(id).and_then(|n| n.record.as_ref())
+    }
+
+    /// RLP-encoded `eth` entry of a node, taken from its node record or else from the last
+    /// session with it.
+    pub fn eth_entry(&self, id: &NodeId) -> Option<&[u8]> {
+        let node = self.nodes.get(id)?;
+        node.record
+            .as_ref()
+            .and_then(|r| r.eth_entry())
+            .or_else(|| node.stats.fork_id.as_ref().map(|f| &f[..]))
+    }
+
+    /// Connection statistics of a node.
+    pub fn stats(&self, id: &NodeId) -> Option<&NodeStats> {
+        self.nodes.get(id).map(|n| &n.stats)
+    }
+
     /// Apply table changes coming from discovery
     pub fn update(&mut self, mut update: TableUpdates, reserved: &HashSet<NodeId>) {
         for (_, node) in update.added.drain() {
@@ -400,6 +478,17 @@ impl NodeTable {
                 .or_insert_with(|| Node::new(node.id, node.endpoint.clone()));
             entry.endpoint = node.endpoint;
         }
+        for (id, record) in update.records.drain() {
+            if let Some(node) = self.nodes.get_mut(&id) {
+                if node
+                    .record
+                    .as_ref()
+                    .map_or(true, |r| r.seq() < record.seq())
+                {
+                    node.record = Some(record);
+                }
+            }
+        }
         for r in update.removed {
             if !reserved.contains(&r) {
                 self.nodes.remove(&r);
@@ -411,13 +500,54 @@ impl NodeTable {
     pub fn note_failure(&mut self, id: &NodeId) {
         if let Some(node) = self.nodes.get_mut(id) {
             node.last_contact = Some(NodeContact::failure());
+            node.stats.failures = node.stats.failures.saturating_add(1);
         }
     }
 
     /// Set last contact as success for a node
     pub fn note_success(&mut self, id: &NodeId) {
         if let Some(node) = self.nodes.get_mut(id) {
-            node.last_contact = Some(NodeContact::success());
+            let contact = NodeContact::success();
+            node.last_contact = Some(contact);
+            node.stats.last_success = Some(contact.time());
+            node.stats.failures = 0;
+        }
+    }
+
+    /// Record what was learned about a node during a session with it.
+    pub fn note_session(&mut self, id: &NodeId, info: &SessionInfo, fork_id: Option<&[u8]>) {
+        if let Some(node) = self.nodes.get_mut(id) {
+            let stats = &mut node.stats;
+            stats.client_version = Some(info.client_version.to_string());
+            if !info.peer_capabilities.is_empty() {
+                stats.capabilities = info
+                    .peer_capabilities
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect();
+            }
+            if let Some(fork_id) = fork_id {
+                stats.fork_id = Some(fork_id.to_vec());
+            }
+            if let Some(ping) = info.ping {
+                stats.latency = Some(match stats.latency {
+                    Some(latency) => (latency * 3 + ping) / 4,
+                    None => ping,
+                });
+            }
+        }
+    }
+
+    /// Drop nodes that keep failing and have not been connected to in the last 30 days.
+    pub fn expire(&mut self, reserved: &HashSet<NodeId>) {
+        let before = self.nodes.len();
+        self.nodes.retain(|id, node| {
+            reserved.contains(id)
+                || node.stats.failures < MAX_FAILURES
+                || node.stats.is_known_good()
+        });
+        if self.nodes.len() != before {
+            debug!(target: "network", "Expired {} dead nodes", before - self.nodes.len());
         }
     }
 
@@ -509,6 +639,7 @@ pub fn validate_node_url(url: &str) -> Option<Error> {
 
 mod json {
     use super::*;
+    use rustc_hex::{FromHex, ToHex};
 
     #[derive(Serialize, Deserialize)]
     pub struct NodeTable {
@@ -540,6 +671,18 @@ mod json {
     pub struct Node {
         pub url: String,
         pub last_contact: Option<NodeContact>,
+        #[serde(default)]
+        pub last_success: Option<u64>,
+        #[serde(default)]
+        pub failures: u32,
+        #[serde(default)]
+        pub client_version: Option<String>,
+        #[serde(default)]
+        pub fork_id: Option<String>,
+        #[serde(default)]
+        pub capabilities: Vec<String>,
+        #[serde(default)]
+        pub latency_ms: Option<u64>,
     }
 
     impl Node {
@@ -547,6 +690,16 @@ mod json {
             match super::Node::from_str(&self.url) {
                 Ok(mut node) => {
                     node.last_contact = self.last_contact.map(|c| c.into_node_contact());
+                    node.stats = super::NodeStats {
+                        last_success: self
+                            .last_success
+                            .map(|s| time::UNIX_EPOCH + Duration::from_secs(s)),
+                        failures: self.failures,
+                        client_version: self.client_version,
+                        fork_id: self.fork_id.and_then(|f| f.from_hex().ok()),
+                        capabilities: self.capabilities,
+                        latency: self.latency_ms.map(Duration::from_millis),
+                    };
                     Some(node)
                 }
                 _ => None,
@@ -567,9 +720,19 @@ mod json {
                     .map(|d| NodeContact::Failure(d.as_secs())),
             });
 
+            let stats = &node.stats;
             Node {
                 url: format!("{}", node),
                 last_contact,
+                last_success: stats
+                    .last_success
+                    .and_then(|t| t.duration_since(time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs()),
+                failures: stats.failures,
+                client_version: stats.client_version.clone(),
+                fork_id: stats.fork_id.as_ref().map(|f| f.to_hex()),
+                capabilities: stats.capabilities.clone(),
+                latency_ms: stats.latency.map(|l| l.as_millis() as u64),
             }
         }
     }
@@ -728,6 +891,83 @@ mod tests {
         }
     }
 
+    #[test]
+    fn table_stats_save_load() {
+        let tempdir = TempDir::new("").unwrap();
+        let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+        let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+        let node3 = Node::from_str("enode://c979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+        let id1 = node1.id;
+        let id2 = node2.id;
+        let id3 = node3.id;
+        let stats = NodeStats {
+            last_success: Some(time::UNIX_EPOCH + Duration::from_secs(1_600_000_000)),
+            failures: 0,
+            client_version: Some("OpenEthereum/v3.3.4".into()),
+            fork_id: Some(vec![0xc7, 0xc6, 0x84, 0xfc, 0x64, 0xec, 0x04, 0x80]),
+            capabilities: vec!["eth/66".into()],
+            latency: Some(Duration::from_millis(40)),
+        };
+
+        {
+            let mut table = NodeTable::new(Some(tempdir.path().to_str().unwrap().to_owned()));
+            table.add_node(node1);
+            table.add_node(node2);
+            table.add_node(node3);
+            table.note_success(&id2);
+            table.get_mut(&id1).unwrap().stats = stats.clone();
+            // known good, but slower
+            table.get_mut(&id3).unwrap().stats = NodeStats {
+                last_success: Some(SystemTime::now()),
+                latency: Some(Duration::from_millis(200)),
+                ..Default::default()
+            };
+        }
+
+        {
+            let table = NodeTable::new(Some(tempdir.path().to_str().unwrap().to_owned()));
+            assert_eq!(table.stats(&id1), Some(&stats));
+            assert_eq!(
+                table.eth_entry(&id1),
+                stats.fork_id.as_ref().map(|f| &f[..])
+            );
+            assert_eq!(table.stats(&id2).unwrap().failures, 0);
+            assert!(table.stats(&id2).unwrap().last_success.is_some());
+
+            let r = table.nodes(&IpFilter::default());
+            assert_eq!(r[0][..], id2[..]); // latest success
+            assert_eq!(r[1][..], id3[..]); // known good
+            assert_eq!(r[2][..], id1[..]); // success too long ago
+        }
+    }
+
+    #[test]
+    fn table_expire() {
+        let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+        let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+        let node3 = Node::from_str("enode://c979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+        let (id1, id2, id3) = (node1.id, node2.id, node3.id);
+        let mut table = NodeTable::new(None);
+        table.add_node(node1);
+        table.add_node(node2);
+        table.add_node(node3);
+
+        table.note_success(&id2);
+        for _ in 0..MAX_FAILURES {
+            table.note_failure(&id1);
+            table.note_failure(&id2);
+            table.note_failure(&id3);
+        }
+
+        let mut reserved = HashSet::new();
+        reserved.insert(id3);
+        table.expire(&reserved);
+
+        assert!(!table.contains(&id1));
+        assert!(table.contains(&id2)); // recently connected to
+        assert!(table.contains(&id3)); // reserved
+    }
+
     #[test]
     fn custom_allow() {
         let filter = IpFilter {
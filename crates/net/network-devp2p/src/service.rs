@@ -19,7 +19,7 @@ use host::Host;
 use io::*;
 use network::{
     ConnectionFilter, Error, NetworkConfiguration, NetworkContext, NetworkIoMessage,
-    NetworkProtocolHandler, NonReservedPeerMode, PeerId, ProtocolId,
+    NetworkProtocolHandler, NodeRecordFilter, NonReservedPeerMode, PeerId, ProtocolId,
 };
 use parking_lot::RwLock;
 use std::{net::SocketAddr, ops::RangeInclusive, sync::Arc};
@@ -49,6 +49,7 @@ pub struct NetworkService {
     host_handler: Arc<HostHandler>,
     config: NetworkConfiguration,
     filter: Option<Arc<dyn ConnectionFilter>>,
+    record_filter: RwLock<Option<Arc<dyn NodeRecordFilter>>>,
 }
 
 impl NetworkService {
@@ -69,9 +70,18 @@ impl NetworkService {
             config,
             host_handler,
             filter,
+            record_filter: RwLock::new(None),
         })
     }
 
+    /// Set the filter for the `eth` entry of node records found through discovery.
+    pub fn set_node_record_filter(&self, filter: Arc<dyn NodeRecordFilter>) {
+        if let Some(ref host) = *self.host.read() {
+            host.set_node_record_filter(filter.clone());
+        }
+        *self.record_filter.write() = Some(filter);
+    }
+
     /// Register a new protocol handler with the event loop.
     pub fn register_protocol(
         &self,
@@ -126,6 +136,9 @@ impl NetworkService {
                 Host::new(self.config.clone(), self.filter.clone())
                     .map_err(|err| (err, listen_addr))?,
             );
+            if let Some(ref filter) = *self.record_filter.read() {
+                h.set_node_record_filter(filter.clone());
+            }
             self.io_service
                 .register_handler(h.clone())
                 .map_err(|err| (err.into(), listen_addr))?;
@@ -60,6 +60,10 @@ enum ProtocolState {
 pub struct Session {
     /// Shared session information
     pub info: SessionInfo,
+    /// Reputation score reported by the protocol handlers
+    pub reputation: i32,
+    /// RLP-encoded `eth` entry (`[fork_id]`) announced by the peer
+    pub fork_id: Option<Vec<u8>>,
     /// Session ready flag. Set after successful Hello packet exchange
     had_hello: bool,
     /// Session is no longer active flag.
@@ -126,6 +130,8 @@ impl Session {
         Ok(Session {
             state: State::Handshake(handshake),
             had_hello: false,
+            reputation: 0,
+            fork_id: None,
             info: SessionInfo {
                 id: id.cloned(),
                 client_version: ClientVersion::from(""),
@@ -136,6 +142,7 @@ impl Session {
                 originated,
                 remote_address: "Handshake".to_owned(),
                 local_address: local_addr,
+                traffic: Default::default(),
             },
             ping_time: Instant::now(),
             pong_time: None,
@@ -341,6 +348,7 @@ impl Session {
             }
             None => packet_id,
         };
+        let key = protocol.map(|protocol| (protocol, packet_id));
         let mut rlp = RlpStream::new();
         rlp.append(&(u32::from(pid)));
         let mut compressed = Vec::new();
@@ -354,7 +362,9 @@ impl Session {
             payload = &compressed[0..len];
         }
         rlp.append_raw(payload, 1);
-        self.send(io, &rlp.drain())
+        let packet = rlp.drain();
+        self.info.traffic.note_egress(key, packet.len());
+        self.send(io, &packet)
     }
 
     /// Keep this session alive. Returns false if ping timeout happened
@@ -418,6 +428,9 @@ impl Session {
         if packet_id != PACKET_HELLO && packet_id != PACKET_DISCONNECT && !self.had_hello {
             return Err(ErrorKind::BadProtocol.into());
         }
+        if packet_id < PACKET_USER || packet_id > PACKET_LAST {
+            self.info.traffic.note_ingress(None, packet.data.len());
+        }
         let data = if self.compression {
             let compressed = &packet.data[1..];
             if snappy::decompressed_len(&compressed)? > MAX_PAYLOAD_SIZE {
@@ -461,6 +474,7 @@ impl Session {
                     i += 1;
                     if i == self.info.capabilities.len() {
                         debug!(target: "network", "Unknown packet: {:?}", packet_id);
+                        self.info.traffic.note_ingress(None, packet.data.len());
                         return Ok(SessionData::Continue);
                     }
                 }
@@ -468,6 +482,9 @@ impl Session {
                 // map to protocol
                 let protocol = self.info.capabilities[i].protocol;
                 let protocol_packet_id = packet_id - self.info.capabilities[i].id_offset;
+                self.info
+                    .traffic
+                    .note_ingress(Some((protocol, protocol_packet_id)), packet.data.len());
 
                 match *self
                     .protocol_states
@@ -513,7 +530,9 @@ impl Session {
             .append_list(&host.capabilities)
             .append(&host.local_endpoint.address.port())
             .append(host.id());
-        self.send(io, &rlp.drain())
+        let packet = rlp.drain();
+        self.info.traffic.note_egress(None, packet.len());
+        self.send(io, &packet)
     }
 
     fn read_hello<Message>(
@@ -44,10 +44,14 @@ pub mod client_version;
 
 mod connection_filter;
 mod error;
+mod node_record_filter;
+mod traffic;
 
 pub use connection_filter::{ConnectionDirection, ConnectionFilter};
 pub use error::{DisconnectReason, Error, ErrorKind};
 pub use io::TimerToken;
+pub use node_record_filter::NodeRecordFilter;
+pub use traffic::{TrafficCounter, TrafficStats};
 
 use client_version::ClientVersion;
 use crypto::publickey::Secret;
@@ -126,6 +130,8 @@ pub struct SessionInfo {
     pub remote_address: String,
     /// Local endpoint address of the session
     pub local_address: String,
+    /// Traffic exchanged over the session
+    pub traffic: TrafficStats,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -196,8 +202,12 @@ pub struct NetworkConfiguration {
     pub nat_enabled: bool,
     /// Enable discovery
     pub discovery_enabled: bool,
+    /// Enable discovery v5 (topic-less, sharing the discovery v4 UDP port)
+    pub discovery_v5_enabled: bool,
     /// List of initial node addresses
     pub boot_nodes: Vec<String>,
+    /// EIP-1459 node tree URLs (`enrtree://<key>@<domain>`) to discover nodes from
+    pub dns_discovery: Vec<String>,
     /// Use provided node key instead of default
     pub use_secret: Option<Secret>,
     /// Minimum number of connected peers to maintain
@@ -235,7 +245,9 @@ impl NetworkConfiguration {
             udp_port: None,
             nat_enabled: true,
             discovery_enabled: true,
+            discovery_v5_enabled: false,
             boot_nodes: Vec::new(),
+            dns_discovery: Vec::new(),
             use_secret: None,
             min_peers: 25,
             max_peers: 50,
@@ -293,6 +305,14 @@ pub trait NetworkContext {
     /// Disconnect peer. Reconnect can be attempted later.
     fn disconnect_peer(&self, peer: PeerId);
 
+    /// Record the protocol-level reputation of a peer. Peers with a low
+    /// reputation are the first to be evicted when the peer table is full.
+    fn set_peer_reputation(&self, peer: PeerId, score: i32);
+
+    /// Record the fork id announced by a peer, RLP-encoded like the `eth` entry of a node record.
+    /// It is remembered across restarts so that nodes on other chains are not dialed again.
+    fn set_peer_fork_id(&self, peer: PeerId, fork_id: Vec<u8>);
+
     /// Check if the session is still active.
     fn is_expired(&self) -> bool;
 
@@ -345,6 +365,14 @@ where
         (**self).disconnect_peer(peer)
     }
 
+    fn set_peer_reputation(&self, peer: PeerId, score: i32) {
+        (**self).set_peer_reputation(peer, score)
+    }
+
+    fn set_peer_fork_id(&self, peer: PeerId, fork_id: Vec<u8>) {
+        (**self).set_peer_fork_id(peer, fork_id)
+    }
+
     fn is_expired(&self) -> bool {
         (**self).is_expired()
     }
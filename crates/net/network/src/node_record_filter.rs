@@ -0,0 +1,27 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Node record filter trait.
+
+/// Filter for the `eth` entry of node records (EIP-778) learned through discovery.
+/// Nodes with an incompatible entry are never dialed.
+pub trait NodeRecordFilter: Send + Sync {
+    /// RLP-encoded `eth` entry to advertise in the local node record.
+    fn local_eth_entry(&self) -> Vec<u8>;
+
+    /// Returns `true` if a node advertising the given RLP-encoded `eth` entry may be dialed.
+    fn is_compatible(&self, eth_entry: &[u8]) -> bool;
+}
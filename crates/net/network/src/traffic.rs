@@ -0,0 +1,206 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Per-session traffic accounting.
+
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use {PacketId, ProtocolId};
+
+/// Period over which transfer rates are averaged.
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// Bytes and messages transferred in one direction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrafficCounter {
+    /// Number of bytes, including the packet id.
+    pub bytes: u64,
+    /// Number of messages.
+    pub messages: u64,
+}
+
+impl TrafficCounter {
+    fn note(&mut self, bytes: usize) {
+        self.bytes += bytes as u64;
+        self.messages += 1;
+    }
+}
+
+/// Transfer rate averaged over the last complete window.
+#[derive(Debug, Clone)]
+struct RollingRate {
+    window_start: Instant,
+    window_bytes: u64,
+    rate: f64,
+}
+
+impl RollingRate {
+    fn new(now: Instant) -> Self {
+        RollingRate {
+            window_start: now,
+            window_bytes: 0,
+            rate: 0.0,
+        }
+    }
+
+    fn note(&mut self, bytes: usize, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed >= RATE_WINDOW {
+            self.rate = self.window_bytes as f64 / elapsed.as_secs_f64();
+            self.window_start = now;
+            self.window_bytes = 0;
+        }
+        self.window_bytes += bytes as u64;
+    }
+
+    fn rate(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed >= RATE_WINDOW * 2 {
+            // nothing was noted during the last window
+            0.0
+        } else if elapsed >= RATE_WINDOW {
+            self.window_bytes as f64 / elapsed.as_secs_f64()
+        } else {
+            self.rate
+        }
+    }
+}
+
+/// Traffic exchanged with a peer, in total and by protocol packet.
+#[derive(Debug, Clone)]
+pub struct TrafficStats {
+    /// Everything received from the peer.
+    pub ingress: TrafficCounter,
+    /// Everything sent to the peer.
+    pub egress: TrafficCounter,
+    /// Received and sent subprotocol packets by protocol and packet id.
+    pub packets: BTreeMap<(ProtocolId, PacketId), (TrafficCounter, TrafficCounter)>,
+    ingress_rate: RollingRate,
+    egress_rate: RollingRate,
+}
+
+impl Default for TrafficStats {
+    fn default() -> Self {
+        let now = Instant::now();
+        TrafficStats {
+            ingress: TrafficCounter::default(),
+            egress: TrafficCounter::default(),
+            packets: BTreeMap::new(),
+            ingress_rate: RollingRate::new(now),
+            egress_rate: RollingRate::new(now),
+        }
+    }
+}
+
+impl TrafficStats {
+    /// Note a received packet. Base protocol packets come without a protocol.
+    pub fn note_ingress(&mut self, protocol: Option<(ProtocolId, PacketId)>, bytes: usize) {
+        self.note_ingress_at(protocol, bytes, Instant::now())
+    }
+
+    /// Note a sent packet. Base protocol packets come without a protocol.
+    pub fn note_egress(&mut self, protocol: Option<(ProtocolId, PacketId)>, bytes: usize) {
+        self.note_egress_at(protocol, bytes, Instant::now())
+    }
+
+    fn note_ingress_at(
+        &mut self,
+        protocol: Option<(ProtocolId, PacketId)>,
+        bytes: usize,
+        now: Instant,
+    ) {
+        self.ingress.note(bytes);
+        self.ingress_rate.note(bytes, now);
+        if let Some(key) = protocol {
+            self.packets.entry(key).or_default().0.note(bytes);
+        }
+    }
+
+    fn note_egress_at(
+        &mut self,
+        protocol: Option<(ProtocolId, PacketId)>,
+        bytes: usize,
+        now: Instant,
+    ) {
+        self.egress.note(bytes);
+        self.egress_rate.note(bytes, now);
+        if let Some(key) = protocol {
+            self.packets.entry(key).or_default().1.note(bytes);
+        }
+    }
+
+    /// Bytes per second received from the peer recently.
+    pub fn ingress_rate(&self) -> f64 {
+        self.ingress_rate.rate(Instant::now())
+    }
+
+    /// Bytes per second sent to the peer recently.
+    pub fn egress_rate(&self) -> f64 {
+        self.egress_rate.rate(Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_types::U64;
+
+    #[test]
+    fn counts_packets_by_protocol() {
+        let eth = U64::from(0x657468);
+        let mut stats = TrafficStats::default();
+        stats.note_ingress(Some((eth, 2)), 100);
+        stats.note_ingress(Some((eth, 2)), 50);
+        stats.note_egress(Some((eth, 3)), 10);
+        stats.note_egress(None, 5);
+
+        assert_eq!(
+            stats.ingress,
+            TrafficCounter {
+                bytes: 150,
+                messages: 2
+            }
+        );
+        assert_eq!(
+            stats.egress,
+            TrafficCounter {
+                bytes: 15,
+                messages: 2
+            }
+        );
+        assert_eq!(stats.packets[&(eth, 2)].0.bytes, 150);
+        assert_eq!(stats.packets[&(eth, 2)].1, TrafficCounter::default());
+        assert_eq!(stats.packets[&(eth, 3)].1.messages, 1);
+        assert_eq!(stats.packets.len(), 2);
+    }
+
+    #[test]
+    fn rate_is_averaged_over_last_window() {
+        let start = Instant::now();
+        let mut stats = TrafficStats::default();
+        stats.ingress_rate = RollingRate::new(start);
+        stats.note_ingress_at(None, 5_000, start);
+        stats.note_ingress_at(None, 5_000, start + Duration::from_secs(5));
+        assert_eq!(stats.ingress_rate.rate(start + Duration::from_secs(5)), 0.0);
+
+        stats.note_ingress_at(None, 100, start + RATE_WINDOW);
+        assert_eq!(stats.ingress_rate.rate(start + RATE_WINDOW), 1_000.0);
+        assert_eq!(stats.ingress_rate.rate(start + RATE_WINDOW * 3), 0.0);
+    }
+}
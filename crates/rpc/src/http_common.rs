@@ -26,6 +26,16 @@ pub trait HttpMetaExtractor: Send + Sync + 'static {
     type Metadata: jsonrpc_core::Metadata;
     /// Extracts metadata from given params.
     fn read_metadata(&self, origin: Option<String>, user_agent: Option<String>) -> Self::Metadata;
+    /// Extracts metadata from given params and the `Authorization` header.
+    /// The header is ignored by default.
+    fn read_metadata_with_authorization(
+        &self,
+        origin: Option<String>,
+        user_agent: Option<String>,
+        _authorization: Option<String>,
+    ) -> Self::Metadata {
+        self.read_metadata(origin, user_agent)
+    }
 }
 
 pub struct MetaExtractor<T> {
@@ -50,6 +60,8 @@ where
 
         let origin = as_string(req.headers().get("origin"));
         let user_agent = as_string(req.headers().get("user-agent"));
-        self.extractor.read_metadata(origin, user_agent)
+        let authorization = as_string(req.headers().get("authorization"));
+        self.extractor
+            .read_metadata_with_authorization(origin, user_agent, authorization)
     }
 }
@@ -0,0 +1,333 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! JWT authentication of HTTP and WebSockets requests.
+//!
+//! Tokens are signed with HS256 using a 32 byte shared secret and must carry an `iat` (issued at)
+//! claim within `IAT_LEEWAY` seconds of the current time, as required by the execution-API
+//! authentication spec.
+
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use base64;
+use crypto::hmac;
+use rand::{rngs::OsRng, RngCore};
+use rustc_hex::{FromHex, ToHex};
+use serde_json;
+
+use authcodes::{DefaultTimeProvider, TimeProvider};
+
+/// Maximal difference in seconds between the `iat` claim and the current time.
+pub const IAT_LEEWAY: u64 = 60;
+/// Length of the shared secret in bytes.
+const SECRET_LENGTH: usize = 32;
+
+/// Reasons for rejecting a token.
+#[derive(Debug, PartialEq)]
+pub enum JwtError {
+    /// Request doesn't carry a bearer token.
+    MissingToken,
+    /// Token is not a valid JWT.
+    Malformed,
+    /// Token is signed with an algorithm other than HS256.
+    UnsupportedAlgorithm(String),
+    /// Signature doesn't match the shared secret.
+    InvalidSignature,
+    /// Token has no `iat` claim.
+    MissingIssuedAt,
+    /// `iat` claim is too far from the current time.
+    Stale(u64),
+}
+
+impl fmt::Display for JwtError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JwtError::MissingToken => write!(f, "missing bearer token"),
+            JwtError::Malformed => write!(f, "malformed token"),
+            JwtError::UnsupportedAlgorithm(ref alg) => write!(f, "unsupported algorithm: {}", alg),
+            JwtError::InvalidSignature => write!(f, "invalid signature"),
+            JwtError::MissingIssuedAt => write!(f, "missing iat claim"),
+            JwtError::Stale(iat) => write!(f, "stale token issued at {}", iat),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Header {
+    alg: String,
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    iat: Option<u64>,
+}
+
+/// Shared secret used to authenticate tokens.
+#[derive(Clone, PartialEq)]
+pub struct JwtSecret([u8; SECRET_LENGTH]);
+
+impl fmt::Debug for JwtSecret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "JwtSecret(..)")
+    }
+}
+
+impl JwtSecret {
+    /// Generates a new random secret.
+    pub fn random() -> Self {
+        let mut secret = [0u8; SECRET_LENGTH];
+        OsRng.fill_bytes(&mut secret);
+        JwtSecret(secret)
+    }
+
+    /// Parses a hex encoded secret, optionally prefixed with `0x`.
+    pub fn from_hex(hex: &str) -> Result<Self, String> {
+        let hex = hex.trim();
+        let hex = hex.trim_start_matches("0x");
+        let bytes: Vec<u8> = hex
+            .from_hex()
+            .map_err(|e| format!("Invalid JWT secret: {}", e))?;
+        if bytes.len() != SECRET_LENGTH {
+            return Err(format!(
+                "Invalid JWT secret: expected {} bytes, got {}",
+                SECRET_LENGTH,
+                bytes.len()
+            ));
+        }
+        let mut secret = [0u8; SECRET_LENGTH];
+        secret.copy_from_slice(&bytes);
+        Ok(JwtSecret(secret))
+    }
+
+    /// Reads the secret from given file, generating and saving a new one if the file doesn't exist.
+    pub fn load_or_generate(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(content) => Self::from_hex(&content),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                let secret = Self::random();
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir).map_err(|e| {
+                        format!("Unable to create directory {}: {}", dir.display(), e)
+                    })?;
+                }
+                write_owner_only(path, &format!("0x{}", secret.0.to_hex::<String>())).map_err(
+                    |e| format!("Unable to write JWT secret to {}: {}", path.display(), e),
+                )?;
+                info!("Generated new JWT secret at {}", path.display());
+                Ok(secret)
+            }
+            Err(e) => Err(format!(
+                "Unable to read JWT secret from {}: {}",
+                path.display(),
+                e
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+impl JwtSecret {
+    /// Signs a HS256 token with given `iat` claim.
+    pub fn sign(&self, iat: u64) -> String {
+        let encode = |part: &[u8]| base64::encode_config(part, base64::URL_SAFE_NO_PAD);
+        let signed = format!(
+            "{}.{}",
+            encode(br#"{"alg":"HS256","typ":"JWT"}"#),
+            encode(format!(r#"{{"iat":{}}}"#, iat).as_bytes())
+        );
+        let signature = hmac::sign(&hmac::SigKey::sha256(&self.0), signed.as_bytes());
+        format!("{}.{}", signed, encode(&*signature))
+    }
+}
+
+/// Creates a new file readable and writable only by the owner.
+fn write_owner_only(path: &Path, content: &str) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(content.as_bytes())
+}
+
+/// Validates JWT tokens against a shared secret.
+pub struct JwtAuth<T: TimeProvider = DefaultTimeProvider> {
+    secret: JwtSecret,
+    now: T,
+}
+
+impl JwtAuth<DefaultTimeProvider> {
+    /// Creates new validator using `DefaultTimeProvider`.
+    pub fn new(secret: JwtSecret) -> Self {
+        JwtAuth::new_with_time(secret, DefaultTimeProvider)
+    }
+}
+
+impl<T: TimeProvider> JwtAuth<T> {
+    /// Creates new validator with custom time provider.
+    pub fn new_with_time(secret: JwtSecret, now: T) -> Self {
+        JwtAuth { secret, now }
+    }
+
+    /// Validates the value of the `Authorization` header.
+    pub fn validate_header(&self, authorization: Option<&str>) -> Result<(), JwtError> {
+        let token = authorization
+            .and_then(|value| {
+                let mut parts = value.trim().splitn(2, ' ');
+                match (parts.next(), parts.next()) {
+                    (Some(scheme), Some(token)) if scheme.eq_ignore_ascii_case("bearer") => {
+                        Some(token.trim())
+                    }
+                    _ => None,
+                }
+            })
+            .ok_or(JwtError::MissingToken)?;
+        self.validate(token)
+    }
+
+    /// Validates a token.
+    pub fn validate(&self, token: &str) -> Result<(), JwtError> {
+        let mut parts = token.split('.');
+        let (header, claims, signature) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(header), Some(claims), Some(signature)) if parts.next().is_none() => {
+                (header, claims, signature)
+            }
+            _ => return Err(JwtError::Malformed),
+        };
+        let decode = |part: &str| {
+            base64::decode_config(part, base64::URL_SAFE_NO_PAD).map_err(|_| JwtError::Malformed)
+        };
+
+        let header: Header =
+            serde_json::from_slice(&decode(header)?).map_err(|_| JwtError::Malformed)?;
+        if header.alg != "HS256" {
+            return Err(JwtError::UnsupportedAlgorithm(header.alg));
+        }
+
+        let signed = &token[..token.len() - signature.len() - 1];
+        let key = hmac::VerifyKey::sha256(&self.secret.0);
+        if !hmac::verify(&key, signed.as_bytes(), &decode(signature)?) {
+            return Err(JwtError::InvalidSignature);
+        }
+
+        let claims: Claims =
+            serde_json::from_slice(&decode(claims)?).map_err(|_| JwtError::Malformed)?;
+        let iat = claims.iat.ok_or(JwtError::MissingIssuedAt)?;
+        let now = self.now.now();
+        if iat > now + IAT_LEEWAY || iat + IAT_LEEWAY < now {
+            return Err(JwtError::Stale(iat));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret() -> JwtSecret {
+        JwtSecret::from_hex("0x7365637265747365637265747365637265747365637265747365637265747365")
+            .unwrap()
+    }
+
+    fn token(header: &str, claims: &str) -> String {
+        let encode = |part: &str| base64::encode_config(part, base64::URL_SAFE_NO_PAD);
+        let signed = format!("{}.{}", encode(header), encode(claims));
+        let signature = hmac::sign(&hmac::SigKey::sha256(&secret().0), signed.as_bytes());
+        format!(
+            "{}.{}",
+            signed,
+            base64::encode_config(&*signature, base64::URL_SAFE_NO_PAD)
+        )
+    }
+
+    fn auth() -> JwtAuth<impl Fn() -> u64> {
+        JwtAuth::new_with_time(secret(), || 1_000)
+    }
+
+    #[test]
+    fn should_accept_fresh_token() {
+        let token = token(r#"{"alg":"HS256","typ":"JWT"}"#, r#"{"iat":1030}"#);
+        assert_eq!(auth().validate(&token), Ok(()));
+        assert_eq!(
+            auth().validate_header(Some(&format!("Bearer {}", token))),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn should_reject_stale_token() {
+        let token = token(r#"{"alg":"HS256","typ":"JWT"}"#, r#"{"iat":939}"#);
+        assert_eq!(auth().validate(&token), Err(JwtError::Stale(939)));
+        let token = self::token(r#"{"alg":"HS256","typ":"JWT"}"#, r#"{"iat":1061}"#);
+        assert_eq!(auth().validate(&token), Err(JwtError::Stale(1061)));
+    }
+
+    #[test]
+    fn should_reject_invalid_tokens() {
+        let token = token(r#"{"alg":"HS256"}"#, r#"{}"#);
+        assert_eq!(auth().validate(&token), Err(JwtError::MissingIssuedAt));
+
+        let token = self::token(r#"{"alg":"none"}"#, r#"{"iat":1000}"#);
+        assert_eq!(
+            auth().validate(&token),
+            Err(JwtError::UnsupportedAlgorithm("none".into()))
+        );
+
+        let token = self::token(r#"{"alg":"HS256"}"#, r#"{"iat":1000}"#);
+        let other = JwtAuth::new_with_time(JwtSecret::random(), || 1_000);
+        assert_eq!(other.validate(&token), Err(JwtError::InvalidSignature));
+
+        assert_eq!(auth().validate("abc.def"), Err(JwtError::Malformed));
+        assert_eq!(
+            auth().validate_header(Some(&format!("Basic {}", token))),
+            Err(JwtError::MissingToken)
+        );
+        assert_eq!(auth().validate_header(None), Err(JwtError::MissingToken));
+    }
+
+    #[test]
+    fn should_generate_secret_readable_by_owner_only() {
+        let dir = ::tempdir::TempDir::new("jwt").unwrap();
+        let path = dir.path().join("jwt.hex");
+        let secret = JwtSecret::load_or_generate(&path).unwrap();
+        assert_eq!(JwtSecret::load_or_generate(&path), Ok(secret));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn should_parse_secret() {
+        assert_eq!(
+            JwtSecret::from_hex(
+                "7365637265747365637265747365637265747365637265747365637265747365\n"
+            ),
+            Ok(secret())
+        );
+        assert!(JwtSecret::from_hex("0x1234").is_err());
+    }
+}
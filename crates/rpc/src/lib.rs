@@ -41,6 +41,7 @@
 extern crate futures;
 
 extern crate ansi_term;
+extern crate base64;
 extern crate itertools;
 extern crate order_stat;
 extern crate parking_lot;
@@ -111,6 +112,7 @@ pub extern crate jsonrpc_ws_server as ws;
 
 mod authcodes;
 mod http_common;
+mod jwt;
 pub mod v1;
 
 pub mod tests;
@@ -127,11 +129,15 @@ pub use jsonrpc_pubsub::Session as PubSubSession;
 
 pub use authcodes::{AuthCodes, TimeProvider};
 pub use http_common::HttpMetaExtractor;
+pub use jwt::{JwtAuth, JwtError, JwtSecret};
 pub use v1::{
     block_import::{is_major_importing, is_major_importing_or_waiting},
     dispatch,
-    extractors::{RpcExtractor, WsDispatcher, WsExtractor, WsStats},
-    informant, signer, Metadata, NetworkSettings, Origin,
+    extractors::{
+        HttpJwtExtractor, HttpJwtMiddleware, JwtDispatcher, RpcExtractor, WsDispatcher,
+        WsExtractor, WsStats,
+    },
+    informant, signer, Metadata, NetworkSettings, Origin, PoolJournal,
 };
 
 use std::net::SocketAddr;
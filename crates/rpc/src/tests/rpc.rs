@@ -52,8 +52,11 @@ fn request(server: Server<HttpServer>, request: &str) -> http_client::Response {
 #[cfg(test)]
 mod tests {
     use super::{request, Server};
+    use http;
     use jsonrpc_core::{MetaIoHandler, Value};
-    use v1::Metadata;
+    use jwt::{JwtAuth, JwtSecret};
+    use std::{sync::Arc, time};
+    use v1::{extractors, Metadata};
 
     fn serve() -> (Server<::HttpServer>, ::std::net::SocketAddr) {
         let mut io = MetaIoHandler::default();
@@ -163,4 +166,141 @@ mod tests {
             res.headers
         );
     }
+
+    fn jwt_token(secret: &JwtSecret) -> String {
+        secret.sign(time::UNIX_EPOCH.elapsed().unwrap().as_secs())
+    }
+
+    /// Serves a public `public` method and a `protected` one requiring a JWT.
+    fn serve_with_jwt(secret: &JwtSecret) -> (Server<::HttpServer>, ::std::net::SocketAddr) {
+        let address = "127.0.0.1:0".parse().unwrap();
+        let mut full_handler = MetaIoHandler::<Metadata>::default();
+        full_handler.add_method("public", |_| Ok(Value::String("public".into())));
+        full_handler.add_method("protected", |_| Ok(Value::String("protected".into())));
+        let mut handler =
+            MetaIoHandler::with_middleware(extractors::JwtDispatcher::new(full_handler));
+        handler.add_method("public", |_| Ok(Value::String("public".into())));
+        let auth = Arc::new(JwtAuth::new(secret.clone()));
+
+        let server = Server::new(|_| {
+            ::start_http(
+                &address,
+                http::DomainsValidation::Disabled,
+                http::DomainsValidation::Disabled,
+                handler,
+                extractors::HttpJwtExtractor::new(auth),
+                1,
+                5,
+                false,
+            )
+            .unwrap()
+        });
+        let address = server.server.address().to_owned();
+
+        (server, address)
+    }
+
+    fn post(
+        server: Server<::HttpServer>,
+        address: ::std::net::SocketAddr,
+        method: &str,
+        token: Option<&str>,
+    ) -> super::http_client::Response {
+        let req = format!(
+            r#"{{"method":"{}","params":[],"jsonrpc":"2.0","id":1}}"#,
+            method
+        );
+        let authorization = token
+            .map(|token| format!("Authorization: Bearer {}\r\n", token))
+            .unwrap_or_default();
+        request(
+            server,
+            &format!(
+                "\
+				POST / HTTP/1.1\r\n\
+				Host: {}\r\n\
+				Content-Type: application/json\r\n\
+				Content-Length: {}\r\n\
+				Connection: close\r\n\
+				{}\
+				\r\n\
+				{}
+			",
+                address,
+                req.len(),
+                authorization,
+                req
+            ),
+        )
+    }
+
+    #[test]
+    fn should_serve_public_apis_without_jwt() {
+        let secret = JwtSecret::random();
+        let (server, address) = serve_with_jwt(&secret);
+
+        let res = post(server, address, "public", None);
+
+        res.assert_status("HTTP/1.1 200 OK");
+        assert_eq!(
+            res.body,
+            "{\"jsonrpc\":\"2.0\",\"result\":\"public\",\"id\":1}\n"
+        );
+    }
+
+    #[test]
+    fn should_reject_protected_apis_without_jwt() {
+        let secret = JwtSecret::random();
+        let (server, address) = serve_with_jwt(&secret);
+
+        // a token signed with another secret doesn't authenticate the request either
+        let token = jwt_token(&JwtSecret::random());
+        let res = post(server, address, "protected", Some(&token));
+
+        res.assert_status("HTTP/1.1 200 OK");
+        assert!(res.body.contains("Method not found"), "{}", res.body);
+    }
+
+    #[test]
+    fn should_serve_protected_apis_with_jwt() {
+        let secret = JwtSecret::random();
+        let (server, address) = serve_with_jwt(&secret);
+
+        let token = jwt_token(&secret);
+        let res = post(server, address, "protected", Some(&token));
+
+        res.assert_status("HTTP/1.1 200 OK");
+        assert_eq!(
+            res.body,
+            "{\"jsonrpc\":\"2.0\",\"result\":\"protected\",\"id\":1}\n"
+        );
+    }
+
+    #[test]
+    fn should_block_requests_without_jwt_when_all_apis_are_protected() {
+        let secret = JwtSecret::random();
+        let address = "127.0.0.1:0".parse().unwrap();
+        let mut handler = MetaIoHandler::<Metadata>::default();
+        handler.add_method("protected", |_| Ok(Value::String("protected".into())));
+        let auth = Arc::new(JwtAuth::new(secret.clone()));
+        let server = Server::new(|_| {
+            ::start_http_with_middleware(
+                &address,
+                http::DomainsValidation::Disabled,
+                http::DomainsValidation::Disabled,
+                handler,
+                extractors::RpcExtractor,
+                extractors::HttpJwtMiddleware::new(auth),
+                1,
+                5,
+                false,
+            )
+            .unwrap()
+        });
+        let address = server.server.address().to_owned();
+
+        let res = post(server, address, "protected", None);
+
+        res.assert_status("HTTP/1.1 401 Unauthorized");
+    }
 }
@@ -19,6 +19,7 @@
 use std::sync::Arc;
 
 use jsonrpc_core::MetaIoHandler;
+use jwt::{JwtAuth, JwtSecret};
 use ws;
 
 use tests::{
@@ -53,6 +54,32 @@ pub fn serve() -> (Server<ws::Server>, usize, GuardedAuthCodes) {
     (res, port, authcodes)
 }
 
+/// Setup a server requiring a JWT in the handshake
+pub fn serve_with_jwt(secret: JwtSecret) -> (Server<ws::Server>, usize) {
+    let address = "127.0.0.1:0".parse().unwrap();
+    let io = MetaIoHandler::default();
+    let stats = Arc::new(informant::RpcStats::default());
+    let auth = Arc::new(JwtAuth::new(secret));
+
+    let res = Server::new(|_| {
+        ::start_ws(
+            &address,
+            io,
+            ws::DomainsValidation::Disabled,
+            ws::DomainsValidation::Disabled,
+            5,
+            extractors::WsExtractor::new(None).with_jwt(auth.clone()),
+            extractors::WsExtractor::new(None).with_jwt(auth),
+            extractors::WsStats::new(stats),
+            5 * 1024 * 1024,
+        )
+        .unwrap()
+    });
+    let port = res.addr().port() as usize;
+
+    (res, port)
+}
+
 /// Test a single request to running server
 pub fn request(server: Server<ws::Server>, request: &str) -> http_client::Response {
     http_client::request(server.server.addr(), request)
@@ -60,8 +87,9 @@ pub fn request(server: Server<ws::Server>, request: &str) -> http_client::Respon
 
 #[cfg(test)]
 mod testing {
-    use super::{http_client, request, serve};
+    use super::{http_client, request, serve, serve_with_jwt};
     use hash::keccak;
+    use jwt::JwtSecret;
     use std::time;
 
     #[test]
@@ -184,4 +212,62 @@ mod testing {
         assert_eq!(response1.status, "HTTP/1.1 403 Forbidden".to_owned());
         http_client::assert_security_headers_present(&response1.headers, None);
     }
+
+    #[test]
+    fn should_block_connection_without_jwt() {
+        // given
+        let (server, port) = serve_with_jwt(JwtSecret::random());
+
+        // when
+        let response = request(
+            server,
+            &format!(
+                "\
+				GET / HTTP/1.1\r\n\
+				Host: 127.0.0.1:{}\r\n\
+				Connection: Upgrade\r\n\
+				Sec-WebSocket-Key: x3JJHMbDL1EzLkh9GBhXDw==\r\n\
+				Sec-WebSocket-Version: 13\r\n\
+				\r\n\
+				{{}}
+			",
+                port
+            ),
+        );
+
+        // then
+        assert_eq!(response.status, "HTTP/1.1 401 Unauthorized".to_owned());
+    }
+
+    #[test]
+    fn should_allow_connection_with_jwt() {
+        // given
+        let secret = JwtSecret::random();
+        let token = secret.sign(time::UNIX_EPOCH.elapsed().unwrap().as_secs());
+        let (server, port) = serve_with_jwt(secret);
+
+        // when
+        let response = request(
+            server,
+            &format!(
+                "\
+				GET / HTTP/1.1\r\n\
+				Host: 127.0.0.1:{}\r\n\
+				Connection: Close\r\n\
+				Authorization: Bearer {}\r\n\
+				Sec-WebSocket-Key: x3JJHMbDL1EzLkh9GBhXDw==\r\n\
+				Sec-WebSocket-Version: 13\r\n\
+				\r\n\
+				{{}}
+			",
+                port, token
+            ),
+        );
+
+        // then
+        assert_eq!(
+            response.status,
+            "HTTP/1.1 101 Switching Protocols".to_owned()
+        );
+    }
 }
@@ -23,11 +23,13 @@ use std::{
 
 use authcodes;
 use ethereum_types::H256;
+use http::{self, hyper, RequestMiddleware, RequestMiddlewareAction};
 use http_common::HttpMetaExtractor;
 use ipc;
 use jsonrpc_core as core;
 use jsonrpc_core::futures::future::Either;
 use jsonrpc_pubsub::Session;
+use jwt::JwtAuth;
 use ws;
 
 use v1::{informant::RpcStats, Metadata, Origin};
@@ -46,6 +48,85 @@ impl HttpMetaExtractor for RpcExtractor {
                 user_agent.unwrap_or_else(|| "unknown agent".to_string())
             )),
             session: None,
+            authenticated: false,
+        }
+    }
+}
+
+/// HTTP metadata extractor marking requests with a valid JWT as authenticated.
+pub struct HttpJwtExtractor {
+    auth: Arc<JwtAuth>,
+}
+
+impl HttpJwtExtractor {
+    /// Creates new `HttpJwtExtractor` validating tokens with given `JwtAuth`.
+    pub fn new(auth: Arc<JwtAuth>) -> Self {
+        HttpJwtExtractor { auth }
+    }
+}
+
+impl HttpMetaExtractor for HttpJwtExtractor {
+    type Metadata = Metadata;
+
+    fn read_metadata(&self, origin: Option<String>, user_agent: Option<String>) -> Metadata {
+        RpcExtractor.read_metadata(origin, user_agent)
+    }
+
+    fn read_metadata_with_authorization(
+        &self,
+        origin: Option<String>,
+        user_agent: Option<String>,
+        authorization: Option<String>,
+    ) -> Metadata {
+        let mut meta = self.read_metadata(origin, user_agent);
+        meta.authenticated = match self
+            .auth
+            .validate_header(authorization.as_ref().map(String::as_str))
+        {
+            Ok(()) => true,
+            Err(err) => {
+                debug!(target: "rpc", "Serving unauthenticated HTTP request: {}", err);
+                false
+            }
+        };
+        meta
+    }
+}
+
+/// HTTP request middleware rejecting requests without a valid JWT.
+pub struct HttpJwtMiddleware {
+    auth: Arc<JwtAuth>,
+}
+
+impl HttpJwtMiddleware {
+    /// Creates new `HttpJwtMiddleware` validating tokens with given `JwtAuth`.
+    pub fn new(auth: Arc<JwtAuth>) -> Self {
+        HttpJwtMiddleware { auth }
+    }
+}
+
+impl RequestMiddleware for HttpJwtMiddleware {
+    fn on_request(&self, request: hyper::Request<hyper::Body>) -> RequestMiddlewareAction {
+        let authorization = request
+            .headers()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok());
+        match self.auth.validate_header(authorization) {
+            Ok(()) => RequestMiddlewareAction::Proceed {
+                should_continue_on_invalid_cors: false,
+                request,
+            },
+            Err(err) => {
+                warn!(target: "rpc", "Blocked HTTP request with invalid JWT: {}", err);
+                http::Response {
+                    code: hyper::StatusCode::UNAUTHORIZED,
+                    content_type: hyper::header::HeaderValue::from_static(
+                        "text/plain; charset=utf-8",
+                    ),
+                    content: format!("Unauthorized: {}\n", err),
+                }
+                .into()
+            }
         }
     }
 }
@@ -55,6 +136,7 @@ impl ipc::MetaExtractor<Metadata> for RpcExtractor {
         Metadata {
             origin: Origin::Ipc(H256::from_low_u64_be(req.session_id)),
             session: Some(Arc::new(Session::new(req.sender.clone()))),
+            authenticated: false,
         }
     }
 }
@@ -62,6 +144,7 @@ impl ipc::MetaExtractor<Metadata> for RpcExtractor {
 /// WebSockets server metadata extractor and request middleware.
 pub struct WsExtractor {
     authcodes_path: Option<PathBuf>,
+    jwt: Option<Arc<JwtAuth>>,
 }
 
 impl WsExtractor {
@@ -69,8 +152,18 @@ impl WsExtractor {
     pub fn new(path: Option<&Path>) -> Self {
         WsExtractor {
             authcodes_path: path.map(ToOwned::to_owned),
+            jwt: None,
         }
     }
+
+    /// Requires a valid JWT in the `Authorization` header of the handshake.
+    ///
+    /// The token authenticates the whole connection, so there is no per-API gating over
+    /// WebSockets: either the handshake is rejected or every API served on it is available.
+    pub fn with_jwt(mut self, auth: Arc<JwtAuth>) -> Self {
+        self.jwt = Some(auth);
+        self
+    }
 }
 
 impl ws::MetaExtractor<Metadata> for WsExtractor {
@@ -95,7 +188,12 @@ impl ws::MetaExtractor<Metadata> for WsExtractor {
             },
         };
         let session = Some(Arc::new(Session::new(req.sender())));
-        Metadata { origin, session }
+        Metadata {
+            origin,
+            session,
+            // connections without a valid token are rejected during the handshake
+            authenticated: self.jwt.is_some(),
+        }
     }
 }
 
@@ -121,6 +219,18 @@ impl ws::RequestMiddleware for WsExtractor {
             return Some(response).into();
         }
 
+        if let Some(ref auth) = self.jwt {
+            let authorization = req
+                .header("authorization")
+                .and_then(|value| ::std::str::from_utf8(value).ok());
+            if let Err(err) = auth.validate_header(authorization) {
+                warn!("Blocked WebSockets connection with invalid JWT: {}", err);
+                let mut response = Response::new(401, "Unauthorized", vec![]);
+                add_security_headers(&mut response);
+                return Some(response).into();
+            }
+        }
+
         // If protocol is provided it needs to be valid.
         let protocols = req.protocols().ok().unwrap_or_else(Vec::new);
         if let Some(ref path) = self.authcodes_path {
@@ -253,6 +363,42 @@ impl<M: core::Middleware<Metadata>> core::Middleware<Metadata> for WsDispatcher<
     }
 }
 
+/// Middleware dispatching requests with a valid JWT to a handler with the protected APIs.
+pub struct JwtDispatcher<M: core::Middleware<Metadata>> {
+    full_handler: core::MetaIoHandler<Metadata, M>,
+}
+
+impl<M: core::Middleware<Metadata>> JwtDispatcher<M> {
+    /// Create new `JwtDispatcher` with given full handler.
+    pub fn new(full_handler: core::MetaIoHandler<Metadata, M>) -> Self {
+        JwtDispatcher { full_handler }
+    }
+}
+
+impl<M: core::Middleware<Metadata>> core::Middleware<Metadata> for JwtDispatcher<M> {
+    type Future = Either<core::FutureRpcResult<M::Future, M::CallFuture>, core::FutureResponse>;
+    type CallFuture = core::middleware::NoopCallFuture;
+
+    fn on_request<F, X>(
+        &self,
+        request: core::Request,
+        meta: Metadata,
+        process: F,
+    ) -> Either<Self::Future, X>
+    where
+        F: FnOnce(core::Request, Metadata) -> X,
+        X: core::futures::Future<Item = Option<core::Response>, Error = ()> + Send + 'static,
+    {
+        if meta.authenticated {
+            Either::A(Either::A(
+                self.full_handler.handle_rpc_request(request, meta),
+            ))
+        } else {
+            Either::B(process(request, meta))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::RpcExtractor;
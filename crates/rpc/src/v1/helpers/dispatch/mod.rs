@@ -92,8 +92,8 @@ use ethereum_types::{Address, H256, H520, U256};
 use ethkey::Password;
 use hash::keccak;
 use types::{
+    header::HeaderTransitions,
     transaction::{PendingTransaction, SignedTransaction},
-    BlockNumber,
 };
 
 use jsonrpc_core::{
@@ -405,14 +405,14 @@ pub fn default_max_priority_fee_per_gas<C, M>(
     client: &C,
     miner: &M,
     percentile: usize,
-    eip1559_transition: BlockNumber,
+    transitions: HeaderTransitions,
 ) -> U256
 where
     C: BlockChainClient,
     M: MinerService,
 {
     client
-        .priority_gas_price_corpus(100, eip1559_transition)
+        .priority_gas_price_corpus(100, transitions)
         .percentile(percentile)
         .cloned()
         .unwrap_or_else(|| miner.sensible_max_priority_fee())
@@ -100,7 +100,10 @@ impl super::Accounts for Signer {
                     return Err(Error::new(ErrorCode::InvalidParams));
                 }
             }
-            None => return Err(Error::new(ErrorCode::InvalidParams)),
+            // blob transactions can't be built from a request without their sidecar
+            Some(TypedTxId::BlobTransaction) | None => {
+                return Err(Error::new(ErrorCode::InvalidParams))
+            }
         };
 
         let hash = t.signature_hash(chain_id);
@@ -21,6 +21,7 @@ use std::fmt;
 use ethcore::{
     client::{BlockChainClient, BlockId},
     error::{CallError, Error as EthcoreError, ErrorKind},
+    miner::BundleError,
 };
 use jsonrpc_core::{Error, ErrorCode, Result as RpcResult, Value};
 use rlp::DecoderError;
@@ -404,11 +405,14 @@ pub fn transaction_message(error: &TransactionError) -> String {
 		SenderBanned => "Sender is banned in local queue.".into(),
 		RecipientBanned => "Recipient is banned in local queue.".into(),
 		CodeBanned => "Code is banned in local queue.".into(),
+		SenderRateLimited => "Sender submits transactions too fast. Try again later.".into(),
+		RecipientRateLimited => "Too many transactions to this recipient. Try again later.".into(),
 		NotAllowed => "Transaction is not permitted.".into(),
 		TooBig => "Transaction is too big, see chain specification for the limit.".into(),
         InvalidRlp(ref descr) => format!("Invalid RLP data: {}", descr),
         TransactionTypeNotEnabled => format!("Transaction type is not enabled for current block"),
         SenderIsNotEOA => "Transaction sender is not an EOA (see EIP-3607)".into(),
+        InvalidBlobVersionedHashes => "Blob transaction has invalid blob versioned hashes (see EIP-4844)".into(),
 	}
 }
 
@@ -429,6 +433,14 @@ pub fn transaction<T: Into<EthcoreError>>(error: T) -> Error {
     }
 }
 
+pub fn bundle(error: BundleError) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::TRANSACTION_ERROR),
+        message: format!("{}", error),
+        data: None,
+    }
+}
+
 pub fn decode<T: Into<EthcoreError>>(error: T) -> Error {
     let error = error.into();
     match *error.kind() {
@@ -33,6 +33,7 @@ pub mod secretstore;
 mod network_settings;
 mod poll_filter;
 mod poll_manager;
+mod pool_journal;
 mod requests;
 mod signature;
 mod subscribers;
@@ -44,6 +45,7 @@ pub use self::{
     network_settings::NetworkSettings,
     poll_filter::{limit_logs, PollFilter, SyncPollFilter},
     poll_manager::PollManager,
+    pool_journal::PoolJournal,
     requests::{
         CallRequest, ConfirmationPayload, ConfirmationRequest, FilledTransactionRequest,
         TransactionRequest,
@@ -99,7 +99,7 @@ impl From<FilledTransactionRequest> for TransactionRequest {
 }
 
 /// Call request
-#[derive(Debug, Default, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct CallRequest {
     /// type of transaction.
     pub transaction_type: Option<U64>,
@@ -18,15 +18,42 @@
 
 use std::sync::Arc;
 
-use ethcore::client::BlockChainClient;
+use ethcore::{
+    client::{BlockChainClient, BlockId, Call, EngineInfo, StateClient, StateInfo, TransactionId},
+    trace::{GethTracer, StructLoggerConfig},
+};
+use ethereum_types::H256;
 use types::{header::Header, transaction::LocalizedTransaction};
 
 use jsonrpc_core::Result;
 use v1::{
+    helpers::{errors, fake_sign},
     traits::Debug,
-    types::{Block, BlockTransactions, Bytes, RichBlock, Transaction},
+    types::{
+        block_number_to_id, Block, BlockNumber, BlockTransactions, Bytes, CallRequest, GethTrace,
+        GethTraceOptions, GethTraceWithTransactionHash, RichBlock, Transaction,
+    },
 };
 
+fn to_geth_tracer(options: Option<GethTraceOptions>) -> Result<GethTracer> {
+    let options = options.unwrap_or_default();
+    let config = options.tracer_config.unwrap_or_default();
+    match options.tracer.as_ref().map(String::as_str) {
+        None => Ok(GethTracer::StructLogger(StructLoggerConfig {
+            disable_stack: options.disable_stack,
+            disable_storage: options.disable_storage,
+            enable_memory: options.enable_memory,
+        })),
+        Some("callTracer") => Ok(GethTracer::Call {
+            only_top_call: config.only_top_call,
+        }),
+        Some("prestateTracer") => Ok(GethTracer::Prestate {
+            diff_mode: config.diff_mode,
+        }),
+        Some(tracer) => Err(errors::invalid_params("tracer", tracer)),
+    }
+}
+
 /// Debug rpc implementation.
 pub struct DebugClient<C> {
     client: Arc<C>,
@@ -39,7 +66,11 @@ impl<C> DebugClient<C> {
     }
 }
 
-impl<C: BlockChainClient + 'static> Debug for DebugClient<C> {
+impl<C, S> Debug for DebugClient<C>
+where
+    S: StateInfo + 'static,
+    C: BlockChainClient + StateClient<State = S> + Call<State = S> + EngineInfo + 'static,
+{
     fn bad_blocks(&self) -> Result<Vec<RichBlock>> {
         fn cast<O, T: Copy + Into<O>>(t: &T) -> O {
             (*t).into()
@@ -112,6 +143,86 @@ impl<C: BlockChainClient + 'static> Debug for DebugClient<C> {
             })
             .collect())
     }
+
+    fn trace_transaction(
+        &self,
+        transaction_hash: H256,
+        options: Option<GethTraceOptions>,
+    ) -> Result<GethTrace> {
+        let tracer = to_geth_tracer(options)?;
+
+        self.client
+            .debug_trace_transaction(TransactionId::Hash(transaction_hash), &tracer)
+            .map(GethTrace::from)
+            .map_err(errors::call)
+    }
+
+    fn trace_call(
+        &self,
+        request: CallRequest,
+        block: Option<BlockNumber>,
+        options: Option<GethTraceOptions>,
+    ) -> Result<GethTrace> {
+        let block = block.unwrap_or_default();
+        let tracer = to_geth_tracer(options)?;
+
+        let request = CallRequest::into(request);
+        let signed = fake_sign::sign_call(request)?;
+
+        let id = match block {
+            BlockNumber::Hash { hash, .. } => BlockId::Hash(hash),
+            BlockNumber::Num(num) => BlockId::Number(num),
+            BlockNumber::Earliest => BlockId::Earliest,
+            BlockNumber::Latest => BlockId::Latest,
+
+            BlockNumber::Pending => {
+                return Err(errors::invalid_params(
+                    "`BlockNumber::Pending` is not supported",
+                    (),
+                ))
+            }
+        };
+
+        let mut state = self.client.state_at(id).ok_or_else(errors::state_pruned)?;
+        let header = self
+            .client
+            .block_header(id)
+            .ok_or_else(errors::state_pruned)?;
+
+        self.client
+            .debug_trace_call(
+                &signed,
+                &tracer,
+                &mut state,
+                &header
+                    .decode(self.client.engine().params().header_transitions())
+                    .map_err(errors::decode)?,
+            )
+            .map(GethTrace::from)
+            .map_err(errors::call)
+    }
+
+    fn trace_block_by_number(
+        &self,
+        block: BlockNumber,
+        options: Option<GethTraceOptions>,
+    ) -> Result<Vec<GethTraceWithTransactionHash>> {
+        let tracer = to_geth_tracer(options)?;
+        let id = match block {
+            BlockNumber::Pending => {
+                return Err(errors::invalid_params(
+                    "`BlockNumber::Pending` is not supported",
+                    (),
+                ))
+            }
+            num => block_number_to_id(num),
+        };
+
+        self.client
+            .debug_trace_block(id, &tracer)
+            .map(|traces| traces.into_iter().map(Into::into).collect())
+            .map_err(errors::call)
+    }
 }
 
 fn serialize<T: ::serde::Serialize>(t: &T) -> String {
@@ -28,8 +28,9 @@ use parking_lot::Mutex;
 use ethash::{self, SeedHashCompute};
 use ethcore::{
     client::{
-        BlockChainClient, BlockId, Call, EngineInfo, ProvingBlockChainClient, StateClient,
-        StateInfo, StateOrBlock, TransactionId, UncleId,
+        BlockChainClient, BlockId, Call, CallAnalytics, EngineInfo, ProvingBlockChainClient,
+        SimulationBlock, StateClient, StateInfo, StateOrBlock, TransactionId, UncleId,
+        SIMULATED_BLOCK_INTERVAL,
     },
     miner::{self, MinerService},
     snapshot::SnapshotService,
@@ -41,7 +42,10 @@ use types::{
     encoded,
     filter::Filter as EthcoreFilter,
     header::Header,
-    transaction::{LocalizedTransaction, SignedTransaction, TypedTransaction},
+    transaction::{
+        AccessListItem as InnerAccessListItem, LocalizedTransaction, SignedTransaction,
+        TypedTransaction,
+    },
     BlockNumber as EthBlockNumber,
 };
 
@@ -58,12 +62,22 @@ use v1::{
     metadata::Metadata,
     traits::Eth,
     types::{
-        block_number_to_id, Block, BlockNumber, BlockTransactions, Bytes, CallRequest, EthAccount,
-        EthFeeHistory, Filter, Index, Log, Receipt, RichBlock, StorageProof, SyncInfo, SyncStatus,
-        Transaction, Work,
+        block_number_to_id, to_call_overrides, AccessList, AccessListWithGasUsed, Block,
+        BlockNumber, BlockOverride, BlockTransactions, BundleHash, Bytes, CallRequest, EthAccount,
+        EthFeeHistory, Filter, Index, Log, Receipt, RichBlock, SendBundleRequest, SimulatePayload,
+        SimulatedBlock, StateOverride, StorageProof, SyncInfo, SyncStatus, Transaction, Work,
     },
 };
 
+/// Maximal number of blocks simulated by `eth_simulateV1`.
+const MAX_SIMULATED_BLOCKS: usize = 256;
+/// Maximal number of calls across all blocks simulated by `eth_simulateV1`.
+const MAX_SIMULATED_CALLS: usize = 1000;
+/// Maximal number of blocks `eth_simulateV1` may skip with a block number override.
+const MAX_SIMULATED_BLOCK_GAP: u64 = 256;
+/// Maximal number of executions `eth_createAccessList` runs to find a stable access list.
+const MAX_ACCESS_LIST_ITERATIONS: usize = 16;
+
 const EXTRA_INFO_PROOF: &str = "Object exists in blockchain (fetched earlier), extra_info is always available if object exists; qed";
 
 /// Eth RPC options
@@ -259,9 +273,10 @@ where
         match (block, difficulty) {
             (Some(block), Some(total_difficulty)) => {
                 let view = block.header_view();
+                let transitions = client.engine().params().header_transitions();
                 let eip1559_enabled = client.engine().schedule(view.number()).eip1559;
                 let base_fee = if eip1559_enabled {
-                    Some(view.base_fee())
+                    Some(view.base_fee(transitions))
                 } else {
                     None
                 };
@@ -292,11 +307,7 @@ where
                         timestamp: view.timestamp().into(),
                         difficulty: view.difficulty(),
                         total_difficulty: Some(total_difficulty),
-                        seal_fields: view
-                            .seal(eip1559_enabled)
-                            .into_iter()
-                            .map(Into::into)
-                            .collect(),
+                        seal_fields: view.seal(transitions).into_iter().map(Into::into).collect(),
                         base_fee_per_gas: base_fee,
                         uncles: block.uncle_hashes(),
                         transactions: match include_txs {
@@ -435,11 +446,12 @@ where
                 };
 
                 let uncle = match client.uncle(uncle_id) {
-                    Some(hdr) => match hdr.decode(self.client.engine().params().eip1559_transition)
-                    {
-                        Ok(h) => h,
-                        Err(e) => return Err(errors::decode(e)),
-                    },
+                    Some(hdr) => {
+                        match hdr.decode(self.client.engine().params().header_transitions()) {
+                            Ok(h) => h,
+                            Err(e) => return Err(errors::decode(e)),
+                        }
+                    }
                     None => {
                         return Ok(None);
                     }
@@ -536,6 +548,33 @@ where
             }
         }
     }
+
+    /// Get the state and header to execute virtual calls on.
+    fn state_and_header(&self, num: BlockNumber) -> Result<(T, Header)> {
+        if num == BlockNumber::Pending {
+            return Ok(self.pending_state_and_header_with_fallback());
+        }
+
+        let id = match num {
+            BlockNumber::Hash { hash, .. } => BlockId::Hash(hash),
+            BlockNumber::Num(num) => BlockId::Number(num),
+            BlockNumber::Earliest => BlockId::Earliest,
+            BlockNumber::Latest => BlockId::Latest,
+            BlockNumber::Pending => unreachable!(), // Already covered
+        };
+
+        let state = self.client.state_at(id).ok_or_else(errors::state_pruned)?;
+        let header = self
+            .client
+            .block_header(id)
+            .ok_or_else(errors::state_pruned)
+            .and_then(|h| {
+                h.decode(self.client.engine().params().header_transitions())
+                    .map_err(errors::decode)
+            })?;
+
+        Ok((state, header))
+    }
 }
 
 pub fn pending_logs<M>(miner: &M, best_block: EthBlockNumber, filter: &EthcoreFilter) -> Vec<Log>
@@ -596,6 +635,32 @@ where
 
 const MAX_QUEUE_SIZE_TO_MINE_ON: usize = 4; // because uncles go back 6.
 
+/// Adds the addresses and storage keys of `accessed` that are missing from `list`.
+/// Returns whether anything was added.
+fn extend_access_list(
+    list: &mut Vec<InnerAccessListItem>,
+    accessed: Vec<InnerAccessListItem>,
+) -> bool {
+    let mut grown = false;
+    for (address, keys) in accessed {
+        let index = match list.iter().position(|item| item.0 == address) {
+            Some(index) => index,
+            None => {
+                list.push((address, Vec::new()));
+                grown = true;
+                list.len() - 1
+            }
+        };
+        for key in keys {
+            if !list[index].1.contains(&key) {
+                list[index].1.push(key);
+                grown = true;
+            }
+        }
+    }
+    grown
+}
+
 impl<C, SN: ?Sized, S: ?Sized, M, EM, T: StateInfo + 'static> Eth for EthClient<C, SN, S, M, EM>
 where
     C: miner::BlockChainClient
@@ -698,14 +763,14 @@ where
 
     fn max_priority_fee_per_gas(&self) -> BoxFuture<U256> {
         let latest_block = self.client.chain_info().best_block_number;
-        let eip1559_transition = self.client.engine().params().eip1559_transition;
+        let transitions = self.client.engine().params().header_transitions();
 
-        if latest_block + 1 >= eip1559_transition {
+        if latest_block + 1 >= transitions.eip1559_transition {
             Box::new(future::ok(default_max_priority_fee_per_gas(
                 &*self.client,
                 &*self.miner,
                 self.options.gas_price_percentile,
-                eip1559_transition,
+                transitions,
             )))
         } else {
             Box::new(future::done(Err(errors::eip1559_not_activated())))
@@ -761,7 +826,7 @@ where
                 .block_header(BlockId::Number(i))
                 .ok_or_else(errors::state_pruned)
                 .and_then(|h| {
-                    h.decode(self.client.engine().params().eip1559_transition)
+                    h.decode(self.client.engine().params().header_transitions())
                         .map_err(errors::decode)
                 })
         };
@@ -1186,6 +1251,30 @@ where
         Box::new(future::done(result))
     }
 
+    fn block_receipts(&self, num: BlockNumber) -> BoxFuture<Option<Vec<Receipt>>> {
+        let id = match num {
+            BlockNumber::Pending => {
+                let best_block = self.client.chain_info().best_block_number;
+                let receipts = self
+                    .miner
+                    .pending_receipts(best_block)
+                    .map(|receipts| receipts.into_iter().map(Into::into).collect());
+                return Box::new(future::ok(receipts));
+            }
+            BlockNumber::Hash { hash, .. } => BlockId::Hash(hash),
+            BlockNumber::Num(num) => BlockId::Number(num),
+            BlockNumber::Earliest => BlockId::Earliest,
+            BlockNumber::Latest => BlockId::Latest,
+        };
+
+        let receipts = self
+            .client
+            .localized_block_receipts(id)
+            .map(|receipts| receipts.into_iter().map(Into::into).collect());
+        let result = Ok(receipts).and_then(errors::check_block_gap(&*self.client, self.options));
+        Box::new(future::done(result))
+    }
+
     fn uncle_by_block_hash_and_index(
         &self,
         hash: H256,
@@ -1350,38 +1439,52 @@ where
         self.send_raw_transaction(raw)
     }
 
-    fn call(&self, request: CallRequest, num: Option<BlockNumber>) -> BoxFuture<Bytes> {
+    fn send_bundle(&self, request: SendBundleRequest) -> Result<BundleHash> {
+        let transactions = request
+            .txs
+            .into_iter()
+            .map(|raw| {
+                TypedTransaction::decode(&raw.into_vec())
+                    .map_err(errors::rlp)
+                    .and_then(|tx| SignedTransaction::new(tx).map_err(errors::transaction))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let bundle = miner::Bundle {
+            transactions,
+            block_number: request.block_number.as_u64(),
+            min_timestamp: request.min_timestamp,
+            max_timestamp: request.max_timestamp,
+            reverting_hashes: request.reverting_tx_hashes.into_iter().collect(),
+        };
+
+        self.miner
+            .submit_bundle(&*self.client, bundle)
+            .map(|bundle_hash| BundleHash { bundle_hash })
+            .map_err(errors::bundle)
+    }
+
+    fn call(
+        &self,
+        request: CallRequest,
+        num: Option<BlockNumber>,
+        state_override: Option<StateOverride>,
+        block_override: Option<BlockOverride>,
+    ) -> BoxFuture<Bytes> {
         let request = CallRequest::into(request);
         let signed = try_bf!(fake_sign::sign_call(request));
+        let overrides = try_bf!(to_call_overrides(state_override, block_override));
 
         let num = num.unwrap_or_default();
 
-        let (mut state, header) = if num == BlockNumber::Pending {
-            self.pending_state_and_header_with_fallback()
-        } else {
-            let id = match num {
-                BlockNumber::Hash { hash, .. } => BlockId::Hash(hash),
-                BlockNumber::Num(num) => BlockId::Number(num),
-                BlockNumber::Earliest => BlockId::Earliest,
-                BlockNumber::Latest => BlockId::Latest,
-                BlockNumber::Pending => unreachable!(), // Already covered
-            };
+        let (mut state, header) = try_bf!(self.state_and_header(num));
 
-            let state = try_bf!(self.client.state_at(id).ok_or_else(errors::state_pruned));
-            let header = try_bf!(self
-                .client
-                .block_header(id)
-                .ok_or_else(errors::state_pruned)
-                .and_then(|h| h
-                    .decode(self.client.engine().params().eip1559_transition)
-                    .map_err(errors::decode)));
-
-            (state, header)
-        };
-
-        let result = self
-            .client
-            .call(&signed, Default::default(), &mut state, &header);
+        let result = self.client.call_with_overrides(
+            &signed,
+            Default::default(),
+            &overrides,
+            &mut state,
+            &header,
+        );
 
         Box::new(future::done(
             result
@@ -1394,32 +1497,161 @@ where
         ))
     }
 
+    fn simulate(
+        &self,
+        payload: SimulatePayload,
+        num: Option<BlockNumber>,
+    ) -> BoxFuture<Vec<SimulatedBlock>> {
+        if payload.validation || payload.trace_transfers || payload.return_full_transactions {
+            return Box::new(future::err(errors::unsupported(
+                "validation, traceTransfers and returnFullTransactions are not supported",
+                None,
+            )));
+        }
+        if payload.block_state_calls.len() > MAX_SIMULATED_BLOCKS {
+            return Box::new(future::err(errors::invalid_params(
+                "blockStateCalls",
+                format!("at most {} blocks can be simulated", MAX_SIMULATED_BLOCKS),
+            )));
+        }
+        let calls: usize = payload
+            .block_state_calls
+            .iter()
+            .map(|block| block.calls.len())
+            .sum();
+        if calls > MAX_SIMULATED_CALLS {
+            return Box::new(future::err(errors::invalid_params(
+                "blockStateCalls",
+                format!("at most {} calls can be simulated", MAX_SIMULATED_CALLS),
+            )));
+        }
+
+        let (mut state, header) = try_bf!(self.state_and_header(num.unwrap_or_default()));
+
+        let mut number = header.number();
+        let mut timestamp = header.timestamp();
+        let mut blocks = Vec::with_capacity(payload.block_state_calls.len());
+        for block in payload.block_state_calls {
+            let overrides = try_bf!(to_call_overrides(
+                block.state_overrides,
+                block.block_overrides
+            ));
+            let (next_number, next_timestamp) = match (
+                number.checked_add(1),
+                timestamp.checked_add(SIMULATED_BLOCK_INTERVAL),
+            ) {
+                (Some(number), Some(timestamp)) => (number, timestamp),
+                _ => {
+                    return Box::new(future::err(errors::invalid_params(
+                        "blockOverrides",
+                        "block number or timestamp overflows",
+                    )))
+                }
+            };
+            let block_number = overrides.block.number.unwrap_or(next_number);
+            let block_timestamp = overrides.block.timestamp.unwrap_or(next_timestamp);
+            if block_number <= number || block_timestamp <= timestamp {
+                return Box::new(future::err(errors::invalid_params(
+                    "blockOverrides",
+                    "block numbers and timestamps must be increasing",
+                )));
+            }
+            if block_number - next_number > MAX_SIMULATED_BLOCK_GAP {
+                return Box::new(future::err(errors::invalid_params(
+                    "blockOverrides",
+                    format!(
+                        "at most {} blocks can be skipped between simulated blocks",
+                        MAX_SIMULATED_BLOCK_GAP
+                    ),
+                )));
+            }
+            number = block_number;
+            timestamp = block_timestamp;
+
+            let transactions = try_bf!(block
+                .calls
+                .into_iter()
+                .map(|request| fake_sign::sign_call(request.into()))
+                .collect::<Result<Vec<_>>>());
+            blocks.push(SimulationBlock {
+                overrides,
+                transactions,
+            });
+        }
+
+        let result = self
+            .client
+            .simulate(&blocks, &mut state, &header)
+            .map_err(errors::call)
+            .map(|simulated| {
+                simulated
+                    .into_iter()
+                    .zip(blocks)
+                    .map(|(block, SimulationBlock { transactions, .. })| {
+                        let hashes = transactions.iter().map(|t| t.hash()).collect();
+                        SimulatedBlock::new(block, hashes)
+                    })
+                    .collect()
+            });
+        Box::new(future::done(result))
+    }
+
+    fn create_access_list(
+        &self,
+        request: CallRequest,
+        num: Option<BlockNumber>,
+    ) -> BoxFuture<AccessListWithGasUsed> {
+        let mut request = CallRequest::into(request);
+        match request.transaction_type.map(|t| t.as_u64()) {
+            None | Some(0) => request.transaction_type = Some(U64::from(1)),
+            _ => {}
+        }
+        let num = num.unwrap_or_default();
+
+        // Every execution may touch new addresses or storage keys, e.g. because the prewarmed
+        // slots make it cheaper, so run it again until the access list doesn't grow. The list
+        // only ever grows, but a contract can keep touching new slots, hence the bound.
+        let mut access_list: Vec<InnerAccessListItem> = request
+            .access_list
+            .take()
+            .unwrap_or_default()
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        let mut iterations = 0;
+        loop {
+            let (mut state, header) = try_bf!(self.state_and_header(num));
+            let used: AccessList = access_list.iter().cloned().map(Into::into).collect();
+            request.access_list = Some(used.clone());
+            let signed = try_bf!(fake_sign::sign_call(request.clone()));
+            let analytics = CallAnalytics {
+                access_list_recording: true,
+                ..Default::default()
+            };
+            let executed = try_bf!(self
+                .client
+                .call(&signed, analytics, &mut state, &header)
+                .map_err(errors::call));
+
+            iterations += 1;
+            let grown =
+                extend_access_list(&mut access_list, executed.access_list.unwrap_or_default());
+            if !grown || iterations == MAX_ACCESS_LIST_ITERATIONS {
+                return Box::new(future::ok(AccessListWithGasUsed {
+                    access_list: used,
+                    gas_used: executed.gas_used,
+                    error: executed.exception.map(|e| e.to_string()),
+                }));
+            }
+        }
+    }
+
     fn estimate_gas(&self, request: CallRequest, num: Option<BlockNumber>) -> BoxFuture<U256> {
         let request = CallRequest::into(request);
         let signed = try_bf!(fake_sign::sign_call(request));
         let num = num.unwrap_or_default();
 
-        let (state, header) = if num == BlockNumber::Pending {
-            self.pending_state_and_header_with_fallback()
-        } else {
-            let id = match num {
-                BlockNumber::Hash { hash, .. } => BlockId::Hash(hash),
-                BlockNumber::Num(num) => BlockId::Number(num),
-                BlockNumber::Earliest => BlockId::Earliest,
-                BlockNumber::Latest => BlockId::Latest,
-                BlockNumber::Pending => unreachable!(), // Already covered
-            };
-
-            let state = try_bf!(self.client.state_at(id).ok_or_else(errors::state_pruned));
-            let header = try_bf!(self
-                .client
-                .block_header(id)
-                .ok_or_else(errors::state_pruned)
-                .and_then(|h| h
-                    .decode(self.client.engine().params().eip1559_transition)
-                    .map_err(errors::decode)));
-            (state, header)
-        };
+        let (state, header) = try_bf!(self.state_and_header(num));
 
         Box::new(future::done(
             self.client
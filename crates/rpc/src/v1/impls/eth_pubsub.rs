@@ -34,7 +34,7 @@ use v1::{
     helpers::{errors, limit_logs, Subscribers},
     metadata::Metadata,
     traits::EthPubSub,
-    types::{pubsub, Header, Log, RichHeader},
+    types::{pubsub, Header, Log, RichHeader, TransactionEvent},
 };
 
 use ethcore::client::{
@@ -54,6 +54,8 @@ pub struct EthPubSubClient<C> {
     heads_subscribers: Arc<RwLock<Subscribers<Client>>>,
     logs_subscribers: Arc<RwLock<Subscribers<(Client, EthFilter)>>>,
     transactions_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    transaction_events_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    transaction_events_changed: Option<Box<dyn Fn() + Send + Sync>>,
 }
 
 impl<C> EthPubSubClient<C> {
@@ -62,6 +64,7 @@ impl<C> EthPubSubClient<C> {
         let heads_subscribers = Arc::new(RwLock::new(Subscribers::default()));
         let logs_subscribers = Arc::new(RwLock::new(Subscribers::default()));
         let transactions_subscribers = Arc::new(RwLock::new(Subscribers::default()));
+        let transaction_events_subscribers = Arc::new(RwLock::new(Subscribers::default()));
 
         EthPubSubClient {
             handler: Arc::new(ChainNotificationHandler {
@@ -70,10 +73,27 @@ impl<C> EthPubSubClient<C> {
                 heads_subscribers: heads_subscribers.clone(),
                 logs_subscribers: logs_subscribers.clone(),
                 transactions_subscribers: transactions_subscribers.clone(),
+                transaction_events_subscribers: transaction_events_subscribers.clone(),
             }),
             heads_subscribers,
             logs_subscribers,
             transactions_subscribers,
+            transaction_events_subscribers,
+            transaction_events_changed: None,
+        }
+    }
+
+    /// Set a callback invoked whenever `transactionEvents` subscriptions are added or removed.
+    ///
+    /// It's called without holding any subscribers lock, use
+    /// `ChainNotificationHandler::has_transaction_events_subscribers` to check the current state.
+    pub fn on_transaction_events_subscribers_changed(&mut self, f: Box<dyn Fn() + Send + Sync>) {
+        self.transaction_events_changed = Some(f);
+    }
+
+    fn transaction_events_changed(&self) {
+        if let Some(ref f) = self.transaction_events_changed {
+            f();
         }
     }
 
@@ -84,6 +104,7 @@ impl<C> EthPubSubClient<C> {
         *client.heads_subscribers.write() = Subscribers::default();
         *client.logs_subscribers.write() = Subscribers::default();
         *client.transactions_subscribers.write() = Subscribers::default();
+        *client.transaction_events_subscribers.write() = Subscribers::default();
         client
     }
 
@@ -100,6 +121,7 @@ pub struct ChainNotificationHandler<C> {
     heads_subscribers: Arc<RwLock<Subscribers<Client>>>,
     logs_subscribers: Arc<RwLock<Subscribers<(Client, EthFilter)>>>,
     transactions_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    transaction_events_subscribers: Arc<RwLock<Subscribers<Client>>>,
 }
 
 impl<C> ChainNotificationHandler<C>
@@ -124,7 +146,7 @@ where
                     pubsub::Result::Header(Box::new(RichHeader {
                         inner: Header::new(
                             header,
-                            self.client.engine().params().eip1559_transition,
+                            self.client.engine().params().header_transitions(),
                         ),
                         extra_info: extra_info.clone(),
                     })),
@@ -180,6 +202,26 @@ where
             }
         }
     }
+
+    /// Returns true if there is at least one `transactionEvents` subscription.
+    pub fn has_transaction_events_subscribers(&self) -> bool {
+        !self.transaction_events_subscribers.read().is_empty()
+    }
+
+    /// Notify all subscribers about transaction pool events.
+    pub fn notify_transaction_events(&self, events: &[miner::pool::events::Event]) {
+        for subscriber in self.transaction_events_subscribers.read().values() {
+            for event in events {
+                Self::notify(
+                    &self.executor,
+                    subscriber,
+                    pubsub::Result::TransactionEvent(Box::new(TransactionEvent::from(
+                        event.clone(),
+                    ))),
+                );
+            }
+        }
+    }
 }
 
 impl<C: BlockChainClient + EngineInfo> ChainNotify for ChainNotificationHandler<C> {
@@ -269,6 +311,14 @@ impl<C: Send + Sync + 'static> EthPubSub for EthPubSubClient<C> {
             (pubsub::Kind::NewPendingTransactions, _) => {
                 errors::invalid_params("newPendingTransactions", "Expected no parameters.")
             }
+            (pubsub::Kind::TransactionEvents, None) => {
+                self.transaction_events_subscribers.write().push(subscriber);
+                self.transaction_events_changed();
+                return;
+            }
+            (pubsub::Kind::TransactionEvents, _) => {
+                errors::invalid_params("transactionEvents", "Expected no parameters.")
+            }
             _ => errors::unimplemented(None),
         };
 
@@ -279,7 +329,15 @@ impl<C: Send + Sync + 'static> EthPubSub for EthPubSubClient<C> {
         let res = self.heads_subscribers.write().remove(&id).is_some();
         let res2 = self.logs_subscribers.write().remove(&id).is_some();
         let res3 = self.transactions_subscribers.write().remove(&id).is_some();
+        let res4 = self
+            .transaction_events_subscribers
+            .write()
+            .remove(&id)
+            .is_some();
+        if res4 {
+            self.transaction_events_changed();
+        }
 
-        Ok(res || res2 || res3)
+        Ok(res || res2 || res3 || res4)
     }
 }
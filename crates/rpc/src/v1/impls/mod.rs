@@ -35,6 +35,7 @@ mod signer;
 mod signing;
 mod signing_unsafe;
 mod traces;
+mod txpool;
 mod web3;
 
 #[cfg(any(test, feature = "accounts"))]
@@ -59,5 +60,6 @@ pub use self::{
     signing::SigningQueueClient,
     signing_unsafe::SigningUnsafeClient,
     traces::TracesClient,
+    txpool::TxPoolClient,
     web3::Web3Client,
 };
@@ -395,7 +395,7 @@ where
         };
 
         Box::new(future::ok(RichHeader {
-            inner: Header::new(&header, self.client.engine().params().eip1559_transition),
+            inner: Header::new(&header, self.client.engine().params().header_transitions()),
             extra_info: extra.unwrap_or_default(),
         }))
     }
@@ -458,7 +458,7 @@ where
                 .client
                 .block_header(id)
                 .ok_or_else(errors::state_pruned)?
-                .decode(self.client.engine().params().eip1559_transition)
+                .decode(self.client.engine().params().header_transitions())
                 .map_err(errors::decode)?;
 
             (state, header)
@@ -29,7 +29,7 @@ use sync::ManageNetwork;
 
 use jsonrpc_core::{futures::Future, BoxFuture, Result};
 use v1::{
-    helpers::errors,
+    helpers::{errors, PoolJournal},
     traits::ParitySet,
     types::{Bytes, Transaction},
 };
@@ -84,6 +84,7 @@ pub struct ParitySetClient<C, M, F = fetch::Client> {
     client: Arc<C>,
     miner: Arc<M>,
     net: Arc<dyn ManageNetwork>,
+    pool_journal: Option<Arc<dyn PoolJournal>>,
     fetch: F,
 }
 
@@ -92,11 +93,18 @@ where
     C: BlockChainClient + 'static,
 {
     /// Creates new `ParitySetClient` with given `Fetch`.
-    pub fn new(client: &Arc<C>, miner: &Arc<M>, net: &Arc<dyn ManageNetwork>, fetch: F) -> Self {
+    pub fn new(
+        client: &Arc<C>,
+        miner: &Arc<M>,
+        net: &Arc<dyn ManageNetwork>,
+        pool_journal: Option<Arc<dyn PoolJournal>>,
+        fetch: F,
+    ) -> Self {
         ParitySetClient {
             client: client.clone(),
             miner: miner.clone(),
             net: net.clone(),
+            pool_journal,
             fetch,
         }
     }
@@ -238,4 +246,16 @@ where
             .remove_transaction(&hash)
             .map(|t| Transaction::from_pending(t.pending().clone())))
     }
+
+    fn dump_transaction_pool(&self) -> Result<usize> {
+        match self.pool_journal {
+            Some(ref journal) => journal
+                .dump()
+                .map_err(|e| errors::internal("Writing pool journal failed", e)),
+            None => Err(errors::unsupported(
+                "Transaction pool journal is disabled.",
+                Some("Restart the node with --tx-queue-journal."),
+            )),
+        }
+    }
 }
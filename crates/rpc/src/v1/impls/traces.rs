@@ -30,8 +30,9 @@ use v1::{
     helpers::{errors, fake_sign},
     traits::Traces,
     types::{
-        block_number_to_id, BlockNumber, Bytes, CallRequest, Index, LocalizedTrace, TraceFilter,
-        TraceOptions, TraceResults, TraceResultsWithTransactionHash,
+        block_number_to_id, to_call_overrides, BlockNumber, BlockOverride, Bytes, CallRequest,
+        Index, LocalizedTrace, StateOverride, TraceFilter, TraceOptions, TraceResults,
+        TraceResultsWithTransactionHash,
     },
     Metadata,
 };
@@ -41,6 +42,7 @@ fn to_call_analytics(flags: TraceOptions) -> CallAnalytics {
         transaction_tracing: flags.contains(&("trace".to_owned())),
         vm_tracing: flags.contains(&("vmTrace".to_owned())),
         state_diffing: flags.contains(&("stateDiff".to_owned())),
+        access_list_recording: false,
     }
 }
 
@@ -105,11 +107,14 @@ where
         request: CallRequest,
         flags: TraceOptions,
         block: Option<BlockNumber>,
+        state_override: Option<StateOverride>,
+        block_override: Option<BlockOverride>,
     ) -> Result<TraceResults> {
         let block = block.unwrap_or_default();
 
         let request = CallRequest::into(request);
         let signed = fake_sign::sign_call(request)?;
+        let overrides = to_call_overrides(state_override, block_override)?;
 
         let id = match block {
             BlockNumber::Hash { hash, .. } => BlockId::Hash(hash),
@@ -132,12 +137,13 @@ where
             .ok_or_else(errors::state_pruned)?;
 
         self.client
-            .call(
+            .call_with_overrides(
                 &signed,
                 to_call_analytics(flags),
+                &overrides,
                 &mut state,
                 &header
-                    .decode(self.client.engine().params().eip1559_transition)
+                    .decode(self.client.engine().params().header_transitions())
                     .map_err(errors::decode)?,
             )
             .map(TraceResults::from)
@@ -185,7 +191,7 @@ where
                 &requests,
                 &mut state,
                 &header
-                    .decode(self.client.engine().params().eip1559_transition)
+                    .decode(self.client.engine().params().header_transitions())
                     .map_err(errors::decode)?,
             )
             .map(|results| results.into_iter().map(TraceResults::from).collect())
@@ -230,7 +236,7 @@ where
                 to_call_analytics(flags),
                 &mut state,
                 &header
-                    .decode(self.client.engine().params().eip1559_transition)
+                    .decode(self.client.engine().params().header_transitions())
                     .map_err(errors::decode)?,
             )
             .map(TraceResults::from)
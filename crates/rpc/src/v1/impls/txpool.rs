@@ -0,0 +1,90 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction pool RPC implementation.
+
+use std::sync::Arc;
+
+use ethcore::{client::Nonce, miner::MinerService};
+use ethereum_types::H160;
+use miner::pool::VerifiedTransaction;
+
+use jsonrpc_core::Result;
+use v1::{
+    traits::TxPool,
+    types::{Transaction, TxPoolContent, TxPoolContentFrom, TxPoolInspect, TxPoolStatus},
+};
+
+/// Transaction pool rpc implementation.
+pub struct TxPoolClient<C, M> {
+    client: Arc<C>,
+    miner: Arc<M>,
+}
+
+impl<C, M> TxPoolClient<C, M>
+where
+    C: Nonce + Sync,
+    M: MinerService,
+{
+    /// Creates new transaction pool client.
+    pub fn new(client: Arc<C>, miner: Arc<M>) -> Self {
+        TxPoolClient { client, miner }
+    }
+
+    /// Returns pending and queued transactions matching given predicate.
+    fn transactions<F>(&self, filter: F) -> (Vec<Transaction>, Vec<Transaction>)
+    where
+        F: Fn(&VerifiedTransaction) -> bool,
+    {
+        let (ready, future) = self.miner.ready_and_future_transactions(&*self.client);
+        let convert = |txs: Vec<Arc<VerifiedTransaction>>| {
+            txs.into_iter()
+                .filter(|tx| filter(tx))
+                .map(|tx| Transaction::from_pending(tx.pending().clone()))
+                .collect()
+        };
+        (convert(ready), convert(future))
+    }
+}
+
+impl<C, M> TxPool for TxPoolClient<C, M>
+where
+    C: Nonce + Sync + Send + 'static,
+    M: MinerService + 'static,
+{
+    fn content(&self) -> Result<TxPoolContent> {
+        let (pending, queued) = self.transactions(|_| true);
+        Ok(TxPoolContent::new(pending, queued))
+    }
+
+    fn content_from(&self, address: H160) -> Result<TxPoolContentFrom> {
+        let (pending, queued) = self.transactions(|tx| tx.pending().sender() == address);
+        Ok(TxPoolContentFrom::new(pending, queued))
+    }
+
+    fn inspect(&self) -> Result<TxPoolInspect> {
+        let (pending, queued) = self.transactions(|_| true);
+        Ok(TxPoolInspect::new(pending, queued))
+    }
+
+    fn status(&self) -> Result<TxPoolStatus> {
+        let (pending, queued) = self.miner.ready_and_future_transactions(&*self.client);
+        Ok(TxPoolStatus {
+            pending: (pending.len() as u64).into(),
+            queued: (queued.len() as u64).into(),
+        })
+    }
+}
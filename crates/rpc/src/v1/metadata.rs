@@ -29,6 +29,8 @@ pub struct Metadata {
     pub origin: Origin,
     /// Request PubSub Session
     pub session: Option<Arc<Session>>,
+    /// Request carries a valid JWT
+    pub authenticated: bool,
 }
 
 impl jsonrpc_core::Metadata for Metadata {}
@@ -42,14 +42,17 @@ pub mod metadata;
 pub mod traits;
 
 pub use self::{
-    extractors::{RpcExtractor, WsDispatcher, WsExtractor, WsStats},
-    helpers::{block_import, dispatch, NetworkSettings},
+    extractors::{
+        HttpJwtExtractor, HttpJwtMiddleware, JwtDispatcher, RpcExtractor, WsDispatcher,
+        WsExtractor, WsStats,
+    },
+    helpers::{block_import, dispatch, NetworkSettings, PoolJournal},
     impls::*,
     metadata::Metadata,
     traits::{
         Debug, Eth, EthFilter, EthPubSub, EthSigning, Net, Parity, ParityAccounts,
         ParityAccountsInfo, ParitySet, ParitySetAccounts, ParitySigning, Personal, PubSub, Rpc,
-        SecretStore, Signer, Traces, Web3,
+        SecretStore, Signer, Traces, TxPool, Web3,
     },
     types::Origin,
 };
@@ -98,7 +98,7 @@ impl EthTester {
 
         for b in chain.blocks_rlp() {
             if let Ok(block) =
-                Unverified::from_rlp(b, tester.client.engine().params().eip1559_transition)
+                Unverified::from_rlp(b, tester.client.engine().params().header_transitions())
             {
                 let _ = tester.client.import_block(block);
                 tester.client.flush_queue();
@@ -568,7 +568,7 @@ fn verify_transaction_counts(name: String, chain: BlockChain) {
 
     let mut id = 1;
     for b in chain.blocks_rlp().into_iter().filter_map(|b| {
-        Unverified::from_rlp(b, tester.client.engine().params().eip1559_transition).ok()
+        Unverified::from_rlp(b, tester.client.engine().params().header_transitions()).ok()
     }) {
         let count = b.transactions.len();
 
@@ -30,7 +30,7 @@ use ethcore::{
     },
     engines::{signer::EngineSigner, EthEngine},
     error::Error,
-    miner::{self, AuthoringParams, MinerService, TransactionFilter},
+    miner::{self, AuthoringParams, Bundle, BundleError, MinerService, TransactionFilter},
 };
 use ethereum_types::{Address, H256, U256};
 use miner::pool::{
@@ -53,6 +53,8 @@ pub struct TestMinerService {
     pub imported_transactions: Mutex<Vec<SignedTransaction>>,
     /// Pre-existed pending transactions
     pub pending_transactions: Mutex<HashMap<H256, SignedTransaction>>,
+    /// Pre-existed future transactions
+    pub future_transactions: Mutex<HashMap<H256, SignedTransaction>>,
     /// Pre-existed local transactions
     pub local_transactions: Mutex<BTreeMap<H256, LocalTransactionStatus>>,
     /// Pre-existed pending receipts
@@ -63,6 +65,8 @@ pub struct TestMinerService {
     pub min_gas_price: RwLock<Option<U256>>,
     /// Signer (if any)
     pub signer: RwLock<Option<Box<dyn EngineSigner>>>,
+    /// Submitted bundles
+    pub bundles: Mutex<Vec<Bundle>>,
 
     authoring_params: RwLock<AuthoringParams>,
 }
@@ -72,6 +76,7 @@ impl Default for TestMinerService {
         TestMinerService {
             imported_transactions: Default::default(),
             pending_transactions: Default::default(),
+            future_transactions: Default::default(),
             local_transactions: Default::default(),
             pending_receipts: Default::default(),
             next_nonces: Default::default(),
@@ -82,6 +87,7 @@ impl Default for TestMinerService {
                 extra_data: vec![1, 2, 3, 4],
             }),
             signer: RwLock::new(None),
+            bundles: Default::default(),
         }
     }
 }
@@ -250,6 +256,12 @@ impl MinerService for TestMinerService {
             .map(|tx| Arc::new(VerifiedTransaction::from_pending_block_transaction(tx)))
     }
 
+    fn submit_bundle<C: Sync>(&self, _chain: &C, bundle: Bundle) -> Result<H256, BundleError> {
+        let hash = bundle.hash();
+        self.bundles.lock().push(bundle);
+        Ok(hash)
+    }
+
     fn remove_transaction(&self, hash: &H256) -> Option<Arc<VerifiedTransaction>> {
         self.pending_transactions
             .lock()
@@ -311,6 +323,25 @@ impl MinerService for TestMinerService {
             .collect()
     }
 
+    fn ready_and_future_transactions<C>(
+        &self,
+        _chain: &C,
+    ) -> (Vec<Arc<VerifiedTransaction>>, Vec<Arc<VerifiedTransaction>>)
+    where
+        C: Nonce + Sync,
+    {
+        let verified = |txs: &HashMap<H256, SignedTransaction>| {
+            txs.values()
+                .cloned()
+                .map(|tx| Arc::new(VerifiedTransaction::from_pending_block_transaction(tx)))
+                .collect()
+        };
+        (
+            verified(&self.pending_transactions.lock()),
+            verified(&self.future_transactions.lock()),
+        )
+    }
+
     fn pending_receipts(&self, _best_block: BlockNumber) -> Option<Vec<RichReceipt>> {
         Some(self.pending_receipts.lock().clone())
     }
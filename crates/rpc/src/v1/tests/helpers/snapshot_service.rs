@@ -14,7 +14,10 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethcore::snapshot::{CreationStatus, ManifestData, RestorationStatus, SnapshotService};
+use ethcore::snapshot::{
+    snap::{MissingNode, SnapData, SnapPivot},
+    CreationStatus, ManifestData, RestorationStatus, SnapshotService,
+};
 
 use bytes::Bytes;
 use ethereum_types::H256;
@@ -67,5 +70,10 @@ impl SnapshotService for TestSnapshotService {
     fn abort_snapshot(&self) {}
     fn restore_state_chunk(&self, _hash: H256, _chunk: Bytes) {}
     fn restore_block_chunk(&self, _hash: H256, _chunk: Bytes) {}
+    fn begin_snap_restore(&self, _pivot: SnapPivot) {}
+    fn restore_snap_data(&self, _data: SnapData) {}
+    fn snap_missing_data(&self, _max: usize) -> Option<(Vec<MissingNode>, Vec<H256>)> {
+        None
+    }
     fn shutdown(&self) {}
 }
@@ -21,7 +21,10 @@ use network::client_version::ClientVersion;
 use parking_lot::RwLock;
 use stats::{PrometheusMetrics, PrometheusRegistry};
 use std::collections::BTreeMap;
-use sync::{EthProtocolInfo, PeerInfo, SyncProvider, SyncState, SyncStatus, TransactionStats};
+use sync::{
+    EthProtocolInfo, PacketTraffic, PeerInfo, PeerTraffic, SyncProvider, SyncState, SyncStatus,
+    TrafficCounter, TransactionStats,
+};
 
 /// TestSyncProvider config.
 pub struct Config {
@@ -89,7 +92,29 @@ impl SyncProvider for TestSyncProvider {
                     version: 63,
                     difficulty: Some(40.into()),
                     head: H256::from_low_u64_be(50),
+                    reputation: 0,
                 }),
+                traffic: PeerTraffic {
+                    ingress: TrafficCounter {
+                        bytes: 1200,
+                        messages: 3,
+                    },
+                    egress: TrafficCounter {
+                        bytes: 100,
+                        messages: 2,
+                    },
+                    ingress_rate: 120.0,
+                    egress_rate: 10.0,
+                    packets: vec![PacketTraffic {
+                        protocol: "eth".to_owned(),
+                        packet: "Transactions".to_owned(),
+                        ingress: TrafficCounter {
+                            bytes: 1000,
+                            messages: 1,
+                        },
+                        egress: TrafficCounter::default(),
+                    }],
+                },
             },
             PeerInfo {
                 id: None,
@@ -101,7 +126,9 @@ impl SyncProvider for TestSyncProvider {
                     version: 65,
                     difficulty: None,
                     head: H256::from_low_u64_be(60),
+                    reputation: -250,
                 }),
+                traffic: Default::default(),
             },
         ]
     }
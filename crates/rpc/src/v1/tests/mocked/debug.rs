@@ -14,16 +14,23 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
-use ethcore::client::TestBlockChainClient;
+use ethcore::{
+    client::{BlockChainClient, EachBlockWith, TestBlockChainClient},
+    trace::{geth, GethTrace, GethTracer},
+};
+use ethereum_types::{Address, H256};
+use types::ids::BlockId;
 
 use jsonrpc_core::IoHandler;
 use v1::{Debug, DebugClient};
 
 fn io() -> IoHandler {
-    let client = Arc::new(TestBlockChainClient::new());
+    io_with(Arc::new(TestBlockChainClient::new()))
+}
 
+fn io_with(client: Arc<TestBlockChainClient>) -> IoHandler {
     let mut io = IoHandler::new();
     io.extend_with(DebugClient::new(client).to_delegate());
     io
@@ -35,3 +42,123 @@ fn rpc_debug_get_bad_blocks() {
     let response = "{\"jsonrpc\":\"2.0\",\"result\":[{\"author\":\"0x0000000000000000000000000000000000000000\",\"difficulty\":\"0x0\",\"extraData\":\"0x\",\"gasLimit\":\"0x0\",\"gasUsed\":\"0x0\",\"hash\":\"0x27bfb37e507ce90da141307204b1c6ba24194380613590ac50ca4b1d7198ff65\",\"logsBloom\":\"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\",\"miner\":\"0x0000000000000000000000000000000000000000\",\"number\":\"0x0\",\"parentHash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"reason\":\"Invalid block\",\"receiptsRoot\":\"0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421\",\"rlp\":\"\\\"0x010203\\\"\",\"sealFields\":[],\"sha3Uncles\":\"0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347\",\"size\":\"0x3\",\"stateRoot\":\"0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421\",\"timestamp\":\"0x0\",\"totalDifficulty\":null,\"transactions\":[],\"transactionsRoot\":\"0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421\",\"uncles\":[]}],\"id\":1}";
     assert_eq!(io().handle_request_sync(request), Some(response.to_owned()));
 }
+
+#[test]
+fn rpc_debug_trace_transaction_unknown_tracer() {
+    let request = r#"{"jsonrpc": "2.0", "method": "debug_traceTransaction", "params": ["0x0000000000000000000000000000000000000000000000000000000000000001", {"tracer": "fourByteTracer"}], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: tracer","data":"\"fourByteTracer\""},"id":1}"#;
+    assert_eq!(io().handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_debug_trace_transaction_call_tracer() {
+    let client = Arc::new(TestBlockChainClient::new());
+    client.set_geth_trace(Ok(GethTrace::Call(geth::CallFrame {
+        call_type: geth::CallFrameType::Call,
+        from: Address::from_low_u64_be(1),
+        to: Address::from_low_u64_be(2),
+        value: Some(0x10.into()),
+        gas: 100_000.into(),
+        gas_used: 0x5300.into(),
+        input: vec![],
+        output: vec![0x01],
+        error: None,
+        calls: vec![geth::CallFrame {
+            call_type: geth::CallFrameType::StaticCall,
+            from: Address::from_low_u64_be(2),
+            to: Address::from_low_u64_be(3),
+            value: None,
+            gas: 0x100.into(),
+            gas_used: 0x10.into(),
+            input: vec![0xab],
+            output: vec![],
+            error: None,
+            calls: vec![],
+        }],
+    })));
+
+    let request = r#"{"jsonrpc": "2.0", "method": "debug_traceTransaction", "params": ["0x0000000000000000000000000000000000000000000000000000000000000001", {"tracer": "callTracer"}], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":{"type":"CALL","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","value":"0x10","gas":"0x186a0","gasUsed":"0x5300","input":"0x","output":"0x01","calls":[{"type":"STATICCALL","from":"0x0000000000000000000000000000000000000002","to":"0x0000000000000000000000000000000000000003","gas":"0x100","gasUsed":"0x10","input":"0xab"}]},"id":1}"#;
+    assert_eq!(
+        io_with(client.clone()).handle_request_sync(request),
+        Some(response.to_owned())
+    );
+    assert_eq!(
+        *client.geth_tracer.read(),
+        Some(GethTracer::Call {
+            only_top_call: false
+        })
+    );
+}
+
+#[test]
+fn rpc_debug_trace_call_prestate_tracer() {
+    let client = Arc::new(TestBlockChainClient::new());
+    let mut accounts = BTreeMap::new();
+    accounts.insert(
+        Address::from_low_u64_be(1),
+        geth::PrestateAccount {
+            balance: Some(0x64.into()),
+            nonce: Some(1.into()),
+            ..Default::default()
+        },
+    );
+    accounts.insert(
+        Address::from_low_u64_be(2),
+        geth::PrestateAccount {
+            balance: Some(0.into()),
+            code: Some(vec![0x60, 0x00]),
+            storage: vec![(H256::from_low_u64_be(1), H256::from_low_u64_be(7))]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        },
+    );
+    client.set_geth_trace(Ok(GethTrace::Prestate(accounts)));
+
+    let request = r#"{"jsonrpc": "2.0", "method": "debug_traceCall", "params": [{"from": "0x0000000000000000000000000000000000000001", "to": "0x0000000000000000000000000000000000000002"}, "latest", {"tracer": "prestateTracer"}], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":{"0x0000000000000000000000000000000000000001":{"balance":"0x64","nonce":1},"0x0000000000000000000000000000000000000002":{"balance":"0x0","code":"0x6000","storage":{"0x0000000000000000000000000000000000000000000000000000000000000001":"0x0000000000000000000000000000000000000000000000000000000000000007"}}},"id":1}"#;
+    assert_eq!(
+        io_with(client.clone()).handle_request_sync(request),
+        Some(response.to_owned())
+    );
+    assert_eq!(
+        *client.geth_tracer.read(),
+        Some(GethTracer::Prestate { diff_mode: false })
+    );
+}
+
+#[test]
+fn rpc_debug_trace_block_prestate_tracer_diff_mode() {
+    let client = Arc::new(TestBlockChainClient::new());
+    client.add_blocks(1, EachBlockWith::Transaction);
+    let tx_hash = client
+        .block_body(BlockId::Number(1))
+        .unwrap()
+        .transaction_hashes()[0];
+    let account = |balance: u64, nonce: u64| geth::PrestateAccount {
+        balance: Some(balance.into()),
+        nonce: Some(nonce.into()),
+        ..Default::default()
+    };
+    let mut diff = geth::PrestateDiff::default();
+    diff.pre
+        .insert(Address::from_low_u64_be(1), account(0x64, 1));
+    diff.post
+        .insert(Address::from_low_u64_be(1), account(0x50, 2));
+    client.set_geth_trace(Ok(GethTrace::PrestateDiff(diff)));
+
+    let request = r#"{"jsonrpc": "2.0", "method": "debug_traceBlockByNumber", "params": ["0x1", {"tracer": "prestateTracer", "tracerConfig": {"diffMode": true}}], "id": 1}"#;
+    let response = format!(
+        r#"{{"jsonrpc":"2.0","result":[{{"txHash":"{:#x}","result":{{"pre":{{"0x0000000000000000000000000000000000000001":{{"balance":"0x64","nonce":1}}}},"post":{{"0x0000000000000000000000000000000000000001":{{"balance":"0x50","nonce":2}}}}}}}}],"id":1}}"#,
+        tx_hash
+    );
+    assert_eq!(
+        io_with(client.clone()).handle_request_sync(request),
+        Some(response)
+    );
+    assert_eq!(
+        *client.geth_tracer.read(),
+        Some(GethTracer::Prestate { diff_mode: true })
+    );
+}
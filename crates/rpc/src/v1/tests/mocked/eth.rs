@@ -846,6 +846,7 @@ fn rpc_eth_call_latest() {
         trace: vec![],
         vm_trace: None,
         state_diff: None,
+        access_list: None,
     }));
 
     let request = r#"{
@@ -870,6 +871,240 @@ fn rpc_eth_call_latest() {
     );
 }
 
+#[test]
+fn rpc_eth_call_with_overrides() {
+    let tester = EthTester::default();
+    tester.client.set_execution_result(Ok(Executed {
+        exception: None,
+        gas: U256::zero(),
+        gas_used: U256::from(0xff30),
+        refunded: U256::from(0x5),
+        cumulative_gas_used: U256::zero(),
+        logs: vec![],
+        contracts_created: vec![],
+        output: vec![0x12, 0x34, 0xff],
+        trace: vec![],
+        vm_trace: None,
+        state_diff: None,
+        access_list: None,
+    }));
+
+    let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_call",
+		"params": [{
+			"from": "0xb60e8dd61c5d32be8058bb8eb970870f07233155",
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567",
+			"data": "0xd46e8dd6"
+		},
+		"latest",
+		{
+			"0xd46e8dd67c5d32be8058bb8eb970870f07244567": {
+				"balance": "0x1",
+				"code": "0x6000",
+				"state": {
+					"0x0000000000000000000000000000000000000000000000000000000000000001": "0x0000000000000000000000000000000000000000000000000000000000000002"
+				}
+			}
+		},
+		{
+			"number": "0x10",
+			"timestamp": "0x20",
+			"gasLimit": "0x30",
+			"coinbase": "0xb60e8dd61c5d32be8058bb8eb970870f07233155",
+			"baseFee": "0x7"
+		}],
+		"id": 1
+	}"#;
+    let response = r#"{"jsonrpc":"2.0","result":"0x1234ff","id":1}"#;
+
+    assert_eq!(
+        tester.io.handle_request_sync(request),
+        Some(response.to_owned())
+    );
+}
+
+#[test]
+fn rpc_eth_simulate() {
+    let tester = EthTester::default();
+    tester.client.set_execution_result(Ok(Executed {
+        exception: None,
+        gas: U256::zero(),
+        gas_used: U256::from(0x5208),
+        refunded: U256::zero(),
+        cumulative_gas_used: U256::from(0x5208),
+        logs: vec![LogEntry {
+            address: Address::from_low_u64_be(1),
+            topics: vec![],
+            data: vec![],
+        }],
+        contracts_created: vec![],
+        output: vec![0x12],
+        trace: vec![],
+        vm_trace: None,
+        state_diff: None,
+        access_list: None,
+    }));
+
+    let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_simulateV1",
+		"params": [{
+			"blockStateCalls": [{
+				"stateOverrides": {"0x0000000000000000000000000000000000000001": {"code": "0x6000"}},
+				"calls": [{"to": "0x0000000000000000000000000000000000000001"}]
+			}, {
+				"calls": [
+					{"to": "0x0000000000000000000000000000000000000001"},
+					{"to": "0x0000000000000000000000000000000000000001", "nonce": "0x1"}
+				]
+			}]
+		},
+		"latest"],
+		"id": 1
+	}"#;
+    let response = tester.io.handle_request_sync(request).unwrap();
+    let response = serde_json::Value::from_str(&response).unwrap();
+    let blocks = response["result"].as_array().unwrap();
+
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(blocks[0]["number"], "0x1");
+    assert_eq!(blocks[1]["number"], "0x2");
+    assert_eq!(blocks[1]["transactions"].as_array().unwrap().len(), 2);
+    let call = &blocks[1]["calls"][1];
+    assert_eq!(call["returnData"], "0x12");
+    assert_eq!(call["status"], "0x1");
+    assert_eq!(call["gasUsed"], "0x5208");
+    assert_eq!(call["logs"][0]["transactionIndex"], "0x1");
+    assert_eq!(call["logs"][0]["logIndex"], "0x1");
+    assert_eq!(call["logs"][0]["blockHash"], blocks[1]["hash"]);
+}
+
+#[test]
+fn rpc_eth_simulate_blocks_out_of_order() {
+    let tester = EthTester::default();
+
+    let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_simulateV1",
+		"params": [{
+			"blockStateCalls": [{"blockOverrides": {"number": "0x10"}}, {"blockOverrides": {"number": "0x10"}}]
+		}],
+		"id": 1
+	}"#;
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: blockOverrides","data":"\"block numbers and timestamps must be increasing\""},"id":1}"#;
+
+    assert_eq!(
+        tester.io.handle_request_sync(request),
+        Some(response.to_owned())
+    );
+}
+
+#[test]
+fn rpc_eth_simulate_rejects_large_block_gaps() {
+    let tester = EthTester::default();
+
+    let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_simulateV1",
+		"params": [{
+			"blockStateCalls": [{"blockOverrides": {"number": "0xffffffffffff"}}]
+		}],
+		"id": 1
+	}"#;
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: blockOverrides","data":"\"at most 256 blocks can be skipped between simulated blocks\""},"id":1}"#;
+
+    assert_eq!(
+        tester.io.handle_request_sync(request),
+        Some(response.to_owned())
+    );
+}
+
+#[test]
+fn rpc_eth_create_access_list() {
+    let tester = EthTester::default();
+    tester.client.set_execution_result(Ok(Executed {
+        exception: None,
+        gas: U256::zero(),
+        gas_used: U256::from(0xff30),
+        refunded: U256::from(0x5),
+        cumulative_gas_used: U256::zero(),
+        logs: vec![],
+        contracts_created: vec![],
+        output: vec![0x12, 0x34, 0xff],
+        trace: vec![],
+        vm_trace: None,
+        state_diff: None,
+        access_list: Some(vec![(
+            Address::from_low_u64_be(1),
+            vec![H256::from_low_u64_be(2)],
+        )]),
+    }));
+
+    let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_createAccessList",
+		"params": [{
+			"from": "0xb60e8dd61c5d32be8058bb8eb970870f07233155",
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567",
+			"gas": "0x76c0",
+			"data": "0xd46e8dd6"
+		},
+		"latest"],
+		"id": 1
+	}"#;
+    let response = r#"{"jsonrpc":"2.0","result":{"accessList":[{"address":"0x0000000000000000000000000000000000000001","storageKeys":["0x0000000000000000000000000000000000000000000000000000000000000002"]}],"gasUsed":"0xff30"},"id":1}"#;
+
+    assert_eq!(
+        tester.io.handle_request_sync(request),
+        Some(response.to_owned())
+    );
+}
+
+#[test]
+fn rpc_eth_create_access_list_with_oscillating_contract() {
+    let tester = EthTester::default();
+    let executed = |slot| Executed {
+        exception: None,
+        gas: U256::zero(),
+        gas_used: U256::from(0xff30),
+        refunded: U256::zero(),
+        cumulative_gas_used: U256::zero(),
+        logs: vec![],
+        contracts_created: vec![],
+        output: vec![],
+        trace: vec![],
+        vm_trace: None,
+        state_diff: None,
+        access_list: Some(vec![(
+            Address::from_low_u64_be(1),
+            vec![H256::from_low_u64_be(slot)],
+        )]),
+    };
+    // A contract that reads a different slot depending on which one is already warm never
+    // produces the same access list twice in a row.
+    tester
+        .client
+        .set_execution_results(vec![Ok(executed(2)), Ok(executed(3))]);
+
+    let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_createAccessList",
+		"params": [{
+			"from": "0xb60e8dd61c5d32be8058bb8eb970870f07233155",
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567"
+		},
+		"latest"],
+		"id": 1
+	}"#;
+    let response = r#"{"jsonrpc":"2.0","result":{"accessList":[{"address":"0x0000000000000000000000000000000000000001","storageKeys":["0x0000000000000000000000000000000000000000000000000000000000000002","0x0000000000000000000000000000000000000000000000000000000000000003"]}],"gasUsed":"0xff30"},"id":1}"#;
+
+    assert_eq!(
+        tester.io.handle_request_sync(request),
+        Some(response.to_owned())
+    );
+}
+
 #[test]
 fn rpc_eth_call_pending() {
     let tester = EthTester::default();
@@ -885,6 +1120,7 @@ fn rpc_eth_call_pending() {
         trace: vec![],
         vm_trace: None,
         state_diff: None,
+        access_list: None,
     }));
 
     let request = r#"{
@@ -925,6 +1161,7 @@ fn rpc_eth_call() {
         trace: vec![],
         vm_trace: None,
         state_diff: None,
+        access_list: None,
     }));
 
     let request = r#"{
@@ -964,6 +1201,7 @@ fn rpc_eth_call_default_block() {
         trace: vec![],
         vm_trace: None,
         state_diff: None,
+        access_list: None,
     }));
 
     let request = r#"{
@@ -1002,6 +1240,7 @@ fn rpc_eth_estimate_gas() {
         trace: vec![],
         vm_trace: None,
         state_diff: None,
+        access_list: None,
     }));
 
     let request = r#"{
@@ -1041,6 +1280,7 @@ fn rpc_eth_estimate_gas_pending() {
         trace: vec![],
         vm_trace: None,
         state_diff: None,
+        access_list: None,
     }));
 
     let request = r#"{
@@ -1081,6 +1321,7 @@ fn rpc_eth_estimate_gas_default_block() {
         trace: vec![],
         vm_trace: None,
         state_diff: None,
+        access_list: None,
     }));
 
     let request = r#"{
@@ -1186,6 +1427,66 @@ fn rpc_eth_send_raw_transaction() {
     assert_eq!(tester.io.handle_request_sync(&req), Some(res));
 }
 
+#[test]
+fn rpc_eth_send_bundle() {
+    let tester = EthTester::default();
+    let address = tester
+        .accounts_provider
+        .new_account(&"abcd".into())
+        .unwrap();
+    tester
+        .accounts_provider
+        .unlock_account_permanently(address, "abcd".into())
+        .unwrap();
+
+    let t = TypedTransaction::Legacy(Transaction {
+        nonce: U256::zero(),
+        gas_price: U256::from(0x9184e72a000u64),
+        gas: U256::from(0x76c0),
+        action: Action::Call(
+            Address::from_str("d46e8dd67c5d32be8058bb8eb970870f07244567").unwrap(),
+        ),
+        value: U256::from(0x9184e72au64),
+        data: vec![],
+    });
+    let signature = tester
+        .accounts_provider
+        .sign(address, None, t.signature_hash(None))
+        .unwrap();
+    let t = t.with_signature(signature, None);
+
+    let req = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_sendBundle",
+		"params": [{
+			"txs": ["0x"#
+        .to_owned()
+        + &t.encode().to_hex()
+        + r#""],
+			"blockNumber": "0x1",
+			"revertingTxHashes": [""#
+        + &format!("0x{:x}", t.hash())
+        + r#""]
+		}],
+		"id": 1
+	}"#;
+
+    let response = tester.io.handle_request_sync(&req).unwrap();
+
+    let bundles = tester.miner.bundles.lock().clone();
+    assert_eq!(bundles.len(), 1);
+    assert_eq!(bundles[0].block_number, 1);
+    assert_eq!(bundles[0].transactions.len(), 1);
+    assert_eq!(bundles[0].transactions[0].hash(), t.hash());
+    assert!(bundles[0].reverting_hashes.contains(&t.hash()));
+    assert_eq!(
+        response,
+        r#"{"jsonrpc":"2.0","result":{"bundleHash":""#.to_owned()
+            + &format!("0x{:x}", bundles[0].hash())
+            + r#""},"id":1}"#
+    );
+}
+
 #[test]
 fn rpc_eth_transaction_receipt() {
     let receipt = LocalizedReceipt {
@@ -1307,6 +1608,76 @@ fn rpc_eth_pending_receipt() {
     );
 }
 
+#[test]
+fn rpc_eth_block_receipts() {
+    let receipt = LocalizedReceipt {
+        from: Address::from_low_u64_be(9),
+        to: None,
+        transaction_hash: H256::from_low_u64_be(1),
+        transaction_index: 0,
+        transaction_type: TypedTxId::AccessList,
+        block_hash: H256::from_low_u64_be(3),
+        block_number: 0,
+        cumulative_gas_used: 21_000.into(),
+        gas_used: 21_000.into(),
+        contract_address: None,
+        logs: vec![],
+        log_bloom: Bloom::zero(),
+        outcome: TransactionOutcome::StatusCode(1),
+        effective_gas_price: 0x10.into(),
+    };
+    let tester = EthTester::default();
+    tester
+        .client
+        .set_transaction_receipt(TransactionId::Hash(H256::from_low_u64_be(1)), receipt);
+
+    let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_getBlockReceipts",
+		"params": ["latest"],
+		"id": 1
+	}"#;
+    let response = r#"{"jsonrpc":"2.0","result":[{"blockHash":"0x0000000000000000000000000000000000000000000000000000000000000003","blockNumber":"0x0","contractAddress":null,"cumulativeGasUsed":"0x5208","effectiveGasPrice":"0x10","from":"0x0000000000000000000000000000000000000009","gasUsed":"0x5208","logs":[],"logsBloom":"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","status":"0x1","to":null,"transactionHash":"0x0000000000000000000000000000000000000000000000000000000000000001","transactionIndex":"0x0","type":"0x1"}],"id":1}"#;
+
+    assert_eq!(
+        tester.io.handle_request_sync(request),
+        Some(response.to_owned())
+    );
+}
+
+#[test]
+fn rpc_eth_block_receipts_pending() {
+    let pending = RichReceipt {
+        from: Address::from_low_u64_be(9),
+        to: Some(Address::from_low_u64_be(10)),
+        transaction_hash: H256::from_low_u64_be(1),
+        transaction_index: 0,
+        transaction_type: TypedTxId::Legacy,
+        cumulative_gas_used: U256::from(0x20),
+        gas_used: U256::from(0x10),
+        contract_address: None,
+        logs: Vec::new(),
+        log_bloom: Bloom::zero(),
+        outcome: TransactionOutcome::Unknown,
+        effective_gas_price: Default::default(),
+    };
+    let tester = EthTester::default();
+    tester.miner.pending_receipts.lock().push(pending);
+
+    let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_getBlockReceipts",
+		"params": ["pending"],
+		"id": 1
+	}"#;
+    let response = r#"{"jsonrpc":"2.0","result":[{"blockHash":null,"blockNumber":null,"contractAddress":null,"cumulativeGasUsed":"0x20","effectiveGasPrice":"0x0","from":"0x0000000000000000000000000000000000000009","gasUsed":"0x10","logs":[],"logsBloom":"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","to":"0x000000000000000000000000000000000000000a","transactionHash":"0x0000000000000000000000000000000000000000000000000000000000000001","transactionIndex":"0x0","type":"0x0"}],"id":1}"#;
+
+    assert_eq!(
+        tester.io.handle_request_sync(request),
+        Some(response.to_owned())
+    );
+}
+
 // These tests are incorrect: their output is undefined as long as eth_getCompilers is [].
 // Will ignore for now, but should probably be replaced by more substantial tests which check
 // the output of eth_getCompilers to determine whether to test. CI systems can then be preinstalled
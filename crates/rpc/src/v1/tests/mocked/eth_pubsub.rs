@@ -31,6 +31,7 @@ use ethcore::client::{
 };
 use ethereum_types::{Address, H256};
 use parity_runtime::Runtime;
+use parking_lot::Mutex;
 
 const DURATION_ZERO: Duration = Duration::from_millis(0);
 
@@ -268,6 +269,66 @@ fn should_subscribe_to_pending_transactions() {
     assert_eq!(res, None);
 }
 
+#[test]
+fn should_subscribe_to_transaction_events() {
+    use miner::pool::events::{Event, EventKind};
+
+    // given
+    let el = Runtime::with_thread_count(1);
+    let client = TestBlockChainClient::new();
+
+    let mut pubsub = EthPubSubClient::new_test(Arc::new(client), el.executor());
+    let handler = pubsub.handler().upgrade().unwrap();
+    let changes = Arc::new(Mutex::new(vec![]));
+    let (c, h) = (changes.clone(), pubsub.handler());
+    pubsub.on_transaction_events_subscribers_changed(Box::new(move || {
+        let h = h.upgrade().unwrap();
+        c.lock().push(h.has_transaction_events_subscribers());
+    }));
+    let pubsub = pubsub.to_delegate();
+
+    let mut io = MetaIoHandler::default();
+    io.extend_with(pubsub);
+
+    let mut metadata = Metadata::default();
+    let (sender, receiver) = futures::sync::mpsc::channel(8);
+    metadata.session = Some(Arc::new(Session::new(sender)));
+
+    // Subscribe
+    let request = r#"{"jsonrpc": "2.0", "method": "eth_subscribe", "params": ["transactionEvents"], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":"0x43ca64edf03768e1","id":1}"#;
+    assert_eq!(
+        io.handle_request_sync(request, metadata.clone()),
+        Some(response.to_owned())
+    );
+
+    // Send an event
+    handler.notify_transaction_events(&[Event {
+        hash: H256::from_low_u64_be(5),
+        from: Address::from_low_u64_be(1),
+        nonce: 2.into(),
+        kind: EventKind::Replaced {
+            replaced_by: H256::from_low_u64_be(7),
+        },
+    }]);
+
+    let (res, receiver) = receiver.into_future().wait().unwrap();
+    let response = r#"{"jsonrpc":"2.0","method":"eth_subscription","params":{"result":{"hash":"0x0000000000000000000000000000000000000000000000000000000000000005","from":"0x0000000000000000000000000000000000000001","nonce":"0x2","event":"replaced","replacedBy":"0x0000000000000000000000000000000000000000000000000000000000000007"},"subscription":"0x43ca64edf03768e1"}}"#;
+    assert_eq!(res, Some(response.into()));
+
+    // And unsubscribe
+    let request = r#"{"jsonrpc": "2.0", "method": "eth_unsubscribe", "params": ["0x43ca64edf03768e1"], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+    assert_eq!(
+        io.handle_request_sync(request, metadata),
+        Some(response.to_owned())
+    );
+    assert_eq!(*changes.lock(), vec![true, false]);
+
+    let (res, _receiver) = receiver.into_future().wait().unwrap();
+    assert_eq!(res, None);
+}
+
 #[test]
 fn should_return_unimplemented() {
     // given
@@ -23,7 +23,7 @@ use sync::ManageNetwork;
 
 use super::manage_network::TestManageNetwork;
 use jsonrpc_core::IoHandler;
-use v1::{tests::helpers::TestMinerService, ParitySet, ParitySetClient};
+use v1::{tests::helpers::TestMinerService, ParitySet, ParitySetClient, PoolJournal};
 
 use fake_fetch::FakeFetch;
 
@@ -51,10 +51,19 @@ fn parity_set_client(
         client,
         miner,
         &(net.clone() as Arc<dyn ManageNetwork>),
+        None,
         FakeFetch::new(Some(1)),
     )
 }
 
+struct TestPoolJournal;
+
+impl PoolJournal for TestPoolJournal {
+    fn dump(&self) -> Result<usize, String> {
+        Ok(3)
+    }
+}
+
 #[test]
 fn rpc_parity_set_min_gas_price() {
     let miner = miner_service();
@@ -208,6 +217,37 @@ fn rpc_parity_remove_transaction() {
     assert_eq!(io.handle_request_sync(&request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_parity_dump_transaction_pool() {
+    let miner = miner_service();
+    let client = client_service();
+    let network = network_service();
+
+    let request =
+        r#"{"jsonrpc": "2.0", "method": "parity_dumpTransactionPool", "params":[], "id": 1}"#;
+
+    // without the journal
+    let mut io = IoHandler::new();
+    io.extend_with(parity_set_client(&client, &miner, &network).to_delegate());
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Transaction pool journal is disabled.","data":"Restart the node with --tx-queue-journal."},"id":1}"#;
+    assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+    // with the journal
+    let mut io = IoHandler::new();
+    io.extend_with(
+        ParitySetClient::new(
+            &client,
+            &miner,
+            &(network.clone() as Arc<dyn ManageNetwork>),
+            Some(Arc::new(TestPoolJournal) as Arc<dyn PoolJournal>),
+            FakeFetch::new(Some(1)),
+        )
+        .to_delegate(),
+    );
+    let response = r#"{"jsonrpc":"2.0","result":3,"id":1}"#;
+    assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_parity_set_engine_signer() {
     use accounts::AccountProvider;
@@ -68,6 +68,7 @@ fn io() -> Tester {
         trace: vec![],
         vm_trace: None,
         state_diff: None,
+        access_list: None,
     }));
     let miner = Arc::new(TestMinerService::default());
     let traces = TracesClient::new(&client);
@@ -201,6 +202,32 @@ fn rpc_trace_call() {
     );
 }
 
+#[test]
+fn rpc_trace_call_with_overrides() {
+    let tester = io();
+
+    let request = r#"{"jsonrpc":"2.0","method":"trace_call","params":[{}, ["trace"], "latest", {"0x0000000000000000000000000000000000000001": {"code": "0x6000"}}, {"number": "0x10"}],"id":1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":{"output":"0x010203","stateDiff":null,"trace":[],"vmTrace":null},"id":1}"#;
+
+    assert_eq!(
+        tester.io.handle_request_sync(request),
+        Some(response.to_owned())
+    );
+}
+
+#[test]
+fn rpc_trace_call_conflicting_overrides() {
+    let tester = io();
+
+    let request = r#"{"jsonrpc":"2.0","method":"trace_call","params":[{}, ["trace"], "latest", {"0x0000000000000000000000000000000000000001": {"state": {}, "stateDiff": {}}}],"id":1}"#;
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: stateOverride","data":"\"account 0x0000000000000000000000000000000000000001 has both state and stateDiff\""},"id":1}"#;
+
+    assert_eq!(
+        tester.io.handle_request_sync(request),
+        Some(response.to_owned())
+    );
+}
+
 #[test]
 fn rpc_trace_multi_call() {
     let tester = io();
@@ -0,0 +1,99 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{str::FromStr, sync::Arc};
+
+use ethcore::client::TestBlockChainClient;
+use ethereum_types::Address;
+use types::transaction::{Action, Transaction, TypedTransaction};
+
+use jsonrpc_core::IoHandler;
+use v1::{tests::helpers::TestMinerService, TxPool, TxPoolClient};
+
+fn io() -> IoHandler {
+    let client = Arc::new(TestBlockChainClient::new());
+    let miner = Arc::new(TestMinerService::default());
+
+    let transaction = |sender: u64, nonce: u64| {
+        TypedTransaction::Legacy(Transaction {
+            nonce: nonce.into(),
+            gas_price: 10.into(),
+            gas: 21_000.into(),
+            action: Action::Call(Address::from_low_u64_be(5)),
+            value: 1_000.into(),
+            data: vec![],
+        })
+        .fake_sign(Address::from_low_u64_be(sender))
+    };
+    let pending = transaction(2, 1);
+    let queued = transaction(3, 7);
+    miner
+        .pending_transactions
+        .lock()
+        .insert(pending.hash(), pending);
+    miner
+        .future_transactions
+        .lock()
+        .insert(queued.hash(), queued);
+
+    let mut io = IoHandler::new();
+    io.extend_with(TxPoolClient::new(client, miner).to_delegate());
+    io
+}
+
+#[test]
+fn rpc_txpool_status() {
+    let io = io();
+    let request = r#"{"jsonrpc": "2.0", "method": "txpool_status", "params": [], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":{"pending":"0x1","queued":"0x1"},"id":1}"#;
+    assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_txpool_inspect() {
+    let io = io();
+    let request = r#"{"jsonrpc": "2.0", "method": "txpool_inspect", "params": [], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":{"pending":{"0x0000000000000000000000000000000000000002":{"1":"0x0000000000000000000000000000000000000005: 1000 wei + 21000 gas × 10 wei"}},"queued":{"0x0000000000000000000000000000000000000003":{"7":"0x0000000000000000000000000000000000000005: 1000 wei + 21000 gas × 10 wei"}}},"id":1}"#;
+    assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_txpool_content() {
+    let io = io();
+    let request = r#"{"jsonrpc": "2.0", "method": "txpool_content", "params": [], "id": 1}"#;
+    let response = io.handle_request_sync(request).unwrap();
+    let response = serde_json::Value::from_str(&response).unwrap();
+    let result = &response["result"];
+    assert_eq!(
+        result["pending"]["0x0000000000000000000000000000000000000002"]["1"]["nonce"],
+        "0x1"
+    );
+    assert_eq!(
+        result["queued"]["0x0000000000000000000000000000000000000003"]["7"]["from"],
+        "0x0000000000000000000000000000000000000003"
+    );
+}
+
+#[test]
+fn rpc_txpool_content_from() {
+    let io = io();
+    let request = r#"{"jsonrpc": "2.0", "method": "txpool_contentFrom", "params": ["0x0000000000000000000000000000000000000003"], "id": 1}"#;
+    let response = io.handle_request_sync(request).unwrap();
+    let response = serde_json::Value::from_str(&response).unwrap();
+    let result = &response["result"];
+    assert!(result["pending"].as_object().unwrap().is_empty());
+    assert_eq!(result["queued"]["7"]["nonce"], "0x7");
+}
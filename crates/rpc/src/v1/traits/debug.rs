@@ -19,7 +19,10 @@
 use jsonrpc_core::Result;
 use jsonrpc_derive::rpc;
 
-use v1::types::RichBlock;
+use ethereum_types::H256;
+use v1::types::{
+    BlockNumber, CallRequest, GethTrace, GethTraceOptions, GethTraceWithTransactionHash, RichBlock,
+};
 
 /// Debug RPC interface.
 #[rpc(server)]
@@ -27,4 +30,25 @@ pub trait Debug {
     /// Returns recently seen bad blocks.
     #[rpc(name = "debug_getBadBlocks")]
     fn bad_blocks(&self) -> Result<Vec<RichBlock>>;
+
+    /// Replays a transaction and returns its geth-style trace.
+    #[rpc(name = "debug_traceTransaction")]
+    fn trace_transaction(&self, _: H256, _: Option<GethTraceOptions>) -> Result<GethTrace>;
+
+    /// Executes a call on top of a block and returns its geth-style trace.
+    #[rpc(name = "debug_traceCall")]
+    fn trace_call(
+        &self,
+        _: CallRequest,
+        _: Option<BlockNumber>,
+        _: Option<GethTraceOptions>,
+    ) -> Result<GethTrace>;
+
+    /// Replays all transactions of a block and returns their geth-style traces.
+    #[rpc(name = "debug_traceBlockByNumber")]
+    fn trace_block_by_number(
+        &self,
+        _: BlockNumber,
+        _: Option<GethTraceOptions>,
+    ) -> Result<Vec<GethTraceWithTransactionHash>>;
 }
@@ -20,8 +20,9 @@ use jsonrpc_core::{BoxFuture, Result};
 use jsonrpc_derive::rpc;
 
 use v1::types::{
-    BlockNumber, Bytes, CallRequest, EthAccount, EthFeeHistory, Filter, FilterChanges, Index, Log,
-    Receipt, RichBlock, SyncStatus, Transaction, Work,
+    AccessListWithGasUsed, BlockNumber, BlockOverride, BundleHash, Bytes, CallRequest, EthAccount,
+    EthFeeHistory, Filter, FilterChanges, Index, Log, Receipt, RichBlock, SendBundleRequest,
+    SimulatePayload, SimulatedBlock, StateOverride, SyncStatus, Transaction, Work,
 };
 
 /// Eth rpc interface.
@@ -129,9 +130,38 @@ pub trait Eth {
     #[rpc(name = "eth_submitTransaction")]
     fn submit_transaction(&self, _: Bytes) -> Result<H256>;
 
-    /// Call contract, returning the output data.
+    /// Submits signed transactions to be included together at the top of the given block,
+    /// returning the bundle hash.
+    #[rpc(name = "eth_sendBundle")]
+    fn send_bundle(&self, _: SendBundleRequest) -> Result<BundleHash>;
+
+    /// Call contract, returning the output data. Accounts and block fields can be replaced for
+    /// the duration of the call.
     #[rpc(name = "eth_call")]
-    fn call(&self, _: CallRequest, _: Option<BlockNumber>) -> BoxFuture<Bytes>;
+    fn call(
+        &self,
+        _: CallRequest,
+        _: Option<BlockNumber>,
+        _: Option<StateOverride>,
+        _: Option<BlockOverride>,
+    ) -> BoxFuture<Bytes>;
+
+    /// Executes the given blocks of calls on top of the given block, each block seeing the state
+    /// changes of the previous ones, and returns the synthetic blocks.
+    #[rpc(name = "eth_simulateV1")]
+    fn simulate(
+        &self,
+        _: SimulatePayload,
+        _: Option<BlockNumber>,
+    ) -> BoxFuture<Vec<SimulatedBlock>>;
+
+    /// Creates an access list for the given call, returning it with the gas used.
+    #[rpc(name = "eth_createAccessList")]
+    fn create_access_list(
+        &self,
+        _: CallRequest,
+        _: Option<BlockNumber>,
+    ) -> BoxFuture<AccessListWithGasUsed>;
 
     /// Estimate gas needed for execution of given contract.
     #[rpc(name = "eth_estimateGas")]
@@ -161,6 +191,10 @@ pub trait Eth {
     #[rpc(name = "eth_getTransactionReceipt")]
     fn transaction_receipt(&self, _: H256) -> BoxFuture<Option<Receipt>>;
 
+    /// Returns all transaction receipts of the given block.
+    #[rpc(name = "eth_getBlockReceipts")]
+    fn block_receipts(&self, _: BlockNumber) -> BoxFuture<Option<Vec<Receipt>>>;
+
     /// Returns an uncles at given block and index.
     #[rpc(name = "eth_getUncleByBlockHashAndIndex")]
     fn uncle_by_block_hash_and_index(&self, _: H256, _: Index) -> BoxFuture<Option<RichBlock>>;
@@ -31,6 +31,7 @@ pub mod rpc;
 pub mod secretstore;
 pub mod signer;
 pub mod traces;
+pub mod txpool;
 pub mod web3;
 
 pub use self::{
@@ -49,5 +50,6 @@ pub use self::{
     secretstore::SecretStore,
     signer::Signer,
     traces::Traces,
+    txpool::TxPool,
     web3::Web3,
 };
@@ -117,4 +117,10 @@ pub trait ParitySet {
     /// Returns `true` when transaction was removed, `false` if it was not found.
     #[rpc(name = "parity_removeTransaction")]
     fn remove_transaction(&self, _: H256) -> Result<Option<Transaction>>;
+
+    /// Writes all transactions in the transaction queue to the pool journal.
+    /// Returns the number of journaled transactions.
+    /// Fails if the node runs without the pool journal.
+    #[rpc(name = "parity_dumpTransactionPool")]
+    fn dump_transaction_pool(&self) -> Result<usize>;
 }
@@ -20,8 +20,8 @@ use ethereum_types::H256;
 use jsonrpc_core::Result;
 use jsonrpc_derive::rpc;
 use v1::types::{
-    BlockNumber, Bytes, CallRequest, Index, LocalizedTrace, TraceFilter, TraceOptions,
-    TraceResults, TraceResultsWithTransactionHash,
+    BlockNumber, BlockOverride, Bytes, CallRequest, Index, LocalizedTrace, StateOverride,
+    TraceFilter, TraceOptions, TraceResults, TraceResultsWithTransactionHash,
 };
 
 /// Traces specific rpc interface.
@@ -46,10 +46,17 @@ pub trait Traces {
     #[rpc(name = "trace_block")]
     fn block_traces(&self, _: BlockNumber) -> Result<Option<Vec<LocalizedTrace>>>;
 
-    /// Executes the given call and returns a number of possible traces for it.
+    /// Executes the given call and returns a number of possible traces for it. Accounts and block
+    /// fields can be replaced for the duration of the call.
     #[rpc(name = "trace_call")]
-    fn call(&self, _: CallRequest, _: TraceOptions, _: Option<BlockNumber>)
-        -> Result<TraceResults>;
+    fn call(
+        &self,
+        _: CallRequest,
+        _: TraceOptions,
+        _: Option<BlockNumber>,
+        _: Option<StateOverride>,
+        _: Option<BlockOverride>,
+    ) -> Result<TraceResults>;
 
     /// Executes all given calls and returns a number of possible traces for each of it.
     #[rpc(name = "trace_callMany")]
@@ -18,7 +18,7 @@ use std::{collections::BTreeMap, ops::Deref};
 
 use ethereum_types::{Bloom as H2048, H160, H256, U256};
 use serde::{ser::Error, Serialize, Serializer};
-use types::{encoded::Header as EthHeader, BlockNumber};
+use types::{encoded::Header as EthHeader, header::HeaderTransitions};
 use v1::types::{Bytes, Transaction};
 
 /// Block Transactions
@@ -137,8 +137,8 @@ pub struct Header {
 }
 
 impl Header {
-    pub fn new(h: &EthHeader, eip1559_transition: BlockNumber) -> Self {
-        let eip1559_enabled = h.number() >= eip1559_transition;
+    pub fn new(h: &EthHeader, transitions: HeaderTransitions) -> Self {
+        let eip1559_enabled = h.number() >= transitions.eip1559_transition;
         Header {
             hash: Some(h.hash()),
 			size: Some(h.rlp().as_raw().len().into()),
@@ -156,12 +156,12 @@ impl Header {
 			timestamp: h.timestamp().into(),
 			difficulty: h.difficulty(),
 			extra_data: h.extra_data().into(),
-			seal_fields: h.view().decode_seal(eip1559_enabled)
+			seal_fields: h.view().decode_seal(transitions)
 				.expect("Client/Miner returns only valid headers. We only serialize headers from Client/Miner; qed")
 				.into_iter().map(Into::into).collect(),
 			base_fee_per_gas: {
 				if eip1559_enabled {
-					Some(h.base_fee())
+					Some(h.base_fee(transitions))
 				} else {
 					None
 				}
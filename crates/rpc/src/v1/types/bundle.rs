@@ -0,0 +1,74 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `eth_sendBundle` types.
+
+use ethereum_types::{H256, U64};
+
+use v1::types::Bytes;
+
+/// `eth_sendBundle` request.
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendBundleRequest {
+    /// Signed raw transactions in the order of inclusion.
+    pub txs: Vec<Bytes>,
+    /// Number of the only block the bundle may be included in.
+    pub block_number: U64,
+    /// Lowest timestamp of the block the bundle may be included in.
+    pub min_timestamp: Option<u64>,
+    /// Highest timestamp of the block the bundle may be included in.
+    pub max_timestamp: Option<u64>,
+    /// Hashes of transactions which are allowed to revert.
+    #[serde(default)]
+    pub reverting_tx_hashes: Vec<H256>,
+}
+
+/// `eth_sendBundle` response.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleHash {
+    /// Hash of the accepted bundle.
+    pub bundle_hash: H256,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn bundle_request_deserialization() {
+        let s = r#"{
+			"txs": ["0x01", "0x02"],
+			"blockNumber": "0xa",
+			"maxTimestamp": 1700000000,
+			"revertingTxHashes": ["0x0000000000000000000000000000000000000000000000000000000000000001"]
+		}"#;
+        let deserialized: SendBundleRequest = serde_json::from_str(s).unwrap();
+
+        assert_eq!(
+            deserialized,
+            SendBundleRequest {
+                txs: vec![vec![1].into(), vec![2].into()],
+                block_number: 10.into(),
+                min_timestamp: None,
+                max_timestamp: Some(1_700_000_000),
+                reverting_tx_hashes: vec![H256::from_low_u64_be(1)],
+            }
+        );
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State and block overrides of `eth_call` and `trace_call`.
+
+use std::collections::BTreeMap;
+
+use ethcore::client::{
+    AccountOverride as EthAccountOverride, BlockOverride as EthBlockOverride, CallOverrides,
+};
+use ethereum_types::{H160, H256, U256, U64};
+use jsonrpc_core::Error as RpcError;
+
+use v1::{helpers::errors::invalid_params, types::Bytes};
+
+/// Account fields to replace before the call.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountOverride {
+    /// Balance
+    pub balance: Option<U256>,
+    /// Nonce
+    pub nonce: Option<U64>,
+    /// Code
+    pub code: Option<Bytes>,
+    /// Storage, replacing the whole storage of the account
+    pub state: Option<BTreeMap<H256, H256>>,
+    /// Storage slots to replace
+    pub state_diff: Option<BTreeMap<H256, H256>>,
+}
+
+/// Accounts to replace before the call.
+pub type StateOverride = BTreeMap<H160, AccountOverride>;
+
+/// Block fields to replace before the call.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockOverride {
+    /// Block number
+    pub number: Option<U64>,
+    /// Block timestamp
+    #[serde(alias = "time")]
+    pub timestamp: Option<U64>,
+    /// Gas limit
+    pub gas_limit: Option<U64>,
+    /// Author
+    pub coinbase: Option<H160>,
+    /// Base fee
+    pub base_fee: Option<U256>,
+}
+
+impl Into<EthBlockOverride> for BlockOverride {
+    fn into(self) -> EthBlockOverride {
+        EthBlockOverride {
+            number: self.number.map(|n| n.as_u64()),
+            timestamp: self.timestamp.map(|t| t.as_u64()),
+            gas_limit: self.gas_limit.map(|g| g.as_u64().into()),
+            coinbase: self.coinbase,
+            base_fee: self.base_fee,
+        }
+    }
+}
+
+/// Convert the optional override parameters of a call.
+pub fn to_call_overrides(
+    state: Option<StateOverride>,
+    block: Option<BlockOverride>,
+) -> Result<CallOverrides, RpcError> {
+    let state = state
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(address, account)| {
+            if account.state.is_some() && account.state_diff.is_some() {
+                return Err(invalid_params(
+                    "stateOverride",
+                    format!("account {:?} has both state and stateDiff", address),
+                ));
+            }
+            let account = EthAccountOverride {
+                balance: account.balance,
+                nonce: account.nonce.map(|n| n.as_u64().into()),
+                code: account.code.map(Bytes::into_vec),
+                state: account.state,
+                state_diff: account.state_diff,
+            };
+            Ok((address, account))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(CallOverrides {
+        state,
+        block: block.map(Into::into).unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn should_deserialize_state_override() {
+        let s = r#"{
+			"0x0000000000000000000000000000000000000001": {
+				"balance": "0x10",
+				"code": "0x6000",
+				"stateDiff": {
+					"0x0000000000000000000000000000000000000000000000000000000000000001": "0x0000000000000000000000000000000000000000000000000000000000000002"
+				}
+			}
+		}"#;
+        let deserialized: StateOverride = serde_json::from_str(s).unwrap();
+        let overrides = to_call_overrides(Some(deserialized), None).unwrap();
+        let account = &overrides.state[&H160::from_low_u64_be(1)];
+
+        assert_eq!(account.balance, Some(0x10.into()));
+        assert_eq!(account.code, Some(vec![0x60, 0x00]));
+        assert_eq!(account.state, None);
+        assert_eq!(
+            account.state_diff.as_ref().unwrap()[&H256::from_low_u64_be(1)],
+            H256::from_low_u64_be(2)
+        );
+    }
+
+    #[test]
+    fn should_reject_state_and_state_diff() {
+        let s = r#"{
+			"0x0000000000000000000000000000000000000001": {
+				"state": {},
+				"stateDiff": {}
+			}
+		}"#;
+        let deserialized: StateOverride = serde_json::from_str(s).unwrap();
+
+        assert!(to_call_overrides(Some(deserialized), None).is_err());
+    }
+
+    #[test]
+    fn should_deserialize_block_override() {
+        let s = r#"{"number":"0x10","time":"0x20","baseFee":"0x7"}"#;
+        let deserialized: BlockOverride = serde_json::from_str(s).unwrap();
+        let block: EthBlockOverride = deserialized.into();
+
+        assert_eq!(
+            block,
+            EthBlockOverride {
+                number: Some(0x10),
+                timestamp: Some(0x20),
+                base_fee: Some(7.into()),
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,347 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Geth-compatible `debug_trace*` types.
+
+use std::collections::BTreeMap;
+
+use ethcore::trace::geth as et;
+use ethereum_types::{H160, H256, U256};
+use rustc_hex::ToHex;
+
+use v1::types::Bytes;
+
+/// Tracer specific options.
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GethTracerConfig {
+    /// `callTracer`: report only the top-level call.
+    #[serde(default)]
+    pub only_top_call: bool,
+    /// `prestateTracer`: report the changed fields before and after the transaction.
+    #[serde(default)]
+    pub diff_mode: bool,
+}
+
+/// Options of the `debug_trace*` methods. Unsupported geth options, such as `timeout`, are
+/// accepted and ignored.
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GethTraceOptions {
+    /// Name of the built-in tracer, the struct logger is used if omitted.
+    pub tracer: Option<String>,
+    /// Tracer specific options.
+    pub tracer_config: Option<GethTracerConfig>,
+    /// Struct logger: do not report the stack.
+    #[serde(default)]
+    pub disable_stack: bool,
+    /// Struct logger: do not report the storage.
+    #[serde(default)]
+    pub disable_storage: bool,
+    /// Struct logger: report the memory.
+    #[serde(default)]
+    pub enable_memory: bool,
+}
+
+/// Single step of the struct logger.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLog {
+    /// Program counter.
+    pub pc: usize,
+    /// Instruction name.
+    pub op: String,
+    /// Gas left before the instruction.
+    pub gas: u64,
+    /// Gas cost of the instruction.
+    pub gas_cost: u64,
+    /// Call depth.
+    pub depth: usize,
+    /// Stack before the instruction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack: Option<Vec<U256>>,
+    /// Memory before the instruction, in 32 byte words.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<Vec<String>>,
+    /// Accessed storage slots of the current call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage: Option<BTreeMap<String, String>>,
+}
+
+impl From<et::StructLog> for StructLog {
+    fn from(log: et::StructLog) -> Self {
+        StructLog {
+            pc: log.pc,
+            op: log.op,
+            gas: log.gas.low_u64(),
+            gas_cost: log.gas_cost.low_u64(),
+            depth: log.depth,
+            stack: log.stack,
+            memory: log
+                .memory
+                .map(|memory| memory.chunks(32).map(|word| word.to_hex()).collect()),
+            storage: log.storage.map(|storage| {
+                storage
+                    .into_iter()
+                    .map(|(key, value)| (format!("{:x}", key), format!("{:x}", value)))
+                    .collect()
+            }),
+        }
+    }
+}
+
+/// Result of the struct logger.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLogs {
+    /// Gas used by the transaction.
+    pub gas: u64,
+    /// Whether the transaction failed.
+    pub failed: bool,
+    /// Output of the transaction, hex encoded without prefix.
+    pub return_value: String,
+    /// Executed instructions.
+    pub struct_logs: Vec<StructLog>,
+}
+
+impl From<et::StructLogs> for StructLogs {
+    fn from(logs: et::StructLogs) -> Self {
+        StructLogs {
+            gas: logs.gas.low_u64(),
+            failed: logs.failed,
+            return_value: logs.return_value.to_hex(),
+            struct_logs: logs.struct_logs.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Single call of the `callTracer` result.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+    /// Type of the call.
+    #[serde(rename = "type")]
+    pub call_type: String,
+    /// Caller.
+    pub from: H160,
+    /// Callee.
+    pub to: H160,
+    /// Transferred value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<U256>,
+    /// Gas given to the call.
+    pub gas: U256,
+    /// Gas used by the call.
+    pub gas_used: U256,
+    /// Call data or init code.
+    pub input: Bytes,
+    /// Output data or created code.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<Bytes>,
+    /// Error, if the call failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Subcalls.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<CallFrame>,
+}
+
+impl From<et::CallFrame> for CallFrame {
+    fn from(frame: et::CallFrame) -> Self {
+        let call_type = match frame.call_type {
+            et::CallFrameType::Call => "CALL",
+            et::CallFrameType::CallCode => "CALLCODE",
+            et::CallFrameType::DelegateCall => "DELEGATECALL",
+            et::CallFrameType::StaticCall => "STATICCALL",
+            et::CallFrameType::Create => "CREATE",
+            et::CallFrameType::SelfDestruct => "SELFDESTRUCT",
+        };
+        CallFrame {
+            call_type: call_type.into(),
+            from: frame.from,
+            to: frame.to,
+            value: frame.value,
+            gas: frame.gas,
+            gas_used: frame.gas_used,
+            input: frame.input.into(),
+            output: Some(frame.output)
+                .filter(|output| !output.is_empty())
+                .map(Into::into),
+            error: frame.error,
+            calls: frame.calls.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Account state reported by the `prestateTracer`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrestateAccount {
+    /// Balance.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<U256>,
+    /// Nonce.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+    /// Code.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<Bytes>,
+    /// Storage slots.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage: BTreeMap<H256, H256>,
+}
+
+impl From<et::PrestateAccount> for PrestateAccount {
+    fn from(account: et::PrestateAccount) -> Self {
+        PrestateAccount {
+            balance: account.balance,
+            nonce: account.nonce.map(|nonce| nonce.low_u64()),
+            code: account.code.map(Into::into),
+            storage: account.storage,
+        }
+    }
+}
+
+fn prestate(accounts: BTreeMap<H160, et::PrestateAccount>) -> BTreeMap<H160, PrestateAccount> {
+    accounts
+        .into_iter()
+        .map(|(address, account)| (address, account.into()))
+        .collect()
+}
+
+/// Result of a `debug_trace*` call for a single transaction.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum GethTrace {
+    /// Result of the struct logger.
+    StructLogs(StructLogs),
+    /// Result of the `callTracer`.
+    Call(CallFrame),
+    /// Result of the `prestateTracer`.
+    Prestate(BTreeMap<H160, PrestateAccount>),
+    /// Result of the `prestateTracer` in diff mode.
+    PrestateDiff {
+        /// Changed fields before the transaction.
+        pre: BTreeMap<H160, PrestateAccount>,
+        /// Changed fields after the transaction.
+        post: BTreeMap<H160, PrestateAccount>,
+    },
+}
+
+impl From<et::GethTrace> for GethTrace {
+    fn from(trace: et::GethTrace) -> Self {
+        match trace {
+            et::GethTrace::StructLogs(logs) => GethTrace::StructLogs(logs.into()),
+            et::GethTrace::Call(frame) => GethTrace::Call(frame.into()),
+            et::GethTrace::Prestate(accounts) => GethTrace::Prestate(prestate(accounts)),
+            et::GethTrace::PrestateDiff(diff) => GethTrace::PrestateDiff {
+                pre: prestate(diff.pre),
+                post: prestate(diff.post),
+            },
+        }
+    }
+}
+
+/// `debug_traceBlockByNumber` result for a single transaction.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GethTraceWithTransactionHash {
+    /// Transaction hash.
+    pub tx_hash: H256,
+    /// Trace of the transaction.
+    pub result: GethTrace,
+}
+
+impl From<(H256, et::GethTrace)> for GethTraceWithTransactionHash {
+    fn from((tx_hash, trace): (H256, et::GethTrace)) -> Self {
+        GethTraceWithTransactionHash {
+            tx_hash,
+            result: trace.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn should_deserialize_trace_options() {
+        let s = r#"{"tracer":"callTracer","tracerConfig":{"onlyTopCall":true},"timeout":"10s"}"#;
+        let deserialized: GethTraceOptions = serde_json::from_str(s).unwrap();
+        assert_eq!(
+            deserialized,
+            GethTraceOptions {
+                tracer: Some("callTracer".into()),
+                tracer_config: Some(GethTracerConfig {
+                    only_top_call: true,
+                    diff_mode: false,
+                }),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn should_serialize_call_frame() {
+        let trace: GethTrace = et::GethTrace::Call(et::CallFrame {
+            call_type: et::CallFrameType::StaticCall,
+            from: H160::from_low_u64_be(1),
+            to: H160::from_low_u64_be(2),
+            value: None,
+            gas: 100.into(),
+            gas_used: 21.into(),
+            input: vec![0x12],
+            output: vec![],
+            error: Some("execution reverted".into()),
+            calls: vec![],
+        })
+        .into();
+        let serialized = serde_json::to_string(&trace).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"STATICCALL","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","gas":"0x64","gasUsed":"0x15","input":"0x12","error":"execution reverted"}"#
+        );
+    }
+
+    #[test]
+    fn should_serialize_struct_logs() {
+        let mut storage = BTreeMap::new();
+        storage.insert(H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+        let trace: GethTrace = et::GethTrace::StructLogs(et::StructLogs {
+            gas: 21003.into(),
+            failed: false,
+            return_value: vec![0xab],
+            struct_logs: vec![et::StructLog {
+                pc: 0,
+                op: "SLOAD".into(),
+                gas: 100.into(),
+                gas_cost: 3.into(),
+                depth: 1,
+                stack: Some(vec![1.into()]),
+                memory: None,
+                storage: Some(storage),
+            }],
+        })
+        .into();
+        let serialized = serde_json::to_string(&trace).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"gas":21003,"failed":false,"returnValue":"ab","structLogs":[{"pc":0,"op":"SLOAD","gas":100,"gasCost":3,"depth":1,"stack":["0x1"],"storage":{"0000000000000000000000000000000000000000000000000000000000000001":"0000000000000000000000000000000000000000000000000000000000000002"}}]}"#
+        );
+    }
+}
@@ -20,7 +20,9 @@ pub use self::{
     account_info::{AccountInfo, EthAccount, ExtAccountInfo, RecoveredAccount, StorageProof},
     block::{Block, BlockTransactions, Header, Rich, RichBlock, RichHeader},
     block_number::{block_number_to_id, BlockNumber},
+    bundle::{BundleHash, SendBundleRequest},
     bytes::Bytes,
+    call_overrides::{to_call_overrides, AccountOverride, BlockOverride, StateOverride},
     call_request::CallRequest,
     confirmations::{
         ConfirmationPayload, ConfirmationRequest, ConfirmationResponse,
@@ -31,6 +33,7 @@ pub use self::{
     eip191::{EIP191Version, PresignedTransaction},
     fee_history::EthFeeHistory,
     filter::{Filter, FilterChanges},
+    geth_trace::{GethTrace, GethTraceOptions, GethTraceWithTransactionHash},
     histogram::Histogram,
     index::Index,
     log::Log,
@@ -39,6 +42,7 @@ pub use self::{
     receipt::Receipt,
     rpc_settings::RpcSettings,
     secretstore::EncryptedDocumentKey,
+    simulate::{BlockStateCall, SimulatePayload, SimulatedBlock, SimulatedCall},
     sync::{
         ChainStatus, EthProtocolInfo, PeerInfo, PeerNetworkInfo, PeerProtocolsInfo, Peers,
         SyncInfo, SyncStatus, TransactionStats,
@@ -46,9 +50,11 @@ pub use self::{
     trace::{LocalizedTrace, TraceResults, TraceResultsWithTransactionHash},
     trace_filter::TraceFilter,
     transaction::{LocalTransactionStatus, RichRawTransaction, Transaction},
-    transaction_access_list::{AccessList, AccessListItem},
+    transaction_access_list::{AccessList, AccessListItem, AccessListWithGasUsed},
     transaction_condition::TransactionCondition,
+    transaction_event::TransactionEvent,
     transaction_request::TransactionRequest,
+    txpool::{TxPoolContent, TxPoolContentFrom, TxPoolInspect, TxPoolStatus},
     work::Work,
 };
 
@@ -58,13 +64,16 @@ mod eth_types;
 mod account_info;
 mod block;
 mod block_number;
+mod bundle;
 mod bytes;
+mod call_overrides;
 mod call_request;
 mod confirmations;
 mod derivation;
 mod eip191;
 mod fee_history;
 mod filter;
+mod geth_trace;
 mod histogram;
 mod index;
 mod log;
@@ -73,13 +82,16 @@ mod provenance;
 mod receipt;
 mod rpc_settings;
 mod secretstore;
+mod simulate;
 mod sync;
 mod trace;
 mod trace_filter;
 mod transaction;
 mod transaction_access_list;
 mod transaction_condition;
+mod transaction_event;
 mod transaction_request;
+mod txpool;
 mod work;
 
 pub mod pubsub;
@@ -0,0 +1,185 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `eth_simulateV1` types.
+
+use ethcore::client::SimulatedBlock as EthSimulatedBlock;
+use ethereum_types::{H160, H256, U256, U64};
+use types::log_entry::LocalizedLogEntry;
+
+use v1::types::{BlockOverride, Bytes, CallRequest, Log, StateOverride};
+
+/// Calls to execute in a single simulated block.
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockStateCall {
+    /// Block fields to replace.
+    pub block_overrides: Option<BlockOverride>,
+    /// Accounts to replace before the first call.
+    pub state_overrides: Option<StateOverride>,
+    /// Calls to execute in order.
+    #[serde(default)]
+    pub calls: Vec<CallRequest>,
+}
+
+/// `eth_simulateV1` request.
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatePayload {
+    /// Blocks to simulate in order.
+    pub block_state_calls: Vec<BlockStateCall>,
+    /// Check nonces and balances like for a real transaction. Not supported.
+    #[serde(default)]
+    pub validation: bool,
+    /// Report ether transfers as logs. Not supported.
+    #[serde(default)]
+    pub trace_transfers: bool,
+    /// Return full transactions instead of hashes. Not supported.
+    #[serde(default)]
+    pub return_full_transactions: bool,
+}
+
+/// Result of a single simulated call.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedCall {
+    /// Output of the call.
+    pub return_data: Bytes,
+    /// Logs of the call.
+    pub logs: Vec<Log>,
+    /// Gas used by the call.
+    pub gas_used: U256,
+    /// 1 on success, 0 on failure.
+    pub status: U64,
+    /// Error, if the call failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of a simulated block.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedBlock {
+    /// Block number
+    pub number: U256,
+    /// Hash of the synthetic header
+    pub hash: H256,
+    /// Parent hash
+    pub parent_hash: H256,
+    /// Timestamp
+    pub timestamp: U256,
+    /// Gas limit
+    pub gas_limit: U256,
+    /// Gas used
+    pub gas_used: U256,
+    /// Author
+    pub miner: H160,
+    /// Base fee
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_fee_per_gas: Option<U256>,
+    /// Hashes of the simulated transactions
+    pub transactions: Vec<H256>,
+    /// Results of the calls
+    pub calls: Vec<SimulatedCall>,
+}
+
+impl SimulatedBlock {
+    /// Build the result of a simulated block, `transactions` are the hashes of its calls.
+    pub fn new(block: EthSimulatedBlock, transactions: Vec<H256>) -> Self {
+        let header = block.header;
+        let hash = header.hash();
+        let mut log_index = 0;
+        let calls = block
+            .results
+            .into_iter()
+            .zip(&transactions)
+            .enumerate()
+            .map(|(transaction_index, (executed, transaction_hash))| {
+                let logs = executed
+                    .logs
+                    .into_iter()
+                    .enumerate()
+                    .map(|(transaction_log_index, entry)| {
+                        let log = LocalizedLogEntry {
+                            entry,
+                            block_hash: hash,
+                            block_number: header.number(),
+                            transaction_hash: *transaction_hash,
+                            transaction_index,
+                            transaction_log_index,
+                            log_index,
+                        };
+                        log_index += 1;
+                        log.into()
+                    })
+                    .collect();
+                SimulatedCall {
+                    return_data: executed.output.into(),
+                    logs,
+                    gas_used: executed.gas_used,
+                    status: U64::from(executed.exception.is_none() as u64),
+                    error: executed.exception.map(|e| e.to_string()),
+                }
+            })
+            .collect();
+
+        SimulatedBlock {
+            number: header.number().into(),
+            hash,
+            parent_hash: *header.parent_hash(),
+            timestamp: header.timestamp().into(),
+            gas_limit: *header.gas_limit(),
+            gas_used: *header.gas_used(),
+            miner: *header.author(),
+            base_fee_per_gas: header.base_fee(),
+            transactions,
+            calls,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn should_deserialize_simulate_payload() {
+        let s = r#"{
+			"blockStateCalls": [{
+				"blockOverrides": {"number": "0x10"},
+				"stateOverrides": {"0x0000000000000000000000000000000000000001": {"balance": "0x1"}},
+				"calls": [{"to": "0x0000000000000000000000000000000000000001"}]
+			}, {}],
+			"validation": false
+		}"#;
+        let deserialized: SimulatePayload = serde_json::from_str(s).unwrap();
+
+        assert_eq!(deserialized.block_state_calls.len(), 2);
+        assert_eq!(
+            deserialized.block_state_calls[0]
+                .block_overrides
+                .as_ref()
+                .unwrap()
+                .number,
+            Some(0x10.into())
+        );
+        assert_eq!(deserialized.block_state_calls[0].calls.len(), 1);
+        assert!(deserialized.block_state_calls[1].calls.is_empty());
+    }
+}
@@ -63,6 +63,8 @@ pub struct PeerInfo {
     pub network: PeerNetworkInfo,
     /// Protocols information
     pub protocols: PeerProtocolsInfo,
+    /// Traffic exchanged with the peer
+    pub traffic: PeerTrafficInfo,
 }
 
 /// Peer network information
@@ -91,6 +93,8 @@ pub struct EthProtocolInfo {
     pub difficulty: Option<U256>,
     /// SHA3 of peer best block hash
     pub head: String,
+    /// Reputation score of the peer, negative for misbehaving peers
+    pub reputation: i32,
 }
 
 impl From<sync::EthProtocolInfo> for EthProtocolInfo {
@@ -99,6 +103,75 @@ impl From<sync::EthProtocolInfo> for EthProtocolInfo {
             version: info.version,
             difficulty: info.difficulty.map(Into::into),
             head: format!("{:x}", info.head),
+            reputation: info.reputation,
+        }
+    }
+}
+
+/// Peer traffic information
+#[derive(Default, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerTrafficInfo {
+    /// Everything received from the peer
+    pub ingress: TrafficInfo,
+    /// Everything sent to the peer
+    pub egress: TrafficInfo,
+    /// Bytes per second recently received from the peer
+    pub ingress_rate: f64,
+    /// Bytes per second recently sent to the peer
+    pub egress_rate: f64,
+    /// Traffic by protocol packet
+    pub packets: Vec<PacketTrafficInfo>,
+}
+
+/// Bytes and messages transferred in one direction
+#[derive(Default, Debug, Serialize)]
+pub struct TrafficInfo {
+    /// Number of bytes
+    pub bytes: u64,
+    /// Number of messages
+    pub messages: u64,
+}
+
+/// Traffic of a single protocol packet type
+#[derive(Default, Debug, Serialize)]
+pub struct PacketTrafficInfo {
+    /// Protocol name
+    pub protocol: String,
+    /// Packet name
+    pub packet: String,
+    /// Received packets
+    pub ingress: TrafficInfo,
+    /// Sent packets
+    pub egress: TrafficInfo,
+}
+
+impl From<sync::TrafficCounter> for TrafficInfo {
+    fn from(counter: sync::TrafficCounter) -> Self {
+        TrafficInfo {
+            bytes: counter.bytes,
+            messages: counter.messages,
+        }
+    }
+}
+
+impl From<sync::PeerTraffic> for PeerTrafficInfo {
+    fn from(traffic: sync::PeerTraffic) -> Self {
+        PeerTrafficInfo {
+            ingress: traffic.ingress.into(),
+            egress: traffic.egress.into(),
+            ingress_rate: traffic.ingress_rate,
+            egress_rate: traffic.egress_rate,
+            packets: traffic
+                .packets
+                .into_iter()
+                .map(|p| PacketTrafficInfo {
+                    protocol: p.protocol,
+                    packet: p.packet,
+                    ingress: p.ingress.into(),
+                    egress: p.egress.into(),
+                })
+                .collect(),
         }
     }
 }
@@ -147,6 +220,7 @@ impl From<SyncPeerInfo> for PeerInfo {
             protocols: PeerProtocolsInfo {
                 eth: p.eth_info.map(Into::into),
             },
+            traffic: p.traffic.into(),
         }
     }
 }
@@ -83,6 +83,12 @@ pub struct Transaction {
     /// miner bribe
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_priority_fee_per_gas: Option<U256>,
+    /// Max fee per blob gas
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_blob_gas: Option<U256>,
+    /// Versioned hashes of the carried blobs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blob_versioned_hashes: Option<Vec<H256>>,
 }
 
 /// Local Transaction Status
@@ -205,15 +211,28 @@ impl Transaction {
                     .map(Into::into)
                     .collect(),
             ),
+            TypedTransaction::BlobTransaction(tx) => Some(
+                tx.transaction
+                    .transaction
+                    .access_list
+                    .clone()
+                    .into_iter()
+                    .map(Into::into)
+                    .collect(),
+            ),
             TypedTransaction::Legacy(_) => None,
         };
 
-        let (max_fee_per_gas, max_priority_fee_per_gas) =
-            if let TypedTransaction::EIP1559Transaction(tx) = t.as_unsigned() {
+        let (max_fee_per_gas, max_priority_fee_per_gas) = match t.as_unsigned() {
+            TypedTransaction::EIP1559Transaction(tx) => {
                 (Some(tx.tx().gas_price), Some(tx.max_priority_fee_per_gas))
-            } else {
-                (None, None)
-            };
+            }
+            TypedTransaction::BlobTransaction(tx) => (
+                Some(tx.tx().gas_price),
+                Some(tx.transaction.max_priority_fee_per_gas),
+            ),
+            _ => (None, None),
+        };
 
         let standard_v = if t.tx_type() == TypedTxId::Legacy {
             Some(t.standard_v())
@@ -254,6 +273,8 @@ impl Transaction {
             transaction_type: t.signed.tx_type().to_U64_option_id(),
             access_list,
             max_priority_fee_per_gas,
+            max_fee_per_blob_gas: t.max_fee_per_blob_gas(),
+            blob_versioned_hashes: t.blob_versioned_hashes().cloned(),
         }
     }
 
@@ -274,15 +295,28 @@ impl Transaction {
                     .map(Into::into)
                     .collect(),
             ),
+            TypedTransaction::BlobTransaction(tx) => Some(
+                tx.transaction
+                    .transaction
+                    .access_list
+                    .clone()
+                    .into_iter()
+                    .map(Into::into)
+                    .collect(),
+            ),
             TypedTransaction::Legacy(_) => None,
         };
 
-        let (max_fee_per_gas, max_priority_fee_per_gas) =
-            if let TypedTransaction::EIP1559Transaction(tx) = t.as_unsigned() {
+        let (max_fee_per_gas, max_priority_fee_per_gas) = match t.as_unsigned() {
+            TypedTransaction::EIP1559Transaction(tx) => {
                 (Some(tx.tx().gas_price), Some(tx.max_priority_fee_per_gas))
-            } else {
-                (None, None)
-            };
+            }
+            TypedTransaction::BlobTransaction(tx) => (
+                Some(tx.tx().gas_price),
+                Some(tx.transaction.max_priority_fee_per_gas),
+            ),
+            _ => (None, None),
+        };
 
         let standard_v = if t.tx_type() == TypedTxId::Legacy {
             Some(t.standard_v())
@@ -323,6 +357,8 @@ impl Transaction {
             transaction_type: t.tx_type().to_U64_option_id(),
             access_list,
             max_priority_fee_per_gas,
+            max_fee_per_blob_gas: t.max_fee_per_blob_gas(),
+            blob_versioned_hashes: t.blob_versioned_hashes().cloned(),
         }
     }
 
@@ -1,4 +1,4 @@
-use ethereum_types::{H160, H256};
+use ethereum_types::{H160, H256, U256};
 use serde::Serialize;
 use std::vec::Vec;
 use types::transaction::AccessListItem as InnerAccessListItem;
@@ -34,3 +34,16 @@ impl From<AccessListItem> for InnerAccessListItem {
         (item.address, item.storage_keys)
     }
 }
+
+/// Result of `eth_createAccessList`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessListWithGasUsed {
+    /// Addresses and storage keys accessed by the call.
+    pub access_list: AccessList,
+    /// Gas used by the call with the access list applied.
+    pub gas_used: U256,
+    /// Error, if the call failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
@@ -0,0 +1,132 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction pool lifecycle events.
+
+use ethereum_types::{H160, H256, U256};
+use miner::pool::events::{self, Event};
+
+/// What happened to a pool transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransactionEventKind {
+    /// Transaction entered the pool.
+    Added,
+    /// Transaction was replaced by another one with the same nonce.
+    Replaced,
+    /// Transaction was removed from the pool without being mined.
+    Dropped,
+    /// Some other transaction with the same nonce got mined.
+    Culled,
+    /// Transaction was included in a block.
+    Mined,
+}
+
+/// Reason for a transaction to be dropped from the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DropReason {
+    /// The pool is full.
+    Limit,
+    /// The transaction stayed in the pool for too long.
+    Stale,
+    /// The transaction is invalid.
+    Invalid,
+    /// The transaction was removed on request.
+    Canceled,
+    /// The whole pool was cleared.
+    Cleared,
+}
+
+/// Lifecycle event of a pool transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionEvent {
+    /// Transaction hash.
+    pub hash: H256,
+    /// Sender.
+    pub from: H160,
+    /// Nonce.
+    pub nonce: U256,
+    /// What happened.
+    pub event: TransactionEventKind,
+    /// Hash of the replacement, for replaced transactions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replaced_by: Option<H256>,
+    /// Why the transaction was dropped, for dropped transactions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<DropReason>,
+}
+
+impl From<events::DropReason> for DropReason {
+    fn from(reason: events::DropReason) -> Self {
+        match reason {
+            events::DropReason::Limit => DropReason::Limit,
+            events::DropReason::Stale => DropReason::Stale,
+            events::DropReason::Invalid => DropReason::Invalid,
+            events::DropReason::Canceled => DropReason::Canceled,
+            events::DropReason::Cleared => DropReason::Cleared,
+        }
+    }
+}
+
+impl From<Event> for TransactionEvent {
+    fn from(e: Event) -> Self {
+        let (event, replaced_by, reason) = match e.kind {
+            events::EventKind::Added => (TransactionEventKind::Added, None, None),
+            events::EventKind::Replaced { replaced_by } => {
+                (TransactionEventKind::Replaced, Some(replaced_by), None)
+            }
+            events::EventKind::Dropped { reason } => {
+                (TransactionEventKind::Dropped, None, Some(reason.into()))
+            }
+            events::EventKind::Culled => (TransactionEventKind::Culled, None, None),
+            events::EventKind::Mined => (TransactionEventKind::Mined, None, None),
+        };
+
+        TransactionEvent {
+            hash: e.hash,
+            from: e.from,
+            nonce: e.nonce,
+            event,
+            replaced_by,
+            reason,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn should_serialize_dropped_event() {
+        let event = TransactionEvent::from(Event {
+            hash: H256::from_low_u64_be(1),
+            from: H160::from_low_u64_be(2),
+            nonce: 3.into(),
+            kind: events::EventKind::Dropped {
+                reason: events::DropReason::Limit,
+            },
+        });
+
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"hash":"0x0000000000000000000000000000000000000000000000000000000000000001","from":"0x0000000000000000000000000000000000000002","nonce":"0x3","event":"dropped","reason":"limit"}"#
+        );
+    }
+}
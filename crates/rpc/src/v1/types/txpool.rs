@@ -0,0 +1,158 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Geth-compatible `txpool_*` types.
+
+use std::collections::BTreeMap;
+
+use ethereum_types::{H160, U64};
+
+use v1::types::Transaction;
+
+/// Transactions of a single sender, keyed by the decimal nonce.
+pub type TxPoolNonces<T> = BTreeMap<String, T>;
+
+/// Transactions grouped by sender and nonce.
+pub type TxPoolSenders<T> = BTreeMap<H160, TxPoolNonces<T>>;
+
+fn group<T, F>(transactions: Vec<Transaction>, f: F) -> TxPoolSenders<T>
+where
+    F: Fn(Transaction) -> T,
+{
+    let mut senders = TxPoolSenders::new();
+    for tx in transactions {
+        senders
+            .entry(tx.from)
+            .or_insert_with(BTreeMap::new)
+            .insert(tx.nonce.to_string(), f(tx));
+    }
+    senders
+}
+
+fn nonces(transactions: Vec<Transaction>) -> TxPoolNonces<Transaction> {
+    transactions
+        .into_iter()
+        .map(|tx| (tx.nonce.to_string(), tx))
+        .collect()
+}
+
+fn summary(tx: Transaction) -> String {
+    let to = match tx.to {
+        Some(to) => format!("{:?}", to),
+        None => "contract creation".into(),
+    };
+    format!(
+        "{}: {} wei + {} gas × {} wei",
+        to, tx.value, tx.gas, tx.gas_price
+    )
+}
+
+/// `txpool_content` result.
+#[derive(Debug, Serialize)]
+pub struct TxPoolContent {
+    /// Transactions ready to be included in the next block.
+    pub pending: TxPoolSenders<Transaction>,
+    /// Transactions waiting for a transaction with a lower nonce.
+    pub queued: TxPoolSenders<Transaction>,
+}
+
+impl TxPoolContent {
+    /// Groups pending and queued transactions by sender and nonce.
+    pub fn new(pending: Vec<Transaction>, queued: Vec<Transaction>) -> Self {
+        TxPoolContent {
+            pending: group(pending, |tx| tx),
+            queued: group(queued, |tx| tx),
+        }
+    }
+}
+
+/// `txpool_contentFrom` result.
+#[derive(Debug, Serialize)]
+pub struct TxPoolContentFrom {
+    /// Transactions ready to be included in the next block.
+    pub pending: TxPoolNonces<Transaction>,
+    /// Transactions waiting for a transaction with a lower nonce.
+    pub queued: TxPoolNonces<Transaction>,
+}
+
+impl TxPoolContentFrom {
+    /// Groups pending and queued transactions of a single sender by nonce.
+    pub fn new(pending: Vec<Transaction>, queued: Vec<Transaction>) -> Self {
+        TxPoolContentFrom {
+            pending: nonces(pending),
+            queued: nonces(queued),
+        }
+    }
+}
+
+/// `txpool_inspect` result, transactions are summarized as
+/// `to: value wei + gas gas × gas price wei`.
+#[derive(Debug, Serialize)]
+pub struct TxPoolInspect {
+    /// Summaries of transactions ready to be included in the next block.
+    pub pending: TxPoolSenders<String>,
+    /// Summaries of transactions waiting for a transaction with a lower nonce.
+    pub queued: TxPoolSenders<String>,
+}
+
+impl TxPoolInspect {
+    /// Groups summaries of pending and queued transactions by sender and nonce.
+    pub fn new(pending: Vec<Transaction>, queued: Vec<Transaction>) -> Self {
+        TxPoolInspect {
+            pending: group(pending, summary),
+            queued: group(queued, summary),
+        }
+    }
+}
+
+/// `txpool_status` result.
+#[derive(Debug, Serialize)]
+pub struct TxPoolStatus {
+    /// Number of transactions ready to be included in the next block.
+    pub pending: U64,
+    /// Number of transactions waiting for a transaction with a lower nonce.
+    pub queued: U64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    fn transaction(from: u64, nonce: u64, to: Option<u64>) -> Transaction {
+        let mut tx = Transaction::default();
+        tx.from = H160::from_low_u64_be(from);
+        tx.nonce = nonce.into();
+        tx.to = to.map(H160::from_low_u64_be);
+        tx.value = 1_000.into();
+        tx.gas = 21_000.into();
+        tx.gas_price = 10.into();
+        tx
+    }
+
+    #[test]
+    fn should_serialize_inspect() {
+        let inspect = TxPoolInspect::new(
+            vec![transaction(1, 9, Some(2)), transaction(1, 10, None)],
+            vec![transaction(3, 5, Some(2))],
+        );
+        let serialized = serde_json::to_string(&inspect).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"pending":{"0x0000000000000000000000000000000000000001":{"10":"contract creation: 1000 wei + 21000 gas × 10 wei","9":"0x0000000000000000000000000000000000000002: 1000 wei + 21000 gas × 10 wei"}},"queued":{"0x0000000000000000000000000000000000000003":{"5":"0x0000000000000000000000000000000000000002: 1000 wei + 21000 gas × 10 wei"}}}"#
+        );
+    }
+}
@@ -67,6 +67,27 @@ impl PrometheusRegistry {
             .expect("prometheus identifiers must be are unique");
     }
 
+    /// Adds a new prometheus gauge with the specified labels and value. Gauges of the same name
+    /// must be given different label values.
+    pub fn register_labeled_gauge(
+        &mut self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        value: i64,
+    ) {
+        let name = format!("{}{}", self.prefix, name);
+        let opts = labels.iter().fold(
+            prometheus::Opts::new(name.as_str(), help),
+            |opts, (k, v)| opts.const_label(*k, *v),
+        );
+        let g = prometheus::IntGauge::with_opts(opts).expect("name and help must be non-empty");
+        g.set(value);
+        self.registry
+            .register(Box::new(g))
+            .expect("prometheus identifiers must be are unique");
+    }
+
     /// Adds a new prometheus counter with the time spent in running the specified function
     pub fn register_optime<F: Fn() -> T, T>(&mut self, name: &str, f: &F) -> T {
         let start = Instant::now();
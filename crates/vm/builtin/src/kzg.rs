@@ -0,0 +1,250 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! KZG proof verification for the EIP-4844 point evaluation precompile.
+//!
+//! The pairing check is done with the EIP-2537 BLS12-381 operations, so only the mainnet
+//! trusted setup point `[tau]G2` is needed on top of the curve generators.
+
+use eth_pairings::public_interface::eip2537::{
+    EIP2537Executor, SCALAR_BYTE_LENGTH, SERIALIZED_G1_POINT_BYTE_LENGTH,
+    SERIALIZED_G2_POINT_BYTE_LENGTH,
+};
+use ethereum_types::U256;
+use log::trace;
+use num::{BigUint, One, Zero};
+
+use crate::modexp;
+
+/// Size of a compressed G1 point (commitment or proof).
+pub const COMPRESSED_G1_POINT_BYTE_LENGTH: usize = 48;
+
+/// Size of a field element in the EIP-2537 encoding (48 bytes left-padded to 64).
+const ENCODED_FP_BYTE_LENGTH: usize = 64;
+const FP_BYTE_LENGTH: usize = 48;
+
+const COMPRESSION_FLAG: u8 = 0x80;
+const INFINITY_FLAG: u8 = 0x40;
+const SIGN_FLAG: u8 = 0x20;
+
+/// Order of the BLS12-381 scalar field.
+pub const BLS_MODULUS: [u8; 32] = [
+    0x73, 0xed, 0xa7, 0x53, 0x29, 0x9d, 0x7d, 0x48, 0x33, 0x39, 0xd8, 0x08, 0x09, 0xa1, 0xd8, 0x05,
+    0x53, 0xbd, 0xa4, 0x02, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01,
+];
+
+/// Modulus of the BLS12-381 base field.
+const FP_MODULUS: [u8; 48] = [
+    0x1a, 0x01, 0x11, 0xea, 0x39, 0x7f, 0xe6, 0x9a, 0x4b, 0x1b, 0xa7, 0xb6, 0x43, 0x4b, 0xac, 0xd7,
+    0x64, 0x77, 0x4b, 0x84, 0xf3, 0x85, 0x12, 0xbf, 0x67, 0x30, 0xd2, 0xa0, 0xf6, 0xb0, 0xf6, 0x24,
+    0x1e, 0xab, 0xff, 0xfe, 0xb1, 0x53, 0xff, 0xff, 0xb9, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xaa, 0xab,
+];
+/// `(p + 1) / 4`, the square root exponent for `p = 3 mod 4`.
+const FP_SQRT_EXPONENT: [u8; 48] = [
+    0x06, 0x80, 0x44, 0x7a, 0x8e, 0x5f, 0xf9, 0xa6, 0x92, 0xc6, 0xe9, 0xed, 0x90, 0xd2, 0xeb, 0x35,
+    0xd9, 0x1d, 0xd2, 0xe1, 0x3c, 0xe1, 0x44, 0xaf, 0xd9, 0xcc, 0x34, 0xa8, 0x3d, 0xac, 0x3d, 0x89,
+    0x07, 0xaa, 0xff, 0xff, 0xac, 0x54, 0xff, 0xff, 0xee, 0x7f, 0xbf, 0xff, 0xff, 0xff, 0xea, 0xab,
+];
+/// Generator of G1.
+const G1_X: [u8; 48] = [
+    0x17, 0xf1, 0xd3, 0xa7, 0x31, 0x97, 0xd7, 0x94, 0x26, 0x95, 0x63, 0x8c, 0x4f, 0xa9, 0xac, 0x0f,
+    0xc3, 0x68, 0x8c, 0x4f, 0x97, 0x74, 0xb9, 0x05, 0xa1, 0x4e, 0x3a, 0x3f, 0x17, 0x1b, 0xac, 0x58,
+    0x6c, 0x55, 0xe8, 0x3f, 0xf9, 0x7a, 0x1a, 0xef, 0xfb, 0x3a, 0xf0, 0x0a, 0xdb, 0x22, 0xc6, 0xbb,
+];
+const G1_Y: [u8; 48] = [
+    0x08, 0xb3, 0xf4, 0x81, 0xe3, 0xaa, 0xa0, 0xf1, 0xa0, 0x9e, 0x30, 0xed, 0x74, 0x1d, 0x8a, 0xe4,
+    0xfc, 0xf5, 0xe0, 0x95, 0xd5, 0xd0, 0x0a, 0xf6, 0x00, 0xdb, 0x18, 0xcb, 0x2c, 0x04, 0xb3, 0xed,
+    0xd0, 0x3c, 0xc7, 0x44, 0xa2, 0x88, 0x8a, 0xe4, 0x0c, 0xaa, 0x23, 0x29, 0x46, 0xc5, 0xe7, 0xe1,
+];
+/// Negated generator of G2.
+const G2_X0: [u8; 48] = [
+    0x02, 0x4a, 0xa2, 0xb2, 0xf0, 0x8f, 0x0a, 0x91, 0x26, 0x08, 0x05, 0x27, 0x2d, 0xc5, 0x10, 0x51,
+    0xc6, 0xe4, 0x7a, 0xd4, 0xfa, 0x40, 0x3b, 0x02, 0xb4, 0x51, 0x0b, 0x64, 0x7a, 0xe3, 0xd1, 0x77,
+    0x0b, 0xac, 0x03, 0x26, 0xa8, 0x05, 0xbb, 0xef, 0xd4, 0x80, 0x56, 0xc8, 0xc1, 0x21, 0xbd, 0xb8,
+];
+const G2_X1: [u8; 48] = [
+    0x13, 0xe0, 0x2b, 0x60, 0x52, 0x71, 0x9f, 0x60, 0x7d, 0xac, 0xd3, 0xa0, 0x88, 0x27, 0x4f, 0x65,
+    0x59, 0x6b, 0xd0, 0xd0, 0x99, 0x20, 0xb6, 0x1a, 0xb5, 0xda, 0x61, 0xbb, 0xdc, 0x7f, 0x50, 0x49,
+    0x33, 0x4c, 0xf1, 0x12, 0x13, 0x94, 0x5d, 0x57, 0xe5, 0xac, 0x7d, 0x05, 0x5d, 0x04, 0x2b, 0x7e,
+];
+const NEG_G2_Y0: [u8; 48] = [
+    0x0d, 0x1b, 0x3c, 0xc2, 0xc7, 0x02, 0x78, 0x88, 0xbe, 0x51, 0xd9, 0xef, 0x69, 0x1d, 0x77, 0xbc,
+    0xb6, 0x79, 0xaf, 0xda, 0x66, 0xc7, 0x3f, 0x17, 0xf9, 0xee, 0x38, 0x37, 0xa5, 0x50, 0x24, 0xf7,
+    0x8c, 0x71, 0x36, 0x32, 0x75, 0xa7, 0x5d, 0x75, 0xd8, 0x6b, 0xab, 0x79, 0xf7, 0x47, 0x82, 0xaa,
+];
+const NEG_G2_Y1: [u8; 48] = [
+    0x13, 0xfa, 0x4d, 0x4a, 0x0a, 0xd8, 0xb1, 0xce, 0x18, 0x6e, 0xd5, 0x06, 0x17, 0x89, 0x21, 0x3d,
+    0x99, 0x39, 0x23, 0x06, 0x6d, 0xdd, 0xaf, 0x10, 0x40, 0xbc, 0x3f, 0xf5, 0x9f, 0x82, 0x5c, 0x78,
+    0xdf, 0x74, 0xf2, 0xd7, 0x54, 0x67, 0xe2, 0x5e, 0x0f, 0x55, 0xf8, 0xa0, 0x0f, 0xa0, 0x30, 0xed,
+];
+/// `[tau]G2` from the mainnet KZG ceremony.
+const TAU_G2_X0: [u8; 48] = [
+    0x18, 0x5c, 0xbf, 0xee, 0x53, 0x49, 0x27, 0x14, 0x73, 0x44, 0x29, 0xb7, 0xb3, 0x86, 0x08, 0xe2,
+    0x39, 0x26, 0xc9, 0x11, 0xcc, 0xec, 0xea, 0xc9, 0xa3, 0x68, 0x51, 0x47, 0x7b, 0xa4, 0xc6, 0x0b,
+    0x08, 0x70, 0x41, 0xde, 0x62, 0x10, 0x00, 0xed, 0xc9, 0x8e, 0xda, 0xda, 0x20, 0xc1, 0xde, 0xf2,
+];
+const TAU_G2_X1: [u8; 48] = [
+    0x15, 0xbf, 0xd7, 0xdd, 0x8c, 0xde, 0xb1, 0x28, 0x84, 0x3b, 0xc2, 0x87, 0x23, 0x0a, 0xf3, 0x89,
+    0x26, 0x18, 0x70, 0x75, 0xcb, 0xfb, 0xef, 0xa8, 0x10, 0x09, 0xa2, 0xce, 0x61, 0x5a, 0xc5, 0x3d,
+    0x29, 0x14, 0xe5, 0x87, 0x0c, 0xb4, 0x52, 0xd2, 0xaf, 0xaa, 0xab, 0x24, 0xf3, 0x49, 0x9f, 0x72,
+];
+const TAU_G2_Y0: [u8; 48] = [
+    0x01, 0x43, 0x53, 0xbd, 0xb9, 0x6b, 0x62, 0x6d, 0xd7, 0xd5, 0xee, 0x85, 0x99, 0xd1, 0xfc, 0xa2,
+    0x13, 0x15, 0x69, 0x49, 0x0e, 0x28, 0xde, 0x18, 0xe8, 0x24, 0x51, 0xa4, 0x96, 0xa9, 0xc9, 0x79,
+    0x4c, 0xe2, 0x6d, 0x10, 0x59, 0x41, 0xf3, 0x83, 0xee, 0x68, 0x9b, 0xfb, 0xbb, 0x83, 0x2a, 0x99,
+];
+const TAU_G2_Y1: [u8; 48] = [
+    0x16, 0x66, 0xc5, 0x4b, 0x0a, 0x32, 0x52, 0x95, 0x03, 0x43, 0x2f, 0xca, 0xe0, 0x18, 0x1b, 0x4b,
+    0xef, 0x79, 0xde, 0x09, 0xfc, 0x63, 0x67, 0x1f, 0xda, 0x5e, 0xd1, 0xba, 0x9b, 0xfa, 0x07, 0x89,
+    0x94, 0x95, 0x34, 0x6f, 0x3d, 0x7a, 0xc9, 0xcd, 0x23, 0x04, 0x8e, 0xf3, 0x0d, 0x0a, 0x15, 0x4f,
+];
+
+/// Checks that `proof` opens `commitment` to `y` at `z`, i.e. that
+/// `e(commitment - [y]G1, -G2) * e(proof, [tau]G2 - [z]G2) == 1`.
+///
+/// `z` and `y` must already be reduced modulo `BLS_MODULUS`.
+pub fn verify_proof(
+    commitment: &[u8],
+    z: U256,
+    y: U256,
+    proof: &[u8],
+) -> Result<bool, &'static str> {
+    let commitment = decompress_g1(commitment)?;
+    let proof = decompress_g1(proof)?;
+
+    let mut g1_mul_input = [0u8; SERIALIZED_G1_POINT_BYTE_LENGTH + SCALAR_BYTE_LENGTH];
+    g1_mul_input[..SERIALIZED_G1_POINT_BYTE_LENGTH].copy_from_slice(&encode_g1(&G1_X, &G1_Y));
+    negate_scalar(y).to_big_endian(&mut g1_mul_input[SERIALIZED_G1_POINT_BYTE_LENGTH..]);
+    let minus_y_g1 = EIP2537Executor::g1_mul(&g1_mul_input[..]).map_err(pairing_error)?;
+
+    let mut g1_add_input = [0u8; 2 * SERIALIZED_G1_POINT_BYTE_LENGTH];
+    g1_add_input[..SERIALIZED_G1_POINT_BYTE_LENGTH].copy_from_slice(&commitment);
+    g1_add_input[SERIALIZED_G1_POINT_BYTE_LENGTH..].copy_from_slice(&minus_y_g1[..]);
+    let lhs = EIP2537Executor::g1_add(&g1_add_input[..]).map_err(pairing_error)?;
+
+    let mut g2_mul_input = [0u8; SERIALIZED_G2_POINT_BYTE_LENGTH + SCALAR_BYTE_LENGTH];
+    g2_mul_input[..SERIALIZED_G2_POINT_BYTE_LENGTH]
+        .copy_from_slice(&encode_g2(&G2_X0, &G2_X1, &NEG_G2_Y0, &NEG_G2_Y1));
+    // [z](-G2) == -[z]G2
+    z.to_big_endian(&mut g2_mul_input[SERIALIZED_G2_POINT_BYTE_LENGTH..]);
+    let minus_z_g2 = EIP2537Executor::g2_mul(&g2_mul_input[..]).map_err(pairing_error)?;
+
+    let mut g2_add_input = [0u8; 2 * SERIALIZED_G2_POINT_BYTE_LENGTH];
+    g2_add_input[..SERIALIZED_G2_POINT_BYTE_LENGTH]
+        .copy_from_slice(&encode_g2(&TAU_G2_X0, &TAU_G2_X1, &TAU_G2_Y0, &TAU_G2_Y1));
+    g2_add_input[SERIALIZED_G2_POINT_BYTE_LENGTH..].copy_from_slice(&minus_z_g2[..]);
+    let rhs = EIP2537Executor::g2_add(&g2_add_input[..]).map_err(pairing_error)?;
+
+    let mut pairing_input =
+        Vec::with_capacity(2 * (SERIALIZED_G1_POINT_BYTE_LENGTH + SERIALIZED_G2_POINT_BYTE_LENGTH));
+    pairing_input.extend_from_slice(&lhs[..]);
+    pairing_input.extend_from_slice(&encode_g2(&G2_X0, &G2_X1, &NEG_G2_Y0, &NEG_G2_Y1));
+    pairing_input.extend_from_slice(&proof);
+    pairing_input.extend_from_slice(&rhs[..]);
+    let result = EIP2537Executor::pair(&pairing_input[..]).map_err(pairing_error)?;
+
+    Ok(result.last() == Some(&1))
+}
+
+fn pairing_error<E: ::std::fmt::Debug>(e: E) -> &'static str {
+    trace!(target: "builtin", "point evaluation pairing check failed: {:?}", e);
+    "point evaluation proof is not a valid curve point"
+}
+
+/// Returns `-value mod r`.
+fn negate_scalar(value: U256) -> U256 {
+    let modulus = U256::from_big_endian(&BLS_MODULUS);
+    (modulus - value) % modulus
+}
+
+/// Writes `value` into a 64-byte EIP-2537 field element slot.
+fn write_fp(out: &mut [u8], value: &[u8]) {
+    let start = ENCODED_FP_BYTE_LENGTH - value.len();
+    out[start..ENCODED_FP_BYTE_LENGTH].copy_from_slice(value);
+}
+
+fn encode_g1(
+    x: &[u8; FP_BYTE_LENGTH],
+    y: &[u8; FP_BYTE_LENGTH],
+) -> [u8; SERIALIZED_G1_POINT_BYTE_LENGTH] {
+    let mut point = [0u8; SERIALIZED_G1_POINT_BYTE_LENGTH];
+    write_fp(&mut point[..ENCODED_FP_BYTE_LENGTH], x);
+    write_fp(&mut point[ENCODED_FP_BYTE_LENGTH..], y);
+    point
+}
+
+fn encode_g2(
+    x0: &[u8; FP_BYTE_LENGTH],
+    x1: &[u8; FP_BYTE_LENGTH],
+    y0: &[u8; FP_BYTE_LENGTH],
+    y1: &[u8; FP_BYTE_LENGTH],
+) -> [u8; SERIALIZED_G2_POINT_BYTE_LENGTH] {
+    let mut point = [0u8; SERIALIZED_G2_POINT_BYTE_LENGTH];
+    for (i, fp) in [x0, x1, y0, y1].iter().enumerate() {
+        write_fp(
+            &mut point[i * ENCODED_FP_BYTE_LENGTH..(i + 1) * ENCODED_FP_BYTE_LENGTH],
+            &fp[..],
+        );
+    }
+    point
+}
+
+/// Decompresses a 48-byte G1 point (ZCash serialization) into the EIP-2537 encoding.
+/// Subgroup membership is left to the pairing check.
+fn decompress_g1(bytes: &[u8]) -> Result<[u8; SERIALIZED_G1_POINT_BYTE_LENGTH], &'static str> {
+    if bytes.len() != COMPRESSED_G1_POINT_BYTE_LENGTH || bytes[0] & COMPRESSION_FLAG == 0 {
+        return Err("point evaluation expects compressed G1 points");
+    }
+
+    let mut point = [0u8; SERIALIZED_G1_POINT_BYTE_LENGTH];
+    if bytes[0] & INFINITY_FLAG != 0 {
+        // The point at infinity is encoded as all zeros apart from the two flags.
+        if bytes[0] != COMPRESSION_FLAG | INFINITY_FLAG || bytes[1..].iter().any(|b| *b != 0) {
+            return Err("point evaluation got a malformed point at infinity");
+        }
+        return Ok(point);
+    }
+
+    let mut x_bytes = [0u8; FP_BYTE_LENGTH];
+    x_bytes.copy_from_slice(bytes);
+    x_bytes[0] &= !(COMPRESSION_FLAG | INFINITY_FLAG | SIGN_FLAG);
+
+    let modulus = BigUint::from_bytes_be(&FP_MODULUS);
+    let x = BigUint::from_bytes_be(&x_bytes);
+    if x >= modulus {
+        return Err("point evaluation got a non-canonical point coordinate");
+    }
+
+    // y^2 = x^3 + 4
+    let rhs = (&x * &x * &x + BigUint::from_bytes_be(&[4])) % &modulus;
+    let mut y = modexp(rhs.clone(), FP_SQRT_EXPONENT.to_vec(), modulus.clone());
+    if &y * &y % &modulus != rhs {
+        return Err("point evaluation got a point that is not on the curve");
+    }
+
+    let sign = bytes[0] & SIGN_FLAG != 0;
+    if y.is_zero() && sign {
+        return Err("point evaluation got a point with an invalid sign");
+    }
+    let half = (&modulus - BigUint::one()) >> 1;
+    if (y > half) != sign {
+        y = &modulus - &y;
+    }
+
+    write_fp(&mut point[..ENCODED_FP_BYTE_LENGTH], &x.to_bytes_be());
+    write_fp(&mut point[ENCODED_FP_BYTE_LENGTH..], &y.to_bytes_be());
+    Ok(point)
+}
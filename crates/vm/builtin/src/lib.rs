@@ -44,6 +44,8 @@ use parity_crypto::{
     publickey::{recover_allowing_all_zero_message, Signature, ZeroesAllowedMessage},
 };
 
+mod kzg;
+
 /// Native implementation of a built-in contract.
 pub trait Implementation: Send + Sync {
     /// execute this built-in on the given input, writing to the given output.
@@ -685,6 +687,8 @@ enum EthereumBuiltin {
     Bls12MapFpToG1(Bls12MapFpToG1),
     /// bls12_381 fp2 to g2 mapping
     Bls12MapFp2ToG2(Bls12MapFp2ToG2),
+    /// point evaluation (EIP-4844)
+    PointEvaluation(PointEvaluation),
 }
 
 impl FromStr for EthereumBuiltin {
@@ -710,6 +714,7 @@ impl FromStr for EthereumBuiltin {
             "bls12_381_pairing" => Ok(EthereumBuiltin::Bls12Pairing(Bls12Pairing)),
             "bls12_381_fp_to_g1" => Ok(EthereumBuiltin::Bls12MapFpToG1(Bls12MapFpToG1)),
             "bls12_381_fp2_to_g2" => Ok(EthereumBuiltin::Bls12MapFp2ToG2(Bls12MapFp2ToG2)),
+            "point_evaluation" => Ok(EthereumBuiltin::PointEvaluation(PointEvaluation)),
             _ => return Err(format!("invalid builtin name: {}", name)),
         }
     }
@@ -736,6 +741,7 @@ impl Implementation for EthereumBuiltin {
             EthereumBuiltin::Bls12Pairing(inner) => inner.execute(input, output),
             EthereumBuiltin::Bls12MapFpToG1(inner) => inner.execute(input, output),
             EthereumBuiltin::Bls12MapFp2ToG2(inner) => inner.execute(input, output),
+            EthereumBuiltin::PointEvaluation(inner) => inner.execute(input, output),
         }
     }
 }
@@ -803,6 +809,10 @@ pub struct Bls12MapFpToG1;
 /// The Bls12MapFp2ToG2 builtin.
 pub struct Bls12MapFp2ToG2;
 
+#[derive(Debug)]
+/// The point evaluation builtin.
+pub struct PointEvaluation;
+
 impl Implementation for Identity {
     fn execute(&self, input: &[u8], output: &mut BytesRef) -> Result<(), &'static str> {
         output.write(0, input);
@@ -856,6 +866,45 @@ impl Implementation for Sha256 {
     }
 }
 
+impl Implementation for PointEvaluation {
+    /// Format of `input`:
+    /// [32 bytes for versioned hash][32 bytes for z][32 bytes for y][48 bytes for commitment][48 bytes for proof]
+    fn execute(&self, input: &[u8], output: &mut BytesRef) -> Result<(), &'static str> {
+        const POINT_EVALUATION_ARG_LEN: usize = 192;
+        const FIELD_ELEMENTS_PER_BLOB: u64 = 4096;
+
+        if input.len() != POINT_EVALUATION_ARG_LEN {
+            trace!(target: "builtin", "input length for point evaluation precompile should be exactly 192 bytes, was {}", input.len());
+            return Err("input length for point evaluation precompile should be exactly 192 bytes");
+        }
+
+        let modulus = U256::from_big_endian(&kzg::BLS_MODULUS);
+        let z = U256::from_big_endian(&input[32..64]);
+        let y = U256::from_big_endian(&input[64..96]);
+        if z >= modulus || y >= modulus {
+            return Err("point evaluation input is not a canonical field element");
+        }
+
+        let mut versioned_hash = digest::sha256(&input[96..144]).to_vec();
+        versioned_hash[0] = 0x01;
+        if versioned_hash[..] != input[..32] {
+            trace!(target: "builtin", "point evaluation versioned hash does not match the commitment");
+            return Err("point evaluation versioned hash does not match the commitment");
+        }
+
+        if !kzg::verify_proof(&input[96..144], z, y, &input[144..192])? {
+            trace!(target: "builtin", "point evaluation proof does not open the commitment at z");
+            return Err("point evaluation proof verification failed");
+        }
+
+        let mut result = [0u8; 64];
+        U256::from(FIELD_ELEMENTS_PER_BLOB).to_big_endian(&mut result[..32]);
+        result[32..].copy_from_slice(&kzg::BLS_MODULUS);
+        output.write(0, &result);
+        Ok(())
+    }
+}
+
 impl Implementation for Blake2F {
     /// Format of `input`:
     /// [4 bytes for rounds][64 bytes for h][128 bytes for m][8 bytes for t_0][8 bytes for t_1][1 byte for f]
@@ -1615,6 +1664,83 @@ mod tests {
         );
     }
 
+    #[test]
+    fn point_evaluation() {
+        let f = EthereumBuiltin::from_str("point_evaluation").unwrap();
+        // commitment and proof are the point at infinity, the opening of the zero polynomial at z = 0
+        let input = hex!("010657f37554c781402a22917dee2f75def7ab966d7b770905398eba3c444014 0000000000000000000000000000000000000000000000000000000000000000 0000000000000000000000000000000000000000000000000000000000000000 c00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000 c00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000");
+        let expected = hex!("000000000000000000000000000000000000000000000000000000000000100073eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001");
+
+        let mut out = [0u8; 64];
+        f.execute(&input[..], &mut BytesRef::Fixed(&mut out[..]))
+            .expect("Builtin should not fail");
+        assert_eq!(&out[..], &expected[..]);
+    }
+
+    #[test]
+    fn point_evaluation_is_err_on_invalid_input() {
+        let f = EthereumBuiltin::from_str("point_evaluation").unwrap();
+        let mut out = [0u8; 64];
+
+        let short = [0u8; 191];
+        assert_eq!(
+            f.execute(&short[..], &mut BytesRef::Fixed(&mut out[..])),
+            Err("input length for point evaluation precompile should be exactly 192 bytes")
+        );
+
+        // versioned hash with a wrong version byte
+        let mut input = hex!("010657f37554c781402a22917dee2f75def7ab966d7b770905398eba3c444014 0000000000000000000000000000000000000000000000000000000000000000 0000000000000000000000000000000000000000000000000000000000000000 c00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000 c00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000");
+        input[0] = 0x02;
+        assert_eq!(
+            f.execute(&input[..], &mut BytesRef::Fixed(&mut out[..])),
+            Err("point evaluation versioned hash does not match the commitment")
+        );
+
+        // z equal to the field modulus
+        input[0] = 0x01;
+        input[32..64].copy_from_slice(&hex!(
+            "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001"
+        ));
+        assert_eq!(
+            f.execute(&input[..], &mut BytesRef::Fixed(&mut out[..])),
+            Err("point evaluation input is not a canonical field element")
+        );
+    }
+
+    #[test]
+    fn point_evaluation_verifies_proof() {
+        let f = EthereumBuiltin::from_str("point_evaluation").unwrap();
+        let expected = hex!("000000000000000000000000000000000000000000000000000000000000100073eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001");
+
+        // consensus spec vectors, also used by other clients
+        let inputs = [
+            hex!("01e798154708fe7789429634053cbf9f99b619f9f084048927333fce637f549b 564c0a11a0f704f4fc3e8acfe0f8245f0ad1347b378fbf96e206da11a5d36306 24d25032e67a7e6a4910df5834b8fe70e6bcfeeac0352434196bdf4b2485d5a1 8f59a8d2a1a625a17f3fea0fe5eb8c896db3764f3185481bc22f91b4aaffcca25f26936857bc3a7c2539ea8ec3a952b7 873033e038326e87ed3e1276fd140253fa08e9fc25fb2d9a98527fc22a2c9612fbeafdad446cbc7bcdbdcd780af2c16a"),
+            hex!("01e798154708fe7789429634053cbf9f99b619f9f084048927333fce637f549b 73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000000 1522a4a7f34e1ea350ae07c29c96c7e79655aa926122e95fe69fcbd932ca49e9 8f59a8d2a1a625a17f3fea0fe5eb8c896db3764f3185481bc22f91b4aaffcca25f26936857bc3a7c2539ea8ec3a952b7 a62ad71d14c5719385c0686f1871430475bf3a00f0aa3f7b8dd99a9abc2160744faf0070725e00b60ad9a026a15b1a8c"),
+        ];
+        for input in inputs.iter() {
+            let mut out = [0u8; 64];
+            f.execute(&input[..], &mut BytesRef::Fixed(&mut out[..]))
+                .expect("Builtin should not fail");
+            assert_eq!(&out[..], &expected[..]);
+        }
+
+        // a different proof for the same opening
+        let mut tampered = inputs[0];
+        tampered[191] ^= 0x01;
+        let mut out = [0u8; 64];
+        assert!(f
+            .execute(&tampered[..], &mut BytesRef::Fixed(&mut out[..]))
+            .is_err());
+
+        // a different claimed value
+        let mut tampered = inputs[0];
+        tampered[95] ^= 0x01;
+        assert_eq!(
+            f.execute(&tampered[..], &mut BytesRef::Fixed(&mut out[..])),
+            Err("point evaluation proof verification failed")
+        );
+    }
+
     #[test]
     fn ripemd160() {
         let f = EthereumBuiltin::from_str("ripemd160").unwrap();
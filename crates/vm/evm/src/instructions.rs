@@ -31,8 +31,8 @@ macro_rules! enum_with_from_u8 {
 		}
 
 		impl $name {
-			#[doc = "Convert from u8 to the given enum"]
-			pub fn from_u8(value: u8) -> Option<Self> {
+			#[doc = "Convert from the enum discriminator to the given enum"]
+			fn from_discriminator(value: u8) -> Option<Self> {
 				match value {
 					$( $discriminator => Some($variant) ),+,
 					_ => None,
@@ -155,6 +155,10 @@ enum_with_from_u8! {
         SELFBALANCE = 0x47,
         #[doc = "get the block's base fee"]
         BASEFEE = 0x48,
+        #[doc = "get versioned hash of a transaction blob"]
+        BLOBHASH = 0x49,
+        #[doc = "get the block's blob base fee"]
+        BLOBBASEFEE = 0x4a,
 
         #[doc = "remove item from stack"]
         POP = 0x50,
@@ -180,7 +184,15 @@ enum_with_from_u8! {
         GAS = 0x5a,
         #[doc = "set a potential jump destination"]
         JUMPDEST = 0x5b,
-
+        #[doc = "load word from transient storage"]
+        TLOAD = 0x5c,
+        #[doc = "save word to transient storage"]
+        TSTORE = 0x5d,
+        #[doc = "copy memory area"]
+        MCOPY = 0x5e,
+
+        #[doc = "place zero on stack"]
+        PUSH0 = 0x5f,
         #[doc = "place 1 byte item on stack"]
         PUSH1 = 0x60,
         #[doc = "place 2 byte item on stack"]
@@ -323,12 +335,14 @@ enum_with_from_u8! {
         #[doc = "Makes a log entry, 4 topics."]
         LOG4 = 0xa4,
 
+        // EIP-2315 encodes the subroutine instructions as 0x5c-0x5e, which Cancun reassigned.
+        // They are only decoded by `from_u8_with_subs`; the discriminators below are internal.
         #[doc = "Marks the entry point to a subroutine."]
-        BEGINSUB = 0x5c,
+        BEGINSUB = 0xb0,
         #[doc = "Returns from a subroutine."]
-        RETURNSUB = 0x5d,
+        RETURNSUB = 0xb1,
         #[doc = "Jumps to a defined BEGINSUB subroutine."]
-        JUMPSUB = 0x5e,
+        JUMPSUB = 0xb2,
 
         #[doc = "create a new account with associated code"]
         CREATE = 0xf0,
@@ -352,6 +366,23 @@ enum_with_from_u8! {
 }
 
 impl Instruction {
+    /// Convert from u8 to the given enum.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Self::from_u8_with_subs(value, false)
+    }
+
+    /// Convert from u8 to the given enum, decoding 0x5c-0x5e as the EIP-2315
+    /// subroutine instructions instead of `TLOAD`, `TSTORE` and `MCOPY` if `have_subs` is set.
+    pub fn from_u8_with_subs(value: u8, have_subs: bool) -> Option<Self> {
+        match value {
+            0x5c if have_subs => Some(BEGINSUB),
+            0x5d if have_subs => Some(RETURNSUB),
+            0x5e if have_subs => Some(JUMPSUB),
+            v if v == BEGINSUB as u8 || v == RETURNSUB as u8 || v == JUMPSUB as u8 => None,
+            v => Self::from_discriminator(v),
+        }
+    }
+
     /// Returns true if given instruction is `PUSHN` instruction.
     pub fn is_push(&self) -> bool {
         *self >= PUSH1 && *self <= PUSH32
@@ -520,6 +551,8 @@ lazy_static! {
         arr[CHAINID as usize] = Some(InstructionInfo::new("CHAINID", 0, 1, GasPriceTier::Base));
         arr[SELFBALANCE as usize] = Some(InstructionInfo::new("SELFBALANCE", 0, 1, GasPriceTier::Low));
         arr[BASEFEE as usize] = Some(InstructionInfo::new("BASEFEE", 0, 1, GasPriceTier::Base));
+        arr[BLOBHASH as usize] = Some(InstructionInfo::new("BLOBHASH", 1, 1, GasPriceTier::VeryLow));
+        arr[BLOBBASEFEE as usize] = Some(InstructionInfo::new("BLOBBASEFEE", 0, 1, GasPriceTier::Base));
         arr[POP as usize] = Some(InstructionInfo::new("POP", 1, 0, GasPriceTier::Base));
         arr[MLOAD as usize] = Some(InstructionInfo::new("MLOAD", 1, 1, GasPriceTier::VeryLow));
         arr[MSTORE as usize] = Some(InstructionInfo::new("MSTORE", 2, 0, GasPriceTier::VeryLow));
@@ -532,6 +565,10 @@ lazy_static! {
         arr[MSIZE as usize] = Some(InstructionInfo::new("MSIZE", 0, 1, GasPriceTier::Base));
         arr[GAS as usize] = Some(InstructionInfo::new("GAS", 0, 1, GasPriceTier::Base));
         arr[JUMPDEST as usize] = Some(InstructionInfo::new("JUMPDEST", 0, 0, GasPriceTier::Special));
+        arr[TLOAD as usize] = Some(InstructionInfo::new("TLOAD", 1, 1, GasPriceTier::Special));
+        arr[TSTORE as usize] = Some(InstructionInfo::new("TSTORE", 2, 0, GasPriceTier::Special));
+        arr[MCOPY as usize] = Some(InstructionInfo::new("MCOPY", 3, 0, GasPriceTier::VeryLow));
+        arr[PUSH0 as usize] = Some(InstructionInfo::new("PUSH0", 0, 1, GasPriceTier::Base));
         arr[PUSH1 as usize] = Some(InstructionInfo::new("PUSH1", 0, 1, GasPriceTier::VeryLow));
         arr[PUSH2 as usize] = Some(InstructionInfo::new("PUSH2", 0, 1, GasPriceTier::VeryLow));
         arr[PUSH3 as usize] = Some(InstructionInfo::new("PUSH3", 0, 1, GasPriceTier::VeryLow));
@@ -629,6 +666,7 @@ mod tests {
         assert!(PUSH1.is_push());
         assert!(PUSH32.is_push());
         assert!(!DUP1.is_push());
+        assert!(!PUSH0.is_push());
     }
 
     #[test]
@@ -658,4 +696,15 @@ mod tests {
         assert_eq!(LOG2.log_topics(), Some(2));
         assert_eq!(LOG4.log_topics(), Some(4));
     }
+
+    #[test]
+    fn test_from_u8_with_subs() {
+        assert_eq!(Instruction::from_u8(0x5c), Some(TLOAD));
+        assert_eq!(Instruction::from_u8(0x5e), Some(MCOPY));
+        assert_eq!(Instruction::from_u8_with_subs(0x5c, true), Some(BEGINSUB));
+        assert_eq!(Instruction::from_u8_with_subs(0x5d, true), Some(RETURNSUB));
+        assert_eq!(Instruction::from_u8_with_subs(0x5e, true), Some(JUMPSUB));
+        assert_eq!(Instruction::from_u8(BEGINSUB as u8), None);
+        assert_eq!(Instruction::from_u8_with_subs(JUMPSUB as u8, true), None);
+    }
 }
@@ -160,6 +160,9 @@ impl<Gas: evm::CostType> Gasometer<Gas> {
 
                 Request::Gas(gas.into())
             }
+            instructions::TLOAD | instructions::TSTORE => {
+                Request::Gas(schedule.warm_storage_read_cost.into())
+            }
             instructions::SLOAD => {
                 let key = BigEndianHash::from_uint(stack.peek(0));
                 let gas = if ext.al_is_enabled() {
@@ -232,6 +235,13 @@ impl<Gas: evm::CostType> Gasometer<Gas> {
                     Gas::from_u256(*stack.peek(2))?,
                 )
             }
+            instructions::MCOPY => {
+                let mem = cmp::max(
+                    mem_needed(stack.peek(0), stack.peek(2))?,
+                    mem_needed(stack.peek(1), stack.peek(2))?,
+                );
+                Request::GasMemCopy(default_gas, mem, Gas::from_u256(*stack.peek(2))?)
+            }
             instructions::EXTCODECOPY => {
                 let address = u256_to_address(stack.peek(0));
                 let gas = accessed_addresses_gas(&address, schedule.extcodecopy_base_gas);
@@ -303,7 +313,8 @@ impl<Gas: evm::CostType> Gasometer<Gas> {
                 let start = stack.peek(1);
                 let len = stack.peek(2);
 
-                let gas = Gas::from(schedule.create_gas);
+                let base = Gas::from(schedule.create_gas);
+                let gas = overflowing!(base.overflow_add(initcode_gas(schedule, len)?));
                 let mem = mem_needed(start, len)?;
 
                 Request::GasMemProvide(gas, mem, None)
@@ -316,6 +327,7 @@ impl<Gas: evm::CostType> Gasometer<Gas> {
                 let word = overflowing!(to_word_size(Gas::from_u256(*len)?));
                 let word_gas = overflowing!(Gas::from(schedule.sha3_word_gas).overflow_mul(word));
                 let gas = overflowing!(base.overflow_add(word_gas));
+                let gas = overflowing!(gas.overflow_add(initcode_gas(schedule, len)?));
                 let mem = mem_needed(start, len)?;
 
                 Request::GasMemProvide(gas, mem, None)
@@ -430,6 +442,22 @@ fn add_gas_usize<Gas: evm::CostType>(value: Gas, num: usize) -> (Gas, bool) {
     value.overflow_add(Gas::from(num))
 }
 
+/// EIP-3860 initcode metering for CREATE and CREATE2. Initcode over the limit aborts the
+/// creating frame the same way running out of gas does.
+#[inline]
+fn initcode_gas<Gas: evm::CostType>(schedule: &Schedule, len: &U256) -> vm::Result<Gas> {
+    if !schedule.eip3860 {
+        return Ok(Gas::from(0));
+    }
+    if *len > U256::from(schedule.max_initcode_size()) {
+        return Err(vm::Error::OutOfGas);
+    }
+    let word = overflowing!(to_word_size(Gas::from_u256(*len)?));
+    Ok(overflowing!(
+        Gas::from(schedule.initcode_word_gas).overflow_mul(word)
+    ))
+}
+
 #[inline]
 fn to_word_size<Gas: evm::CostType>(value: Gas) -> (Gas, bool) {
     let (gas, overflow) = add_gas_usize(value, 31);
@@ -147,6 +147,8 @@ struct InterpreterParams {
     pub call_type: CallType,
     /// Param types encoding
     pub params_type: ParamsType,
+    /// Versioned hashes of the transaction blobs.
+    pub blob_versioned_hashes: Vec<H256>,
 }
 
 impl From<ActionParams> for InterpreterParams {
@@ -163,6 +165,7 @@ impl From<ActionParams> for InterpreterParams {
             data: params.data,
             call_type: params.call_type,
             params_type: params.params_type,
+            blob_versioned_hashes: params.blob_versioned_hashes,
         }
     }
 }
@@ -363,7 +366,7 @@ impl<Cost: CostType> Interpreter<Cost> {
             Some(result) => result,
             None => {
                 let opcode = self.reader.code[self.reader.position];
-                let instruction = Instruction::from_u8(opcode);
+                let instruction = Instruction::from_u8_with_subs(opcode, ext.schedule().have_subs);
                 self.reader.position += 1;
 
                 // TODO: make compile-time removable if too much of a performance hit.
@@ -591,6 +594,11 @@ impl<Cost: CostType> Interpreter<Cost> {
             || (instruction == CHAINID && !schedule.have_chain_id)
             || (instruction == SELFBALANCE && !schedule.have_selfbalance)
             || (instruction == BASEFEE && !schedule.eip3198)
+            || (instruction == PUSH0 && !schedule.eip3855)
+            || ((instruction == TLOAD || instruction == TSTORE) && !schedule.eip1153)
+            || (instruction == MCOPY && !schedule.eip5656)
+            || (instruction == BLOBHASH && !schedule.eip4844)
+            || (instruction == BLOBBASEFEE && !schedule.eip7516)
             || ((instruction == BEGINSUB || instruction == JUMPSUB || instruction == RETURNSUB)
                 && !schedule.have_subs)
         {
@@ -625,6 +633,7 @@ impl<Cost: CostType> Interpreter<Cost> {
                 Some((read(0), read(2)))
             }
             instructions::EXTCODECOPY => Some((read(1), read(3))),
+            instructions::MCOPY => Some((read(0), read(2))),
             instructions::CALL | instructions::CALLCODE => Some((read(5), read(6))),
             instructions::DELEGATECALL | instructions::STATICCALL => Some((read(4), read(5))),
             _ => None,
@@ -945,6 +954,9 @@ impl<Cost: CostType> Interpreter<Cost> {
                     .collect();
                 ext.log(topics, self.mem.read_slice(offset, size))?;
             }
+            instructions::PUSH0 => {
+                self.stack.push(U256::zero());
+            }
             instructions::PUSH1
             | instructions::PUSH2
             | instructions::PUSH3
@@ -1036,6 +1048,25 @@ impl<Cost: CostType> Interpreter<Cost> {
                 ext.set_storage(key, BigEndianHash::from_uint(&val))?;
                 ext.al_insert_storage_key(self.params.address, key);
             }
+            instructions::TLOAD => {
+                let key = BigEndianHash::from_uint(&self.stack.pop_back());
+                let word = ext.transient_storage_at(&key)?.into_uint();
+                self.stack.push(word);
+            }
+            instructions::TSTORE => {
+                let key = BigEndianHash::from_uint(&self.stack.pop_back());
+                let val = self.stack.pop_back();
+                ext.set_transient_storage(key, BigEndianHash::from_uint(&val))?;
+            }
+            instructions::MCOPY => {
+                let dest_offset = self.stack.pop_back();
+                let source_offset = self.stack.pop_back();
+                let size = self.stack.pop_back();
+                if !size.is_zero() {
+                    let data = self.mem.read_slice(source_offset, size).to_vec();
+                    self.mem.write_slice(dest_offset, &data);
+                }
+            }
             instructions::PC => {
                 self.stack.push(U256::from(self.reader.position - 1));
             }
@@ -1168,6 +1199,19 @@ impl<Cost: CostType> Interpreter<Cost> {
             instructions::BASEFEE => {
                 self.stack.push(ext.env_info().base_fee.unwrap_or_default());
             }
+            instructions::BLOBHASH => {
+                let index = self.stack.pop_back();
+                let hash = if index < U256::from(self.params.blob_versioned_hashes.len()) {
+                    self.params.blob_versioned_hashes[index.as_usize()].into_uint()
+                } else {
+                    U256::zero()
+                };
+                self.stack.push(hash);
+            }
+            instructions::BLOBBASEFEE => {
+                self.stack
+                    .push(ext.env_info().blob_base_fee.unwrap_or_default());
+            }
 
             // Stack instructions
             instructions::DUP1
@@ -87,7 +87,9 @@ impl SharedCache {
         let mut position = 0;
 
         while position < code.len() {
-            let instruction = Instruction::from_u8(code[position]);
+            // Subroutine entrypoints are only consulted by JUMPSUB, so recording them
+            // regardless of the schedule is harmless.
+            let instruction = Instruction::from_u8_with_subs(code[position], true);
 
             if let Some(instruction) = instruction {
                 match instruction {
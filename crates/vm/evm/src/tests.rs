@@ -490,6 +490,172 @@ fn test_base_fee(factory: super::Factory) {
     );
 }
 
+evm_test! {test_push0: test_push0_int}
+fn test_push0(factory: super::Factory) {
+    let code = "60015f55".from_hex().unwrap();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    let mut ext = FakeExt::new_shanghai(
+        Address::from_str("0000000000000000000000000000000000000000").unwrap(),
+        Address::from_str("000000000000000000000000636F6E7472616374").unwrap(),
+        &[],
+    );
+
+    let gas_left = {
+        let vm = factory.create(params, ext.schedule(), ext.depth());
+        test_finalize(vm.exec(&mut ext).ok().unwrap()).unwrap()
+    };
+
+    assert_eq!(gas_left, U256::from(77_895));
+    assert_store(
+        &ext,
+        0,
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+}
+
+evm_test! {test_tload_tstore: test_tload_tstore_int}
+fn test_tload_tstore(factory: super::Factory) {
+    // TSTORE 0x2a at key 1, TLOAD it back and SSTORE the result at key 0
+    let code = "602a60015d60015c600055".from_hex().unwrap();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    let mut ext = FakeExt::new_cancun(
+        Address::from_str("0000000000000000000000000000000000000000").unwrap(),
+        Address::from_str("000000000000000000000000636F6E7472616374").unwrap(),
+        &[],
+    );
+
+    let gas_left = {
+        let vm = factory.create(params, ext.schedule(), ext.depth());
+        test_finalize(vm.exec(&mut ext).ok().unwrap()).unwrap()
+    };
+
+    assert_eq!(gas_left, U256::from(77_688));
+    assert_store(
+        &ext,
+        0,
+        "000000000000000000000000000000000000000000000000000000000000002a",
+    );
+    assert!(!ext.store.contains_key(&H256::from_low_u64_be(1)));
+}
+
+evm_test! {test_blobhash: test_blobhash_int}
+fn test_blobhash(factory: super::Factory) {
+    // BLOBHASH of the blob at index 1 and of a missing blob at index 2, SSTORE them at keys 0 and 1
+    let code = "600149600055600249600155".from_hex().unwrap();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    params.blob_versioned_hashes = vec![
+        H256::from_low_u64_be(0x0100_0000_0000_0000),
+        H256::from_str("01000000000000000000000000000000000000000000000000000000000000ff").unwrap(),
+    ];
+    let mut ext = FakeExt::new_cancun(
+        Address::from_str("0000000000000000000000000000000000000000").unwrap(),
+        Address::from_str("000000000000000000000000636F6E7472616374").unwrap(),
+        &[],
+    );
+
+    let gas_left = {
+        let vm = factory.create(params, ext.schedule(), ext.depth());
+        test_finalize(vm.exec(&mut ext).ok().unwrap()).unwrap()
+    };
+
+    assert_eq!(gas_left, U256::from(75_682));
+    assert_store(
+        &ext,
+        0,
+        "01000000000000000000000000000000000000000000000000000000000000ff",
+    );
+    assert_store(
+        &ext,
+        1,
+        "0000000000000000000000000000000000000000000000000000000000000000",
+    );
+}
+
+evm_test! {test_blobbasefee: test_blobbasefee_int}
+fn test_blobbasefee(factory: super::Factory) {
+    // BLOBBASEFEE and SSTORE it at key 0
+    let code = "4a600055".from_hex().unwrap();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    let mut ext = FakeExt::new_cancun(
+        Address::from_str("0000000000000000000000000000000000000000").unwrap(),
+        Address::from_str("000000000000000000000000636F6E7472616374").unwrap(),
+        &[],
+    );
+    ext.info.blob_base_fee = Some(U256::from(0x17));
+
+    let gas_left = {
+        let vm = factory.create(params, ext.schedule(), ext.depth());
+        test_finalize(vm.exec(&mut ext).ok().unwrap()).unwrap()
+    };
+
+    assert_eq!(gas_left, U256::from(77_895));
+    assert_store(
+        &ext,
+        0,
+        "0000000000000000000000000000000000000000000000000000000000000017",
+    );
+}
+
+evm_test! {test_mcopy: test_mcopy_int}
+fn test_mcopy(factory: super::Factory) {
+    // MSTORE 0x2a at 0, MCOPY the word to 0x20, MLOAD it back and SSTORE the result at key 0
+    let code = "602a6000526020600060205e602051600055".from_hex().unwrap();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    let mut ext = FakeExt::new_cancun(
+        Address::from_str("0000000000000000000000000000000000000000").unwrap(),
+        Address::from_str("000000000000000000000000636F6E7472616374").unwrap(),
+        &[],
+    );
+
+    let gas_left = {
+        let vm = factory.create(params, ext.schedule(), ext.depth());
+        test_finalize(vm.exec(&mut ext).ok().unwrap()).unwrap()
+    };
+
+    assert_eq!(gas_left, U256::from(77_861));
+    assert_store(
+        &ext,
+        0,
+        "000000000000000000000000000000000000000000000000000000000000002a",
+    );
+}
+
+#[test]
+fn test_tload_before_cancun_int() {
+    let factory = super::Factory::new(VMType::Interpreter, 1024 * 32);
+    let code = "60015c".from_hex().unwrap();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    let mut ext = FakeExt::new_istanbul();
+
+    let err = {
+        let vm = factory.create(params, ext.schedule(), ext.depth());
+        test_finalize(vm.exec(&mut ext).ok().unwrap()).unwrap_err()
+    };
+
+    match err {
+        vm::Error::BadInstruction { instruction: 0x5c } => (),
+        _ => assert!(false, "Expected bad instruction"),
+    }
+}
+
 evm_test! {test_gas_limit: test_gas_limit_int}
 fn test_gas_limit(factory: super::Factory) {
     let gas_limit = U256::from(0x1234);
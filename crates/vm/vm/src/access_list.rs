@@ -1,7 +1,7 @@
 use ethereum_types::{Address, H256};
 use std::{
     borrow::Borrow,
-    collections::HashMap,
+    collections::{BTreeMap, BTreeSet, HashMap},
     hash::{Hash, Hasher},
 };
 
@@ -56,6 +56,8 @@ struct Journal {
     last_id: usize,
     addresses: HashMap<Address, usize>,
     storage_keys: HashMap<(Address, H256), usize>,
+    // Every address and storage key inserted since `record_accessed`, including rolled back ones.
+    accessed: Option<BTreeMap<Address, BTreeSet<H256>>>,
 }
 #[derive(Debug)]
 pub struct AccessList {
@@ -102,6 +104,7 @@ impl AccessList {
             last_id: 0,
             addresses: HashMap::new(),
             storage_keys: HashMap::new(),
+            accessed: None,
         };
         Self {
             id: 0,
@@ -136,6 +139,11 @@ impl AccessList {
     /// Inserts a storage key
     pub fn insert_storage_key(&mut self, address: Address, key: H256) {
         let mut journal = self.journal.as_ref().borrow_mut();
+        if journal.enabled {
+            if let Some(ref mut accessed) = journal.accessed {
+                accessed.entry(address).or_default().insert(key);
+            }
+        }
         if journal.enabled
             && !journal
                 .storage_keys
@@ -157,10 +165,31 @@ impl AccessList {
     /// Inserts an address
     pub fn insert_address(&mut self, address: Address) {
         let mut journal = self.journal.as_ref().borrow_mut();
+        if journal.enabled {
+            if let Some(ref mut accessed) = journal.accessed {
+                accessed.entry(address).or_default();
+            }
+        }
         if journal.enabled && !journal.addresses.contains_key(&address) {
             journal.addresses.insert(address, self.id);
         }
     }
+    /// Starts recording the inserted addresses and storage keys, whether they are already in the
+    /// list or not. Recorded entries are kept on rollback.
+    pub fn record_accessed(&mut self) {
+        let mut journal = self.journal.as_ref().borrow_mut();
+        journal.accessed = Some(BTreeMap::new());
+    }
+    /// Returns the addresses and storage keys recorded since `record_accessed`
+    pub fn accessed(&self) -> Option<Vec<(Address, Vec<H256>)>> {
+        let journal = self.journal.as_ref().borrow();
+        journal.accessed.as_ref().map(|accessed| {
+            accessed
+                .iter()
+                .map(|(address, keys)| (*address, keys.iter().cloned().collect()))
+                .collect()
+        })
+    }
     /// Removes all changes in journal
     pub fn rollback(&self) {
         let mut journal = self.journal.as_ref().borrow_mut();
@@ -288,4 +317,29 @@ mod tests {
                 .contains_storage_key(&Address::from_low_u64_be(6), &H256::from_low_u64_be(7))
         );
     }
+
+    #[test]
+    fn recorded_accesses_are_kept_on_rollback() {
+        let mut access_list = AccessList::new(true);
+        access_list.insert_address(Address::from_low_u64_be(1));
+        access_list.record_accessed();
+
+        let mut access_list_call = access_list.clone();
+        access_list_call.insert_address(Address::from_low_u64_be(1));
+        access_list_call.insert_storage_key(Address::from_low_u64_be(2), H256::from_low_u64_be(3));
+        access_list_call.rollback();
+
+        assert_eq!(
+            access_list.accessed(),
+            Some(vec![
+                (Address::from_low_u64_be(1), vec![]),
+                (Address::from_low_u64_be(2), vec![H256::from_low_u64_be(3)]),
+            ])
+        );
+        assert_eq!(
+            false,
+            access_list
+                .contains_storage_key(&Address::from_low_u64_be(2), &H256::from_low_u64_be(3))
+        );
+    }
 }
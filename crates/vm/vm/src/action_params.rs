@@ -15,7 +15,7 @@
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Evm input params.
-use super::access_list::AccessList;
+use super::{access_list::AccessList, transient_state::TransientState};
 use bytes::Bytes;
 use call_type::CallType;
 use ethereum_types::{Address, H256, U256};
@@ -92,6 +92,10 @@ pub struct ActionParams {
     pub params_type: ParamsType,
     /// Current access list
     pub access_list: AccessList,
+    /// Transaction-scoped transient state
+    pub transient_state: TransientState,
+    /// Versioned hashes of the blobs carried by the transaction (EIP-4844)
+    pub blob_versioned_hashes: Vec<H256>,
 }
 
 impl Default for ActionParams {
@@ -111,6 +115,8 @@ impl Default for ActionParams {
             call_type: CallType::None,
             params_type: ParamsType::Separate,
             access_list: AccessList::default(),
+            transient_state: TransientState::default(),
+            blob_versioned_hashes: Vec::new(),
         }
     }
 }
@@ -135,6 +141,8 @@ impl From<ethjson::vm::Transaction> for ActionParams {
             }, // TODO @debris is this correct?
             params_type: ParamsType::Separate,
             access_list: AccessList::default(),
+            transient_state: TransientState::default(),
+            blob_versioned_hashes: Vec::new(),
         }
     }
 }
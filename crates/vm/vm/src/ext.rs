@@ -74,6 +74,12 @@ pub trait Ext {
     /// Stores a value for given key.
     fn set_storage(&mut self, key: H256, value: H256) -> Result<()>;
 
+    /// Returns a transient storage value for given key.
+    fn transient_storage_at(&self, key: &H256) -> Result<H256>;
+
+    /// Stores a transient storage value for given key.
+    fn set_transient_storage(&mut self, key: H256, value: H256) -> Result<()>;
+
     /// Determine whether an account exists.
     fn exists(&self, address: &Address) -> Result<bool>;
 
@@ -31,6 +31,7 @@ mod error;
 mod ext;
 mod return_data;
 pub mod schedule;
+pub mod transient_state;
 
 pub mod tests;
 
@@ -42,6 +43,7 @@ pub use error::{Error, ExecTrapError, ExecTrapResult, Result, TrapError, TrapKin
 pub use ext::{ContractCreateResult, CreateContractAddress, Ext, MessageCallResult};
 pub use return_data::{GasLeft, ReturnData};
 pub use schedule::{CleanDustMode, Schedule, WasmCosts};
+pub use transient_state::TransientState;
 
 /// Virtual Machine interface
 pub trait Exec {
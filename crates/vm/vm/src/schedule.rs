@@ -34,6 +34,8 @@ pub const EIP3529_MAX_REFUND_QUOTIENT: usize = 5;
 /// Reduced SSTORE refund as by EIP-3529
 pub const EIP3529_SSTORE_CLEARS_SCHEDULE: usize =
     EIP2929_SSTORE_RESET_GAS + EIP2930_ACCESS_LIST_STORAGE_KEY_COST;
+/// Gas per 32-byte word of initcode as by EIP-3860
+pub const EIP3860_INITCODE_WORD_GAS: usize = 2;
 
 /// Definition of the cost schedule and other parameterisations for the EVM.
 #[derive(Debug)]
@@ -173,6 +175,24 @@ pub struct Schedule {
     pub max_refund_quotient: usize,
     // Enable EIP-3541 rule
     pub eip3541: bool,
+    /// Enable EIP-3651 rule, COINBASE is warm at the start of the transaction
+    pub eip3651: bool,
+    /// Enable PUSH0 opcode
+    pub eip3855: bool,
+    /// Enable EIP-3860 rules, initcode size limit and metering
+    pub eip3860: bool,
+    /// Gas per 32-byte word of initcode, charged only if EIP-3860 is enabled
+    pub initcode_word_gas: usize,
+    /// Enable TLOAD and TSTORE opcodes (EIP-1153)
+    pub eip1153: bool,
+    /// Enable MCOPY opcode (EIP-5656)
+    pub eip5656: bool,
+    /// SELFDESTRUCT only deletes contracts created in the same transaction (EIP-6780)
+    pub eip6780: bool,
+    /// Enable blob transactions and BLOBHASH opcode (EIP-4844)
+    pub eip4844: bool,
+    /// Enable BLOBBASEFEE opcode (EIP-7516)
+    pub eip7516: bool,
 }
 
 /// Wasm cost table
@@ -326,6 +346,15 @@ impl Schedule {
             eip3198: false,
             max_refund_quotient: MAX_REFUND_QUOTIENT,
             eip3541: false,
+            eip3651: false,
+            eip3855: false,
+            eip3860: false,
+            initcode_word_gas: EIP3860_INITCODE_WORD_GAS,
+            eip1153: false,
+            eip5656: false,
+            eip6780: false,
+            eip4844: false,
+            eip7516: false,
         }
     }
 
@@ -400,6 +429,32 @@ impl Schedule {
         schedule
     }
 
+    /// Schedule for the Shanghai fork of the Ethereum main net.
+    pub fn new_shanghai() -> Schedule {
+        let mut schedule = Self::new_london();
+
+        schedule.eip3651 = true;
+        schedule.eip3855 = true;
+        schedule.eip3860 = true;
+
+        schedule
+    }
+
+    /// Schedule for the Cancun fork of the Ethereum main net.
+    pub fn new_cancun() -> Schedule {
+        let mut schedule = Self::new_shanghai();
+        // EIP-1153 and EIP-5656 take over the opcodes of EIP-2315
+        schedule.have_subs = false;
+
+        schedule.eip1153 = true;
+        schedule.eip5656 = true;
+        schedule.eip6780 = true;
+        schedule.eip4844 = true;
+        schedule.eip7516 = true;
+
+        schedule
+    }
+
     fn new(efcd: bool, hdc: bool, tcg: usize) -> Schedule {
         Schedule {
             exceptional_failed_code_deposit: efcd,
@@ -469,9 +524,24 @@ impl Schedule {
             eip3198: false,
             max_refund_quotient: MAX_REFUND_QUOTIENT,
             eip3541: false,
+            eip3651: false,
+            eip3855: false,
+            eip3860: false,
+            initcode_word_gas: EIP3860_INITCODE_WORD_GAS,
+            eip1153: false,
+            eip5656: false,
+            eip6780: false,
+            eip4844: false,
+            eip7516: false,
         }
     }
 
+    /// Maximum size of initcode accepted by CREATE, CREATE2 and contract creation
+    /// transactions once EIP-3860 is enabled.
+    pub fn max_initcode_size(&self) -> usize {
+        self.create_data_limit.saturating_mul(2)
+    }
+
     /// Returns wasm schedule
     ///
     /// May panic if there is no wasm schedule
@@ -499,3 +569,13 @@ fn schedule_evm_assumptions() {
     assert_eq!(s1.quad_coeff_div, 512);
     assert_eq!(s2.quad_coeff_div, 512);
 }
+
+#[test]
+#[cfg(test)]
+fn shanghai_initcode_limit_is_twice_max_code_size() {
+    let s = Schedule::new_shanghai();
+
+    assert!(s.eip3860);
+    assert_eq!(s.create_data_limit, 24576);
+    assert_eq!(s.max_initcode_size(), 49152);
+}
@@ -0,0 +1,164 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State that lives for the duration of a single transaction.
+
+use ethereum_types::{Address, H256};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+#[derive(Debug, Default)]
+struct Journal {
+    last_id: usize,
+    storage: HashMap<(Address, H256), H256>,
+    // Previous values of written transient slots, in write order, tagged with the writer id.
+    storage_changes: Vec<(usize, (Address, H256), Option<H256>)>,
+    created: HashMap<Address, usize>,
+}
+
+/// Transient storage (EIP-1153) and the set of contracts created in the current
+/// transaction (EIP-6780).
+///
+/// Clones share the underlying journal, each one with a new id, so that every call frame
+/// sees the writes of the whole transaction and a `rollback` undoes only the writes of the
+/// frame it was cloned for and of its subcalls.
+#[derive(Debug)]
+pub struct TransientState {
+    id: usize,
+    journal: Rc<RefCell<Journal>>,
+}
+
+impl Clone for TransientState {
+    fn clone(&self) -> Self {
+        let mut journal = self.journal.as_ref().borrow_mut();
+        let id = journal.last_id + 1;
+        journal.last_id = id;
+        Self {
+            id,
+            journal: self.journal.clone(),
+        }
+    }
+}
+
+impl Default for TransientState {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            journal: Rc::new(RefCell::new(Journal::default())),
+        }
+    }
+}
+
+impl TransientState {
+    /// Returns the transient storage value of `address` for given key.
+    pub fn storage_at(&self, address: &Address, key: &H256) -> H256 {
+        let journal = self.journal.as_ref().borrow();
+        journal
+            .storage
+            .get(&(*address, *key))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Sets the transient storage value of `address` for given key.
+    pub fn set_storage(&mut self, address: Address, key: H256, value: H256) {
+        let mut journal = self.journal.as_ref().borrow_mut();
+        let previous = if value.is_zero() {
+            journal.storage.remove(&(address, key))
+        } else {
+            journal.storage.insert((address, key), value)
+        };
+        journal
+            .storage_changes
+            .push((self.id, (address, key), previous));
+    }
+
+    /// Records that the contract at `address` was created in this transaction.
+    pub fn insert_created(&mut self, address: Address) {
+        let mut journal = self.journal.as_ref().borrow_mut();
+        if !journal.created.contains_key(&address) {
+            journal.created.insert(address, self.id);
+        }
+    }
+
+    /// Checks if the contract at `address` was created in this transaction.
+    pub fn is_created(&self, address: &Address) -> bool {
+        let journal = self.journal.as_ref().borrow();
+        journal.created.contains_key(address)
+    }
+
+    /// Removes all changes made through this handle and the ones cloned from it afterwards.
+    pub fn rollback(&self) {
+        let mut journal = self.journal.as_ref().borrow_mut();
+        while journal
+            .storage_changes
+            .last()
+            .map_or(false, |&(id, _, _)| id >= self.id)
+        {
+            let (_, key, previous) = journal
+                .storage_changes
+                .pop()
+                .expect("last change exists; qed");
+            match previous {
+                Some(value) => journal.storage.insert(key, value),
+                None => journal.storage.remove(&key),
+            };
+        }
+        journal.created.retain(|_, id| *id < self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cloned_transient_state_shares_storage() {
+        let address = Address::from_low_u64_be(1);
+        let key = H256::from_low_u64_be(2);
+        let mut state = TransientState::default();
+        let mut state_call = state.clone();
+
+        state_call.set_storage(address, key, H256::from_low_u64_be(3));
+        assert_eq!(state.storage_at(&address, &key), H256::from_low_u64_be(3));
+
+        state.set_storage(address, key, H256::zero());
+        assert_eq!(state_call.storage_at(&address, &key), H256::zero());
+    }
+
+    #[test]
+    fn cloned_transient_state_rollbacks_in_parent() {
+        let address = Address::from_low_u64_be(1);
+        let key = H256::from_low_u64_be(2);
+        let mut state = TransientState::default();
+        state.set_storage(address, key, H256::from_low_u64_be(3));
+
+        let mut state_call = state.clone();
+        state_call.set_storage(address, key, H256::from_low_u64_be(4));
+        state_call.insert_created(Address::from_low_u64_be(5));
+
+        let mut state_call_call = state_call.clone();
+        state_call_call.set_storage(address, H256::from_low_u64_be(6), H256::from_low_u64_be(7));
+
+        state_call.rollback();
+
+        assert_eq!(state.storage_at(&address, &key), H256::from_low_u64_be(3));
+        assert_eq!(
+            state.storage_at(&address, &H256::from_low_u64_be(6)),
+            H256::zero()
+        );
+        assert!(!state.is_created(&Address::from_low_u64_be(5)));
+    }
+}